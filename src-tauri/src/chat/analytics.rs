@@ -0,0 +1,389 @@
+//! Usage analytics across projects, models and time
+//!
+//! Aggregates the runs recorded in every session's metadata: runs per day,
+//! outcome rates from `RunStatus`, average run duration, token usage by
+//! project, model and backend, and the most used execution modes. Takes the
+//! same filters as the cost summary (`CostFilters`).
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::cost::{backend_key, CostFilters};
+use super::storage::{list_all_session_ids, load_metadata};
+use super::time::utc_day;
+use super::types::{Backend, RunEntry, RunStatus};
+
+/// Run counts, tokens and duration for one group of runs
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageBucket {
+    pub key: String,
+    /// Display name (project name, or the backend of a model) when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub runs: u32,
+    pub completed: u32,
+    pub cancelled: u32,
+    pub crashed: u32,
+    pub timed_out: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    /// Mean wall-clock duration of finished runs, in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average_duration_secs: Option<f64>,
+    #[serde(skip)]
+    duration_secs_total: u64,
+    #[serde(skip)]
+    timed_runs: u32,
+}
+
+impl UsageBucket {
+    fn new(key: String, label: Option<String>) -> Self {
+        Self {
+            key,
+            label,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, run: &RunEntry) {
+        self.runs += 1;
+        match run.status {
+            RunStatus::Completed => self.completed += 1,
+            RunStatus::Cancelled => self.cancelled += 1,
+            RunStatus::Crashed => self.crashed += 1,
+            RunStatus::TimedOut => self.timed_out += 1,
+            RunStatus::Running | RunStatus::Resumable => {}
+        }
+        if let Some(usage) = &run.usage {
+            self.input_tokens += usage.input_tokens;
+            self.output_tokens += usage.output_tokens;
+            self.cache_read_input_tokens += usage.cache_read_input_tokens;
+            self.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+        }
+        if let Some(ended_at) = run.ended_at.filter(|end| *end >= run.started_at) {
+            self.duration_secs_total += ended_at - run.started_at;
+            self.timed_runs += 1;
+            self.average_duration_secs =
+                Some(self.duration_secs_total as f64 / self.timed_runs as f64);
+        }
+    }
+
+    /// Runs that reached an outcome (not running or resumable)
+    fn finished(&self) -> u32 {
+        self.completed + self.cancelled + self.crashed + self.timed_out
+    }
+
+    fn total_tokens(&self) -> u64 {
+        self.input_tokens
+            + self.output_tokens
+            + self.cache_read_input_tokens
+            + self.cache_creation_input_tokens
+    }
+}
+
+/// Share of finished runs per outcome (0.0-1.0)
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct OutcomeRates {
+    pub success: f64,
+    pub cancel: f64,
+    pub crash: f64,
+    pub timeout: f64,
+}
+
+impl OutcomeRates {
+    fn of(bucket: &UsageBucket) -> Self {
+        let finished = bucket.finished();
+        if finished == 0 {
+            return Self::default();
+        }
+        let rate = |count: u32| count as f64 / finished as f64;
+        Self {
+            success: rate(bucket.completed),
+            cancel: rate(bucket.cancelled),
+            crash: rate(bucket.crashed),
+            timeout: rate(bucket.timed_out),
+        }
+    }
+}
+
+/// How often an execution mode was used
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExecutionModeUsage {
+    pub mode: String,
+    pub runs: u32,
+    /// Share of all runs (0.0-1.0)
+    pub share: f64,
+}
+
+/// Usage analytics response
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageAnalytics {
+    pub total: UsageBucket,
+    pub rates: OutcomeRates,
+    /// One bucket per UTC day with runs, oldest first
+    pub per_day: Vec<UsageBucket>,
+    /// Sorted by total tokens, highest first
+    pub by_project: Vec<UsageBucket>,
+    pub by_model: Vec<UsageBucket>,
+    pub by_backend: Vec<UsageBucket>,
+    /// Most used first
+    pub execution_modes: Vec<ExecutionModeUsage>,
+}
+
+/// Accumulates runs into the analytics groups
+#[derive(Default)]
+struct Aggregator {
+    total: UsageBucket,
+    per_day: HashMap<String, UsageBucket>,
+    by_project: HashMap<String, UsageBucket>,
+    by_model: HashMap<String, UsageBucket>,
+    by_backend: HashMap<String, UsageBucket>,
+    modes: HashMap<String, u32>,
+}
+
+fn add_to(
+    buckets: &mut HashMap<String, UsageBucket>,
+    key: String,
+    label: Option<String>,
+    run: &RunEntry,
+) {
+    buckets
+        .entry(key.clone())
+        .or_insert_with(|| UsageBucket::new(key, label))
+        .add(run);
+}
+
+impl Aggregator {
+    /// Add a run of a session on `backend` in `project` (id, name)
+    fn add(&mut self, run: &RunEntry, backend: &Backend, project: Option<(&str, &str)>) {
+        let backend = backend_key(backend);
+        self.total.add(run);
+        add_to(&mut self.per_day, utc_day(run.started_at), None, run);
+        add_to(
+            &mut self.by_project,
+            project.map_or_else(|| "unknown".to_string(), |(id, _)| id.to_string()),
+            project.map(|(_, name)| name.to_string()),
+            run,
+        );
+        add_to(
+            &mut self.by_model,
+            run.model.clone().unwrap_or_else(|| "default".to_string()),
+            Some(backend.clone()),
+            run,
+        );
+        add_to(&mut self.by_backend, backend, None, run);
+        let mode = run.execution_mode.as_deref().unwrap_or("unspecified");
+        *self.modes.entry(mode.to_string()).or_default() += 1;
+    }
+
+    fn finish(self) -> UsageAnalytics {
+        let by_tokens = |buckets: HashMap<String, UsageBucket>| {
+            let mut buckets: Vec<UsageBucket> = buckets.into_values().collect();
+            buckets.sort_by(|a, b| {
+                b.total_tokens()
+                    .cmp(&a.total_tokens())
+                    .then_with(|| b.runs.cmp(&a.runs))
+                    .then_with(|| a.key.cmp(&b.key))
+            });
+            buckets
+        };
+
+        let mut per_day: Vec<UsageBucket> = self.per_day.into_values().collect();
+        per_day.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let total_runs = self.total.runs.max(1) as f64;
+        let mut execution_modes: Vec<ExecutionModeUsage> = self
+            .modes
+            .into_iter()
+            .map(|(mode, runs)| ExecutionModeUsage {
+                mode,
+                runs,
+                share: runs as f64 / total_runs,
+            })
+            .collect();
+        execution_modes.sort_by(|a, b| b.runs.cmp(&a.runs).then_with(|| a.mode.cmp(&b.mode)));
+
+        UsageAnalytics {
+            rates: OutcomeRates::of(&self.total),
+            total: self.total,
+            per_day,
+            by_project: by_tokens(self.by_project),
+            by_model: by_tokens(self.by_model),
+            by_backend: by_tokens(self.by_backend),
+            execution_modes,
+        }
+    }
+}
+
+/// Aggregate usage analytics across all sessions
+#[tauri::command]
+pub async fn get_usage_analytics(
+    app: AppHandle,
+    filters: Option<CostFilters>,
+) -> Result<UsageAnalytics, String> {
+    let filters = filters.unwrap_or_default();
+    log::trace!("Computing usage analytics");
+
+    let projects = crate::projects::storage::load_projects_data(&app).ok();
+    let project_of = |worktree_id: &str| {
+        let data = projects.as_ref()?;
+        let worktree = data.find_worktree(worktree_id)?;
+        data.find_project(&worktree.project_id)
+    };
+
+    let session_ids = match filters.session_id {
+        Some(ref id) => vec![id.clone()],
+        None => list_all_session_ids(&app)?,
+    };
+    let mut aggregator = Aggregator {
+        total: UsageBucket::new("total".to_string(), None),
+        ..Default::default()
+    };
+    for session_id in session_ids {
+        let Some(metadata) = load_metadata(&app, &session_id)? else {
+            continue;
+        };
+        let project = project_of(&metadata.worktree_id);
+        if filters
+            .worktree_id
+            .as_ref()
+            .is_some_and(|w| *w != metadata.worktree_id)
+            || filters
+                .project_id
+                .as_ref()
+                .is_some_and(|p| project.map(|project| &project.id) != Some(p))
+        {
+            continue;
+        }
+
+        for run in &metadata.runs {
+            let backend = run.backend.as_ref().unwrap_or(&metadata.backend);
+            if filters.backend.as_ref().is_some_and(|b| b != backend)
+                || filters.from.is_some_and(|from| run.started_at < from)
+                || filters.to.is_some_and(|to| run.started_at > to)
+            {
+                continue;
+            }
+            aggregator.add(
+                run,
+                backend,
+                project.map(|p| (p.id.as_str(), p.name.as_str())),
+            );
+        }
+    }
+
+    Ok(aggregator.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::UsageData;
+
+    fn run(
+        status: RunStatus,
+        model: &str,
+        mode: Option<&str>,
+        started_at: u64,
+        ended_at: Option<u64>,
+        output_tokens: u64,
+    ) -> RunEntry {
+        RunEntry {
+            model: Some(model.to_string()),
+            execution_mode: mode.map(str::to_string),
+            started_at,
+            ended_at,
+            usage: Some(UsageData {
+                input_tokens: 100,
+                output_tokens,
+                cache_read_input_tokens: 0,
+                cache_creation_input_tokens: 0,
+            }),
+            ..RunEntry::test(&format!("run-{started_at}"), status)
+        }
+    }
+
+    #[test]
+    fn test_aggregate_outcomes_durations_and_groups() {
+        const DAY: u64 = 86_400;
+        let mut aggregator = Aggregator::default();
+        let project = Some(("p1", "Jean"));
+        aggregator.add(
+            &run(
+                RunStatus::Completed,
+                "opus",
+                Some("plan"),
+                0,
+                Some(60),
+                1000,
+            ),
+            &Backend::Claude,
+            project,
+        );
+        aggregator.add(
+            &run(
+                RunStatus::Completed,
+                "opus",
+                Some("build"),
+                10,
+                Some(130),
+                500,
+            ),
+            &Backend::Claude,
+            project,
+        );
+        aggregator.add(
+            &run(
+                RunStatus::Cancelled,
+                "gpt-5.2-codex",
+                Some("build"),
+                DAY,
+                Some(DAY + 30),
+                10,
+            ),
+            &Backend::Codex,
+            None,
+        );
+        aggregator.add(
+            &run(RunStatus::Crashed, "opus", None, DAY + 5, None, 0),
+            &Backend::Claude,
+            project,
+        );
+        aggregator.add(
+            &run(RunStatus::Running, "opus", Some("build"), DAY + 9, None, 0),
+            &Backend::Claude,
+            project,
+        );
+        let analytics = aggregator.finish();
+
+        assert_eq!(analytics.total.runs, 5);
+        // The running run has no outcome yet
+        assert_eq!(analytics.rates.success, 0.5);
+        assert_eq!(analytics.rates.cancel, 0.25);
+        assert_eq!(analytics.rates.crash, 0.25);
+        // Only runs with an end time count toward the duration
+        assert_eq!(analytics.total.average_duration_secs, Some(70.0));
+
+        let days: Vec<(&str, u32)> = analytics
+            .per_day
+            .iter()
+            .map(|d| (d.key.as_str(), d.runs))
+            .collect();
+        assert_eq!(days, vec![("1970-01-01", 2), ("1970-01-02", 3)]);
+
+        assert_eq!(analytics.by_model[0].key, "opus");
+        assert_eq!(analytics.by_model[0].label.as_deref(), Some("claude"));
+        assert_eq!(analytics.by_model[0].output_tokens, 1500);
+        assert_eq!(analytics.by_backend[1].key, "codex");
+        assert_eq!(analytics.by_project[0].label.as_deref(), Some("Jean"));
+        assert_eq!(analytics.by_project[1].key, "unknown");
+
+        assert_eq!(analytics.execution_modes[0].mode, "build");
+        assert_eq!(analytics.execution_modes[0].runs, 3);
+        assert_eq!(analytics.execution_modes[0].share, 0.6);
+    }
+}
@@ -0,0 +1,1308 @@
+//! Pluggable agent backends
+//!
+//! Claude CLI, Codex CLI, OpenCode and Gemini CLI each implement
+//! `AgentBackend`, which covers everything the chat layer needs from a
+//! backend: starting a run, re-attaching to a detached run after a restart,
+//! cancelling, one-shot structured calls for magic prompts, and parsing run
+//! logs back into messages or replaying them as events. Call sites look the
+//! implementation up with `backend_for` (by session backend) or
+//! `backend_for_model` (by model ID) instead of matching on `Backend`, so
+//! adding a backend means adding an implementation here.
+//!
+//! `MockBackend` replays scripted fixtures (see `mock`) so session lifecycles
+//! can be tested without a real CLI.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use super::claude::ClaudeResponse;
+use super::codex::CodexResponse;
+use super::gemini::GeminiResponse;
+use super::opencode::OpenCodeResponse;
+use super::replay::EventReplayer;
+use super::storage::with_sessions_mut;
+use super::types::{
+    Backend, ChatMessage, ContentBlock, EffortLevel, ForkOrigin, RunEntry, Session, ThinkingLevel,
+    ToolCall, UsageData,
+};
+
+/// Callback invoked with the PID right after a run's process is spawned
+pub type PidCallback = Box<dyn FnOnce(u32) + Send>;
+
+/// What a backend supports, so callers can branch on features instead of names
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct BackendCapabilities {
+    /// Supports a read-only planning mode
+    pub plan_mode: bool,
+    /// Plans end with an ExitPlanMode tool call the user approves. Backends
+    /// without it are waiting for the user whenever a plan-mode run has output.
+    pub plan_approval_tool: bool,
+    /// Accepts Claude-style thinking levels
+    pub thinking_levels: bool,
+    /// Accepts reasoning effort levels
+    pub effort_levels: bool,
+    /// Asks Jean to approve tool calls interactively (build mode)
+    pub approvals: bool,
+    /// Can search the web while planning (when allowed in preferences)
+    pub web_search_in_plan: bool,
+    /// Runs outlive the app and can be re-attached with `tail`
+    pub detached: bool,
+    /// Stalled runs are killed by the run watchdog (idle and wall-clock limits)
+    pub watchdog: bool,
+    /// Forks conversations natively instead of seeding the transcript
+    pub native_fork: bool,
+    /// Runs under a custom provider profile when one is selected
+    pub custom_profiles: bool,
+    /// Can spread work over parallel agent threads (when enabled in preferences)
+    pub multi_agent: bool,
+    /// Attached images reach the model (document page images are only made
+    /// for these)
+    pub vision: bool,
+    /// How the project permission policy is applied to tool calls
+    pub policy_enforcement: PolicyEnforcement,
+}
+
+/// How a backend applies the project permission policy (see `policy`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyEnforcement {
+    /// The policy is never read
+    None,
+    /// Rules are passed to the CLI as permission settings, enforced in every mode
+    CliSettings,
+    /// Command and file change approval requests are answered from the policy
+    /// (build mode only; other modes don't ask)
+    BuildApprovals,
+}
+
+/// Everything needed to start one run of a chat session
+pub struct ExecuteRequest<'a> {
+    pub app: &'a AppHandle,
+    pub session_id: &'a str,
+    pub worktree_id: &'a str,
+    pub worktree_path: &'a str,
+    /// Input file with the user message (read by detached processes)
+    pub input_file: &'a Path,
+    /// NDJSON file the run's output is written to
+    pub output_file: &'a Path,
+    pub working_dir: &'a Path,
+    /// Backend conversation to continue (see `AgentBackend::resume_id`)
+    pub resume_id: Option<&'a str>,
+    pub message: &'a str,
+    pub model: Option<&'a str>,
+    pub execution_mode: Option<&'a str>,
+    pub thinking_level: Option<&'a ThinkingLevel>,
+    pub effort_level: Option<&'a EffortLevel>,
+    pub allowed_tools: Option<&'a [String]>,
+    pub parallel_execution_prompt: Option<&'a str>,
+    pub ai_language: Option<&'a str>,
+    pub mcp_config: Option<&'a str>,
+    pub chrome_enabled: bool,
+    pub custom_profile_name: Option<&'a str>,
+    /// Set on the first run of a forked session that can fork natively
+    pub fork: Option<&'a ForkOrigin>,
+    /// Allow web search (plan mode, when enabled in preferences)
+    pub web_search: bool,
+    /// Multi-agent mode and its thread limit
+    pub multi_agent: bool,
+    pub max_agent_threads: Option<u32>,
+    /// Builds the callback that persists the PID for crash recovery. A factory
+    /// because a backend may spawn more than once (e.g. retrying a stale resume).
+    pub pid_callback: &'a dyn Fn() -> PidCallback,
+}
+
+/// Result of a run, the same shape for every backend
+#[derive(Debug, Clone)]
+pub struct AgentResponse {
+    pub content: String,
+    /// Backend conversation ID for resuming (Claude session, Codex thread,
+    /// OpenCode or Gemini session)
+    pub resume_id: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub content_blocks: Vec<ContentBlock>,
+    pub cancelled: bool,
+    /// Whether a chat:error event was emitted during execution
+    pub error_emitted: bool,
+    pub usage: Option<UsageData>,
+    /// Context tokens of the last API call (Claude only)
+    pub context_tokens: Option<u64>,
+}
+
+impl From<ClaudeResponse> for AgentResponse {
+    fn from(response: ClaudeResponse) -> Self {
+        Self {
+            content: response.content,
+            resume_id: response.session_id,
+            tool_calls: response.tool_calls,
+            content_blocks: response.content_blocks,
+            cancelled: response.cancelled,
+            error_emitted: false,
+            usage: response.usage,
+            context_tokens: response.context_tokens,
+        }
+    }
+}
+
+impl From<CodexResponse> for AgentResponse {
+    fn from(response: CodexResponse) -> Self {
+        Self {
+            content: response.content,
+            resume_id: response.thread_id,
+            tool_calls: response.tool_calls,
+            content_blocks: response.content_blocks,
+            cancelled: response.cancelled,
+            error_emitted: response.error_emitted,
+            usage: response.usage,
+            context_tokens: None,
+        }
+    }
+}
+
+impl From<GeminiResponse> for AgentResponse {
+    fn from(response: GeminiResponse) -> Self {
+        Self {
+            content: response.content,
+            resume_id: response.session_id,
+            tool_calls: response.tool_calls,
+            content_blocks: response.content_blocks,
+            cancelled: response.cancelled,
+            error_emitted: response.error_emitted,
+            usage: response.usage,
+            context_tokens: None,
+        }
+    }
+}
+
+impl From<OpenCodeResponse> for AgentResponse {
+    fn from(response: OpenCodeResponse) -> Self {
+        Self {
+            content: response.content,
+            resume_id: response.session_id,
+            tool_calls: response.tool_calls,
+            content_blocks: response.content_blocks,
+            cancelled: response.cancelled,
+            error_emitted: false,
+            usage: response.usage,
+            context_tokens: None,
+        }
+    }
+}
+
+/// A chat backend
+pub trait AgentBackend: Send + Sync {
+    /// The `Backend` stored on sessions
+    fn kind(&self) -> Backend;
+
+    /// Human-readable name for logs and errors
+    fn display_name(&self) -> &'static str;
+
+    fn capabilities(&self) -> BackendCapabilities;
+
+    /// Whether a model ID belongs to this backend
+    fn handles_model(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// The stored conversation ID this backend resumes from
+    fn resume_id(&self, session: &Session) -> Option<String>;
+
+    /// Store the conversation ID returned by a run
+    fn set_resume_id(&self, session: &mut Session, resume_id: Option<String>);
+
+    /// The conversation ID to record in the run log (`claude_session_id`), from
+    /// which an interrupted run resumes. Only Claude's are recorded there.
+    fn run_log_resume_id<'a>(&self, _resume_id: &'a str) -> Option<&'a str> {
+        None
+    }
+
+    /// Start a run and block until it finishes. Returns the PID and the response.
+    fn execute(&self, req: &ExecuteRequest) -> Result<(u32, AgentResponse), String>;
+
+    /// Re-attach to a detached run that survived an app restart and follow it
+    /// until it finishes
+    fn tail(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        output_file: &Path,
+        pid: u32,
+        execution_mode: Option<&str>,
+    ) -> Result<AgentResponse, String>;
+
+    /// Stop the session's running process. Returns whether one was running.
+    fn cancel(&self, app: &AppHandle, session_id: &str, worktree_id: &str) -> Result<bool, String> {
+        super::registry::cancel_process(app, session_id, worktree_id)
+    }
+
+    /// Run a single prompt without a session and return the JSON output
+    /// matching `json_schema` (used by magic prompts)
+    fn one_shot(
+        &self,
+        app: &AppHandle,
+        prompt: &str,
+        model: &str,
+        json_schema: &str,
+        working_dir: Option<&Path>,
+        custom_profile_name: Option<&str>,
+    ) -> Result<String, String>;
+
+    /// Rebuild the assistant message of a run from its NDJSON log
+    fn parse_run(&self, lines: &[String], run: &RunEntry) -> Result<ChatMessage, String> {
+        super::run_log::parse_run_to_message(lines, run)
+    }
+
+    /// Re-emits the chat events of a recorded run from its NDJSON log (see
+    /// `replay`). Logs are in Claude's stream-json format unless overridden.
+    fn replayer(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        _execution_mode: Option<&str>,
+    ) -> Box<dyn EventReplayer> {
+        Box::new(super::claude::ClaudeReplayer::new(
+            app,
+            session_id,
+            worktree_id,
+        ))
+    }
+}
+
+/// Map an effort level to the reasoning effort Codex and OpenCode understand
+fn reasoning_effort(level: Option<&EffortLevel>) -> Option<&'static str> {
+    match level? {
+        EffortLevel::Low => Some("low"),
+        EffortLevel::Medium => Some("medium"),
+        EffortLevel::High => Some("high"),
+        EffortLevel::Max => Some("xhigh"),
+        EffortLevel::Off => None,
+    }
+}
+
+/// App data directories a sandboxed CLI needs to read for a session (pasted
+/// images and texts, loaded context, the session's run logs)
+fn session_data_dirs(app: &AppHandle, session_id: &str) -> Vec<String> {
+    let mut data_dirs = Vec::new();
+    if let Ok(app_data_dir) = app.path().app_data_dir() {
+        if cfg!(debug_assertions) {
+            data_dirs.push(app_data_dir.to_string_lossy().to_string());
+        } else {
+            for subdir in [
+                "pasted-images",
+                "pasted-texts",
+                "session-context",
+                "git-context",
+                "combined-contexts",
+            ] {
+                data_dirs.push(app_data_dir.join(subdir).to_string_lossy().to_string());
+            }
+            data_dirs.push(
+                app_data_dir
+                    .join("runs")
+                    .join(session_id)
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+    }
+    data_dirs
+}
+
+/// System prompt for backends without Claude's prompt flags (Codex, OpenCode,
+/// Gemini): language, global/project/parallel prompts, embedded binary hints
+/// and loaded context. `preamble` goes first; `default_global_prompt` stands in
+/// for an unset global system prompt.
+fn inline_system_prompt(
+    req: &ExecuteRequest,
+    preamble: Option<&str>,
+    default_global_prompt: Option<&str>,
+) -> Option<String> {
+    use crate::projects::github_issues::{
+        get_github_contexts_dir, get_session_issue_refs, get_session_pr_refs,
+    };
+    use crate::projects::storage::load_projects_data;
+
+    let mut system_prompt_parts: Vec<String> = Vec::new();
+    system_prompt_parts.extend(preamble.map(str::to_string));
+
+    // AI language preference
+    if let Some(lang) = req.ai_language {
+        let lang = lang.trim();
+        if !lang.is_empty() {
+            system_prompt_parts.push(format!("Respond to the user in {lang}."));
+        }
+    }
+
+    // Global system prompt from preferences
+    if let Ok(prefs_path) = crate::get_preferences_path(req.app) {
+        if let Ok(contents) = std::fs::read_to_string(&prefs_path) {
+            if let Ok(prefs) = serde_json::from_str::<crate::AppPreferences>(&contents) {
+                if let Some(prompt) = prefs
+                    .magic_prompts
+                    .global_system_prompt
+                    .as_deref()
+                    .map(|s| s.trim())
+                    .filter(|s| !s.is_empty())
+                    .or(default_global_prompt)
+                {
+                    system_prompt_parts.push(prompt.to_string());
+                }
+            }
+        }
+    }
+
+    // Parallel execution prompt
+    if let Some(prompt) = req.parallel_execution_prompt {
+        let prompt = prompt.trim();
+        if !prompt.is_empty() {
+            system_prompt_parts.push(prompt.to_string());
+        }
+    }
+
+    // Per-project custom system prompt
+    if let Ok(data) = load_projects_data(req.app) {
+        if let Some(worktree) = data.find_worktree(req.worktree_id) {
+            if let Some(project) = data.find_project(&worktree.project_id) {
+                if let Some(prompt) = &project.custom_system_prompt {
+                    let prompt = prompt.trim();
+                    if !prompt.is_empty() {
+                        system_prompt_parts.push(prompt.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    // Embedded binary path hints
+    let gh_binary = crate::gh_cli::config::resolve_gh_binary(req.app);
+    if gh_binary != std::path::PathBuf::from("gh") {
+        system_prompt_parts.push(format!(
+            "When running GitHub CLI commands, use the full path to the embedded binary: {}\n\
+                 Do NOT use bare `gh` — always use the full path above.",
+            gh_binary.display()
+        ));
+    }
+    if let Ok(claude_binary) = crate::claude_cli::get_cli_binary_path(req.app) {
+        if claude_binary.exists() {
+            system_prompt_parts.push(format!(
+                "When running Claude CLI commands, use the full path to the embedded binary: {}\n\
+                     Do NOT use bare `claude` — always use the full path above.",
+                claude_binary.display()
+            ));
+        }
+    }
+    if let Ok(codex_binary) = crate::codex_cli::get_cli_binary_path(req.app) {
+        if codex_binary.exists() {
+            system_prompt_parts.push(format!(
+                "When running Codex CLI commands, use the full path to the embedded binary: {}\n\
+                     Do NOT use bare `codex` — always use the full path above.",
+                codex_binary.display()
+            ));
+        }
+    }
+
+    // Collect and inline context files (issues, PRs, saved contexts)
+    let mut context_content = String::new();
+
+    let mut issue_keys = get_session_issue_refs(req.app, req.session_id).unwrap_or_default();
+    if let Ok(wt_keys) = get_session_issue_refs(req.app, req.worktree_id) {
+        for key in wt_keys {
+            if !issue_keys.contains(&key) {
+                issue_keys.push(key);
+            }
+        }
+    }
+    if !issue_keys.is_empty() {
+        if let Ok(contexts_dir) = get_github_contexts_dir(req.app) {
+            for key in &issue_keys {
+                let parts: Vec<&str> = key.rsplitn(2, '-').collect();
+                if parts.len() == 2 {
+                    let number = parts[0];
+                    let repo_key = parts[1];
+                    let file_path = contexts_dir.join(format!("{repo_key}-issue-{number}.md"));
+                    if let Ok(content) = std::fs::read_to_string(&file_path) {
+                        context_content.push_str(&content);
+                        context_content.push_str("\n\n---\n\n");
+                    }
+                }
+            }
+        }
+    }
+
+    let mut pr_keys = get_session_pr_refs(req.app, req.session_id).unwrap_or_default();
+    if let Ok(wt_keys) = get_session_pr_refs(req.app, req.worktree_id) {
+        for key in wt_keys {
+            if !pr_keys.contains(&key) {
+                pr_keys.push(key);
+            }
+        }
+    }
+    if !pr_keys.is_empty() {
+        if let Ok(contexts_dir) = get_github_contexts_dir(req.app) {
+            for key in &pr_keys {
+                let parts: Vec<&str> = key.rsplitn(2, '-').collect();
+                if parts.len() == 2 {
+                    let number = parts[0];
+                    let repo_key = parts[1];
+                    let file_path = contexts_dir.join(format!("{repo_key}-pr-{number}.md"));
+                    if let Ok(content) = std::fs::read_to_string(&file_path) {
+                        context_content.push_str(&content);
+                        context_content.push_str("\n\n---\n\n");
+                    }
+                }
+            }
+        }
+    }
+
+    // Saved context files
+    if let Ok(app_data_dir) = req.app.path().app_data_dir() {
+        let saved_contexts_dir = app_data_dir.join("session-context");
+        if saved_contexts_dir.exists() {
+            let prefix = format!("{}-context-", req.session_id);
+            if let Ok(entries) = std::fs::read_dir(&saved_contexts_dir) {
+                let mut context_files: Vec<_> = entries
+                    .flatten()
+                    .filter(|entry| {
+                        let name = entry.file_name().to_string_lossy().to_string();
+                        name.starts_with(&prefix) && name.ends_with(".md")
+                    })
+                    .collect();
+                context_files.sort_by_key(|e| e.file_name());
+                for entry in context_files {
+                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
+                        context_content.push_str(&content);
+                        context_content.push_str("\n\n---\n\n");
+                    }
+                }
+            }
+        }
+    }
+
+    // Build final system prompt
+    let mut final_prompt = String::new();
+    if !system_prompt_parts.is_empty() {
+        final_prompt.push_str(&system_prompt_parts.join("\n\n"));
+    }
+    if !context_content.is_empty() {
+        if !final_prompt.is_empty() {
+            final_prompt.push_str("\n\n---\n\n");
+        }
+        final_prompt.push_str("# Loaded Context\n\n");
+        final_prompt.push_str(
+            "The following context has been loaded. \
+                 You should be aware of this when working on this task.\n\n---\n\n",
+        );
+        final_prompt.push_str(&context_content);
+    }
+
+    if final_prompt.is_empty() {
+        None
+    } else {
+        Some(final_prompt)
+    }
+}
+
+// =============================================================================
+// Claude CLI
+// =============================================================================
+
+pub struct ClaudeBackend;
+
+impl AgentBackend for ClaudeBackend {
+    fn kind(&self) -> Backend {
+        Backend::Claude
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Claude CLI"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            plan_mode: true,
+            plan_approval_tool: true,
+            thinking_levels: true,
+            effort_levels: true,
+            approvals: false,
+            web_search_in_plan: true,
+            detached: true,
+            watchdog: true,
+            native_fork: true,
+            custom_profiles: true,
+            multi_agent: false,
+            vision: true,
+            policy_enforcement: PolicyEnforcement::CliSettings,
+        }
+    }
+
+    fn resume_id(&self, session: &Session) -> Option<String> {
+        session.claude_session_id.clone()
+    }
+
+    fn set_resume_id(&self, session: &mut Session, resume_id: Option<String>) {
+        session.claude_session_id = resume_id;
+    }
+
+    fn run_log_resume_id<'a>(&self, resume_id: &'a str) -> Option<&'a str> {
+        (!resume_id.is_empty()).then_some(resume_id)
+    }
+
+    fn execute(&self, req: &ExecuteRequest) -> Result<(u32, AgentResponse), String> {
+        // Web search in plan mode means allowing the WebFetch/WebSearch tools
+        let mut allowed_tools = req
+            .allowed_tools
+            .map(<[String]>::to_vec)
+            .unwrap_or_default();
+        if req.web_search {
+            allowed_tools.push("WebFetch".to_string());
+            allowed_tools.push("WebSearch".to_string());
+        }
+        let allowed_tools = (!allowed_tools.is_empty()).then_some(allowed_tools.as_slice());
+
+        let mut claude_session_id_for_call = req.resume_id.map(str::to_string);
+        loop {
+            log::trace!("About to call execute_claude_detached...");
+
+            match super::claude::execute_claude_detached(
+                req.app,
+                req.session_id,
+                req.worktree_id,
+                req.input_file,
+                req.output_file,
+                req.working_dir,
+                claude_session_id_for_call.as_deref(),
+                req.model,
+                req.execution_mode,
+                req.thinking_level,
+                req.effort_level,
+                allowed_tools,
+                req.parallel_execution_prompt,
+                req.ai_language,
+                req.mcp_config,
+                req.chrome_enabled,
+                req.custom_profile_name,
+                req.fork,
+                Some((req.pid_callback)()),
+            ) {
+                Ok((pid, response)) => {
+                    log::trace!("execute_claude_detached succeeded (PID: {pid})");
+
+                    let compacted = response
+                        .content_blocks
+                        .iter()
+                        .any(|b| matches!(b, ContentBlock::Compaction { .. }));
+                    if response.content.is_empty()
+                        && response.usage.is_none()
+                        && !compacted
+                        && claude_session_id_for_call.is_some()
+                    {
+                        log::warn!(
+                            "Empty response while resuming session {}, clearing stale session ID",
+                            claude_session_id_for_call.as_deref().unwrap_or("")
+                        );
+                        let _ = with_sessions_mut(
+                            req.app,
+                            req.worktree_path,
+                            req.worktree_id,
+                            |sessions| {
+                                if let Some(session) = sessions.find_session_mut(req.session_id) {
+                                    session.claude_session_id = None;
+                                }
+                                Ok(())
+                            },
+                        );
+                    }
+
+                    break Ok((pid, response.into()));
+                }
+                Err(e) => {
+                    let is_session_not_found = e.to_lowercase().contains("session")
+                        && (e.to_lowercase().contains("not found")
+                            || e.to_lowercase().contains("invalid")
+                            || e.to_lowercase().contains("expired"));
+
+                    if is_session_not_found && claude_session_id_for_call.is_some() {
+                        log::warn!(
+                            "Session not found, clearing stored session ID and retrying: {}",
+                            claude_session_id_for_call.as_deref().unwrap_or("")
+                        );
+                        match with_sessions_mut(
+                            req.app,
+                            req.worktree_path,
+                            req.worktree_id,
+                            |sessions| {
+                                if let Some(session) = sessions.find_session_mut(req.session_id) {
+                                    session.claude_session_id = None;
+                                }
+                                Ok(())
+                            },
+                        ) {
+                            Ok(_) => {
+                                claude_session_id_for_call = None;
+                                continue;
+                            }
+                            Err(e) => {
+                                break Err(format!(
+                                    "Session expired and failed to clear stale session state: {e}"
+                                ));
+                            }
+                        }
+                    }
+
+                    log::error!("execute_claude_detached FAILED: {e}");
+                    break Err(e);
+                }
+            }
+        }
+    }
+
+    fn tail(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        output_file: &Path,
+        pid: u32,
+        _execution_mode: Option<&str>,
+    ) -> Result<AgentResponse, String> {
+        super::claude::tail_claude_output(app, session_id, worktree_id, output_file, pid)
+            .map(Into::into)
+    }
+
+    fn one_shot(
+        &self,
+        app: &AppHandle,
+        prompt: &str,
+        model: &str,
+        json_schema: &str,
+        working_dir: Option<&Path>,
+        custom_profile_name: Option<&str>,
+    ) -> Result<String, String> {
+        super::claude::execute_one_shot_claude(
+            app,
+            prompt,
+            model,
+            json_schema,
+            working_dir,
+            custom_profile_name,
+        )
+    }
+}
+
+// =============================================================================
+// Codex CLI
+// =============================================================================
+
+/// Global system prompt for Codex when none is set in preferences
+const CODEX_DEFAULT_SYSTEM_PROMPT: &str = "\
+## Plan Mode\n\
+\n\
+- Make the plan extremely concise. Sacrifice grammar for the sake of concision.\n\
+- At the end of each plan, give me a list of unresolved questions to answer, if any.\n\
+\n\
+## Not Plan Mode\n\
+\n\
+- After each finished task, please write a few bullet points on how to test the changes.\n\
+- When multiple independent operations are needed, batch them into parallel tool calls. Launch independent Task subagents simultaneously rather than sequentially.\n\
+- When specifying subagent_type for Task tool calls, always use the fully qualified name exactly as listed in the system prompt (e.g., \"code-simplifier:code-simplifier\", not just \"code-simplifier\"). If the agent type contains a colon, include the full namespace:name string.";
+
+/// Leads the instructions of plan-mode runs, which Codex runs in a read-only
+/// sandbox
+const CODEX_PLAN_MODE_PROMPT: &str =
+    "You are in PLANNING MODE (read-only sandbox). Create a detailed implementation plan. \
+     Do NOT attempt to make any file changes — you are running in a read-only sandbox and writes will fail. \
+     Describe exactly what changes you WOULD make: which files to create/modify, \
+     what code to write, and in what order. End with any unresolved questions.";
+
+pub struct CodexBackend;
+
+impl AgentBackend for CodexBackend {
+    fn kind(&self) -> Backend {
+        Backend::Codex
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Codex CLI"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            plan_mode: true,
+            plan_approval_tool: false,
+            thinking_levels: false,
+            effort_levels: true,
+            approvals: true,
+            web_search_in_plan: true,
+            detached: true,
+            watchdog: true,
+            native_fork: false,
+            custom_profiles: false,
+            multi_agent: true,
+            vision: true,
+            policy_enforcement: PolicyEnforcement::BuildApprovals,
+        }
+    }
+
+    fn handles_model(&self, model: &str) -> bool {
+        crate::is_codex_model(model)
+    }
+
+    fn resume_id(&self, session: &Session) -> Option<String> {
+        session.codex_thread_id.clone()
+    }
+
+    fn set_resume_id(&self, session: &mut Session, resume_id: Option<String>) {
+        session.codex_thread_id = resume_id;
+    }
+
+    fn execute(&self, req: &ExecuteRequest) -> Result<(u32, AgentResponse), String> {
+        log::trace!("About to call execute_codex_detached...");
+
+        let codex_reasoning_effort = reasoning_effort(req.effort_level);
+
+        // Build add_dirs for Codex
+        let mut codex_add_dirs = session_data_dirs(req.app, req.session_id);
+        if let Some(home) = dirs::home_dir() {
+            let codex_skills_dir = home.join(".codex").join("skills");
+            if codex_skills_dir.exists() {
+                codex_add_dirs.push(codex_skills_dir.to_string_lossy().to_string());
+            }
+        }
+
+        // Build combined instructions file (system prompt equivalent for Codex)
+        let plan_prompt = (req.execution_mode == Some("plan")).then_some(CODEX_PLAN_MODE_PROMPT);
+        let codex_instructions_file =
+            inline_system_prompt(req, plan_prompt, Some(CODEX_DEFAULT_SYSTEM_PROMPT)).and_then(
+                |content| {
+                    let app_data_dir = req.app.path().app_data_dir().ok()?;
+                    let combined_dir = app_data_dir.join("combined-contexts");
+                    let _ = std::fs::create_dir_all(&combined_dir);
+                    let combined_file =
+                        combined_dir.join(format!("{}-codex-combined.md", req.session_id));
+
+                    match std::fs::write(&combined_file, &content) {
+                        Ok(_) => {
+                            log::debug!("Created Codex instructions file: {:?}", combined_file);
+                            Some(combined_file)
+                        }
+                        Err(e) => {
+                            log::error!("Failed to write Codex instructions file: {e}");
+                            None
+                        }
+                    }
+                },
+            );
+
+        // For Codex: first message uses positional arg, resume pipes via stdin
+        let prompt = Some(req.message);
+
+        match super::codex::execute_codex_detached(
+            req.app,
+            req.session_id,
+            req.worktree_id,
+            req.output_file,
+            req.working_dir,
+            req.resume_id,
+            req.model,
+            req.execution_mode,
+            codex_reasoning_effort,
+            req.web_search,
+            &codex_add_dirs,
+            prompt,
+            codex_instructions_file.as_deref(),
+            req.multi_agent,
+            req.max_agent_threads,
+            Some((req.pid_callback)()),
+        ) {
+            Ok((pid, response)) => Ok((pid, response.into())),
+            Err(e) => {
+                log::error!("execute_codex_detached FAILED: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    fn tail(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        output_file: &Path,
+        pid: u32,
+        execution_mode: Option<&str>,
+    ) -> Result<AgentResponse, String> {
+        super::codex::tail_codex_output(
+            app,
+            session_id,
+            worktree_id,
+            output_file,
+            pid,
+            execution_mode == Some("plan"),
+        )
+        .map(Into::into)
+    }
+
+    fn one_shot(
+        &self,
+        app: &AppHandle,
+        prompt: &str,
+        model: &str,
+        json_schema: &str,
+        working_dir: Option<&Path>,
+        _custom_profile_name: Option<&str>,
+    ) -> Result<String, String> {
+        super::codex::execute_one_shot_codex(app, prompt, model, json_schema, working_dir)
+    }
+
+    fn parse_run(&self, lines: &[String], run: &RunEntry) -> Result<ChatMessage, String> {
+        super::codex::parse_codex_run_to_message(lines, run)
+    }
+
+    fn replayer(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        execution_mode: Option<&str>,
+    ) -> Box<dyn EventReplayer> {
+        Box::new(super::codex::CodexReplayer::new(
+            app,
+            session_id,
+            worktree_id,
+            execution_mode == Some("plan"),
+        ))
+    }
+}
+
+// =============================================================================
+// OpenCode
+// =============================================================================
+
+pub struct OpencodeBackend;
+
+impl AgentBackend for OpencodeBackend {
+    fn kind(&self) -> Backend {
+        Backend::Opencode
+    }
+
+    fn display_name(&self) -> &'static str {
+        "OpenCode"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            plan_mode: true,
+            plan_approval_tool: false,
+            thinking_levels: false,
+            effort_levels: true,
+            approvals: false,
+            web_search_in_plan: false,
+            detached: false,
+            watchdog: false,
+            native_fork: true,
+            custom_profiles: false,
+            multi_agent: false,
+            vision: true,
+            policy_enforcement: PolicyEnforcement::None,
+        }
+    }
+
+    fn handles_model(&self, model: &str) -> bool {
+        crate::is_opencode_model(model)
+    }
+
+    fn resume_id(&self, session: &Session) -> Option<String> {
+        session.opencode_session_id.clone()
+    }
+
+    fn set_resume_id(&self, session: &mut Session, resume_id: Option<String>) {
+        session.opencode_session_id = resume_id;
+    }
+
+    fn execute(&self, req: &ExecuteRequest) -> Result<(u32, AgentResponse), String> {
+        log::trace!("About to call execute_opencode...");
+        let opencode_reasoning_effort = reasoning_effort(req.effort_level);
+
+        let system_prompt = inline_system_prompt(req, None, None);
+
+        match super::opencode::execute_opencode_http(
+            req.app,
+            req.session_id,
+            req.worktree_id,
+            req.working_dir,
+            req.resume_id,
+            req.model,
+            req.execution_mode,
+            opencode_reasoning_effort,
+            req.message,
+            system_prompt.as_deref(),
+        ) {
+            // OpenCode runs in-process over HTTP, so the app PID stands in for the run
+            Ok(response) => Ok((std::process::id(), response.into())),
+            Err(e) => {
+                log::error!("execute_opencode FAILED: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    fn tail(
+        &self,
+        _app: &AppHandle,
+        _session_id: &str,
+        _worktree_id: &str,
+        _output_file: &Path,
+        _pid: u32,
+        _execution_mode: Option<&str>,
+    ) -> Result<AgentResponse, String> {
+        Err("OpenCode runs are served over HTTP and cannot be re-attached".to_string())
+    }
+
+    fn one_shot(
+        &self,
+        app: &AppHandle,
+        prompt: &str,
+        model: &str,
+        json_schema: &str,
+        working_dir: Option<&Path>,
+        _custom_profile_name: Option<&str>,
+    ) -> Result<String, String> {
+        super::opencode::execute_one_shot_opencode(
+            app,
+            prompt,
+            model,
+            Some(json_schema),
+            working_dir,
+        )
+    }
+}
+
+// =============================================================================
+// Gemini CLI
+// =============================================================================
+
+/// Prepended to plan-mode prompts. Headless Gemini already drops the tools that
+/// would need approval, this keeps the answer a plan rather than a refusal.
+const GEMINI_PLAN_MODE_PROMPT: &str =
+    "You are in PLANNING MODE (read-only). Create a detailed implementation plan. \
+     Do NOT attempt to make any file changes or run commands that modify the workspace. \
+     Describe exactly what changes you WOULD make: which files to create/modify, \
+     what code to write, and in what order. End with any unresolved questions.";
+
+pub struct GeminiBackend;
+
+impl AgentBackend for GeminiBackend {
+    fn kind(&self) -> Backend {
+        Backend::Gemini
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Gemini CLI"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            plan_mode: true,
+            plan_approval_tool: false,
+            thinking_levels: false,
+            effort_levels: false,
+            approvals: false,
+            web_search_in_plan: false,
+            detached: true,
+            watchdog: false,
+            native_fork: false,
+            custom_profiles: false,
+            multi_agent: false,
+            // Attached images are only named in the prompt, as a file for
+            // Claude's Read tool to open
+            vision: false,
+            policy_enforcement: PolicyEnforcement::None,
+        }
+    }
+
+    fn handles_model(&self, model: &str) -> bool {
+        crate::is_gemini_model(model)
+    }
+
+    fn resume_id(&self, session: &Session) -> Option<String> {
+        session.gemini_session_id.clone()
+    }
+
+    fn set_resume_id(&self, session: &mut Session, resume_id: Option<String>) {
+        session.gemini_session_id = resume_id;
+    }
+
+    fn execute(&self, req: &ExecuteRequest) -> Result<(u32, AgentResponse), String> {
+        log::trace!("About to call execute_gemini_detached...");
+
+        // Gemini has no system prompt flag: instructions and loaded context go
+        // in front of the first message, plan instructions in front of every
+        // plan-mode message
+        let mut prompt_parts = Vec::new();
+        if req.execution_mode == Some("plan") {
+            prompt_parts.push(GEMINI_PLAN_MODE_PROMPT.to_string());
+        }
+        if req.resume_id.is_none() {
+            prompt_parts.extend(inline_system_prompt(req, None, None));
+        }
+        let prompt = if prompt_parts.is_empty() {
+            req.message.to_string()
+        } else {
+            format!("{}\n\n---\n\n{}", prompt_parts.join("\n\n"), req.message)
+        };
+
+        // Headless Gemini reads the prompt from stdin as plain text
+        let prompt_file = req.input_file.with_extension("txt");
+        std::fs::write(&prompt_file, &prompt)
+            .map_err(|e| format!("Failed to write Gemini prompt file: {e}"))?;
+
+        match super::gemini::execute_gemini_detached(
+            req.app,
+            req.session_id,
+            req.worktree_id,
+            &prompt_file,
+            req.output_file,
+            req.working_dir,
+            req.resume_id,
+            req.model,
+            req.execution_mode,
+            &session_data_dirs(req.app, req.session_id),
+            req.mcp_config,
+            Some((req.pid_callback)()),
+        ) {
+            Ok((pid, response)) => Ok((pid, response.into())),
+            Err(e) => {
+                log::error!("execute_gemini_detached FAILED: {e}");
+                Err(e)
+            }
+        }
+    }
+
+    fn tail(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        output_file: &Path,
+        pid: u32,
+        execution_mode: Option<&str>,
+    ) -> Result<AgentResponse, String> {
+        super::gemini::tail_gemini_output(
+            app,
+            session_id,
+            worktree_id,
+            output_file,
+            pid,
+            execution_mode == Some("plan"),
+        )
+        .map(Into::into)
+    }
+
+    fn one_shot(
+        &self,
+        app: &AppHandle,
+        prompt: &str,
+        model: &str,
+        json_schema: &str,
+        working_dir: Option<&Path>,
+        _custom_profile_name: Option<&str>,
+    ) -> Result<String, String> {
+        super::gemini::execute_one_shot_gemini(app, prompt, model, json_schema, working_dir)
+    }
+
+    fn parse_run(&self, lines: &[String], run: &RunEntry) -> Result<ChatMessage, String> {
+        super::gemini::parse_gemini_run_to_message(lines, run)
+    }
+
+    fn replayer(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        execution_mode: Option<&str>,
+    ) -> Box<dyn EventReplayer> {
+        Box::new(super::gemini::GeminiReplayer::new(
+            app,
+            session_id,
+            worktree_id,
+            execution_mode == Some("plan"),
+        ))
+    }
+}
+
+// =============================================================================
+// Mock (scripted fixtures, for tests)
+// =============================================================================
+
+pub struct MockBackend;
+
+impl AgentBackend for MockBackend {
+    fn kind(&self) -> Backend {
+        Backend::Mock
+    }
+
+    fn display_name(&self) -> &'static str {
+        "Mock"
+    }
+
+    fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            plan_mode: true,
+            plan_approval_tool: true,
+            thinking_levels: false,
+            effort_levels: false,
+            approvals: false,
+            web_search_in_plan: false,
+            detached: true,
+            watchdog: false,
+            native_fork: false,
+            custom_profiles: false,
+            multi_agent: false,
+            vision: false,
+            policy_enforcement: PolicyEnforcement::None,
+        }
+    }
+
+    fn handles_model(&self, model: &str) -> bool {
+        super::mock::is_mock_model(model)
+    }
+
+    // Fixtures are replayed from scratch every run, there is nothing to resume
+    fn resume_id(&self, _session: &Session) -> Option<String> {
+        None
+    }
+
+    fn set_resume_id(&self, _session: &mut Session, _resume_id: Option<String>) {}
+
+    fn execute(&self, req: &ExecuteRequest) -> Result<(u32, AgentResponse), String> {
+        let model = req.model.unwrap_or(super::mock::MOCK_MODEL_PREFIX);
+        super::mock::execute_mock_detached(
+            req.app,
+            req.session_id,
+            req.worktree_id,
+            model,
+            req.message,
+            req.input_file,
+            req.output_file,
+            req.working_dir,
+            Some((req.pid_callback)()),
+        )
+        .map(|(pid, response)| (pid, response.into()))
+    }
+
+    fn tail(
+        &self,
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        output_file: &Path,
+        pid: u32,
+        _execution_mode: Option<&str>,
+    ) -> Result<AgentResponse, String> {
+        super::claude::tail_claude_output(app, session_id, worktree_id, output_file, pid)
+            .map(Into::into)
+    }
+
+    fn one_shot(
+        &self,
+        app: &AppHandle,
+        prompt: &str,
+        model: &str,
+        _json_schema: &str,
+        _working_dir: Option<&Path>,
+        _custom_profile_name: Option<&str>,
+    ) -> Result<String, String> {
+        super::mock::execute_one_shot_mock(app, prompt, model)
+    }
+}
+
+// =============================================================================
+// Registry
+// =============================================================================
+
+/// All backends, in the order model IDs are matched (mock first, so fixture
+/// names can't be claimed by another backend's model pattern)
+static BACKENDS: [&dyn AgentBackend; 5] = [
+    &ClaudeBackend,
+    &MockBackend,
+    &CodexBackend,
+    &OpencodeBackend,
+    &GeminiBackend,
+];
+
+/// The implementation behind a session backend
+pub fn backend_for(backend: &Backend) -> &'static dyn AgentBackend {
+    BACKENDS
+        .iter()
+        .copied()
+        .find(|b| b.kind() == *backend)
+        .unwrap_or(&ClaudeBackend)
+}
+
+/// The backend that owns a model ID. Claude model aliases (`opus`, `haiku`, ...)
+/// are not claimed by anyone, so callers fall back to their default backend.
+pub fn backend_for_model(model: &str) -> Option<&'static dyn AgentBackend> {
+    BACKENDS.iter().copied().find(|b| b.handles_model(model))
+}
+
+/// The backend to run a one-shot prompt on: the model's owner, else Claude
+pub fn one_shot_backend(model: &str) -> &'static dyn AgentBackend {
+    backend_for_model(model).unwrap_or(&ClaudeBackend)
+}
+
+/// Parse a backend name ("claude", "codex", "opencode", "gemini", "mock")
+pub fn parse_backend(name: &str) -> Option<Backend> {
+    serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+}
+
+/// Capabilities of a backend, so the UI can hide unsupported options
+#[tauri::command]
+pub async fn get_backend_capabilities(backend: String) -> Result<BackendCapabilities, String> {
+    let backend = parse_backend(&backend).ok_or_else(|| format!("Unknown backend: {backend}"))?;
+    Ok(backend_for(&backend).capabilities())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_for_model() {
+        let kind = |model: &str| backend_for_model(model).map(|b| b.kind());
+        assert_eq!(
+            kind("opencode/anthropic/claude-sonnet-4"),
+            Some(Backend::Opencode)
+        );
+        assert_eq!(kind("gpt-5.1-codex"), Some(Backend::Codex));
+        assert_eq!(kind("gemini-2.5-pro"), Some(Backend::Gemini));
+        assert_eq!(kind("mock/codex-review"), Some(Backend::Mock));
+        assert_eq!(kind("opus"), None);
+        assert_eq!(one_shot_backend("haiku").kind(), Backend::Claude);
+    }
+
+    #[test]
+    fn test_backend_for_kind() {
+        for backend in [
+            Backend::Claude,
+            Backend::Codex,
+            Backend::Opencode,
+            Backend::Gemini,
+            Backend::Mock,
+        ] {
+            assert_eq!(backend_for(&backend).kind(), backend);
+        }
+        assert_eq!(parse_backend("codex"), Some(Backend::Codex));
+        assert_eq!(parse_backend("gemini"), Some(Backend::Gemini));
+        assert_eq!(parse_backend("aider"), None);
+    }
+
+    #[test]
+    fn test_reasoning_effort() {
+        assert_eq!(reasoning_effort(Some(&EffortLevel::Max)), Some("xhigh"));
+        assert_eq!(reasoning_effort(Some(&EffortLevel::Off)), None);
+        assert_eq!(reasoning_effort(None), None);
+    }
+}
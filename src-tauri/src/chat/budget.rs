@@ -0,0 +1,657 @@
+//! Token and cost budgets
+//!
+//! Budgets are configured per session (default + per-session overrides), per
+//! project and per UTC day, and stored in `app-data/budgets.json`. Each limit has
+//! an optional soft threshold (emits `chat:budget_warning`) and hard threshold.
+//!
+//! - Before a run starts, a session already over a hard limit is refused. The
+//!   spend computed for the check is kept as the baseline for tracking the run.
+//! - While a run streams, usage is fed in by the tailer; crossing a hard limit
+//!   cancels the run via `registry::cancel_process_with_reason`, recording
+//!   `CancelReason::Budget` on the run and emitting `chat:budget_exceeded`.
+//!
+//! Claude reports usage per API call while streaming, so its runs are stopped
+//! mid-stream. Codex and OpenCode only report usage at the end of a run, so their
+//! budgets are enforced at run boundaries.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::cost::{load_pricing_table, PricingTable};
+use super::storage::{list_all_session_ids, load_metadata};
+use super::time::{now, utc_day};
+use super::types::{Backend, BudgetMetric, BudgetScope, CancelReason, SessionMetadata, UsageData};
+use crate::http_server::EmitExt;
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Soft/hard limits for one budget. Unset thresholds are not enforced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct BudgetLimit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hard_cost_usd: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soft_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hard_tokens: Option<u64>,
+}
+
+/// Budget configuration (app-data/budgets.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetSettings {
+    /// Default limit for every session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<BudgetLimit>,
+    /// Per-session overrides of the default (keyed by session ID)
+    #[serde(default)]
+    pub sessions: HashMap<String, BudgetLimit>,
+    /// Per-project limits (keyed by project ID)
+    #[serde(default)]
+    pub projects: HashMap<String, BudgetLimit>,
+    /// Global limit per UTC day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daily: Option<BudgetLimit>,
+}
+
+fn get_budgets_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join("budgets.json"))
+}
+
+fn load_budget_settings(app: &AppHandle) -> BudgetSettings {
+    get_budgets_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Get the budget configuration
+#[tauri::command]
+pub async fn get_budget_settings(app: AppHandle) -> Result<BudgetSettings, String> {
+    Ok(load_budget_settings(&app))
+}
+
+/// Replace the budget configuration
+#[tauri::command]
+pub async fn set_budget_settings(app: AppHandle, settings: BudgetSettings) -> Result<(), String> {
+    log::trace!("Saving budget settings");
+
+    let invalid_cost = |l: &BudgetLimit| {
+        [l.soft_cost_usd, l.hard_cost_usd]
+            .iter()
+            .flatten()
+            .any(|v| !v.is_finite() || *v < 0.0)
+    };
+    let mut all_limits = settings
+        .session
+        .iter()
+        .chain(settings.daily.iter())
+        .chain(settings.sessions.values())
+        .chain(settings.projects.values());
+    if all_limits.any(invalid_cost) {
+        return Err("Budget cost limits must be non-negative numbers".to_string());
+    }
+
+    let path = get_budgets_path(&app)?;
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize budget settings: {e}"))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write budget settings: {e}"))?;
+    fs::rename(&temp_path, &path)
+        .map_err(|e| format!("Failed to finalize budget settings: {e}"))?;
+    Ok(())
+}
+
+// ============================================================================
+// Spend
+// ============================================================================
+
+/// Accumulated spend against a budget
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq)]
+pub struct Spend {
+    pub cost_usd: f64,
+    pub tokens: u64,
+}
+
+impl Spend {
+    fn add(&mut self, usage: &UsageData, cost: Option<f64>) {
+        self.tokens += usage.input_tokens + usage.output_tokens;
+        self.cost_usd += cost.unwrap_or(0.0);
+    }
+
+    fn plus(self, other: Spend) -> Spend {
+        Spend {
+            cost_usd: self.cost_usd + other.cost_usd,
+            tokens: self.tokens + other.tokens,
+        }
+    }
+}
+
+/// Whether a threshold was crossed, and how hard
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Level {
+    Soft,
+    Hard,
+}
+
+/// A crossed threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Breach {
+    metric: BudgetMetric,
+    level: Level,
+    limit: f64,
+    spent: f64,
+}
+
+/// Check spend against a limit. Hard breaches take precedence over soft ones per metric.
+fn evaluate(limit: &BudgetLimit, spent: Spend) -> Vec<Breach> {
+    let mut breaches = Vec::new();
+    let checks = [
+        (
+            BudgetMetric::CostUsd,
+            limit.soft_cost_usd,
+            limit.hard_cost_usd,
+            spent.cost_usd,
+        ),
+        (
+            BudgetMetric::Tokens,
+            limit.soft_tokens.map(|t| t as f64),
+            limit.hard_tokens.map(|t| t as f64),
+            spent.tokens as f64,
+        ),
+    ];
+    for (metric, soft, hard, spent) in checks {
+        if let Some(hard) = hard.filter(|h| spent >= *h) {
+            breaches.push(Breach {
+                metric,
+                level: Level::Hard,
+                limit: hard,
+                spent,
+            });
+        } else if let Some(soft) = soft.filter(|s| spent >= *s) {
+            breaches.push(Breach {
+                metric,
+                level: Level::Soft,
+                limit: soft,
+                spent,
+            });
+        }
+    }
+    breaches
+}
+
+/// The limits that apply to a session, with spend so far (excluding the current run)
+fn applicable_budgets(
+    app: &AppHandle,
+    settings: &BudgetSettings,
+    pricing: &PricingTable,
+    metadata: &SessionMetadata,
+) -> Vec<(BudgetScope, BudgetLimit, Spend)> {
+    let run_spend = |metadata: &SessionMetadata, today: Option<&str>| {
+        let mut spend = Spend::default();
+        for run in &metadata.runs {
+            if today.is_some_and(|day| utc_day(run.started_at) != day) {
+                continue;
+            }
+            if let Some(ref usage) = run.usage {
+                let cost = run
+                    .cost_usd
+                    .or_else(|| pricing.run_cost(run, &metadata.backend));
+                spend.add(usage, cost);
+            }
+        }
+        spend
+    };
+
+    let mut budgets = Vec::new();
+    if let Some(limit) = settings
+        .sessions
+        .get(&metadata.id)
+        .or(settings.session.as_ref())
+    {
+        budgets.push((
+            BudgetScope::Session,
+            limit.clone(),
+            run_spend(metadata, None),
+        ));
+    }
+
+    let projects = crate::projects::storage::load_projects_data(app).ok();
+    let project_id = projects
+        .as_ref()
+        .and_then(|data| data.find_worktree(&metadata.worktree_id))
+        .map(|w| w.project_id.clone());
+    let project_limit = project_id.as_ref().and_then(|id| settings.projects.get(id));
+    if project_limit.is_none() && settings.daily.is_none() {
+        return budgets;
+    }
+
+    // Project and daily budgets need spend across all sessions
+    let project_worktrees: HashSet<&str> = match (&projects, &project_id, project_limit) {
+        (Some(data), Some(pid), Some(_)) => data
+            .worktrees
+            .iter()
+            .filter(|w| &w.project_id == pid)
+            .map(|w| w.id.as_str())
+            .collect(),
+        _ => HashSet::new(),
+    };
+    let today = utc_day(now());
+    let mut project_spend = Spend::default();
+    let mut daily_spend = Spend::default();
+    for session_id in list_all_session_ids(app).unwrap_or_default() {
+        let Ok(Some(other)) = load_metadata(app, &session_id) else {
+            continue;
+        };
+        if project_worktrees.contains(other.worktree_id.as_str()) {
+            project_spend = project_spend.plus(run_spend(&other, None));
+        }
+        if settings.daily.is_some() {
+            daily_spend = daily_spend.plus(run_spend(&other, Some(&today)));
+        }
+    }
+
+    if let Some(limit) = project_limit {
+        budgets.push((BudgetScope::Project, limit.clone(), project_spend));
+    }
+    if let Some(ref limit) = settings.daily {
+        budgets.push((BudgetScope::Daily, limit.clone(), daily_spend));
+    }
+    budgets
+}
+
+fn scope_name(scope: BudgetScope) -> &'static str {
+    match scope {
+        BudgetScope::Session => "session",
+        BudgetScope::Project => "project",
+        BudgetScope::Daily => "daily",
+    }
+}
+
+fn format_amount(metric: BudgetMetric, value: f64) -> String {
+    match metric {
+        BudgetMetric::CostUsd => format!("${value:.2}"),
+        BudgetMetric::Tokens => format!("{} tokens", value as u64),
+    }
+}
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Payload for chat:budget_warning and chat:budget_exceeded
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetEvent {
+    pub session_id: String,
+    pub worktree_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    pub scope: BudgetScope,
+    pub metric: BudgetMetric,
+    pub limit: f64,
+    pub spent: f64,
+}
+
+fn emit_budget_event(
+    app: &AppHandle,
+    event_name: &str,
+    session_id: &str,
+    worktree_id: &str,
+    run_id: Option<&str>,
+    scope: BudgetScope,
+    breach: &Breach,
+) {
+    let event = BudgetEvent {
+        session_id: session_id.to_string(),
+        worktree_id: worktree_id.to_string(),
+        run_id: run_id.map(|s| s.to_string()),
+        scope,
+        metric: breach.metric,
+        limit: breach.limit,
+        spent: breach.spent,
+    };
+    if let Err(e) = app.emit_all(event_name, &event) {
+        log::error!("Failed to emit {event_name}: {e}");
+    }
+}
+
+// ============================================================================
+// Run Tracking
+// ============================================================================
+
+/// Budget state for a streaming run
+struct TrackedRun {
+    run_id: String,
+    worktree_id: String,
+    backend: Backend,
+    model: Option<String>,
+    provider: Option<String>,
+    pricing: PricingTable,
+    budgets: Vec<(BudgetScope, BudgetLimit, Spend)>,
+    /// Usage per API message (Claude repeats a message's usage on every content block)
+    usage_by_message: HashMap<String, UsageData>,
+    warned: HashSet<(BudgetScope, BudgetMetric)>,
+    stopped: bool,
+}
+
+/// Streaming runs with budgets, keyed by session ID
+static TRACKED_RUNS: Lazy<Mutex<HashMap<String, TrackedRun>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The budgets that apply to a run about to start, with spend before it
+pub struct RunBudgets {
+    pricing: PricingTable,
+    budgets: Vec<(BudgetScope, BudgetLimit, Spend)>,
+}
+
+/// Load the budgets for a session's next (or resumed) run. None when the
+/// session has no metadata or no budget applies to it.
+pub fn load_run_budgets(app: &AppHandle, session_id: &str) -> Result<Option<RunBudgets>, String> {
+    let settings = load_budget_settings(app);
+    let Some(metadata) = load_metadata(app, session_id)? else {
+        return Ok(None);
+    };
+    let pricing = load_pricing_table(app);
+    let budgets = applicable_budgets(app, &settings, &pricing, &metadata);
+    if budgets.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(RunBudgets { pricing, budgets }))
+}
+
+/// Refuse to start a run if the session is already over a hard limit.
+/// Emits warnings for soft limits that are already crossed. Returns the
+/// budgets to pass to `track_run` once the run starts.
+pub fn check_can_start(
+    app: &AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+) -> Result<Option<RunBudgets>, String> {
+    let Some(run_budgets) = load_run_budgets(app, session_id)? else {
+        return Ok(None);
+    };
+
+    for (scope, limit, spent) in &run_budgets.budgets {
+        let scope = *scope;
+        for breach in evaluate(limit, *spent) {
+            match breach.level {
+                Level::Hard => {
+                    emit_budget_event(
+                        app,
+                        "chat:budget_exceeded",
+                        session_id,
+                        worktree_id,
+                        None,
+                        scope,
+                        &breach,
+                    );
+                    return Err(format!(
+                        "The {} budget is exhausted ({} of {}). Raise the limit to continue.",
+                        scope_name(scope),
+                        format_amount(breach.metric, breach.spent),
+                        format_amount(breach.metric, breach.limit)
+                    ));
+                }
+                Level::Soft => emit_budget_event(
+                    app,
+                    "chat:budget_warning",
+                    session_id,
+                    worktree_id,
+                    None,
+                    scope,
+                    &breach,
+                ),
+            }
+        }
+    }
+    Ok(Some(run_budgets))
+}
+
+/// Start tracking a run's streaming usage against the session's budgets
+pub fn track_run(
+    session_id: &str,
+    worktree_id: &str,
+    run_id: &str,
+    backend: &Backend,
+    model: Option<&str>,
+    provider: Option<&str>,
+    run_budgets: RunBudgets,
+) {
+    let RunBudgets { pricing, budgets } = run_budgets;
+    TRACKED_RUNS.lock().unwrap().insert(
+        session_id.to_string(),
+        TrackedRun {
+            run_id: run_id.to_string(),
+            worktree_id: worktree_id.to_string(),
+            backend: backend.clone(),
+            model: model.map(|s| s.to_string()),
+            provider: provider.map(|s| s.to_string()),
+            pricing,
+            budgets,
+            usage_by_message: HashMap::new(),
+            warned: HashSet::new(),
+            stopped: false,
+        },
+    );
+}
+
+/// Stop tracking a session's run (called when the run ends)
+pub fn untrack_run(session_id: &str) {
+    TRACKED_RUNS.lock().unwrap().remove(session_id);
+}
+
+/// Record usage reported while a run streams. `message_id` identifies the API
+/// message; repeated reports for the same message replace earlier ones.
+pub fn record_usage(app: &AppHandle, session_id: &str, message_id: &str, usage: UsageData) {
+    let (worktree_id, run_id, warnings, exceeded) = {
+        let mut tracked = TRACKED_RUNS.lock().unwrap();
+        let Some(run) = tracked.get_mut(session_id) else {
+            return;
+        };
+        if run.stopped {
+            return;
+        }
+        run.usage_by_message.insert(message_id.to_string(), usage);
+
+        let mut run_usage = UsageData::default();
+        for u in run.usage_by_message.values() {
+            run_usage.input_tokens += u.input_tokens;
+            run_usage.output_tokens += u.output_tokens;
+            run_usage.cache_read_input_tokens += u.cache_read_input_tokens;
+            run_usage.cache_creation_input_tokens += u.cache_creation_input_tokens;
+        }
+        let mut run_spend = Spend::default();
+        let cost = run.pricing.cost_of(
+            &run_usage,
+            run.model.as_deref(),
+            run.provider.as_deref(),
+            &run.backend,
+        );
+        run_spend.add(&run_usage, cost);
+
+        let mut warnings = Vec::new();
+        let mut exceeded = None;
+        for (scope, limit, baseline) in &run.budgets {
+            for breach in evaluate(limit, baseline.plus(run_spend)) {
+                match breach.level {
+                    Level::Hard if exceeded.is_none() => exceeded = Some((*scope, breach)),
+                    Level::Hard => {}
+                    Level::Soft => {
+                        if run.warned.insert((*scope, breach.metric)) {
+                            warnings.push((*scope, breach));
+                        }
+                    }
+                }
+            }
+        }
+        if exceeded.is_some() {
+            run.stopped = true;
+        }
+        (
+            run.worktree_id.clone(),
+            run.run_id.clone(),
+            warnings,
+            exceeded,
+        )
+    };
+
+    for (scope, breach) in warnings {
+        log::trace!(
+            "Session {session_id} crossed soft {} budget: {:?}",
+            scope_name(scope),
+            breach
+        );
+        emit_budget_event(
+            app,
+            "chat:budget_warning",
+            session_id,
+            &worktree_id,
+            Some(&run_id),
+            scope,
+            &breach,
+        );
+    }
+
+    if let Some((scope, breach)) = exceeded {
+        log::warn!(
+            "Session {session_id} exceeded hard {} budget ({} of {}), cancelling run {run_id}",
+            scope_name(scope),
+            format_amount(breach.metric, breach.spent),
+            format_amount(breach.metric, breach.limit)
+        );
+        emit_budget_event(
+            app,
+            "chat:budget_exceeded",
+            session_id,
+            &worktree_id,
+            Some(&run_id),
+            scope,
+            &breach,
+        );
+        let reason = CancelReason::Budget {
+            scope,
+            metric: breach.metric,
+            limit: breach.limit,
+            spent: breach.spent,
+        };
+        if let Err(e) =
+            super::registry::cancel_process_with_reason(app, session_id, &worktree_id, reason)
+        {
+            log::error!("Failed to cancel over-budget run {run_id}: {e}");
+        }
+    }
+}
+
+/// Budget status for a session: each applicable budget with spend so far
+#[derive(Debug, Clone, Serialize)]
+pub struct BudgetStatus {
+    pub scope: BudgetScope,
+    pub limit: BudgetLimit,
+    pub spent: Spend,
+}
+
+/// Get the budgets that apply to a session and how much of each is used
+#[tauri::command]
+pub async fn get_budget_status(
+    app: AppHandle,
+    session_id: String,
+) -> Result<Vec<BudgetStatus>, String> {
+    let settings = load_budget_settings(&app);
+    let Some(metadata) = load_metadata(&app, &session_id)? else {
+        return Ok(vec![]);
+    };
+    let pricing = load_pricing_table(&app);
+    Ok(applicable_budgets(&app, &settings, &pricing, &metadata)
+        .into_iter()
+        .map(|(scope, limit, spent)| BudgetStatus {
+            scope,
+            limit,
+            spent,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spend(cost_usd: f64, tokens: u64) -> Spend {
+        Spend { cost_usd, tokens }
+    }
+
+    #[test]
+    fn test_evaluate_soft_and_hard() {
+        let limit = BudgetLimit {
+            soft_cost_usd: Some(5.0),
+            hard_cost_usd: Some(10.0),
+            soft_tokens: None,
+            hard_tokens: Some(1_000),
+        };
+
+        assert!(evaluate(&limit, spend(1.0, 10)).is_empty());
+
+        let soft = evaluate(&limit, spend(5.0, 10));
+        assert_eq!(soft.len(), 1);
+        assert_eq!(soft[0].metric, BudgetMetric::CostUsd);
+        assert_eq!(soft[0].level, Level::Soft);
+
+        // Hard replaces soft for the same metric
+        let hard = evaluate(&limit, spend(12.0, 1_500));
+        assert_eq!(hard.len(), 2);
+        assert!(hard.iter().all(|b| b.level == Level::Hard));
+        assert_eq!(hard[1].limit, 1_000.0);
+    }
+
+    #[test]
+    fn test_evaluate_empty_limit_never_breaches() {
+        assert!(evaluate(&BudgetLimit::default(), spend(1e9, u64::MAX / 2)).is_empty());
+    }
+
+    #[test]
+    fn test_spend_counts_input_and_output_tokens() {
+        let mut s = Spend::default();
+        s.add(
+            &UsageData {
+                input_tokens: 100,
+                output_tokens: 50,
+                cache_read_input_tokens: 1_000,
+                cache_creation_input_tokens: 10,
+            },
+            Some(0.25),
+        );
+        assert_eq!(s, spend(0.25, 150));
+        assert_eq!(s.plus(spend(0.75, 50)), spend(1.0, 200));
+    }
+
+    #[test]
+    fn test_cancel_reason_serialization() {
+        let reason = CancelReason::Budget {
+            scope: BudgetScope::Daily,
+            metric: BudgetMetric::CostUsd,
+            limit: 20.0,
+            spent: 20.5,
+        };
+        let json = serde_json::to_value(&reason).unwrap();
+        assert_eq!(json["type"], "budget");
+        assert_eq!(json["scope"], "daily");
+        assert_eq!(json["metric"], "cost_usd");
+        assert_eq!(
+            serde_json::to_value(CancelReason::User).unwrap()["type"],
+            "user"
+        );
+    }
+}
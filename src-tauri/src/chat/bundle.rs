@@ -0,0 +1,919 @@
+//! Session export/import
+//!
+//! A session bundle is a zip archive that holds everything needed to recreate
+//! a session on another machine:
+//! - `bundle.json`: format version and the attachment map
+//! - `metadata.json`: the session's SessionMetadata
+//! - `runs/{run_id}.jsonl`: run NDJSON logs
+//! - `attachments/images/*`, `attachments/texts/*`: pasted images and texts
+//! - `contexts/*`: saved contexts attached to the session
+//! - `plans/*`: plan files written during the session
+//!
+//! Importing remaps session, run and message IDs, relinks attachment paths, and
+//! seeds the next run with the transcript (backend conversation IDs don't travel).
+//!
+//! Transcripts can also be rendered as Markdown or standalone HTML.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use super::commands::{extract_image_paths, extract_text_file_paths};
+use super::run_log::load_session_messages;
+use super::search::is_plan_path;
+use super::storage::{
+    get_images_dir, get_pastes_dir, get_saved_contexts_dir, get_session_dir, load_metadata,
+    with_metadata_mut, with_sessions_mut,
+};
+use super::time::{now, utc_day};
+use super::types::{
+    ChatMessage, ContentBlock, ForkOrigin, MessageRole, RunStatus, Session, SessionMetadata,
+    ToolCall,
+};
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// Kind of file carried in a bundle besides the run logs
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleAttachmentKind {
+    Image,
+    Text,
+    Context,
+    Plan,
+}
+
+/// A file in the bundle and where it lived on the exporting machine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleAttachment {
+    pub kind: BundleAttachmentKind,
+    /// Absolute path on the exporting machine
+    pub original_path: String,
+    /// Entry name inside the archive
+    pub entry: String,
+}
+
+/// Bundle manifest (bundle.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub exported_at: u64,
+    pub app_version: String,
+    pub session_id: String,
+    pub session_name: String,
+    #[serde(default)]
+    pub attachments: Vec<BundleAttachment>,
+}
+
+/// Output format for rendered transcripts
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Markdown,
+    Html,
+}
+
+// ============================================================================
+// Export
+// ============================================================================
+
+/// Collect plan file paths referenced by a session (current plan + plan writes)
+fn collect_plan_paths(metadata: &SessionMetadata, messages: &[ChatMessage]) -> Vec<String> {
+    let mut paths: Vec<String> = metadata.plan_file_path.iter().cloned().collect();
+    for tool in messages.iter().flat_map(|m| m.tool_calls.iter()) {
+        if tool.name != "Write" && tool.name != "Edit" {
+            continue;
+        }
+        if let Some(path) = tool.input.get("file_path").and_then(|v| v.as_str()) {
+            if is_plan_path(path) && !paths.iter().any(|p| p == path) {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    paths
+}
+
+/// Pick a unique archive entry name under `dir` for a file
+fn entry_name(dir: &str, path: &str, used: &mut HashSet<String>) -> String {
+    let file_name = Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let mut entry = format!("{dir}/{file_name}");
+    let mut counter = 1;
+    while used.contains(&entry) {
+        entry = format!("{dir}/{counter}-{file_name}");
+        counter += 1;
+    }
+    used.insert(entry.clone());
+    entry
+}
+
+fn zip_options() -> zip::write::SimpleFileOptions {
+    zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated)
+}
+
+fn write_entry<W: Write + std::io::Seek>(
+    zip: &mut zip::ZipWriter<W>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), String> {
+    zip.start_file(name, zip_options())
+        .map_err(|e| format!("Failed to add {name} to bundle: {e}"))?;
+    zip.write_all(data)
+        .map_err(|e| format!("Failed to write {name} to bundle: {e}"))
+}
+
+/// Export a session as a portable bundle (zip archive) at `output_path`
+#[tauri::command]
+pub async fn export_session_bundle(
+    app: AppHandle,
+    session_id: String,
+    output_path: String,
+) -> Result<BundleManifest, String> {
+    log::trace!("Exporting session {session_id} to {output_path}");
+
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    let messages = load_session_messages(&app, &session_id)?;
+
+    let mut used = HashSet::new();
+    let mut attachments = Vec::new();
+    let mut add = |kind, path: String, dir: &str| {
+        if attachments
+            .iter()
+            .any(|a: &BundleAttachment| a.original_path == path)
+        {
+            return;
+        }
+        let entry = entry_name(dir, &path, &mut used);
+        attachments.push(BundleAttachment {
+            kind,
+            original_path: path,
+            entry,
+        });
+    };
+
+    for run in &metadata.runs {
+        for path in extract_image_paths(&run.user_message) {
+            add(BundleAttachmentKind::Image, path, "attachments/images");
+        }
+        for path in extract_text_file_paths(&run.user_message) {
+            add(BundleAttachmentKind::Text, path, "attachments/texts");
+        }
+    }
+    let contexts_dir = get_saved_contexts_dir(&app)?;
+    let context_prefix = format!("{session_id}-context-");
+    if let Ok(entries) = fs::read_dir(&contexts_dir) {
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if file_name.starts_with(&context_prefix) && file_name.ends_with(".md") {
+                add(
+                    BundleAttachmentKind::Context,
+                    entry.path().to_string_lossy().to_string(),
+                    "contexts",
+                );
+            }
+        }
+    }
+    for path in collect_plan_paths(&metadata, &messages) {
+        add(BundleAttachmentKind::Plan, path, "plans");
+    }
+
+    // Only keep attachments that still exist on disk
+    attachments.retain(|a| Path::new(&a.original_path).is_file());
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        exported_at: now(),
+        app_version: app.package_info().version.to_string(),
+        session_id: metadata.id.clone(),
+        session_name: metadata.name.clone(),
+        attachments,
+    };
+
+    let file =
+        File::create(&output_path).map_err(|e| format!("Failed to create bundle file: {e}"))?;
+    let mut zip = zip::ZipWriter::new(file);
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize bundle manifest: {e}"))?;
+    write_entry(&mut zip, "bundle.json", &manifest_json)?;
+
+    let metadata_json = serde_json::to_vec_pretty(&metadata)
+        .map_err(|e| format!("Failed to serialize session metadata: {e}"))?;
+    write_entry(&mut zip, "metadata.json", &metadata_json)?;
+
+    let session_dir = get_session_dir(&app, &session_id)?;
+    for run in &metadata.runs {
+        let log_path = session_dir.join(format!("{}.jsonl", run.run_id));
+        // Bundles always carry plain logs, even for runs gzipped by storage maintenance
+        let Some(mut reader) = super::retention::open_run_log(&log_path)? else {
+            continue;
+        };
+        let mut data = Vec::new();
+        reader
+            .read_to_end(&mut data)
+            .map_err(|e| format!("Failed to read run log: {e}"))?;
+        write_entry(&mut zip, &format!("runs/{}.jsonl", run.run_id), &data)?;
+    }
+
+    for attachment in &manifest.attachments {
+        let data = fs::read(&attachment.original_path).map_err(|e| {
+            format!(
+                "Failed to read attachment {}: {e}",
+                attachment.original_path
+            )
+        })?;
+        write_entry(&mut zip, &attachment.entry, &data)?;
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finish bundle: {e}"))?;
+
+    log::trace!(
+        "Exported session {session_id} ({} run(s), {} attachment(s))",
+        metadata.runs.len(),
+        manifest.attachments.len()
+    );
+    Ok(manifest)
+}
+
+// ============================================================================
+// Import
+// ============================================================================
+
+fn read_entry<R: Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<Vec<u8>, String> {
+    let mut file = archive
+        .by_name(name)
+        .map_err(|e| format!("Bundle is missing {name}: {e}"))?;
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .map_err(|e| format!("Failed to read {name} from bundle: {e}"))?;
+    Ok(data)
+}
+
+/// Choose a destination path in `dir` that doesn't clobber an existing file
+fn unique_destination(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let short_id = &Uuid::new_v4().to_string()[..8];
+    dir.join(format!("{short_id}-{file_name}"))
+}
+
+/// Replace every old path with its new path, both raw and JSON-escaped
+/// (run logs store paths inside JSON strings)
+fn relink_paths(text: &str, path_map: &[(String, String)]) -> String {
+    let mut result = text.to_string();
+    for (old, new) in path_map {
+        if result.contains(old.as_str()) {
+            result = result.replace(old.as_str(), new);
+        }
+        let old_escaped = serde_json::to_string(old).unwrap_or_default();
+        let new_escaped = serde_json::to_string(new).unwrap_or_default();
+        let old_inner = old_escaped.trim_matches('"');
+        if old_inner != old && result.contains(old_inner) {
+            result = result.replace(old_inner, new_escaped.trim_matches('"'));
+        }
+    }
+    result
+}
+
+/// Rewrite a run log for its new session: relinked paths and a new `_run_meta` header
+fn rewrite_run_log(
+    data: &str,
+    path_map: &[(String, String)],
+    new_run_id: &str,
+    new_session_id: &str,
+    new_worktree_id: &str,
+    message_ids: &HashMap<String, String>,
+) -> String {
+    let mut out = String::with_capacity(data.len());
+    for (idx, line) in data.lines().enumerate() {
+        let mut line = relink_paths(line, path_map);
+        if idx == 0 && line.contains("\"_run_meta\"") {
+            if let Ok(mut meta) = serde_json::from_str::<serde_json::Value>(&line) {
+                meta["run_id"] = serde_json::json!(new_run_id);
+                meta["session_id"] = serde_json::json!(new_session_id);
+                meta["worktree_id"] = serde_json::json!(new_worktree_id);
+                if let Some(new_id) = meta
+                    .get("user_message_id")
+                    .and_then(|v| v.as_str())
+                    .and_then(|id| message_ids.get(id))
+                {
+                    meta["user_message_id"] = serde_json::json!(new_id);
+                }
+                line = meta.to_string();
+            }
+        }
+        out.push_str(&line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Remap IDs and reset machine-specific state on imported metadata
+fn remap_metadata(
+    mut imported: SessionMetadata,
+    message_ids: &HashMap<String, String>,
+    run_ids: &HashMap<String, String>,
+    path_map: &[(String, String)],
+) -> SessionMetadata {
+    let remap = |id: &String| message_ids.get(id).cloned().unwrap_or_else(|| id.clone());
+
+    for run in &mut imported.runs {
+        run.run_id = run_ids
+            .get(&run.run_id)
+            .cloned()
+            .unwrap_or_else(|| run.run_id.clone());
+        run.user_message_id = remap(&run.user_message_id);
+        run.assistant_message_id = run.assistant_message_id.as_ref().map(remap);
+        run.user_message = relink_paths(&run.user_message, path_map);
+        run.pid = None;
+        run.claude_session_id = None;
+        if matches!(run.status, RunStatus::Running | RunStatus::Resumable) {
+            run.status = RunStatus::Crashed;
+        }
+    }
+    imported.approved_plan_message_ids = imported
+        .approved_plan_message_ids
+        .iter()
+        .map(remap)
+        .collect();
+    imported.pending_plan_message_id = imported.pending_plan_message_id.as_ref().map(remap);
+    imported.plan_file_path = imported
+        .plan_file_path
+        .as_ref()
+        .map(|p| relink_paths(p, path_map));
+
+    // Backend conversations and in-flight state don't travel between machines
+    imported.claude_session_id = None;
+    imported.codex_thread_id = None;
+    imported.opencode_session_id = None;
+    imported.gemini_session_id = None;
+    imported.pending_permission_denials.clear();
+    imported.denied_message_context = None;
+    imported.queued_messages.clear();
+    imported.is_reviewing = false;
+    imported.archived_at = None;
+    imported.last_opened_at = None;
+    imported
+}
+
+/// Import a session bundle into a worktree. IDs are remapped and attachments
+/// are copied into this machine's app data directory.
+#[tauri::command]
+pub async fn import_session_bundle(
+    app: AppHandle,
+    worktree_id: String,
+    bundle_path: String,
+) -> Result<Session, String> {
+    log::trace!("Importing session bundle {bundle_path} into worktree {worktree_id}");
+
+    let file = File::open(&bundle_path).map_err(|e| format!("Failed to open bundle: {e}"))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read bundle archive: {e}"))?;
+
+    let manifest: BundleManifest =
+        serde_json::from_slice(&read_entry(&mut archive, "bundle.json")?)
+            .map_err(|e| format!("Failed to parse bundle manifest: {e}"))?;
+    if manifest.format_version > BUNDLE_FORMAT_VERSION {
+        return Err(format!(
+            "Bundle format version {} is newer than supported ({BUNDLE_FORMAT_VERSION}). Update the app to import it.",
+            manifest.format_version
+        ));
+    }
+    // Bundles exported by older versions carry older metadata formats
+    let mut imported: serde_json::Value =
+        serde_json::from_slice(&read_entry(&mut archive, "metadata.json")?)
+            .map_err(|e| format!("Failed to parse session metadata: {e}"))?;
+    crate::migrations::migrate_value(
+        crate::migrations::StorageFormat::SessionMetadata,
+        &mut imported,
+    )
+    .map_err(|e| format!("Failed to upgrade session metadata: {e}"))?;
+    let imported: SessionMetadata = serde_json::from_value(imported)
+        .map_err(|e| format!("Failed to parse session metadata: {e}"))?;
+
+    // Create the new session (index entry + metadata)
+    let session = with_sessions_mut(&app, "", &worktree_id, |sessions| {
+        let mut session = Session::new(
+            imported.name.clone(),
+            sessions.sessions.len() as u32,
+            imported.backend.clone(),
+        );
+        session.session_naming_completed = true;
+        sessions.active_session_id = Some(session.id.clone());
+        sessions.sessions.push(session.clone());
+        Ok(session)
+    })?;
+
+    // Copy attachments and record old → new paths
+    let mut path_map = Vec::new();
+    for attachment in &manifest.attachments {
+        let data = read_entry(&mut archive, &attachment.entry)?;
+        let file_name = Path::new(&attachment.entry)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| "file".to_string());
+        let destination = match attachment.kind {
+            BundleAttachmentKind::Image => unique_destination(&get_images_dir(&app)?, &file_name),
+            BundleAttachmentKind::Text => unique_destination(&get_pastes_dir(&app)?, &file_name),
+            BundleAttachmentKind::Context => {
+                // Saved contexts are keyed by session: {session_id}-context-{slug}.md
+                let slug = file_name
+                    .strip_prefix(&format!("{}-context-", manifest.session_id))
+                    .unwrap_or(&file_name)
+                    .to_string();
+                get_saved_contexts_dir(&app)?.join(format!("{}-context-{slug}", session.id))
+            }
+            BundleAttachmentKind::Plan => {
+                let plans_dir = get_session_dir(&app, &session.id)?.join("plans");
+                fs::create_dir_all(&plans_dir)
+                    .map_err(|e| format!("Failed to create plans directory: {e}"))?;
+                unique_destination(&plans_dir, &file_name)
+            }
+        };
+        fs::write(&destination, &data)
+            .map_err(|e| format!("Failed to write imported attachment: {e}"))?;
+        path_map.push((
+            attachment.original_path.clone(),
+            destination.to_string_lossy().to_string(),
+        ));
+    }
+
+    // Remap IDs
+    let mut message_ids = HashMap::new();
+    let mut run_ids = HashMap::new();
+    for run in &imported.runs {
+        run_ids.insert(run.run_id.clone(), Uuid::new_v4().to_string());
+        message_ids.insert(run.user_message_id.clone(), Uuid::new_v4().to_string());
+        if let Some(ref id) = run.assistant_message_id {
+            message_ids.insert(id.clone(), Uuid::new_v4().to_string());
+        }
+    }
+
+    // Rewrite run logs into the new session directory
+    let session_dir = get_session_dir(&app, &session.id)?;
+    for run in &imported.runs {
+        let entry = format!("runs/{}.jsonl", run.run_id);
+        let Ok(data) = read_entry(&mut archive, &entry) else {
+            log::warn!("Bundle has no log for run {}", run.run_id);
+            continue;
+        };
+        let new_run_id = &run_ids[&run.run_id];
+        let rewritten = rewrite_run_log(
+            &String::from_utf8_lossy(&data),
+            &path_map,
+            new_run_id,
+            &session.id,
+            &worktree_id,
+            &message_ids,
+        );
+        fs::write(session_dir.join(format!("{new_run_id}.jsonl")), rewritten)
+            .map_err(|e| format!("Failed to write imported run log: {e}"))?;
+    }
+
+    let source_session_id = imported.id.clone();
+    let mut remapped = remap_metadata(imported, &message_ids, &run_ids, &path_map);
+
+    // Seed the first run with the transcript, like a fork without native support
+    let last_message_id = remapped.runs.last().map(|r| {
+        r.assistant_message_id
+            .clone()
+            .unwrap_or_else(|| r.user_message_id.clone())
+    });
+    remapped.forked_from = last_message_id.map(|message_id| ForkOrigin {
+        session_id: source_session_id,
+        message_id,
+        forked_at: now(),
+        resume_at: None,
+        pending: true,
+    });
+
+    let metadata = with_metadata_mut(
+        &app,
+        &session.id,
+        &worktree_id,
+        &session.name,
+        session.order,
+        |metadata| {
+            remapped.id = metadata.id.clone();
+            remapped.worktree_id = metadata.worktree_id.clone();
+            remapped.order = metadata.order;
+            remapped.version = metadata.version;
+            *metadata = remapped;
+            Ok(metadata.clone())
+        },
+    )?;
+
+    log::trace!(
+        "Imported session {} as {} ({} run(s), {} attachment(s))",
+        manifest.session_id,
+        session.id,
+        metadata.runs.len(),
+        manifest.attachments.len()
+    );
+    Ok(metadata.to_session())
+}
+
+// ============================================================================
+// Transcript Rendering
+// ============================================================================
+
+/// Format a unix timestamp as `YYYY-MM-DD HH:MM UTC`
+fn format_utc(timestamp: u64) -> String {
+    let secs = timestamp % 86_400;
+    format!(
+        "{} {:02}:{:02} UTC",
+        utc_day(timestamp),
+        secs / 3_600,
+        (secs % 3_600) / 60
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Pick a Markdown code fence longer than any backtick run in the content
+fn code_fence(content: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in content.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    "`".repeat((longest + 1).max(3))
+}
+
+fn tool_input_text(tool: &ToolCall) -> String {
+    serde_json::to_string_pretty(&tool.input).unwrap_or_default()
+}
+
+/// One piece of an assistant message in display order
+enum Part<'a> {
+    Text(&'a str),
+    Thinking(&'a str),
+    Tool(&'a ToolCall),
+}
+
+/// Message content in display order (content blocks when available)
+fn message_parts(message: &ChatMessage) -> Vec<Part<'_>> {
+    if message.content_blocks.is_empty() {
+        let mut parts = vec![Part::Text(message.content.as_str())];
+        parts.extend(message.tool_calls.iter().map(Part::Tool));
+        return parts;
+    }
+    message
+        .content_blocks
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Text { text } => Some(Part::Text(text.as_str())),
+            ContentBlock::Thinking { thinking } => Some(Part::Thinking(thinking.as_str())),
+            ContentBlock::ToolUse { tool_call_id } => message
+                .tool_calls
+                .iter()
+                .find(|t| &t.id == tool_call_id)
+                .map(Part::Tool),
+            // Compaction boundaries are CLI bookkeeping, not part of the transcript
+            ContentBlock::Compaction { .. } => None,
+        })
+        .collect()
+}
+
+fn role_label(message: &ChatMessage) -> &'static str {
+    match message.role {
+        MessageRole::User => "User",
+        MessageRole::Assistant => "Assistant",
+    }
+}
+
+/// Render a transcript as Markdown (tool calls collapse via `<details>`)
+fn render_markdown(metadata: &SessionMetadata, messages: &[ChatMessage]) -> String {
+    let mut out = format!(
+        "# {}\n\n- Session: `{}`\n- Backend: {:?}\n- Created: {}\n",
+        metadata.name,
+        metadata.id,
+        metadata.backend,
+        format_utc(metadata.created_at)
+    );
+
+    for message in messages {
+        out.push_str(&format!(
+            "\n---\n\n## {} · {}\n\n",
+            role_label(message),
+            format_utc(message.timestamp)
+        ));
+        if message.role == MessageRole::User {
+            out.push_str(message.content.trim());
+            out.push('\n');
+            continue;
+        }
+        for part in message_parts(message) {
+            match part {
+                Part::Text(text) if !text.trim().is_empty() => {
+                    out.push_str(text.trim());
+                    out.push_str("\n\n");
+                }
+                Part::Text(_) => {}
+                Part::Thinking(thinking) => {
+                    let fence = code_fence(thinking);
+                    out.push_str(&format!(
+                        "<details>\n<summary>Thinking</summary>\n\n{fence}\n{}\n{fence}\n\n</details>\n\n",
+                        thinking.trim()
+                    ));
+                }
+                Part::Tool(tool) => {
+                    let input = tool_input_text(tool);
+                    let fence = code_fence(&input);
+                    out.push_str(&format!(
+                        "<details>\n<summary>Tool: {}</summary>\n\n{fence}json\n{input}\n{fence}\n",
+                        escape_html(&tool.name)
+                    ));
+                    if let Some(ref output) = tool.output {
+                        let fence = code_fence(output);
+                        out.push_str(&format!(
+                            "\nOutput:\n\n{fence}\n{}\n{fence}\n",
+                            output.trim_end()
+                        ));
+                    }
+                    out.push_str("\n</details>\n\n");
+                }
+            }
+        }
+        if message.cancelled {
+            out.push_str("_(cancelled)_\n\n");
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:900px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.5}\
+header{border-bottom:1px solid #d0d7de;margin-bottom:1rem}\
+.meta{color:#656d76;font-size:.9rem}\
+.message{border:1px solid #d0d7de;border-radius:8px;padding:.75rem 1rem;margin:1rem 0}\
+.message.user{background:#f6f8fa}\
+.role{font-weight:600;margin-bottom:.5rem}\
+.role time{font-weight:400;color:#656d76;font-size:.85rem;margin-left:.5rem}\
+.text{white-space:pre-wrap}\
+details{border:1px solid #d0d7de;border-radius:6px;margin:.5rem 0;padding:.25rem .75rem}\
+summary{cursor:pointer;font-family:ui-monospace,monospace;font-size:.9rem}\
+pre{background:#f6f8fa;padding:.5rem;border-radius:6px;overflow-x:auto;white-space:pre-wrap;word-break:break-word;font-size:.85rem}\
+.cancelled{color:#cf222e;font-style:italic}";
+
+/// Render a transcript as a standalone HTML page (tool calls collapse via `<details>`)
+fn render_html(metadata: &SessionMetadata, messages: &[ChatMessage]) -> String {
+    let title = escape_html(&metadata.name);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<header>\n<h1>{title}</h1>\n<p class=\"meta\">Session <code>{}</code> · {:?} · {}</p>\n</header>\n",
+        escape_html(&metadata.id),
+        metadata.backend,
+        format_utc(metadata.created_at)
+    );
+
+    for message in messages {
+        let class = match message.role {
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+        };
+        out.push_str(&format!(
+            "<section class=\"message {class}\" id=\"{}\">\n<div class=\"role\">{}<time>{}</time></div>\n",
+            escape_html(&message.id),
+            role_label(message),
+            format_utc(message.timestamp)
+        ));
+        if message.role == MessageRole::User {
+            out.push_str(&format!(
+                "<div class=\"text\">{}</div>\n",
+                escape_html(message.content.trim())
+            ));
+        } else {
+            for part in message_parts(message) {
+                match part {
+                    Part::Text(text) if !text.trim().is_empty() => out.push_str(&format!(
+                        "<div class=\"text\">{}</div>\n",
+                        escape_html(text.trim())
+                    )),
+                    Part::Text(_) => {}
+                    Part::Thinking(thinking) => out.push_str(&format!(
+                        "<details><summary>Thinking</summary><pre>{}</pre></details>\n",
+                        escape_html(thinking.trim())
+                    )),
+                    Part::Tool(tool) => {
+                        out.push_str(&format!(
+                            "<details><summary>Tool: {}</summary><pre>{}</pre>",
+                            escape_html(&tool.name),
+                            escape_html(&tool_input_text(tool))
+                        ));
+                        if let Some(ref output) = tool.output {
+                            out.push_str(&format!(
+                                "<div class=\"meta\">Output</div><pre>{}</pre>",
+                                escape_html(output.trim_end())
+                            ));
+                        }
+                        out.push_str("</details>\n");
+                    }
+                }
+            }
+        }
+        if message.cancelled {
+            out.push_str("<p class=\"cancelled\">(cancelled)</p>\n");
+        }
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Render a session transcript as Markdown or standalone HTML.
+/// Writes it to `output_path` when given; always returns the rendered text.
+#[tauri::command]
+pub async fn export_session_transcript(
+    app: AppHandle,
+    session_id: String,
+    format: TranscriptFormat,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    log::trace!("Rendering {format:?} transcript for session {session_id}");
+
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    let messages = load_session_messages(&app, &session_id)?;
+
+    let rendered = match format {
+        TranscriptFormat::Markdown => render_markdown(&metadata, &messages),
+        TranscriptFormat::Html => render_html(&metadata, &messages),
+    };
+
+    if let Some(path) = output_path {
+        fs::write(&path, &rendered).map_err(|e| format!("Failed to write transcript: {e}"))?;
+    }
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(id: &str, output: Option<&str>) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: "Bash".to_string(),
+            input: serde_json::json!({ "command": "echo <hi>" }),
+            output: output.map(|o| o.to_string()),
+            parent_tool_use_id: None,
+        }
+    }
+
+    fn message(role: MessageRole, content: &str, tool_calls: Vec<ToolCall>) -> ChatMessage {
+        ChatMessage {
+            id: Uuid::new_v4().to_string(),
+            session_id: "s1".to_string(),
+            role,
+            content: content.to_string(),
+            timestamp: 0,
+            tool_calls,
+            content_blocks: vec![],
+            cancelled: false,
+            plan_approved: false,
+            model: None,
+            execution_mode: None,
+            thinking_level: None,
+            effort_level: None,
+            recovered: false,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00 UTC");
+        assert_eq!(format_utc(1_700_000_000), "2023-11-14 22:13 UTC");
+    }
+
+    #[test]
+    fn test_code_fence_is_longer_than_content_backticks() {
+        assert_eq!(code_fence("plain"), "```");
+        assert_eq!(code_fence("has ```` inside"), "`````");
+    }
+
+    #[test]
+    fn test_relink_paths_handles_json_escaping() {
+        let map = vec![(r"C:\old\img.png".to_string(), r"D:\new\img.png".to_string())];
+        assert_eq!(
+            relink_paths(r"see C:\old\img.png", &map),
+            r"see D:\new\img.png"
+        );
+        assert_eq!(
+            relink_paths(r#"{"file_path":"C:\\old\\img.png"}"#, &map),
+            r#"{"file_path":"D:\\new\\img.png"}"#
+        );
+    }
+
+    #[test]
+    fn test_rewrite_run_log_header() {
+        let mut ids = HashMap::new();
+        ids.insert("u1".to_string(), "u2".to_string());
+        let data = "{\"_run_meta\":true,\"run_id\":\"r1\",\"session_id\":\"s1\",\"worktree_id\":\"w1\",\"user_message_id\":\"u1\"}\n{\"type\":\"assistant\",\"path\":\"/old/a.png\"}\n";
+        let map = vec![("/old/a.png".to_string(), "/new/a.png".to_string())];
+        let out = rewrite_run_log(data, &map, "r2", "s2", "w2", &ids);
+        let lines: Vec<&str> = out.lines().collect();
+        let meta: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(meta["run_id"], "r2");
+        assert_eq!(meta["session_id"], "s2");
+        assert_eq!(meta["worktree_id"], "w2");
+        assert_eq!(meta["user_message_id"], "u2");
+        assert!(lines[1].contains("/new/a.png"));
+    }
+
+    #[test]
+    fn test_entry_name_dedupes() {
+        let mut used = HashSet::new();
+        assert_eq!(
+            entry_name("plans", "/a/plan.md", &mut used),
+            "plans/plan.md"
+        );
+        assert_eq!(
+            entry_name("plans", "/b/plan.md", &mut used),
+            "plans/1-plan.md"
+        );
+    }
+
+    #[test]
+    fn test_render_html_escapes_and_collapses_tools() {
+        let metadata = SessionMetadata::new(
+            "s1".to_string(),
+            "w1".to_string(),
+            "<Incident>".to_string(),
+            0,
+        );
+        let messages = vec![
+            message(MessageRole::User, "run <script>", vec![]),
+            message(
+                MessageRole::Assistant,
+                "done",
+                vec![tool("t1", Some("<hi>"))],
+            ),
+        ];
+        let html = render_html(&metadata, &messages);
+        assert!(html.contains("<title>&lt;Incident&gt;</title>"));
+        assert!(html.contains("run &lt;script&gt;"));
+        assert!(html.contains("<details><summary>Tool: Bash</summary>"));
+        assert!(!html.contains("<script>"));
+    }
+
+    #[test]
+    fn test_render_markdown_uses_content_block_order() {
+        let metadata =
+            SessionMetadata::new("s1".to_string(), "w1".to_string(), "Demo".to_string(), 0);
+        let mut assistant = message(MessageRole::Assistant, "", vec![tool("t1", None)]);
+        assistant.content_blocks = vec![
+            ContentBlock::Text {
+                text: "before".to_string(),
+            },
+            ContentBlock::ToolUse {
+                tool_call_id: "t1".to_string(),
+            },
+            ContentBlock::Text {
+                text: "after".to_string(),
+            },
+        ];
+        let md = render_markdown(&metadata, &[assistant]);
+        let before = md.find("before").unwrap();
+        let tool_pos = md.find("<summary>Tool: Bash</summary>").unwrap();
+        let after = md.find("after").unwrap();
+        assert!(before < tool_pos && tool_pos < after);
+    }
+}
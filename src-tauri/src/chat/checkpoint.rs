@@ -0,0 +1,498 @@
+//! Worktree checkpoints for agent runs
+//!
+//! Before a build or yolo run starts, the worktree's files (tracked and
+//! untracked, minus ignored ones) are committed into a detached commit kept
+//! under `refs/jean/checkpoints/{session_id}/{run_id}`. The snapshot is built
+//! in a throwaway index, so the user's staging area, HEAD and branches are
+//! left alone, and the hidden ref keeps it from being garbage collected.
+//!
+//! When such a run ends, a second snapshot goes to `{run_id}-end` and the
+//! diff between the two is recorded on the run (`RunEntry::changes`), so each
+//! change can be traced to the run that made it. `get_run_diff` returns the
+//! full diff.
+//!
+//! `restore_checkpoint` writes a snapshot back over the worktree: files are
+//! reset to their checkpointed content and files created since are removed.
+//! Branch history and the staging area are untouched; the state being replaced
+//! is first saved under `refs/jean/checkpoints/{session_id}/pre-restore`.
+//!
+//! Refs are removed with their session, and storage maintenance prunes those
+//! of older runs (see `retention`).
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tauri::AppHandle;
+
+use super::run_log::RunLogWriter;
+use super::storage::load_metadata;
+use super::types::{RunChanges, RunCheckpoint, RunFileChange};
+use crate::platform::silent_command;
+use crate::projects::git_status::{parse_diff, GitDiff};
+
+/// Namespace of the hidden checkpoint refs
+const CHECKPOINT_REFS: &str = "refs/jean/checkpoints";
+
+/// Run ID under which the state replaced by a restore is kept
+const PRE_RESTORE: &str = "pre-restore";
+
+/// Appended to a run's checkpoint ref for its end-of-run snapshot
+const END_SUFFIX: &str = "-end";
+
+/// Identity for snapshot commits, so they don't depend on the user's git config
+const CHECKPOINT_AUTHOR: (&str, &str) = ("Jean", "jean@localhost");
+
+/// Hidden ref for a run's checkpoint
+pub fn checkpoint_ref(session_id: &str, run_id: &str) -> String {
+    format!("{CHECKPOINT_REFS}/{session_id}/{run_id}")
+}
+
+fn git(worktree_path: &Path) -> Command {
+    let mut cmd = silent_command("git");
+    cmd.current_dir(worktree_path);
+    cmd
+}
+
+/// Run a git command and return its trimmed stdout
+fn run_git(cmd: &mut Command, action: &str) -> Result<String, String> {
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run git {action}: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {action} failed: {}", stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// NUL-separated paths printed by `git ls-files -z` / `git ls-tree -z`
+fn split_paths(output: &str) -> HashSet<String> {
+    output
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Absolute path of a file in the worktree's git directory
+fn git_path(worktree_path: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = run_git(
+        git(worktree_path).args(["rev-parse", "--git-path", name]),
+        "rev-parse",
+    )?;
+    Ok(worktree_path.join(path))
+}
+
+/// Temporary index file, removed when dropped
+struct ScratchIndex(PathBuf);
+
+impl ScratchIndex {
+    fn new(worktree_path: &Path, run_id: &str) -> Result<Self, String> {
+        git_path(worktree_path, &format!("jean-checkpoint-{run_id}.index")).map(Self)
+    }
+
+    fn git(&self, worktree_path: &Path) -> Command {
+        let mut cmd = git(worktree_path);
+        cmd.env("GIT_INDEX_FILE", &self.0);
+        cmd
+    }
+}
+
+impl Drop for ScratchIndex {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Commit the worktree's files (built in a scratch index) and point `git_ref`
+/// at the commit. Returns the commit and the HEAD it was taken on.
+fn snapshot(
+    worktree: &Path,
+    git_ref: &str,
+    message: &str,
+) -> Result<(String, Option<String>), String> {
+    let name = git_ref.rsplit('/').next().unwrap_or("snapshot");
+    let index = ScratchIndex::new(worktree, name)?;
+
+    // Start from the real index so `git add` only rehashes changed files
+    if let Ok(real_index) = git_path(worktree, "index") {
+        if real_index.exists() {
+            fs::copy(&real_index, &index.0)
+                .map_err(|e| format!("Failed to copy git index: {e}"))?;
+        }
+    }
+
+    run_git(index.git(worktree).args(["add", "--all"]), "add")?;
+    let tree = run_git(index.git(worktree).arg("write-tree"), "write-tree")?;
+
+    let head = run_git(
+        git(worktree).args(["rev-parse", "--verify", "--quiet", "HEAD"]),
+        "rev-parse",
+    )
+    .ok()
+    .filter(|h| !h.is_empty());
+
+    let (name, email) = CHECKPOINT_AUTHOR;
+    let mut commit_tree = git(worktree);
+    commit_tree
+        .env("GIT_AUTHOR_NAME", name)
+        .env("GIT_AUTHOR_EMAIL", email)
+        .env("GIT_COMMITTER_NAME", name)
+        .env("GIT_COMMITTER_EMAIL", email)
+        .args(["commit-tree", &tree, "-m", message]);
+    if let Some(head) = &head {
+        commit_tree.args(["-p", head]);
+    }
+    let commit = run_git(&mut commit_tree, "commit-tree")?;
+
+    run_git(
+        git(worktree).args(["update-ref", git_ref, &commit]),
+        "update-ref",
+    )?;
+
+    log::trace!("Created snapshot {commit} at {git_ref}");
+    Ok((commit, head))
+}
+
+/// Snapshot the worktree into `refs/jean/checkpoints/{session_id}/{run_id}`
+pub fn create_checkpoint(
+    worktree_path: &str,
+    session_id: &str,
+    run_id: &str,
+) -> Result<RunCheckpoint, String> {
+    let git_ref = checkpoint_ref(session_id, run_id);
+    let (commit, head) = snapshot(
+        Path::new(worktree_path),
+        &git_ref,
+        &format!("Jean checkpoint before run {run_id}"),
+    )?;
+    Ok(RunCheckpoint {
+        git_ref,
+        commit,
+        head,
+    })
+}
+
+/// Unified diff between two snapshots
+fn diff_patch(worktree: &Path, from: &str, to: &str) -> Result<String, String> {
+    let output = git(worktree)
+        .args(["diff", "--unified=3", "--find-renames", from, to])
+        .output()
+        .map_err(|e| format!("Failed to run git diff: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Git diff failed: {}", stderr.trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Snapshot the worktree at the end of a run and diff it against the run's
+/// start checkpoint
+fn run_changes(
+    worktree_path: &str,
+    session_id: &str,
+    run_id: &str,
+    checkpoint: &RunCheckpoint,
+) -> Result<RunChanges, String> {
+    let worktree = Path::new(worktree_path);
+    let git_ref = format!("{}{END_SUFFIX}", checkpoint_ref(session_id, run_id));
+    let (commit, _) = snapshot(
+        worktree,
+        &git_ref,
+        &format!("Jean checkpoint after run {run_id}"),
+    )?;
+
+    let files: Vec<RunFileChange> = parse_diff(&diff_patch(worktree, &checkpoint.commit, &commit)?)
+        .into_iter()
+        .map(|file| RunFileChange {
+            path: file.path,
+            old_path: file.old_path,
+            status: file.status,
+            additions: file.additions,
+            deletions: file.deletions,
+            is_binary: file.is_binary,
+        })
+        .collect();
+
+    Ok(RunChanges {
+        git_ref,
+        commit,
+        additions: files.iter().map(|f| f.additions).sum(),
+        deletions: files.iter().map(|f| f.deletions).sum(),
+        files,
+    })
+}
+
+/// Record which files a finished run created, modified or deleted. Runs that
+/// started without a checkpoint (plan runs) are skipped; failures are logged.
+pub fn record_run_changes(
+    writer: &mut RunLogWriter,
+    worktree_path: &str,
+    checkpoint: Option<&RunCheckpoint>,
+) {
+    let Some(checkpoint) = checkpoint else {
+        return;
+    };
+    let run_id = writer.run_id().to_string();
+    match run_changes(worktree_path, writer.session_id(), &run_id, checkpoint) {
+        Ok(changes) => {
+            log::trace!(
+                "Run {run_id} changed {} file(s) (+{} -{})",
+                changes.files.len(),
+                changes.additions,
+                changes.deletions
+            );
+            if let Err(e) = writer.set_changes(changes) {
+                log::warn!("Failed to record changes of run {run_id}: {e}");
+            }
+        }
+        Err(e) => log::warn!("Failed to attribute changes of run {run_id}: {e}"),
+    }
+}
+
+/// Make the worktree's files match a snapshot commit. HEAD, branches and the
+/// index stay where they are; files are written through a scratch index.
+fn restore_files(worktree_path: &str, commit: &str) -> Result<(), String> {
+    let worktree = Path::new(worktree_path);
+
+    // Files that exist now but weren't in the snapshot are removed afterwards
+    let current = split_paths(&run_git(
+        git(worktree).args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ]),
+        "ls-files",
+    )?);
+    let snapshot = split_paths(&run_git(
+        git(worktree).args(["ls-tree", "-r", "-z", "--name-only", commit]),
+        "ls-tree",
+    )?);
+
+    let index = ScratchIndex::new(worktree, "restore")?;
+    run_git(index.git(worktree).args(["read-tree", commit]), "read-tree")?;
+    run_git(
+        index
+            .git(worktree)
+            .args(["checkout-index", "--all", "--force"]),
+        "checkout-index",
+    )?;
+
+    for path in current.difference(&snapshot) {
+        let target = worktree.join(path);
+        if target.is_file() || target.is_symlink() {
+            fs::remove_file(&target)
+                .map_err(|e| format!("Failed to remove {}: {e}", target.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete a run's checkpoint refs (start and end-of-run snapshots)
+pub(super) fn delete_run_checkpoint(
+    repo_path: &str,
+    session_id: &str,
+    run_id: &str,
+) -> Result<(), String> {
+    let git_ref = checkpoint_ref(session_id, run_id);
+    for git_ref in [format!("{git_ref}{END_SUFFIX}"), git_ref] {
+        run_git(
+            git(Path::new(repo_path)).args(["update-ref", "-d", &git_ref]),
+            "update-ref",
+        )?;
+    }
+    Ok(())
+}
+
+/// Delete all checkpoint refs of a session. Failures are logged, not returned,
+/// since the refs only cost disk space.
+pub fn delete_session_checkpoints(repo_path: &str, session_id: &str) {
+    let prefix = format!("{CHECKPOINT_REFS}/{session_id}/");
+    let refs = match run_git(
+        git(Path::new(repo_path)).args(["for-each-ref", "--format=%(refname)", &prefix]),
+        "for-each-ref",
+    ) {
+        Ok(refs) => refs,
+        Err(e) => {
+            log::warn!("Failed to list checkpoints for session {session_id}: {e}");
+            return;
+        }
+    };
+
+    for git_ref in refs.lines().filter(|r| !r.is_empty()) {
+        if let Err(e) = run_git(
+            git(Path::new(repo_path)).args(["update-ref", "-d", git_ref]),
+            "update-ref",
+        ) {
+            log::warn!("Failed to delete checkpoint {git_ref}: {e}");
+        }
+    }
+}
+
+/// Roll the worktree's files back to the state before a run started.
+///
+/// Only runs with a checkpoint (build/yolo runs) can be restored. Commits and
+/// branches are not touched; uncommitted work being replaced is kept under
+/// the session's `pre-restore` checkpoint ref.
+#[tauri::command]
+pub async fn restore_checkpoint(
+    app: AppHandle,
+    worktree_path: String,
+    session_id: String,
+    run_id: String,
+) -> Result<RunCheckpoint, String> {
+    log::trace!("Restoring checkpoint of run {run_id} in session {session_id}");
+
+    if super::registry::is_process_running(&session_id) {
+        return Err(
+            "Cannot restore a checkpoint while the session has a run in progress".to_string(),
+        );
+    }
+
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    let checkpoint = metadata
+        .find_run(&run_id)
+        .ok_or_else(|| format!("Run not found: {run_id}"))?
+        .checkpoint
+        .clone()
+        .ok_or_else(|| format!("Run {run_id} has no checkpoint"))?;
+
+    // Make sure the snapshot is still there (the ref may have been deleted)
+    let spec = format!("{}^{{commit}}", checkpoint.git_ref);
+    run_git(
+        git(Path::new(&worktree_path)).args(["rev-parse", "--verify", "--quiet", &spec]),
+        "rev-parse",
+    )
+    .map_err(|_| format!("Checkpoint for run {run_id} no longer exists"))?;
+
+    create_checkpoint(&worktree_path, &session_id, PRE_RESTORE)
+        .map_err(|e| format!("Failed to save the current state before restoring: {e}"))?;
+    restore_files(&worktree_path, &checkpoint.git_ref)?;
+
+    log::trace!("Restored checkpoint {}", checkpoint.commit);
+    Ok(checkpoint)
+}
+
+/// Full diff of the files a run changed, between its start checkpoint and its
+/// end-of-run snapshot
+#[tauri::command]
+pub async fn get_run_diff(
+    app: AppHandle,
+    worktree_path: String,
+    session_id: String,
+    run_id: String,
+) -> Result<GitDiff, String> {
+    log::trace!("Getting diff of run {run_id} in session {session_id}");
+
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    let run = metadata
+        .find_run(&run_id)
+        .ok_or_else(|| format!("Run not found: {run_id}"))?;
+    let (Some(checkpoint), Some(changes)) = (&run.checkpoint, &run.changes) else {
+        return Err(format!("Run {run_id} has no recorded changes"));
+    };
+
+    let raw_patch = diff_patch(
+        Path::new(&worktree_path),
+        &checkpoint.git_ref,
+        &changes.git_ref,
+    )?;
+    let files = parse_diff(&raw_patch);
+
+    Ok(GitDiff {
+        diff_type: "run".to_string(),
+        base_ref: checkpoint.commit.clone(),
+        target_ref: changes.commit.clone(),
+        total_additions: files.iter().map(|f| f.additions).sum(),
+        total_deletions: files.iter().map(|f| f.deletions).sum(),
+        files,
+        raw_patch,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_in(dir: &Path, args: &[&str]) -> String {
+        run_git(
+            git(dir)
+                .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+                .args(args),
+            args[0],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checkpoint_changes_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        git_in(root, &["init", "--quiet"]);
+        fs::write(root.join(".gitignore"), "ignored.txt\n").unwrap();
+        fs::write(root.join("tracked.txt"), "v1").unwrap();
+        git_in(root, &["add", "--all"]);
+        git_in(root, &["commit", "--quiet", "-m", "initial"]);
+        let head = git_in(root, &["rev-parse", "HEAD"]);
+
+        // Uncommitted work at checkpoint time
+        fs::write(root.join("tracked.txt"), "v2").unwrap();
+        fs::write(root.join("untracked.txt"), "draft").unwrap();
+        fs::write(root.join("ignored.txt"), "cache").unwrap();
+        let path = root.to_str().unwrap();
+        let checkpoint = create_checkpoint(path, "session", "run").unwrap();
+        assert_eq!(checkpoint.git_ref, "refs/jean/checkpoints/session/run");
+        assert_eq!(checkpoint.head.as_deref(), Some(head.as_str()));
+
+        // What the agent did
+        fs::write(root.join("tracked.txt"), "v3").unwrap();
+        fs::remove_file(root.join("untracked.txt")).unwrap();
+        fs::write(root.join("new.txt"), "agent").unwrap();
+        git_in(root, &["add", "new.txt"]);
+
+        let changes = run_changes(path, "session", "run", &checkpoint).unwrap();
+        assert_eq!(changes.git_ref, "refs/jean/checkpoints/session/run-end");
+        let mut files: Vec<_> = changes
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.status.as_str()))
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                ("new.txt", "added"),
+                ("tracked.txt", "modified"),
+                ("untracked.txt", "deleted"),
+            ]
+        );
+        assert_eq!((changes.additions, changes.deletions), (2, 2));
+
+        restore_files(path, &checkpoint.git_ref).unwrap();
+        assert_eq!(fs::read_to_string(root.join("tracked.txt")).unwrap(), "v2");
+        assert_eq!(
+            fs::read_to_string(root.join("untracked.txt")).unwrap(),
+            "draft"
+        );
+        assert!(!root.join("new.txt").exists());
+        assert!(root.join("ignored.txt").exists());
+        assert_eq!(git_in(root, &["rev-parse", "HEAD"]), head);
+        // What was staged before the restore stays staged
+        assert_eq!(
+            git_in(root, &["diff", "--cached", "--name-only"]),
+            "new.txt"
+        );
+
+        delete_run_checkpoint(path, "session", "run").unwrap();
+        assert!(git_in(root, &["for-each-ref", CHECKPOINT_REFS]).is_empty());
+        create_checkpoint(path, "session", "other").unwrap();
+        delete_session_checkpoints(path, "session");
+        assert!(git_in(root, &["for-each-ref", CHECKPOINT_REFS]).is_empty());
+    }
+}
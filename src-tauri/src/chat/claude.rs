@@ -1,14 +1,16 @@
 use tauri::Manager;
 
+use super::policy::ActivePolicy;
 use super::types::{
-    CompactMetadata, ContentBlock, EffortLevel, PermissionDenial, PermissionDeniedEvent,
-    ThinkingLevel, ToolCall, UsageData,
+    Backend, CompactMetadata, ContentBlock, EffortLevel, ForkOrigin, PermissionDenial,
+    PermissionDeniedEvent, ThinkingLevel, ToolCall, UsageData,
 };
 use crate::http_server::EmitExt;
 use crate::projects::github_issues::{
     get_github_contexts_dir, get_session_issue_refs, get_session_pr_refs,
 };
 use crate::projects::storage::load_projects_data;
+use crate::projects::types::PolicyAction;
 
 // =============================================================================
 // Constants
@@ -88,6 +90,8 @@ pub struct ClaudeResponse {
     pub cancelled: bool,
     /// Token usage for this response
     pub usage: Option<UsageData>,
+    /// Context tokens of the last API call (how full the context window is)
+    pub context_tokens: Option<u64>,
 }
 
 /// Payload for text chunk events sent to frontend
@@ -224,6 +228,7 @@ fn build_claude_args(
     mcp_config: Option<&str>,
     chrome_enabled: bool,
     custom_profile_name: Option<&str>,
+    fork: Option<&ForkOrigin>,
 ) -> (Vec<String>, Vec<(String, String)>) {
     let mut args = Vec::new();
     let mut env_vars = Vec::new();
@@ -335,6 +340,16 @@ fn build_claude_args(
         }
     }
 
+    // Project permission policy from jean.json, enforced by the CLI itself
+    if let Some(permissions) = super::policy::load_policy(app, worktree_id)
+        .and_then(|(_, policy)| super::policy::claude_permission_settings(&policy))
+    {
+        let obj = settings_json.get_or_insert_with(|| serde_json::json!({}));
+        if let Some(map) = obj.as_object_mut() {
+            map.insert("permissions".to_string(), permissions);
+        }
+    }
+
     // Emit --settings if we have any settings to pass
     if let Some(settings) = &settings_json {
         args.push("--settings".to_string());
@@ -652,6 +667,16 @@ fn build_claude_args(
     if let Some(claude_sid) = existing_claude_session_id {
         args.push("--resume".to_string());
         args.push(claude_sid.to_string());
+
+        // Forked session: branch off into a new Claude session ID, truncated at
+        // the message the fork was taken at
+        if let Some(fork) = fork {
+            args.push("--fork-session".to_string());
+            if let Some(ref resume_at) = fork.resume_at {
+                args.push("--resume-session-at".to_string());
+                args.push(resume_at.clone());
+            }
+        }
     }
 
     // Disable background tasks - forces all Task subagents to run in foreground.
@@ -704,6 +729,7 @@ pub fn execute_claude_detached(
     mcp_config: Option<&str>,
     chrome_enabled: bool,
     custom_profile_name: Option<&str>,
+    fork: Option<&ForkOrigin>,
     pid_callback: Option<Box<dyn FnOnce(u32) + Send>>,
 ) -> Result<(u32, ClaudeResponse), String> {
     use super::detached::spawn_detached_claude;
@@ -748,6 +774,7 @@ pub fn execute_claude_detached(
         mcp_config,
         chrome_enabled,
         custom_profile_name,
+        fork,
     );
 
     // Log the full Claude CLI command for debugging
@@ -810,6 +837,7 @@ pub fn execute_claude_detached(
                 content_blocks: vec![],
                 cancelled: true,
                 usage: None,
+                context_tokens: None,
             },
         ));
     }
@@ -861,15 +889,14 @@ pub fn tail_claude_output(
     log::trace!("Output file: {output_file:?}, PID: {pid}");
 
     // Create tailer starting from beginning (we want all content)
-    let mut tailer = NdjsonTailer::new_from_start(output_file)?;
+    let mut tailer = NdjsonTailer::new_from_start_with_timing(output_file)?;
 
-    let mut full_content = String::new();
-    let mut claude_session_id = String::new();
-    let mut tool_calls: Vec<ToolCall> = Vec::new();
-    let mut content_blocks: Vec<ContentBlock> = Vec::new();
-    let mut completed = false;
+    let mut run = ClaudeRunState {
+        policy: ActivePolicy::load(app, session_id, worktree_id, Backend::Claude),
+        ..Default::default()
+    };
+    let mut context = super::compaction::ContextTracker::load(app, session_id, worktree_id);
     let mut cancelled = false;
-    let mut usage: Option<UsageData> = None;
     let mut error_lines: Vec<String> = Vec::new();
 
     // Timeout configuration:
@@ -920,368 +947,66 @@ pub fn tail_claude_output(
                 }
             };
 
-            // Capture session_id from any message that has it
-            if let Some(sid) = msg.get("session_id").and_then(|v| v.as_str()) {
-                if !sid.is_empty() {
-                    claude_session_id = sid.to_string();
+            // Feed streaming usage to context tracking and budget enforcement
+            // (keyed by message id, since each content block of a message
+            // repeats the same usage)
+            if msg.get("type").and_then(|v| v.as_str()) == Some("assistant") {
+                if let Some(message) = msg.get("message") {
+                    if let (Some(message_id), Some(usage_obj)) = (
+                        message.get("id").and_then(|v| v.as_str()),
+                        message.get("usage"),
+                    ) {
+                        if let Ok(message_usage) =
+                            serde_json::from_value::<UsageData>(usage_obj.clone())
+                        {
+                            context.observe(app, session_id, &message_usage);
+                            super::budget::record_usage(app, session_id, message_id, message_usage);
+                        }
+                    }
                 }
             }
 
-            // Track parent_tool_use_id for sub-agent tool calls
-            // Must reset to None for root-level messages, otherwise parallel Tasks get wrong parent
-            let current_parent_tool_use_id = msg
-                .get("parent_tool_use_id")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-
-            let msg_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-            match msg_type {
-                "assistant" => {
-                    if let Some(message) = msg.get("message") {
-                        if let Some(blocks) = message.get("content").and_then(|c| c.as_array()) {
-                            for block in blocks {
-                                let block_type =
-                                    block.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-                                match block_type {
-                                    "text" => {
-                                        if let Some(text) =
-                                            block.get("text").and_then(|v| v.as_str())
-                                        {
-                                            // Skip CLI placeholder text emitted when extended
-                                            // thinking starts before any real text content
-                                            if text == "(no content)" {
-                                                continue;
-                                            }
-                                            full_content.push_str(text);
-                                            content_blocks.push(ContentBlock::Text {
-                                                text: text.to_string(),
-                                            });
-
-                                            // Emit chunk event
-                                            let event = ChunkEvent {
-                                                session_id: session_id.to_string(),
-                                                worktree_id: worktree_id.to_string(),
-                                                content: text.to_string(),
-                                            };
-                                            if let Err(e) = app.emit_all("chat:chunk", &event) {
-                                                log::error!("Failed to emit chunk: {e}");
-                                            }
-                                        }
-                                    }
-                                    "tool_use" => {
-                                        let id = block
-                                            .get("id")
-                                            .and_then(|v| v.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-                                        let name = block
-                                            .get("name")
-                                            .and_then(|v| v.as_str())
-                                            .unwrap_or("")
-                                            .to_string();
-                                        let input = block
-                                            .get("input")
-                                            .cloned()
-                                            .unwrap_or(serde_json::Value::Null);
-
-                                        tool_calls.push(ToolCall {
-                                            id: id.clone(),
-                                            name: name.clone(),
-                                            input: input.clone(),
-                                            output: None,
-                                            parent_tool_use_id: current_parent_tool_use_id.clone(),
-                                        });
-
-                                        content_blocks.push(ContentBlock::ToolUse {
-                                            tool_call_id: id.clone(),
-                                        });
-
-                                        // Emit tool_use event
-                                        let event = ToolUseEvent {
-                                            session_id: session_id.to_string(),
-                                            worktree_id: worktree_id.to_string(),
-                                            id: id.clone(),
-                                            name: name.clone(),
-                                            input: input.clone(),
-                                            parent_tool_use_id: current_parent_tool_use_id.clone(),
-                                        };
-                                        if let Err(e) = app.emit_all("chat:tool_use", &event) {
-                                            log::error!("Failed to emit tool_use: {e}");
-                                        }
+            // Check for blocking tools - kill process and return
+            if let Some(name) = process_claude_event(app, session_id, worktree_id, &msg, &mut run) {
+                log::trace!("Detected blocking tool {name}, killing detached process");
 
-                                        // Emit tool_block event
-                                        let block_event = ToolBlockEvent {
-                                            session_id: session_id.to_string(),
-                                            worktree_id: worktree_id.to_string(),
-                                            tool_call_id: id.clone(),
-                                        };
-                                        if let Err(e) =
-                                            app.emit_all("chat:tool_block", &block_event)
-                                        {
-                                            log::error!("Failed to emit tool_block: {e}");
-                                        }
-
-                                        // Check for blocking tools - kill process and return
-                                        if name == "AskUserQuestion" || name == "ExitPlanMode" {
-                                            log::trace!("Detected blocking tool {name}, killing detached process");
-
-                                            // Kill the detached process
-                                            #[cfg(unix)]
-                                            unsafe {
-                                                libc::kill(pid as i32, libc::SIGKILL);
-                                            }
-                                            #[cfg(windows)]
-                                            {
-                                                let _ = crate::platform::silent_command("taskkill")
-                                                    .args(["/F", "/PID", &pid.to_string()])
-                                                    .output();
-                                            }
-
-                                            // Emit done event so frontend knows streaming is complete
-                                            let done_event = DoneEvent {
-                                                session_id: session_id.to_string(),
-                                                worktree_id: worktree_id.to_string(),
-                                                waiting_for_plan: false,
-                                            };
-                                            if let Err(e) = app.emit_all("chat:done", &done_event) {
-                                                log::error!("Failed to emit done event: {e}");
-                                            }
-
-                                            // Return partial response (blocking tool is already in tool_calls)
-                                            return Ok(ClaudeResponse {
-                                                content: full_content,
-                                                session_id: claude_session_id,
-                                                tool_calls,
-                                                content_blocks,
-                                                cancelled: false,
-                                                usage: None, // No usage for partial responses
-                                            });
-                                        }
-                                    }
-                                    "thinking" => {
-                                        if let Some(thinking) =
-                                            block.get("thinking").and_then(|v| v.as_str())
-                                        {
-                                            content_blocks.push(ContentBlock::Thinking {
-                                                thinking: thinking.to_string(),
-                                            });
-
-                                            let event = ThinkingEvent {
-                                                session_id: session_id.to_string(),
-                                                worktree_id: worktree_id.to_string(),
-                                                content: thinking.to_string(),
-                                            };
-                                            if let Err(e) = app.emit_all("chat:thinking", &event) {
-                                                log::error!("Failed to emit thinking: {e}");
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                    }
+                // Kill the detached process
+                #[cfg(unix)]
+                unsafe {
+                    libc::kill(pid as i32, libc::SIGKILL);
                 }
-                "user" => {
-                    // User messages contain tool results
-                    if let Some(message) = msg.get("message") {
-                        if let Some(blocks) = message.get("content").and_then(|c| c.as_array()) {
-                            for block in blocks {
-                                let block_type =
-                                    block.get("type").and_then(|v| v.as_str()).unwrap_or("");
-
-                                if block_type == "tool_result" {
-                                    let tool_id = block
-                                        .get("tool_use_id")
-                                        .and_then(|v| v.as_str())
-                                        .unwrap_or("");
-                                    // Content can be a string OR an array of content blocks
-                                    let output = block
-                                        .get("content")
-                                        .map(|v| {
-                                            if let Some(s) = v.as_str() {
-                                                s.to_string()
-                                            } else if let Some(arr) = v.as_array() {
-                                                arr.iter()
-                                                    .filter_map(|item| {
-                                                        if item.get("type").and_then(|t| t.as_str())
-                                                            == Some("text")
-                                                        {
-                                                            item.get("text")
-                                                                .and_then(|t| t.as_str())
-                                                                .map(|s| s.to_string())
-                                                        } else {
-                                                            None
-                                                        }
-                                                    })
-                                                    .collect::<Vec<_>>()
-                                                    .join("\n")
-                                            } else {
-                                                String::new()
-                                            }
-                                        })
-                                        .unwrap_or_default();
-
-                                    // Update matching tool call's output
-                                    if let Some(tc) =
-                                        tool_calls.iter_mut().find(|t| t.id == tool_id)
-                                    {
-                                        tc.output = Some(output.clone());
-                                    }
-
-                                    // Emit tool_result event
-                                    let event = ToolResultEvent {
-                                        session_id: session_id.to_string(),
-                                        worktree_id: worktree_id.to_string(),
-                                        tool_use_id: tool_id.to_string(),
-                                        output,
-                                    };
-                                    if let Err(e) = app.emit_all("chat:tool_result", &event) {
-                                        log::error!("Failed to emit tool_result: {e}");
-                                    }
-                                }
-                            }
-                        }
-                    }
+                #[cfg(windows)]
+                {
+                    let _ = crate::platform::silent_command("taskkill")
+                        .args(["/F", "/PID", &pid.to_string()])
+                        .output();
                 }
-                "result" => {
-                    // Final result - Claude CLI completed
-                    if full_content.is_empty() {
-                        if let Some(result) = msg.get("result").and_then(|v| v.as_str()) {
-                            full_content = result.to_string();
-                        }
-                    }
-
-                    // Extract token usage data
-                    if let Some(usage_obj) = msg.get("usage") {
-                        usage = Some(UsageData {
-                            input_tokens: usage_obj
-                                .get("input_tokens")
-                                .and_then(|v| v.as_u64())
-                                .unwrap_or(0),
-                            output_tokens: usage_obj
-                                .get("output_tokens")
-                                .and_then(|v| v.as_u64())
-                                .unwrap_or(0),
-                            cache_read_input_tokens: usage_obj
-                                .get("cache_read_input_tokens")
-                                .and_then(|v| v.as_u64())
-                                .unwrap_or(0),
-                            cache_creation_input_tokens: usage_obj
-                                .get("cache_creation_input_tokens")
-                                .and_then(|v| v.as_u64())
-                                .unwrap_or(0),
-                        });
-                        log::trace!(
-                            "Token usage: input={}, output={}, cache_read={}, cache_create={}",
-                            usage.as_ref().map(|u| u.input_tokens).unwrap_or(0),
-                            usage.as_ref().map(|u| u.output_tokens).unwrap_or(0),
-                            usage
-                                .as_ref()
-                                .map(|u| u.cache_read_input_tokens)
-                                .unwrap_or(0),
-                            usage
-                                .as_ref()
-                                .map(|u| u.cache_creation_input_tokens)
-                                .unwrap_or(0),
-                        );
-                    }
-
-                    // Check for permission denials and emit event
-                    if let Some(denials) = msg.get("permission_denials").and_then(|v| v.as_array())
-                    {
-                        if !denials.is_empty() {
-                            let denial_events: Vec<PermissionDenial> = denials
-                                .iter()
-                                .filter_map(|d| {
-                                    let tool_name = d.get("tool_name")?.as_str()?;
-                                    let tool_input = d.get("tool_input")?;
-
-                                    // Skip plan file cleanup denials (benign Claude housekeeping)
-                                    if tool_name == "Bash" {
-                                        if let Some(cmd) =
-                                            tool_input.get("command").and_then(|c| c.as_str())
-                                        {
-                                            if cmd.contains(".claude/plans/")
-                                                && cmd.starts_with("rm ")
-                                            {
-                                                log::trace!(
-                                                    "Ignoring plan cleanup denial: {}",
-                                                    cmd
-                                                );
-                                                return None;
-                                            }
-                                        }
-                                    }
-
-                                    Some(PermissionDenial {
-                                        tool_name: tool_name.to_string(),
-                                        tool_use_id: d.get("tool_use_id")?.as_str()?.to_string(),
-                                        tool_input: tool_input.clone(),
-                                        rpc_id: None,
-                                    })
-                                })
-                                .collect();
-
-                            if !denial_events.is_empty() {
-                                log::trace!(
-                                    "Emitting permission_denied event with {} denials",
-                                    denial_events.len()
-                                );
-                                let event = PermissionDeniedEvent {
-                                    session_id: session_id.to_string(),
-                                    worktree_id: worktree_id.to_string(),
-                                    denials: denial_events,
-                                };
-                                if let Err(e) = app.emit_all("chat:permission_denied", &event) {
-                                    log::error!("Failed to emit permission_denied: {e}");
-                                }
-                            }
-                        }
-                    }
 
-                    completed = true;
-                    log::trace!("Received result message - Claude CLI completed");
+                // Emit done event so frontend knows streaming is complete
+                let done_event = DoneEvent {
+                    session_id: session_id.to_string(),
+                    worktree_id: worktree_id.to_string(),
+                    waiting_for_plan: false,
+                };
+                if let Err(e) = app.emit_all("chat:done", &done_event) {
+                    log::error!("Failed to emit done event: {e}");
                 }
-                "system" => {
-                    let subtype = msg.get("subtype").and_then(|v| v.as_str()).unwrap_or("");
-                    if subtype == "compact_boundary" {
-                        log::trace!("Detected compact_boundary system message");
 
-                        // Signal UI that compaction is in progress
-                        let compacting_event = CompactingEvent {
-                            session_id: session_id.to_string(),
-                            worktree_id: worktree_id.to_string(),
-                        };
-                        if let Err(e) = app.emit_all("chat:compacting", &compacting_event) {
-                            log::error!("Failed to emit compacting: {e}");
-                        }
-
-                        // Emit compacted event with metadata if available
-                        if let Some(metadata_val) = msg.get("compactMetadata") {
-                            if let Ok(metadata) =
-                                serde_json::from_value::<CompactMetadata>(metadata_val.clone())
-                            {
-                                let compacted_event = CompactedEvent {
-                                    session_id: session_id.to_string(),
-                                    worktree_id: worktree_id.to_string(),
-                                    metadata,
-                                };
-                                if let Err(e) = app.emit_all("chat:compacted", &compacted_event) {
-                                    log::error!("Failed to emit compacted: {e}");
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {}
+                // Return partial response (blocking tool is already in tool_calls)
+                return Ok(ClaudeResponse {
+                    content: run.full_content,
+                    session_id: run.session_id,
+                    tool_calls: run.tool_calls,
+                    content_blocks: run.content_blocks,
+                    cancelled: false,
+                    usage: None, // No usage for partial responses
+                    context_tokens: context.used_tokens(),
+                });
             }
         }
 
         // Check if completed
-        if completed {
+        if run.completed {
             break;
         }
 
@@ -1344,7 +1069,7 @@ pub fn tail_claude_output(
     }
 
     // Surface CLI errors when process failed with no meaningful output
-    if cancelled || (full_content.is_empty() && !received_claude_output) {
+    if cancelled || (run.full_content.is_empty() && !received_claude_output) {
         // Drain any remaining buffered content from the output file
         if let Ok(remaining) = tailer.poll() {
             for line in remaining {
@@ -1363,7 +1088,7 @@ pub fn tail_claude_output(
         }
     }
 
-    if !error_lines.is_empty() && full_content.is_empty() {
+    if !error_lines.is_empty() && run.full_content.is_empty() {
         let error_text = error_lines.join("\n");
         log::warn!("CLI error output for session {session_id}: {error_text}");
         let _ = app.emit_all(
@@ -1391,16 +1116,559 @@ pub fn tail_claude_output(
 
     log::trace!(
         "Tailing complete: {} chars, {} tool calls, cancelled: {cancelled}",
-        full_content.len(),
-        tool_calls.len()
+        run.full_content.len(),
+        run.tool_calls.len()
     );
 
     Ok(ClaudeResponse {
-        content: full_content,
-        session_id: claude_session_id,
-        tool_calls,
-        content_blocks,
+        content: run.full_content,
+        session_id: run.session_id,
+        tool_calls: run.tool_calls,
+        content_blocks: run.content_blocks,
         cancelled,
-        usage,
+        usage: run.usage,
+        context_tokens: context.used_tokens(),
     })
 }
+
+/// State of a Claude run, built up from its stream-json lines
+#[derive(Default)]
+pub(super) struct ClaudeRunState {
+    pub full_content: String,
+    /// Claude session ID (for resuming)
+    pub session_id: String,
+    pub tool_calls: Vec<ToolCall>,
+    pub content_blocks: Vec<ContentBlock>,
+    pub usage: Option<UsageData>,
+    /// A result line was received
+    pub completed: bool,
+    /// Project permission policy, for auditing its decisions (None on replay)
+    pub policy: Option<ActivePolicy>,
+}
+
+/// Process a single stream-json line: update the run state and emit its chat
+/// events. Shared between the detached tailer and run replay.
+///
+/// Returns the name of a blocking tool (AskUserQuestion/ExitPlanMode) the line
+/// called; the run stops there until the user answers.
+pub(super) fn process_claude_event(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    msg: &serde_json::Value,
+    run: &mut ClaudeRunState,
+) -> Option<String> {
+    // Capture session_id from any message that has it
+    if let Some(sid) = msg.get("session_id").and_then(|v| v.as_str()) {
+        if !sid.is_empty() {
+            run.session_id = sid.to_string();
+        }
+    }
+
+    // Track parent_tool_use_id for sub-agent tool calls
+    // Must reset to None for root-level messages, otherwise parallel Tasks get wrong parent
+    let current_parent_tool_use_id = msg
+        .get("parent_tool_use_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let msg_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+    match msg_type {
+        "assistant" => {
+            if let Some(message) = msg.get("message") {
+                if let Some(blocks) = message.get("content").and_then(|c| c.as_array()) {
+                    for block in blocks {
+                        let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                        match block_type {
+                            "text" => {
+                                if let Some(text) = block.get("text").and_then(|v| v.as_str()) {
+                                    // Skip CLI placeholder text emitted when extended
+                                    // thinking starts before any real text content
+                                    if text == "(no content)" {
+                                        continue;
+                                    }
+                                    run.full_content.push_str(text);
+                                    run.content_blocks.push(ContentBlock::Text {
+                                        text: text.to_string(),
+                                    });
+
+                                    // Emit chunk event
+                                    let event = ChunkEvent {
+                                        session_id: session_id.to_string(),
+                                        worktree_id: worktree_id.to_string(),
+                                        content: text.to_string(),
+                                    };
+                                    if let Err(e) = app.emit_all("chat:chunk", &event) {
+                                        log::error!("Failed to emit chunk: {e}");
+                                    }
+                                }
+                            }
+                            "tool_use" => {
+                                let id = block
+                                    .get("id")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or("")
+                                    .to_string();
+                                let name = block
+                                    .get("name")
+                                    .and_then(|v| v.as_str())
+                                    .unwrap_or("")
+                                    .to_string();
+                                let input = block
+                                    .get("input")
+                                    .cloned()
+                                    .unwrap_or(serde_json::Value::Null);
+
+                                // The CLI applies the policy itself; record its denials, and the
+                                // allows that let through a call the execution mode would have
+                                // asked about
+                                if let Some(policy) = &run.policy {
+                                    let request = policy.request(&name, &input);
+                                    let action = match policy.evaluate(&request) {
+                                        Some(PolicyAction::Deny) => Some(PolicyAction::Deny),
+                                        Some(PolicyAction::Allow)
+                                            if !policy.claude_mode_allows(&name) =>
+                                        {
+                                            Some(PolicyAction::Allow)
+                                        }
+                                        _ => None,
+                                    };
+                                    if let Some(action) = action {
+                                        policy.record(app, &request, action, Some(&id));
+                                    }
+                                }
+
+                                run.tool_calls.push(ToolCall {
+                                    id: id.clone(),
+                                    name: name.clone(),
+                                    input: input.clone(),
+                                    output: None,
+                                    parent_tool_use_id: current_parent_tool_use_id.clone(),
+                                });
+
+                                run.content_blocks.push(ContentBlock::ToolUse {
+                                    tool_call_id: id.clone(),
+                                });
+
+                                // Emit tool_use event
+                                let event = ToolUseEvent {
+                                    session_id: session_id.to_string(),
+                                    worktree_id: worktree_id.to_string(),
+                                    id: id.clone(),
+                                    name: name.clone(),
+                                    input: input.clone(),
+                                    parent_tool_use_id: current_parent_tool_use_id.clone(),
+                                };
+                                if let Err(e) = app.emit_all("chat:tool_use", &event) {
+                                    log::error!("Failed to emit tool_use: {e}");
+                                }
+
+                                // Emit tool_block event
+                                let block_event = ToolBlockEvent {
+                                    session_id: session_id.to_string(),
+                                    worktree_id: worktree_id.to_string(),
+                                    tool_call_id: id.clone(),
+                                };
+                                if let Err(e) = app.emit_all("chat:tool_block", &block_event) {
+                                    log::error!("Failed to emit tool_block: {e}");
+                                }
+
+                                // Blocking tools end the run until the user answers
+                                if name == "AskUserQuestion" || name == "ExitPlanMode" {
+                                    return Some(name);
+                                }
+                            }
+                            "thinking" => {
+                                if let Some(thinking) =
+                                    block.get("thinking").and_then(|v| v.as_str())
+                                {
+                                    run.content_blocks.push(ContentBlock::Thinking {
+                                        thinking: thinking.to_string(),
+                                    });
+
+                                    let event = ThinkingEvent {
+                                        session_id: session_id.to_string(),
+                                        worktree_id: worktree_id.to_string(),
+                                        content: thinking.to_string(),
+                                    };
+                                    if let Err(e) = app.emit_all("chat:thinking", &event) {
+                                        log::error!("Failed to emit thinking: {e}");
+                                    }
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        "user" => {
+            // User messages contain tool results
+            if let Some(message) = msg.get("message") {
+                if let Some(blocks) = message.get("content").and_then(|c| c.as_array()) {
+                    for block in blocks {
+                        let block_type = block.get("type").and_then(|v| v.as_str()).unwrap_or("");
+
+                        if block_type == "tool_result" {
+                            let tool_id = block
+                                .get("tool_use_id")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("");
+                            // Content can be a string OR an array of content blocks
+                            let output = block
+                                .get("content")
+                                .map(|v| {
+                                    if let Some(s) = v.as_str() {
+                                        s.to_string()
+                                    } else if let Some(arr) = v.as_array() {
+                                        arr.iter()
+                                            .filter_map(|item| {
+                                                if item.get("type").and_then(|t| t.as_str())
+                                                    == Some("text")
+                                                {
+                                                    item.get("text")
+                                                        .and_then(|t| t.as_str())
+                                                        .map(|s| s.to_string())
+                                                } else {
+                                                    None
+                                                }
+                                            })
+                                            .collect::<Vec<_>>()
+                                            .join("\n")
+                                    } else {
+                                        String::new()
+                                    }
+                                })
+                                .unwrap_or_default();
+
+                            // Update matching tool call's output
+                            if let Some(tc) = run.tool_calls.iter_mut().find(|t| t.id == tool_id) {
+                                tc.output = Some(output.clone());
+                            }
+
+                            // Emit tool_result event
+                            let event = ToolResultEvent {
+                                session_id: session_id.to_string(),
+                                worktree_id: worktree_id.to_string(),
+                                tool_use_id: tool_id.to_string(),
+                                output,
+                            };
+                            if let Err(e) = app.emit_all("chat:tool_result", &event) {
+                                log::error!("Failed to emit tool_result: {e}");
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        "result" => {
+            // Final result - Claude CLI completed
+            if run.full_content.is_empty() {
+                if let Some(result) = msg.get("result").and_then(|v| v.as_str()) {
+                    run.full_content = result.to_string();
+                }
+            }
+
+            // Extract token usage data
+            if let Some(usage_obj) = msg.get("usage") {
+                run.usage = Some(UsageData {
+                    input_tokens: usage_obj
+                        .get("input_tokens")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0),
+                    output_tokens: usage_obj
+                        .get("output_tokens")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0),
+                    cache_read_input_tokens: usage_obj
+                        .get("cache_read_input_tokens")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0),
+                    cache_creation_input_tokens: usage_obj
+                        .get("cache_creation_input_tokens")
+                        .and_then(|v| v.as_u64())
+                        .unwrap_or(0),
+                });
+                log::trace!(
+                    "Token usage: input={}, output={}, cache_read={}, cache_create={}",
+                    run.usage.as_ref().map(|u| u.input_tokens).unwrap_or(0),
+                    run.usage.as_ref().map(|u| u.output_tokens).unwrap_or(0),
+                    run.usage
+                        .as_ref()
+                        .map(|u| u.cache_read_input_tokens)
+                        .unwrap_or(0),
+                    run.usage
+                        .as_ref()
+                        .map(|u| u.cache_creation_input_tokens)
+                        .unwrap_or(0),
+                );
+            }
+
+            // Check for permission denials and emit event
+            if let Some(denials) = msg.get("permission_denials").and_then(|v| v.as_array()) {
+                if !denials.is_empty() {
+                    let denial_events: Vec<PermissionDenial> = denials
+                        .iter()
+                        .filter_map(|d| {
+                            let tool_name = d.get("tool_name")?.as_str()?;
+                            let tool_input = d.get("tool_input")?;
+
+                            // Skip plan file cleanup denials (benign Claude housekeeping)
+                            if tool_name == "Bash" {
+                                if let Some(cmd) =
+                                    tool_input.get("command").and_then(|c| c.as_str())
+                                {
+                                    if cmd.contains(".claude/plans/") && cmd.starts_with("rm ") {
+                                        log::trace!("Ignoring plan cleanup denial: {}", cmd);
+                                        return None;
+                                    }
+                                }
+                            }
+
+                            let tool_use_id = d.get("tool_use_id")?.as_str()?;
+
+                            // Denied by the project policy (recorded with the tool call):
+                            // nothing to approve
+                            if let Some(policy) = &run.policy {
+                                let request = policy.request(tool_name, tool_input);
+                                if policy.evaluate(&request) == Some(PolicyAction::Deny) {
+                                    return None;
+                                }
+                            }
+
+                            Some(PermissionDenial {
+                                tool_name: tool_name.to_string(),
+                                tool_use_id: tool_use_id.to_string(),
+                                tool_input: tool_input.clone(),
+                                rpc_id: None,
+                            })
+                        })
+                        .collect();
+
+                    if !denial_events.is_empty() {
+                        log::trace!(
+                            "Emitting permission_denied event with {} denials",
+                            denial_events.len()
+                        );
+                        let event = PermissionDeniedEvent {
+                            session_id: session_id.to_string(),
+                            worktree_id: worktree_id.to_string(),
+                            denials: denial_events,
+                        };
+                        if let Err(e) = app.emit_all("chat:permission_denied", &event) {
+                            log::error!("Failed to emit permission_denied: {e}");
+                        }
+                    }
+                }
+            }
+
+            run.completed = true;
+            log::trace!("Received result message - Claude CLI completed");
+        }
+        "system" => {
+            if let Some(metadata) = super::compaction::parse_compact_boundary(msg) {
+                log::trace!(
+                    "Detected compact_boundary system message ({} trigger, {} tokens)",
+                    metadata.trigger,
+                    metadata.pre_tokens
+                );
+
+                // Signal UI that compaction is in progress
+                let compacting_event = CompactingEvent {
+                    session_id: session_id.to_string(),
+                    worktree_id: worktree_id.to_string(),
+                };
+                if let Err(e) = app.emit_all("chat:compacting", &compacting_event) {
+                    log::error!("Failed to emit compacting: {e}");
+                }
+
+                run.content_blocks.push(ContentBlock::Compaction {
+                    trigger: metadata.trigger.clone(),
+                    pre_tokens: metadata.pre_tokens,
+                });
+
+                let compacted_event = CompactedEvent {
+                    session_id: session_id.to_string(),
+                    worktree_id: worktree_id.to_string(),
+                    metadata,
+                };
+                if let Err(e) = app.emit_all("chat:compacted", &compacted_event) {
+                    log::error!("Failed to emit compacted: {e}");
+                }
+            }
+        }
+        _ => {}
+    }
+
+    None
+}
+
+/// Replays a recorded Claude run through `process_claude_event`
+pub struct ClaudeReplayer {
+    app: tauri::AppHandle,
+    session_id: String,
+    worktree_id: String,
+    run: ClaudeRunState,
+}
+
+impl ClaudeReplayer {
+    pub fn new(app: &tauri::AppHandle, session_id: &str, worktree_id: &str) -> Self {
+        Self {
+            app: app.clone(),
+            session_id: session_id.to_string(),
+            worktree_id: worktree_id.to_string(),
+            run: ClaudeRunState::default(),
+        }
+    }
+}
+
+impl super::replay::EventReplayer for ClaudeReplayer {
+    fn replay_line(&mut self, msg: &serde_json::Value) -> bool {
+        process_claude_event(
+            &self.app,
+            &self.session_id,
+            &self.worktree_id,
+            msg,
+            &mut self.run,
+        )
+        .is_some()
+    }
+
+    fn finish(&mut self) {
+        let done_event = DoneEvent {
+            session_id: self.session_id.clone(),
+            worktree_id: self.worktree_id.clone(),
+            waiting_for_plan: false,
+        };
+        if let Err(e) = self.app.emit_all("chat:done", &done_event) {
+            log::error!("Failed to emit done event: {e}");
+        }
+    }
+}
+
+// =============================================================================
+// One-shot Claude execution (for magic prompts with --json-schema)
+// =============================================================================
+
+/// Execute a one-shot Claude CLI call with `--json-schema` for structured JSON output.
+///
+/// Runs without tools for a single turn and returns the raw JSON string of the
+/// `StructuredOutput` tool call.
+pub fn execute_one_shot_claude(
+    app: &tauri::AppHandle,
+    prompt: &str,
+    model: &str,
+    json_schema: &str,
+    working_dir: Option<&std::path::Path>,
+    custom_profile_name: Option<&str>,
+) -> Result<String, String> {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let cli_path = crate::claude_cli::resolve_cli_binary(app);
+    if !cli_path.exists() {
+        return Err("Claude CLI not installed".to_string());
+    }
+
+    log::trace!("Executing one-shot Claude CLI: model={model}, working_dir={working_dir:?}");
+
+    let mut cmd = crate::platform::silent_command(&cli_path);
+    apply_custom_profile_settings(&mut cmd, custom_profile_name);
+    cmd.args([
+        "--print",
+        "--verbose",
+        "--input-format",
+        "stream-json",
+        "--output-format",
+        "stream-json",
+        "--model",
+        model,
+        "--no-session-persistence",
+        "--tools",
+        "",
+        "--max-turns",
+        "1",
+        "--json-schema",
+        json_schema,
+    ]);
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    cmd.stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn Claude CLI: {e}"))?;
+
+    // Write prompt to stdin
+    {
+        let stdin = child.stdin.as_mut().ok_or("Failed to open stdin")?;
+        let input_message = serde_json::json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": prompt
+            }
+        });
+        writeln!(stdin, "{input_message}").map_err(|e| format!("Failed to write to stdin: {e}"))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait for Claude CLI: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        return Err(format!(
+            "Claude CLI failed: stderr={}, stdout={}",
+            stderr.trim(),
+            stdout.trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    log::trace!("Claude CLI one-shot stdout: {stdout}");
+
+    extract_structured_output(&stdout)
+}
+
+/// Extract structured output from Claude CLI stream-json response
+/// Handles the StructuredOutput tool call pattern used with --json-schema
+pub fn extract_structured_output(output: &str) -> Result<String, String> {
+    for line in output.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let parsed: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        if parsed.get("type").and_then(|t| t.as_str()) == Some("assistant") {
+            if let Some(message) = parsed.get("message") {
+                if let Some(content) = message.get("content").and_then(|c| c.as_array()) {
+                    for block in content {
+                        if block.get("type").and_then(|t| t.as_str()) == Some("tool_use")
+                            && block.get("name").and_then(|n| n.as_str())
+                                == Some("StructuredOutput")
+                        {
+                            if let Some(input) = block.get("input") {
+                                return Ok(input.to_string());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Err("No structured output found in Claude response".to_string())
+}
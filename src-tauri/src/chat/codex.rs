@@ -8,8 +8,12 @@
 //! - Different JSONL event format (item.started/completed vs assistant/user/result)
 //! - No thinking/effort levels, no --settings, no --add-dir, no MCP config
 
-use super::types::{ContentBlock, PermissionDenial, PermissionDeniedEvent, ToolCall, UsageData};
+use super::policy::{ActivePolicy, ToolRequest};
+use super::types::{
+    Backend, ContentBlock, PermissionDenial, PermissionDeniedEvent, ToolCall, UsageData,
+};
 use crate::http_server::EmitExt;
+use crate::projects::types::PolicyAction;
 
 use std::collections::HashMap;
 use std::io::Write;
@@ -58,10 +62,8 @@ fn cleanup_approval_channel(session_id: &str) {
     }
 }
 
-/// Send an approval decision to a waiting Codex process.
-/// Called from the `approve_codex_command` Tauri command.
-pub fn send_approval(session_id: &str, rpc_id: u64, decision: &str) -> Result<(), String> {
-    // Write the JSON-RPC response directly to stdin
+/// Write a JSON-RPC approval response to a Codex process's stdin
+fn write_approval(session_id: &str, rpc_id: u64, decision: &str) -> Result<(), String> {
     let response = format!("{{\"id\":{rpc_id},\"result\":\"{decision}\"}}\n");
     let mut handles = CODEX_STDIN_HANDLES
         .lock()
@@ -76,6 +78,13 @@ pub fn send_approval(session_id: &str, rpc_id: u64, decision: &str) -> Result<()
     stdin
         .flush()
         .map_err(|e| format!("Failed to flush Codex stdin: {e}"))?;
+    Ok(())
+}
+
+/// Send an approval decision to a waiting Codex process.
+/// Called from the `approve_codex_command` Tauri command.
+pub fn send_approval(session_id: &str, rpc_id: u64, decision: &str) -> Result<(), String> {
+    write_approval(session_id, rpc_id, decision)?;
 
     // Signal the tailer thread to resume
     let senders = CODEX_APPROVAL_SENDERS
@@ -633,9 +642,95 @@ fn execute_codex_attached(
 // Attached stdout tailing (build mode — with approval support)
 // =============================================================================
 
+/// Ask the user to approve a request: emit `chat:permission_denied` and block
+/// until `approve_codex_command` answers it.
+///
+/// Returns false if the session was cancelled while waiting.
+fn wait_for_approval(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    denial: PermissionDenial,
+    approval_rx: &std::sync::mpsc::Receiver<(u64, String)>,
+) -> bool {
+    let rpc_id = denial.rpc_id.unwrap_or(0);
+    let event = PermissionDeniedEvent {
+        session_id: session_id.to_string(),
+        worktree_id: worktree_id.to_string(),
+        denials: vec![denial],
+    };
+    if let Err(e) = app.emit_all("chat:permission_denied", &event) {
+        log::error!("Failed to emit permission_denied: {e}");
+    }
+
+    log::trace!("Blocking on approval response for rpc_id={rpc_id}...");
+    super::watchdog::set_waiting(session_id, true);
+    let approved = loop {
+        if !super::registry::is_process_running(session_id) {
+            log::trace!("Session cancelled while waiting for approval");
+            break false;
+        }
+        match approval_rx.recv_timeout(std::time::Duration::from_millis(200)) {
+            Ok((id, _decision)) => {
+                log::trace!("Received approval response: rpc_id={id}");
+                break true;
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                log::trace!("Approval channel disconnected");
+                break false;
+            }
+        }
+    };
+    super::watchdog::set_waiting(session_id, false);
+    approved
+}
+
+/// Paths a file change approval request touches: listed in the request, or
+/// else in the `file_change` item it belongs to
+fn file_change_paths(params: &serde_json::Value, tool_calls: &[ToolCall]) -> Vec<String> {
+    let item_id = params.get("itemId").and_then(|v| v.as_str()).unwrap_or("");
+    let changes = params.get("changes").or_else(|| {
+        tool_calls
+            .iter()
+            .find(|tc| !item_id.is_empty() && tc.id == item_id)
+            .map(|tc| &tc.input)
+    });
+    changes
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|c| c.get("path").and_then(|p| p.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Decide a file change from the project policy (evaluated as the `Edit`
+/// tool): the strictest action for any of its paths
+fn decide_file_change(
+    policy: &ActivePolicy,
+    paths: &[String],
+) -> Option<(PolicyAction, ToolRequest)> {
+    let requests: Vec<ToolRequest> = if paths.is_empty() {
+        vec![policy.request("Edit", &serde_json::Value::Null)]
+    } else {
+        paths
+            .iter()
+            .map(|path| policy.request("Edit", &serde_json::json!({ "file_path": path })))
+            .collect()
+    };
+    requests
+        .into_iter()
+        .filter_map(|request| policy.evaluate(&request).map(|action| (action, request)))
+        .max_by_key(|(action, _)| *action)
+}
+
 /// Tail Codex stdout from an attached process, handling JSON-RPC approval requests.
 ///
-/// Reads JSONL line-by-line from stdout. When an approval request arrives:
+/// Reads JSONL line-by-line from stdout. Approval requests are first decided by
+/// the project permission policy (see `policy`); otherwise:
 /// - `item/fileChange/requestApproval` → auto-accept (build mode = acceptEdits)
 /// - `item/commandExecution/requestApproval` → emit `chat:permission_denied`, block until response
 ///
@@ -649,10 +744,11 @@ fn tail_codex_attached(
     approval_rx: &std::sync::mpsc::Receiver<(u64, String)>,
 ) -> Result<CodexResponse, String> {
     use std::io::BufRead;
-    use std::time::Duration;
 
     log::trace!("Starting attached Codex tailing for session: {session_id}");
 
+    let policy = ActivePolicy::load(app, session_id, worktree_id, Backend::Codex);
+
     let mut output_writer = std::fs::OpenOptions::new()
         .create(true)
         .append(true)
@@ -711,10 +807,42 @@ fn tail_codex_attached(
 
             match method {
                 "item/fileChange/requestApproval" => {
-                    // Auto-accept file changes in build mode
-                    log::trace!("Auto-accepting file change (rpc_id={rpc_id})");
-                    send_approval(session_id, rpc_id, "accept").unwrap_or_else(|e| {
-                        log::error!("Failed to auto-accept file change: {e}");
+                    let item_id = params
+                        .get("itemId")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("")
+                        .to_string();
+                    let decision = policy.as_ref().and_then(|policy| {
+                        decide_file_change(policy, &file_change_paths(params, &tool_calls))
+                    });
+
+                    if let Some((PolicyAction::Ask, request)) = decision {
+                        log::trace!("Policy asks about file change (rpc_id={rpc_id})");
+                        let denial = PermissionDenial {
+                            tool_name: "Edit".to_string(),
+                            tool_use_id: item_id,
+                            tool_input: serde_json::json!({ "file_path": request.path }),
+                            rpc_id: Some(rpc_id),
+                        };
+                        if !wait_for_approval(app, session_id, worktree_id, denial, approval_rx) {
+                            cancelled = true;
+                            break;
+                        }
+                        continue;
+                    }
+
+                    // Otherwise auto-accept file changes in build mode, unless the policy denies
+                    // them (an allow changes nothing, so only denials are recorded)
+                    let answer = match (&policy, &decision) {
+                        (Some(policy), Some((PolicyAction::Deny, request))) => {
+                            policy.record(app, request, PolicyAction::Deny, Some(&item_id));
+                            "decline"
+                        }
+                        _ => "accept",
+                    };
+                    log::trace!("Answering file change with {answer} (rpc_id={rpc_id})");
+                    write_approval(session_id, rpc_id, answer).unwrap_or_else(|e| {
+                        log::error!("Failed to answer file change approval: {e}");
                     });
                 }
                 "item/commandExecution/requestApproval" => {
@@ -736,46 +864,35 @@ fn tail_codex_attached(
 
                     log::trace!("Command approval requested (rpc_id={rpc_id}): {command_parts}");
 
+                    let tool_input = serde_json::json!({ "command": command_parts });
+
+                    // Commands the project policy allows or denies are answered without asking
+                    if let Some(policy) = &policy {
+                        let request = policy.request("Bash", &tool_input);
+                        let answer = match policy.evaluate(&request) {
+                            Some(PolicyAction::Allow) => Some((PolicyAction::Allow, "accept")),
+                            Some(PolicyAction::Deny) => Some((PolicyAction::Deny, "decline")),
+                            _ => None,
+                        };
+                        if let Some((action, answer)) = answer {
+                            policy.record(app, &request, action, Some(&item_id));
+                            write_approval(session_id, rpc_id, answer).unwrap_or_else(|e| {
+                                log::error!("Failed to answer command approval: {e}");
+                            });
+                            continue;
+                        }
+                    }
+
                     let denial = PermissionDenial {
                         tool_name: "Bash".to_string(),
                         tool_use_id: item_id,
-                        tool_input: serde_json::json!({ "command": command_parts }),
+                        tool_input,
                         rpc_id: Some(rpc_id),
                     };
 
-                    let event = PermissionDeniedEvent {
-                        session_id: session_id.to_string(),
-                        worktree_id: worktree_id.to_string(),
-                        denials: vec![denial],
-                    };
-
-                    if let Err(e) = app.emit_all("chat:permission_denied", &event) {
-                        log::error!("Failed to emit permission_denied: {e}");
-                    }
-
                     // Block until frontend responds via approve_codex_command
-                    log::trace!("Blocking on approval response for rpc_id={rpc_id}...");
-                    loop {
-                        if !super::registry::is_process_running(session_id) {
-                            log::trace!("Session cancelled while waiting for approval");
-                            cancelled = true;
-                            break;
-                        }
-                        match approval_rx.recv_timeout(Duration::from_millis(200)) {
-                            Ok((id, _decision)) => {
-                                log::trace!("Received approval response: rpc_id={id}");
-                                break;
-                            }
-                            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
-                            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-                                log::trace!("Approval channel disconnected");
-                                cancelled = true;
-                                break;
-                            }
-                        }
-                    }
-
-                    if cancelled {
+                    if !wait_for_approval(app, session_id, worktree_id, denial, approval_rx) {
+                        cancelled = true;
                         break;
                     }
                 }
@@ -1381,7 +1498,8 @@ fn format_codex_user_error(error_msg: &str) -> String {
     }
 }
 
-/// Process a single Codex JSONL event. Shared between attached and detached tailers.
+/// Process a single Codex JSONL event. Shared between attached and detached tailers
+/// and run replay.
 #[allow(clippy::too_many_arguments)]
 fn process_codex_event(
     app: &tauri::AppHandle,
@@ -1896,6 +2014,83 @@ fn process_codex_event(
     }
 }
 
+/// Replays a recorded Codex run through `process_codex_event`
+pub struct CodexReplayer {
+    app: tauri::AppHandle,
+    session_id: String,
+    worktree_id: String,
+    is_plan_mode: bool,
+    full_content: String,
+    thread_id: String,
+    tool_calls: Vec<ToolCall>,
+    content_blocks: Vec<ContentBlock>,
+    pending_tool_ids: HashMap<String, String>,
+    completed: bool,
+    usage: Option<UsageData>,
+    error_emitted: bool,
+}
+
+impl CodexReplayer {
+    pub fn new(
+        app: &tauri::AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        is_plan_mode: bool,
+    ) -> Self {
+        Self {
+            app: app.clone(),
+            session_id: session_id.to_string(),
+            worktree_id: worktree_id.to_string(),
+            is_plan_mode,
+            full_content: String::new(),
+            thread_id: String::new(),
+            tool_calls: Vec::new(),
+            content_blocks: Vec::new(),
+            pending_tool_ids: HashMap::new(),
+            completed: false,
+            usage: None,
+            error_emitted: false,
+        }
+    }
+}
+
+impl super::replay::EventReplayer for CodexReplayer {
+    fn replay_line(&mut self, msg: &serde_json::Value) -> bool {
+        let event_type = msg.get("type").and_then(|v| v.as_str()).unwrap_or("");
+        process_codex_event(
+            &self.app,
+            &self.session_id,
+            &self.worktree_id,
+            msg,
+            event_type,
+            &mut self.full_content,
+            &mut self.thread_id,
+            &mut self.tool_calls,
+            &mut self.content_blocks,
+            &mut self.pending_tool_ids,
+            &mut self.completed,
+            &mut self.usage,
+            &mut self.error_emitted,
+        );
+        self.completed
+    }
+
+    fn finish(&mut self) {
+        // Same rule as the tailer: chat:done would clear a replayed error
+        if self.error_emitted {
+            return;
+        }
+        let _ = self.app.emit_all(
+            "chat:done",
+            &DoneEvent {
+                session_id: self.session_id.clone(),
+                worktree_id: self.worktree_id.clone(),
+                waiting_for_plan: self.is_plan_mode && !self.full_content.is_empty(),
+            },
+        );
+    }
+}
+
 // =============================================================================
 // File-based tailing for detached Codex CLI
 // =============================================================================
@@ -1918,7 +2113,7 @@ pub fn tail_codex_output(
 
     log::trace!("Starting to tail Codex NDJSON output for session: {session_id}");
 
-    let mut tailer = NdjsonTailer::new_from_start(output_file)?;
+    let mut tailer = NdjsonTailer::new_from_start_with_timing(output_file)?;
 
     let mut full_content = String::new();
     let mut thread_id = String::new();
@@ -6,17 +6,18 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Manager};
 use uuid::Uuid;
 
+use super::backend::parse_backend;
 use super::naming::{spawn_naming_task, NamingRequest};
 use super::registry::cancel_process;
 use super::run_log;
 use super::storage::{
-    delete_session_data, get_base_index_path, get_data_dir, get_index_path, get_session_dir,
-    load_metadata, load_sessions, save_metadata, with_sessions_mut,
+    delete_session_data, get_data_dir, get_session_dir, load_metadata, load_preserved_base_index,
+    load_sessions, save_metadata, with_sessions_mut,
 };
 use super::types::{
-    AllSessionsEntry, AllSessionsResponse, Backend, ChatMessage, ClaudeContext, EffortLevel,
-    LabelData, MessageRole, RunStatus, Session, SessionDigest, ThinkingLevel, WorktreeIndex,
-    WorktreeSessions,
+    AllSessionsEntry, AllSessionsResponse, Backend, ChatMessage, ClaudeContext, CompactMetadata,
+    ContentBlock, EffortLevel, LabelData, MessageRole, RunStatus, Session, SessionDigest,
+    ThinkingLevel, WorktreeSessions,
 };
 use crate::claude_cli::resolve_cli_binary;
 use crate::http_server::EmitExt;
@@ -38,11 +39,7 @@ pub(crate) fn resolve_default_backend(app: &AppHandle, worktree_id: Option<&str>
         .map(|p| p.default_backend)
         .unwrap_or_else(|| "claude".to_string());
 
-    let mut resolved = match prefs_backend.as_str() {
-        "codex" => Backend::Codex,
-        "opencode" => Backend::Opencode,
-        _ => Backend::Claude,
-    };
+    let mut resolved = parse_backend(&prefs_backend).unwrap_or_default();
 
     // Check project-level override if worktree_id is provided
     if let Some(wt_id) = worktree_id {
@@ -54,13 +51,8 @@ pub(crate) fn resolve_default_backend(app: &AppHandle, worktree_id: Option<&str>
                         .iter()
                         .any(|w| w.id == wt_id && w.project_id == p.id)
             }) {
-                if let Some(ref pb) = project.default_backend {
-                    resolved = match pb.as_str() {
-                        "codex" => Backend::Codex,
-                        "opencode" => Backend::Opencode,
-                        "claude" => Backend::Claude,
-                        _ => resolved,
-                    };
+                if let Some(pb) = project.default_backend.as_deref().and_then(parse_backend) {
+                    resolved = pb;
                 }
             }
         }
@@ -264,19 +256,13 @@ pub async fn create_session(
     log::trace!("Creating new session for worktree: {worktree_id}");
 
     // Resolve backend: explicit param → project default → global preference → Claude
-    let backend_enum = match backend.as_deref() {
-        Some("codex") => Backend::Codex,
-        Some("opencode") => Backend::Opencode,
-        Some("claude") => Backend::Claude,
-        _ => {
+    let backend_enum = match backend.as_deref().and_then(parse_backend) {
+        Some(explicit) => explicit,
+        None => {
             // No explicit backend — check project default, then global preference
             let mut resolved = Backend::Claude;
             if let Ok(prefs) = crate::load_preferences(app.clone()).await {
-                if prefs.default_backend == "codex" {
-                    resolved = Backend::Codex;
-                } else if prefs.default_backend == "opencode" {
-                    resolved = Backend::Opencode;
-                }
+                resolved = parse_backend(&prefs.default_backend).unwrap_or_default();
             }
             // Check project-level override
             if let Ok(data) = crate::projects::storage::load_projects_data(&app) {
@@ -289,13 +275,8 @@ pub async fn create_session(
                             .iter()
                             .any(|w| w.id == worktree_id && w.project_id == p.id)
                 }) {
-                    if let Some(ref pb) = project.default_backend {
-                        resolved = match pb.as_str() {
-                            "codex" => Backend::Codex,
-                            "opencode" => Backend::Opencode,
-                            "claude" => Backend::Claude,
-                            _ => resolved,
-                        };
+                    if let Some(pb) = project.default_backend.as_deref().and_then(parse_backend) {
+                        resolved = pb;
                     }
                 }
             }
@@ -494,7 +475,7 @@ pub async fn update_session_state(
 
 /// Extract pasted image paths from message content
 /// Matches: [Image attached: /path/to/image.png - Use the Read tool to view this image]
-fn extract_image_paths(content: &str) -> Vec<String> {
+pub(super) fn extract_image_paths(content: &str) -> Vec<String> {
     use regex::Regex;
     // Lazy static would be better, but for simplicity we'll compile here
     let re = Regex::new(r"\[Image attached: (.+?) - Use the Read tool to view this image\]")
@@ -506,7 +487,7 @@ fn extract_image_paths(content: &str) -> Vec<String> {
 
 /// Extract pasted text file paths from message content
 /// Matches: [Text file attached: /path/to/file.txt - Use the Read tool to view this file]
-fn extract_text_file_paths(content: &str) -> Vec<String> {
+pub(super) fn extract_text_file_paths(content: &str) -> Vec<String> {
     use regex::Regex;
     let re = Regex::new(r"\[Text file attached: (.+?) - Use the Read tool to view this file\]")
         .expect("Invalid regex");
@@ -515,19 +496,6 @@ fn extract_text_file_paths(content: &str) -> Vec<String> {
         .collect()
 }
 
-/// Delete a pasted file (image or text) by path - internal helper
-/// Does not validate path (validation done at command level)
-fn delete_pasted_file(path: &str) {
-    let file_path = std::path::PathBuf::from(path);
-    if file_path.exists() {
-        if let Err(e) = std::fs::remove_file(&file_path) {
-            log::warn!("Failed to delete pasted file {path}: {e}");
-        } else {
-            log::trace!("Deleted pasted file: {path}");
-        }
-    }
-}
-
 /// Close/delete a session tab
 /// Returns the new active session ID (if any)
 /// Also cleans up any pasted images and text files associated with the session
@@ -544,22 +512,11 @@ pub async fn close_session(
     let _ = cancel_process(&app, &session_id, &worktree_id);
 
     // Collect pasted file paths for cleanup (outside lock - read-only NDJSON access)
-    let mut files_to_delete: Vec<String> = Vec::new();
+    let mut pasted_files: Vec<String> = Vec::new();
     let messages = run_log::load_session_messages(&app, &session_id).unwrap_or_default();
     for message in &messages {
-        files_to_delete.extend(extract_image_paths(&message.content));
-        files_to_delete.extend(extract_text_file_paths(&message.content));
-    }
-
-    // Delete pasted files (outside lock - doesn't touch sessions file)
-    if !files_to_delete.is_empty() {
-        log::trace!(
-            "Cleaning up {} pasted files for session {session_id}",
-            files_to_delete.len()
-        );
-        for path in files_to_delete {
-            delete_pasted_file(&path);
-        }
+        pasted_files.extend(extract_image_paths(&message.content));
+        pasted_files.extend(extract_text_file_paths(&message.content));
     }
 
     // Delete session data (outside lock - separate directory)
@@ -567,6 +524,17 @@ pub async fn close_session(
         log::warn!("Failed to delete session data: {e}");
     }
 
+    // Release pasted files once the session no longer references them; files
+    // other sessions still use are kept
+    if !pasted_files.is_empty() {
+        log::trace!(
+            "Releasing {} pasted files of session {session_id}",
+            pasted_files.len()
+        );
+        super::pastes::release_pastes(&app, &pasted_files);
+    }
+    super::checkpoint::delete_session_checkpoints(&worktree_path, &session_id);
+
     // Clean up context references for this session
     if let Err(e) =
         crate::projects::github_issues::cleanup_issue_contexts_for_session(&app, &session_id)
@@ -899,6 +867,7 @@ pub async fn delete_archived_session(
     if let Err(e) = delete_session_data(&app, &session_id) {
         log::warn!("Failed to delete session data: {e}");
     }
+    super::checkpoint::delete_session_checkpoints(&worktree_path, &session_id);
 
     // Clean up context references
     if let Err(e) =
@@ -1005,47 +974,37 @@ pub async fn list_all_archived_sessions(
             }
         }
 
-        // Also check preserved base session files (base-{project_id}.json)
+        // Also check preserved base session indexes
         // These are created when a base session is closed with archiving
-        if let Ok(base_path) = get_base_index_path(&app, &project.id) {
-            if base_path.exists() {
-                if let Ok(contents) = std::fs::read_to_string(&base_path) {
-                    if let Ok(index) = serde_json::from_str::<WorktreeIndex>(&contents) {
-                        for entry in &index.sessions {
-                            if entry.archived_at.is_some() {
-                                // Load full session metadata
-                                let session =
-                                    if let Ok(Some(metadata)) = load_metadata(&app, &entry.id) {
-                                        let mut s = metadata.to_session();
-                                        // Ensure archived_at from index is preserved
-                                        if s.archived_at.is_none() {
-                                            s.archived_at = entry.archived_at;
-                                        }
-                                        s
-                                    } else {
-                                        // No metadata — build a minimal Session from index entry
-                                        let mut s = Session::new(
-                                            entry.name.clone(),
-                                            entry.order,
-                                            Backend::default(),
-                                        );
-                                        s.id = entry.id.clone();
-                                        s.message_count = Some(entry.message_count);
-                                        s.archived_at = entry.archived_at;
-                                        s
-                                    };
-
-                                entries.push(ArchivedSessionEntry {
-                                    session,
-                                    worktree_id: index.worktree_id.clone(),
-                                    worktree_name: format!("{} (base)", project.name),
-                                    worktree_path: project.path.clone(),
-                                    project_id: project.id.clone(),
-                                    project_name: project.name.clone(),
-                                });
-                            }
+        if let Ok(Some(index)) = load_preserved_base_index(&app, &project.id) {
+            for entry in &index.sessions {
+                if entry.archived_at.is_some() {
+                    // Load full session metadata
+                    let session = if let Ok(Some(metadata)) = load_metadata(&app, &entry.id) {
+                        let mut s = metadata.to_session();
+                        // Ensure archived_at from index is preserved
+                        if s.archived_at.is_none() {
+                            s.archived_at = entry.archived_at;
                         }
-                    }
+                        s
+                    } else {
+                        // No metadata — build a minimal Session from index entry
+                        let mut s =
+                            Session::new(entry.name.clone(), entry.order, Backend::default());
+                        s.id = entry.id.clone();
+                        s.message_count = Some(entry.message_count);
+                        s.archived_at = entry.archived_at;
+                        s
+                    };
+
+                    entries.push(ArchivedSessionEntry {
+                        session,
+                        worktree_id: index.worktree_id.clone(),
+                        worktree_name: format!("{} (base)", project.name),
+                        worktree_path: project.path.clone(),
+                        project_id: project.id.clone(),
+                        project_name: project.name.clone(),
+                    });
                 }
             }
         }
@@ -1340,38 +1299,59 @@ pub async fn send_chat_message(
         .find_session(&session_id)
         .map(|s| s.backend.clone())
         .unwrap_or_default();
-    let effective_backend = match backend.as_deref() {
-        Some("codex") => Backend::Codex,
-        Some("opencode") => Backend::Opencode,
-        Some("claude") => Backend::Claude,
-        _ => session_backend,
-    };
+    let effective_backend = backend
+        .as_deref()
+        .and_then(parse_backend)
+        .unwrap_or(session_backend);
     // Override backend based on model string (safety net: model always wins)
-    let effective_backend = if let Some(ref m) = model {
-        if crate::is_opencode_model(m) {
-            Backend::Opencode
-        } else if crate::is_codex_model(m) {
-            Backend::Codex
-        } else {
-            effective_backend
-        }
-    } else {
-        effective_backend
-    };
+    let effective_backend = model
+        .as_deref()
+        .and_then(super::backend::backend_for_model)
+        .map(|b| b.kind())
+        .unwrap_or(effective_backend);
+    let agent = super::backend::backend_for(&effective_backend);
 
     // Build context for Claude
     let context = ClaudeContext::new(worktree_path.clone());
 
-    // Get the session IDs for resumption
-    let claude_session_id = sessions
-        .find_session(&session_id)
-        .and_then(|s| s.claude_session_id.clone());
-    let codex_thread_id = sessions
+    // Get the backend conversation ID for resumption
+    let resume_id = sessions
         .find_session(&session_id)
-        .and_then(|s| s.codex_thread_id.clone());
-    let opencode_session_id = sessions
-        .find_session(&session_id)
-        .and_then(|s| s.opencode_session_id.clone());
+        .and_then(|s| agent.resume_id(s));
+
+    // Refuse to start on a permission policy the backend can't apply as written
+    super::policy::check_policy(
+        &app,
+        &worktree_id,
+        agent.capabilities().policy_enforcement,
+        agent.display_name(),
+        execution_mode.as_deref().unwrap_or("plan"),
+    )?;
+
+    // Refuse to start when a hard budget is already exhausted
+    let run_budgets = super::budget::check_can_start(&app, &session_id, &worktree_id)?;
+
+    // Forked session: the first run branches the backend conversation. Claude
+    // forks natively via --fork-session; without a resumable conversation the
+    // copied history is seeded into the prompt instead.
+    let fork_origin = super::fork::pending_fork(&app, &session_id);
+    let has_resume_id = resume_id.is_some();
+    let backend_message = match fork_origin {
+        Some(_) if !has_resume_id => super::fork::build_fork_seed(&app, &session_id, &message)
+            .unwrap_or_else(|| message.clone()),
+        // Switched backend: hand off the turns this conversation hasn't seen
+        _ => super::handoff::build_handoff(
+            &app,
+            &session_id,
+            &effective_backend,
+            has_resume_id,
+            &message,
+        )
+        .unwrap_or_else(|| message.clone()),
+    };
+    let claude_fork = fork_origin
+        .clone()
+        .filter(|_| has_resume_id && agent.capabilities().native_fork);
 
     // Start NDJSON run log for crash recovery
     let mut run_log_writer = run_log::start_run(
@@ -1392,6 +1372,10 @@ pub async fn send_chat_message(
             .as_ref()
             .and_then(|e| e.effort_value())
             .or(None),
+        &effective_backend,
+        custom_profile_name
+            .as_deref()
+            .filter(|_| agent.capabilities().custom_profiles),
     )?;
 
     // Get file paths for detached execution
@@ -1399,8 +1383,22 @@ pub async fn send_chat_message(
     let output_file = run_log_writer.output_file_path()?;
     let run_id = run_log_writer.run_id().to_string();
 
+    // Snapshot the worktree before a run that can edit files, so it can be rolled back
+    let mut run_checkpoint = None;
+    if matches!(execution_mode.as_deref(), Some("build") | Some("yolo")) {
+        match super::checkpoint::create_checkpoint(&worktree_path, &session_id, &run_id) {
+            Ok(checkpoint) => {
+                if let Err(e) = run_log_writer.set_checkpoint(checkpoint.clone()) {
+                    log::warn!("Failed to record checkpoint for run {run_id}: {e}");
+                }
+                run_checkpoint = Some(checkpoint);
+            }
+            Err(e) => log::warn!("Failed to create checkpoint for run {run_id}: {e}"),
+        }
+    }
+
     // Write input file with the user message
-    run_log::write_input_file(&app, &session_id, &run_id, &message)?;
+    run_log::write_input_file(&app, &session_id, &run_id, &backend_message)?;
 
     // Use passed parameter for parallel execution prompt (None = disabled)
     let parallel_execution_prompt = parallel_execution_prompt.filter(|p| !p.trim().is_empty());
@@ -1408,57 +1406,26 @@ pub async fn send_chat_message(
     // Use passed parameter for Chrome browser integration (default false - beta)
     let chrome = chrome_enabled.unwrap_or(false);
 
-    // Inject web tools in plan mode if preference is enabled
-    // Claude: add WebFetch/WebSearch to allowed tools
-    // Codex: set search_enabled flag for --search
-    let mut final_allowed_tools = allowed_tools.unwrap_or_default();
-    let mut codex_search_enabled = false;
-    let mut codex_multi_agent_enabled = false;
-    let mut codex_max_agent_threads: Option<u32> = None;
-    if execution_mode.as_deref() == Some("plan") {
+    // Allow web tools in plan mode if preference is enabled (the backend decides
+    // how: Claude adds WebFetch/WebSearch to allowed tools, Codex passes --search)
+    let mut web_search_enabled = false;
+    let mut multi_agent_enabled = false;
+    let mut max_agent_threads: Option<u32> = None;
+    if execution_mode.as_deref() == Some("plan") && agent.capabilities().web_search_in_plan {
         if let Ok(prefs) = crate::load_preferences(app.clone()).await {
-            if prefs.allow_web_tools_in_plan_mode {
-                match effective_backend {
-                    Backend::Claude => {
-                        final_allowed_tools.push("WebFetch".to_string());
-                        final_allowed_tools.push("WebSearch".to_string());
-                    }
-                    Backend::Codex => {
-                        codex_search_enabled = true;
-                    }
-                    Backend::Opencode => {}
-                }
-            }
+            web_search_enabled = prefs.allow_web_tools_in_plan_mode;
         }
     }
-    // Read Codex multi-agent preferences
-    if effective_backend == Backend::Codex {
+    // Read multi-agent preferences
+    if agent.capabilities().multi_agent {
         if let Ok(prefs) = crate::load_preferences(app.clone()).await {
-            codex_multi_agent_enabled = prefs.codex_multi_agent_enabled;
-            if codex_multi_agent_enabled {
-                codex_max_agent_threads = Some(prefs.codex_max_agent_threads.clamp(1, 8));
+            multi_agent_enabled = prefs.codex_multi_agent_enabled;
+            if multi_agent_enabled {
+                max_agent_threads = Some(prefs.codex_max_agent_threads.clamp(1, 8));
             }
         }
     }
-    let allowed_tools_for_cli = if final_allowed_tools.is_empty() {
-        None
-    } else {
-        Some(final_allowed_tools)
-    };
-
-    // Unified response type for both backends
-    struct UnifiedResponse {
-        content: String,
-        /// Claude session ID or Codex thread ID (for resumption)
-        resume_id: String,
-        tool_calls: Vec<super::types::ToolCall>,
-        content_blocks: Vec<super::types::ContentBlock>,
-        cancelled: bool,
-        /// Whether a chat:error event was emitted during execution
-        error_emitted: bool,
-        usage: Option<super::types::UsageData>,
-        backend: Backend,
-    }
+    let allowed_tools_for_cli = allowed_tools.filter(|tools| !tools.is_empty());
 
     // Execute CLI in detached mode on a dedicated OS thread.
     // This prevents tokio thread pool starvation when many sessions run concurrently.
@@ -1469,9 +1436,7 @@ pub async fn send_chat_message(
     let thread_input_file = input_file.clone();
     let thread_output_file = output_file.clone();
     let thread_working_dir = context.worktree_path.clone();
-    let thread_claude_session_id = claude_session_id.clone();
-    let thread_codex_thread_id = codex_thread_id.clone();
-    let thread_opencode_session_id = opencode_session_id.clone();
+    let thread_resume_id = resume_id.clone();
     let thread_model = model.clone();
     let thread_execution_mode = execution_mode.clone();
     let thread_thinking_level = thinking_level.clone();
@@ -1481,11 +1446,8 @@ pub async fn send_chat_message(
     let thread_ai_language = ai_language.clone();
     let thread_mcp_config = mcp_config.clone();
     let thread_custom_profile = custom_profile_name.clone();
-    let thread_message = message.clone();
-    let thread_backend = effective_backend.clone();
-    let thread_codex_search = codex_search_enabled;
-    let thread_codex_multi_agent = codex_multi_agent_enabled;
-    let thread_codex_max_threads = codex_max_agent_threads;
+    let thread_message = backend_message;
+    let thread_claude_fork = claude_fork;
 
     // Build a callback factory that persists the PID to metadata immediately after spawn
     // (before tailing starts). This is critical for crash recovery — without it,
@@ -1514,679 +1476,70 @@ pub async fn send_chat_message(
         })
     };
 
-    let (tx, rx) = tokio::sync::oneshot::channel();
-    std::thread::spawn(move || {
-        let result: Result<(u32, UnifiedResponse), String> = match thread_backend {
-            Backend::Claude => {
-                // === Claude execution path (unchanged) ===
-                let mut claude_session_id_for_call = thread_claude_session_id;
-                let result = loop {
-                    log::trace!("About to call execute_claude_detached...");
-
-                    match super::claude::execute_claude_detached(
-                        &thread_app,
-                        &thread_session_id,
-                        &thread_worktree_id,
-                        &thread_input_file,
-                        &thread_output_file,
-                        std::path::Path::new(&thread_working_dir),
-                        claude_session_id_for_call.as_deref(),
-                        thread_model.as_deref(),
-                        thread_execution_mode.as_deref(),
-                        thread_thinking_level.as_ref(),
-                        thread_effort_level.as_ref(),
-                        thread_allowed_tools.as_deref(),
-                        thread_parallel_prompt.as_deref(),
-                        thread_ai_language.as_deref(),
-                        thread_mcp_config.as_deref(),
-                        chrome,
-                        thread_custom_profile.as_deref(),
-                        Some(make_pid_callback()),
-                    ) {
-                        Ok((pid, response)) => {
-                            log::trace!("execute_claude_detached succeeded (PID: {pid})");
-
-                            if response.content.is_empty()
-                                && response.usage.is_none()
-                                && claude_session_id_for_call.is_some()
-                            {
-                                log::warn!(
-                                    "Empty response while resuming session {}, clearing stale session ID",
-                                    claude_session_id_for_call.as_deref().unwrap_or("")
-                                );
-                                let _ = with_sessions_mut(
-                                    &thread_app,
-                                    &thread_worktree_path,
-                                    &thread_worktree_id,
-                                    |sessions| {
-                                        if let Some(session) =
-                                            sessions.find_session_mut(&thread_session_id)
-                                        {
-                                            session.claude_session_id = None;
-                                        }
-                                        Ok(())
-                                    },
-                                );
-                            }
-
-                            break Ok((
-                                pid,
-                                UnifiedResponse {
-                                    content: response.content,
-                                    resume_id: response.session_id,
-                                    tool_calls: response.tool_calls,
-                                    content_blocks: response.content_blocks,
-                                    cancelled: response.cancelled,
-                                    error_emitted: false,
-                                    usage: response.usage,
-                                    backend: Backend::Claude,
-                                },
-                            ));
-                        }
-                        Err(e) => {
-                            let is_session_not_found = e.to_lowercase().contains("session")
-                                && (e.to_lowercase().contains("not found")
-                                    || e.to_lowercase().contains("invalid")
-                                    || e.to_lowercase().contains("expired"));
-
-                            if is_session_not_found && claude_session_id_for_call.is_some() {
-                                log::warn!(
-                                    "Session not found, clearing stored session ID and retrying: {}",
-                                    claude_session_id_for_call.as_deref().unwrap_or("")
-                                );
-                                match with_sessions_mut(
-                                    &thread_app,
-                                    &thread_worktree_path,
-                                    &thread_worktree_id,
-                                    |sessions| {
-                                        if let Some(session) =
-                                            sessions.find_session_mut(&thread_session_id)
-                                        {
-                                            session.claude_session_id = None;
-                                        }
-                                        Ok(())
-                                    },
-                                ) {
-                                    Ok(_) => {
-                                        claude_session_id_for_call = None;
-                                        continue;
-                                    }
-                                    Err(e) => {
-                                        break Err(format!(
-                                            "Session expired and failed to clear stale session state: {e}"
-                                        ));
-                                    }
-                                }
-                            }
-
-                            log::error!("execute_claude_detached FAILED: {e}");
-                            break Err(e);
-                        }
-                    }
-                };
-                result
-            }
-            Backend::Codex => {
-                // === Codex execution path ===
-                log::trace!("About to call execute_codex_detached...");
-
-                // Map EffortLevel to Codex reasoning effort values
-                let codex_reasoning_effort: Option<String> =
-                    thread_effort_level.as_ref().and_then(|e| match e {
-                        super::types::EffortLevel::Low => Some("low".to_string()),
-                        super::types::EffortLevel::Medium => Some("medium".to_string()),
-                        super::types::EffortLevel::High => Some("high".to_string()),
-                        super::types::EffortLevel::Max => Some("xhigh".to_string()),
-                        super::types::EffortLevel::Off => None,
-                    });
-
-                // Build add_dirs for Codex
-                let mut codex_add_dirs = Vec::new();
-                if let Ok(app_data_dir) = thread_app.path().app_data_dir() {
-                    if cfg!(debug_assertions) {
-                        codex_add_dirs.push(app_data_dir.to_string_lossy().to_string());
-                    } else {
-                        for subdir in [
-                            "pasted-images",
-                            "pasted-texts",
-                            "session-context",
-                            "git-context",
-                            "combined-contexts",
-                        ] {
-                            codex_add_dirs
-                                .push(app_data_dir.join(subdir).to_string_lossy().to_string());
-                        }
-                        codex_add_dirs.push(
-                            app_data_dir
-                                .join("runs")
-                                .join(&thread_session_id)
-                                .to_string_lossy()
-                                .to_string(),
-                        );
-                    }
-                }
-                if let Some(home) = dirs::home_dir() {
-                    let codex_skills_dir = home.join(".codex").join("skills");
-                    if codex_skills_dir.exists() {
-                        codex_add_dirs.push(codex_skills_dir.to_string_lossy().to_string());
-                    }
-                }
-
-                // Build combined instructions file (system prompt equivalent for Codex)
-                let codex_instructions_file = {
-                    use crate::projects::github_issues::{
-                        get_github_contexts_dir, get_session_issue_refs, get_session_pr_refs,
-                    };
-                    use crate::projects::storage::load_projects_data;
-
-                    const DEFAULT_GLOBAL_SYSTEM_PROMPT: &str = "\
-## Plan Mode\n\
-\n\
-- Make the plan extremely concise. Sacrifice grammar for the sake of concision.\n\
-- At the end of each plan, give me a list of unresolved questions to answer, if any.\n\
-\n\
-## Not Plan Mode\n\
-\n\
-- After each finished task, please write a few bullet points on how to test the changes.\n\
-- When multiple independent operations are needed, batch them into parallel tool calls. Launch independent Task subagents simultaneously rather than sequentially.\n\
-- When specifying subagent_type for Task tool calls, always use the fully qualified name exactly as listed in the system prompt (e.g., \"code-simplifier:code-simplifier\", not just \"code-simplifier\"). If the agent type contains a colon, include the full namespace:name string.";
-
-                    let mut system_prompt_parts: Vec<String> = Vec::new();
-
-                    // Codex plan mode: inject planning-only instructions
-                    if thread_execution_mode.as_deref() == Some("plan") {
-                        system_prompt_parts.push(
-                            "You are in PLANNING MODE (read-only sandbox). Create a detailed implementation plan. \
-                             Do NOT attempt to make any file changes — you are running in a read-only sandbox and writes will fail. \
-                             Describe exactly what changes you WOULD make: which files to create/modify, \
-                             what code to write, and in what order. End with any unresolved questions."
-                                .to_string(),
-                        );
-                    }
-
-                    // AI language preference
-                    if let Some(lang) = &thread_ai_language {
-                        let lang = lang.trim();
-                        if !lang.is_empty() {
-                            system_prompt_parts.push(format!("Respond to the user in {lang}."));
-                        }
-                    }
-
-                    // Global system prompt from preferences
-                    if let Ok(prefs_path) = crate::get_preferences_path(&thread_app) {
-                        if let Ok(contents) = std::fs::read_to_string(&prefs_path) {
-                            if let Ok(prefs) =
-                                serde_json::from_str::<crate::AppPreferences>(&contents)
-                            {
-                                let prompt = prefs
-                                    .magic_prompts
-                                    .global_system_prompt
-                                    .as_deref()
-                                    .map(|s| s.trim())
-                                    .filter(|s| !s.is_empty())
-                                    .unwrap_or(DEFAULT_GLOBAL_SYSTEM_PROMPT);
-                                system_prompt_parts.push(prompt.to_string());
-                            }
-                        }
-                    }
-
-                    // Parallel execution prompt
-                    if let Some(prompt) = &thread_parallel_prompt {
-                        let prompt = prompt.trim();
-                        if !prompt.is_empty() {
-                            system_prompt_parts.push(prompt.to_string());
-                        }
-                    }
-
-                    // Per-project custom system prompt
-                    if let Ok(data) = load_projects_data(&thread_app) {
-                        if let Some(worktree) = data.find_worktree(&thread_worktree_id) {
-                            if let Some(project) = data.find_project(&worktree.project_id) {
-                                if let Some(prompt) = &project.custom_system_prompt {
-                                    let prompt = prompt.trim();
-                                    if !prompt.is_empty() {
-                                        system_prompt_parts.push(prompt.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // Embedded binary path hints
-                    let gh_binary = crate::gh_cli::config::resolve_gh_binary(&thread_app);
-                    if gh_binary != std::path::PathBuf::from("gh") {
-                        system_prompt_parts.push(format!(
-                            "When running GitHub CLI commands, use the full path to the embedded binary: {}\n\
-                             Do NOT use bare `gh` — always use the full path above.",
-                            gh_binary.display()
-                        ));
-                    }
-                    if let Ok(claude_binary) = crate::claude_cli::get_cli_binary_path(&thread_app) {
-                        if claude_binary.exists() {
-                            system_prompt_parts.push(format!(
-                                "When running Claude CLI commands, use the full path to the embedded binary: {}\n\
-                                 Do NOT use bare `claude` — always use the full path above.",
-                                claude_binary.display()
-                            ));
-                        }
-                    }
-                    if let Ok(codex_binary) = crate::codex_cli::get_cli_binary_path(&thread_app) {
-                        if codex_binary.exists() {
-                            system_prompt_parts.push(format!(
-                                "When running Codex CLI commands, use the full path to the embedded binary: {}\n\
-                                 Do NOT use bare `codex` — always use the full path above.",
-                                codex_binary.display()
-                            ));
-                        }
-                    }
-
-                    // Collect context file paths (issues, PRs, saved contexts)
-                    let mut all_context_paths: Vec<std::path::PathBuf> = Vec::new();
-
-                    let mut issue_keys =
-                        get_session_issue_refs(&thread_app, &thread_session_id).unwrap_or_default();
-                    if let Ok(wt_keys) = get_session_issue_refs(&thread_app, &thread_worktree_id) {
-                        for key in wt_keys {
-                            if !issue_keys.contains(&key) {
-                                issue_keys.push(key);
-                            }
-                        }
-                    }
-                    if !issue_keys.is_empty() {
-                        if let Ok(contexts_dir) = get_github_contexts_dir(&thread_app) {
-                            for key in &issue_keys {
-                                let parts: Vec<&str> = key.rsplitn(2, '-').collect();
-                                if parts.len() == 2 {
-                                    let number = parts[0];
-                                    let repo_key = parts[1];
-                                    let file_path =
-                                        contexts_dir.join(format!("{repo_key}-issue-{number}.md"));
-                                    if file_path.exists() {
-                                        all_context_paths.push(file_path);
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    let mut pr_keys =
-                        get_session_pr_refs(&thread_app, &thread_session_id).unwrap_or_default();
-                    if let Ok(wt_keys) = get_session_pr_refs(&thread_app, &thread_worktree_id) {
-                        for key in wt_keys {
-                            if !pr_keys.contains(&key) {
-                                pr_keys.push(key);
-                            }
-                        }
-                    }
-                    if !pr_keys.is_empty() {
-                        if let Ok(contexts_dir) = get_github_contexts_dir(&thread_app) {
-                            for key in &pr_keys {
-                                let parts: Vec<&str> = key.rsplitn(2, '-').collect();
-                                if parts.len() == 2 {
-                                    let number = parts[0];
-                                    let repo_key = parts[1];
-                                    let file_path =
-                                        contexts_dir.join(format!("{repo_key}-pr-{number}.md"));
-                                    if file_path.exists() {
-                                        all_context_paths.push(file_path);
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // Saved context files
-                    if let Ok(app_data_dir) = thread_app.path().app_data_dir() {
-                        let saved_contexts_dir = app_data_dir.join("session-context");
-                        if saved_contexts_dir.exists() {
-                            let prefix = format!("{}-context-", thread_session_id);
-                            if let Ok(entries) = std::fs::read_dir(&saved_contexts_dir) {
-                                let mut context_files: Vec<_> = entries
-                                    .flatten()
-                                    .filter(|entry| {
-                                        let name = entry.file_name().to_string_lossy().to_string();
-                                        name.starts_with(&prefix) && name.ends_with(".md")
-                                    })
-                                    .collect();
-                                context_files.sort_by_key(|e| e.file_name());
-                                for entry in context_files {
-                                    all_context_paths.push(entry.path());
-                                }
-                            }
-                        }
-                    }
-
-                    // Write combined instructions file if we have anything
-                    if !system_prompt_parts.is_empty() || !all_context_paths.is_empty() {
-                        if let Ok(app_data_dir) = thread_app.path().app_data_dir() {
-                            let combined_dir = app_data_dir.join("combined-contexts");
-                            let _ = std::fs::create_dir_all(&combined_dir);
-                            let combined_file = combined_dir
-                                .join(format!("{}-codex-combined.md", thread_session_id));
-
-                            let mut content = String::new();
-
-                            if !system_prompt_parts.is_empty() {
-                                content.push_str("# Instructions\n\n");
-                                for part in &system_prompt_parts {
-                                    content.push_str(part);
-                                    content.push('\n');
-                                }
-                                content.push_str("\n---\n\n");
-                            }
-
-                            if !all_context_paths.is_empty() {
-                                content.push_str("# Loaded Context\n\n");
-                                content.push_str(
-                                    "The following context has been loaded. \
-                                     You should be aware of this when working on this task.\n\n---\n\n",
-                                );
-                                for path in &all_context_paths {
-                                    if let Ok(file_content) = std::fs::read_to_string(path) {
-                                        content.push_str(&file_content);
-                                        content.push_str("\n\n---\n\n");
-                                    }
-                                }
-                            }
-
-                            match std::fs::write(&combined_file, &content) {
-                                Ok(_) => {
-                                    log::debug!(
-                                        "Created Codex instructions file: {:?}",
-                                        combined_file
-                                    );
-                                    Some(combined_file)
-                                }
-                                Err(e) => {
-                                    log::error!("Failed to write Codex instructions file: {e}");
-                                    None
-                                }
-                            }
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    }
-                };
-
-                // For Codex: first message uses positional arg, resume pipes via stdin
-                let prompt = Some(thread_message.as_str());
-
-                match super::codex::execute_codex_detached(
-                    &thread_app,
-                    &thread_session_id,
-                    &thread_worktree_id,
-                    &thread_output_file,
-                    std::path::Path::new(&thread_working_dir),
-                    thread_codex_thread_id.as_deref(),
-                    thread_model.as_deref(),
-                    thread_execution_mode.as_deref(),
-                    codex_reasoning_effort.as_deref(),
-                    thread_codex_search,
-                    &codex_add_dirs,
-                    prompt,
-                    codex_instructions_file.as_deref(),
-                    thread_codex_multi_agent,
-                    thread_codex_max_threads,
-                    Some(make_pid_callback()),
-                ) {
-                    Ok((pid, response)) => Ok((
-                        pid,
-                        UnifiedResponse {
-                            content: response.content,
-                            resume_id: response.thread_id,
-                            tool_calls: response.tool_calls,
-                            content_blocks: response.content_blocks,
-                            cancelled: response.cancelled,
-                            error_emitted: response.error_emitted,
-                            usage: response.usage,
-                            backend: Backend::Codex,
-                        },
-                    )),
-                    Err(e) => {
-                        log::error!("execute_codex_detached FAILED: {e}");
-                        Err(e)
-                    }
-                }
-            }
-            Backend::Opencode => {
-                log::trace!("About to call execute_opencode...");
-                let opencode_reasoning_effort: Option<String> =
-                    thread_effort_level.as_ref().and_then(|e| match e {
-                        super::types::EffortLevel::Low => Some("low".to_string()),
-                        super::types::EffortLevel::Medium => Some("medium".to_string()),
-                        super::types::EffortLevel::High => Some("high".to_string()),
-                        super::types::EffortLevel::Max => Some("xhigh".to_string()),
-                        super::types::EffortLevel::Off => None,
-                    });
-
-                let system_prompt = {
-                    use crate::projects::github_issues::{
-                        get_github_contexts_dir, get_session_issue_refs, get_session_pr_refs,
-                    };
-                    use crate::projects::storage::load_projects_data;
-
-                    let mut system_prompt_parts: Vec<String> = Vec::new();
-
-                    // AI language preference
-                    if let Some(lang) = &thread_ai_language {
-                        let lang = lang.trim();
-                        if !lang.is_empty() {
-                            system_prompt_parts.push(format!("Respond to the user in {lang}."));
-                        }
-                    }
-
-                    // Global system prompt from preferences
-                    if let Ok(prefs_path) = crate::get_preferences_path(&thread_app) {
-                        if let Ok(contents) = std::fs::read_to_string(&prefs_path) {
-                            if let Ok(prefs) =
-                                serde_json::from_str::<crate::AppPreferences>(&contents)
-                            {
-                                if let Some(prompt) = prefs
-                                    .magic_prompts
-                                    .global_system_prompt
-                                    .as_deref()
-                                    .map(|s| s.trim())
-                                    .filter(|s| !s.is_empty())
-                                {
-                                    system_prompt_parts.push(prompt.to_string());
-                                }
-                            }
-                        }
-                    }
-
-                    // Parallel execution prompt
-                    if let Some(prompt) = &thread_parallel_prompt {
-                        let prompt = prompt.trim();
-                        if !prompt.is_empty() {
-                            system_prompt_parts.push(prompt.to_string());
-                        }
-                    }
-
-                    // Per-project custom system prompt
-                    if let Ok(data) = load_projects_data(&thread_app) {
-                        if let Some(worktree) = data.find_worktree(&thread_worktree_id) {
-                            if let Some(project) = data.find_project(&worktree.project_id) {
-                                if let Some(prompt) = &project.custom_system_prompt {
-                                    let prompt = prompt.trim();
-                                    if !prompt.is_empty() {
-                                        system_prompt_parts.push(prompt.to_string());
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // Embedded binary path hints
-                    let gh_binary = crate::gh_cli::config::resolve_gh_binary(&thread_app);
-                    if gh_binary != std::path::PathBuf::from("gh") {
-                        system_prompt_parts.push(format!(
-                            "When running GitHub CLI commands, use the full path to the embedded binary: {}\n\
-                             Do NOT use bare `gh` — always use the full path above.",
-                            gh_binary.display()
-                        ));
-                    }
-                    if let Ok(claude_binary) = crate::claude_cli::get_cli_binary_path(&thread_app) {
-                        if claude_binary.exists() {
-                            system_prompt_parts.push(format!(
-                                "When running Claude CLI commands, use the full path to the embedded binary: {}\n\
-                                 Do NOT use bare `claude` — always use the full path above.",
-                                claude_binary.display()
-                            ));
-                        }
-                    }
-                    if let Ok(codex_binary) = crate::codex_cli::get_cli_binary_path(&thread_app) {
-                        if codex_binary.exists() {
-                            system_prompt_parts.push(format!(
-                                "When running Codex CLI commands, use the full path to the embedded binary: {}\n\
-                                 Do NOT use bare `codex` — always use the full path above.",
-                                codex_binary.display()
-                            ));
-                        }
-                    }
-
-                    // Collect and inline context files (issues, PRs, saved contexts)
-                    let mut context_content = String::new();
-
-                    let mut issue_keys =
-                        get_session_issue_refs(&thread_app, &thread_session_id).unwrap_or_default();
-                    if let Ok(wt_keys) = get_session_issue_refs(&thread_app, &thread_worktree_id) {
-                        for key in wt_keys {
-                            if !issue_keys.contains(&key) {
-                                issue_keys.push(key);
-                            }
-                        }
-                    }
-                    if !issue_keys.is_empty() {
-                        if let Ok(contexts_dir) = get_github_contexts_dir(&thread_app) {
-                            for key in &issue_keys {
-                                let parts: Vec<&str> = key.rsplitn(2, '-').collect();
-                                if parts.len() == 2 {
-                                    let number = parts[0];
-                                    let repo_key = parts[1];
-                                    let file_path =
-                                        contexts_dir.join(format!("{repo_key}-issue-{number}.md"));
-                                    if let Ok(content) = std::fs::read_to_string(&file_path) {
-                                        context_content.push_str(&content);
-                                        context_content.push_str("\n\n---\n\n");
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    let mut pr_keys =
-                        get_session_pr_refs(&thread_app, &thread_session_id).unwrap_or_default();
-                    if let Ok(wt_keys) = get_session_pr_refs(&thread_app, &thread_worktree_id) {
-                        for key in wt_keys {
-                            if !pr_keys.contains(&key) {
-                                pr_keys.push(key);
-                            }
-                        }
-                    }
-                    if !pr_keys.is_empty() {
-                        if let Ok(contexts_dir) = get_github_contexts_dir(&thread_app) {
-                            for key in &pr_keys {
-                                let parts: Vec<&str> = key.rsplitn(2, '-').collect();
-                                if parts.len() == 2 {
-                                    let number = parts[0];
-                                    let repo_key = parts[1];
-                                    let file_path =
-                                        contexts_dir.join(format!("{repo_key}-pr-{number}.md"));
-                                    if let Ok(content) = std::fs::read_to_string(&file_path) {
-                                        context_content.push_str(&content);
-                                        context_content.push_str("\n\n---\n\n");
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // Saved context files
-                    if let Ok(app_data_dir) = thread_app.path().app_data_dir() {
-                        let saved_contexts_dir = app_data_dir.join("session-context");
-                        if saved_contexts_dir.exists() {
-                            let prefix = format!("{}-context-", thread_session_id);
-                            if let Ok(entries) = std::fs::read_dir(&saved_contexts_dir) {
-                                let mut context_files: Vec<_> = entries
-                                    .flatten()
-                                    .filter(|entry| {
-                                        let name = entry.file_name().to_string_lossy().to_string();
-                                        name.starts_with(&prefix) && name.ends_with(".md")
-                                    })
-                                    .collect();
-                                context_files.sort_by_key(|e| e.file_name());
-                                for entry in context_files {
-                                    if let Ok(content) = std::fs::read_to_string(entry.path()) {
-                                        context_content.push_str(&content);
-                                        context_content.push_str("\n\n---\n\n");
-                                    }
-                                }
-                            }
-                        }
-                    }
-
-                    // Build final system prompt
-                    let mut final_prompt = String::new();
-                    if !system_prompt_parts.is_empty() {
-                        final_prompt.push_str(&system_prompt_parts.join("\n\n"));
-                    }
-                    if !context_content.is_empty() {
-                        if !final_prompt.is_empty() {
-                            final_prompt.push_str("\n\n---\n\n");
-                        }
-                        final_prompt.push_str("# Loaded Context\n\n");
-                        final_prompt.push_str(
-                            "The following context has been loaded. \
-                             You should be aware of this when working on this task.\n\n---\n\n",
-                        );
-                        final_prompt.push_str(&context_content);
-                    }
+    // Watch streaming usage against session/project/daily budgets. Registered
+    // here, after the last early return, so the untrack below always runs.
+    if let Some(run_budgets) = run_budgets {
+        super::budget::track_run(
+            &session_id,
+            &worktree_id,
+            &run_id,
+            &effective_backend,
+            model.as_deref(),
+            custom_profile_name
+                .as_deref()
+                .filter(|_| agent.capabilities().custom_profiles),
+            run_budgets,
+        );
+    }
 
-                    if final_prompt.is_empty() {
-                        None
-                    } else {
-                        Some(final_prompt)
-                    }
-                };
+    // Kill detached processes that stall (idle or wall-clock timeout)
+    if agent.capabilities().watchdog {
+        super::watchdog::watch_run(
+            &app,
+            &session_id,
+            &worktree_id,
+            &run_id,
+            output_file.clone(),
+            std::time::Duration::ZERO,
+        );
+    }
 
-                match super::opencode::execute_opencode_http(
-                    &thread_app,
-                    &thread_session_id,
-                    &thread_worktree_id,
-                    std::path::Path::new(&thread_working_dir),
-                    thread_opencode_session_id.as_deref(),
-                    thread_model.as_deref(),
-                    thread_execution_mode.as_deref(),
-                    opencode_reasoning_effort.as_deref(),
-                    &thread_message,
-                    system_prompt.as_deref(),
-                ) {
-                    Ok(response) => Ok((
-                        std::process::id(),
-                        UnifiedResponse {
-                            content: response.content,
-                            resume_id: response.session_id,
-                            tool_calls: response.tool_calls,
-                            content_blocks: response.content_blocks,
-                            cancelled: response.cancelled,
-                            error_emitted: false,
-                            usage: response.usage,
-                            backend: Backend::Opencode,
-                        },
-                    )),
-                    Err(e) => {
-                        log::error!("execute_opencode FAILED: {e}");
-                        Err(e)
-                    }
-                }
-            }
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::spawn(move || {
+        let request = super::backend::ExecuteRequest {
+            app: &thread_app,
+            session_id: &thread_session_id,
+            worktree_id: &thread_worktree_id,
+            worktree_path: &thread_worktree_path,
+            input_file: &thread_input_file,
+            output_file: &thread_output_file,
+            working_dir: std::path::Path::new(&thread_working_dir),
+            resume_id: thread_resume_id.as_deref(),
+            message: &thread_message,
+            model: thread_model.as_deref(),
+            execution_mode: thread_execution_mode.as_deref(),
+            thinking_level: thread_thinking_level.as_ref(),
+            effort_level: thread_effort_level.as_ref(),
+            allowed_tools: thread_allowed_tools.as_deref(),
+            parallel_execution_prompt: thread_parallel_prompt.as_deref(),
+            ai_language: thread_ai_language.as_deref(),
+            mcp_config: thread_mcp_config.as_deref(),
+            chrome_enabled: chrome,
+            custom_profile_name: thread_custom_profile.as_deref(),
+            fork: thread_claude_fork.as_ref(),
+            web_search: web_search_enabled,
+            multi_agent: multi_agent_enabled,
+            max_agent_threads,
+            pid_callback: &make_pid_callback,
         };
+        let result = agent.execute(&request);
         let _ = tx.send(result);
     });
 
-    let (_pid, unified_response) = match rx.await {
+    let thread_result = rx.await;
+    super::budget::untrack_run(&session_id);
+    super::watchdog::unwatch_run(&session_id);
+    let (_pid, unified_response) = match thread_result {
         Ok(Ok(result)) => result,
         Ok(Err(e)) => {
             // Thread completed with an error before register_process was called —
@@ -2205,9 +1558,9 @@ pub async fn send_chat_message(
     // PID is now persisted via pid_callback immediately after spawn (before tailing).
     // No need to set_pid here — it was already saved for crash recovery.
 
-    // OpenCode runs are HTTP-based (no detached JSONL stream).
+    // Non-detached runs (OpenCode over HTTP) have no JSONL stream.
     // Write a synthetic assistant line so history reload can reconstruct content.
-    if unified_response.backend == Backend::Opencode {
+    if !agent.capabilities().detached {
         if let Ok(mut file) = std::fs::OpenOptions::new().append(true).open(&output_file) {
             let synthetic = serde_json::json!({
                 "type": "assistant",
@@ -2228,12 +1581,47 @@ pub async fn send_chat_message(
         log::warn!("Failed to delete input file: {e}");
     }
 
+    // Attribute the files changed since the run's checkpoint to this run
+    super::checkpoint::record_run_changes(
+        &mut run_log_writer,
+        &worktree_path,
+        run_checkpoint.as_ref(),
+    );
+
+    // Record compactions and how full the context window ended up
+    let compactions: Vec<CompactMetadata> = unified_response
+        .content_blocks
+        .iter()
+        .filter_map(|block| match block {
+            ContentBlock::Compaction {
+                trigger,
+                pre_tokens,
+            } => Some(CompactMetadata {
+                trigger: trigger.clone(),
+                pre_tokens: *pre_tokens,
+            }),
+            _ => None,
+        })
+        .collect();
+    let compacted = !compactions.is_empty();
+    if compacted {
+        if let Err(e) = run_log_writer.set_compactions(compactions) {
+            log::warn!("Failed to record compactions: {e}");
+        }
+    }
+    if let Some(used_tokens) = unified_response.context_tokens {
+        let usage = super::compaction::context_window_usage(used_tokens, model.as_deref());
+        if let Err(e) = run_log_writer.set_context_window(usage) {
+            log::warn!("Failed to record context window usage: {e}");
+        }
+    }
+
     // Handle cancellation: only save if there's meaningful content (>10 chars) or tool calls
     // This avoids cluttering history with empty cancelled messages from instant cancellations
-    let has_meaningful_content = unified_response.content.len() >= 10;
+    let has_meaningful_content = unified_response.content.len() >= 10 || compacted;
     let has_tool_calls = !unified_response.tool_calls.is_empty();
     let resume_id_for_log = unified_response.resume_id.clone();
-    let response_backend = unified_response.backend.clone();
+    let run_log_resume_id = agent.run_log_resume_id(&resume_id_for_log);
 
     // Handle error_emitted: backend emitted chat:error during execution (e.g., Codex usage limit).
     // Treat like undo_send so the user message doesn't persist in history.
@@ -2263,13 +1651,8 @@ pub async fn send_chat_message(
 
     if unified_response.cancelled && !has_meaningful_content && !has_tool_calls {
         // Instant cancellation with no content
-        let resume_sid = if response_backend != Backend::Claude || resume_id_for_log.is_empty() {
-            None
-        } else {
-            Some(resume_id_for_log.as_str())
-        };
         // Cancel the run log, persisting session ID if available so next run can --resume
-        if let Err(e) = run_log_writer.cancel(None, resume_sid) {
+        if let Err(e) = run_log_writer.cancel(None, run_log_resume_id) {
             log::warn!("Failed to cancel run log: {e}");
         }
 
@@ -2278,17 +1661,7 @@ pub async fn send_chat_message(
             if let Some(session) = sessions.find_session_mut(&session_id) {
                 // Persist resume ID so next run can resume context even after cancellation
                 if !resume_id_for_log.is_empty() {
-                    match response_backend {
-                        Backend::Claude => {
-                            session.claude_session_id = Some(resume_id_for_log.clone());
-                        }
-                        Backend::Codex => {
-                            session.codex_thread_id = Some(resume_id_for_log.clone());
-                        }
-                        Backend::Opencode => {
-                            session.opencode_session_id = Some(resume_id_for_log.clone());
-                        }
-                    }
+                    agent.set_resume_id(session, Some(resume_id_for_log.clone()));
                 }
                 // Remove user message (undo send) - allows frontend to restore to input field
                 if session
@@ -2325,7 +1698,8 @@ pub async fn send_chat_message(
     }
 
     // Create assistant message with tool calls and content blocks
-    let has_content = !unified_response.content.is_empty();
+    // A compaction-only run (`/compact`) has no text but continues the conversation
+    let has_content = !unified_response.content.is_empty() || compacted;
     let assistant_msg_id = Uuid::new_v4().to_string();
     let assistant_msg = ChatMessage {
         id: assistant_msg_id.clone(),
@@ -2349,23 +1723,12 @@ pub async fn send_chat_message(
 
     // Finalize run log (complete or cancel based on response status)
     if unified_response.cancelled {
-        let cancel_resume_sid =
-            if response_backend != Backend::Claude || resume_id_for_log.is_empty() {
-                None
-            } else {
-                Some(resume_id_for_log.as_str())
-            };
-        if let Err(e) = run_log_writer.cancel(Some(&assistant_msg_id), cancel_resume_sid) {
+        if let Err(e) = run_log_writer.cancel(Some(&assistant_msg_id), run_log_resume_id) {
             log::warn!("Failed to cancel run log: {e}");
         }
     } else {
-        let resume_sid = if response_backend != Backend::Claude || resume_id_for_log.is_empty() {
-            None
-        } else {
-            Some(resume_id_for_log.as_str())
-        };
         if let Err(e) =
-            run_log_writer.complete(&assistant_msg_id, resume_sid, unified_response.usage)
+            run_log_writer.complete(&assistant_msg_id, run_log_resume_id, unified_response.usage)
         {
             log::warn!("Failed to complete run log: {e}");
         }
@@ -2377,22 +1740,16 @@ pub async fn send_chat_message(
     with_sessions_mut(&app, &worktree_path, &worktree_id, |sessions| {
         if let Some(session) = sessions.find_session_mut(&session_id) {
             if !resume_id_for_log.is_empty() && has_content {
-                match response_backend {
-                    Backend::Claude => {
-                        session.claude_session_id = Some(resume_id_for_log.clone());
-                    }
-                    Backend::Codex => {
-                        session.codex_thread_id = Some(resume_id_for_log.clone());
-                    }
-                    Backend::Opencode => {
-                        session.opencode_session_id = Some(resume_id_for_log.clone());
-                    }
-                }
+                agent.set_resume_id(session, Some(resume_id_for_log.clone()));
             }
         }
         Ok(())
     })?;
 
+    if fork_origin.is_some() && has_content && !unified_response.cancelled {
+        super::fork::complete_fork(&app, &session_id);
+    }
+
     // NOTE: Plan-waiting state for Codex/Opencode is now signaled via the
     // `waiting_for_plan` field in the chat:done event, and persisted by the
     // frontend's chat:done handler. The previous approach of setting it here
@@ -2402,6 +1759,19 @@ pub async fn send_chat_message(
         log::trace!("Chat message cancelled but partial response saved for session: {session_id}");
     } else {
         log::trace!("Chat message sent and response received for session: {session_id}");
+
+        super::digest::schedule_digest_refresh(&app, &session_id);
+
+        // Continue with the next queued follow-up, unless the run stopped to
+        // ask the user a question. A queued message takes priority over
+        // approving a plan.
+        let waiting_for_user = assistant_msg
+            .tool_calls
+            .iter()
+            .any(|t| t.name == "AskUserQuestion");
+        if !waiting_for_user {
+            super::queue::dispatch_next_queued_message(&app, &session_id, false);
+        }
     }
     Ok(assistant_msg)
 }
@@ -2422,6 +1792,7 @@ pub async fn clear_session_history(
     if let Err(e) = delete_session_data(&app, &session_id) {
         log::warn!("Failed to delete session data: {e}");
     }
+    super::checkpoint::delete_session_checkpoints(&worktree_path, &session_id);
 
     with_sessions_mut(&app, &worktree_path, &worktree_id, |sessions| {
         if let Some(session) = sessions.find_session_mut(&session_id) {
@@ -2433,6 +1804,7 @@ pub async fn clear_session_history(
             session.claude_session_id = None;
             session.codex_thread_id = None;
             session.opencode_session_id = None;
+            session.gemini_session_id = None;
             session.selected_model = selected_model;
             session.selected_thinking_level = selected_thinking_level;
             session.selected_provider = selected_provider;
@@ -2513,7 +1885,8 @@ pub async fn set_session_provider(
     })
 }
 
-/// Set the backend for a session
+/// Set the backend for a session. The next run on the new backend is seeded
+/// with a handoff of the earlier turns (see `handoff`).
 #[tauri::command]
 pub async fn set_session_backend(
     app: AppHandle,
@@ -2526,11 +1899,7 @@ pub async fn set_session_backend(
 
     with_sessions_mut(&app, &worktree_path, &worktree_id, |sessions| {
         if let Some(session) = sessions.find_session_mut(&session_id) {
-            session.backend = match backend.as_str() {
-                "codex" => super::types::Backend::Codex,
-                "opencode" => super::types::Backend::Opencode,
-                _ => super::types::Backend::Claude,
-            };
+            session.backend = parse_backend(&backend).unwrap_or_default();
             log::trace!("Backend selection saved");
             Ok(())
         } else {
@@ -2548,7 +1917,10 @@ pub async fn cancel_chat_message(
     worktree_id: String,
 ) -> Result<bool, String> {
     log::trace!("Cancel chat message requested for session: {session_id}");
-    cancel_process(&app, &session_id, &worktree_id)
+    let backend = load_metadata(&app, &session_id)?
+        .map(|metadata| metadata.backend)
+        .unwrap_or_default();
+    super::backend::backend_for(&backend).cancel(&app, &session_id, &worktree_id)
 }
 
 /// Check if any sessions have running Claude processes
@@ -2694,7 +2066,10 @@ fn process_dynamic_image(
 
 /// Process image: resize to Claude's optimal limit (1568px) and convert opaque PNG→JPEG.
 /// Returns (processed_bytes, final_extension) — extension may change (e.g. png→jpg).
-fn process_image(image_data: &[u8], extension: &str) -> Result<(Vec<u8>, String), String> {
+pub(super) fn process_image(
+    image_data: &[u8],
+    extension: &str,
+) -> Result<(Vec<u8>, String), String> {
     // Skip GIFs (may be animated) and small images
     if extension == "gif" || image_data.len() < MIN_PROCESS_SIZE {
         return Ok((image_data.to_vec(), extension.to_string()));
@@ -2774,24 +2149,18 @@ pub async fn save_pasted_image(
     Ok(result)
 }
 
-/// Save processed image data to disk with atomic write (temp file + rename).
+/// Save processed image data to disk, content-addressed (see `pastes`).
 /// Shared by save_pasted_image, read_clipboard_image, and save_dropped_image.
-fn save_image_to_disk(
+pub(super) fn save_image_to_disk(
     images_dir: &std::path::Path,
     data: &[u8],
     ext: &str,
 ) -> Result<SaveImageResponse, String> {
-    let timestamp = now();
-    let short_uuid = &Uuid::new_v4().to_string()[..8];
-    let filename = format!("image-{timestamp}-{short_uuid}.{ext}");
-    let file_path = images_dir.join(&filename);
-
-    let temp_path = file_path.with_extension("tmp");
-    std::fs::write(&temp_path, data)
-        .map_err(|e| format!("Failed to write image file: {e}"))?;
-
-    std::fs::rename(&temp_path, &file_path)
-        .map_err(|e| format!("Failed to finalize image file: {e}"))?;
+    let file_path = super::pastes::store_blob(images_dir, "image", data, ext)?;
+    let filename = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
 
     let path_str = file_path
         .to_str()
@@ -2962,10 +2331,10 @@ pub async fn delete_pasted_image(app: AppHandle, path: String) -> Result<(), Str
         return Err("Invalid path: must be within allowed directories".to_string());
     }
 
-    // Delete the file
-    std::fs::remove_file(&file_path).map_err(|e| format!("Failed to delete image: {e}"))?;
+    // Removed once no session references it (shared files after a grace period)
+    super::pastes::release_pastes(&app, std::slice::from_ref(&path));
 
-    log::trace!("Image deleted: {path}");
+    log::trace!("Image released: {path}");
     Ok(())
 }
 
@@ -2998,18 +2367,12 @@ pub async fn save_pasted_text(app: AppHandle, content: String) -> Result<SaveTex
     // Get the pastes directory (now in app data dir)
     let pastes_dir = get_pastes_dir(&app)?;
 
-    // Generate unique filename
-    let timestamp = now();
-    let short_uuid = &Uuid::new_v4().to_string()[..8];
-    let filename = format!("paste-{timestamp}-{short_uuid}.txt");
-    let file_path = pastes_dir.join(&filename);
-
-    // Write file atomically (temp file + rename)
-    let temp_path = file_path.with_extension("tmp");
-    std::fs::write(&temp_path, &content).map_err(|e| format!("Failed to write text file: {e}"))?;
-
-    std::fs::rename(&temp_path, &file_path)
-        .map_err(|e| format!("Failed to finalize text file: {e}"))?;
+    // Stored under its content hash; pasting the same text again reuses the file
+    let file_path = super::pastes::store_blob(&pastes_dir, "paste", content.as_bytes(), "txt")?;
+    let filename = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
 
     let path_str = file_path
         .to_str()
@@ -3028,14 +2391,15 @@ pub async fn save_pasted_text(app: AppHandle, content: String) -> Result<SaveTex
 
 /// Update the content of a pasted text file
 ///
-/// Overwrites the file content atomically (temp file + rename).
-/// Returns the new file size in bytes.
+/// Pasted files are content-addressed and may be shared, so the new content is
+/// stored as its own file and the old one is released.
+/// Returns the new file (path, size).
 #[tauri::command]
 pub async fn update_pasted_text(
     app: AppHandle,
     path: String,
     content: String,
-) -> Result<usize, String> {
+) -> Result<SaveTextResponse, String> {
     let size = content.len();
     log::trace!("Updating pasted text file: {path}, new size: {size} bytes");
 
@@ -3070,15 +2434,13 @@ pub async fn update_pasted_text(
         return Err("Invalid path: must be within allowed directories".to_string());
     }
 
-    // Write file atomically (temp file + rename)
-    let temp_path = file_path.with_extension("tmp");
-    std::fs::write(&temp_path, &content).map_err(|e| format!("Failed to write text file: {e}"))?;
-
-    std::fs::rename(&temp_path, &file_path)
-        .map_err(|e| format!("Failed to finalize text file: {e}"))?;
+    let updated = save_pasted_text(app.clone(), content).await?;
+    if updated.path != path {
+        super::pastes::release_pastes(&app, &[path.clone()]);
+    }
 
-    log::trace!("Text file updated: {path}");
-    Ok(size)
+    log::trace!("Text file updated: {path} -> {}", updated.path);
+    Ok(updated)
 }
 
 /// Delete a pasted text file
@@ -3115,10 +2477,10 @@ pub async fn delete_pasted_text(app: AppHandle, path: String) -> Result<(), Stri
         return Err("Invalid path: must be within allowed directories".to_string());
     }
 
-    // Delete the file
-    std::fs::remove_file(&file_path).map_err(|e| format!("Failed to delete text file: {e}"))?;
+    // Removed once no session references it (shared files after a grace period)
+    super::pastes::release_pastes(&app, std::slice::from_ref(&path));
 
-    log::trace!("Text file deleted: {path}");
+    log::trace!("Text file released: {path}");
     Ok(())
 }
 
@@ -3873,34 +3235,22 @@ fn execute_summarization_claude(
 ) -> Result<ContextSummaryResponse, String> {
     let model_str = model.unwrap_or("opus");
 
-    // Route to OpenCode if model is an OpenCode model
-    if crate::is_opencode_model(model_str) {
-        log::trace!("Executing one-shot OpenCode summarization");
-        let json_str = super::opencode::execute_one_shot_opencode(
-            app,
-            prompt,
-            model_str,
-            Some(CONTEXT_SUMMARY_SCHEMA),
-            working_dir,
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse OpenCode summarization JSON: {e}, content: {json_str}");
-            format!("Failed to parse summarization response: {e}")
-        });
-    }
-
-    // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(model_str) {
-        log::trace!("Executing one-shot Codex summarization with output-schema");
-        let json_str = super::codex::execute_one_shot_codex(
+    // Route OpenCode and Codex models to their backend's structured one-shot call
+    if let Some(agent) = super::backend::backend_for_model(model_str) {
+        log::trace!("Executing one-shot {} summarization", agent.display_name());
+        let json_str = agent.one_shot(
             app,
             prompt,
             model_str,
             CONTEXT_SUMMARY_SCHEMA,
             working_dir,
+            custom_profile_name,
         )?;
         return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse Codex summarization JSON: {e}, content: {json_str}");
+            log::error!(
+                "Failed to parse {} summarization JSON: {e}, content: {json_str}",
+                agent.display_name()
+            );
             format!("Failed to parse summarization response: {e}")
         });
     }
@@ -4160,11 +3510,12 @@ pub async fn get_session_debug_info(
 
     let app_data_str = app_data_dir.to_str().unwrap_or("unknown").to_string();
 
-    // Get index file path (was sessions file)
-    let sessions_file = get_index_path(&app, &worktree_id)?
+    // Indexes and metadata live in the database
+    let database_path = crate::db::get_database_path(&app)?
         .to_str()
         .unwrap_or("unknown")
         .to_string();
+    let sessions_file = database_path.clone();
 
     // Get data directory (was runs directory)
     let runs_dir = get_data_dir(&app)?
@@ -4200,24 +3551,18 @@ pub async fn get_session_debug_info(
         None
     });
 
-    // Get session directory and metadata file path (was manifest)
     let session_dir = get_session_dir(&app, &session_id)?;
-    let metadata_path = session_dir.join("metadata.json");
-    let manifest_file = if metadata_path.exists() {
-        metadata_path.to_str().map(|s| s.to_string())
-    } else {
-        None
-    };
 
     // Load metadata to get run info
     let metadata = load_metadata(&app, &session_id)?;
+    let manifest_file = metadata.as_ref().map(|_| database_path);
 
     // Build JSONL file info list
     let mut run_log_files = Vec::new();
     if let Some(metadata) = metadata {
         for run in &metadata.runs {
-            let jsonl_path = session_dir.join(format!("{}.jsonl", run.run_id));
-            if jsonl_path.exists() {
+            let log_path = session_dir.join(format!("{}.jsonl", run.run_id));
+            if let Some(jsonl_path) = super::retention::existing_run_log(&log_path) {
                 // Truncate user message preview to 50 chars
                 let preview = if run.user_message.len() > 50 {
                     format!("{}...", &run.user_message[..47])
@@ -4231,6 +3576,10 @@ pub async fn get_session_debug_info(
                     status: run.status.clone(),
                     user_message_preview: preview,
                     usage: run.usage.clone(),
+                    cost_usd: run.cost_usd,
+                    checkpoint: run.checkpoint.clone(),
+                    changes: run.changes.clone(),
+                    compactions: run.compactions.clone(),
                 });
             }
         }
@@ -4353,13 +3702,44 @@ pub async fn resume_session(
             });
         }
 
+        if let Ok(Some(run_budgets)) = super::budget::load_run_budgets(&app, &session_id) {
+            super::budget::track_run(
+                &session_id,
+                &worktree_id,
+                &run_id,
+                &metadata.backend,
+                run.model.as_deref(),
+                run.provider.as_deref(),
+                run_budgets,
+            );
+        }
+        let agent = super::backend::backend_for(&metadata.backend);
+        if agent.capabilities().watchdog {
+            let run_age = now().saturating_sub(run.started_at);
+            super::watchdog::watch_run(
+                &app,
+                &session_id,
+                &worktree_id,
+                &run_id,
+                output_file.clone(),
+                std::time::Duration::from_secs(run_age),
+            );
+        }
+
         // Clone values for the async task
         let app_clone = app.clone();
         let session_id_clone = session_id.clone();
         let worktree_id_clone = worktree_id.clone();
         let run_id_clone = run_id.clone();
-        let is_codex = metadata.backend == Backend::Codex;
-        let is_plan_mode = run.execution_mode.as_deref() == Some("plan");
+        let execution_mode = run.execution_mode.clone();
+        // Checkpointed runs get their changes attributed when they finish
+        let checkpoint = run.checkpoint.clone();
+        let worktree_path = checkpoint.as_ref().and_then(|_| {
+            crate::projects::storage::load_projects_data(&app)
+                .ok()?
+                .find_worktree(&worktree_id)
+                .map(|w| w.path.clone())
+        });
 
         // Spawn a task to tail the output file
         tauri::async_runtime::spawn(async move {
@@ -4373,60 +3753,53 @@ pub async fn resume_session(
                 );
             };
 
-            // Tail the output file — route by backend
-            let (resume_id, usage, cancelled) = if is_codex {
-                match super::codex::tail_codex_output(
-                    &app_clone,
-                    &session_id_clone,
-                    &worktree_id_clone,
-                    &output_file,
-                    pid,
-                    is_plan_mode,
-                ) {
-                    Ok(response) => (response.thread_id, response.usage, response.cancelled),
-                    Err(e) => {
-                        log::error!("Resume Codex tail failed for run: {run_id_clone}, error: {e}");
-                        super::registry::unregister_process(&session_id_clone);
-                        if let Ok(mut writer) =
-                            RunLogWriter::resume(&app_clone, &session_id_clone, &run_id_clone)
-                        {
-                            if let Err(e) = writer.crash() {
-                                log::error!("Failed to mark run as crashed: {e}");
-                            }
-                        }
-                        emit_done(&app_clone, &session_id_clone, &worktree_id_clone);
-                        return;
-                    }
+            // Tail the output file with the session's backend
+            let (resume_id, usage, cancelled, waiting_for_user) = match agent.tail(
+                &app_clone,
+                &session_id_clone,
+                &worktree_id_clone,
+                &output_file,
+                pid,
+                execution_mode.as_deref(),
+            ) {
+                Ok(response) => {
+                    // Backends without a plan approval tool wait after every plan
+                    // Queued messages wait for answers, not for plan approval
+                    let waiting_for_user = response
+                        .tool_calls
+                        .iter()
+                        .any(|t| t.name == "AskUserQuestion");
+                    (
+                        response.resume_id,
+                        response.usage,
+                        response.cancelled,
+                        waiting_for_user,
+                    )
                 }
-            } else {
-                match super::claude::tail_claude_output(
-                    &app_clone,
-                    &session_id_clone,
-                    &worktree_id_clone,
-                    &output_file,
-                    pid,
-                ) {
-                    Ok(response) => (response.session_id, response.usage, response.cancelled),
-                    Err(e) => {
-                        log::error!(
-                            "Resume Claude tail failed for run: {run_id_clone}, error: {e}"
-                        );
-                        super::registry::unregister_process(&session_id_clone);
-                        if let Ok(mut writer) =
-                            RunLogWriter::resume(&app_clone, &session_id_clone, &run_id_clone)
-                        {
-                            if let Err(e) = writer.crash() {
-                                log::error!("Failed to mark run as crashed: {e}");
-                            }
+                Err(e) => {
+                    log::error!(
+                        "Resume {} tail failed for run: {run_id_clone}, error: {e}",
+                        agent.display_name()
+                    );
+                    super::registry::unregister_process(&session_id_clone);
+                    super::budget::untrack_run(&session_id_clone);
+                    super::watchdog::unwatch_run(&session_id_clone);
+                    if let Ok(mut writer) =
+                        RunLogWriter::resume(&app_clone, &session_id_clone, &run_id_clone)
+                    {
+                        if let Err(e) = writer.crash() {
+                            log::error!("Failed to mark run as crashed: {e}");
                         }
-                        emit_done(&app_clone, &session_id_clone, &worktree_id_clone);
-                        return;
                     }
+                    emit_done(&app_clone, &session_id_clone, &worktree_id_clone);
+                    return;
                 }
             };
 
             // Unregister from process registry now that tailing is complete
             super::registry::unregister_process(&session_id_clone);
+            super::budget::untrack_run(&session_id_clone);
+            super::watchdog::unwatch_run(&session_id_clone);
 
             log::trace!(
                 "Resume completed for run: {run_id_clone}, resume_id: {:?}, cancelled: {cancelled}",
@@ -4450,6 +3823,13 @@ pub async fn resume_session(
                     } else {
                         Some(resume_id.as_str())
                     };
+                    if let Some(worktree_path) = &worktree_path {
+                        super::checkpoint::record_run_changes(
+                            &mut writer,
+                            worktree_path,
+                            checkpoint.as_ref(),
+                        );
+                    }
                     if let Err(e) =
                         writer.complete(&assistant_message_id, resume_sid, usage.clone())
                     {
@@ -4466,6 +3846,13 @@ pub async fn resume_session(
                     }
                 }
             }
+
+            if !cancelled {
+                super::digest::schedule_digest_refresh(&app_clone, &session_id_clone);
+            }
+            if !cancelled && !waiting_for_user {
+                super::queue::dispatch_next_queued_message(&app_clone, &session_id_clone, false);
+            }
         });
     }
 
@@ -4490,6 +3877,9 @@ pub async fn check_resumable_sessions(
 
     let resumable: Vec<_> = recovered.into_iter().filter(|r| r.resumable).collect();
 
+    // Queued follow-ups persisted before the restart continue where they left off
+    super::queue::resume_pending_queues(&app);
+
     log::trace!("Found {} resumable session(s)", resumable.len());
 
     Ok(resumable)
@@ -4529,34 +3919,22 @@ fn execute_digest_claude(
     custom_profile_name: Option<&str>,
     working_dir: Option<&std::path::Path>,
 ) -> Result<SessionDigestResponse, String> {
-    // Route to OpenCode if model is an OpenCode model
-    if crate::is_opencode_model(model) {
-        log::trace!("Executing one-shot OpenCode digest");
-        let json_str = super::opencode::execute_one_shot_opencode(
-            app,
-            prompt,
-            model,
-            Some(SESSION_DIGEST_SCHEMA),
-            working_dir,
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse OpenCode digest JSON: {e}, content: {json_str}");
-            format!("Failed to parse digest response: {e}")
-        });
-    }
-
-    // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(model) {
-        log::trace!("Executing one-shot Codex digest with output-schema");
-        let json_str = super::codex::execute_one_shot_codex(
+    // Route OpenCode and Codex models to their backend's structured one-shot call
+    if let Some(agent) = super::backend::backend_for_model(model) {
+        log::trace!("Executing one-shot {} digest", agent.display_name());
+        let json_str = agent.one_shot(
             app,
             prompt,
             model,
             SESSION_DIGEST_SCHEMA,
             working_dir,
+            custom_profile_name,
         )?;
         return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse Codex digest JSON: {e}, content: {json_str}");
+            log::error!(
+                "Failed to parse {} digest JSON: {e}, content: {json_str}",
+                agent.display_name()
+            );
             format!("Failed to parse digest response: {e}")
         });
     }
@@ -4664,14 +4042,23 @@ pub async fn generate_session_digest(
     session_id: String,
 ) -> Result<SessionDigest, String> {
     log::trace!("Generating digest for session {}", session_id);
+    build_session_digest(&app, &session_id, None).await
+}
 
+/// Generate a digest of a session's conversation. `model` overrides the
+/// session recap model (with its custom provider, if any).
+pub(super) async fn build_session_digest(
+    app: &AppHandle,
+    session_id: &str,
+    model: Option<(&str, Option<&str>)>,
+) -> Result<SessionDigest, String> {
     // Load preferences to get model
     let prefs = crate::load_preferences(app.clone())
         .await
         .map_err(|e| format!("Failed to load preferences: {e}"))?;
 
     // Load messages from session
-    let messages = run_log::load_session_messages(&app, &session_id)?;
+    let messages = run_log::load_session_messages(app, session_id)?;
 
     if messages.len() < 2 {
         return Err("Session has too few messages for digest".to_string());
@@ -4689,15 +4076,17 @@ pub async fn generate_session_digest(
         .unwrap_or(SESSION_DIGEST_PROMPT);
     let prompt = prompt_template.replace("{conversation}", &conversation_history);
 
-    // Use magic prompt model/provider
-    let model = &prefs.magic_prompt_models.session_recap_model;
-    let provider = prefs
-        .magic_prompt_providers
-        .session_recap_provider
-        .as_deref();
+    // Use magic prompt model/provider unless overridden
+    let (model, provider) = model.unwrap_or((
+        prefs.magic_prompt_models.session_recap_model.as_str(),
+        prefs
+            .magic_prompt_providers
+            .session_recap_provider
+            .as_deref(),
+    ));
 
     // Call Claude CLI with JSON schema (non-streaming)
-    let response = execute_digest_claude(&app, &prompt, model, provider, None)?;
+    let response = execute_digest_claude(app, &prompt, model, provider, None)?;
 
     Ok(SessionDigest {
         chat_summary: response.chat_summary,
@@ -4715,6 +4104,7 @@ pub async fn generate_session_digest(
 /// Update a session's persisted digest
 ///
 /// Called after generating a digest to persist it to disk so it survives app reload.
+/// The digest it replaces is kept in the session's digest history.
 #[tauri::command]
 pub async fn update_session_digest(
     app: AppHandle,
@@ -4723,15 +4113,7 @@ pub async fn update_session_digest(
 ) -> Result<(), String> {
     log::trace!("Persisting digest for session {session_id}");
 
-    // Load existing metadata
-    let metadata = load_metadata(&app, &session_id)?
-        .ok_or_else(|| format!("Session {session_id} not found"))?;
-
-    // Update and save with new digest
-    let mut updated = metadata;
-    updated.digest = Some(digest);
-
-    super::storage::save_metadata(&app, &updated)?;
+    super::digest::record_digest(&app, &session_id, digest)?;
 
     log::trace!("Digest persisted for session {session_id}");
     Ok(())
@@ -4769,7 +4151,7 @@ pub struct McpServerInfo {
     pub scope: String, // "user", "local", "project"
     /// Whether the server is disabled in its config (has "disabled": true)
     pub disabled: bool,
-    /// Which backend this server belongs to: "claude", "codex", or "opencode"
+    /// Which backend this server belongs to: "claude", "codex", "opencode", or "gemini"
     pub backend: String,
 }
 
@@ -4796,6 +4178,7 @@ pub struct McpHealthResult {
 /// - Claude:   ~/.claude.json (user + local scope) + <worktree>/.mcp.json (project scope)
 /// - Codex:    ~/.codex/config.toml (global) + <worktree>/.codex/config.toml (project)
 /// - OpenCode: ~/.config/opencode/opencode.json (global) + <worktree>/opencode.json (project)
+/// - Gemini:   ~/.gemini/settings.json (global) + <worktree>/.gemini/settings.json (project)
 #[tauri::command]
 pub async fn get_mcp_servers(
     backend: Option<String>,
@@ -4805,6 +4188,7 @@ pub async fn get_mcp_servers(
     let servers = match backend.as_deref() {
         Some("codex") => crate::codex_cli::mcp::get_mcp_servers(wt),
         Some("opencode") => crate::opencode_cli::mcp::get_mcp_servers(wt),
+        Some("gemini") => crate::gemini_cli::mcp::get_mcp_servers(wt),
         _ => crate::claude_cli::mcp::get_mcp_servers(wt),
     };
     Ok(servers)
@@ -4859,6 +4243,7 @@ fn parse_mcp_list_output(output: &str) -> std::collections::HashMap<String, McpH
 /// - Claude:   `claude mcp list` (text output)
 /// - Codex:    `codex mcp list --json` (JSON output)
 /// - OpenCode: `opencode mcp list` (text output)
+/// - Gemini:   `gemini mcp list` (text output)
 #[tauri::command]
 pub async fn check_mcp_health(
     app: AppHandle,
@@ -4867,6 +4252,7 @@ pub async fn check_mcp_health(
     match backend.as_deref() {
         Some("codex") => check_mcp_health_codex(&app),
         Some("opencode") => check_mcp_health_opencode(&app),
+        Some("gemini") => check_mcp_health_gemini(&app),
         _ => check_mcp_health_claude(&app),
     }
 }
@@ -4950,6 +4336,47 @@ fn check_mcp_health_opencode(app: &AppHandle) -> Result<McpHealthResult, String>
     Ok(McpHealthResult { statuses })
 }
 
+fn check_mcp_health_gemini(app: &AppHandle) -> Result<McpHealthResult, String> {
+    let cli_path = crate::gemini_cli::resolve_cli_binary(app);
+    if !cli_path.exists() {
+        return Err("Gemini CLI not installed".to_string());
+    }
+
+    log::debug!("Running: gemini mcp list");
+
+    let output = silent_command(&cli_path)
+        .args(["mcp", "list"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Failed to run gemini mcp list: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("gemini mcp list failed: {stderr}"));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let statuses = parse_mcp_list_output(&normalize_gemini_mcp_list(&stdout));
+    log::debug!("MCP health check (Gemini): {} servers", statuses.len());
+    Ok(McpHealthResult { statuses })
+}
+
+/// Rewrite `gemini mcp list` output into the `claude mcp list` line format.
+///
+/// Gemini prefixes each server with a status glyph, prints a
+/// "Configured MCP servers:" header and reports failures as "Disconnected"
+/// (which would otherwise match "connected").
+fn normalize_gemini_mcp_list(output: &str) -> String {
+    output
+        .lines()
+        .map(|line| line.trim_start_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|line| !line.starts_with("Configured MCP servers"))
+        .map(|line| line.replace("- Disconnected", "- Could not connect"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Parse `codex mcp list --json` output into health statuses.
 fn parse_codex_mcp_list_json(
     output: &str,
@@ -5113,6 +4540,26 @@ my-disabled: /usr/bin/disabled (STDIO) - disabled";
         );
     }
 
+    #[test]
+    fn test_parse_gemini_mcp_list_output() {
+        let output = "\
+Configured MCP servers:
+
+\u{2713} filesystem: npx -y fs-server (stdio) - Connected
+\u{2717} notion: https://mcp.notion.com/mcp (http) - Disconnected";
+
+        let statuses = parse_mcp_list_output(&normalize_gemini_mcp_list(output));
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(
+            statuses.get("filesystem"),
+            Some(&McpHealthStatus::Connected)
+        );
+        assert_eq!(
+            statuses.get("notion"),
+            Some(&McpHealthStatus::CouldNotConnect)
+        );
+    }
+
     #[test]
     fn test_parse_mcp_list_output_empty() {
         let output = "Checking MCP server health...\n\n";
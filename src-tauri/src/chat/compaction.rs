@@ -0,0 +1,253 @@
+//! Context compaction and context-window tracking
+//!
+//! Claude CLI compacts a conversation on its own when the context window fills
+//! up (or on `/compact`), and reports it with a `compact_boundary` system line.
+//! Boundaries become `ContentBlock::Compaction` in the message and are recorded
+//! on the run (`RunEntry.compactions`).
+//!
+//! While a Claude run streams, each API call's usage tells how full the context
+//! window is. `ContextTracker` emits `chat:context_window` with the fill and
+//! `chat:context_warning` once it crosses `CONTEXT_WARNING_RATIO`, and the last
+//! fill is persisted on the session (`SessionMetadata.context_window`).
+//!
+//! `compact_session` runs a compaction turn on demand, optionally focused by
+//! an instruction (`/compact <instructions>`).
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::storage::load_metadata;
+use super::time::now;
+use super::types::{Backend, ChatMessage, CompactMetadata, ContextWindowUsage, UsageData};
+use crate::http_server::EmitExt;
+
+/// Default context window of Claude models
+const DEFAULT_CONTEXT_WINDOW: u64 = 200_000;
+/// Context window of Claude models with the 1M context beta (`[1m]` suffix)
+const EXTENDED_CONTEXT_WINDOW: u64 = 1_000_000;
+/// Context window of GPT-5 (Codex) models
+const GPT5_CONTEXT_WINDOW: u64 = 272_000;
+/// Fill ratio at which `chat:context_warning` is emitted
+const CONTEXT_WARNING_RATIO: f64 = 0.8;
+
+/// Context window size for a model ID
+pub fn context_window_limit(model: Option<&str>) -> u64 {
+    let model = model.unwrap_or("").to_lowercase();
+    if model.contains("[1m]") {
+        EXTENDED_CONTEXT_WINDOW
+    } else if model.starts_with("gpt-5") {
+        GPT5_CONTEXT_WINDOW
+    } else {
+        DEFAULT_CONTEXT_WINDOW
+    }
+}
+
+/// Tokens in context for an API call: everything sent as input, cached or not
+pub fn context_tokens(usage: &UsageData) -> u64 {
+    usage.input_tokens + usage.cache_read_input_tokens + usage.cache_creation_input_tokens
+}
+
+/// Parse a Claude `compact_boundary` system line. The CLI has reported its
+/// metadata as both `compact_metadata` and `compactMetadata`.
+pub(super) fn parse_compact_boundary(msg: &serde_json::Value) -> Option<CompactMetadata> {
+    if msg.get("type").and_then(|v| v.as_str()) != Some("system")
+        || msg.get("subtype").and_then(|v| v.as_str()) != Some("compact_boundary")
+    {
+        return None;
+    }
+    let metadata = msg
+        .get("compact_metadata")
+        .or_else(|| msg.get("compactMetadata"))
+        .and_then(|v| serde_json::from_value::<CompactMetadata>(v.clone()).ok());
+    Some(metadata.unwrap_or_else(|| CompactMetadata {
+        trigger: "auto".to_string(),
+        pre_tokens: 0,
+    }))
+}
+
+/// Payload for chat:context_window and chat:context_warning
+#[derive(Debug, Clone, Serialize)]
+struct ContextWindowEvent {
+    session_id: String,
+    worktree_id: String,
+    used_tokens: u64,
+    limit_tokens: u64,
+}
+
+/// Tracks the context-window fill of a streaming Claude run
+pub(super) struct ContextTracker {
+    worktree_id: String,
+    limit_tokens: u64,
+    used_tokens: Option<u64>,
+    warned: bool,
+}
+
+impl ContextTracker {
+    /// Start tracking for the session's current run (its model sets the limit)
+    pub fn load(app: &AppHandle, session_id: &str, worktree_id: &str) -> Self {
+        let model = load_metadata(app, session_id)
+            .ok()
+            .flatten()
+            .and_then(|metadata| metadata.runs.last().and_then(|run| run.model.clone()));
+        Self {
+            worktree_id: worktree_id.to_string(),
+            limit_tokens: context_window_limit(model.as_deref()),
+            used_tokens: None,
+            warned: false,
+        }
+    }
+
+    /// Context tokens of the run's latest API call
+    pub fn used_tokens(&self) -> Option<u64> {
+        self.used_tokens
+    }
+
+    /// Record an API call's usage and emit the fill (and a warning when it
+    /// first crosses the threshold; a compaction re-arms the warning)
+    pub fn observe(&mut self, app: &AppHandle, session_id: &str, usage: &UsageData) {
+        let used_tokens = context_tokens(usage);
+        if used_tokens == 0 || self.used_tokens == Some(used_tokens) {
+            return;
+        }
+        self.used_tokens = Some(used_tokens);
+
+        let event = ContextWindowEvent {
+            session_id: session_id.to_string(),
+            worktree_id: self.worktree_id.clone(),
+            used_tokens,
+            limit_tokens: self.limit_tokens,
+        };
+        if let Err(e) = app.emit_all("chat:context_window", &event) {
+            log::error!("Failed to emit context_window: {e}");
+        }
+
+        let over = used_tokens as f64 >= self.limit_tokens as f64 * CONTEXT_WARNING_RATIO;
+        if over && !self.warned {
+            log::trace!(
+                "Session {session_id} context window at {used_tokens} of {} tokens",
+                self.limit_tokens
+            );
+            if let Err(e) = app.emit_all("chat:context_warning", &event) {
+                log::error!("Failed to emit context_warning: {e}");
+            }
+        }
+        self.warned = over;
+    }
+}
+
+/// Context-window fill to persist for a finished run
+pub fn context_window_usage(used_tokens: u64, model: Option<&str>) -> ContextWindowUsage {
+    ContextWindowUsage {
+        used_tokens,
+        limit_tokens: context_window_limit(model),
+        updated_at: now(),
+    }
+}
+
+/// Compact a Claude session's conversation now, optionally focusing the
+/// summary with `instructions`. Runs as a regular (plan mode) turn.
+#[tauri::command]
+pub async fn compact_session(
+    app: AppHandle,
+    session_id: String,
+    worktree_id: String,
+    worktree_path: String,
+    instructions: Option<String>,
+) -> Result<ChatMessage, String> {
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    if metadata.backend != Backend::Claude {
+        return Err("Only Claude sessions can be compacted".to_string());
+    }
+    if metadata.claude_session_id.is_none() {
+        return Err("Session has no conversation to compact yet".to_string());
+    }
+    if super::registry::is_process_running(&session_id) {
+        return Err("Cannot compact while a response is streaming".to_string());
+    }
+
+    let message = match instructions.as_deref().map(str::trim) {
+        Some(focus) if !focus.is_empty() => format!("/compact {focus}"),
+        _ => "/compact".to_string(),
+    };
+    log::trace!("Compacting session {session_id}");
+
+    super::send_chat_message(
+        app,
+        session_id,
+        worktree_id,
+        worktree_path,
+        message,
+        metadata.selected_model,
+        Some("plan".to_string()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        metadata.selected_provider,
+        Some("claude".to_string()),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_window_limit() {
+        assert_eq!(context_window_limit(Some("opus")), DEFAULT_CONTEXT_WINDOW);
+        assert_eq!(
+            context_window_limit(Some("sonnet[1m]")),
+            EXTENDED_CONTEXT_WINDOW
+        );
+        assert_eq!(
+            context_window_limit(Some("gpt-5.2-codex")),
+            GPT5_CONTEXT_WINDOW
+        );
+        assert_eq!(context_window_limit(None), DEFAULT_CONTEXT_WINDOW);
+    }
+
+    #[test]
+    fn test_parse_compact_boundary() {
+        let line = serde_json::json!({
+            "type": "system",
+            "subtype": "compact_boundary",
+            "compact_metadata": { "trigger": "manual", "pre_tokens": 151_234 }
+        });
+        assert_eq!(
+            parse_compact_boundary(&line),
+            Some(CompactMetadata {
+                trigger: "manual".to_string(),
+                pre_tokens: 151_234,
+            })
+        );
+
+        let camel = serde_json::json!({
+            "type": "system",
+            "subtype": "compact_boundary",
+            "compactMetadata": { "trigger": "auto", "pre_tokens": 180_000 }
+        });
+        assert_eq!(
+            parse_compact_boundary(&camel).map(|m| m.pre_tokens),
+            Some(180_000)
+        );
+
+        let init = serde_json::json!({ "type": "system", "subtype": "init" });
+        assert_eq!(parse_compact_boundary(&init), None);
+    }
+
+    #[test]
+    fn test_context_tokens_include_cache() {
+        let usage = UsageData {
+            input_tokens: 10,
+            output_tokens: 500,
+            cache_read_input_tokens: 90_000,
+            cache_creation_input_tokens: 2_000,
+        };
+        assert_eq!(context_tokens(&usage), 92_010);
+    }
+}
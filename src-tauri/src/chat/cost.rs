@@ -0,0 +1,553 @@
+//! Cost accounting for runs
+//!
+//! Turns `UsageData` into USD using a pricing table keyed by model and provider
+//! (custom CLI profile). Built-in rates can be overridden per user; overrides are
+//! stored in `app-data/pricing.json`. Each run's cost is computed on completion
+//! and stored on `RunEntry.cost_usd`; aggregation commands roll costs up by
+//! session, worktree, project, backend, model or day.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::storage::{list_all_session_ids, load_metadata};
+use super::time::utc_day;
+use super::types::{Backend, RunEntry, UsageData};
+
+// ============================================================================
+// Pricing Table
+// ============================================================================
+
+/// Rates for a model, in USD per million tokens
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModelRate {
+    /// Model name or alias as stored on runs (e.g. "opus", "gpt-5.2-codex").
+    /// Matches models containing it; "*" matches any model.
+    pub model: String,
+    /// Custom CLI profile name this rate applies to (None = any provider)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+    #[serde(default)]
+    pub cache_read_per_mtok: f64,
+    #[serde(default)]
+    pub cache_write_per_mtok: f64,
+}
+
+impl ModelRate {
+    fn new(model: &str, input: f64, output: f64, cache_read: f64, cache_write: f64) -> Self {
+        Self {
+            model: model.to_string(),
+            provider: None,
+            input_per_mtok: input,
+            output_per_mtok: output,
+            cache_read_per_mtok: cache_read,
+            cache_write_per_mtok: cache_write,
+        }
+    }
+
+    /// Match specificity against a model name (None = no match)
+    fn match_len(&self, model: Option<&str>) -> Option<usize> {
+        if self.model == "*" {
+            return Some(0);
+        }
+        let model = model?.to_lowercase();
+        let pattern = self.model.to_lowercase();
+        if model == pattern {
+            Some(usize::MAX)
+        } else if model.contains(&pattern) {
+            Some(pattern.len())
+        } else {
+            None
+        }
+    }
+}
+
+/// Built-in list prices (USD per million tokens)
+fn default_rates() -> Vec<ModelRate> {
+    vec![
+        // Claude
+        ModelRate::new("opus", 5.0, 25.0, 0.5, 6.25),
+        ModelRate::new("opus-4.1", 15.0, 75.0, 1.5, 18.75),
+        ModelRate::new("sonnet", 3.0, 15.0, 0.3, 3.75),
+        ModelRate::new("haiku", 1.0, 5.0, 0.1, 1.25),
+        // Codex / OpenAI (cache writes aren't billed separately)
+        ModelRate::new("gpt-5", 1.25, 10.0, 0.125, 0.0),
+        ModelRate::new("gpt-5.2", 1.75, 14.0, 0.175, 0.0),
+        ModelRate::new("gpt-5.3", 1.75, 14.0, 0.175, 0.0),
+        ModelRate::new("gpt-5.1-codex-max", 1.25, 10.0, 0.125, 0.0),
+        ModelRate::new("gpt-5.1-codex-mini", 0.25, 2.0, 0.025, 0.0),
+    ]
+}
+
+/// User overrides file contents (app-data/pricing.json)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct PricingOverrides {
+    #[serde(default)]
+    rates: Vec<ModelRate>,
+}
+
+/// Effective pricing table: user overrides take precedence over defaults
+#[derive(Debug, Clone, Serialize)]
+pub struct PricingTable {
+    pub defaults: Vec<ModelRate>,
+    pub overrides: Vec<ModelRate>,
+}
+
+impl PricingTable {
+    fn with_overrides(overrides: Vec<ModelRate>) -> Self {
+        Self {
+            defaults: default_rates(),
+            overrides,
+        }
+    }
+
+    /// Find the rate for a model/provider. Provider-specific overrides win, then
+    /// generic overrides, then defaults; within each tier the most specific model match wins.
+    pub fn rate_for(&self, model: Option<&str>, provider: Option<&str>) -> Option<&ModelRate> {
+        if let Some(p) = provider {
+            if let Some(rate) = best_rate(&self.overrides, model, Some(p)) {
+                return Some(rate);
+            }
+        }
+        if let Some(rate) = best_rate(&self.overrides, model, None) {
+            return Some(rate);
+        }
+        // Built-in list prices don't apply to custom providers
+        if provider.is_some() {
+            return None;
+        }
+        best_rate(&self.defaults, model, None)
+    }
+
+    /// Cost of token usage in USD (None if no rate matches)
+    pub fn cost_of(
+        &self,
+        usage: &UsageData,
+        model: Option<&str>,
+        provider: Option<&str>,
+        backend: &Backend,
+    ) -> Option<f64> {
+        let rate = self.rate_for(model, provider)?;
+        Some(usage_cost(usage, rate, backend))
+    }
+
+    /// Cost of a run in USD (None if it has no usage or no matching rate)
+    pub fn run_cost(&self, run: &RunEntry, session_backend: &Backend) -> Option<f64> {
+        self.cost_of(
+            run.usage.as_ref()?,
+            run.model.as_deref(),
+            run.provider.as_deref(),
+            run.backend.as_ref().unwrap_or(session_backend),
+        )
+    }
+}
+
+/// Most specific rate matching the model among rates for exactly this provider
+fn best_rate<'a>(
+    rates: &'a [ModelRate],
+    model: Option<&str>,
+    provider: Option<&str>,
+) -> Option<&'a ModelRate> {
+    rates
+        .iter()
+        .filter(|r| r.provider.as_deref() == provider)
+        .filter_map(|r| r.match_len(model).map(|len| (len, r)))
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, r)| r)
+}
+
+/// Compute the USD cost of token usage at the given rate.
+/// Codex and Gemini report cached tokens as part of input_tokens; Claude and
+/// OpenCode don't.
+fn usage_cost(usage: &UsageData, rate: &ModelRate, backend: &Backend) -> f64 {
+    let uncached_input = if matches!(backend, Backend::Codex | Backend::Gemini) {
+        usage
+            .input_tokens
+            .saturating_sub(usage.cache_read_input_tokens)
+    } else {
+        usage.input_tokens
+    };
+    (uncached_input as f64 * rate.input_per_mtok
+        + usage.output_tokens as f64 * rate.output_per_mtok
+        + usage.cache_read_input_tokens as f64 * rate.cache_read_per_mtok
+        + usage.cache_creation_input_tokens as f64 * rate.cache_write_per_mtok)
+        / 1_000_000.0
+}
+
+fn get_pricing_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join("pricing.json"))
+}
+
+/// Load the pricing table (defaults + user overrides). Falls back to defaults on error.
+pub fn load_pricing_table(app: &AppHandle) -> PricingTable {
+    let overrides = get_pricing_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<PricingOverrides>(&content).ok())
+        .unwrap_or_default();
+    PricingTable::with_overrides(overrides.rates)
+}
+
+/// Get the effective pricing table
+#[tauri::command]
+pub async fn get_pricing_table(app: AppHandle) -> Result<PricingTable, String> {
+    Ok(load_pricing_table(&app))
+}
+
+/// Replace the user's pricing overrides
+#[tauri::command]
+pub async fn set_pricing_overrides(
+    app: AppHandle,
+    overrides: Vec<ModelRate>,
+) -> Result<PricingTable, String> {
+    log::trace!("Saving {} pricing override(s)", overrides.len());
+
+    if let Some(rate) = overrides.iter().find(|r| {
+        [
+            r.input_per_mtok,
+            r.output_per_mtok,
+            r.cache_read_per_mtok,
+            r.cache_write_per_mtok,
+        ]
+        .iter()
+        .any(|v| !v.is_finite() || *v < 0.0)
+    }) {
+        return Err(format!("Invalid rate for model '{}'", rate.model));
+    }
+
+    let path = get_pricing_path(&app)?;
+    let content = serde_json::to_string_pretty(&PricingOverrides {
+        rates: overrides.clone(),
+    })
+    .map_err(|e| format!("Failed to serialize pricing overrides: {e}"))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content)
+        .map_err(|e| format!("Failed to write pricing overrides: {e}"))?;
+    fs::rename(&temp_path, &path)
+        .map_err(|e| format!("Failed to finalize pricing overrides: {e}"))?;
+
+    Ok(PricingTable::with_overrides(overrides))
+}
+
+// ============================================================================
+// Aggregation
+// ============================================================================
+
+/// Dimension to group costs by
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CostGroupBy {
+    Session,
+    Worktree,
+    Project,
+    Backend,
+    Model,
+    Day,
+}
+
+/// Optional filters for get_cost_summary
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CostFilters {
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub worktree_id: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub backend: Option<Backend>,
+    /// Only runs started at or after this unix timestamp
+    #[serde(default)]
+    pub from: Option<u64>,
+    /// Only runs started at or before this unix timestamp
+    #[serde(default)]
+    pub to: Option<u64>,
+}
+
+/// Aggregated usage and cost for one group
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CostBucket {
+    pub key: String,
+    /// Display name (session/worktree/project name) when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub runs: u32,
+    /// Runs with usage but no matching rate (not included in cost_usd)
+    pub unpriced_runs: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    pub cache_creation_input_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl CostBucket {
+    fn add(&mut self, usage: Option<&UsageData>, cost: Option<f64>) {
+        self.runs += 1;
+        if let Some(usage) = usage {
+            self.input_tokens += usage.input_tokens;
+            self.output_tokens += usage.output_tokens;
+            self.cache_read_input_tokens += usage.cache_read_input_tokens;
+            self.cache_creation_input_tokens += usage.cache_creation_input_tokens;
+            match cost {
+                Some(cost) => self.cost_usd += cost,
+                None => self.unpriced_runs += 1,
+            }
+        }
+    }
+}
+
+/// Cost summary response
+#[derive(Debug, Clone, Serialize)]
+pub struct CostSummary {
+    /// Buckets sorted by cost (highest first), or by day for day grouping
+    pub buckets: Vec<CostBucket>,
+    pub total: CostBucket,
+}
+
+pub(super) fn backend_key(backend: &Backend) -> String {
+    serde_json::to_value(backend)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+/// Aggregate run costs across all sessions.
+/// Runs keep the cost computed at completion; `reprice` recomputes every run
+/// with the current pricing table (runs without a stored cost are always priced).
+#[tauri::command]
+pub async fn get_cost_summary(
+    app: AppHandle,
+    group_by: CostGroupBy,
+    filters: Option<CostFilters>,
+    reprice: Option<bool>,
+) -> Result<CostSummary, String> {
+    let filters = filters.unwrap_or_default();
+    let reprice = reprice.unwrap_or(false);
+    let pricing = load_pricing_table(&app);
+    log::trace!("Computing cost summary grouped by {group_by:?}");
+
+    let projects = crate::projects::storage::load_projects_data(&app).ok();
+    let worktree_info: HashMap<String, (String, String)> = projects
+        .as_ref()
+        .map(|data| {
+            data.worktrees
+                .iter()
+                .map(|w| (w.id.clone(), (w.name.clone(), w.project_id.clone())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let project_names: HashMap<String, String> = projects
+        .as_ref()
+        .map(|data| {
+            data.projects
+                .iter()
+                .map(|p| (p.id.clone(), p.name.clone()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut buckets: HashMap<String, CostBucket> = HashMap::new();
+    let mut total = CostBucket {
+        key: "total".to_string(),
+        ..Default::default()
+    };
+
+    let session_ids = match filters.session_id {
+        Some(ref id) => vec![id.clone()],
+        None => list_all_session_ids(&app)?,
+    };
+    for session_id in session_ids {
+        let Some(metadata) = load_metadata(&app, &session_id)? else {
+            continue;
+        };
+        let worktree = worktree_info.get(&metadata.worktree_id);
+        let project_id = worktree.map(|(_, p)| p.clone());
+        if filters
+            .worktree_id
+            .as_ref()
+            .is_some_and(|w| *w != metadata.worktree_id)
+            || filters
+                .project_id
+                .as_ref()
+                .is_some_and(|p| project_id.as_ref() != Some(p))
+        {
+            continue;
+        }
+
+        for run in &metadata.runs {
+            let backend = run.backend.as_ref().unwrap_or(&metadata.backend);
+            if filters.backend.as_ref().is_some_and(|b| b != backend)
+                || filters.from.is_some_and(|from| run.started_at < from)
+                || filters.to.is_some_and(|to| run.started_at > to)
+            {
+                continue;
+            }
+
+            let cost = match run.cost_usd {
+                Some(cost) if !reprice => Some(cost),
+                _ => pricing.run_cost(run, &metadata.backend),
+            };
+
+            let (key, label) = match group_by {
+                CostGroupBy::Session => (metadata.id.clone(), Some(metadata.name.clone())),
+                CostGroupBy::Worktree => (
+                    metadata.worktree_id.clone(),
+                    worktree.map(|(name, _)| name.clone()),
+                ),
+                CostGroupBy::Project => {
+                    let key = project_id.clone().unwrap_or_else(|| "unknown".to_string());
+                    let label = project_names.get(&key).cloned();
+                    (key, label)
+                }
+                CostGroupBy::Backend => (backend_key(backend), None),
+                CostGroupBy::Model => (
+                    run.model.clone().unwrap_or_else(|| "default".to_string()),
+                    run.provider.clone(),
+                ),
+                CostGroupBy::Day => (utc_day(run.started_at), None),
+            };
+
+            let bucket = buckets.entry(key.clone()).or_insert_with(|| CostBucket {
+                key,
+                label,
+                ..Default::default()
+            });
+            bucket.add(run.usage.as_ref(), cost);
+            total.add(run.usage.as_ref(), cost);
+        }
+    }
+
+    let mut buckets: Vec<CostBucket> = buckets.into_values().collect();
+    if group_by == CostGroupBy::Day {
+        buckets.sort_by(|a, b| a.key.cmp(&b.key));
+    } else {
+        buckets.sort_by(|a, b| {
+            b.cost_usd
+                .partial_cmp(&a.cost_usd)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.key.cmp(&b.key))
+        });
+    }
+
+    Ok(CostSummary { buckets, total })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::RunStatus;
+
+    fn usage(input: u64, output: u64, cache_read: u64, cache_write: u64) -> UsageData {
+        UsageData {
+            input_tokens: input,
+            output_tokens: output,
+            cache_read_input_tokens: cache_read,
+            cache_creation_input_tokens: cache_write,
+        }
+    }
+
+    fn run(model: Option<&str>, provider: Option<&str>, usage: Option<UsageData>) -> RunEntry {
+        RunEntry {
+            model: model.map(|s| s.to_string()),
+            usage,
+            provider: provider.map(|s| s.to_string()),
+            ..RunEntry::test("run-1", RunStatus::Completed)
+        }
+    }
+
+    #[test]
+    fn test_rate_for_prefers_most_specific_model() {
+        let table = PricingTable::with_overrides(vec![]);
+        assert_eq!(
+            table
+                .rate_for(Some("gpt-5.1-codex-mini"), None)
+                .unwrap()
+                .model,
+            "gpt-5.1-codex-mini"
+        );
+        assert_eq!(
+            table.rate_for(Some("gpt-5.2-codex"), None).unwrap().model,
+            "gpt-5.2"
+        );
+        assert_eq!(
+            table.rate_for(Some("claude-opus-4-6"), None).unwrap().model,
+            "opus"
+        );
+        assert!(table.rate_for(Some("mystery-model"), None).is_none());
+        assert!(table.rate_for(None, None).is_none());
+    }
+
+    #[test]
+    fn test_rate_for_overrides_and_providers() {
+        let mut openrouter = ModelRate::new("*", 2.0, 8.0, 0.0, 0.0);
+        openrouter.provider = Some("OpenRouter".to_string());
+        let table = PricingTable::with_overrides(vec![
+            ModelRate::new("sonnet", 2.5, 12.0, 0.25, 3.0),
+            openrouter,
+        ]);
+
+        assert_eq!(
+            table.rate_for(Some("sonnet"), None).unwrap().input_per_mtok,
+            2.5
+        );
+        assert_eq!(
+            table
+                .rate_for(Some("opus"), Some("OpenRouter"))
+                .unwrap()
+                .input_per_mtok,
+            2.0
+        );
+        // Generic overrides still apply to providers; built-in prices don't
+        assert_eq!(
+            table
+                .rate_for(Some("sonnet"), Some("MiniMax"))
+                .unwrap()
+                .input_per_mtok,
+            2.5
+        );
+        assert!(table.rate_for(Some("opus"), Some("MiniMax")).is_none());
+    }
+
+    #[test]
+    fn test_usage_cost_claude_vs_codex_cache_accounting() {
+        let rate = ModelRate::new("x", 1.0, 10.0, 0.1, 2.0);
+        let u = usage(1_000_000, 100_000, 500_000, 0);
+        // Claude: input excludes cache reads
+        let claude = usage_cost(&u, &rate, &Backend::Claude);
+        assert!((claude - (1.0 + 1.0 + 0.05)).abs() < 1e-9);
+        // Codex: cached tokens are part of input_tokens
+        let codex = usage_cost(&u, &rate, &Backend::Codex);
+        assert!((codex - (0.5 + 1.0 + 0.05)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_run_cost() {
+        let table = PricingTable::with_overrides(vec![]);
+        let priced = run(Some("sonnet"), None, Some(usage(1_000_000, 0, 0, 0)));
+        assert_eq!(table.run_cost(&priced, &Backend::Claude), Some(3.0));
+        let no_usage = run(Some("sonnet"), None, None);
+        assert_eq!(table.run_cost(&no_usage, &Backend::Claude), None);
+    }
+
+    #[test]
+    fn test_bucket_counts_unpriced_runs() {
+        let mut bucket = CostBucket::default();
+        bucket.add(Some(&usage(10, 5, 0, 0)), Some(0.5));
+        bucket.add(Some(&usage(10, 5, 0, 0)), None);
+        bucket.add(None, None);
+        assert_eq!(bucket.runs, 3);
+        assert_eq!(bucket.unpriced_runs, 1);
+        assert_eq!(bucket.input_tokens, 20);
+        assert_eq!(bucket.cost_usd, 0.5);
+    }
+}
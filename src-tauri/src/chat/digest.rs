@@ -0,0 +1,387 @@
+//! Automatic digest refresh, digest history and project rollups
+//!
+//! `generate_session_digest` makes a digest on demand (the recap shown for
+//! sessions that finished out of focus). When a run completes, the digest is
+//! also regenerated in the background once `refresh_after_messages` messages
+//! have arrived since the current one (`SessionDigest.message_count`), with
+//! the model configured in `app-data/digest.json` (the session recap model by
+//! default). Refreshed digests are broadcast as `chat:digest_updated`.
+//!
+//! A replaced digest moves to `SessionMetadata.digest_history`, so a
+//! long-running session keeps a timeline of what it was doing.
+//! `get_project_digest_rollup` collects the latest digest of every active
+//! session of a project in one view.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::storage::{list_all_session_ids, load_metadata, with_metadata_mut};
+use super::types::{RunStatus, SessionDigest, SessionMetadata};
+use crate::http_server::EmitExt;
+
+/// Sessions with a background digest refresh in flight
+static REFRESHING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Digest refresh configuration (app-data/digest.json)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DigestSettings {
+    /// Regenerate digests in the background as sessions progress
+    #[serde(default = "default_auto_refresh")]
+    pub auto_refresh: bool,
+    /// New messages since the current digest that trigger a refresh
+    #[serde(default = "default_refresh_after_messages")]
+    pub refresh_after_messages: usize,
+    /// Model for background refreshes (unset = session recap model)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Custom CLI profile for `model`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Earlier digests kept per session
+    #[serde(default = "default_history_limit")]
+    pub history_limit: usize,
+}
+
+fn default_auto_refresh() -> bool {
+    true
+}
+
+fn default_refresh_after_messages() -> usize {
+    10
+}
+
+fn default_history_limit() -> usize {
+    50
+}
+
+impl Default for DigestSettings {
+    fn default() -> Self {
+        Self {
+            auto_refresh: default_auto_refresh(),
+            refresh_after_messages: default_refresh_after_messages(),
+            model: None,
+            provider: None,
+            history_limit: default_history_limit(),
+        }
+    }
+}
+
+fn get_digest_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join("digest.json"))
+}
+
+fn load_digest_settings(app: &AppHandle) -> DigestSettings {
+    get_digest_settings_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Get the digest refresh configuration
+#[tauri::command]
+pub async fn get_digest_settings(app: AppHandle) -> Result<DigestSettings, String> {
+    Ok(load_digest_settings(&app))
+}
+
+/// Replace the digest refresh configuration
+#[tauri::command]
+pub async fn set_digest_settings(app: AppHandle, settings: DigestSettings) -> Result<(), String> {
+    log::trace!("Saving digest settings");
+
+    if settings.refresh_after_messages < 2 {
+        return Err("Digests can refresh after 2 messages at the earliest".to_string());
+    }
+    if settings.history_limit == 0 {
+        return Err("Digest history must keep at least 1 digest".to_string());
+    }
+
+    let path = get_digest_settings_path(&app)?;
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize digest settings: {e}"))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write digest settings: {e}"))?;
+    fs::rename(&temp_path, &path)
+        .map_err(|e| format!("Failed to finalize digest settings: {e}"))?;
+    Ok(())
+}
+
+// ============================================================================
+// History
+// ============================================================================
+
+/// Make `digest` the session's current digest, moving the one it replaces into
+/// the history. A digest of the same message count replaces it outright.
+fn push_digest(metadata: &mut SessionMetadata, digest: SessionDigest, limit: usize) {
+    let same_point = metadata
+        .digest
+        .as_ref()
+        .is_some_and(|current| current.message_count == digest.message_count);
+    if let Some(previous) = metadata.digest.replace(digest) {
+        if !same_point {
+            metadata.digest_history.push(previous);
+        }
+    }
+    let excess = metadata.digest_history.len().saturating_sub(limit);
+    metadata.digest_history.drain(..excess);
+}
+
+/// Persist a new digest for a session, keeping the previous one in its history
+pub(super) fn record_digest(
+    app: &AppHandle,
+    session_id: &str,
+    digest: SessionDigest,
+) -> Result<(), String> {
+    let limit = load_digest_settings(app).history_limit;
+    let existing =
+        load_metadata(app, session_id)?.ok_or_else(|| format!("Session {session_id} not found"))?;
+    with_metadata_mut(
+        app,
+        session_id,
+        &existing.worktree_id,
+        &existing.name,
+        existing.order,
+        |metadata| {
+            push_digest(metadata, digest, limit);
+            Ok(())
+        },
+    )
+}
+
+/// A session's digests, oldest first, ending with the current one
+#[tauri::command]
+pub async fn get_digest_history(
+    app: AppHandle,
+    session_id: String,
+) -> Result<Vec<SessionDigest>, String> {
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session {session_id} not found"))?;
+    let mut history = metadata.digest_history;
+    history.extend(metadata.digest);
+    Ok(history)
+}
+
+// ============================================================================
+// Background refresh
+// ============================================================================
+
+/// Messages since the session's current digest (two per run: request and
+/// reply), or all of them when it has none
+fn messages_since_digest(metadata: &SessionMetadata) -> usize {
+    let digested = metadata
+        .digest
+        .as_ref()
+        .and_then(|d| d.message_count)
+        .unwrap_or(0);
+    (metadata.runs.len() * 2).saturating_sub(digested)
+}
+
+/// Payload for chat:digest_updated
+#[derive(Debug, Clone, Serialize)]
+struct DigestUpdatedEvent {
+    session_id: String,
+    worktree_id: String,
+    digest: SessionDigest,
+}
+
+async fn refresh_digest(
+    app: &AppHandle,
+    metadata: &SessionMetadata,
+    settings: &DigestSettings,
+) -> Result<(), String> {
+    let model = settings
+        .model
+        .as_deref()
+        .map(|model| (model, settings.provider.as_deref()));
+    let digest = super::commands::build_session_digest(app, &metadata.id, model).await?;
+    record_digest(app, &metadata.id, digest.clone())?;
+
+    let event = DigestUpdatedEvent {
+        session_id: metadata.id.clone(),
+        worktree_id: metadata.worktree_id.clone(),
+        digest,
+    };
+    app.emit_all("chat:digest_updated", &event)
+        .map_err(|e| format!("Failed to emit digest_updated: {e}"))
+}
+
+/// Regenerate a session's digest in the background if enough messages
+/// arrived since the current one. Called when a run completes.
+pub(super) fn schedule_digest_refresh(app: &AppHandle, session_id: &str) {
+    let settings = load_digest_settings(app);
+    if !settings.auto_refresh {
+        return;
+    }
+    let Ok(Some(metadata)) = load_metadata(app, session_id) else {
+        return;
+    };
+    if metadata.archived_at.is_some()
+        || messages_since_digest(&metadata) < settings.refresh_after_messages
+    {
+        return;
+    }
+    if !REFRESHING.lock().unwrap().insert(session_id.to_string()) {
+        return;
+    }
+
+    log::trace!("Refreshing digest of session {session_id}");
+    let app = app.clone();
+    // The digest call blocks on a one-shot CLI process; keep it off the runtime
+    std::thread::spawn(move || {
+        let result = tauri::async_runtime::block_on(refresh_digest(&app, &metadata, &settings));
+        REFRESHING.lock().unwrap().remove(&metadata.id);
+        if let Err(e) = result {
+            log::warn!("Failed to refresh digest of session {}: {e}", metadata.id);
+        }
+    });
+}
+
+// ============================================================================
+// Project rollup
+// ============================================================================
+
+/// Latest digest of one session in a project rollup
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDigestSummary {
+    pub session_id: String,
+    pub session_name: String,
+    pub worktree_id: String,
+    pub worktree_name: String,
+    /// None until the session's first digest
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<SessionDigest>,
+    /// Messages the digest doesn't cover yet
+    pub messages_since_digest: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run_status: Option<RunStatus>,
+    /// Unix seconds of the last run's end (or start), or session creation
+    pub last_activity_at: u64,
+}
+
+/// Digests of all active sessions of a project
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectDigestRollup {
+    pub project_id: String,
+    pub project_name: String,
+    /// Most recently active first
+    pub sessions: Vec<SessionDigestSummary>,
+}
+
+fn last_activity_at(metadata: &SessionMetadata) -> u64 {
+    metadata.runs.last().map_or(metadata.created_at, |run| {
+        run.ended_at.unwrap_or(run.started_at)
+    })
+}
+
+/// Collect the latest digest of every active (unarchived, with at least one
+/// run) session across a project's worktrees
+#[tauri::command]
+pub async fn get_project_digest_rollup(
+    app: AppHandle,
+    project_id: String,
+) -> Result<ProjectDigestRollup, String> {
+    log::trace!("Building digest rollup for project {project_id}");
+
+    let projects = crate::projects::storage::load_projects_data(&app)?;
+    let project = projects
+        .find_project(&project_id)
+        .ok_or_else(|| format!("Project {project_id} not found"))?;
+
+    let mut sessions = Vec::new();
+    for session_id in list_all_session_ids(&app)? {
+        let Some(metadata) = load_metadata(&app, &session_id)? else {
+            continue;
+        };
+        let Some(worktree) = projects
+            .find_worktree(&metadata.worktree_id)
+            .filter(|w| w.project_id == project_id)
+        else {
+            continue;
+        };
+        if metadata.archived_at.is_some() || metadata.runs.is_empty() {
+            continue;
+        }
+
+        sessions.push(SessionDigestSummary {
+            messages_since_digest: messages_since_digest(&metadata),
+            last_run_status: metadata.runs.last().map(|run| run.status.clone()),
+            last_activity_at: last_activity_at(&metadata),
+            session_id: metadata.id,
+            session_name: metadata.name,
+            worktree_id: metadata.worktree_id,
+            worktree_name: worktree.name.clone(),
+            digest: metadata.digest,
+        });
+    }
+    sessions.sort_by(|a, b| b.last_activity_at.cmp(&a.last_activity_at));
+
+    Ok(ProjectDigestRollup {
+        project_id,
+        project_name: project.name.clone(),
+        sessions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::RunEntry;
+
+    fn digest(summary: &str, message_count: usize) -> SessionDigest {
+        SessionDigest {
+            chat_summary: summary.to_string(),
+            last_action: String::new(),
+            created_at: None,
+            message_count: Some(message_count),
+        }
+    }
+
+    fn run() -> RunEntry {
+        RunEntry::test("run", RunStatus::Completed)
+    }
+
+    #[test]
+    fn test_push_digest_keeps_history() {
+        let mut metadata =
+            SessionMetadata::new("s".to_string(), "w".to_string(), "Session 1".to_string(), 0);
+        push_digest(&mut metadata, digest("first", 4), 2);
+        push_digest(&mut metadata, digest("second", 14), 2);
+        // Regenerated at the same point: replaces the current digest
+        push_digest(&mut metadata, digest("second again", 14), 2);
+        push_digest(&mut metadata, digest("third", 24), 2);
+        push_digest(&mut metadata, digest("fourth", 34), 2);
+
+        let history: Vec<&str> = metadata
+            .digest_history
+            .iter()
+            .map(|d| d.chat_summary.as_str())
+            .collect();
+        assert_eq!(history, vec!["second again", "third"]);
+        assert_eq!(metadata.digest.unwrap().chat_summary, "fourth");
+    }
+
+    #[test]
+    fn test_messages_since_digest() {
+        let mut metadata =
+            SessionMetadata::new("s".to_string(), "w".to_string(), "Session 1".to_string(), 0);
+        metadata.runs = vec![run(); 6];
+        assert_eq!(messages_since_digest(&metadata), 12);
+
+        metadata.digest = Some(digest("summary", 8));
+        assert_eq!(messages_since_digest(&metadata), 4);
+    }
+}
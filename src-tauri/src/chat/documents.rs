@@ -0,0 +1,871 @@
+//! Document attachments (PDF, DOCX, spreadsheets)
+//!
+//! A dropped document is copied to `pasted-documents/` and its text is
+//! extracted locally into `pasted-texts/`, so it is attached with the same
+//! `[Text file attached: ...]` marker as a pasted text file and every backend
+//! can read it. The text is split into pages (sheets for spreadsheets) and an
+//! optional page range limits what is extracted.
+//!
+//! - PDF: text via `pdf-extract`; page images are rendered with poppler's
+//!   `pdftoppm` when it is installed and the session's backend can look at
+//!   images
+//! - DOCX: `word/document.xml`, split at rendered or explicit page breaks
+//! - XLSX: one tab-separated section per sheet; CSV/TSV are attached as-is
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use super::backend::{backend_for, parse_backend};
+use super::commands::{process_image, save_image_to_disk};
+use super::storage::{get_documents_dir, get_images_dir, get_pastes_dir};
+use super::time::now;
+use super::types::{SaveDocumentResponse, SaveImageResponse};
+
+/// Maximum size of a dropped document (50MB)
+const MAX_DOCUMENT_SIZE: u64 = 50 * 1024 * 1024;
+/// Maximum size of the extracted text (same as a pasted text file)
+const MAX_EXTRACT_SIZE: usize = 10 * 1024 * 1024;
+/// Page images rendered per PDF (the first pages of the selection)
+const MAX_PAGE_IMAGES: usize = 10;
+/// Resolution of rendered page images
+const PAGE_IMAGE_DPI: u32 = 110;
+
+/// Extensions accepted by `save_dropped_document`
+const ALLOWED_EXTENSIONS: [&str; 5] = ["pdf", "docx", "xlsx", "csv", "tsv"];
+
+static DOCX_TOKEN_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r#"<w:t(?:\s[^>]*)?>([^<]*)</w:t>|<w:tab/>|</w:tc>|</w:p>|<w:lastRenderedPageBreak/>|<w:br\b[^>]*w:type="page"[^>]*/>"#,
+    )
+    .expect("Invalid regex")
+});
+static XLSX_SHEET_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<sheet\b([^>]*)/?>").expect("Invalid regex"));
+static XLSX_REL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<Relationship\b([^>]*)/?>").expect("Invalid regex"));
+static XLSX_SI_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<si>(.*?)</si>").expect("Invalid regex"));
+static XLSX_ROW_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<row\b[^>]*?(?:/>|>(.*?)</row>)").expect("Invalid regex"));
+static XLSX_CELL_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?s)<c\b([^>]*?)(?:/>|>(.*?)</c>)").expect("Invalid regex"));
+static XML_TEXT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<t(?:\s[^>]*)?>([^<]*)</t>").expect("Invalid regex"));
+static XML_VALUE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<v>([^<]*)</v>").expect("Invalid regex"));
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DocumentKind {
+    Pdf,
+    Docx,
+    Xlsx,
+    /// CSV/TSV: already text
+    Delimited,
+}
+
+impl DocumentKind {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "pdf" => Some(Self::Pdf),
+            "docx" => Some(Self::Docx),
+            "xlsx" => Some(Self::Xlsx),
+            "csv" | "tsv" => Some(Self::Delimited),
+            _ => None,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Pdf => "PDF",
+            Self::Docx => "Word document",
+            Self::Xlsx => "Spreadsheet",
+            Self::Delimited => "Delimited text",
+        }
+    }
+
+    /// What a "page" is for this kind of document
+    fn unit(self) -> &'static str {
+        match self {
+            Self::Xlsx => "Sheet",
+            _ => "Page",
+        }
+    }
+}
+
+// ============================================================================
+// Page ranges
+// ============================================================================
+
+/// Inclusive 1-based page range; `end` None runs to the last page
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageRange {
+    start: u32,
+    end: Option<u32>,
+}
+
+/// Parse a page selection like "1-3, 7, 10-"
+fn parse_page_ranges(spec: &str) -> Result<Vec<PageRange>, String> {
+    let parse_page = |s: &str| -> Result<u32, String> {
+        match s.trim().parse::<u32>() {
+            Ok(page) if page > 0 => Ok(page),
+            _ => Err(format!("Invalid page number in \"{spec}\": {s}")),
+        }
+    };
+
+    let mut ranges = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let range = match part.split_once('-') {
+            Some((start, end)) => PageRange {
+                start: parse_page(start)?,
+                end: if end.trim().is_empty() {
+                    None
+                } else {
+                    Some(parse_page(end)?)
+                },
+            },
+            None => {
+                let page = parse_page(part)?;
+                PageRange {
+                    start: page,
+                    end: Some(page),
+                }
+            }
+        };
+        if range.end.is_some_and(|end| end < range.start) {
+            return Err(format!("Invalid page range: {part}"));
+        }
+        ranges.push(range);
+    }
+    if ranges.is_empty() {
+        return Err("Page range is empty".to_string());
+    }
+    Ok(ranges)
+}
+
+/// Pages of a `count`-page document selected by `ranges` (all when None), in order
+fn select_pages(ranges: Option<&[PageRange]>, count: u32) -> Vec<u32> {
+    let Some(ranges) = ranges else {
+        return (1..=count).collect();
+    };
+    let mut pages: Vec<u32> = ranges
+        .iter()
+        .flat_map(|r| r.start..=r.end.unwrap_or(count).min(count))
+        .collect();
+    pages.sort_unstable();
+    pages.dedup();
+    pages
+}
+
+/// Group sorted pages into contiguous (first, last) runs
+fn page_runs(pages: &[u32]) -> Vec<(u32, u32)> {
+    let mut runs: Vec<(u32, u32)> = Vec::new();
+    for &page in pages {
+        match runs.last_mut() {
+            Some((_, last)) if *last + 1 == page => *last = page,
+            _ => runs.push((page, page)),
+        }
+    }
+    runs
+}
+
+/// Describe sorted pages compactly ("1-3, 7")
+fn describe_pages(pages: &[u32]) -> String {
+    page_runs(pages)
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{first}-{last}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// ============================================================================
+// Extraction
+// ============================================================================
+
+/// One page (or sheet) of extracted text
+#[derive(Debug)]
+struct Section {
+    /// 1-based page number
+    page: u32,
+    /// Sheet name, for spreadsheets
+    title: Option<String>,
+    text: String,
+}
+
+/// Text extracted from a document
+#[derive(Debug)]
+struct Extracted {
+    /// Pages in the whole document (None for documents without pages)
+    page_count: Option<u32>,
+    sections: Vec<Section>,
+}
+
+impl Extracted {
+    fn pages(&self) -> Vec<u32> {
+        self.sections.iter().map(|s| s.page).collect()
+    }
+}
+
+fn decode_xml_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(idx) = rest.find('&') {
+        out.push_str(&rest[..idx]);
+        rest = &rest[idx..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Value of an XML attribute in a tag's attribute string
+fn xml_attr<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let needle = format!("{name}=\"");
+    let start = attrs
+        .match_indices(&needle)
+        .find(|(idx, _)| *idx == 0 || attrs.as_bytes()[idx - 1].is_ascii_whitespace())
+        .map(|(idx, _)| idx + needle.len())?;
+    let len = attrs[start..].find('"')?;
+    Some(&attrs[start..start + len])
+}
+
+/// Read a text entry of an archive. Entries that decompress past
+/// `MAX_EXTRACT_SIZE` are rejected; the declared size can lie, so the read
+/// itself is capped too.
+fn read_zip_entry<R: std::io::Read + std::io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<Option<String>, String> {
+    let entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(format!("Failed to read {name}: {e}")),
+    };
+    let too_large = || format!("Failed to read {name}: larger than 10MB uncompressed");
+    if entry.size() > MAX_EXTRACT_SIZE as u64 {
+        return Err(too_large());
+    }
+    let mut content = String::new();
+    entry
+        .take(MAX_EXTRACT_SIZE as u64 + 1)
+        .read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read {name}: {e}"))?;
+    if content.len() > MAX_EXTRACT_SIZE {
+        return Err(too_large());
+    }
+    Ok(Some(content))
+}
+
+fn open_zip(path: &Path) -> Result<zip::ZipArchive<fs::File>, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open document: {e}"))?;
+    zip::ZipArchive::new(file).map_err(|e| format!("Failed to read document archive: {e}"))
+}
+
+/// Split a DOCX body into pages of text. Rendered page breaks (saved by Word)
+/// are preferred; explicit page breaks are used when there are none.
+fn docx_pages(document_xml: &str) -> Vec<String> {
+    let rendered_breaks = document_xml.contains("<w:lastRenderedPageBreak/>");
+    let mut pages = vec![String::new()];
+    for cap in DOCX_TOKEN_RE.captures_iter(document_xml) {
+        let current = pages.last_mut().expect("pages is never empty");
+        if let Some(text) = cap.get(1) {
+            current.push_str(&decode_xml_entities(text.as_str()));
+            continue;
+        }
+        match &cap[0] {
+            "<w:tab/>" | "</w:tc>" => current.push('\t'),
+            "</w:p>" => current.push('\n'),
+            "<w:lastRenderedPageBreak/>" if rendered_breaks => pages.push(String::new()),
+            "<w:lastRenderedPageBreak/>" => {}
+            _ if !rendered_breaks => pages.push(String::new()),
+            _ => {}
+        }
+    }
+    pages
+        .iter()
+        .map(|page| page.trim_end().to_string())
+        .collect()
+}
+
+fn extract_docx(path: &Path, ranges: Option<&[PageRange]>) -> Result<Extracted, String> {
+    let mut archive = open_zip(path)?;
+    let document = read_zip_entry(&mut archive, "word/document.xml")?
+        .ok_or_else(|| "Not a Word document (word/document.xml is missing)".to_string())?;
+    let pages = docx_pages(&document);
+    let count = pages.len() as u32;
+    let selected = select_pages(ranges, count);
+    let sections = selected
+        .into_iter()
+        .map(|page| Section {
+            page,
+            title: None,
+            text: pages[page as usize - 1].clone(),
+        })
+        .collect();
+    Ok(Extracted {
+        page_count: Some(count),
+        sections,
+    })
+}
+
+/// Zero-based column index of a cell reference ("B2" -> 1, "AA10" -> 26)
+fn column_index(cell_ref: &str) -> Option<usize> {
+    let letters: Vec<u8> = cell_ref
+        .bytes()
+        .take_while(|b| b.is_ascii_alphabetic())
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    let index = letters.iter().fold(0usize, |acc, b| {
+        acc * 26 + (b.to_ascii_uppercase() - b'A') as usize + 1
+    });
+    Some(index - 1)
+}
+
+fn xlsx_shared_strings(xml: &str) -> Vec<String> {
+    XLSX_SI_RE
+        .captures_iter(xml)
+        .map(|si| {
+            XML_TEXT_RE
+                .captures_iter(&si[1])
+                .map(|t| decode_xml_entities(&t[1]))
+                .collect::<String>()
+        })
+        .collect()
+}
+
+/// Render a worksheet as tab-separated rows (empty rows are skipped)
+fn xlsx_sheet_text(xml: &str, shared: &[String]) -> String {
+    let mut lines = Vec::new();
+    for row in XLSX_ROW_RE.captures_iter(xml) {
+        let Some(row_xml) = row.get(1) else {
+            continue;
+        };
+        let mut cells: Vec<String> = Vec::new();
+        for cell in XLSX_CELL_RE.captures_iter(row_xml.as_str()) {
+            let attrs = &cell[1];
+            let body = cell.get(2).map_or("", |m| m.as_str());
+            let raw = XML_VALUE_RE
+                .captures(body)
+                .map(|v| decode_xml_entities(&v[1]))
+                .unwrap_or_default();
+            let value = match xml_attr(attrs, "t") {
+                Some("s") => raw
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| shared.get(idx).cloned())
+                    .unwrap_or_default(),
+                Some("inlineStr") => XML_TEXT_RE
+                    .captures_iter(body)
+                    .map(|t| decode_xml_entities(&t[1]))
+                    .collect(),
+                Some("b") => match raw.as_str() {
+                    "1" => "TRUE".to_string(),
+                    _ => "FALSE".to_string(),
+                },
+                _ => raw,
+            };
+            let column = xml_attr(attrs, "r")
+                .and_then(column_index)
+                .unwrap_or(cells.len());
+            if cells.len() <= column {
+                cells.resize(column + 1, String::new());
+            }
+            cells[column] = value.replace(['\t', '\n'], " ");
+        }
+        if cells.iter().any(|c| !c.is_empty()) {
+            lines.push(cells.join("\t").trim_end().to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+fn extract_xlsx(path: &Path, ranges: Option<&[PageRange]>) -> Result<Extracted, String> {
+    let mut archive = open_zip(path)?;
+    let workbook = read_zip_entry(&mut archive, "xl/workbook.xml")?
+        .ok_or_else(|| "Not a spreadsheet (xl/workbook.xml is missing)".to_string())?;
+    let rels = read_zip_entry(&mut archive, "xl/_rels/workbook.xml.rels")?.unwrap_or_default();
+    let shared = read_zip_entry(&mut archive, "xl/sharedStrings.xml")?
+        .map(|xml| xlsx_shared_strings(&xml))
+        .unwrap_or_default();
+
+    let targets: HashMap<&str, String> = XLSX_REL_RE
+        .captures_iter(&rels)
+        .filter_map(|rel| {
+            let attrs = rel.get(1)?.as_str();
+            let target = xml_attr(attrs, "Target")?;
+            let target = match target.strip_prefix('/') {
+                Some(absolute) => absolute.to_string(),
+                None => format!("xl/{target}"),
+            };
+            Some((xml_attr(attrs, "Id")?, target))
+        })
+        .collect();
+    let sheets: Vec<(String, String)> = XLSX_SHEET_RE
+        .captures_iter(&workbook)
+        .enumerate()
+        .filter_map(|(idx, sheet)| {
+            let attrs = sheet.get(1)?.as_str();
+            let name = decode_xml_entities(xml_attr(attrs, "name").unwrap_or("Sheet"));
+            let target = xml_attr(attrs, "r:id")
+                .and_then(|id| targets.get(id).cloned())
+                .unwrap_or_else(|| format!("xl/worksheets/sheet{}.xml", idx + 1));
+            Some((name, target))
+        })
+        .collect();
+
+    let count = sheets.len() as u32;
+    let mut sections = Vec::new();
+    for page in select_pages(ranges, count) {
+        let (name, target) = &sheets[page as usize - 1];
+        let text = read_zip_entry(&mut archive, target)?
+            .map(|xml| xlsx_sheet_text(&xml, &shared))
+            .unwrap_or_default();
+        sections.push(Section {
+            page,
+            title: Some(name.clone()),
+            text,
+        });
+    }
+    Ok(Extracted {
+        page_count: Some(count),
+        sections,
+    })
+}
+
+fn extract_pdf(path: &Path, ranges: Option<&[PageRange]>) -> Result<Extracted, String> {
+    // pdf-extract panics on some malformed files instead of returning an error
+    let pages = std::panic::catch_unwind(|| pdf_extract::extract_text_by_pages(path))
+        .map_err(|_| "Failed to read PDF: the file could not be parsed".to_string())?
+        .map_err(|e| format!("Failed to read PDF: {e}"))?;
+    let count = pages.len() as u32;
+    let sections = select_pages(ranges, count)
+        .into_iter()
+        .map(|page| Section {
+            page,
+            title: None,
+            text: pages[page as usize - 1].trim_end().to_string(),
+        })
+        .collect();
+    Ok(Extracted {
+        page_count: Some(count),
+        sections,
+    })
+}
+
+fn extract(
+    kind: DocumentKind,
+    path: &Path,
+    ranges: Option<&[PageRange]>,
+) -> Result<Extracted, String> {
+    match kind {
+        DocumentKind::Pdf => extract_pdf(path, ranges),
+        DocumentKind::Docx => extract_docx(path, ranges),
+        DocumentKind::Xlsx => extract_xlsx(path, ranges),
+        DocumentKind::Delimited => {
+            let bytes = fs::read(path).map_err(|e| format!("Failed to read document: {e}"))?;
+            Ok(Extracted {
+                page_count: None,
+                sections: vec![Section {
+                    page: 1,
+                    title: None,
+                    text: String::from_utf8_lossy(&bytes).to_string(),
+                }],
+            })
+        }
+    }
+}
+
+/// Format extracted text for the agent: a header naming the document and the
+/// extracted pages, then one section per page
+fn format_extract(
+    filename: &str,
+    kind: DocumentKind,
+    original_path: &str,
+    extracted: &Extracted,
+) -> String {
+    let pages = extracted.pages();
+    let scope = match extracted.page_count {
+        Some(count) => format!(
+            ", {} {} of {count}",
+            kind.unit().to_lowercase() + "s",
+            describe_pages(&pages)
+        ),
+        None => String::new(),
+    };
+    let mut out = format!(
+        "Document: {filename} ({}{scope})\nOriginal file: {original_path}\n",
+        kind.label()
+    );
+    for section in &extracted.sections {
+        if extracted.page_count.is_some() {
+            let title = section
+                .title
+                .as_deref()
+                .map(|t| format!(": {t}"))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "\n--- {} {}{title} ---\n",
+                kind.unit(),
+                section.page
+            ));
+        } else {
+            out.push('\n');
+        }
+        if section.text.trim().is_empty() {
+            out.push_str("(no text)\n");
+        } else {
+            out.push_str(&section.text);
+            out.push('\n');
+        }
+    }
+
+    if out.len() > MAX_EXTRACT_SIZE {
+        let mut end = MAX_EXTRACT_SIZE;
+        while !out.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.truncate(end);
+        out.push_str("\n[... truncated]\n");
+    }
+    out
+}
+
+/// Run poppler's `pdftoppm`
+fn run_pdftoppm(args: &[&OsStr]) -> Result<(), String> {
+    let output = crate::platform::silent_command("pdftoppm")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run pdftoppm: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "pdftoppm failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Render PDF pages to images for vision-capable backends. Needs poppler's
+/// `pdftoppm`, which isn't bundled; without it, or on failure, pages are
+/// skipped and only the extracted text is attached.
+fn render_pdf_pages(path: &Path, pages: &[u32], images_dir: &Path) -> Vec<SaveImageResponse> {
+    if !crate::platform::executable_exists("pdftoppm") {
+        log::debug!("pdftoppm not found, skipping PDF page images");
+        return Vec::new();
+    }
+    let temp_dir = std::env::temp_dir().join(format!("jean-pdf-{}", Uuid::new_v4()));
+    if let Err(e) = fs::create_dir_all(&temp_dir) {
+        log::warn!("Failed to create temp dir for PDF pages: {e}");
+        return Vec::new();
+    }
+
+    let mut images = Vec::new();
+    for &page in pages.iter().take(MAX_PAGE_IMAGES) {
+        let prefix = temp_dir.join(format!("page-{page}"));
+        let (page_arg, dpi_arg) = (page.to_string(), PAGE_IMAGE_DPI.to_string());
+        let rendered = run_pdftoppm(&[
+            OsStr::new("-png"),
+            OsStr::new("-r"),
+            OsStr::new(&dpi_arg),
+            OsStr::new("-f"),
+            OsStr::new(&page_arg),
+            OsStr::new("-l"),
+            OsStr::new(&page_arg),
+            OsStr::new("-singlefile"),
+            path.as_os_str(),
+            prefix.as_os_str(),
+        ])
+        .and_then(|_| {
+            fs::read(prefix.with_extension("png"))
+                .map_err(|e| format!("Failed to read rendered page: {e}"))
+        })
+        .and_then(|data| process_image(&data, "png"))
+        .and_then(|(data, ext)| save_image_to_disk(images_dir, &data, &ext));
+        match rendered {
+            Ok(image) => images.push(image),
+            Err(e) => log::warn!("Failed to render PDF page {page}: {e}"),
+        }
+    }
+
+    let _ = fs::remove_dir_all(&temp_dir);
+    images
+}
+
+/// Save a dropped document and extract its text for attaching to a message
+///
+/// `pages` limits extraction to a page selection ("1-3, 7"; sheets for
+/// spreadsheets). PDF page images are rendered too when `backend` can look at
+/// images.
+#[tauri::command]
+pub async fn save_dropped_document(
+    app: AppHandle,
+    source_path: String,
+    pages: Option<String>,
+    backend: Option<String>,
+) -> Result<SaveDocumentResponse, String> {
+    log::trace!("Saving dropped document from: {source_path} (pages: {pages:?})");
+
+    let source = PathBuf::from(&source_path);
+    if !source.is_file() {
+        return Err(format!("Source file not found: {source_path}"));
+    }
+
+    let extension = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .ok_or_else(|| "File has no extension".to_string())?;
+    let kind = DocumentKind::from_extension(&extension).ok_or_else(|| {
+        format!(
+            "Invalid document type: .{extension}. Allowed types: {}",
+            ALLOWED_EXTENSIONS.join(", ")
+        )
+    })?;
+
+    let size = fs::metadata(&source)
+        .map_err(|e| format!("Failed to read file metadata: {e}"))?
+        .len();
+    if size > MAX_DOCUMENT_SIZE {
+        return Err(format!(
+            "Document too large: {size} bytes. Maximum size: {MAX_DOCUMENT_SIZE} bytes (50MB)"
+        ));
+    }
+
+    let ranges = pages
+        .as_deref()
+        .filter(|p| !p.trim().is_empty())
+        .map(parse_page_ranges)
+        .transpose()?;
+    let vision = backend
+        .as_deref()
+        .and_then(parse_backend)
+        .is_some_and(|b| backend_for(&b).capabilities().vision);
+
+    let documents_dir = get_documents_dir(&app)?;
+    let pastes_dir = get_pastes_dir(&app)?;
+    let images_dir = get_images_dir(&app)?;
+    let filename = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| format!("document.{extension}"));
+
+    // Extraction shells out and parses archives; keep it off the async runtime
+    tokio::task::spawn_blocking(move || -> Result<SaveDocumentResponse, String> {
+        let stem = format!("document-{}-{}", now(), &Uuid::new_v4().to_string()[..8]);
+        let original = documents_dir.join(format!("{stem}.{extension}"));
+        fs::copy(&source, &original).map_err(|e| format!("Failed to copy document: {e}"))?;
+        let original_path = original.to_string_lossy().to_string();
+
+        let extracted = match extract(kind, &original, ranges.as_deref()) {
+            Ok(extracted) if extracted.sections.is_empty() => {
+                let _ = fs::remove_file(&original);
+                return Err(format!(
+                    "No pages selected: the document has {} {}(s)",
+                    extracted.page_count.unwrap_or(0),
+                    kind.unit().to_lowercase()
+                ));
+            }
+            Ok(extracted) => extracted,
+            Err(e) => {
+                let _ = fs::remove_file(&original);
+                return Err(e);
+            }
+        };
+        let pages = extracted.pages();
+        let text = format_extract(&filename, kind, &original_path, &extracted);
+
+        let text_path = pastes_dir.join(format!("{stem}.txt"));
+        let temp_path = text_path.with_extension("tmp");
+        fs::write(&temp_path, &text).map_err(|e| format!("Failed to write text file: {e}"))?;
+        fs::rename(&temp_path, &text_path)
+            .map_err(|e| format!("Failed to finalize text file: {e}"))?;
+
+        let images = if vision && kind == DocumentKind::Pdf {
+            render_pdf_pages(&original, &pages, &images_dir)
+        } else {
+            Vec::new()
+        };
+
+        log::trace!(
+            "Document {filename} extracted to {} ({} pages, {} images)",
+            text_path.display(),
+            pages.len(),
+            images.len()
+        );
+
+        Ok(SaveDocumentResponse {
+            id: Uuid::new_v4().to_string(),
+            filename,
+            path: text_path.to_string_lossy().to_string(),
+            size: text.len(),
+            original_path,
+            page_count: extracted.page_count,
+            pages: extracted.page_count.map(|_| describe_pages(&pages)),
+            images,
+        })
+    })
+    .await
+    .map_err(|e| format!("Document processing task failed: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_and_select_page_ranges() {
+        let ranges = parse_page_ranges("1-3, 7, 10-").unwrap();
+        assert_eq!(
+            ranges[2],
+            PageRange {
+                start: 10,
+                end: None
+            }
+        );
+        assert_eq!(
+            select_pages(Some(&ranges), 12),
+            vec![1, 2, 3, 7, 10, 11, 12]
+        );
+        // Out-of-range pages are dropped
+        assert_eq!(select_pages(Some(&ranges), 2), vec![1, 2]);
+        assert_eq!(select_pages(None, 3), vec![1, 2, 3]);
+        assert_eq!(describe_pages(&[1, 2, 3, 7, 10, 11]), "1-3, 7, 10-11");
+
+        assert!(parse_page_ranges("0").is_err());
+        assert!(parse_page_ranges("5-2").is_err());
+        assert!(parse_page_ranges("a-b").is_err());
+    }
+
+    #[test]
+    fn test_docx_pages() {
+        let xml = r#"<w:body><w:p><w:r><w:t>Title &amp; intro</w:t></w:r></w:p><w:p><w:r><w:t xml:space="preserve">Tab</w:t><w:tab/><w:t>bed</w:t></w:r></w:p><w:p><w:r><w:br w:type="page"/><w:t>Second page</w:t></w:r></w:p></w:body>"#;
+        assert_eq!(
+            docx_pages(xml),
+            vec![
+                "Title & intro\nTab\tbed".to_string(),
+                "Second page".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xlsx_sheet_text() {
+        let shared = xlsx_shared_strings(
+            r#"<sst><si><t>Name</t></si><si><r><t>Ada</t></r><r><t xml:space="preserve"> L.</t></r></si></sst>"#,
+        );
+        assert_eq!(shared, vec!["Name".to_string(), "Ada L.".to_string()]);
+
+        let sheet = r#"<sheetData><row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1"><v>42</v></c></row><row r="2"/><row r="3"><c r="A3" t="s"><v>1</v></c><c r="B3" t="b"><v>1</v></c><c r="C3" t="inlineStr"><is><t>x &lt; y</t></is></c></row></sheetData>"#;
+        assert_eq!(
+            xlsx_sheet_text(sheet, &shared),
+            "Name\t\t42\nAda L.\tTRUE\tx < y"
+        );
+        assert_eq!(column_index("AA10"), Some(26));
+    }
+
+    #[test]
+    fn test_extract_xlsx_sheets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("book.xlsx");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        for (name, content) in [
+            (
+                "xl/workbook.xml",
+                r#"<workbook><sheets><sheet name="Bugs" sheetId="1" r:id="rId1"/><sheet name="Notes" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<sheetData><row><c r="A1"><v>1</v></c></row></sheetData>"#,
+            ),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<sheetData><row><c r="A1" t="inlineStr"><is><t>ok</t></is></c></row></sheetData>"#,
+            ),
+        ] {
+            zip.start_file(name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        let ranges = parse_page_ranges("2").unwrap();
+        let extracted = extract(DocumentKind::Xlsx, &path, Some(&ranges)).unwrap();
+        assert_eq!(extracted.page_count, Some(2));
+        let text = format_extract(
+            "book.xlsx",
+            DocumentKind::Xlsx,
+            "/docs/book.xlsx",
+            &extracted,
+        );
+        assert!(text.starts_with("Document: book.xlsx (Spreadsheet, sheets 2 of 2)\n"));
+        assert!(text.contains("--- Sheet 2: Notes ---\nok\n"));
+        assert!(!text.contains("Bugs"));
+    }
+
+    #[test]
+    fn test_read_zip_entry_rejects_oversized_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bomb.docx");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("word/document.xml", options).unwrap();
+        zip.write_all(&vec![b' '; MAX_EXTRACT_SIZE + 1]).unwrap();
+        zip.start_file("small.xml", options).unwrap();
+        zip.write_all(b"<ok/>").unwrap();
+        zip.finish().unwrap();
+
+        let mut archive = open_zip(&path).unwrap();
+        assert!(read_zip_entry(&mut archive, "word/document.xml").is_err());
+        let small = read_zip_entry(&mut archive, "small.xml").unwrap();
+        assert_eq!(small.as_deref(), Some("<ok/>"));
+        assert_eq!(read_zip_entry(&mut archive, "missing.xml").unwrap(), None);
+    }
+}
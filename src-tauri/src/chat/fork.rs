@@ -0,0 +1,475 @@
+//! Session forking
+//!
+//! `fork_session` creates a new session from any earlier message of an
+//! existing one. Run history (metadata entries + NDJSON logs) up to the fork
+//! point is copied, and the first run of the fork branches the backend
+//! conversation:
+//! - Claude: `--resume <id> --fork-session --resume-session-at <message uuid>`
+//! - OpenCode: server-side fork when forking at the latest message
+//! - Codex (and OpenCode otherwise): the first prompt is seeded with a
+//!   transcript of the copied history, including tool calls
+
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use super::storage::{get_session_dir, load_metadata, with_metadata_mut, with_sessions_mut};
+use super::time::now;
+use super::types::{Backend, ChatMessage, ForkOrigin, MessageRole, RunEntry, RunStatus, Session};
+
+/// Per-message character budget when seeding a fork transcript
+const SEED_MESSAGE_CHAR_LIMIT: usize = 4000;
+/// Per-tool-call character budget (input and output each) in a fork transcript
+const SEED_TOOL_CHAR_LIMIT: usize = 500;
+
+/// Resolve how many runs to copy for a fork at `message_id`.
+///
+/// Forking at an assistant message keeps that whole exchange. Forking at a
+/// user message keeps everything before it, so the prompt can be rewritten.
+fn find_fork_point(runs: &[RunEntry], message_id: &str) -> Result<usize, String> {
+    for (idx, run) in runs.iter().enumerate() {
+        if run.assistant_message_id.as_deref() == Some(message_id) {
+            if matches!(run.status, RunStatus::Running | RunStatus::Resumable) {
+                return Err("Cannot fork at a message that is still streaming".to_string());
+            }
+            return Ok(idx + 1);
+        }
+        if run.user_message_id == message_id {
+            return Ok(idx);
+        }
+    }
+    Err(format!("Message not found in session: {message_id}"))
+}
+
+/// Find the UUID of the last conversation message in a Claude NDJSON run log.
+/// Used with `--resume-session-at` to truncate the forked conversation.
+fn last_message_uuid(lines: &[String]) -> Option<String> {
+    lines.iter().rev().find_map(|line| {
+        let msg: serde_json::Value = serde_json::from_str(line).ok()?;
+        let msg_type = msg.get("type").and_then(|v| v.as_str())?;
+        if msg_type != "assistant" && msg_type != "user" {
+            return None;
+        }
+        msg.get("uuid")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+    })
+}
+
+/// Claude CLI project directory name for a working directory
+/// (~/.claude/projects/<name>/<session-id>.jsonl)
+fn claude_project_dir_name(path: &str) -> String {
+    path.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// Make a Claude session resumable from another worktree by copying its
+/// transcript into the target worktree's Claude project directory.
+fn copy_claude_session_file(claude_session_id: &str, target_worktree_path: &str) {
+    let Some(home) = dirs::home_dir() else {
+        return;
+    };
+    let claude_projects = home.join(".claude").join("projects");
+    let file_name = format!("{claude_session_id}.jsonl");
+    let target_dir = claude_projects.join(claude_project_dir_name(target_worktree_path));
+    if target_dir.join(&file_name).exists() {
+        return;
+    }
+
+    let source = fs::read_dir(&claude_projects).ok().and_then(|entries| {
+        entries
+            .flatten()
+            .map(|e| e.path().join(&file_name))
+            .find(|p| p.exists())
+    });
+    let Some(source) = source else {
+        log::warn!("Claude session file not found for fork: {claude_session_id}");
+        return;
+    };
+
+    if let Err(e) = fs::create_dir_all(&target_dir)
+        .and_then(|_| fs::copy(&source, target_dir.join(&file_name)).map(|_| ()))
+    {
+        log::warn!("Failed to copy Claude session file for fork: {e}");
+    }
+}
+
+/// Copy a run's NDJSON log into another session, rewriting the `_run_meta`
+/// header so it points at the new session and run.
+fn copy_run_log(
+    source_dir: &Path,
+    target_dir: &Path,
+    old_run_id: &str,
+    new_run_id: &str,
+    new_session_id: &str,
+    new_worktree_id: &str,
+) -> Result<(), String> {
+    let source = source_dir.join(format!("{old_run_id}.jsonl"));
+    let target: PathBuf = target_dir.join(format!("{new_run_id}.jsonl"));
+    // The source may be gzipped; the fork's copy is always plain
+    let Some(reader) = super::retention::open_run_log(&source)? else {
+        return Ok(());
+    };
+    let mut out =
+        fs::File::create(&target).map_err(|e| format!("Failed to create forked run log: {e}"))?;
+
+    for (idx, line) in reader.lines().enumerate() {
+        let mut line = line.map_err(|e| format!("Failed to read run log: {e}"))?;
+        if idx == 0 && line.contains("\"_run_meta\"") {
+            if let Ok(mut meta) = serde_json::from_str::<serde_json::Value>(&line) {
+                meta["run_id"] = serde_json::json!(new_run_id);
+                meta["session_id"] = serde_json::json!(new_session_id);
+                meta["worktree_id"] = serde_json::json!(new_worktree_id);
+                line = meta.to_string();
+            }
+        }
+        writeln!(out, "{line}").map_err(|e| format!("Failed to write forked run log: {e}"))?;
+    }
+    out.flush()
+        .map_err(|e| format!("Failed to flush forked run log: {e}"))?;
+    Ok(())
+}
+
+pub(super) fn truncate_chars(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(limit).collect();
+    format!("{truncated}… [truncated]")
+}
+
+/// Format copied history as a transcript for backends that cannot fork natively.
+/// Unlike the context summary, tool calls are kept (trimmed) so the new
+/// conversation knows what was already read, edited and run.
+fn format_fork_transcript(messages: &[ChatMessage]) -> String {
+    messages
+        .iter()
+        .map(|msg| {
+            let role = match msg.role {
+                MessageRole::User => "User",
+                MessageRole::Assistant => "Assistant",
+            };
+            let mut section = format!(
+                "### {role}\n{}",
+                truncate_chars(&msg.content, SEED_MESSAGE_CHAR_LIMIT)
+            );
+            for tool in &msg.tool_calls {
+                section.push_str(&format!(
+                    "\n\n[Tool: {}]\nInput: {}",
+                    tool.name,
+                    truncate_chars(&tool.input.to_string(), SEED_TOOL_CHAR_LIMIT)
+                ));
+                if let Some(ref output) = tool.output {
+                    section.push_str(&format!(
+                        "\nOutput: {}",
+                        truncate_chars(output, SEED_TOOL_CHAR_LIMIT)
+                    ));
+                }
+            }
+            section
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+/// Get the fork origin of a session if its first forked run hasn't happened yet
+pub(crate) fn pending_fork(app: &AppHandle, session_id: &str) -> Option<ForkOrigin> {
+    load_metadata(app, session_id)
+        .ok()
+        .flatten()
+        .and_then(|m| m.forked_from)
+        .filter(|f| f.pending)
+}
+
+/// Build the seeded prompt for the first run of a fork on a backend without
+/// native forking. Returns None if there is no history to seed.
+pub(crate) fn build_fork_seed(app: &AppHandle, session_id: &str, message: &str) -> Option<String> {
+    let messages = super::run_log::load_session_messages(app, session_id).ok()?;
+    if messages.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "This conversation continues from an earlier session. Transcript so far:\n\n{}\n\n---\n\nContinue from here. New request:\n\n{message}",
+        format_fork_transcript(&messages)
+    ))
+}
+
+/// Mark the fork as established after its first successful run
+pub(crate) fn complete_fork(app: &AppHandle, session_id: &str) {
+    let Ok(Some(existing)) = load_metadata(app, session_id) else {
+        return;
+    };
+    let _ = with_metadata_mut(
+        app,
+        session_id,
+        &existing.worktree_id,
+        &existing.name,
+        existing.order,
+        |metadata| {
+            if let Some(ref mut fork) = metadata.forked_from {
+                fork.pending = false;
+            }
+            Ok(())
+        },
+    );
+}
+
+/// Fork a session at a message into a new session.
+///
+/// The new session is created in `target_worktree_id` (defaults to the source
+/// worktree), inherits the source session's backend and model settings, and
+/// contains the run history up to `message_id`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn fork_session(
+    app: AppHandle,
+    worktree_id: String,
+    session_id: String,
+    message_id: String,
+    target_worktree_id: Option<String>,
+    target_worktree_path: Option<String>,
+    name: Option<String>,
+) -> Result<Session, String> {
+    log::trace!("Forking session {session_id} at message {message_id}");
+
+    let source = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    let copy_count = find_fork_point(&source.runs, &message_id)?;
+    let forked_at_latest = copy_count == source.runs.len();
+
+    let target_worktree_id = target_worktree_id.unwrap_or_else(|| worktree_id.clone());
+    let cross_worktree = target_worktree_id != source.worktree_id;
+    let target_worktree_path = match target_worktree_path {
+        Some(path) => path,
+        None => crate::projects::storage::load_projects_data(&app)?
+            .find_worktree(&target_worktree_id)
+            .map(|w| w.path.clone())
+            .ok_or_else(|| format!("Worktree not found: {target_worktree_id}"))?,
+    };
+
+    // Create the new session (index entry + metadata)
+    let fork_name = name.unwrap_or_else(|| format!("{} (fork)", source.name));
+    let session = with_sessions_mut(&app, "", &target_worktree_id, |sessions| {
+        let mut session = Session::new(
+            fork_name.clone(),
+            sessions.sessions.len() as u32,
+            source.backend.clone(),
+        );
+        session.selected_model = source.selected_model.clone();
+        session.selected_thinking_level = source.selected_thinking_level.clone();
+        session.selected_provider = source.selected_provider.clone();
+        session.enabled_mcp_servers = source.enabled_mcp_servers.clone();
+        session.session_naming_completed = true;
+        sessions.active_session_id = Some(session.id.clone());
+        sessions.sessions.push(session.clone());
+        Ok(session)
+    })?;
+
+    // Copy run history up to the fork point
+    let source_dir = get_session_dir(&app, &session_id)?;
+    let target_dir = get_session_dir(&app, &session.id)?;
+    let mut runs = Vec::with_capacity(copy_count);
+    for run in &source.runs[..copy_count] {
+        let new_run_id = Uuid::new_v4().to_string();
+        copy_run_log(
+            &source_dir,
+            &target_dir,
+            &run.run_id,
+            &new_run_id,
+            &session.id,
+            &target_worktree_id,
+        )?;
+        let mut copied = run.clone();
+        copied.run_id = new_run_id;
+        copied.pid = None;
+        runs.push(copied);
+    }
+
+    let copied_message_ids: std::collections::HashSet<&str> = runs
+        .iter()
+        .flat_map(|r| {
+            std::iter::once(r.user_message_id.as_str()).chain(r.assistant_message_id.as_deref())
+        })
+        .collect();
+    let approved_plan_message_ids: Vec<String> = source
+        .approved_plan_message_ids
+        .iter()
+        .filter(|id| copied_message_ids.contains(id.as_str()))
+        .cloned()
+        .collect();
+
+    // Prepare the backend conversation for the first forked run
+    let mut claude_session_id = None;
+    let mut opencode_session_id = None;
+    let mut resume_at = None;
+    let mut pending = !runs.is_empty();
+    match source.backend {
+        Backend::Claude => {
+            claude_session_id = runs.iter().rev().find_map(|r| r.claude_session_id.clone());
+            if let Some(ref sid) = claude_session_id {
+                if let Some(last) = runs.last() {
+                    let lines = super::run_log::read_run_log(&app, &session.id, &last.run_id)?;
+                    resume_at = last_message_uuid(&lines);
+                }
+                if cross_worktree {
+                    copy_claude_session_file(sid, &target_worktree_path);
+                }
+            } else {
+                // Nothing to resume from - seed the transcript instead
+                log::trace!("No Claude session ID at fork point, forked session starts fresh");
+            }
+        }
+        Backend::Opencode => {
+            if forked_at_latest {
+                if let Some(ref source_oc_id) = source.opencode_session_id {
+                    match super::opencode::fork_opencode_session(
+                        &app,
+                        source_oc_id,
+                        Path::new(&target_worktree_path),
+                    ) {
+                        Ok(id) => {
+                            opencode_session_id = Some(id);
+                            pending = false;
+                        }
+                        Err(e) => {
+                            log::warn!("OpenCode fork failed, seeding transcript instead: {e}")
+                        }
+                    }
+                }
+            }
+        }
+        Backend::Codex | Backend::Gemini | Backend::Mock => {}
+    }
+
+    let fork_origin = ForkOrigin {
+        session_id: session_id.clone(),
+        message_id: message_id.clone(),
+        forked_at: now(),
+        resume_at,
+        pending,
+    };
+
+    let metadata = with_metadata_mut(
+        &app,
+        &session.id,
+        &target_worktree_id,
+        &session.name,
+        session.order,
+        |metadata| {
+            metadata.runs = runs;
+            metadata.claude_session_id = claude_session_id;
+            metadata.opencode_session_id = opencode_session_id;
+            metadata.approved_plan_message_ids = approved_plan_message_ids;
+            metadata.forked_from = Some(fork_origin);
+            Ok(metadata.clone())
+        },
+    )?;
+
+    log::trace!(
+        "Forked session {session_id} into {} ({} run(s) copied)",
+        session.id,
+        copy_count
+    );
+
+    Ok(metadata.to_session())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::ToolCall;
+
+    fn run(user_id: &str, assistant_id: Option<&str>, status: RunStatus) -> RunEntry {
+        RunEntry {
+            user_message_id: user_id.to_string(),
+            assistant_message_id: assistant_id.map(|s| s.to_string()),
+            ..RunEntry::test(&format!("run-{user_id}"), status)
+        }
+    }
+
+    #[test]
+    fn test_find_fork_point() {
+        let runs = vec![
+            run("u1", Some("a1"), RunStatus::Completed),
+            run("u2", Some("a2"), RunStatus::Completed),
+            run("u3", None, RunStatus::Running),
+        ];
+        assert_eq!(find_fork_point(&runs, "a1").unwrap(), 1);
+        assert_eq!(find_fork_point(&runs, "u2").unwrap(), 1);
+        assert_eq!(find_fork_point(&runs, "a2").unwrap(), 2);
+        assert_eq!(find_fork_point(&runs, "u1").unwrap(), 0);
+        assert!(find_fork_point(&runs, "missing").is_err());
+    }
+
+    #[test]
+    fn test_last_message_uuid() {
+        let lines = vec![
+            r#"{"_run_meta":true,"run_id":"r"}"#.to_string(),
+            r#"{"type":"assistant","uuid":"a-1","message":{"content":[]}}"#.to_string(),
+            r#"{"type":"user","uuid":"u-2","message":{"content":[]}}"#.to_string(),
+            r#"{"type":"result","uuid":"res","result":"done"}"#.to_string(),
+        ];
+        assert_eq!(last_message_uuid(&lines), Some("u-2".to_string()));
+        assert_eq!(last_message_uuid(&[]), None);
+    }
+
+    #[test]
+    fn test_claude_project_dir_name() {
+        assert_eq!(
+            claude_project_dir_name("/Users/me/.jean/my_repo"),
+            "-Users-me--jean-my-repo"
+        );
+    }
+
+    #[test]
+    fn test_copy_run_log_rewrites_header() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        fs::write(
+            source.path().join("old.jsonl"),
+            "{\"_run_meta\":true,\"run_id\":\"old\",\"session_id\":\"s1\",\"worktree_id\":\"w1\"}\n{\"type\":\"assistant\"}\n",
+        )
+        .unwrap();
+
+        copy_run_log(source.path(), target.path(), "old", "new", "s2", "w2").unwrap();
+
+        let copied = fs::read_to_string(target.path().join("new.jsonl")).unwrap();
+        let lines: Vec<&str> = copied.lines().collect();
+        let meta: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(meta["run_id"], "new");
+        assert_eq!(meta["session_id"], "s2");
+        assert_eq!(meta["worktree_id"], "w2");
+        assert_eq!(lines[1], "{\"type\":\"assistant\"}");
+    }
+
+    #[test]
+    fn test_format_fork_transcript_keeps_tool_calls() {
+        let user = ChatMessage {
+            role: MessageRole::User,
+            content: "Fix the bug".to_string(),
+            ..Default::default()
+        };
+        let assistant = ChatMessage {
+            role: MessageRole::Assistant,
+            content: "Done".to_string(),
+            tool_calls: vec![ToolCall {
+                id: "t1".to_string(),
+                name: "Edit".to_string(),
+                input: serde_json::json!({ "file_path": "src/main.rs" }),
+                output: Some("x".repeat(2000)),
+                parent_tool_use_id: None,
+            }],
+            ..Default::default()
+        };
+
+        let transcript = format_fork_transcript(&[user, assistant]);
+        assert!(transcript.starts_with("### User\nFix the bug"));
+        assert!(transcript.contains("[Tool: Edit]"));
+        assert!(transcript.contains("src/main.rs"));
+        assert!(transcript.contains("[truncated]"));
+    }
+}
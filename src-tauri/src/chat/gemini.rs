@@ -0,0 +1,982 @@
+//! Gemini CLI execution engine
+//!
+//! Mirrors the Claude CLI execution pattern (claude.rs) but adapted for
+//! Google's Gemini CLI. Key differences:
+//! - Headless mode reads the prompt as plain text from stdin
+//! - `--output-format stream-json` emits init/message/tool_use/tool_result/
+//!   error/result events
+//! - Resume uses `--resume <session_id>` with the ID from the `init` event
+//! - Jean's plan/build/yolo modes map onto `--approval-mode`
+//! - No thinking/effort levels and no system prompt flag, so instructions are
+//!   prepended to the prompt
+
+use super::types::{ContentBlock, ToolCall, UsageData};
+use crate::http_server::EmitExt;
+
+// =============================================================================
+// Response type (same shape as ClaudeResponse)
+// =============================================================================
+
+/// Response from Gemini CLI execution
+pub struct GeminiResponse {
+    /// The text response content
+    pub content: String,
+    /// The Gemini session ID (for resuming conversations)
+    pub session_id: String,
+    /// Tool calls made during this response
+    pub tool_calls: Vec<ToolCall>,
+    /// Ordered content blocks preserving tool position in response
+    pub content_blocks: Vec<ContentBlock>,
+    /// Whether the response was cancelled by the user
+    pub cancelled: bool,
+    /// Whether a chat:error event was emitted during execution
+    pub error_emitted: bool,
+    /// Token usage for this response
+    pub usage: Option<UsageData>,
+}
+
+// =============================================================================
+// Event structs (reuse same Tauri event names as Claude for frontend compat)
+// =============================================================================
+
+#[derive(serde::Serialize, Clone)]
+struct ChunkEvent {
+    session_id: String,
+    worktree_id: String,
+    content: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ToolUseEvent {
+    session_id: String,
+    worktree_id: String,
+    id: String,
+    name: String,
+    input: serde_json::Value,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ToolResultEvent {
+    session_id: String,
+    worktree_id: String,
+    tool_use_id: String,
+    output: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ToolBlockEvent {
+    session_id: String,
+    worktree_id: String,
+    tool_call_id: String,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct DoneEvent {
+    session_id: String,
+    worktree_id: String,
+    /// True when a plan-mode run completed with content
+    waiting_for_plan: bool,
+}
+
+#[derive(serde::Serialize, Clone)]
+struct ErrorEvent {
+    session_id: String,
+    worktree_id: String,
+    error: String,
+}
+
+// =============================================================================
+// Arg builder
+// =============================================================================
+
+/// Map Jean's execution mode onto Gemini's `--approval-mode`.
+///
+/// Headless Gemini leaves out tools that would need a confirmation, so
+/// `default` only has read-only tools (plan), `auto_edit` adds file edits
+/// (build) and `yolo` allows everything including shell commands.
+pub fn approval_mode(execution_mode: Option<&str>) -> &'static str {
+    match execution_mode.unwrap_or("plan") {
+        "build" => "auto_edit",
+        "yolo" => "yolo",
+        _ => "default",
+    }
+}
+
+/// Build CLI arguments for Gemini CLI.
+pub fn build_gemini_args(
+    existing_session_id: Option<&str>,
+    model: Option<&str>,
+    execution_mode: Option<&str>,
+    include_dirs: &[String],
+    mcp_config: Option<&str>,
+) -> Vec<String> {
+    let mut args = vec!["--output-format".to_string(), "stream-json".to_string()];
+
+    if let Some(m) = model {
+        args.push("--model".to_string());
+        args.push(m.to_string());
+    }
+
+    args.push("--approval-mode".to_string());
+    args.push(approval_mode(execution_mode).to_string());
+
+    if let Some(session_id) = existing_session_id {
+        args.push("--resume".to_string());
+        args.push(session_id.to_string());
+    }
+
+    // Additional directories (pasted images, context files, etc.)
+    if !include_dirs.is_empty() {
+        args.push("--include-directories".to_string());
+        args.push(include_dirs.join(","));
+    }
+
+    // Gemini reads MCP servers from its own settings; restrict them to the
+    // servers enabled for this session
+    if let Some(config) = mcp_config.filter(|c| !c.is_empty()) {
+        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(config) {
+            if let Some(servers) = parsed.get("mcpServers").and_then(|v| v.as_object()) {
+                for server_name in servers.keys() {
+                    args.push("--allowed-mcp-server-names".to_string());
+                    args.push(server_name.clone());
+                }
+            }
+        }
+    }
+
+    args
+}
+
+// =============================================================================
+// Detached execution
+// =============================================================================
+
+/// Execute Gemini CLI as a detached process and tail its output.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_gemini_detached(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    prompt_file: &std::path::Path,
+    output_file: &std::path::Path,
+    working_dir: &std::path::Path,
+    existing_session_id: Option<&str>,
+    model: Option<&str>,
+    execution_mode: Option<&str>,
+    include_dirs: &[String],
+    mcp_config: Option<&str>,
+    pid_callback: Option<Box<dyn FnOnce(u32) + Send>>,
+) -> Result<(u32, GeminiResponse), String> {
+    use super::detached::spawn_detached_claude;
+    use crate::gemini_cli::resolve_cli_binary;
+
+    let cli_path = resolve_cli_binary(app);
+
+    let emit_error = |error_msg: &str| {
+        let _ = app.emit_all(
+            "chat:error",
+            &ErrorEvent {
+                session_id: session_id.to_string(),
+                worktree_id: worktree_id.to_string(),
+                error: error_msg.to_string(),
+            },
+        );
+    };
+
+    if !cli_path.exists() {
+        let error_msg = format!(
+            "Gemini CLI not found at {}. Please install it in Settings > General.",
+            cli_path.display()
+        );
+        log::error!("{error_msg}");
+        emit_error(&error_msg);
+        return Err(error_msg);
+    }
+
+    let args = build_gemini_args(
+        existing_session_id,
+        model,
+        execution_mode,
+        include_dirs,
+        mcp_config,
+    );
+
+    log::debug!(
+        "Gemini CLI command: {} {}",
+        cli_path.display(),
+        args.join(" ")
+    );
+
+    // Gemini reads the prompt from piped stdin, the same way Claude reads its
+    // stream-json input, so the Claude spawner works unchanged
+    let pid = spawn_detached_claude(&cli_path, &args, prompt_file, output_file, working_dir, &[])
+        .map_err(|e| {
+        let error_msg = format!("Failed to start Gemini CLI: {e}");
+        log::error!("{error_msg}");
+        emit_error(&error_msg);
+        error_msg
+    })?;
+
+    log::trace!("Detached Gemini CLI spawned with PID: {pid}");
+
+    // Persist PID to metadata immediately (before tailing) for crash recovery
+    if let Some(cb) = pid_callback {
+        cb(pid);
+    }
+
+    if !super::registry::register_process(session_id.to_string(), pid) {
+        // Process was killed by pending cancel — return cancelled response
+        return Ok((
+            pid,
+            GeminiResponse {
+                content: String::new(),
+                session_id: String::new(),
+                tool_calls: vec![],
+                content_blocks: vec![],
+                cancelled: true,
+                error_emitted: false,
+                usage: None,
+            },
+        ));
+    }
+
+    let is_plan_mode = execution_mode.unwrap_or("plan") == "plan";
+    super::increment_tailer_count();
+    let result = tail_gemini_output(app, session_id, worktree_id, output_file, pid, is_plan_mode);
+    super::decrement_tailer_count();
+    super::registry::unregister_process(session_id);
+
+    Ok((pid, result?))
+}
+
+// =============================================================================
+// Event processing
+// =============================================================================
+
+/// A change to the run caused by one event, emitted by the live tailer
+enum GeminiUpdate {
+    Chunk(String),
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    ToolResult {
+        id: String,
+        output: String,
+    },
+    Error(String),
+}
+
+/// State of a Gemini run, built from its stream-json events. Shared by the
+/// live tailer and the history parser.
+#[derive(Default)]
+struct GeminiRun {
+    content: String,
+    session_id: String,
+    tool_calls: Vec<ToolCall>,
+    content_blocks: Vec<ContentBlock>,
+    usage: Option<UsageData>,
+    completed: bool,
+    /// Last error-severity `error` event, reported if the run fails
+    last_error: Option<String>,
+}
+
+impl GeminiRun {
+    fn apply(&mut self, msg: &serde_json::Value) -> Option<GeminiUpdate> {
+        let str_field = |key: &str| msg.get(key).and_then(|v| v.as_str());
+
+        match str_field("type").unwrap_or("") {
+            "init" => {
+                if let Some(sid) = str_field("session_id") {
+                    self.session_id = sid.to_string();
+                    log::trace!("Gemini session started: {sid}");
+                }
+                None
+            }
+            "message" => {
+                if str_field("role") != Some("assistant") {
+                    return None;
+                }
+                let text = str_field("content").filter(|t| !t.is_empty())?;
+                self.content.push_str(text);
+                // Deltas continue the current text block until a tool call
+                match self.content_blocks.last_mut() {
+                    Some(ContentBlock::Text { text: block }) => block.push_str(text),
+                    _ => self.content_blocks.push(ContentBlock::Text {
+                        text: text.to_string(),
+                    }),
+                }
+                Some(GeminiUpdate::Chunk(text.to_string()))
+            }
+            "tool_use" => {
+                let id = str_field("tool_id")
+                    .filter(|id| !id.is_empty())
+                    .map(str::to_string)
+                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+                let (name, input) = map_tool(
+                    str_field("tool_name").unwrap_or("unknown"),
+                    msg.get("parameters").unwrap_or(&serde_json::Value::Null),
+                );
+                self.tool_calls.push(ToolCall {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input: input.clone(),
+                    output: None,
+                    parent_tool_use_id: None,
+                });
+                self.content_blocks.push(ContentBlock::ToolUse {
+                    tool_call_id: id.clone(),
+                });
+                Some(GeminiUpdate::ToolUse { id, name, input })
+            }
+            "tool_result" => {
+                let id = str_field("tool_id")?.to_string();
+                let output = if str_field("status") == Some("error") {
+                    let message = msg
+                        .pointer("/error/message")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("Tool failed");
+                    format!("Error: {message}")
+                } else {
+                    str_field("output").unwrap_or("").to_string()
+                };
+                let tool_call = self.tool_calls.iter_mut().find(|t| t.id == id)?;
+                tool_call.output = Some(output.clone());
+                Some(GeminiUpdate::ToolResult { id, output })
+            }
+            "error" => {
+                let message = str_field("message").unwrap_or("Unknown Gemini error");
+                if str_field("severity") == Some("warning") {
+                    log::warn!("Gemini warning: {message}");
+                } else {
+                    self.last_error = Some(message.to_string());
+                }
+                None
+            }
+            "result" => {
+                self.completed = true;
+                if let Some(stats) = msg.get("stats") {
+                    let tokens = |key: &str| stats.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+                    self.usage = Some(UsageData {
+                        input_tokens: tokens("input_tokens"),
+                        output_tokens: tokens("output_tokens"),
+                        cache_read_input_tokens: tokens("cached"),
+                        cache_creation_input_tokens: 0,
+                    });
+                }
+                if str_field("status") == Some("error") {
+                    let message = msg
+                        .pointer("/error/message")
+                        .and_then(|v| v.as_str())
+                        .map(str::to_string)
+                        .or_else(|| self.last_error.clone())
+                        .unwrap_or_else(|| "Gemini CLI run failed".to_string());
+                    return Some(GeminiUpdate::Error(message));
+                }
+                None
+            }
+            other => {
+                log::trace!("Unknown Gemini event type: {other}");
+                None
+            }
+        }
+    }
+}
+
+/// Map Gemini's built-in tools onto the Claude tool names the frontend renders
+fn map_tool(name: &str, parameters: &serde_json::Value) -> (String, serde_json::Value) {
+    let mapped = match name {
+        "run_shell_command" => "Bash",
+        "read_file" => "Read",
+        "write_file" => "Write",
+        "replace" => "Edit",
+        "glob" => "Glob",
+        "search_file_content" => "Grep",
+        "google_web_search" => "WebSearch",
+        "web_fetch" => "WebFetch",
+        other => other,
+    };
+
+    let mut input = parameters.clone();
+    // read_file takes `absolute_path` where Claude's Read takes `file_path`
+    if let Some(obj) = input.as_object_mut() {
+        if !obj.contains_key("file_path") {
+            if let Some(path) = obj.get("absolute_path").cloned() {
+                obj.insert("file_path".to_string(), path);
+            }
+        }
+    }
+
+    (mapped.to_string(), input)
+}
+
+/// Format a raw Gemini error message into a user-friendly string.
+fn format_gemini_user_error(error_msg: &str) -> String {
+    let lower = error_msg.to_lowercase();
+    if lower.contains("api key")
+        || lower.contains("gemini_api_key")
+        || lower.contains("authentication")
+    {
+        "Gemini CLI is not authenticated. Run `gemini` once in a terminal to sign in, or set GEMINI_API_KEY."
+            .to_string()
+    } else if lower.contains("resource_exhausted") || lower.contains("quota") {
+        format!("Gemini quota exceeded: {error_msg}")
+    } else {
+        format!("Gemini error: {error_msg}")
+    }
+}
+
+// =============================================================================
+// File-based tailing for detached Gemini CLI
+// =============================================================================
+
+/// Emit the chat events for one run update. Returns true if an error was emitted.
+fn emit_gemini_update(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    update: GeminiUpdate,
+) -> bool {
+    let ids = (session_id.to_string(), worktree_id.to_string());
+    match update {
+        GeminiUpdate::Chunk(content) => {
+            let _ = app.emit_all(
+                "chat:chunk",
+                &ChunkEvent {
+                    session_id: ids.0,
+                    worktree_id: ids.1,
+                    content,
+                },
+            );
+        }
+        GeminiUpdate::ToolUse { id, name, input } => {
+            let _ = app.emit_all(
+                "chat:tool_use",
+                &ToolUseEvent {
+                    session_id: ids.0.clone(),
+                    worktree_id: ids.1.clone(),
+                    id: id.clone(),
+                    name,
+                    input,
+                },
+            );
+            let _ = app.emit_all(
+                "chat:tool_block",
+                &ToolBlockEvent {
+                    session_id: ids.0,
+                    worktree_id: ids.1,
+                    tool_call_id: id,
+                },
+            );
+        }
+        GeminiUpdate::ToolResult { id, output } => {
+            let _ = app.emit_all(
+                "chat:tool_result",
+                &ToolResultEvent {
+                    session_id: ids.0,
+                    worktree_id: ids.1,
+                    tool_use_id: id,
+                    output,
+                },
+            );
+        }
+        GeminiUpdate::Error(message) => {
+            log::error!("Gemini run failed for session {session_id}: {message}");
+            let _ = app.emit_all(
+                "chat:error",
+                &ErrorEvent {
+                    session_id: ids.0,
+                    worktree_id: ids.1,
+                    error: format_gemini_user_error(&message),
+                },
+            );
+            return true;
+        }
+    }
+    false
+}
+
+/// Replays a recorded Gemini run through `GeminiRun::apply`
+pub struct GeminiReplayer {
+    app: tauri::AppHandle,
+    session_id: String,
+    worktree_id: String,
+    is_plan_mode: bool,
+    run: GeminiRun,
+    error_emitted: bool,
+}
+
+impl GeminiReplayer {
+    pub fn new(
+        app: &tauri::AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        is_plan_mode: bool,
+    ) -> Self {
+        Self {
+            app: app.clone(),
+            session_id: session_id.to_string(),
+            worktree_id: worktree_id.to_string(),
+            is_plan_mode,
+            run: GeminiRun::default(),
+            error_emitted: false,
+        }
+    }
+}
+
+impl super::replay::EventReplayer for GeminiReplayer {
+    fn replay_line(&mut self, msg: &serde_json::Value) -> bool {
+        if let Some(update) = self.run.apply(msg) {
+            self.error_emitted |=
+                emit_gemini_update(&self.app, &self.session_id, &self.worktree_id, update);
+        }
+        self.run.completed
+    }
+
+    fn finish(&mut self) {
+        // Same rule as the tailer: chat:done would clear a replayed error
+        if self.error_emitted {
+            return;
+        }
+        let _ = self.app.emit_all(
+            "chat:done",
+            &DoneEvent {
+                session_id: self.session_id.clone(),
+                worktree_id: self.worktree_id.clone(),
+                waiting_for_plan: self.is_plan_mode && !self.run.content.is_empty(),
+            },
+        );
+    }
+}
+
+/// Tail a Gemini stream-json output file and emit events as new lines appear.
+///
+/// Maps Gemini events to the same Tauri events used by Claude, so the
+/// frontend streaming infrastructure works unchanged.
+pub fn tail_gemini_output(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    output_file: &std::path::Path,
+    pid: u32,
+    is_plan_mode: bool,
+) -> Result<GeminiResponse, String> {
+    use super::detached::is_process_alive;
+    use super::tail::{NdjsonTailer, POLL_INTERVAL};
+    use std::time::{Duration, Instant};
+
+    log::trace!("Starting to tail Gemini NDJSON output for session: {session_id}");
+
+    let mut tailer = NdjsonTailer::new_from_start_with_timing(output_file)?;
+
+    let mut run = GeminiRun::default();
+    let mut cancelled = false;
+    // Stopped because the process went away rather than a user cancel
+    let mut process_died = false;
+    let mut error_emitted = false;
+    let mut error_lines: Vec<String> = Vec::new();
+
+    let startup_timeout = Duration::from_secs(120);
+    let dead_process_timeout = Duration::from_secs(2);
+    let started_at = Instant::now();
+    let mut last_output_time = Instant::now();
+    let mut received_gemini_output = false;
+
+    let emit_error = |error: String| {
+        let _ = app.emit_all(
+            "chat:error",
+            &ErrorEvent {
+                session_id: session_id.to_string(),
+                worktree_id: worktree_id.to_string(),
+                error,
+            },
+        );
+    };
+
+    loop {
+        let lines = tailer.poll()?;
+
+        if !lines.is_empty() {
+            last_output_time = Instant::now();
+        }
+
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Skip our metadata header
+            if line.contains("\"_run_meta\"") {
+                continue;
+            }
+
+            let msg: serde_json::Value = match serde_json::from_str(&line) {
+                Ok(m) => m,
+                Err(_) => {
+                    // Gemini logs plain text (e.g. credential notices) to stderr
+                    log::trace!("Non-JSON Gemini output: {line}");
+                    error_lines.push(line.trim().to_string());
+                    continue;
+                }
+            };
+
+            if !received_gemini_output {
+                log::trace!("Received first Gemini output for session: {session_id}");
+                received_gemini_output = true;
+            }
+
+            if let Some(update) = run.apply(&msg) {
+                error_emitted |= emit_gemini_update(app, session_id, worktree_id, update);
+            }
+        }
+
+        if run.completed {
+            break;
+        }
+
+        // Check if externally cancelled
+        if !super::registry::is_process_running(session_id) {
+            log::trace!("Session {session_id} cancelled externally, stopping Gemini tail");
+            cancelled = true;
+            break;
+        }
+
+        // Timeout logic
+        let process_alive = is_process_alive(pid);
+
+        if received_gemini_output {
+            if !process_alive && last_output_time.elapsed() > dead_process_timeout {
+                log::trace!("Gemini process {pid} is no longer running and no new output");
+                cancelled = true;
+                process_died = true;
+                break;
+            }
+        } else {
+            let elapsed = started_at.elapsed();
+
+            if !process_alive && elapsed > Duration::from_secs(5) {
+                log::warn!(
+                    "Gemini process {pid} died during startup after {:.1}s with no output",
+                    elapsed.as_secs_f64()
+                );
+                cancelled = true;
+                process_died = true;
+                break;
+            }
+
+            if elapsed > startup_timeout {
+                log::warn!("Startup timeout exceeded waiting for Gemini output");
+                cancelled = true;
+                process_died = true;
+                break;
+            }
+        }
+
+        std::thread::sleep(POLL_INTERVAL);
+    }
+
+    // Surface errors from a run that died without finishing
+    if !error_emitted && process_died && run.content.is_empty() {
+        let drained = tailer.drain_buffer();
+        if !drained.trim().is_empty() {
+            error_lines.push(drained.trim().to_string());
+        }
+
+        let error_text = run
+            .last_error
+            .clone()
+            .or_else(|| (!error_lines.is_empty()).then(|| error_lines.join("\n")));
+        if let Some(error_text) = error_text {
+            log::warn!("Gemini CLI error output for session {session_id}: {error_text}");
+            emit_error(format_gemini_user_error(&error_text));
+            error_emitted = true;
+        } else {
+            log::warn!("Gemini process died silently for session {session_id} with no output");
+            emit_error(
+                "Gemini CLI exited unexpectedly without producing output. Check your login and usage limits."
+                    .to_string(),
+            );
+            error_emitted = true;
+        }
+    }
+
+    // Don't emit chat:done if an error was emitted — the frontend chat:done
+    // handler clears errors, which would hide the error message from the user
+    if !cancelled && !error_emitted {
+        let _ = app.emit_all(
+            "chat:done",
+            &DoneEvent {
+                session_id: session_id.to_string(),
+                worktree_id: worktree_id.to_string(),
+                waiting_for_plan: is_plan_mode && !run.content.is_empty(),
+            },
+        );
+    }
+
+    log::trace!(
+        "Gemini tailing complete: {} chars, {} tool calls, cancelled: {cancelled}",
+        run.content.len(),
+        run.tool_calls.len()
+    );
+
+    Ok(GeminiResponse {
+        content: run.content,
+        session_id: run.session_id,
+        tool_calls: run.tool_calls,
+        content_blocks: run.content_blocks,
+        cancelled,
+        error_emitted,
+        usage: run.usage,
+    })
+}
+
+// =============================================================================
+// JSONL history parser (for loading saved sessions)
+// =============================================================================
+
+/// Parse stored Gemini stream-json output into a ChatMessage (for loading history).
+pub fn parse_gemini_run_to_message(
+    lines: &[String],
+    run: &super::types::RunEntry,
+) -> Result<super::types::ChatMessage, String> {
+    use super::types::{ChatMessage, MessageRole};
+    use uuid::Uuid;
+
+    let mut state = GeminiRun::default();
+    for line in lines {
+        let Ok(msg) = serde_json::from_str::<serde_json::Value>(line) else {
+            continue;
+        };
+        if msg
+            .get("_run_meta")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false)
+        {
+            continue;
+        }
+        state.apply(&msg);
+    }
+
+    Ok(ChatMessage {
+        id: run
+            .assistant_message_id
+            .clone()
+            .unwrap_or_else(|| Uuid::new_v4().to_string()),
+        session_id: String::new(), // Set by caller
+        role: MessageRole::Assistant,
+        content: state.content,
+        timestamp: run.started_at,
+        tool_calls: state.tool_calls,
+        content_blocks: state.content_blocks,
+        cancelled: run.cancelled,
+        plan_approved: false,
+        model: None,
+        execution_mode: None,
+        thinking_level: None,
+        effort_level: None,
+        recovered: run.recovered,
+        usage: run.usage.clone(),
+    })
+}
+
+// =============================================================================
+// One-shot Gemini execution (for magic prompts)
+// =============================================================================
+
+/// Execute a one-shot Gemini CLI call and return JSON matching `json_schema`.
+///
+/// Gemini CLI has no structured output flag, so the schema is appended to the
+/// prompt and the JSON object is extracted from the `response` field of
+/// `--output-format json`.
+pub fn execute_one_shot_gemini(
+    app: &tauri::AppHandle,
+    prompt: &str,
+    model: &str,
+    json_schema: &str,
+    working_dir: Option<&std::path::Path>,
+) -> Result<String, String> {
+    let cli_path = crate::gemini_cli::resolve_cli_binary(app);
+
+    if !cli_path.exists() {
+        return Err("Gemini CLI not installed".to_string());
+    }
+
+    log::info!(
+        "Executing one-shot Gemini CLI: model={model}, working_dir={:?}",
+        working_dir
+    );
+
+    let full_prompt = format!(
+        "{prompt}\n\nRespond with only a JSON object that matches this JSON schema, \
+         without markdown fences or any other text:\n{json_schema}"
+    );
+
+    let mut cmd = crate::platform::silent_command(&cli_path);
+    cmd.args([
+        "--output-format",
+        "json",
+        "--model",
+        model,
+        "--approval-mode",
+        "default",
+    ])
+    .stdin(std::process::Stdio::piped())
+    .stdout(std::process::Stdio::piped())
+    .stderr(std::process::Stdio::piped());
+
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to spawn Gemini CLI: {e}"))?;
+
+    // Write prompt to stdin
+    if let Some(mut stdin) = child.stdin.take() {
+        use std::io::Write;
+        let _ = stdin.write_all(full_prompt.as_bytes());
+        // stdin is dropped here, closing the pipe
+    }
+
+    // Wait with timeout to avoid hanging indefinitely (e.g. MCP server connection issues)
+    let timeout = std::time::Duration::from_secs(120);
+    let start = std::time::Instant::now();
+    let output = loop {
+        match child.try_wait() {
+            Ok(Some(_status)) => {
+                break child
+                    .wait_with_output()
+                    .map_err(|e| format!("Failed to collect Gemini CLI output: {e}"))?;
+            }
+            Ok(None) => {
+                if start.elapsed() > timeout {
+                    let _ = child.kill();
+                    return Err("Gemini CLI timed out after 120s".to_string());
+                }
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            Err(e) => {
+                return Err(format!("Failed to check Gemini CLI status: {e}"));
+            }
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        log::warn!(
+            "Gemini CLI one-shot failed (exit {}): stderr={}, stdout={}",
+            output.status,
+            stderr.trim(),
+            stdout.trim()
+        );
+        // The json output format reports errors in an `error` object on stdout
+        let message = serde_json::from_str::<serde_json::Value>(&stdout)
+            .ok()
+            .and_then(|v| {
+                v.pointer("/error/message")
+                    .and_then(|m| m.as_str())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| stderr.trim().to_string());
+        return Err(format_gemini_user_error(&message));
+    }
+
+    let parsed: serde_json::Value = serde_json::from_str(stdout.trim())
+        .map_err(|e| format!("Failed to parse Gemini CLI output: {e}"))?;
+    let response = parsed
+        .get("response")
+        .and_then(|v| v.as_str())
+        .ok_or("Gemini CLI output has no response")?;
+
+    extract_json_object(response)
+        .ok_or_else(|| "No structured output found in Gemini response".to_string())
+}
+
+/// Extract the JSON object from a model response that may wrap it in
+/// markdown fences or prose
+fn extract_json_object(text: &str) -> Option<String> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let candidate = text.get(start..=end)?;
+    serde_json::from_str::<serde_json::Value>(candidate)
+        .ok()
+        .map(|_| candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_run_from_stream_events() {
+        let mut run = GeminiRun::default();
+        let events = [
+            json!({"type": "init", "session_id": "abc-123", "model": "gemini-2.5-pro"}),
+            json!({"type": "message", "role": "user", "content": "read it"}),
+            json!({"type": "message", "role": "assistant", "content": "Let me ", "delta": true}),
+            json!({"type": "message", "role": "assistant", "content": "look.", "delta": true}),
+            json!({"type": "tool_use", "tool_name": "read_file", "tool_id": "t1",
+                   "parameters": {"absolute_path": "/repo/a.rs"}}),
+            json!({"type": "tool_result", "tool_id": "t1", "status": "success", "output": "fn main() {}"}),
+            json!({"type": "message", "role": "assistant", "content": "Done.", "delta": true}),
+            json!({"type": "result", "status": "success",
+                   "stats": {"input_tokens": 120, "output_tokens": 30, "cached": 100}}),
+        ];
+        for event in &events {
+            run.apply(event);
+        }
+
+        assert_eq!(run.session_id, "abc-123");
+        assert_eq!(run.content, "Let me look.Done.");
+        assert!(run.completed);
+        assert_eq!(run.content_blocks.len(), 3);
+        assert!(
+            matches!(&run.content_blocks[0], ContentBlock::Text { text } if text == "Let me look.")
+        );
+        assert_eq!(run.tool_calls[0].name, "Read");
+        assert_eq!(run.tool_calls[0].input["file_path"], "/repo/a.rs");
+        assert_eq!(run.tool_calls[0].output.as_deref(), Some("fn main() {}"));
+        let usage = run.usage.unwrap();
+        assert_eq!(usage.input_tokens, 120);
+        assert_eq!(usage.cache_read_input_tokens, 100);
+    }
+
+    #[test]
+    fn test_failed_result_reports_error() {
+        let mut run = GeminiRun::default();
+        run.apply(&json!({"type": "error", "severity": "error", "message": "quota"}));
+        let update = run.apply(&json!({"type": "result", "status": "error"}));
+        assert!(matches!(update, Some(GeminiUpdate::Error(m)) if m == "quota"));
+    }
+
+    #[test]
+    fn test_build_gemini_args() {
+        let args = build_gemini_args(
+            Some("abc-123"),
+            Some("gemini-2.5-pro"),
+            Some("build"),
+            &["/data/pasted-images".to_string()],
+            Some(r#"{"mcpServers":{"notion":{}}}"#),
+        );
+        let joined = args.join(" ");
+        assert!(joined.contains("--approval-mode auto_edit"));
+        assert!(joined.contains("--resume abc-123"));
+        assert!(joined.contains("--include-directories /data/pasted-images"));
+        assert!(joined.contains("--allowed-mcp-server-names notion"));
+        assert_eq!(approval_mode(None), "default");
+        assert_eq!(approval_mode(Some("yolo")), "yolo");
+    }
+
+    #[test]
+    fn test_extract_json_object() {
+        assert_eq!(
+            extract_json_object("```json\n{\"title\": \"x\"}\n```").as_deref(),
+            Some("{\"title\": \"x\"}")
+        );
+        assert_eq!(extract_json_object("no json here"), None);
+    }
+}
@@ -0,0 +1,323 @@
+//! Context handoff across a backend switch
+//!
+//! Each backend keeps its own conversation (`claude_session_id`,
+//! `codex_thread_id`, ...), so after `set_session_backend` the new CLI starts
+//! with no memory of the turns taken on the previous one. The first run on the
+//! new backend is seeded with a handoff built from the session's history: a
+//! compact summary of the older turns, then the most recent turns with their
+//! tool calls trimmed to a budget.
+//!
+//! Only runs the target conversation hasn't seen are handed off: all of them
+//! when it has no conversation yet, otherwise the ones after its own last run
+//! (switching back and forth).
+
+use std::collections::{BTreeSet, HashMap};
+
+use tauri::AppHandle;
+
+use super::fork::truncate_chars;
+use super::storage::load_metadata;
+use super::types::{Backend, ChatMessage, RunEntry, RunStatus, SessionDigest};
+
+/// Turns kept in full at the end of a handoff (older ones are summarized)
+const RECENT_TURNS: usize = 3;
+/// Per-message character budget for recent turns
+const HANDOFF_MESSAGE_CHAR_LIMIT: usize = 4000;
+/// Per-tool-call character budget (input and output each)
+const HANDOFF_TOOL_CHAR_LIMIT: usize = 300;
+/// Character budget for all tool calls of the recent turns. Once spent, older
+/// tool calls are listed by name only.
+const HANDOFF_TOOL_BUDGET: usize = 6000;
+/// Per-request character budget in the summary
+const SUMMARY_REQUEST_CHAR_LIMIT: usize = 200;
+/// Changed files listed in the summary
+const SUMMARY_FILE_LIMIT: usize = 30;
+
+/// A handed-off exchange: the user's request and the assistant's reply
+struct Turn<'a> {
+    request: &'a str,
+    response: Option<&'a ChatMessage>,
+    /// Files the run changed
+    files: Vec<String>,
+}
+
+/// Index of the first run the `target` conversation hasn't seen, or None when
+/// there is nothing to hand off. Runs that don't record a backend are assumed
+/// to be from the session's only backend so far.
+fn handoff_start(runs: &[RunEntry], target: &Backend, has_resume_id: bool) -> Option<usize> {
+    let from_target = |run: &RunEntry| run.backend.as_ref().is_none_or(|b| b == target);
+    if runs.iter().all(from_target) {
+        return None;
+    }
+
+    let start = if has_resume_id {
+        runs.iter().rposition(from_target).map_or(0, |idx| idx + 1)
+    } else {
+        0
+    };
+    (start < runs.len()).then_some(start)
+}
+
+/// Files a run changed: its recorded changes, plus the paths its edit tools targeted
+fn changed_files(run: &RunEntry, response: Option<&ChatMessage>) -> Vec<String> {
+    let recorded = run
+        .changes
+        .iter()
+        .flat_map(|changes| changes.files.iter().map(|f| f.path.clone()));
+    let edited = response
+        .into_iter()
+        .flat_map(|msg| msg.tool_calls.iter())
+        .filter_map(|tool| tool.input.get("file_path").and_then(|v| v.as_str()))
+        .map(str::to_string);
+    recorded.chain(edited).collect()
+}
+
+fn format_summary(digest: Option<&SessionDigest>, turns: &[Turn]) -> String {
+    let mut lines = Vec::new();
+    if let Some(digest) = digest {
+        lines.push(format!("Goal: {}", digest.chat_summary));
+        lines.push(format!("Last action: {}", digest.last_action));
+    }
+
+    let older = &turns[..turns.len().saturating_sub(RECENT_TURNS)];
+    if !older.is_empty() {
+        lines.push("Earlier requests:".to_string());
+        for turn in older {
+            let first_line = turn.request.lines().next().unwrap_or("").trim();
+            lines.push(format!(
+                "- {}",
+                truncate_chars(first_line, SUMMARY_REQUEST_CHAR_LIMIT)
+            ));
+        }
+    }
+
+    let files: BTreeSet<&str> = turns
+        .iter()
+        .flat_map(|turn| turn.files.iter().map(String::as_str))
+        .collect();
+    if !files.is_empty() {
+        let listed: Vec<&str> = files.iter().take(SUMMARY_FILE_LIMIT).copied().collect();
+        let more = files.len() - listed.len();
+        let suffix = if more > 0 {
+            format!(" (+{more} more)")
+        } else {
+            String::new()
+        };
+        lines.push(format!("Files changed: {}{suffix}", listed.join(", ")));
+    }
+
+    lines.join("\n")
+}
+
+/// Format the recent turns, spending the tool budget on the newest first
+fn format_recent_turns(turns: &[Turn]) -> String {
+    let recent = &turns[turns.len().saturating_sub(RECENT_TURNS)..];
+    let mut tool_budget = HANDOFF_TOOL_BUDGET;
+
+    let mut sections: Vec<String> = recent
+        .iter()
+        .rev()
+        .map(|turn| {
+            let mut section = format!(
+                "### User\n{}",
+                truncate_chars(turn.request, HANDOFF_MESSAGE_CHAR_LIMIT)
+            );
+            let Some(response) = turn.response else {
+                return section;
+            };
+
+            section.push_str(&format!(
+                "\n\n### Assistant\n{}",
+                truncate_chars(&response.content, HANDOFF_MESSAGE_CHAR_LIMIT)
+            ));
+            for tool in &response.tool_calls {
+                let input = truncate_chars(&tool.input.to_string(), HANDOFF_TOOL_CHAR_LIMIT);
+                let output = tool
+                    .output
+                    .as_deref()
+                    .map(|o| truncate_chars(o, HANDOFF_TOOL_CHAR_LIMIT));
+                let cost = input.len() + output.as_ref().map_or(0, String::len);
+                if cost > tool_budget {
+                    section.push_str(&format!("\n\n[Tool: {}] (details omitted)", tool.name));
+                    continue;
+                }
+                tool_budget -= cost;
+                section.push_str(&format!("\n\n[Tool: {}]\nInput: {input}", tool.name));
+                if let Some(output) = output {
+                    section.push_str(&format!("\nOutput: {output}"));
+                }
+            }
+            section
+        })
+        .collect();
+    sections.reverse();
+    sections.join("\n\n---\n\n")
+}
+
+fn format_handoff(
+    from: &[&str],
+    digest: Option<&SessionDigest>,
+    turns: &[Turn],
+    message: &str,
+) -> String {
+    let summary = format_summary(digest, turns);
+    let summary = if summary.is_empty() {
+        String::new()
+    } else {
+        format!("## Summary\n{summary}\n\n")
+    };
+    format!(
+        "You are taking over this conversation from {}. Context so far:\n\n{summary}## Recent turns\n\n{}\n\n---\n\nContinue from here. New request:\n\n{message}",
+        from.join(" and "),
+        format_recent_turns(turns)
+    )
+}
+
+/// Build the seeded prompt for a run on `backend` when the session's earlier
+/// turns ran on another backend. Returns None when there is nothing to hand off.
+pub(crate) fn build_handoff(
+    app: &AppHandle,
+    session_id: &str,
+    backend: &Backend,
+    has_resume_id: bool,
+    message: &str,
+) -> Option<String> {
+    let metadata = load_metadata(app, session_id).ok().flatten()?;
+    let start = handoff_start(&metadata.runs, backend, has_resume_id)?;
+    let messages = super::run_log::load_session_messages(app, session_id).ok()?;
+    let by_id: HashMap<&str, &ChatMessage> = messages.iter().map(|m| (m.id.as_str(), m)).collect();
+
+    let unseen = &metadata.runs[start..];
+    let turns: Vec<Turn> = unseen
+        .iter()
+        // Runs cancelled before any reply (undo send) aren't part of the history
+        .filter(|run| run.status != RunStatus::Cancelled || run.assistant_message_id.is_some())
+        .map(|run| {
+            let response = run
+                .assistant_message_id
+                .as_deref()
+                .and_then(|id| by_id.get(id).copied());
+            Turn {
+                request: &run.user_message,
+                response,
+                files: changed_files(run, response),
+            }
+        })
+        .collect();
+    if turns.is_empty() {
+        return None;
+    }
+
+    let mut from: Vec<&str> = Vec::new();
+    for run in unseen {
+        let name = super::backend::backend_for(run.backend.as_ref().unwrap_or(&metadata.backend))
+            .display_name();
+        if !from.contains(&name) {
+            from.push(name);
+        }
+    }
+
+    log::trace!(
+        "Handing off {} turn(s) of session {session_id} to {backend:?}",
+        turns.len()
+    );
+    Some(format_handoff(
+        &from,
+        metadata.digest.as_ref(),
+        &turns,
+        message,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::{MessageRole, ToolCall};
+
+    fn run(id: &str, backend: Option<Backend>) -> RunEntry {
+        RunEntry {
+            user_message_id: format!("user-{id}"),
+            user_message: format!("Request {id}"),
+            assistant_message_id: Some(format!("assistant-{id}")),
+            backend,
+            ..RunEntry::test(id, RunStatus::Completed)
+        }
+    }
+
+    fn reply(content: &str, tool_output: &str) -> ChatMessage {
+        ChatMessage {
+            id: "assistant".to_string(),
+            session_id: "session".to_string(),
+            role: MessageRole::Assistant,
+            content: content.to_string(),
+            timestamp: 0,
+            tool_calls: vec![ToolCall {
+                id: "tool".to_string(),
+                name: "Edit".to_string(),
+                input: serde_json::json!({ "file_path": "src/main.rs" }),
+                output: Some(tool_output.to_string()),
+                parent_tool_use_id: None,
+            }],
+            content_blocks: vec![],
+            cancelled: false,
+            plan_approved: false,
+            model: None,
+            execution_mode: None,
+            thinking_level: None,
+            effort_level: None,
+            recovered: false,
+            usage: None,
+        }
+    }
+
+    #[test]
+    fn test_handoff_start() {
+        let claude = Some(Backend::Claude);
+        let codex = Some(Backend::Codex);
+
+        // Single-backend sessions (including ones predating per-run backends) need no handoff
+        let runs = vec![run("1", None), run("2", claude.clone())];
+        assert_eq!(handoff_start(&runs, &Backend::Claude, true), None);
+
+        // A new conversation gets everything
+        assert_eq!(handoff_start(&runs, &Backend::Codex, false), Some(0));
+
+        // Switching back only hands off the turns taken elsewhere
+        let runs = vec![
+            run("1", claude.clone()),
+            run("2", codex.clone()),
+            run("3", codex),
+        ];
+        assert_eq!(handoff_start(&runs, &Backend::Claude, true), Some(1));
+        assert_eq!(handoff_start(&runs, &Backend::Codex, true), None);
+    }
+
+    #[test]
+    fn test_format_handoff_summarizes_and_trims() {
+        let long_output = "x".repeat(HANDOFF_TOOL_CHAR_LIMIT * 2);
+        let replies: Vec<ChatMessage> = (0..5)
+            .map(|i| reply(&format!("Reply {i}"), &long_output))
+            .collect();
+        let requests: Vec<String> = (0..5).map(|i| format!("Request {i}\nmore")).collect();
+        let turns: Vec<Turn> = replies
+            .iter()
+            .zip(&requests)
+            .map(|(response, request)| Turn {
+                request,
+                response: Some(response),
+                files: vec!["src/main.rs".to_string()],
+            })
+            .collect();
+
+        let handoff = format_handoff(&["Claude"], None, &turns, "Next step");
+        assert!(handoff.starts_with("You are taking over this conversation from Claude."));
+        assert!(handoff.contains("Earlier requests:\n- Request 0\n- Request 1\n"));
+        assert!(handoff.contains("Files changed: src/main.rs\n"));
+        // Older turns are summarized only; recent ones are kept with trimmed tool output
+        assert!(!handoff.contains("Reply 1"));
+        assert!(handoff.contains("Reply 2") && handoff.contains("Reply 4"));
+        assert!(handoff.contains("… [truncated]"));
+        assert!(!handoff.contains(&long_output));
+        assert!(handoff.ends_with("New request:\n\nNext step"));
+    }
+}
@@ -0,0 +1,361 @@
+//! Scripted mock backend for offline testing
+//!
+//! Models named `mock/<fixture>` run on `MockBackend`, which replays an NDJSON
+//! fixture in Claude CLI `stream-json` format instead of calling a real CLI.
+//! The fixture is piped through a detached shell process that echoes it line
+//! by line into the run's output file, so it takes the same path as a real
+//! Claude run: `NdjsonTailer`, `tail_claude_output` and `parse_run_to_message`.
+//!
+//! Fixtures are looked up in `$JEAN_MOCK_FIXTURES_DIR`, then
+//! `<app data>/mock-fixtures`, then the built-in ones below. `{{message}}` in
+//! a fixture is replaced with the user message (JSON-escaped).
+
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Manager};
+
+use super::claude::{ClaudeResponse, ErrorEvent};
+use crate::http_server::EmitExt;
+
+/// Model prefix that selects the mock backend
+pub const MOCK_MODEL_PREFIX: &str = "mock/";
+
+/// Directory with user fixtures, checked before the app data directory
+const FIXTURES_DIR_ENV: &str = "JEAN_MOCK_FIXTURES_DIR";
+
+/// Delay between replayed lines in milliseconds (default 20)
+const LINE_DELAY_ENV: &str = "JEAN_MOCK_LINE_DELAY_MS";
+const DEFAULT_LINE_DELAY_MS: u64 = 20;
+
+/// Placeholder replaced with the user message
+const MESSAGE_PLACEHOLDER: &str = "{{message}}";
+
+/// Fixtures shipped with Jean, by name
+const BUILTIN_FIXTURES: [(&str, &str); 6] = [
+    ("basic", include_str!("mock_fixtures/basic.jsonl")),
+    ("tool-calls", include_str!("mock_fixtures/tool-calls.jsonl")),
+    (
+        "ask-user-question",
+        include_str!("mock_fixtures/ask-user-question.jsonl"),
+    ),
+    (
+        "exit-plan-mode",
+        include_str!("mock_fixtures/exit-plan-mode.jsonl"),
+    ),
+    (
+        "permission-denied",
+        include_str!("mock_fixtures/permission-denied.jsonl"),
+    ),
+    ("error", include_str!("mock_fixtures/error.jsonl")),
+];
+
+/// Returns true if the model selects the mock backend
+pub fn is_mock_model(model: &str) -> bool {
+    model.starts_with(MOCK_MODEL_PREFIX)
+}
+
+/// Fixture name of a `mock/<fixture>` model
+fn fixture_name(model: &str) -> Result<&str, String> {
+    let name = model
+        .strip_prefix(MOCK_MODEL_PREFIX)
+        .ok_or_else(|| format!("Not a mock model: {model}"))?;
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!("Invalid mock fixture name: {name:?}"));
+    }
+    Ok(name)
+}
+
+/// Find a fixture by name in the given directories, then the built-in ones
+fn find_fixture(name: &str, dirs: &[PathBuf]) -> Result<String, String> {
+    for dir in dirs {
+        let path = dir.join(format!("{name}.jsonl"));
+        if path.exists() {
+            return std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read mock fixture {}: {e}", path.display()));
+        }
+    }
+    BUILTIN_FIXTURES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, fixture)| fixture.to_string())
+        .ok_or_else(|| format!("Mock fixture not found: {name}"))
+}
+
+/// Load the fixture for a `mock/<fixture>` model
+fn load_fixture(app: &AppHandle, model: &str) -> Result<String, String> {
+    let name = fixture_name(model)?;
+    let mut dirs = Vec::new();
+    if let Some(dir) = std::env::var_os(FIXTURES_DIR_ENV) {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Ok(app_data_dir) = app.path().app_data_dir() {
+        dirs.push(app_data_dir.join("mock-fixtures"));
+    }
+    find_fixture(name, &dirs)
+}
+
+/// Substitute the user message into a fixture
+fn render_fixture(fixture: &str, message: &str) -> String {
+    let escaped = serde_json::to_string(message).unwrap_or_default();
+    let escaped = escaped
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(&escaped);
+    let mut rendered = fixture.replace(MESSAGE_PLACEHOLDER, escaped);
+    if !rendered.ends_with('\n') {
+        rendered.push('\n');
+    }
+    rendered
+}
+
+fn line_delay_ms() -> u64 {
+    std::env::var(LINE_DELAY_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_LINE_DELAY_MS)
+}
+
+/// Program and arguments that copy stdin to stdout one line at a time
+#[cfg(unix)]
+fn replay_command(delay_ms: u64) -> (PathBuf, Vec<String>) {
+    let script =
+        r#"while IFS= read -r line || [ -n "$line" ]; do printf '%s\n' "$line"; sleep "$1"; done"#;
+    (
+        PathBuf::from("sh"),
+        vec![
+            "-c".to_string(),
+            script.to_string(),
+            "jean-mock".to_string(),
+            format!("{:.3}", delay_ms as f64 / 1000.0),
+        ],
+    )
+}
+
+/// Program and arguments that copy stdin to stdout (no line delay on Windows)
+#[cfg(windows)]
+fn replay_command(_delay_ms: u64) -> (PathBuf, Vec<String>) {
+    (PathBuf::from("findstr"), vec!["^".to_string()])
+}
+
+fn emit_error(app: &AppHandle, session_id: &str, worktree_id: &str, error: &str) {
+    let _ = app.emit_all(
+        "chat:error",
+        &ErrorEvent {
+            session_id: session_id.to_string(),
+            worktree_id: worktree_id.to_string(),
+            error: error.to_string(),
+        },
+    );
+}
+
+/// Replay a fixture as a detached run and tail it like a Claude CLI run.
+///
+/// The rendered fixture is written next to `input_file` and piped to the
+/// replay process, which appends it to `output_file`.
+#[allow(clippy::too_many_arguments)]
+pub fn execute_mock_detached(
+    app: &AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    model: &str,
+    message: &str,
+    input_file: &Path,
+    output_file: &Path,
+    working_dir: &Path,
+    pid_callback: Option<Box<dyn FnOnce(u32) + Send>>,
+) -> Result<(u32, ClaudeResponse), String> {
+    use super::detached::spawn_detached_claude;
+
+    log::trace!("Executing mock backend ({model}) for session: {session_id}");
+
+    let fixture = load_fixture(app, model).map_err(|e| {
+        log::error!("{e}");
+        emit_error(app, session_id, worktree_id, &e);
+        e
+    })?;
+
+    let script_file = input_file.with_extension("mock");
+    std::fs::write(&script_file, render_fixture(&fixture, message))
+        .map_err(|e| format!("Failed to write mock fixture: {e}"))?;
+
+    let (program, args) = replay_command(line_delay_ms());
+    let spawned =
+        spawn_detached_claude(&program, &args, &script_file, output_file, working_dir, &[]);
+    let pid = spawned.map_err(|e| {
+        let error_msg = format!("Failed to start mock backend: {e}");
+        log::error!("{error_msg}");
+        emit_error(app, session_id, worktree_id, &error_msg);
+        error_msg
+    })?;
+
+    log::trace!("Mock replay spawned with PID: {pid}");
+
+    if let Some(cb) = pid_callback {
+        cb(pid);
+    }
+
+    // Register the process for cancellation (returns false if pending cancel exists)
+    if !super::registry::register_process(session_id.to_string(), pid) {
+        return Ok((
+            pid,
+            ClaudeResponse {
+                content: String::new(),
+                session_id: String::new(),
+                tool_calls: vec![],
+                content_blocks: vec![],
+                cancelled: true,
+                usage: None,
+                context_tokens: None,
+            },
+        ));
+    }
+
+    super::increment_tailer_count();
+    let response =
+        super::claude::tail_claude_output(app, session_id, worktree_id, output_file, pid);
+    super::decrement_tailer_count();
+    super::registry::unregister_process(session_id);
+
+    Ok((pid, response?))
+}
+
+/// Output of the fixture's last `result` line: `structured_output` if present,
+/// else the `result` text
+fn one_shot_output(rendered: &str) -> Result<String, String> {
+    let result = rendered
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+        .find(|msg| msg.get("type").and_then(|v| v.as_str()) == Some("result"))
+        .ok_or("Mock fixture has no result line")?;
+
+    let text = result
+        .get("result")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    if result.get("is_error").and_then(|v| v.as_bool()) == Some(true) {
+        return Err(format!("Mock backend returned an error: {text}"));
+    }
+    match result.get("structured_output") {
+        Some(output) if !output.is_null() => Ok(output.to_string()),
+        _ => Ok(text),
+    }
+}
+
+/// One-shot prompt on the mock backend (magic prompts, session naming)
+pub fn execute_one_shot_mock(app: &AppHandle, prompt: &str, model: &str) -> Result<String, String> {
+    let fixture = load_fixture(app, model)?;
+    one_shot_output(&render_fixture(&fixture, prompt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::{RunEntry, RunStatus};
+
+    fn run() -> RunEntry {
+        RunEntry {
+            user_message_id: "user-1".to_string(),
+            model: Some("mock/basic".to_string()),
+            ended_at: Some(1),
+            assistant_message_id: Some("assistant-1".to_string()),
+            ..RunEntry::test("run-1", RunStatus::Completed)
+        }
+    }
+
+    fn parse_builtin(name: &str, message: &str) -> crate::chat::types::ChatMessage {
+        let rendered = render_fixture(&find_fixture(name, &[]).unwrap(), message);
+        let lines: Vec<String> = rendered.lines().map(str::to_string).collect();
+        crate::chat::run_log::parse_run_to_message(&lines, &run()).unwrap()
+    }
+
+    #[test]
+    fn test_fixture_name() {
+        assert_eq!(fixture_name("mock/tool-calls"), Ok("tool-calls"));
+        assert!(fixture_name("mock/../secrets").is_err());
+        assert!(fixture_name("mock/").is_err());
+        assert!(fixture_name("opus").is_err());
+        assert!(is_mock_model("mock/basic"));
+        assert!(!is_mock_model("gemini-2.5-pro"));
+    }
+
+    #[test]
+    fn test_user_fixture_overrides_builtin() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("basic.jsonl"), "custom\n").unwrap();
+        let dirs = [dir.path().to_path_buf()];
+        assert_eq!(find_fixture("basic", &dirs).unwrap(), "custom\n");
+        assert!(find_fixture("tool-calls", &dirs).is_ok());
+        assert!(find_fixture("missing", &dirs).is_err());
+    }
+
+    #[test]
+    fn test_builtin_fixtures_parse() {
+        let basic = parse_builtin("basic", "say \"hi\"");
+        assert_eq!(basic.content, "Mock reply to: say \"hi\"");
+
+        let tools = parse_builtin("tool-calls", "");
+        let names: Vec<&str> = tools.tool_calls.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["Read", "Bash"]);
+        assert_eq!(
+            tools.tool_calls[1].output.as_deref(),
+            Some(" M src/main.rs")
+        );
+
+        let ask = parse_builtin("ask-user-question", "");
+        assert_eq!(ask.tool_calls[0].name, "AskUserQuestion");
+
+        let plan = parse_builtin("exit-plan-mode", "");
+        assert_eq!(plan.tool_calls[0].name, "ExitPlanMode");
+
+        let denied = parse_builtin("permission-denied", "");
+        assert!(denied.content.contains("rm -rf build"));
+    }
+
+    #[test]
+    fn test_one_shot_output() {
+        let basic = render_fixture(&find_fixture("basic", &[]).unwrap(), "ping");
+        assert_eq!(one_shot_output(&basic).unwrap(), "Mock reply to: ping");
+
+        let structured =
+            r#"{"type":"result","is_error":false,"result":"","structured_output":{"title":"x"}}"#;
+        assert_eq!(one_shot_output(structured).unwrap(), r#"{"title":"x"}"#);
+
+        let error = find_fixture("error", &[]).unwrap();
+        assert!(one_shot_output(&error).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replay_through_detached_process() {
+        use crate::chat::detached::{is_process_alive, spawn_detached_claude};
+        use crate::chat::tail::{NdjsonTailer, POLL_INTERVAL};
+
+        let dir = tempfile::tempdir().unwrap();
+        let script_file = dir.path().join("run.mock");
+        let output_file = dir.path().join("run.jsonl");
+        let rendered = render_fixture(&find_fixture("tool-calls", &[]).unwrap(), "");
+        std::fs::write(&script_file, &rendered).unwrap();
+        std::fs::write(&output_file, "").unwrap();
+
+        let (program, args) = replay_command(0);
+        let pid =
+            spawn_detached_claude(&program, &args, &script_file, &output_file, dir.path(), &[])
+                .unwrap();
+
+        let mut tailer = NdjsonTailer::new_from_start(&output_file).unwrap();
+        let mut lines = Vec::new();
+        let started = std::time::Instant::now();
+        while started.elapsed() < std::time::Duration::from_secs(10) {
+            lines.extend(tailer.poll().unwrap());
+            if !is_process_alive(pid) && lines.len() == rendered.lines().count() {
+                break;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        let expected: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines, expected);
+    }
+}
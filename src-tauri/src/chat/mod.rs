@@ -1,16 +1,51 @@
+mod analytics;
+mod backend;
+mod budget;
+mod bundle;
+mod checkpoint;
 pub(crate) mod claude;
 pub(crate) mod codex;
 mod commands;
+mod compaction;
+mod cost;
 pub mod detached;
+mod digest;
+mod documents;
+mod fork;
+mod handoff;
+pub(crate) mod gemini;
+mod mock;
 mod naming;
 pub(crate) mod opencode;
+mod pastes;
+pub mod policy;
+mod queue;
 pub mod registry;
+mod replay;
+mod retention;
 pub mod run_log;
+mod search;
 pub mod storage;
 pub mod tail;
+pub(crate) mod time;
 pub mod types;
+mod watchdog;
 
+pub use analytics::*;
+pub use backend::*;
+pub use budget::*;
+pub use bundle::*;
+pub use checkpoint::*;
 pub use commands::*;
+pub use compaction::*;
+pub use cost::*;
+pub use digest::*;
+pub use documents::*;
+pub use fork::*;
+pub use queue::*;
+pub use replay::*;
+pub use retention::*;
+pub use search::*;
 pub use storage::{preserve_base_sessions, restore_base_sessions, with_sessions_mut};
 
 use std::sync::atomic::{AtomicUsize, Ordering};
@@ -346,9 +346,9 @@ fn generate_names(app: &AppHandle, request: &NamingRequest) -> Result<NamingOutp
         return generate_names_opencode(app, &prompt, &request.model, request);
     }
 
-    // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(&request.model) {
-        return generate_names_codex(app, &prompt, &request.model, request);
+    // Other non-Claude backends (Codex) take the schema through their one-shot call
+    if let Some(agent) = super::backend::backend_for_model(&request.model) {
+        return generate_names_one_shot(app, agent, &prompt, request);
     }
 
     let cli_path = resolve_cli_binary(app);
@@ -509,24 +509,26 @@ const NAMING_SCHEMA: &str = r#"{
     }
 }"#;
 
-/// Generate names using Codex CLI with --output-schema
-fn generate_names_codex(
+/// Generate names with a backend's structured one-shot call (e.g. Codex --output-schema)
+fn generate_names_one_shot(
     app: &tauri::AppHandle,
+    agent: &dyn super::backend::AgentBackend,
     prompt: &str,
-    model: &str,
     request: &NamingRequest,
 ) -> Result<NamingOutput, String> {
-    log::trace!("Generating names with Codex CLI using model {model}");
-    let json_str = super::codex::execute_one_shot_codex(
+    let name = agent.display_name();
+    log::trace!("Generating names with {name} using model {}", request.model);
+    let json_str = agent.one_shot(
         app,
         prompt,
-        model,
+        &request.model,
         NAMING_SCHEMA,
         Some(&request.worktree_path),
+        request.custom_profile_name.as_deref(),
     )?;
-    log::trace!("Codex generated naming response: {json_str}");
+    log::trace!("{name} generated naming response: {json_str}");
     serde_json::from_str(&json_str)
-        .map_err(|e| format!("Failed to parse Codex naming JSON: {e}, raw: {json_str}"))
+        .map_err(|e| format!("Failed to parse {name} naming JSON: {e}, raw: {json_str}"))
 }
 
 fn choose_opencode_model(all_providers: &serde_json::Value) -> Option<(String, String)> {
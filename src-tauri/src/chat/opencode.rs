@@ -595,6 +595,59 @@ pub fn execute_one_shot_opencode(
         .map_err(|_| "OpenCode one-shot thread panicked".to_string())?
 }
 
+/// Fork an OpenCode session via the server's `/session/{id}/fork` endpoint.
+///
+/// Returns the ID of the new OpenCode session. Runs on a dedicated OS thread
+/// for the same reason as `execute_one_shot_opencode`.
+pub fn fork_opencode_session(
+    app: &tauri::AppHandle,
+    opencode_session_id: &str,
+    working_dir: &std::path::Path,
+) -> Result<String, String> {
+    let app = app.clone();
+    let opencode_session_id = opencode_session_id.to_string();
+    let dir = working_dir.to_string_lossy().to_string();
+
+    let handle = std::thread::spawn(move || {
+        let base_url = crate::opencode_server::acquire(&app)?;
+        let result = (|| -> Result<String, String> {
+            let client = reqwest::blocking::Client::builder()
+                .timeout(std::time::Duration::from_secs(60))
+                .build()
+                .map_err(|e| format!("Failed to build OpenCode HTTP client: {e}"))?;
+
+            let fork_url = format!("{base_url}/session/{opencode_session_id}/fork");
+            let resp = client
+                .post(&fork_url)
+                .query(&[("directory", dir.as_str())])
+                .json(&serde_json::json!({}))
+                .send()
+                .map_err(|e| format!("Failed to fork OpenCode session: {e}"))?;
+            if !resp.status().is_success() {
+                let status = resp.status();
+                let body = resp.text().unwrap_or_default();
+                return Err(format!(
+                    "OpenCode session fork failed: status={status}, body={body}"
+                ));
+            }
+            let forked: serde_json::Value = resp
+                .json()
+                .map_err(|e| format!("Failed to parse OpenCode fork response: {e}"))?;
+            forked
+                .get("id")
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .ok_or_else(|| "OpenCode fork response missing id".to_string())
+        })();
+        crate::opencode_server::release();
+        result
+    });
+
+    handle
+        .join()
+        .map_err(|_| "OpenCode fork thread panicked".to_string())?
+}
+
 /// Blocking HTTP logic for one-shot OpenCode calls (runs on a dedicated OS thread).
 fn one_shot_opencode_blocking(
     base_url: &str,
@@ -0,0 +1,373 @@
+//! Content-addressed storage for pasted images and texts
+//!
+//! Pastes are stored under the hash of their content (`image-<hash>.png`,
+//! `paste-<hash>.txt`), so the same screenshot pasted into five sessions is
+//! one file. Sessions reference pasted files through the `[Image attached: ...]`
+//! and `[Text file attached: ...]` markers in their sent and queued messages;
+//! a file is in use while any session references it.
+//!
+//! Unreferenced files are garbage collected by storage maintenance, and when
+//! the session that referenced them is closed or a draft attachment is
+//! removed. Drafts aren't persisted, so a file may be in use by one that
+//! hasn't been sent yet:
+//! - collection only removes files untouched for `PASTE_GRACE_PERIOD`
+//!   (pasting existing content again touches its file)
+//! - releasing a file removes it right away only when it can't be in another
+//!   draft: per-paste files (documents, and pastes from before content
+//!   addressing). Shared blobs are left to collection.
+//!
+//! If any session's metadata can't be read, nothing is removed.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::AppHandle;
+
+use super::commands::{extract_image_paths, extract_text_file_paths};
+use super::storage::{
+    get_documents_dir, get_images_dir, get_pastes_dir, list_all_session_ids, load_metadata,
+};
+
+/// How long an unreferenced file is kept after it was last pasted
+const PASTE_GRACE_PERIOD: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+
+/// Hex digits of the content hash used in file names (128 bits)
+const HASH_LEN: usize = 32;
+
+/// Serializes writes and collection, so a paste that reuses a blob can't race
+/// its removal
+static PASTE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn content_hash(data: &[u8]) -> String {
+    let digest = Sha256::digest(data);
+    digest
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>()[..HASH_LEN]
+        .to_string()
+}
+
+/// Whether a file name is a content-addressed blob (`<prefix>-<hash>.<ext>`)
+fn is_blob_name(name: &str) -> bool {
+    let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+    stem.split_once('-').is_some_and(|(prefix, hash)| {
+        matches!(prefix, "image" | "paste")
+            && hash.len() == HASH_LEN
+            && hash.bytes().all(|b| b.is_ascii_hexdigit())
+    })
+}
+
+/// Store pasted content under its hash in `dir`, reusing an existing copy
+/// (written atomically, temp file + rename). Returns the file's path.
+pub(super) fn store_blob(
+    dir: &Path,
+    prefix: &str,
+    data: &[u8],
+    ext: &str,
+) -> Result<PathBuf, String> {
+    let _guard = PASTE_LOCK.lock().unwrap();
+    let path = dir.join(format!("{prefix}-{}.{ext}", content_hash(data)));
+
+    if fs::metadata(&path).is_ok_and(|m| m.len() == data.len() as u64) {
+        // Restart the grace period: the blob may be in a new draft now
+        let touched = fs::File::options()
+            .write(true)
+            .open(&path)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        if let Err(e) = touched {
+            log::warn!("Failed to touch pasted file {}: {e}", path.display());
+        }
+        log::trace!("Reusing pasted file: {}", path.display());
+        return Ok(path);
+    }
+
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, data).map_err(|e| format!("Failed to write pasted file: {e}"))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("Failed to finalize pasted file: {e}"))?;
+    Ok(path)
+}
+
+/// Pasted files referenced by sessions: file name -> number of referencing
+/// sessions. Fails if any session's metadata can't be read.
+fn referenced_files(app: &AppHandle) -> Result<HashMap<String, u32>, String> {
+    let mut refs: HashMap<String, u32> = HashMap::new();
+    for session_id in list_all_session_ids(app)? {
+        let Some(metadata) = load_metadata(app, &session_id)? else {
+            continue;
+        };
+        let messages = metadata
+            .runs
+            .iter()
+            .map(|run| run.user_message.as_str())
+            .chain(metadata.queued_messages.iter().map(|q| q.message.as_str()));
+
+        let mut names = HashSet::new();
+        for message in messages {
+            for path in extract_image_paths(message)
+                .into_iter()
+                .chain(extract_text_file_paths(message))
+            {
+                if let Some(name) = Path::new(&path).file_name() {
+                    names.insert(name.to_string_lossy().to_string());
+                }
+            }
+        }
+        for name in names {
+            *refs.entry(name).or_default() += 1;
+        }
+    }
+    Ok(refs)
+}
+
+/// Name under which a file is referenced. A stored document original is
+/// referenced through its extracted text (`document-<id>.txt`).
+fn reference_name(dir: &Path, documents_dir: &Path, name: &str) -> String {
+    if dir == documents_dir {
+        let stem = name.split_once('.').map_or(name, |(stem, _)| stem);
+        format!("{stem}.txt")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Time since a file was last modified
+fn file_age(metadata: &fs::Metadata, now: SystemTime) -> Duration {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .unwrap_or_default()
+}
+
+/// Whether an unreferenced pasted file may be removed. `released` files are
+/// ones a session or draft just let go of.
+fn is_removable(name: &str, age: Duration, released: bool) -> bool {
+    let grace_over = age >= PASTE_GRACE_PERIOD;
+    if name.ends_with(".tmp") || is_blob_name(name) {
+        grace_over
+    } else {
+        released || grace_over
+    }
+}
+
+/// What a garbage collection pass did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PasteGcResult {
+    /// Pasted files examined
+    pub scanned: u32,
+    /// Files referenced by at least one session
+    pub referenced: u32,
+    /// Files referenced by more than one session
+    pub shared: u32,
+    pub removed: u32,
+    pub bytes_freed: u64,
+}
+
+/// Remove unreferenced pasted files. With `released`, only those files (by
+/// reference name) are considered.
+fn collect(app: &AppHandle, released: Option<&HashSet<String>>) -> Result<PasteGcResult, String> {
+    let _guard = PASTE_LOCK.lock().unwrap();
+    let refs = referenced_files(app)?;
+    let documents_dir = get_documents_dir(app)?;
+    let dirs = [
+        get_images_dir(app)?,
+        get_pastes_dir(app)?,
+        documents_dir.clone(),
+    ];
+    let now = SystemTime::now();
+
+    let mut result = PasteGcResult::default();
+    for dir in &dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let ref_name = reference_name(dir, &documents_dir, &name);
+            if released.is_some_and(|names| !names.contains(&ref_name)) {
+                continue;
+            }
+
+            result.scanned += 1;
+            if let Some(&count) = refs.get(&ref_name) {
+                result.referenced += 1;
+                if count > 1 {
+                    result.shared += 1;
+                }
+                continue;
+            }
+
+            if !is_removable(&name, file_age(&metadata, now), released.is_some()) {
+                continue;
+            }
+            match fs::remove_file(entry.path()) {
+                Ok(()) => {
+                    log::trace!("Removed unreferenced pasted file: {name}");
+                    result.removed += 1;
+                    result.bytes_freed += metadata.len();
+                }
+                Err(e) => log::warn!("Failed to remove pasted file {name}: {e}"),
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// Pasted files no session references whose grace period is over. The global
+/// storage quota may evict these, never referenced files or fresh pastes a
+/// draft may still be holding.
+pub(super) fn unreferenced_pastes(app: &AppHandle) -> Result<Vec<PathBuf>, String> {
+    let _guard = PASTE_LOCK.lock().unwrap();
+    let refs = referenced_files(app)?;
+    let documents_dir = get_documents_dir(app)?;
+    let dirs = [
+        get_images_dir(app)?,
+        get_pastes_dir(app)?,
+        documents_dir.clone(),
+    ];
+    let now = SystemTime::now();
+
+    let mut paths = Vec::new();
+    for dir in &dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() || file_age(&metadata, now) < PASTE_GRACE_PERIOD {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !refs.contains_key(&reference_name(dir, &documents_dir, &name)) {
+                paths.push(entry.path());
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Remove pasted files no session references anymore (storage maintenance)
+pub fn collect_paste_garbage(app: &AppHandle) -> Result<PasteGcResult, String> {
+    let result = collect(app, None)?;
+    if result.removed > 0 {
+        log::info!(
+            "Removed {} unreferenced pasted files ({} bytes)",
+            result.removed,
+            result.bytes_freed
+        );
+    }
+    Ok(result)
+}
+
+/// Let go of pasted files a closed session or removed draft attachment used.
+/// Files nothing else references are removed (shared blobs after the grace
+/// period); files outside the app's paste directories (legacy `.jean/`
+/// locations) are deleted directly.
+pub(super) fn release_pastes(app: &AppHandle, paths: &[String]) {
+    if paths.is_empty() {
+        return;
+    }
+    let dirs = [
+        get_images_dir(app),
+        get_pastes_dir(app),
+        get_documents_dir(app),
+    ];
+    let in_paste_dirs = |path: &Path| {
+        dirs.iter()
+            .flatten()
+            .any(|dir| path.parent().is_some_and(|parent| parent == dir))
+    };
+
+    let mut released = HashSet::new();
+    for path in paths.iter().map(Path::new) {
+        if in_paste_dirs(path) {
+            if let Some(name) = path.file_name() {
+                released.insert(name.to_string_lossy().to_string());
+            }
+        } else if path.exists() {
+            if let Err(e) = fs::remove_file(path) {
+                log::warn!("Failed to delete pasted file {}: {e}", path.display());
+            }
+        }
+    }
+    if released.is_empty() {
+        return;
+    }
+
+    match collect(app, Some(&released)) {
+        Ok(result) => log::trace!(
+            "Released {} pasted files, removed {}",
+            released.len(),
+            result.removed
+        ),
+        Err(e) => log::warn!("Failed to release pasted files: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store_blob_dedupes_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = store_blob(dir.path(), "image", b"screenshot", "png").unwrap();
+        let again = store_blob(dir.path(), "image", b"screenshot", "png").unwrap();
+        let other = store_blob(dir.path(), "image", b"another one", "png").unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, other);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        let name = first.file_name().unwrap().to_string_lossy().to_string();
+        assert!(is_blob_name(&name), "{name}");
+    }
+
+    #[test]
+    fn test_is_removable() {
+        let blob = format!("paste-{}.txt", "a".repeat(HASH_LEN));
+        let fresh = Duration::from_secs(60);
+        let old = PASTE_GRACE_PERIOD + fresh;
+
+        // Shared blobs wait out the grace period even when released
+        assert!(!is_removable(&blob, fresh, true));
+        assert!(is_removable(&blob, old, false));
+
+        // Per-paste files go as soon as they are released
+        let legacy = "image-1704067200-abc12345.png";
+        assert!(!is_blob_name(legacy));
+        assert!(is_removable(legacy, fresh, true));
+        assert!(!is_removable(legacy, fresh, false));
+        assert!(is_removable("document-1704067200-abc12345.pdf", old, false));
+
+        assert!(!is_removable("paste-123.tmp", fresh, true));
+    }
+
+    #[test]
+    fn test_reference_name_maps_documents_to_their_text() {
+        let documents = Path::new("/data/pasted-documents");
+        let pastes = Path::new("/data/pasted-texts");
+        assert_eq!(
+            reference_name(documents, documents, "document-1-abc.pdf"),
+            "document-1-abc.txt"
+        );
+        assert_eq!(
+            reference_name(pastes, documents, "document-1-abc.txt"),
+            "document-1-abc.txt"
+        );
+    }
+}
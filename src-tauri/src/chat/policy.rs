@@ -0,0 +1,665 @@
+//! Per-project tool permission policy
+//!
+//! `jean.json` can carry a `permissions` section of rules that allow, deny or
+//! ask for a tool call based on the tool name, the Bash command and the file
+//! path it touches:
+//!
+//! ```json
+//! { "permissions": { "rules": [
+//!     { "action": "allow", "tool": "Bash", "command": "npm run *" },
+//!     { "action": "deny", "path": ".env*" },
+//!     { "action": "ask", "tool": "Bash", "command": "git push*" }
+//! ] } }
+//! ```
+//!
+//! When several rules match, deny wins over ask, and ask over allow. A call no
+//! rule matches is left to the execution mode, as before. A run doesn't start
+//! while the policy has a rule that can never match, or a deny or ask rule the
+//! backend can't enforce in the run's execution mode (see `PolicyEnforcement`).
+//!
+//! - Claude: rules are passed to the CLI as `permissions` settings so it
+//!   enforces them itself. Its decisions are recorded from the tool calls in
+//!   the stream. Denials Claude still reports that a deny rule matches are
+//!   dropped instead of being offered for approval.
+//! - Codex: approval requests are answered from the policy in build mode; only
+//!   calls it doesn't decide (or asks about) reach the user.
+//! - OpenCode and Gemini don't read the policy, so only allow rules are
+//!   accepted for them.
+//!
+//! Every decision that changed what the execution mode would have done is
+//! appended to `sessions/data/{session_id}/permission-audit.ndjson`.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+
+use super::backend::PolicyEnforcement;
+use super::storage::{get_session_dir, load_metadata};
+use super::time::now;
+use super::types::Backend;
+use crate::projects::git::read_jean_config;
+use crate::projects::storage::load_projects_data;
+use crate::projects::types::{PermissionPolicy, PermissionRule, PolicyAction};
+
+/// Audit log file in the session directory
+const AUDIT_FILE: &str = "permission-audit.ndjson";
+
+/// Claude tools that run without asking in every permission mode
+const CLAUDE_READ_ONLY_TOOLS: &[&str] = &[
+    "Read",
+    "Glob",
+    "Grep",
+    "LS",
+    "NotebookRead",
+    "TodoWrite",
+    "Task",
+];
+
+/// Claude tools that `acceptEdits` (build mode) runs without asking
+const CLAUDE_EDIT_TOOLS: &[&str] = &["Edit", "MultiEdit", "Write", "NotebookEdit"];
+
+/// Convert a glob to an anchored regex. With `path` set, `*` and `?` stay
+/// within one directory and `**` crosses directories; otherwise `*` matches
+/// anything.
+fn glob_regex(glob: &str, path: bool) -> Option<Regex> {
+    let mut pattern = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if path && chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    pattern.push_str("(?:.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' if path => pattern.push_str("[^/]*"),
+            '*' => pattern.push_str(".*"),
+            '?' if path => pattern.push_str("[^/]"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+
+    match Regex::new(&pattern) {
+        Ok(re) => Some(re),
+        Err(e) => {
+            log::warn!("Invalid permission pattern '{glob}': {e}");
+            None
+        }
+    }
+}
+
+fn glob_matches(glob: &str, value: &str, path: bool) -> bool {
+    glob_regex(glob, path).is_some_and(|re| re.is_match(value))
+}
+
+/// A tool call, reduced to what rules match on
+#[derive(Debug, Default)]
+pub struct ToolRequest {
+    pub tool: String,
+    pub command: Option<String>,
+    /// Relative to the worktree root when the call targets a file inside it
+    pub path: Option<String>,
+}
+
+impl ToolRequest {
+    /// Extract the command and path from a tool's input
+    pub fn from_input(tool: &str, input: &Value, worktree_path: &str) -> Self {
+        let command = input
+            .get("command")
+            .and_then(|v| v.as_str())
+            .map(|c| c.trim().to_string());
+        let path = ["file_path", "notebook_path", "path"]
+            .iter()
+            .find_map(|key| input.get(*key).and_then(|v| v.as_str()))
+            .map(|p| relative_path(p, worktree_path));
+
+        Self {
+            tool: tool.to_string(),
+            command,
+            path,
+        }
+    }
+}
+
+/// Make `path` relative to the worktree root if it lies inside it
+fn relative_path(path: &str, worktree_path: &str) -> String {
+    Path::new(path)
+        .strip_prefix(worktree_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.trim_start_matches("./").to_string())
+}
+
+fn rule_matches(rule: &PermissionRule, request: &ToolRequest) -> bool {
+    if let Some(tool) = &rule.tool {
+        if !glob_matches(tool, &request.tool, false) {
+            return false;
+        }
+    }
+    if let Some(command) = &rule.command {
+        match &request.command {
+            Some(value) if glob_matches(command, value, false) => {}
+            _ => return false,
+        }
+    }
+    if let Some(path) = &rule.path {
+        match &request.path {
+            Some(value) if glob_matches(path, value, true) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Decide a tool call: the strictest action among the rules that match it, or
+/// None when no rule does
+pub fn evaluate(policy: &PermissionPolicy, request: &ToolRequest) -> Option<PolicyAction> {
+    policy
+        .rules
+        .iter()
+        .filter(|rule| rule_matches(rule, request))
+        .map(|rule| rule.action)
+        .max()
+}
+
+/// Claude CLI permission rule strings for one policy rule. Rules Claude's
+/// syntax can't express (tool wildcards other than a whole MCP server, or a
+/// command and a path together) are skipped; `check_rules` rejects deny and ask
+/// rules among them, and Jean still applies the rest to the denials Claude reports.
+fn claude_rules(rule: &PermissionRule) -> Vec<String> {
+    let specifier = match (&rule.command, &rule.path) {
+        (Some(_), Some(_)) => return Vec::new(),
+        (Some(command), None) => Some(command.clone()),
+        // `./` is relative to the working directory (the worktree), `//` is absolute
+        (None, Some(path)) if path.starts_with('/') => Some(format!("/{path}")),
+        (None, Some(path)) => Some(format!("./{path}")),
+        (None, None) => None,
+    };
+
+    let tools: Vec<String> = match (&rule.tool, &rule.command, &rule.path) {
+        // Bash specifiers are commands, not paths
+        (Some(tool), None, Some(_)) if tool == "Bash" => return Vec::new(),
+        (Some(tool), _, _) => match tool.strip_suffix("__*") {
+            Some(server) if server.starts_with("mcp__") && !server.contains('*') => {
+                vec![server.to_string()]
+            }
+            _ if tool.contains('*') => return Vec::new(),
+            _ => vec![tool.clone()],
+        },
+        (None, Some(_), None) => vec!["Bash".to_string()],
+        (None, None, Some(_)) => vec!["Read".to_string(), "Edit".to_string()],
+        _ => return Vec::new(),
+    };
+
+    tools
+        .into_iter()
+        .map(|tool| match &specifier {
+            Some(spec) if !tool.starts_with("mcp__") => format!("{tool}({spec})"),
+            _ => tool,
+        })
+        .collect()
+}
+
+/// The policy as a Claude `permissions` settings object, or None when it has
+/// no rules Claude can enforce
+pub fn claude_permission_settings(policy: &PermissionPolicy) -> Option<Value> {
+    let mut allow = Vec::new();
+    let mut ask = Vec::new();
+    let mut deny = Vec::new();
+    for rule in &policy.rules {
+        let target = match rule.action {
+            PolicyAction::Allow => &mut allow,
+            PolicyAction::Ask => &mut ask,
+            PolicyAction::Deny => &mut deny,
+        };
+        target.extend(claude_rules(rule));
+    }
+
+    if allow.is_empty() && ask.is_empty() && deny.is_empty() {
+        return None;
+    }
+    Some(serde_json::json!({ "allow": allow, "ask": ask, "deny": deny }))
+}
+
+/// Why a rule can never match a tool call, if it can't: no call carries both a
+/// command and a path, and Bash calls have no path
+fn unmatchable_reason(rule: &PermissionRule) -> Option<&'static str> {
+    match (&rule.tool, &rule.command, &rule.path) {
+        (_, Some(_), Some(_)) => Some("combines a command and a path"),
+        (Some(tool), None, Some(_)) if tool == "Bash" => Some("gives Bash a path"),
+        _ => None,
+    }
+}
+
+/// Whether a backend applies a rule in `execution_mode`. Allow rules always
+/// pass: ignoring one only means the call is asked about as before.
+fn enforces(enforcement: PolicyEnforcement, rule: &PermissionRule, execution_mode: &str) -> bool {
+    if rule.action == PolicyAction::Allow {
+        return true;
+    }
+    match enforcement {
+        PolicyEnforcement::None => false,
+        PolicyEnforcement::CliSettings => !claude_rules(rule).is_empty(),
+        // Approvals are only requested for commands (Bash) and file changes (Edit)
+        PolicyEnforcement::BuildApprovals => {
+            execution_mode == "build"
+                && match &rule.tool {
+                    Some(tool) => {
+                        glob_matches(tool, "Bash", false) || glob_matches(tool, "Edit", false)
+                    }
+                    None => true,
+                }
+        }
+    }
+}
+
+/// Reject rules that can never match, and deny or ask rules the backend can't
+/// enforce in `execution_mode`
+fn check_rules(
+    policy: &PermissionPolicy,
+    enforcement: PolicyEnforcement,
+    backend_name: &str,
+    execution_mode: &str,
+) -> Result<(), String> {
+    for (index, rule) in policy.rules.iter().enumerate() {
+        if let Some(reason) = unmatchable_reason(rule) {
+            return Err(format!(
+                "Permission rule {} in jean.json {reason}; fix or remove it",
+                index + 1
+            ));
+        }
+        if !enforces(enforcement, rule, execution_mode) {
+            return Err(format!(
+                "Permission rule {} in jean.json can't be enforced by {backend_name} in \
+                 {execution_mode} mode; remove it or use another backend or mode",
+                index + 1
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Check the worktree's policy before starting a run: refuses rules the
+/// backend wouldn't apply
+pub fn check_policy(
+    app: &AppHandle,
+    worktree_id: &str,
+    enforcement: PolicyEnforcement,
+    backend_name: &str,
+    execution_mode: &str,
+) -> Result<(), String> {
+    match load_policy(app, worktree_id) {
+        Some((_, policy)) => check_rules(&policy, enforcement, backend_name, execution_mode),
+        None => Ok(()),
+    }
+}
+
+/// Whether Claude runs `tool` without asking in `execution_mode`, so an allow
+/// rule for it changes nothing
+fn claude_mode_allows(execution_mode: &str, tool: &str) -> bool {
+    match execution_mode {
+        "yolo" => true,
+        "build" if CLAUDE_EDIT_TOOLS.contains(&tool) => true,
+        _ => CLAUDE_READ_ONLY_TOOLS.contains(&tool),
+    }
+}
+
+/// Load the policy for a worktree: its own jean.json, falling back to the
+/// project's. Returns the worktree path along with it.
+pub fn load_policy(app: &AppHandle, worktree_id: &str) -> Option<(String, PermissionPolicy)> {
+    let data = load_projects_data(app).ok()?;
+    let worktree = data.find_worktree(worktree_id)?;
+    let config = read_jean_config(&worktree.path).or_else(|| {
+        data.find_project(&worktree.project_id)
+            .and_then(|project| read_jean_config(&project.path))
+    })?;
+
+    if config.permissions.is_empty() {
+        return None;
+    }
+    Some((worktree.path.clone(), config.permissions))
+}
+
+/// The policy applying to a running session, for deciding its tool calls
+pub struct ActivePolicy {
+    policy: PermissionPolicy,
+    worktree_path: String,
+    session_id: String,
+    backend: Backend,
+    /// Execution mode of the session's latest run
+    execution_mode: String,
+}
+
+impl ActivePolicy {
+    /// None when the worktree has no permission rules
+    pub fn load(
+        app: &AppHandle,
+        session_id: &str,
+        worktree_id: &str,
+        backend: Backend,
+    ) -> Option<Self> {
+        let (worktree_path, policy) = load_policy(app, worktree_id)?;
+        log::trace!(
+            "Loaded {} permission rules for session {session_id}",
+            policy.rules.len()
+        );
+        let execution_mode = load_metadata(app, session_id)
+            .ok()
+            .flatten()
+            .and_then(|metadata| metadata.runs.last()?.execution_mode.clone())
+            .unwrap_or_else(|| "plan".to_string());
+        Some(Self {
+            policy,
+            worktree_path,
+            session_id: session_id.to_string(),
+            backend,
+            execution_mode,
+        })
+    }
+
+    pub fn request(&self, tool: &str, input: &Value) -> ToolRequest {
+        ToolRequest::from_input(tool, input, &self.worktree_path)
+    }
+
+    pub fn evaluate(&self, request: &ToolRequest) -> Option<PolicyAction> {
+        evaluate(&self.policy, request)
+    }
+
+    /// Whether Claude would have run the tool without asking anyway
+    pub fn claude_mode_allows(&self, tool: &str) -> bool {
+        claude_mode_allows(&self.execution_mode, tool)
+    }
+
+    /// Append a decision the policy made to the session's audit log
+    pub fn record(
+        &self,
+        app: &AppHandle,
+        request: &ToolRequest,
+        action: PolicyAction,
+        tool_use_id: Option<&str>,
+    ) {
+        let entry = PermissionAuditEntry {
+            timestamp: now(),
+            backend: self.backend.clone(),
+            tool: request.tool.clone(),
+            command: request.command.clone(),
+            path: request.path.clone(),
+            action,
+            tool_use_id: tool_use_id.filter(|id| !id.is_empty()).map(str::to_string),
+        };
+        log::trace!(
+            "Permission policy: {:?} {} for session {}",
+            action,
+            request.tool,
+            self.session_id
+        );
+        if let Err(e) = append_audit_entry(app, &self.session_id, &entry) {
+            log::warn!("Failed to record permission decision: {e}");
+        }
+    }
+}
+
+// ============================================================================
+// Audit log
+// ============================================================================
+
+/// One automatic permission decision
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PermissionAuditEntry {
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    pub backend: Backend,
+    pub tool: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub action: PolicyAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
+}
+
+fn append_audit_entry(
+    app: &AppHandle,
+    session_id: &str,
+    entry: &PermissionAuditEntry,
+) -> Result<(), String> {
+    let path = get_session_dir(app, session_id)?.join(AUDIT_FILE);
+    let line = serde_json::to_string(entry)
+        .map_err(|e| format!("Failed to serialize audit entry: {e}"))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open permission audit log: {e}"))?;
+    writeln!(file, "{line}").map_err(|e| format!("Failed to write permission audit log: {e}"))
+}
+
+fn parse_audit_log(contents: &str) -> Vec<PermissionAuditEntry> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("Skipping malformed permission audit entry: {e}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Automatic permission decisions made in a session, oldest first
+#[tauri::command]
+pub async fn get_permission_audit(
+    app: AppHandle,
+    session_id: String,
+) -> Result<Vec<PermissionAuditEntry>, String> {
+    let path = get_session_dir(&app, &session_id)?.join(AUDIT_FILE);
+    match fs::read_to_string(&path) {
+        Ok(contents) => Ok(parse_audit_log(&contents)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("Failed to read permission audit log: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        action: PolicyAction,
+        tool: Option<&str>,
+        command: Option<&str>,
+        path: Option<&str>,
+    ) -> PermissionRule {
+        PermissionRule {
+            action,
+            tool: tool.map(str::to_string),
+            command: command.map(str::to_string),
+            path: path.map(str::to_string),
+        }
+    }
+
+    fn policy() -> PermissionPolicy {
+        PermissionPolicy {
+            rules: vec![
+                rule(PolicyAction::Allow, Some("Bash"), Some("npm run *"), None),
+                rule(PolicyAction::Ask, Some("Bash"), Some("git push*"), None),
+                rule(PolicyAction::Allow, Some("Edit"), None, Some("src/**")),
+                rule(PolicyAction::Deny, None, None, Some("**/.env*")),
+                rule(PolicyAction::Deny, Some("mcp__prod__*"), None, None),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_evaluate_matches_commands_and_paths() {
+        let policy = policy();
+        let root = "/repo";
+        let decide = |tool: &str, input: Value| {
+            evaluate(&policy, &ToolRequest::from_input(tool, &input, root))
+        };
+
+        assert_eq!(
+            decide(
+                "Bash",
+                serde_json::json!({ "command": "npm run test -- --watch" })
+            ),
+            Some(PolicyAction::Allow)
+        );
+        assert_eq!(
+            decide(
+                "Bash",
+                serde_json::json!({ "command": "git push origin main" })
+            ),
+            Some(PolicyAction::Ask)
+        );
+        assert_eq!(decide("Bash", serde_json::json!({ "command": "ls" })), None);
+        assert_eq!(
+            decide(
+                "Edit",
+                serde_json::json!({ "file_path": "/repo/src/a/b.rs" })
+            ),
+            Some(PolicyAction::Allow)
+        );
+        // `*` doesn't cross directories, `**` does
+        assert_eq!(
+            decide(
+                "Edit",
+                serde_json::json!({ "file_path": "/repo/docs/a.md" })
+            ),
+            None
+        );
+        assert_eq!(
+            decide("mcp__prod__query", serde_json::json!({})),
+            Some(PolicyAction::Deny)
+        );
+    }
+
+    #[test]
+    fn test_deny_wins_over_allow() {
+        let request = ToolRequest::from_input(
+            "Edit",
+            &serde_json::json!({ "file_path": "/repo/src/.env.local" }),
+            "/repo",
+        );
+        assert_eq!(request.path.as_deref(), Some("src/.env.local"));
+        assert_eq!(evaluate(&policy(), &request), Some(PolicyAction::Deny));
+    }
+
+    #[test]
+    fn test_claude_permission_settings() {
+        let settings = claude_permission_settings(&policy()).unwrap();
+        assert_eq!(
+            settings,
+            serde_json::json!({
+                "allow": ["Bash(npm run *)", "Edit(./src/**)"],
+                "ask": ["Bash(git push*)"],
+                "deny": ["Read(./**/.env*)", "Edit(./**/.env*)", "mcp__prod"],
+            })
+        );
+
+        let untranslatable = PermissionPolicy {
+            rules: vec![rule(
+                PolicyAction::Deny,
+                Some("Bash"),
+                Some("rm *"),
+                Some("src/**"),
+            )],
+        };
+        assert_eq!(claude_permission_settings(&untranslatable), None);
+    }
+
+    #[test]
+    fn test_check_rules_rejects_unenforceable_rules() {
+        let claude = |policy: &PermissionPolicy, mode: &str| {
+            check_rules(policy, PolicyEnforcement::CliSettings, "Claude CLI", mode)
+        };
+        let codex = |policy: &PermissionPolicy, mode: &str| {
+            check_rules(policy, PolicyEnforcement::BuildApprovals, "Codex CLI", mode)
+        };
+        assert_eq!(claude(&policy(), "yolo"), Ok(()));
+
+        let command_and_path = PermissionPolicy {
+            rules: vec![rule(PolicyAction::Allow, None, Some("cat *"), Some("*.md"))],
+        };
+        assert!(codex(&command_and_path, "build")
+            .unwrap_err()
+            .contains("combines a command and a path"));
+
+        let bash_path = PermissionPolicy {
+            rules: vec![rule(
+                PolicyAction::Deny,
+                Some("Bash"),
+                None,
+                Some("secrets/**"),
+            )],
+        };
+        assert!(codex(&bash_path, "build")
+            .unwrap_err()
+            .contains("gives Bash a path"));
+
+        // Codex answers command approvals in build mode only; Claude can't
+        // express tool wildcards
+        let commands = PermissionPolicy {
+            rules: vec![
+                rule(PolicyAction::Deny, Some("Bash*"), Some("rm *"), None),
+                rule(PolicyAction::Ask, None, None, Some("**/.env*")),
+            ],
+        };
+        assert_eq!(codex(&commands, "build"), Ok(()));
+        assert!(codex(&commands, "yolo").is_err());
+        assert!(claude(&commands, "build").is_err());
+
+        // Codex isn't asked about MCP tools
+        let mcp = PermissionPolicy {
+            rules: vec![rule(PolicyAction::Deny, Some("mcp__prod__*"), None, None)],
+        };
+        assert_eq!(claude(&mcp, "plan"), Ok(()));
+        assert!(codex(&mcp, "build").is_err());
+
+        // Backends that never read the policy only accept allow rules
+        let allow_only = PermissionPolicy {
+            rules: vec![rule(PolicyAction::Allow, Some("Bash"), Some("ls"), None)],
+        };
+        assert_eq!(
+            check_rules(&allow_only, PolicyEnforcement::None, "OpenCode", "yolo"),
+            Ok(())
+        );
+        assert!(check_rules(&policy(), PolicyEnforcement::None, "OpenCode", "plan").is_err());
+    }
+
+    #[test]
+    fn test_claude_mode_allows() {
+        assert!(claude_mode_allows("plan", "Read"));
+        assert!(!claude_mode_allows("plan", "Edit"));
+        assert!(claude_mode_allows("build", "Edit"));
+        assert!(!claude_mode_allows("build", "Bash"));
+        assert!(claude_mode_allows("yolo", "Bash"));
+    }
+
+    #[test]
+    fn test_parse_audit_log_skips_malformed_lines() {
+        let entry = PermissionAuditEntry {
+            timestamp: 1,
+            backend: Backend::Codex,
+            tool: "Bash".to_string(),
+            command: Some("npm run build".to_string()),
+            path: None,
+            action: PolicyAction::Allow,
+            tool_use_id: Some("item_1".to_string()),
+        };
+        let contents = format!("{}\nnot json\n\n", serde_json::to_string(&entry).unwrap());
+        assert_eq!(parse_audit_log(&contents), vec![entry]);
+    }
+}
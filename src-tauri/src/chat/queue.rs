@@ -0,0 +1,494 @@
+//! Persistent per-session message queue
+//!
+//! Follow-up prompts can be queued while a run is still in progress. The queue
+//! lives in `SessionMetadata.queued_messages` so it survives app restarts, and
+//! the head of the queue is dispatched through `send_chat_message` once the
+//! current run reaches `Completed`.
+//!
+//! The chat window mirrors the queue from `chat:queue_updated` and prepares
+//! the session for a dispatched message on `chat:queue_dispatched`. A message
+//! whose send fails goes back to the head of the queue.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use super::storage::{list_all_session_ids, load_metadata, with_metadata_mut};
+use super::time::now;
+use super::types::{EffortLevel, QueuedMessage, RunStatus, SessionMetadata, ThinkingLevel};
+use crate::http_server::EmitExt;
+
+/// Sessions with a queued message currently being dispatched.
+/// Prevents two completions (or a completion and startup recovery) from
+/// popping more than one message for the same session.
+static DISPATCHING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Payload for chat:queue_updated events
+#[derive(Debug, Clone, serde::Serialize)]
+struct QueueUpdatedEvent {
+    session_id: String,
+    worktree_id: String,
+    queued_messages: Vec<QueuedMessage>,
+}
+
+/// Payload for chat:queue_dispatched events
+#[derive(Debug, Clone, serde::Serialize)]
+struct QueueDispatchedEvent {
+    session_id: String,
+    worktree_id: String,
+    queued_message: QueuedMessage,
+}
+
+/// Atomically modify the queue of an existing session and broadcast the result
+fn with_queue_mut<F, T>(app: &AppHandle, session_id: &str, f: F) -> Result<T, String>
+where
+    F: FnOnce(&mut Vec<QueuedMessage>) -> Result<T, String>,
+{
+    let existing = load_metadata(app, session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+
+    let (result, queue) = with_metadata_mut(
+        app,
+        session_id,
+        &existing.worktree_id,
+        &existing.name,
+        existing.order,
+        |metadata| {
+            let result = f(&mut metadata.queued_messages)?;
+            Ok((result, metadata.queued_messages.clone()))
+        },
+    )?;
+
+    emit_queue_updated(app, session_id, &existing.worktree_id, queue);
+    Ok(result)
+}
+
+fn emit_queue_updated(
+    app: &AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    queued_messages: Vec<QueuedMessage>,
+) {
+    let event = QueueUpdatedEvent {
+        session_id: session_id.to_string(),
+        worktree_id: worktree_id.to_string(),
+        queued_messages,
+    };
+    if let Err(e) = app.emit_all("chat:queue_updated", &event) {
+        log::error!("Failed to emit chat:queue_updated: {e}");
+    }
+}
+
+/// Reorder a queue to match the given ID order.
+/// IDs missing from `ordered_ids` keep their relative order at the end,
+/// so a stale frontend can never drop a queued message by reordering.
+fn reorder_queue(queue: &mut Vec<QueuedMessage>, ordered_ids: &[String]) {
+    let mut remaining = std::mem::take(queue);
+    for id in ordered_ids {
+        if let Some(pos) = remaining.iter().position(|m| &m.id == id) {
+            queue.push(remaining.remove(pos));
+        }
+    }
+    queue.append(&mut remaining);
+}
+
+/// Whether the session is idle enough for the next queued message to be sent.
+/// Without `force`, the queue pauses after a cancelled/crashed run or while
+/// the session waits for the user (AskUserQuestion, ExitPlanMode).
+fn is_ready_for_dispatch(metadata: &SessionMetadata, force: bool) -> bool {
+    match metadata.runs.last().map(|r| &r.status) {
+        Some(RunStatus::Running) | Some(RunStatus::Resumable) => false,
+        Some(RunStatus::Completed) | None => force || !metadata.waiting_for_input,
+        Some(_) => force,
+    }
+}
+
+/// Send the next queued message for a session if the session is ready.
+///
+/// Called after a run completes, after startup recovery, and when a message is
+/// enqueued on an idle session. Returns true if a message was dispatched.
+pub fn dispatch_next_queued_message(app: &AppHandle, session_id: &str, force: bool) -> bool {
+    if super::registry::is_process_running(session_id) {
+        return false;
+    }
+
+    {
+        let mut dispatching = DISPATCHING.lock().unwrap();
+        if !dispatching.insert(session_id.to_string()) {
+            log::trace!("Queue dispatch already in progress for session: {session_id}");
+            return false;
+        }
+    }
+
+    let popped = match pop_ready_message(app, session_id, force) {
+        Ok(popped) => popped,
+        Err(e) => {
+            log::warn!("Failed to pop queued message for session {session_id}: {e}");
+            None
+        }
+    };
+
+    let Some((worktree_id, queued, remaining)) = popped else {
+        DISPATCHING.lock().unwrap().remove(session_id);
+        return false;
+    };
+
+    log::trace!(
+        "Dispatching queued message {} for session: {session_id}",
+        queued.id
+    );
+    emit_queue_updated(app, session_id, &worktree_id, remaining);
+    if let Err(e) = app.emit_all(
+        "chat:queue_dispatched",
+        &QueueDispatchedEvent {
+            session_id: session_id.to_string(),
+            worktree_id: worktree_id.clone(),
+            queued_message: queued.clone(),
+        },
+    ) {
+        log::error!("Failed to emit chat:queue_dispatched: {e}");
+    }
+
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let sent = queued.clone();
+        let result = super::send_chat_message(
+            app,
+            session_id,
+            worktree_id,
+            sent.worktree_path,
+            sent.message,
+            sent.model,
+            sent.execution_mode,
+            sent.thinking_level,
+            sent.effort_level,
+            sent.parallel_execution_prompt,
+            sent.ai_language,
+            sent.allowed_tools,
+            sent.mcp_config,
+            sent.chrome_enabled,
+            sent.custom_profile_name,
+            sent.backend,
+        )
+        .await;
+
+        // send_chat_message skips its own queue check while we hold the
+        // dispatch slot, so continue with the next message from here.
+        DISPATCHING.lock().unwrap().remove(&session_id);
+        match result {
+            Ok(_) => {
+                dispatch_next_queued_message(&app, &session_id, false);
+            }
+            // Put the message back at the head so it isn't lost; the queue
+            // stays paused until the next run or a manual resume
+            Err(e) => {
+                log::warn!("Queued message failed for session {session_id}: {e}");
+                let result = with_queue_mut(&app, &session_id, |queue| {
+                    queue.insert(0, queued);
+                    Ok(())
+                });
+                if let Err(e) = result {
+                    log::error!("Failed to requeue message for session {session_id}: {e}");
+                }
+            }
+        }
+    });
+
+    true
+}
+
+/// Pop the head of the queue if the session is ready for dispatch.
+/// Returns (worktree_id, popped message, remaining queue).
+fn pop_ready_message(
+    app: &AppHandle,
+    session_id: &str,
+    force: bool,
+) -> Result<Option<(String, QueuedMessage, Vec<QueuedMessage>)>, String> {
+    let existing = match load_metadata(app, session_id)? {
+        Some(m) => m,
+        None => return Ok(None),
+    };
+    if existing.queued_messages.is_empty() || existing.archived_at.is_some() {
+        return Ok(None);
+    }
+
+    with_metadata_mut(
+        app,
+        session_id,
+        &existing.worktree_id,
+        &existing.name,
+        existing.order,
+        |metadata| {
+            if metadata.queued_messages.is_empty() || !is_ready_for_dispatch(metadata, force) {
+                return Ok(None);
+            }
+            let queued = metadata.queued_messages.remove(0);
+            Ok(Some((
+                metadata.worktree_id.clone(),
+                queued,
+                metadata.queued_messages.clone(),
+            )))
+        },
+    )
+}
+
+/// Dispatch pending queues after startup recovery.
+/// Sessions whose interrupted run was recovered as Completed continue with
+/// their queue; resumable runs dispatch when `resume_session` finishes tailing.
+pub fn resume_pending_queues(app: &AppHandle) {
+    let session_ids = match list_all_session_ids(app) {
+        Ok(ids) => ids,
+        Err(e) => {
+            log::warn!("Failed to list sessions for queue recovery: {e}");
+            return;
+        }
+    };
+
+    for session_id in session_ids {
+        let has_queue = load_metadata(app, &session_id)
+            .ok()
+            .flatten()
+            .is_some_and(|m| !m.queued_messages.is_empty());
+        if has_queue && dispatch_next_queued_message(app, &session_id, false) {
+            log::trace!("Resumed message queue for session: {session_id}");
+        }
+    }
+}
+
+// ============================================================================
+// Queue Commands
+// ============================================================================
+
+/// Get the queued messages for a session
+#[tauri::command]
+pub async fn get_queued_messages(
+    app: AppHandle,
+    session_id: String,
+) -> Result<Vec<QueuedMessage>, String> {
+    Ok(load_metadata(&app, &session_id)?
+        .map(|m| m.queued_messages)
+        .unwrap_or_default())
+}
+
+/// Append a message to a session's queue.
+/// If the session is idle, the message is dispatched immediately.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn enqueue_message(
+    app: AppHandle,
+    session_id: String,
+    worktree_path: String,
+    message: String,
+    model: Option<String>,
+    execution_mode: Option<String>,
+    thinking_level: Option<ThinkingLevel>,
+    effort_level: Option<EffortLevel>,
+    parallel_execution_prompt: Option<String>,
+    ai_language: Option<String>,
+    allowed_tools: Option<Vec<String>>,
+    mcp_config: Option<String>,
+    chrome_enabled: Option<bool>,
+    custom_profile_name: Option<String>,
+    backend: Option<String>,
+) -> Result<QueuedMessage, String> {
+    log::trace!("Queueing message for session: {session_id}");
+
+    if message.trim().is_empty() {
+        return Err("Message cannot be empty".to_string());
+    }
+    if worktree_path.is_empty() {
+        return Err("Worktree path cannot be empty".to_string());
+    }
+
+    let queued = QueuedMessage {
+        id: Uuid::new_v4().to_string(),
+        message,
+        worktree_path,
+        queued_at: now(),
+        model,
+        execution_mode,
+        thinking_level,
+        effort_level,
+        parallel_execution_prompt,
+        ai_language,
+        allowed_tools,
+        mcp_config,
+        chrome_enabled,
+        custom_profile_name,
+        backend,
+    };
+
+    let entry = queued.clone();
+    with_queue_mut(&app, &session_id, |queue| {
+        queue.push(entry);
+        Ok(())
+    })?;
+
+    dispatch_next_queued_message(&app, &session_id, false);
+
+    Ok(queued)
+}
+
+/// Edit the text (and optionally model/mode) of a queued message
+#[tauri::command]
+pub async fn update_queued_message(
+    app: AppHandle,
+    session_id: String,
+    queued_message_id: String,
+    message: String,
+    model: Option<String>,
+    execution_mode: Option<String>,
+) -> Result<QueuedMessage, String> {
+    if message.trim().is_empty() {
+        return Err("Message cannot be empty".to_string());
+    }
+
+    with_queue_mut(&app, &session_id, |queue| {
+        let entry = queue
+            .iter_mut()
+            .find(|m| m.id == queued_message_id)
+            .ok_or_else(|| format!("Queued message not found: {queued_message_id}"))?;
+        entry.message = message;
+        if model.is_some() {
+            entry.model = model;
+        }
+        if execution_mode.is_some() {
+            entry.execution_mode = execution_mode;
+        }
+        Ok(entry.clone())
+    })
+}
+
+/// Reorder a session's queue to match the given IDs
+#[tauri::command]
+pub async fn reorder_queued_messages(
+    app: AppHandle,
+    session_id: String,
+    queued_message_ids: Vec<String>,
+) -> Result<Vec<QueuedMessage>, String> {
+    with_queue_mut(&app, &session_id, |queue| {
+        reorder_queue(queue, &queued_message_ids);
+        Ok(queue.clone())
+    })
+}
+
+/// Remove (cancel) a single queued message
+#[tauri::command]
+pub async fn remove_queued_message(
+    app: AppHandle,
+    session_id: String,
+    queued_message_id: String,
+) -> Result<bool, String> {
+    with_queue_mut(&app, &session_id, |queue| {
+        let before = queue.len();
+        queue.retain(|m| m.id != queued_message_id);
+        Ok(queue.len() != before)
+    })
+}
+
+/// Remove all queued messages for a session
+#[tauri::command]
+pub async fn clear_message_queue(app: AppHandle, session_id: String) -> Result<(), String> {
+    with_queue_mut(&app, &session_id, |queue| {
+        queue.clear();
+        Ok(())
+    })
+}
+
+/// Resume a queue that paused after a cancelled or crashed run
+#[tauri::command]
+pub async fn resume_message_queue(app: AppHandle, session_id: String) -> Result<bool, String> {
+    Ok(dispatch_next_queued_message(&app, &session_id, true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::RunEntry;
+
+    fn queued(id: &str) -> QueuedMessage {
+        QueuedMessage {
+            id: id.to_string(),
+            message: format!("message {id}"),
+            worktree_path: "/tmp/wt".to_string(),
+            queued_at: 0,
+            model: None,
+            execution_mode: None,
+            thinking_level: None,
+            effort_level: None,
+            parallel_execution_prompt: None,
+            ai_language: None,
+            allowed_tools: None,
+            mcp_config: None,
+            chrome_enabled: None,
+            custom_profile_name: None,
+            backend: None,
+        }
+    }
+
+    fn run_with_status(status: RunStatus) -> RunEntry {
+        RunEntry::test("run-1", status)
+    }
+
+    fn ids(queue: &[QueuedMessage]) -> Vec<&str> {
+        queue.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_reorder_queue() {
+        let mut queue = vec![queued("a"), queued("b"), queued("c")];
+        reorder_queue(
+            &mut queue,
+            &["c".to_string(), "a".to_string(), "b".to_string()],
+        );
+        assert_eq!(ids(&queue), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_reorder_queue_keeps_unlisted_and_ignores_unknown() {
+        let mut queue = vec![queued("a"), queued("b"), queued("c")];
+        reorder_queue(&mut queue, &["c".to_string(), "zzz".to_string()]);
+        assert_eq!(ids(&queue), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_is_ready_for_dispatch() {
+        let mut metadata =
+            SessionMetadata::new("s".to_string(), "w".to_string(), "Session 1".to_string(), 0);
+        assert!(is_ready_for_dispatch(&metadata, false));
+
+        metadata.runs.push(run_with_status(RunStatus::Running));
+        assert!(!is_ready_for_dispatch(&metadata, false));
+        assert!(!is_ready_for_dispatch(&metadata, true));
+
+        metadata.runs[0].status = RunStatus::Completed;
+        assert!(is_ready_for_dispatch(&metadata, false));
+
+        metadata.waiting_for_input = true;
+        assert!(!is_ready_for_dispatch(&metadata, false));
+        assert!(is_ready_for_dispatch(&metadata, true));
+
+        metadata.waiting_for_input = false;
+        metadata.runs[0].status = RunStatus::Cancelled;
+        assert!(!is_ready_for_dispatch(&metadata, false));
+        assert!(is_ready_for_dispatch(&metadata, true));
+    }
+
+    #[test]
+    fn test_queued_messages_persist_in_metadata() {
+        let mut metadata =
+            SessionMetadata::new("s".to_string(), "w".to_string(), "Session 1".to_string(), 0);
+        let json = serde_json::to_string(&metadata).unwrap();
+        assert!(!json.contains("queued_messages"));
+
+        metadata.queued_messages.push(queued("a"));
+        let json = serde_json::to_string(&metadata).unwrap();
+        let parsed: SessionMetadata = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.queued_messages, vec![queued("a")]);
+        assert_eq!(parsed.to_session().queued_messages.len(), 1);
+    }
+}
@@ -7,6 +7,7 @@ use tauri::AppHandle;
 use super::claude::CancelledEvent;
 use super::run_log;
 use super::storage;
+use super::types::CancelReason;
 use crate::http_server::EmitExt;
 
 /// Global registry of running Claude process PIDs by session_id
@@ -84,6 +85,16 @@ pub fn cancel_process(
     app: &AppHandle,
     session_id: &str,
     worktree_id: &str,
+) -> Result<bool, String> {
+    cancel_process_with_reason(app, session_id, worktree_id, CancelReason::User)
+}
+
+/// Cancel a running process like `cancel_process`, recording why on the run
+pub fn cancel_process_with_reason(
+    app: &AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    reason: CancelReason,
 ) -> Result<bool, String> {
     let mut registry = PROCESS_REGISTRY.lock().unwrap();
     log::warn!("cancel_process called for session: {session_id}");
@@ -127,7 +138,7 @@ pub fn cancel_process(
 
         // Update manifest SYNCHRONOUSLY before emitting event
         // This ensures any frontend refetch sees "Cancelled" status, not "Running"
-        if let Err(e) = run_log::mark_running_run_cancelled(app, session_id) {
+        if let Err(e) = run_log::mark_running_run_cancelled(app, session_id, &reason) {
             log::warn!("Failed to mark run as cancelled in manifest: {e}");
         }
 
@@ -152,7 +163,7 @@ pub fn cancel_process(
         }
 
         // Try to mark run as cancelled (may not exist yet if still preparing, that's ok)
-        let _ = run_log::mark_running_run_cancelled(app, session_id);
+        let _ = run_log::mark_running_run_cancelled(app, session_id, &reason);
 
         // Emit cancelled event so frontend handles it immediately
         let event = CancelledEvent {
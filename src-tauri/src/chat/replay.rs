@@ -0,0 +1,230 @@
+//! Replay of recorded runs
+//!
+//! `replay_run` re-emits the `chat:*` events of a finished run from its NDJSON
+//! log, so the frontend renders it exactly as it streamed the first time.
+//! Lines are paced by the arrival times the live tailer recorded next to the
+//! log (see `tail::read_timing`); runs recorded before timing existed replay
+//! at a fixed pace. Each backend turns its own log format back into events
+//! with an `EventReplayer` (see `AgentBackend::replayer`).
+//!
+//! Replayed events carry `"replay": true` (see `http_server::mark_replay_thread`):
+//! the frontend renders them like a live run but doesn't persist anything,
+//! prompt for approvals or change the session's status.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::backend_for;
+use super::run_log::{get_run_log_path, read_run_log};
+use super::storage::load_metadata;
+use super::tail::read_timing;
+use crate::http_server::EmitExt;
+
+/// Gap between lines of a run recorded without timing
+const DEFAULT_LINE_GAP: Duration = Duration::from_millis(50);
+
+/// Longest pause between two lines (before speed scaling), so a run that sat
+/// waiting on a slow tool doesn't stall its replay
+const MAX_LINE_GAP: Duration = Duration::from_secs(5);
+
+/// Sessions with a replay in progress
+static REPLAYING: Lazy<Mutex<HashSet<String>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// Turns a backend's logged output lines back into live chat events
+pub trait EventReplayer: Send {
+    /// Emit the events for one parsed log line. Returns true when the run
+    /// stopped at this line (e.g. a blocking tool waiting for the user).
+    fn replay_line(&mut self, msg: &serde_json::Value) -> bool;
+
+    /// Emit whatever ends the run (normally chat:done)
+    fn finish(&mut self);
+}
+
+/// Payload for chat:sending when a replay starts
+#[derive(Debug, Clone, Serialize)]
+struct SendingEvent {
+    session_id: String,
+    worktree_id: String,
+}
+
+/// Summary of a started replay
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplayInfo {
+    pub session_id: String,
+    pub run_id: String,
+    /// Log lines that will be replayed
+    pub lines: usize,
+    /// Expected replay duration at the requested speed
+    pub duration_ms: u64,
+}
+
+/// Delay before each line: the recorded gap to the previous timed line,
+/// capped at `MAX_LINE_GAP` and divided by `speed`. Without any timing every
+/// line after the first waits `DEFAULT_LINE_GAP`.
+fn line_delays(line_count: usize, timing: &HashMap<usize, u64>, speed: f64) -> Vec<Duration> {
+    let mut previous: Option<u64> = None;
+    (0..line_count)
+        .map(|index| {
+            let gap = if timing.is_empty() {
+                if index == 0 {
+                    Duration::ZERO
+                } else {
+                    DEFAULT_LINE_GAP
+                }
+            } else {
+                match (timing.get(&index), previous) {
+                    (Some(&at), Some(prev)) => {
+                        previous = Some(at);
+                        Duration::from_millis(at.saturating_sub(prev)).min(MAX_LINE_GAP)
+                    }
+                    (Some(&at), None) => {
+                        previous = Some(at);
+                        Duration::ZERO
+                    }
+                    // Lines without an entry arrived in the same poll as their neighbours
+                    (None, _) => Duration::ZERO,
+                }
+            };
+            gap.div_f64(speed)
+        })
+        .collect()
+}
+
+/// Replay a recorded run's events into its session.
+///
+/// `speed` scales the original timing (2.0 replays twice as fast, default 1.0).
+/// Returns as soon as the replay starts; it ends with the run's usual
+/// chat:done, and stops early if the session starts a real run meanwhile.
+#[tauri::command]
+pub async fn replay_run(
+    app: AppHandle,
+    session_id: String,
+    run_id: String,
+    speed: Option<f64>,
+) -> Result<ReplayInfo, String> {
+    let speed = speed.unwrap_or(1.0);
+    if !speed.is_finite() || speed <= 0.0 {
+        return Err(format!("Invalid replay speed: {speed}"));
+    }
+
+    if super::registry::is_process_running(&session_id) {
+        return Err("Cannot replay while the session has a run in progress".to_string());
+    }
+
+    let metadata = load_metadata(&app, &session_id)?
+        .ok_or_else(|| format!("Session not found: {session_id}"))?;
+    let run = metadata
+        .find_run(&run_id)
+        .ok_or_else(|| format!("Run not found: {run_id}"))?;
+    if run.log_evicted {
+        return Err(format!(
+            "Run {run_id}'s log was deleted by the storage quota"
+        ));
+    }
+    let backend = backend_for(run.backend.as_ref().unwrap_or(&metadata.backend));
+
+    let lines = read_run_log(&app, &session_id, &run_id)?;
+    if lines.is_empty() {
+        return Err(format!("Run {run_id} has no recorded output"));
+    }
+    let timing = read_timing(&get_run_log_path(&app, &session_id, &run_id)?);
+    let delays = line_delays(lines.len(), &timing, speed);
+    let duration_ms = delays.iter().sum::<Duration>().as_millis() as u64;
+
+    if !REPLAYING.lock().unwrap().insert(session_id.clone()) {
+        return Err("This session is already replaying a run".to_string());
+    }
+
+    log::trace!(
+        "Replaying run {run_id} of session {session_id} ({} lines, {} backend, speed {speed})",
+        lines.len(),
+        backend.display_name()
+    );
+
+    let worktree_id = metadata.worktree_id.clone();
+    let mut replayer = backend.replayer(
+        &app,
+        &session_id,
+        &worktree_id,
+        run.execution_mode.as_deref(),
+    );
+    let info = ReplayInfo {
+        session_id: session_id.clone(),
+        run_id,
+        lines: lines.len(),
+        duration_ms,
+    };
+
+    std::thread::spawn(move || {
+        crate::http_server::mark_replay_thread();
+        let _ = app.emit_all(
+            "chat:sending",
+            &SendingEvent {
+                session_id: session_id.clone(),
+                worktree_id,
+            },
+        );
+
+        let mut interrupted = false;
+        for (line, delay) in lines.iter().zip(delays) {
+            std::thread::sleep(delay);
+
+            if super::registry::is_process_running(&session_id) {
+                log::trace!("Session {session_id} started a run, stopping replay");
+                interrupted = true;
+                break;
+            }
+
+            let line = line.trim();
+            if line.is_empty() || line.contains("\"_run_meta\"") {
+                continue;
+            }
+            let Ok(msg) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            if replayer.replay_line(&msg) {
+                break;
+            }
+        }
+
+        if !interrupted {
+            replayer.finish();
+        }
+        REPLAYING.lock().unwrap().remove(&session_id);
+        log::trace!("Replay finished for session {session_id}");
+    });
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_delays_follow_recorded_timing() {
+        let timing = HashMap::from([(0, 1_000), (1, 1_300), (3, 1_500), (4, 60_000)]);
+        let delays = line_delays(5, &timing, 2.0);
+        assert_eq!(
+            delays,
+            [
+                Duration::ZERO,
+                Duration::from_millis(150),
+                Duration::ZERO,
+                Duration::from_millis(100),
+                MAX_LINE_GAP / 2,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_delays_without_timing() {
+        let delays = line_delays(3, &HashMap::new(), 1.0);
+        assert_eq!(delays, [Duration::ZERO, DEFAULT_LINE_GAP, DEFAULT_LINE_GAP]);
+    }
+}
@@ -0,0 +1,719 @@
+//! Run log retention, compression and disk quotas
+//!
+//! Run logs, `pasted-images` and `pasted-texts` otherwise grow without bound.
+//! Storage maintenance runs at startup, every few hours after that, and on
+//! demand via `run_storage_maintenance`:
+//! - run logs of runs that ended more than `compress_after_days` ago are
+//!   gzipped to `<run_id>.jsonl.gz`; `open_run_log` reads either form, so
+//!   history loading, forks and exports are unaffected
+//! - pasted files no session references are garbage collected (see `pastes`)
+//! - worktree checkpoints (see `checkpoint`) are kept for a session's last
+//!   `checkpoints_per_session` checkpointed runs; older runs lose theirs
+//! - the per-session quota deletes that session's oldest run logs
+//! - the global quota deletes the oldest run logs and unreferenced pasted
+//!   files across all sessions. Pasted files a session references are never
+//!   evicted.
+//!
+//! Runs whose logs were evicted keep their entry (and user message), flagged
+//! with `log_evicted`.
+//!
+//! The newest run of a session is never compressed or evicted (it may be
+//! resumed or still recovering), nor is anything in a session with a running
+//! process. Settings are stored in `app-data/storage.json`.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::storage::{
+    get_data_dir, get_session_dir, list_all_session_ids, load_metadata, with_metadata_mut,
+};
+use super::types::{RunStatus, SessionMetadata};
+use crate::projects::types::ProjectsData;
+
+/// Time between background maintenance passes
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// App data directories holding pasted assets (global, not per session)
+const ASSET_DIRS: [&str; 3] = ["pasted-images", "pasted-texts", "pasted-documents"];
+
+/// Serializes maintenance passes (background thread and command)
+static MAINTENANCE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Storage configuration (app-data/storage.json)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageSettings {
+    /// Gzip run logs of runs that ended more than this many days ago (0 = never)
+    #[serde(default = "default_compress_after_days")]
+    pub compress_after_days: u32,
+    /// Maximum size of one session's run logs in MB (unset = unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_quota_mb: Option<u64>,
+    /// Maximum size of all run logs and pasted assets in MB (unset = unlimited)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub global_quota_mb: Option<u64>,
+    /// Checkpointed runs per session whose checkpoints are kept (0 = all)
+    #[serde(default = "default_checkpoints_per_session")]
+    pub checkpoints_per_session: u32,
+}
+
+fn default_compress_after_days() -> u32 {
+    14
+}
+
+fn default_checkpoints_per_session() -> u32 {
+    20
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            compress_after_days: default_compress_after_days(),
+            session_quota_mb: None,
+            global_quota_mb: None,
+            checkpoints_per_session: default_checkpoints_per_session(),
+        }
+    }
+}
+
+fn get_storage_settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join("storage.json"))
+}
+
+fn load_storage_settings(app: &AppHandle) -> StorageSettings {
+    get_storage_settings_path(app)
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Get the storage configuration
+#[tauri::command]
+pub async fn get_storage_settings(app: AppHandle) -> Result<StorageSettings, String> {
+    Ok(load_storage_settings(&app))
+}
+
+/// Replace the storage configuration
+#[tauri::command]
+pub async fn set_storage_settings(app: AppHandle, settings: StorageSettings) -> Result<(), String> {
+    log::trace!("Saving storage settings");
+
+    if settings.session_quota_mb == Some(0) || settings.global_quota_mb == Some(0) {
+        return Err("Storage quotas must be at least 1 MB".to_string());
+    }
+
+    let path = get_storage_settings_path(&app)?;
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize storage settings: {e}"))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write storage settings: {e}"))?;
+    fs::rename(&temp_path, &path)
+        .map_err(|e| format!("Failed to finalize storage settings: {e}"))?;
+    Ok(())
+}
+
+// ============================================================================
+// Compressed run logs
+// ============================================================================
+
+/// Path of the gzipped form of a run log (`<run_id>.jsonl.gz`)
+pub fn compressed_path(path: &Path) -> PathBuf {
+    path.with_extension("jsonl.gz")
+}
+
+/// The run log file that exists on disk, plain or gzipped
+pub fn existing_run_log(path: &Path) -> Option<PathBuf> {
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+    let gz_path = compressed_path(path);
+    gz_path.exists().then_some(gz_path)
+}
+
+/// Open a run log for reading, transparently decompressing `.jsonl.gz`.
+/// Returns None if neither form exists.
+pub fn open_run_log(path: &Path) -> Result<Option<Box<dyn BufRead>>, String> {
+    // Try the plain file first; it may be compressed between a check and the open
+    match File::open(path) {
+        Ok(file) => return Ok(Some(Box::new(BufReader::new(file)))),
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            return Err(format!("Failed to open run log: {e}"));
+        }
+        Err(_) => {}
+    }
+
+    match File::open(compressed_path(path)) {
+        Ok(file) => Ok(Some(Box::new(BufReader::new(GzDecoder::new(file))))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to open compressed run log: {e}")),
+    }
+}
+
+/// Gzip a run log in place. Returns the compressed size.
+fn compress_run_log(path: &Path) -> Result<u64, String> {
+    let gz_path = compressed_path(path);
+    let temp_path = gz_path.with_extension("gz.tmp");
+
+    let mut input = File::open(path).map_err(|e| format!("Failed to open run log: {e}"))?;
+    let output =
+        File::create(&temp_path).map_err(|e| format!("Failed to create compressed log: {e}"))?;
+    let mut encoder = GzEncoder::new(output, Compression::default());
+    io::copy(&mut input, &mut encoder).map_err(|e| format!("Failed to compress run log: {e}"))?;
+    encoder
+        .finish()
+        .map_err(|e| format!("Failed to finish compressed log: {e}"))?;
+
+    fs::rename(&temp_path, &gz_path)
+        .map_err(|e| format!("Failed to finalize compressed log: {e}"))?;
+    fs::remove_file(path).map_err(|e| format!("Failed to remove uncompressed log: {e}"))?;
+
+    Ok(file_size(&gz_path))
+}
+
+// ============================================================================
+// Disk usage
+// ============================================================================
+
+/// Disk usage of one session's data directory
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDiskUsage {
+    pub session_id: String,
+    pub session_name: String,
+    pub worktree_id: String,
+    pub bytes: u64,
+    pub run_logs: u32,
+    /// Run logs stored gzipped
+    pub compressed_logs: u32,
+}
+
+/// Disk usage of a project's sessions
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectDiskUsage {
+    /// None for sessions whose worktree no longer exists
+    pub project_id: Option<String>,
+    pub project_name: String,
+    pub bytes: u64,
+    /// Largest first
+    pub sessions: Vec<SessionDiskUsage>,
+}
+
+/// Disk usage report for `get_disk_usage`
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsageReport {
+    pub total_bytes: u64,
+    pub sessions_bytes: u64,
+    pub pasted_images_bytes: u64,
+    pub pasted_texts_bytes: u64,
+    pub pasted_documents_bytes: u64,
+    /// Largest first
+    pub projects: Vec<ProjectDiskUsage>,
+    pub settings: StorageSettings,
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Total size of the files under a directory
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(t) if t.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|m| m.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Total size of the pasted asset directories
+fn assets_size(app_data_dir: &Path) -> u64 {
+    ASSET_DIRS
+        .iter()
+        .map(|dir| dir_size(&app_data_dir.join(dir)))
+        .sum()
+}
+
+fn session_usage(app: &AppHandle, session_id: &str) -> Result<SessionDiskUsage, String> {
+    let session_dir = get_session_dir(app, session_id)?;
+    let metadata = load_metadata(app, session_id)?;
+
+    let mut run_logs = 0;
+    let mut compressed_logs = 0;
+    if let Ok(entries) = fs::read_dir(&session_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.ends_with(".jsonl.gz") {
+                run_logs += 1;
+                compressed_logs += 1;
+            } else if name.ends_with(".jsonl") && !name.ends_with(".input.jsonl") {
+                run_logs += 1;
+            }
+        }
+    }
+
+    Ok(SessionDiskUsage {
+        session_id: session_id.to_string(),
+        session_name: metadata
+            .as_ref()
+            .map(|m| m.name.clone())
+            .unwrap_or_default(),
+        worktree_id: metadata.map(|m| m.worktree_id).unwrap_or_default(),
+        bytes: dir_size(&session_dir),
+        run_logs,
+        compressed_logs,
+    })
+}
+
+/// Report disk usage per project and session, plus pasted assets
+#[tauri::command]
+pub async fn get_disk_usage(app: AppHandle) -> Result<DiskUsageReport, String> {
+    log::trace!("Computing disk usage");
+
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    let projects_data = crate::projects::storage::load_projects_data(&app)?;
+
+    let mut projects: HashMap<Option<String>, ProjectDiskUsage> = HashMap::new();
+    for session_id in list_all_session_ids(&app)? {
+        let usage = session_usage(&app, &session_id)?;
+        let project = projects_data
+            .find_worktree(&usage.worktree_id)
+            .and_then(|w| projects_data.find_project(&w.project_id));
+        let key = project.map(|p| p.id.clone());
+        let entry = projects
+            .entry(key.clone())
+            .or_insert_with(|| ProjectDiskUsage {
+                project_id: key,
+                project_name: project
+                    .map(|p| p.name.clone())
+                    .unwrap_or_else(|| "Deleted worktrees".to_string()),
+                bytes: 0,
+                sessions: Vec::new(),
+            });
+        entry.bytes += usage.bytes;
+        entry.sessions.push(usage);
+    }
+
+    let mut projects: Vec<ProjectDiskUsage> = projects.into_values().collect();
+    for project in &mut projects {
+        project.sessions.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+    }
+    projects.sort_by(|a, b| b.bytes.cmp(&a.bytes));
+
+    let sessions_bytes = dir_size(&get_data_dir(&app)?);
+    let [pasted_images_bytes, pasted_texts_bytes, pasted_documents_bytes] =
+        ASSET_DIRS.map(|dir| dir_size(&app_data_dir.join(dir)));
+
+    Ok(DiskUsageReport {
+        total_bytes: sessions_bytes
+            + pasted_images_bytes
+            + pasted_texts_bytes
+            + pasted_documents_bytes,
+        sessions_bytes,
+        pasted_images_bytes,
+        pasted_texts_bytes,
+        pasted_documents_bytes,
+        projects,
+        settings: load_storage_settings(&app),
+    })
+}
+
+// ============================================================================
+// Maintenance
+// ============================================================================
+
+/// What a maintenance pass did
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct StorageMaintenanceResult {
+    pub compressed_logs: u32,
+    pub evicted_logs: u32,
+    pub evicted_assets: u32,
+    /// Unreferenced pasted files removed
+    pub collected_assets: u32,
+    /// Runs whose worktree checkpoints were deleted
+    pub pruned_checkpoints: u32,
+    /// Bytes reclaimed by compression, collection and eviction
+    pub bytes_freed: u64,
+}
+
+/// A file (with its sidecars) that quotas may delete
+#[derive(Debug, Clone)]
+struct Evictable {
+    paths: Vec<PathBuf>,
+    bytes: u64,
+    /// Unix seconds; oldest is evicted first
+    age_key: u64,
+    /// Session and run whose log this is (None for pasted files)
+    run: Option<(String, String)>,
+}
+
+/// Delete the oldest candidates until `used` fits in `quota`. Returns the
+/// deleted candidates and the bytes freed.
+fn evict_oldest(candidates: &mut Vec<Evictable>, used: u64, quota: u64) -> (Vec<Evictable>, u64) {
+    candidates.sort_by_key(|c| c.age_key);
+
+    let mut evicted = Vec::new();
+    let mut freed = 0;
+    while used.saturating_sub(freed) > quota && !candidates.is_empty() {
+        let candidate = candidates.remove(0);
+        for path in &candidate.paths {
+            if let Err(e) = fs::remove_file(path) {
+                if e.kind() != io::ErrorKind::NotFound {
+                    log::warn!("Failed to evict {}: {e}", path.display());
+                }
+            }
+        }
+        freed += candidate.bytes;
+        evicted.push(candidate);
+    }
+    (evicted, freed)
+}
+
+/// Delete the checkpoints of all but the session's last `keep` checkpointed
+/// runs. Returns how many runs lost theirs.
+fn prune_checkpoints(
+    app: &AppHandle,
+    metadata: &SessionMetadata,
+    keep: u32,
+    projects: &ProjectsData,
+) -> Result<u32, String> {
+    let checkpointed: Vec<&str> = metadata
+        .runs
+        .iter()
+        .filter(|run| run.checkpoint.is_some())
+        .map(|run| run.run_id.as_str())
+        .collect();
+    let excess = checkpointed.len().saturating_sub(keep as usize);
+    if keep == 0 || excess == 0 {
+        return Ok(0);
+    }
+    let Some(worktree) = projects.find_worktree(&metadata.worktree_id) else {
+        // Refs of deleted worktrees went with their session data
+        return Ok(0);
+    };
+
+    let mut pruned = HashSet::new();
+    for run_id in &checkpointed[..excess] {
+        match super::checkpoint::delete_run_checkpoint(&worktree.path, &metadata.id, run_id) {
+            Ok(()) => {
+                pruned.insert(*run_id);
+            }
+            Err(e) => log::warn!("Failed to prune checkpoint of run {run_id}: {e}"),
+        }
+    }
+    if pruned.is_empty() {
+        return Ok(0);
+    }
+
+    with_metadata_mut(
+        app,
+        &metadata.id,
+        &metadata.worktree_id,
+        &metadata.name,
+        metadata.order,
+        |metadata| {
+            for run in &mut metadata.runs {
+                if pruned.contains(run.run_id.as_str()) {
+                    run.checkpoint = None;
+                }
+            }
+            Ok(())
+        },
+    )?;
+    Ok(pruned.len() as u32)
+}
+
+/// Compress old run logs of one session, prune its checkpoints and collect
+/// the logs quotas may evict
+fn maintain_session(
+    app: &AppHandle,
+    session_id: &str,
+    settings: &StorageSettings,
+    projects: &ProjectsData,
+    result: &mut StorageMaintenanceResult,
+) -> Result<Vec<Evictable>, String> {
+    if super::registry::is_process_running(session_id) {
+        return Ok(Vec::new());
+    }
+    let Some(metadata) = load_metadata(app, session_id)? else {
+        return Ok(Vec::new());
+    };
+    match prune_checkpoints(app, &metadata, settings.checkpoints_per_session, projects) {
+        Ok(pruned) => result.pruned_checkpoints += pruned,
+        Err(e) => log::warn!("Failed to prune checkpoints of session {session_id}: {e}"),
+    }
+    let session_dir = get_session_dir(app, session_id)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let compress_before = (settings.compress_after_days > 0)
+        .then(|| now.saturating_sub(settings.compress_after_days as u64 * 86400));
+
+    let mut candidates = Vec::new();
+    let finished_runs = metadata
+        .runs
+        .iter()
+        .take(metadata.runs.len().saturating_sub(1))
+        .filter(|run| run.status != RunStatus::Running);
+    for run in finished_runs {
+        let log_path = session_dir.join(format!("{}.jsonl", run.run_id));
+        let ended_at = run.ended_at.unwrap_or(run.started_at);
+
+        if log_path.exists() && compress_before.is_some_and(|cutoff| ended_at < cutoff) {
+            let before = file_size(&log_path);
+            match compress_run_log(&log_path) {
+                Ok(after) => {
+                    result.compressed_logs += 1;
+                    result.bytes_freed += before.saturating_sub(after);
+                }
+                Err(e) => log::warn!("Failed to compress run log {}: {e}", run.run_id),
+            }
+        }
+
+        let Some(path) = existing_run_log(&log_path) else {
+            continue;
+        };
+        let timing_path = super::tail::timing_path(&log_path);
+        candidates.push(Evictable {
+            bytes: file_size(&path) + file_size(&timing_path),
+            paths: vec![path, timing_path],
+            age_key: ended_at,
+            run: Some((session_id.to_string(), run.run_id.clone())),
+        });
+    }
+
+    if let Some(quota_mb) = settings.session_quota_mb {
+        let used = dir_size(&session_dir);
+        let (evicted, freed) = evict_oldest(&mut candidates, used, quota_mb * 1024 * 1024);
+        if !evicted.is_empty() {
+            log::info!(
+                "Evicted {} run logs ({freed} bytes) from session {session_id} over its quota",
+                evicted.len()
+            );
+            result.evicted_logs += evicted.len() as u32;
+            result.bytes_freed += freed;
+            mark_evicted_runs(app, &evicted);
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Flag runs whose logs were evicted, so history shows why their response is
+/// missing
+fn mark_evicted_runs(app: &AppHandle, evicted: &[Evictable]) {
+    let mut by_session: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for (session_id, run_id) in evicted.iter().filter_map(|c| c.run.as_ref()) {
+        by_session
+            .entry(session_id.as_str())
+            .or_default()
+            .insert(run_id.as_str());
+    }
+
+    for (session_id, run_ids) in by_session {
+        let result = load_metadata(app, session_id).and_then(|existing| {
+            let Some(existing) = existing else {
+                return Ok(());
+            };
+            with_metadata_mut(
+                app,
+                session_id,
+                &existing.worktree_id,
+                &existing.name,
+                existing.order,
+                |metadata| {
+                    for run in &mut metadata.runs {
+                        if run_ids.contains(run.run_id.as_str()) {
+                            run.log_evicted = true;
+                        }
+                    }
+                    Ok(())
+                },
+            )
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to flag evicted runs of session {session_id}: {e}");
+        }
+    }
+}
+
+/// Pasted files no session references (see `pastes::unreferenced_pastes`)
+fn asset_candidates(app: &AppHandle) -> Result<Vec<Evictable>, String> {
+    let paths = super::pastes::unreferenced_pastes(app)?;
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok().filter(|m| m.is_file())?;
+            let age_key = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            Some(Evictable {
+                paths: vec![path],
+                bytes: metadata.len(),
+                age_key,
+                run: None,
+            })
+        })
+        .collect())
+}
+
+fn maintain_storage(app: &AppHandle) -> Result<StorageMaintenanceResult, String> {
+    crate::instance::ensure_writer()?;
+    let _guard = MAINTENANCE_LOCK.lock().unwrap();
+    let settings = load_storage_settings(app);
+    let mut result = StorageMaintenanceResult::default();
+    let projects = crate::projects::storage::load_projects_data(app)?;
+
+    let mut candidates = Vec::new();
+    for session_id in list_all_session_ids(app)? {
+        match maintain_session(app, &session_id, &settings, &projects, &mut result) {
+            Ok(session_candidates) => candidates.extend(session_candidates),
+            Err(e) => log::warn!("Storage maintenance failed for session {session_id}: {e}"),
+        }
+    }
+
+    match super::pastes::collect_paste_garbage(app) {
+        Ok(collected) => {
+            result.collected_assets += collected.removed;
+            result.bytes_freed += collected.bytes_freed;
+        }
+        Err(e) => log::warn!("Pasted file collection failed: {e}"),
+    }
+
+    if let Some(quota_mb) = settings.global_quota_mb {
+        let app_data_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+        let used = dir_size(&get_data_dir(app)?) + assets_size(&app_data_dir);
+        match asset_candidates(app) {
+            Ok(assets) => candidates.extend(assets),
+            // Without knowing what's referenced, only run logs are evicted
+            Err(e) => log::warn!("Failed to find unreferenced pasted files: {e}"),
+        }
+
+        let (evicted, freed) = evict_oldest(&mut candidates, used, quota_mb * 1024 * 1024);
+        if !evicted.is_empty() {
+            let assets = evicted.iter().filter(|c| c.run.is_none()).count() as u32;
+            log::info!(
+                "Evicted {} files ({freed} bytes) over the global storage quota",
+                evicted.len()
+            );
+            result.evicted_assets += assets;
+            result.evicted_logs += evicted.len() as u32 - assets;
+            result.bytes_freed += freed;
+            mark_evicted_runs(app, &evicted);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Compress old run logs and enforce the storage quotas now
+#[tauri::command]
+pub async fn run_storage_maintenance(app: AppHandle) -> Result<StorageMaintenanceResult, String> {
+    log::trace!("Running storage maintenance");
+    tokio::task::spawn_blocking(move || maintain_storage(&app))
+        .await
+        .map_err(|e| format!("Storage maintenance task failed: {e}"))?
+}
+
+/// Run storage maintenance in the background now and every few hours
+pub fn start_storage_maintenance(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        match maintain_storage(&app) {
+            Ok(result) => log::trace!("Storage maintenance complete: {result:?}"),
+            Err(e) => log::warn!("Storage maintenance failed: {e}"),
+        }
+        std::thread::sleep(MAINTENANCE_INTERVAL);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_compressed_run_log_reads_transparently() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+        fs::write(&path, "{\"type\":\"a\"}\n{\"type\":\"b\"}\n").unwrap();
+
+        compress_run_log(&path).unwrap();
+        assert!(!path.exists());
+        assert_eq!(
+            existing_run_log(&path),
+            Some(dir.path().join("run.jsonl.gz"))
+        );
+
+        let mut content = String::new();
+        open_run_log(&path)
+            .unwrap()
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "{\"type\":\"a\"}\n{\"type\":\"b\"}\n");
+
+        assert!(open_run_log(&dir.path().join("missing.jsonl"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_evict_oldest_until_under_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut candidates: Vec<Evictable> = [("new", 30), ("old", 10), ("mid", 20)]
+            .iter()
+            .map(|(name, age_key)| {
+                let path = dir.path().join(name);
+                fs::write(&path, [0u8; 100]).unwrap();
+                Evictable {
+                    paths: vec![path],
+                    bytes: 100,
+                    age_key: *age_key,
+                    run: None,
+                }
+            })
+            .collect();
+
+        let (evicted, freed) = evict_oldest(&mut candidates, 350, 200);
+        assert_eq!(freed, 200);
+        assert_eq!(evicted.len(), 2);
+        assert!(!dir.path().join("old").exists());
+        assert!(!dir.path().join("mid").exists());
+        assert!(dir.path().join("new").exists());
+
+        let (evicted, _) = evict_oldest(&mut candidates, 150, 200);
+        assert!(evicted.is_empty());
+    }
+}
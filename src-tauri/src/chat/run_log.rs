@@ -14,7 +14,8 @@ use super::storage::{
     get_session_dir, list_all_session_ids, load_metadata, save_metadata, with_metadata_mut,
 };
 use super::types::{
-    Backend, ChatMessage, ContentBlock, MessageRole, RunEntry, RunStatus, ToolCall, UsageData,
+    Backend, CancelReason, ChatMessage, CompactMetadata, ContentBlock, ContextWindowUsage,
+    MessageRole, RunChanges, RunCheckpoint, RunEntry, RunStatus, ToolCall, UsageData,
 };
 
 // ============================================================================
@@ -68,6 +69,7 @@ impl RunLogWriter {
         let now = now_timestamp();
         let run_id = self.run_id.clone();
         let claude_sid = claude_session_id.map(|s| s.to_string());
+        let pricing = super::cost::load_pricing_table(&self.app);
 
         with_metadata_mut(
             &self.app,
@@ -76,12 +78,14 @@ impl RunLogWriter {
             &self.session_name,
             self.order,
             |metadata| {
+                let session_backend = metadata.backend.clone();
                 if let Some(run) = metadata.find_run_mut(&run_id) {
                     run.status = RunStatus::Completed;
                     run.ended_at = Some(now);
                     run.assistant_message_id = Some(assistant_message_id.to_string());
                     run.claude_session_id = claude_sid.clone();
                     run.usage = usage.clone();
+                    run.cost_usd = pricing.run_cost(run, &session_backend);
                 }
 
                 // Update metadata's claude_session_id for resumption
@@ -94,6 +98,9 @@ impl RunLogWriter {
         )?;
 
         log::trace!("Run completed: {}", self.run_id);
+
+        // Keep the full-text search index current
+        super::search::schedule_index_run(&self.app, &self.session_id, &self.run_id);
         Ok(())
     }
 
@@ -117,7 +124,11 @@ impl RunLogWriter {
             self.order,
             |metadata| {
                 if let Some(run) = metadata.find_run_mut(&run_id) {
-                    run.status = RunStatus::Cancelled;
+                    // Keep TimedOut if the watchdog killed the run
+                    run.status = run
+                        .cancel_reason
+                        .as_ref()
+                        .map_or(RunStatus::Cancelled, CancelReason::run_status);
                     run.ended_at = Some(now);
                     run.cancelled = true;
                     run.assistant_message_id = asst_id;
@@ -186,6 +197,78 @@ impl RunLogWriter {
         Ok(())
     }
 
+    /// Record the worktree snapshot taken before this run
+    pub fn set_checkpoint(&mut self, checkpoint: RunCheckpoint) -> Result<(), String> {
+        let run_id = self.run_id.clone();
+
+        with_metadata_mut(
+            &self.app,
+            &self.session_id,
+            &self.worktree_id,
+            &self.session_name,
+            self.order,
+            |metadata| {
+                if let Some(run) = metadata.find_run_mut(&run_id) {
+                    run.checkpoint = Some(checkpoint);
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Record the files this run changed
+    pub fn set_changes(&mut self, changes: RunChanges) -> Result<(), String> {
+        let run_id = self.run_id.clone();
+
+        with_metadata_mut(
+            &self.app,
+            &self.session_id,
+            &self.worktree_id,
+            &self.session_name,
+            self.order,
+            |metadata| {
+                if let Some(run) = metadata.find_run_mut(&run_id) {
+                    run.changes = Some(changes);
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Record the context compactions that happened during this run
+    pub fn set_compactions(&mut self, compactions: Vec<CompactMetadata>) -> Result<(), String> {
+        let run_id = self.run_id.clone();
+
+        with_metadata_mut(
+            &self.app,
+            &self.session_id,
+            &self.worktree_id,
+            &self.session_name,
+            self.order,
+            |metadata| {
+                if let Some(run) = metadata.find_run_mut(&run_id) {
+                    run.compactions = compactions;
+                }
+                Ok(())
+            },
+        )
+    }
+
+    /// Record the session's context-window fill at the end of this run
+    pub fn set_context_window(&mut self, usage: ContextWindowUsage) -> Result<(), String> {
+        with_metadata_mut(
+            &self.app,
+            &self.session_id,
+            &self.worktree_id,
+            &self.session_name,
+            self.order,
+            |metadata| {
+                metadata.context_window = Some(usage);
+                Ok(())
+            },
+        )
+    }
+
     /// Get the path to the JSONL output file for this run
     pub fn output_file_path(&self) -> Result<PathBuf, String> {
         let session_dir = get_session_dir(&self.app, &self.session_id)?;
@@ -199,7 +282,6 @@ impl RunLogWriter {
     }
 
     /// Get the session ID
-    #[allow(dead_code)] // Will be used when detached streaming is fully connected
     pub fn session_id(&self) -> &str {
         &self.session_id
     }
@@ -276,6 +358,8 @@ pub fn start_run(
     execution_mode: Option<&str>,
     thinking_level: Option<&str>,
     effort_level: Option<&str>,
+    backend: &Backend,
+    provider: Option<&str>,
 ) -> Result<RunLogWriter, String> {
     let run_id = Uuid::new_v4().to_string();
     let now = now_timestamp();
@@ -328,6 +412,14 @@ pub fn start_run(
         claude_session_id: None,
         pid: None,   // Set later via set_pid() after spawning detached process
         usage: None, // Set on completion via complete()
+        backend: Some(backend.clone()),
+        provider: provider.map(|s| s.to_string()),
+        cost_usd: None, // Set on completion via complete()
+        cancel_reason: None,
+        checkpoint: None, // Set via set_checkpoint() before the agent starts
+        changes: None,    // Set via set_changes() when the run ends
+        compactions: vec![],
+        log_evicted: false,
     };
 
     with_metadata_mut(
@@ -428,7 +520,7 @@ pub fn get_run_log_path(
     Ok(session_dir.join(format!("{run_id}.jsonl")))
 }
 
-/// Read all lines from a run's JSONL file
+/// Read all lines from a run's JSONL file (plain or gzipped)
 pub fn read_run_log(
     app: &tauri::AppHandle,
     session_id: &str,
@@ -436,13 +528,11 @@ pub fn read_run_log(
 ) -> Result<Vec<String>, String> {
     let path = get_run_log_path(app, session_id, run_id)?;
 
-    if !path.exists() {
+    // Old runs may have been gzipped by storage maintenance
+    let Some(reader) = super::retention::open_run_log(&path)? else {
         return Ok(vec![]);
-    }
-
-    let file = File::open(&path).map_err(|e| format!("Failed to open run log: {e}"))?;
+    };
 
-    let reader = BufReader::new(file);
     let lines: Result<Vec<_>, _> = reader.lines().collect();
 
     lines.map_err(|e| format!("Failed to read run log: {e}"))
@@ -584,6 +674,14 @@ pub fn parse_run_to_message(lines: &[String], run: &RunEntry) -> Result<ChatMess
                     }
                 }
             }
+            "system" => {
+                if let Some(metadata) = super::compaction::parse_compact_boundary(&msg) {
+                    content_blocks.push(ContentBlock::Compaction {
+                        trigger: metadata.trigger,
+                        pre_tokens: metadata.pre_tokens,
+                    });
+                }
+            }
             "result" => {
                 // Use result if we somehow missed content
                 if content.is_empty() {
@@ -658,6 +756,22 @@ pub fn parse_run_to_message(lines: &[String], run: &RunEntry) -> Result<ChatMess
 // Message Loading
 // ============================================================================
 
+/// Parse a single run's JSONL log into its assistant message (routed by the
+/// backend that produced it; `backend` is used for runs that don't record one)
+pub fn load_run_message(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    run: &RunEntry,
+    backend: &Backend,
+) -> Result<ChatMessage, String> {
+    let lines = read_run_log(app, session_id, &run.run_id)?;
+    let backend = run.backend.as_ref().unwrap_or(backend);
+
+    let mut assistant_msg = super::backend::backend_for(backend).parse_run(&lines, run)?;
+    assistant_msg.session_id = session_id.to_string();
+    Ok(assistant_msg)
+}
+
 /// Load all messages for a session by parsing JSONL files
 /// Returns messages in chronological order (user message, then assistant response)
 pub fn load_session_messages(
@@ -702,15 +816,7 @@ pub fn load_session_messages(
         // (ExitPlanMode/AskUserQuestion blocked the CLI — JSONL has complete content up to the block)
         let include_waiting_run = run.status == RunStatus::Running && metadata.waiting_for_input;
         if (run.status != RunStatus::Running || include_waiting_run) && !is_undo_send {
-            let lines = read_run_log(app, session_id, &run.run_id)?;
-
-            // Parse JSONL content — route by backend
-            let mut assistant_msg = if metadata.backend == Backend::Codex {
-                super::codex::parse_codex_run_to_message(&lines, run)?
-            } else {
-                parse_run_to_message(&lines, run)?
-            };
-            assistant_msg.session_id = session_id.to_string();
+            let mut assistant_msg = load_run_message(app, session_id, run, &metadata.backend)?;
 
             // For crashed runs with no content (only metadata header), add placeholder
             if run.status == RunStatus::Crashed
@@ -720,6 +826,14 @@ pub fn load_session_messages(
                 assistant_msg.content =
                     "*Response lost - Jean was closed before receiving a response.*".to_string();
             }
+            if run.log_evicted
+                && assistant_msg.content.is_empty()
+                && assistant_msg.tool_calls.is_empty()
+            {
+                assistant_msg.content =
+                    "*Response removed - this run's log was deleted by the storage quota.*"
+                        .to_string();
+            }
 
             messages.push(assistant_msg);
         }
@@ -731,7 +845,11 @@ pub fn load_session_messages(
 /// Mark any running run for this session as cancelled (called by cancel_process)
 /// This is called synchronously when the user cancels, before emitting chat:cancelled event.
 /// This ensures the metadata is updated immediately, not after tail_claude_output times out.
-pub fn mark_running_run_cancelled(app: &tauri::AppHandle, session_id: &str) -> Result<(), String> {
+pub fn mark_running_run_cancelled(
+    app: &tauri::AppHandle,
+    session_id: &str,
+    reason: &CancelReason,
+) -> Result<(), String> {
     let mut metadata = match load_metadata(app, session_id)? {
         Some(m) => m,
         None => return Ok(()), // No metadata = nothing to cancel
@@ -742,9 +860,10 @@ pub fn mark_running_run_cancelled(app: &tauri::AppHandle, session_id: &str) -> R
 
     for run in &mut metadata.runs {
         if run.status == RunStatus::Running {
-            run.status = RunStatus::Cancelled;
+            run.status = reason.run_status();
             run.ended_at = Some(now);
             run.cancelled = true;
+            run.cancel_reason = Some(reason.clone());
             // Leave assistant_message_id as None (undo_send case)
             modified = true;
             log::trace!(
@@ -953,7 +1072,8 @@ pub fn mark_run_as_crashed(
 // Cleanup Functions
 // ============================================================================
 
-/// Delete all JSONL files for a session (called when deleting session)
+/// Delete all JSONL files (and their timing sidecars) for a session (called
+/// when deleting session)
 #[allow(dead_code)]
 pub fn delete_run_logs(app: &tauri::AppHandle, session_id: &str) -> Result<usize, String> {
     let session_dir = get_session_dir(app, session_id)?;
@@ -966,7 +1086,12 @@ pub fn delete_run_logs(app: &tauri::AppHandle, session_id: &str) -> Result<usize
             .flatten()
         {
             let path = entry.path();
-            if path.extension().is_some_and(|ext| ext == "jsonl") {
+            if path.extension().is_some_and(|ext| ext == "timing") {
+                let _ = fs::remove_file(&path);
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "jsonl" || ext == "gz")
+            {
                 fs::remove_file(&path).map_err(|e| format!("Failed to delete run log: {e}"))?;
                 deleted += 1;
             }
@@ -0,0 +1,783 @@
+//! Full-text search across session transcripts
+//!
+//! An incremental on-disk index under `sessions/search/`:
+//! - `manifest.json`: which runs/plans are indexed per session, plus term → session postings
+//! - `docs/{session_id}.json`: searchable documents (user messages, assistant text,
+//!   tool inputs/outputs, plan files), each linking back to its message
+//!
+//! Runs are indexed when `RunLogWriter::complete` runs; anything missed (older
+//! history, forks, recovered runs) is picked up by a catch-up pass before each search.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::run_log::load_run_message;
+use super::storage::{get_sessions_dir, list_all_session_ids, load_metadata};
+use super::types::{Backend, RunEntry, RunStatus, SessionMetadata};
+
+/// Serializes all index reads-modify-writes (manifest + doc files)
+static SEARCH_INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+const SEARCH_INDEX_VERSION: u32 = 1;
+const DEFAULT_RESULT_LIMIT: usize = 50;
+const SNIPPET_CONTEXT_CHARS: usize = 80;
+const MAX_TOKEN_CHARS: usize = 64;
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// What part of a transcript a search document came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDocumentKind {
+    UserMessage,
+    AssistantText,
+    ToolInput,
+    ToolOutput,
+    Plan,
+}
+
+impl SearchDocumentKind {
+    /// Ranking boost - conversation text ranks above tool noise
+    fn weight(&self) -> f64 {
+        match self {
+            SearchDocumentKind::UserMessage => 3.0,
+            SearchDocumentKind::AssistantText | SearchDocumentKind::Plan => 2.0,
+            SearchDocumentKind::ToolInput => 1.0,
+            SearchDocumentKind::ToolOutput => 0.5,
+        }
+    }
+}
+
+/// A single searchable unit of text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SearchDocument {
+    run_id: String,
+    message_id: String,
+    kind: SearchDocumentKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+    /// Plan file path (for Plan documents)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    text: String,
+    timestamp: u64,
+}
+
+/// Index state for one session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct IndexedSession {
+    #[serde(default)]
+    indexed_runs: HashSet<String>,
+}
+
+/// Index manifest (sessions/search/manifest.json)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SearchManifest {
+    version: u32,
+    #[serde(default)]
+    sessions: HashMap<String, IndexedSession>,
+    /// Term → session IDs containing it (prunes which doc files to scan)
+    #[serde(default)]
+    postings: HashMap<String, HashSet<String>>,
+}
+
+impl Default for SearchManifest {
+    fn default() -> Self {
+        Self {
+            version: SEARCH_INDEX_VERSION,
+            sessions: HashMap::new(),
+            postings: HashMap::new(),
+        }
+    }
+}
+
+/// Optional filters for search_sessions
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchFilters {
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub worktree_id: Option<String>,
+    #[serde(default)]
+    pub backend: Option<Backend>,
+    /// Label name (case-insensitive)
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Only documents at or after this unix timestamp
+    #[serde(default)]
+    pub from: Option<u64>,
+    /// Only documents at or before this unix timestamp
+    #[serde(default)]
+    pub to: Option<u64>,
+    /// Restrict to these document kinds (None = all)
+    #[serde(default)]
+    pub kinds: Option<Vec<SearchDocumentKind>>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A search hit linking back to the exact message
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub session_id: String,
+    pub session_name: String,
+    pub worktree_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<String>,
+    pub backend: Backend,
+    pub run_id: String,
+    pub message_id: String,
+    pub kind: SearchDocumentKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub snippet: String,
+    pub timestamp: u64,
+    pub score: f64,
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Get the search index directory (creates if not exists)
+/// Structure: sessions/search/
+fn get_search_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = get_sessions_dir(app)?.join("search");
+    fs::create_dir_all(dir.join("docs"))
+        .map_err(|e| format!("Failed to create search index directory: {e}"))?;
+    Ok(dir)
+}
+
+fn docs_path(search_dir: &Path, session_id: &str) -> PathBuf {
+    search_dir.join("docs").join(format!("{session_id}.json"))
+}
+
+fn read_json_or_default<T: serde::de::DeserializeOwned + Default>(path: &Path) -> T {
+    File::open(path)
+        .ok()
+        .and_then(|f| serde_json::from_reader(BufReader::new(f)).ok())
+        .unwrap_or_default()
+}
+
+/// Atomic write (temp file + rename)
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let temp_path = path.with_extension("tmp");
+    let file = File::create(&temp_path)
+        .map_err(|e| format!("Failed to create temp search index file: {e}"))?;
+    serde_json::to_writer(BufWriter::new(file), value)
+        .map_err(|e| format!("Failed to write search index: {e}"))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("Failed to rename search index file: {e}"))
+}
+
+fn load_manifest(search_dir: &Path) -> SearchManifest {
+    let manifest: SearchManifest = read_json_or_default(&search_dir.join("manifest.json"));
+    if manifest.version != SEARCH_INDEX_VERSION {
+        // Format changed - rebuild from scratch via catch-up
+        return SearchManifest::default();
+    }
+    manifest
+}
+
+// ============================================================================
+// Tokenizing & Extraction
+// ============================================================================
+
+/// Split text into lowercase search terms
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|t| t.chars().count() >= 2)
+        .map(|t| {
+            t.chars()
+                .take(MAX_TOKEN_CHARS)
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .collect()
+}
+
+/// Flatten a tool input JSON value into searchable text (string values only)
+fn flatten_json_text(value: &serde_json::Value, out: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(s) => out.push(s.clone()),
+        serde_json::Value::Array(items) => items.iter().for_each(|v| flatten_json_text(v, out)),
+        serde_json::Value::Object(map) => map.values().for_each(|v| flatten_json_text(v, out)),
+        _ => {}
+    }
+}
+
+/// Whether a written file path is a plan file
+pub(super) fn is_plan_path(path: &str) -> bool {
+    path.ends_with(".md") && path.replace('\\', "/").contains("/plans/")
+}
+
+/// Build search documents for a finished run
+fn build_run_documents(
+    app: &AppHandle,
+    metadata: &SessionMetadata,
+    run: &RunEntry,
+) -> Result<Vec<SearchDocument>, String> {
+    let mut docs = Vec::new();
+    let is_undo_send = run.status == RunStatus::Cancelled && run.assistant_message_id.is_none();
+    if is_undo_send {
+        return Ok(docs);
+    }
+
+    docs.push(SearchDocument {
+        run_id: run.run_id.clone(),
+        message_id: run.user_message_id.clone(),
+        kind: SearchDocumentKind::UserMessage,
+        tool_call_id: None,
+        tool_name: None,
+        path: None,
+        text: run.user_message.clone(),
+        timestamp: run.started_at,
+    });
+
+    let message = load_run_message(app, &metadata.id, run, &metadata.backend)?;
+    let timestamp = run.ended_at.unwrap_or(run.started_at);
+    let doc = |kind, text: String| SearchDocument {
+        run_id: run.run_id.clone(),
+        message_id: message.id.clone(),
+        kind,
+        tool_call_id: None,
+        tool_name: None,
+        path: None,
+        text,
+        timestamp,
+    };
+
+    if !message.content.trim().is_empty() {
+        docs.push(doc(
+            SearchDocumentKind::AssistantText,
+            message.content.clone(),
+        ));
+    }
+
+    let mut plan_paths = Vec::new();
+    for tool in &message.tool_calls {
+        let mut parts = Vec::new();
+        flatten_json_text(&tool.input, &mut parts);
+        if !parts.is_empty() {
+            docs.push(SearchDocument {
+                tool_call_id: Some(tool.id.clone()),
+                tool_name: Some(tool.name.clone()),
+                ..doc(SearchDocumentKind::ToolInput, parts.join("\n"))
+            });
+        }
+        if let Some(ref output) = tool.output {
+            if !output.trim().is_empty() {
+                docs.push(SearchDocument {
+                    tool_call_id: Some(tool.id.clone()),
+                    tool_name: Some(tool.name.clone()),
+                    ..doc(SearchDocumentKind::ToolOutput, output.clone())
+                });
+            }
+        }
+        if tool.name == "Write" || tool.name == "Edit" {
+            if let Some(path) = tool.input.get("file_path").and_then(|v| v.as_str()) {
+                if is_plan_path(path) && !plan_paths.iter().any(|p| p == path) {
+                    plan_paths.push(path.to_string());
+                }
+            }
+        }
+    }
+
+    for path in plan_paths {
+        if let Ok(content) = fs::read_to_string(&path) {
+            docs.push(SearchDocument {
+                path: Some(path),
+                ..doc(SearchDocumentKind::Plan, content)
+            });
+        }
+    }
+
+    Ok(docs)
+}
+
+// ============================================================================
+// Indexing
+// ============================================================================
+
+/// Add documents for the given runs of a session to the index.
+/// Plan documents replace older versions of the same plan file.
+fn index_session_runs(
+    search_dir: &Path,
+    manifest: &mut SearchManifest,
+    session_id: &str,
+    run_ids: Vec<String>,
+    new_docs: Vec<SearchDocument>,
+) -> Result<(), String> {
+    let path = docs_path(search_dir, session_id);
+    let mut docs: Vec<SearchDocument> = read_json_or_default(&path);
+
+    let new_plan_paths: HashSet<&str> = new_docs.iter().filter_map(|d| d.path.as_deref()).collect();
+    docs.retain(|d| {
+        d.path
+            .as_deref()
+            .is_none_or(|p| !new_plan_paths.contains(p))
+    });
+
+    for doc in &new_docs {
+        for term in tokenize(&doc.text) {
+            manifest
+                .postings
+                .entry(term)
+                .or_default()
+                .insert(session_id.to_string());
+        }
+    }
+    docs.extend(new_docs);
+    write_json(&path, &docs)?;
+
+    manifest
+        .sessions
+        .entry(session_id.to_string())
+        .or_default()
+        .indexed_runs
+        .extend(run_ids);
+    Ok(())
+}
+
+/// Index any finished runs of a session that aren't in the index yet.
+/// Returns true if anything was added.
+fn catch_up_session(
+    app: &AppHandle,
+    search_dir: &Path,
+    manifest: &mut SearchManifest,
+    metadata: &SessionMetadata,
+) -> Result<bool, String> {
+    let indexed = manifest
+        .sessions
+        .get(&metadata.id)
+        .map(|s| s.indexed_runs.clone())
+        .unwrap_or_default();
+
+    let mut run_ids = Vec::new();
+    let mut docs = Vec::new();
+    for run in &metadata.runs {
+        if indexed.contains(&run.run_id)
+            || matches!(run.status, RunStatus::Running | RunStatus::Resumable)
+        {
+            continue;
+        }
+        match build_run_documents(app, metadata, run) {
+            Ok(run_docs) => docs.extend(run_docs),
+            Err(e) => log::warn!("Failed to index run {}: {e}", run.run_id),
+        }
+        run_ids.push(run.run_id.clone());
+    }
+
+    if run_ids.is_empty() {
+        return Ok(false);
+    }
+    index_session_runs(search_dir, manifest, &metadata.id, run_ids, docs)?;
+    Ok(true)
+}
+
+/// Index a single session's pending runs (used after run completion)
+fn index_session(app: &AppHandle, session_id: &str) -> Result<(), String> {
+    // Load metadata before taking the index lock (never hold both)
+    let Some(metadata) = load_metadata(app, session_id)? else {
+        return Ok(());
+    };
+
+    let _guard = SEARCH_INDEX_LOCK.lock().unwrap();
+    let search_dir = get_search_dir(app)?;
+    let mut manifest = load_manifest(&search_dir);
+    if catch_up_session(app, &search_dir, &mut manifest, &metadata)? {
+        write_json(&search_dir.join("manifest.json"), &manifest)?;
+    }
+    Ok(())
+}
+
+/// Index a completed run in the background so completion isn't delayed
+pub fn schedule_index_run(app: &AppHandle, session_id: &str, run_id: &str) {
+    let app = app.clone();
+    let session_id = session_id.to_string();
+    let run_id = run_id.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = index_session(&app, &session_id) {
+            log::warn!("Failed to index run {run_id} for search: {e}");
+        } else {
+            log::trace!("Indexed run {run_id} for search");
+        }
+    });
+}
+
+/// Bring the whole index up to date: index unindexed runs and drop deleted sessions
+fn catch_up_all(app: &AppHandle) -> Result<(), String> {
+    let session_ids = list_all_session_ids(app)?;
+    let metadatas: Vec<SessionMetadata> = session_ids
+        .iter()
+        .filter_map(|id| load_metadata(app, id).ok().flatten())
+        .collect();
+
+    let _guard = SEARCH_INDEX_LOCK.lock().unwrap();
+    let search_dir = get_search_dir(app)?;
+    let mut manifest = load_manifest(&search_dir);
+    let mut modified = false;
+
+    // Drop sessions whose data was deleted
+    let existing: HashSet<&str> = session_ids.iter().map(|s| s.as_str()).collect();
+    let removed: Vec<String> = manifest
+        .sessions
+        .keys()
+        .filter(|id| !existing.contains(id.as_str()))
+        .cloned()
+        .collect();
+    if !removed.is_empty() {
+        for id in &removed {
+            manifest.sessions.remove(id);
+            let _ = fs::remove_file(docs_path(&search_dir, id));
+        }
+        manifest.postings.retain(|_, sessions| {
+            sessions.retain(|s| !removed.contains(s));
+            !sessions.is_empty()
+        });
+        modified = true;
+    }
+
+    for metadata in &metadatas {
+        modified |= catch_up_session(app, &search_dir, &mut manifest, metadata)?;
+    }
+
+    if modified {
+        write_json(&search_dir.join("manifest.json"), &manifest)?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// Querying
+// ============================================================================
+
+/// Whether every query term matches a document token (the last term as a prefix,
+/// so results update while typing). Returns the number of matching tokens.
+fn match_terms(doc_tokens: &[String], terms: &[String]) -> Option<usize> {
+    let mut hits = 0;
+    for (i, term) in terms.iter().enumerate() {
+        let is_last = i == terms.len() - 1;
+        let count = doc_tokens
+            .iter()
+            .filter(|t| {
+                if is_last {
+                    t.starts_with(term.as_str())
+                } else {
+                    *t == term
+                }
+            })
+            .count();
+        if count == 0 {
+            return None;
+        }
+        hits += count;
+    }
+    Some(hits)
+}
+
+/// Build a short snippet around the first occurrence of a term
+fn make_snippet(text: &str, term: &str) -> String {
+    let lower = text.to_lowercase();
+    // to_lowercase can change byte lengths; fall back to the start of the text
+    let char_pos = lower
+        .find(term)
+        .filter(|_| lower.len() == text.len())
+        .map(|byte_pos| text[..byte_pos].chars().count())
+        .unwrap_or(0);
+
+    let start = char_pos.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let total = text.chars().count();
+    let end = (char_pos + term.chars().count() + SNIPPET_CONTEXT_CHARS).min(total);
+    let mut snippet: String = text
+        .chars()
+        .skip(start)
+        .take(end - start)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if start > 0 {
+        snippet.insert_str(0, "…");
+    }
+    if end < total {
+        snippet.push('…');
+    }
+    snippet
+}
+
+/// Sessions that contain every term (the last term as a prefix)
+fn candidate_sessions(manifest: &SearchManifest, terms: &[String]) -> HashSet<String> {
+    let mut candidates: Option<HashSet<String>> = None;
+    for (i, term) in terms.iter().enumerate() {
+        let is_last = i == terms.len() - 1;
+        let mut sessions = HashSet::new();
+        if is_last {
+            for (key, ids) in &manifest.postings {
+                if key.starts_with(term.as_str()) {
+                    sessions.extend(ids.iter().cloned());
+                }
+            }
+        } else if let Some(ids) = manifest.postings.get(term) {
+            sessions.extend(ids.iter().cloned());
+        }
+        candidates = Some(match candidates {
+            Some(existing) => existing.intersection(&sessions).cloned().collect(),
+            None => sessions,
+        });
+    }
+    candidates.unwrap_or_default()
+}
+
+/// Search all session transcripts
+#[tauri::command]
+pub async fn search_sessions(
+    app: AppHandle,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<SearchResult>, String> {
+    let filters = filters.unwrap_or_default();
+    let terms = tokenize(&query);
+    if terms.is_empty() {
+        return Ok(vec![]);
+    }
+    log::trace!("Searching sessions for {terms:?}");
+
+    tokio::task::spawn_blocking(move || search_blocking(&app, &terms, &filters))
+        .await
+        .map_err(|e| format!("Search task failed: {e}"))?
+}
+
+fn search_blocking(
+    app: &AppHandle,
+    terms: &[String],
+    filters: &SearchFilters,
+) -> Result<Vec<SearchResult>, String> {
+    catch_up_all(app)?;
+
+    let (search_dir, candidates) = {
+        let _guard = SEARCH_INDEX_LOCK.lock().unwrap();
+        let search_dir = get_search_dir(app)?;
+        let manifest = load_manifest(&search_dir);
+        (search_dir, candidate_sessions(&manifest, terms))
+    };
+
+    let worktree_projects: HashMap<String, String> =
+        crate::projects::storage::load_projects_data(app)
+            .map(|data| {
+                data.worktrees
+                    .iter()
+                    .map(|w| (w.id.clone(), w.project_id.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+    let mut results = Vec::new();
+    for session_id in candidates {
+        let Some(metadata) = load_metadata(app, &session_id)? else {
+            continue;
+        };
+        let project_id = worktree_projects.get(&metadata.worktree_id).cloned();
+
+        if filters
+            .worktree_id
+            .as_ref()
+            .is_some_and(|w| *w != metadata.worktree_id)
+            || filters
+                .project_id
+                .as_ref()
+                .is_some_and(|p| project_id.as_ref() != Some(p))
+            || filters
+                .backend
+                .as_ref()
+                .is_some_and(|b| *b != metadata.backend)
+            || filters.label.as_ref().is_some_and(|l| {
+                metadata
+                    .label
+                    .as_ref()
+                    .is_none_or(|label| !label.name.eq_ignore_ascii_case(l))
+            })
+        {
+            continue;
+        }
+
+        let docs: Vec<SearchDocument> = {
+            let _guard = SEARCH_INDEX_LOCK.lock().unwrap();
+            read_json_or_default(&docs_path(&search_dir, &session_id))
+        };
+        for doc in docs {
+            if filters.from.is_some_and(|from| doc.timestamp < from)
+                || filters.to.is_some_and(|to| doc.timestamp > to)
+                || filters
+                    .kinds
+                    .as_ref()
+                    .is_some_and(|kinds| !kinds.contains(&doc.kind))
+            {
+                continue;
+            }
+            let Some(hits) = match_terms(&tokenize(&doc.text), terms) else {
+                continue;
+            };
+            results.push(SearchResult {
+                session_id: metadata.id.clone(),
+                session_name: metadata.name.clone(),
+                worktree_id: metadata.worktree_id.clone(),
+                project_id: project_id.clone(),
+                backend: metadata.backend.clone(),
+                run_id: doc.run_id,
+                message_id: doc.message_id,
+                kind: doc.kind,
+                tool_call_id: doc.tool_call_id,
+                tool_name: doc.tool_name,
+                path: doc.path,
+                snippet: make_snippet(&doc.text, &terms[0]),
+                timestamp: doc.timestamp,
+                score: hits as f64 * doc.kind.weight(),
+            });
+        }
+    }
+
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.timestamp.cmp(&a.timestamp))
+    });
+    results.truncate(filters.limit.unwrap_or(DEFAULT_RESULT_LIMIT));
+    Ok(results)
+}
+
+/// Drop the search index and rebuild it from all session run logs
+#[tauri::command]
+pub async fn rebuild_search_index(app: AppHandle) -> Result<(), String> {
+    log::trace!("Rebuilding search index");
+    tokio::task::spawn_blocking(move || {
+        {
+            let _guard = SEARCH_INDEX_LOCK.lock().unwrap();
+            let search_dir = get_search_dir(&app)?;
+            fs::remove_dir_all(&search_dir)
+                .map_err(|e| format!("Failed to clear search index: {e}"))?;
+        }
+        catch_up_all(&app)
+    })
+    .await
+    .map_err(|e| format!("Search index task failed: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(kind: SearchDocumentKind, text: &str, path: Option<&str>) -> SearchDocument {
+        SearchDocument {
+            run_id: "run-1".to_string(),
+            message_id: "msg-1".to_string(),
+            kind,
+            tool_call_id: None,
+            tool_name: None,
+            path: path.map(|p| p.to_string()),
+            text: text.to_string(),
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Fix the parse_run bug in src/main.rs, a b"),
+            vec!["fix", "the", "parse_run", "bug", "in", "src", "main", "rs"]
+        );
+    }
+
+    #[test]
+    fn test_flatten_json_text() {
+        let mut out = Vec::new();
+        flatten_json_text(
+            &serde_json::json!({ "command": "cargo test", "opts": ["--all", 3], "n": 1 }),
+            &mut out,
+        );
+        out.sort();
+        assert_eq!(out, vec!["--all", "cargo test"]);
+    }
+
+    #[test]
+    fn test_match_terms_last_term_is_prefix() {
+        let tokens = tokenize("refactor the tailer polling loop");
+        assert!(match_terms(&tokens, &["tailer".to_string(), "poll".to_string()]).is_some());
+        assert!(match_terms(&tokens, &["tail".to_string(), "loop".to_string()]).is_none());
+        assert!(match_terms(&tokens, &["missing".to_string()]).is_none());
+    }
+
+    #[test]
+    fn test_make_snippet() {
+        let text = format!("{} needle {}", "a".repeat(200), "b".repeat(200));
+        let snippet = make_snippet(&text, "needle");
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+        assert_eq!(make_snippet("short text", "text"), "short text");
+    }
+
+    #[test]
+    fn test_is_plan_path() {
+        assert!(is_plan_path("/Users/me/.claude/plans/fancy-plan.md"));
+        assert!(!is_plan_path("/repo/docs/plans.md"));
+        assert!(!is_plan_path("/repo/plans/data.json"));
+    }
+
+    #[test]
+    fn test_index_session_runs_and_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        let mut manifest = SearchManifest::default();
+
+        index_session_runs(
+            dir.path(),
+            &mut manifest,
+            "s1",
+            vec!["run-1".to_string()],
+            vec![
+                doc(SearchDocumentKind::UserMessage, "add retry logic", None),
+                doc(SearchDocumentKind::Plan, "old plan", Some("/p/plans/x.md")),
+            ],
+        )
+        .unwrap();
+        index_session_runs(
+            dir.path(),
+            &mut manifest,
+            "s1",
+            vec!["run-2".to_string()],
+            vec![doc(
+                SearchDocumentKind::Plan,
+                "new plan",
+                Some("/p/plans/x.md"),
+            )],
+        )
+        .unwrap();
+
+        let docs: Vec<SearchDocument> = read_json_or_default(&docs_path(dir.path(), "s1"));
+        assert_eq!(docs.len(), 2);
+        assert!(docs.iter().any(|d| d.text == "new plan"));
+        assert!(!docs.iter().any(|d| d.text == "old plan"));
+        assert_eq!(manifest.sessions["s1"].indexed_runs.len(), 2);
+
+        let hits = candidate_sessions(&manifest, &["add".to_string(), "ret".to_string()]);
+        assert!(hits.contains("s1"));
+        let misses = candidate_sessions(&manifest, &["unrelated".to_string()]);
+        assert!(misses.is_empty());
+    }
+}
@@ -1,6 +1,5 @@
 use std::collections::HashMap;
-use std::fs::{self, File};
-use std::io::{BufReader, BufWriter};
+use std::fs;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
@@ -11,17 +10,19 @@ use super::types::{
     SavedContextsMetadata, Session, SessionIndexEntry, SessionMetadata, WorktreeIndex,
     WorktreeSessions,
 };
+use crate::db;
+use crate::instance::ensure_writer;
 
 // ============================================================================
 // Locking
 // ============================================================================
 
-/// Per-worktree mutex to prevent concurrent read-modify-write races on index files.
+/// Per-worktree mutex to prevent concurrent read-modify-write races on worktree indexes.
 /// Each worktree gets its own mutex so different worktrees don't block each other.
 static INDEX_LOCKS: Lazy<Mutex<HashMap<String, Arc<Mutex<()>>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
-/// Per-session mutex to prevent concurrent read-modify-write races on metadata files.
+/// Per-session mutex to prevent concurrent read-modify-write races on session metadata.
 /// Each session gets its own mutex so different sessions don't block each other.
 static METADATA_LOCKS: Lazy<Mutex<HashMap<String, Arc<Mutex<()>>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
@@ -84,17 +85,6 @@ pub fn get_sessions_dir(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(sessions_dir)
 }
 
-/// Get the index directory (creates if not exists)
-/// Structure: sessions/index/
-pub fn get_index_dir(app: &AppHandle) -> Result<PathBuf, String> {
-    let sessions_dir = get_sessions_dir(app)?;
-    let index_dir = sessions_dir.join("index");
-
-    fs::create_dir_all(&index_dir).map_err(|e| format!("Failed to create index directory: {e}"))?;
-
-    Ok(index_dir)
-}
-
 /// Get the data directory (creates if not exists)
 /// Structure: sessions/data/
 pub fn get_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
@@ -106,14 +96,6 @@ pub fn get_data_dir(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(data_dir)
 }
 
-/// Get the index file path for a worktree
-/// Path: sessions/index/{worktree_id}.json
-pub fn get_index_path(app: &AppHandle, worktree_id: &str) -> Result<PathBuf, String> {
-    let index_dir = get_index_dir(app)?;
-    let safe_id = sanitize_filename(worktree_id);
-    Ok(index_dir.join(format!("{safe_id}.json")))
-}
-
 /// Get the session data directory (creates if not exists)
 /// Path: sessions/data/{session_id}/
 pub fn get_session_dir(app: &AppHandle, session_id: &str) -> Result<PathBuf, String> {
@@ -126,67 +108,29 @@ pub fn get_session_dir(app: &AppHandle, session_id: &str) -> Result<PathBuf, Str
     Ok(session_dir)
 }
 
-/// Get the metadata file path for a session
-/// Path: sessions/data/{session_id}/metadata.json
-pub fn get_metadata_path(app: &AppHandle, session_id: &str) -> Result<PathBuf, String> {
-    let session_dir = get_session_dir(app, session_id)?;
-    Ok(session_dir.join("metadata.json"))
-}
-
-/// Get the path for a closed base session's preserved index file
-/// Path: sessions/index/base-{project_id}.json
-pub fn get_base_index_path(app: &AppHandle, project_id: &str) -> Result<PathBuf, String> {
-    let index_dir = get_index_dir(app)?;
-    let safe_id = sanitize_filename(project_id);
-    Ok(index_dir.join(format!("base-{safe_id}.json")))
-}
-
 // ============================================================================
 // Index Operations (WorktreeIndex)
 // ============================================================================
 
-/// Load a worktree index (internal, no locking)
-fn load_index_internal(app: &AppHandle, worktree_id: &str) -> Result<WorktreeIndex, String> {
-    let path = get_index_path(app, worktree_id)?;
-
-    if path.exists() {
-        let contents = fs::read_to_string(&path).map_err(|e| {
-            log::error!("Failed to read index file: {e}");
-            format!("Failed to read index: {e}")
-        })?;
-
-        let index: WorktreeIndex = serde_json::from_str(&contents).map_err(|e| {
-            log::error!("Failed to parse index JSON: {e}");
-            format!("Failed to parse index: {e}")
-        })?;
-
-        return Ok(index);
-    }
-
-    // No data exists - create new index with default session
-    log::trace!("No existing index found, creating default for worktree {worktree_id}");
-    Ok(WorktreeIndex::new(worktree_id.to_string()))
+/// Load a worktree index (internal, no locking). Returns None if the worktree
+/// has no index yet.
+fn load_index_internal(
+    app: &AppHandle,
+    worktree_id: &str,
+) -> Result<Option<WorktreeIndex>, String> {
+    db::with_connection(app, |conn| db::load_index(conn, worktree_id)).map_err(|e| {
+        log::error!("Failed to load index for worktree {worktree_id}: {e}");
+        e
+    })
 }
 
-/// Save a worktree index (internal, no locking - atomic write)
+/// Save a worktree index (internal, no locking)
 fn save_index_internal(app: &AppHandle, index: &WorktreeIndex) -> Result<(), String> {
+    ensure_writer()?;
     log::trace!("Saving index for worktree: {}", index.worktree_id);
-    let path = get_index_path(app, &index.worktree_id)?;
-    let temp_path = path.with_extension("tmp");
-
-    let json_content = serde_json::to_string_pretty(index).map_err(|e| {
-        log::error!("Failed to serialize index: {e}");
-        format!("Failed to serialize index: {e}")
-    })?;
-
-    fs::write(&temp_path, &json_content).map_err(|e| {
-        log::error!("Failed to write index file: {e}");
-        format!("Failed to write index: {e}")
-    })?;
-
-    fs::rename(&temp_path, &path).map_err(|e| {
-        log::error!("Failed to finalize index file: {e}");
-        format!("Failed to finalize index: {e}")
+    db::with_connection(app, |conn| db::save_index(conn, index)).map_err(|e| {
+        log::error!("Failed to save index: {e}");
+        e
     })?;
 
     log::trace!(
@@ -211,31 +155,26 @@ pub fn load_index(app: &AppHandle, worktree_id: &str) -> Result<WorktreeIndex, S
     let lock = get_index_lock(worktree_id);
     let _guard = lock.lock().unwrap();
 
-    let index = load_index_internal(app, worktree_id)?;
+    if let Some(index) = load_index_internal(app, worktree_id)? {
+        return Ok(index);
+    }
 
-    // If this was a new index file, save it
-    let index_path = get_index_path(app, worktree_id)?;
-    if !index_path.exists() {
+    // No data exists - create and save a new index with default session
+    // (a read-only instance shows the default without persisting it)
+    log::trace!("No existing index found, creating default for worktree {worktree_id}");
+    let index = WorktreeIndex::new(worktree_id.to_string());
+    if ensure_writer().is_ok() {
         save_index_internal(app, &index)?;
     }
-
     Ok(index)
 }
 
-/// Atomically load, modify, and save a worktree index.
-/// This prevents race conditions by holding a lock for the entire operation.
-pub fn with_index_mut<F, T>(app: &AppHandle, worktree_id: &str, f: F) -> Result<T, String>
-where
-    F: FnOnce(&mut WorktreeIndex) -> Result<T, String>,
-{
+/// Delete a worktree's index. Returns whether one existed.
+pub fn delete_index(app: &AppHandle, worktree_id: &str) -> Result<bool, String> {
+    ensure_writer()?;
     let lock = get_index_lock(worktree_id);
     let _guard = lock.lock().unwrap();
-
-    let mut index = load_index_internal(app, worktree_id)?;
-    let result = f(&mut index)?;
-    save_index_internal(app, &index)?;
-
-    Ok(result)
+    db::with_connection(app, |conn| db::delete_index(conn, worktree_id))
 }
 
 // ============================================================================
@@ -247,36 +186,13 @@ fn load_metadata_internal(
     app: &AppHandle,
     session_id: &str,
 ) -> Result<Option<SessionMetadata>, String> {
-    let path = get_metadata_path(app, session_id)?;
-
-    if !path.exists() {
-        return Ok(None);
-    }
-
-    let file =
-        File::open(&path).map_err(|e| format!("Failed to open metadata file {path:?}: {e}"))?;
-
-    let reader = BufReader::new(file);
-    let metadata: SessionMetadata = serde_json::from_reader(reader)
-        .map_err(|e| format!("Failed to parse metadata file {path:?}: {e}"))?;
-
-    Ok(Some(metadata))
+    db::with_connection(app, |conn| db::load_metadata(conn, session_id))
 }
 
-/// Save session metadata (internal, no locking - atomic write)
+/// Save session metadata (internal, no locking)
 fn save_metadata_internal(app: &AppHandle, metadata: &SessionMetadata) -> Result<(), String> {
-    let path = get_metadata_path(app, &metadata.id)?;
-    let temp_path = path.with_extension("tmp");
-
-    let file = File::create(&temp_path)
-        .map_err(|e| format!("Failed to create temp metadata file: {e}"))?;
-
-    let writer = BufWriter::new(file);
-    serde_json::to_writer_pretty(writer, metadata)
-        .map_err(|e| format!("Failed to write metadata: {e}"))?;
-
-    fs::rename(&temp_path, &path).map_err(|e| format!("Failed to rename metadata file: {e}"))?;
-
+    ensure_writer()?;
+    db::with_connection(app, |conn| db::save_metadata(conn, metadata))?;
     log::trace!("Saved metadata for session: {}", metadata.id);
     Ok(())
 }
@@ -308,6 +224,8 @@ pub fn with_metadata_mut<F, T>(
 where
     F: FnOnce(&mut SessionMetadata) -> Result<T, String>,
 {
+    ensure_writer()?;
+
     let lock = get_metadata_lock(session_id);
     let _guard = lock.lock().unwrap();
 
@@ -328,72 +246,40 @@ where
 
 /// Delete a session's metadata and all data files (with locking)
 pub fn delete_session_data(app: &AppHandle, session_id: &str) -> Result<(), String> {
+    ensure_writer()?;
     let lock = get_metadata_lock(session_id);
     let _guard = lock.lock().unwrap();
 
+    db::with_connection(app, |conn| db::delete_metadata(conn, session_id))?;
+
     let data_dir = get_data_dir(app)?;
     let session_dir = data_dir.join(session_id);
 
     if session_dir.exists() {
         fs::remove_dir_all(&session_dir)
             .map_err(|e| format!("Failed to delete session directory: {e}"))?;
-        log::trace!("Deleted session data for: {session_id}");
     }
+    log::trace!("Deleted session data for: {session_id}");
 
     Ok(())
 }
 
-/// List all session IDs in the data directory (for recovery scanning)
+/// List the IDs of all sessions with metadata (for recovery scanning)
 pub fn list_all_session_ids(app: &AppHandle) -> Result<Vec<String>, String> {
-    let data_dir = get_data_dir(app)?;
-    let mut session_ids = Vec::new();
-
-    let entries =
-        fs::read_dir(&data_dir).map_err(|e| format!("Failed to read data directory: {e}"))?;
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-
-        // Check if it's a directory with a metadata.json
-        if path.is_dir() && path.join("metadata.json").exists() {
-            if let Some(session_id) = path.file_name().and_then(|n| n.to_str()) {
-                session_ids.push(session_id.to_string());
-            }
-        }
-    }
-
-    Ok(session_ids)
+    db::with_connection(app, db::list_session_ids)
 }
 
-/// Delete orphaned session data directories that are not referenced by any index file.
-/// Returns the number of orphaned directories deleted.
+/// Delete orphaned session data that is not referenced by any worktree index.
+/// Returns the number of orphaned sessions deleted.
 pub fn cleanup_orphaned_session_data(app: &AppHandle) -> Result<u32, String> {
-    // Collect all session IDs referenced in index files
-    let index_dir = get_index_dir(app)?;
-    let mut referenced_ids = std::collections::HashSet::new();
-
-    if let Ok(entries) = fs::read_dir(&index_dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.extension().is_some_and(|ext| ext == "json") {
-                if let Ok(content) = fs::read_to_string(&path) {
-                    if let Ok(index) =
-                        serde_json::from_str::<crate::chat::types::WorktreeIndex>(&content)
-                    {
-                        for session in &index.sessions {
-                            referenced_ids.insert(session.id.clone());
-                        }
-                    }
-                }
-            }
-        }
-    }
+    // Collect all session IDs referenced in indexes (including preserved base indexes)
+    let referenced_ids = db::with_connection(app, db::referenced_session_ids)?;
 
-    // Compare with what's on disk
-    let all_on_disk = list_all_session_ids(app)?;
+    // Compare with the sessions that have metadata
+    let all_stored = list_all_session_ids(app)?;
     let mut deleted = 0u32;
 
-    for session_id in all_on_disk {
+    for session_id in all_stored {
         if !referenced_ids.contains(&session_id) {
             log::trace!("Deleting orphaned session data: {session_id}");
             if let Err(e) = delete_session_data(app, &session_id) {
@@ -405,7 +291,7 @@ pub fn cleanup_orphaned_session_data(app: &AppHandle) -> Result<u32, String> {
     }
 
     if deleted > 0 {
-        log::debug!("Cleaned up {deleted} orphaned session data directories");
+        log::debug!("Cleaned up {deleted} orphaned sessions");
     }
 
     Ok(deleted)
@@ -448,6 +334,7 @@ pub fn load_sessions(
                 claude_session_id: None,
                 codex_thread_id: None,
                 opencode_session_id: None,
+                gemini_session_id: None,
                 selected_model: None,
                 selected_thinking_level: None,
                 selected_provider: None,
@@ -468,6 +355,9 @@ pub fn load_sessions(
                 pending_plan_message_id: None,
                 enabled_mcp_servers: None,
                 digest: None,
+                context_window: None,
+                queued_messages: vec![],
+                forked_from: None,
                 last_run_status: None,
                 last_run_execution_mode: None,
                 label: None,
@@ -487,7 +377,7 @@ pub fn load_sessions(
 }
 
 /// Atomically modify sessions (backward compatible with old with_sessions_mut).
-/// Updates both index and metadata files.
+/// Updates the index and every session's metadata in one transaction.
 pub fn with_sessions_mut<F, T>(
     app: &AppHandle,
     _worktree_path: &str,
@@ -497,18 +387,31 @@ pub fn with_sessions_mut<F, T>(
 where
     F: FnOnce(&mut WorktreeSessions) -> Result<T, String>,
 {
+    ensure_writer()?;
+
     // Load current state
     let mut sessions = load_sessions(app, "", worktree_id)?;
 
     // Apply mutation
     let result = f(&mut sessions)?;
 
-    // Save changes back to index
-    with_index_mut(app, worktree_id, |index| {
+    // Hold the index lock and every affected metadata lock (in a fixed order,
+    // so concurrent callers can't deadlock) while writing
+    let index_lock = get_index_lock(worktree_id);
+    let _index_guard = index_lock.lock().unwrap();
+    let mut session_ids: Vec<&str> = sessions.sessions.iter().map(|s| s.id.as_str()).collect();
+    session_ids.sort_unstable();
+    session_ids.dedup();
+    let metadata_locks: Vec<_> = session_ids.iter().map(|id| get_metadata_lock(id)).collect();
+    let _metadata_guards: Vec<_> = metadata_locks.iter().map(|l| l.lock().unwrap()).collect();
+
+    db::with_transaction(app, |conn| {
+        let mut index = db::load_index(conn, worktree_id)?
+            .unwrap_or_else(|| WorktreeIndex::new(worktree_id.to_string()));
         index.active_session_id = sessions.active_session_id.clone();
         index.branch_naming_completed = sessions.branch_naming_completed;
 
-        // Update index entries and track which sessions need metadata updates
+        // Update index entries and track which sessions are still in use
         let mut session_ids_in_use: std::collections::HashSet<String> =
             std::collections::HashSet::new();
 
@@ -537,117 +440,116 @@ where
         index
             .sessions
             .retain(|e| session_ids_in_use.contains(&e.id));
+        db::save_index(conn, &index)?;
+
+        // Save metadata for each session
+        for session in &sessions.sessions {
+            let mut metadata = db::load_metadata(conn, &session.id)?.unwrap_or_else(|| {
+                SessionMetadata::new(
+                    session.id.clone(),
+                    worktree_id.to_string(),
+                    session.name.clone(),
+                    session.order,
+                )
+            });
+
+            metadata.update_from_session(session);
+            db::save_metadata(conn, &metadata)?;
+        }
 
         Ok(())
     })?;
 
-    // Save metadata for each session
-    for session in &sessions.sessions {
-        let lock = get_metadata_lock(&session.id);
-        let _guard = lock.lock().unwrap();
-
-        let mut metadata = load_metadata_internal(app, &session.id)?.unwrap_or_else(|| {
-            SessionMetadata::new(
-                session.id.clone(),
-                worktree_id.to_string(),
-                session.name.clone(),
-                session.order,
-            )
-        });
-
-        metadata.update_from_session(session);
-        save_metadata_internal(app, &metadata)?;
-    }
-
     Ok(result)
 }
 
-/// Get the index file path (for backward compatibility with old get_sessions_path)
-pub fn get_sessions_path(app: &AppHandle, worktree_id: &str) -> Result<PathBuf, String> {
-    get_index_path(app, worktree_id)
-}
-
 /// Load sessions by worktree_id only (for cleanup when worktree path may not exist)
 pub fn load_sessions_by_id(app: &AppHandle, worktree_id: &str) -> Result<WorktreeSessions, String> {
     load_sessions(app, "", worktree_id)
 }
 
-/// Get the path for a closed base session's preserved index file
-/// (Backward compatible with old get_closed_base_sessions_path)
-pub fn get_closed_base_sessions_path(app: &AppHandle, project_id: &str) -> Result<PathBuf, String> {
-    get_base_index_path(app, project_id)
-}
-
 // ============================================================================
 // Base Session Preservation
 // ============================================================================
 
 /// Preserve sessions when closing a base session
-/// Moves index file to base-{project_id}.json
+/// Moves the worktree's index to the project's preserved base index
 pub fn preserve_base_sessions(
     app: &AppHandle,
     worktree_id: &str,
     project_id: &str,
 ) -> Result<(), String> {
+    ensure_writer()?;
     let lock = get_index_lock(worktree_id);
     let _guard = lock.lock().unwrap();
 
-    let current_path = get_index_path(app, worktree_id)?;
-    let preserved_path = get_base_index_path(app, project_id)?;
+    let preserved = db::with_transaction(app, |conn| {
+        let Some(index) = db::load_index(conn, worktree_id)? else {
+            return Ok(false);
+        };
+        db::save_preserved_index(conn, project_id, &index)?;
+        db::delete_index(conn, worktree_id)?;
+        Ok(true)
+    })
+    .map_err(|e| {
+        log::error!("Failed to preserve base sessions: {e}");
+        format!("Failed to preserve base sessions: {e}")
+    })?;
 
-    if current_path.exists() {
-        fs::rename(&current_path, &preserved_path).map_err(|e| {
-            log::error!("Failed to preserve base sessions: {e}");
-            format!("Failed to preserve base sessions: {e}")
-        })?;
-        log::trace!("Preserved base sessions from {current_path:?} to {preserved_path:?}");
+    if preserved {
+        log::trace!("Preserved base sessions of worktree {worktree_id} for project {project_id}");
     }
 
     Ok(())
 }
 
 /// Restore preserved sessions when reopening a base session
-/// Loads from base-{project_id}.json and updates worktree_id
+/// Moves the project's preserved base index to the new worktree_id
 pub fn restore_base_sessions(
     app: &AppHandle,
     project_id: &str,
     new_worktree_id: &str,
 ) -> Result<Option<WorktreeIndex>, String> {
+    ensure_writer()?;
     let lock = get_index_lock(new_worktree_id);
     let _guard = lock.lock().unwrap();
 
-    let preserved_path = get_base_index_path(app, project_id)?;
+    let restored = db::with_transaction(app, |conn| {
+        let Some(mut index) = db::load_preserved_index(conn, project_id)? else {
+            return Ok(None);
+        };
 
-    if !preserved_path.exists() {
-        log::trace!("No preserved base sessions found for project {project_id}");
-        return Ok(None);
+        // Update the worktree_id to the new one
+        index.worktree_id = new_worktree_id.to_string();
+        db::save_index(conn, &index)?;
+        db::delete_preserved_index(conn, project_id)?;
+        Ok(Some(index))
+    })
+    .map_err(|e| format!("Failed to restore base sessions: {e}"))?;
+
+    match &restored {
+        Some(index) => log::trace!(
+            "Restored {} sessions for base session {new_worktree_id}",
+            index.sessions.len()
+        ),
+        None => log::trace!("No preserved base sessions found for project {project_id}"),
     }
 
-    // Load the preserved index
-    let contents = fs::read_to_string(&preserved_path)
-        .map_err(|e| format!("Failed to read preserved index: {e}"))?;
-
-    let mut index: WorktreeIndex = serde_json::from_str(&contents)
-        .map_err(|e| format!("Failed to parse preserved index: {e}"))?;
-
-    // Update the worktree_id to the new one
-    index.worktree_id = new_worktree_id.to_string();
-
-    // Save to the new location
-    save_index_internal(app, &index)?;
-
-    // Delete the preserved file
-    fs::remove_file(&preserved_path).map_err(|e| {
-        log::warn!("Failed to delete preserved index file: {e}");
-        format!("Failed to delete preserved index: {e}")
-    })?;
+    Ok(restored)
+}
 
-    log::trace!(
-        "Restored {} sessions for base session {new_worktree_id}",
-        index.sessions.len()
-    );
+/// Load a project's preserved base session index, if any
+pub fn load_preserved_base_index(
+    app: &AppHandle,
+    project_id: &str,
+) -> Result<Option<WorktreeIndex>, String> {
+    db::with_connection(app, |conn| db::load_preserved_index(conn, project_id))
+}
 
-    Ok(Some(index))
+/// Delete a project's preserved base session index. Returns whether one existed.
+pub fn delete_preserved_base_sessions(app: &AppHandle, project_id: &str) -> Result<bool, String> {
+    ensure_writer()?;
+    db::with_connection(app, |conn| db::delete_preserved_index(conn, project_id))
 }
 
 // ============================================================================
@@ -684,6 +586,21 @@ pub fn get_pastes_dir(app: &AppHandle) -> Result<PathBuf, String> {
     Ok(path)
 }
 
+/// Get the documents directory path in app data directory (creates if not exists)
+/// Used for storing attached documents (PDF, DOCX, spreadsheets): ~/Library/Application Support/<app>/pasted-documents/
+pub fn get_documents_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+
+    let path = app_data_dir.join("pasted-documents");
+
+    fs::create_dir_all(&path).map_err(|e| format!("Failed to create documents directory: {e}"))?;
+
+    Ok(path)
+}
+
 /// Get the saved contexts directory path in app data directory (creates if not exists)
 /// Used for storing conversation context summaries: ~/Library/Application Support/<app>/session-context/
 pub fn get_saved_contexts_dir(app: &AppHandle) -> Result<PathBuf, String> {
@@ -728,6 +645,7 @@ pub fn save_saved_contexts_metadata(
     app: &AppHandle,
     metadata: &SavedContextsMetadata,
 ) -> Result<(), String> {
+    ensure_writer()?;
     let _lock = SAVED_CONTEXTS_LOCK.lock().unwrap();
 
     let path = get_saved_contexts_metadata_path(app)?;
@@ -2,11 +2,15 @@
 //!
 //! This module provides functionality to tail an NDJSON file and read new lines
 //! as they are written by a detached Claude CLI process.
+//!
+//! Live tailers also record when each line arrived in a `.timing` file next to
+//! the run log, so a finished run can be replayed with its original pacing.
 
-use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
-use std::path::Path;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Polling interval for tailing NDJSON files (50ms)
 pub const POLL_INTERVAL: Duration = Duration::from_millis(50);
@@ -19,6 +23,33 @@ pub struct NdjsonTailer {
     reader: BufReader<File>,
     /// Buffer for incomplete lines (no trailing newline yet)
     buffer: String,
+    /// Complete lines returned so far (index of the next line)
+    line_count: usize,
+    /// Timing file that line arrival times are appended to
+    timing: Option<File>,
+}
+
+/// Path of the timing file for an NDJSON file (`<run_id>.jsonl` → `<run_id>.timing`)
+pub fn timing_path(path: &Path) -> PathBuf {
+    path.with_extension("timing")
+}
+
+/// Read a timing file: line index → Unix time in milliseconds when the line
+/// was first read. A re-attached tailer re-reads earlier lines, so only the
+/// first entry per line counts.
+pub fn read_timing(path: &Path) -> HashMap<usize, u64> {
+    let mut timing = HashMap::new();
+    let Ok(contents) = std::fs::read_to_string(timing_path(path)) else {
+        return timing;
+    };
+    for entry in contents.lines() {
+        if let Some((index, at_ms)) = entry.split_once(' ') {
+            if let (Ok(index), Ok(at_ms)) = (index.parse(), at_ms.parse()) {
+                timing.entry(index).or_insert(at_ms);
+            }
+        }
+    }
+    timing
 }
 
 impl NdjsonTailer {
@@ -40,6 +71,8 @@ impl NdjsonTailer {
         Ok(Self {
             reader,
             buffer: String::new(),
+            line_count: 0,
+            timing: None,
         })
     }
 
@@ -55,9 +88,26 @@ impl NdjsonTailer {
         Ok(Self {
             reader,
             buffer: String::new(),
+            line_count: 0,
+            timing: None,
         })
     }
 
+    /// Create a tailer from the beginning of file that also records when each
+    /// line arrived (see `read_timing`). Used by the live run tailers.
+    pub fn new_from_start_with_timing(path: &Path) -> Result<Self, String> {
+        let mut tailer = Self::new_from_start(path)?;
+        match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(timing_path(path))
+        {
+            Ok(file) => tailer.timing = Some(file),
+            Err(e) => log::warn!("Failed to open run timing file: {e}"),
+        }
+        Ok(tailer)
+    }
+
     /// Poll for new complete lines.
     ///
     /// Returns a vector of complete lines (without trailing newlines).
@@ -91,6 +141,20 @@ impl NdjsonTailer {
             }
         }
 
+        if let (Some(timing), false) = (self.timing.as_mut(), lines.is_empty()) {
+            let now_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis();
+            let entries: String = (self.line_count..self.line_count + lines.len())
+                .map(|index| format!("{index} {now_ms}\n"))
+                .collect();
+            if let Err(e) = timing.write_all(entries.as_bytes()) {
+                log::warn!("Failed to record run timing: {e}");
+            }
+        }
+        self.line_count += lines.len();
+
         Ok(lines)
     }
 
@@ -308,6 +372,29 @@ mod tests {
         assert!(lines[0].contains(r#""type": "crlf""#));
     }
 
+    #[test]
+    fn test_tailer_records_timing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("run.jsonl");
+        std::fs::write(&path, "{\"a\":1}\n{\"b\":2}\n").unwrap();
+
+        let mut tailer = NdjsonTailer::new_from_start_with_timing(&path).unwrap();
+        assert_eq!(tailer.poll().unwrap().len(), 2);
+        let first = read_timing(&path);
+        assert_eq!(first.len(), 2);
+
+        // A re-attached tailer re-reads old lines; their first timing is kept
+        std::thread::sleep(Duration::from_millis(5));
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        writeln!(file, r#"{{"c": 3}}"#).unwrap();
+        let mut reattached = NdjsonTailer::new_from_start_with_timing(&path).unwrap();
+        assert_eq!(reattached.poll().unwrap().len(), 3);
+        let timing = read_timing(&path);
+        assert_eq!(timing.len(), 3);
+        assert_eq!(timing[&0], first[&0]);
+        assert!(timing[&2] > first[&1]);
+    }
+
     #[test]
     fn test_poll_interval_constant() {
         // Verify the poll interval is a reasonable value
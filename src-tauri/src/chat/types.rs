@@ -24,7 +24,7 @@ pub struct SessionDigest {
 // Label Types
 // ============================================================================
 
-const DEFAULT_LABEL_COLOR: &str = "#eab308";
+pub(crate) const DEFAULT_LABEL_COLOR: &str = "#eab308";
 
 /// User-assigned label with color for session cards
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,15 +61,26 @@ where
 // ============================================================================
 
 /// Metadata from a compaction event
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CompactMetadata {
     /// How compaction was triggered
     pub trigger: String, // "manual" or "auto"
     /// Token count before compaction
+    #[serde(default)]
     pub pre_tokens: u64,
 }
 
+/// How full a session's context window was after its last API call
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContextWindowUsage {
+    /// Tokens in context (input + cache read + cache creation)
+    pub used_tokens: u64,
+    /// Context window size of the model
+    pub limit_tokens: u64,
+    /// Unix timestamp of the measurement
+    pub updated_at: u64,
+}
+
 // ============================================================================
 // Usage Types
 // ============================================================================
@@ -93,7 +104,7 @@ pub struct UsageData {
 // Message Types
 // ============================================================================
 
-/// Backend for a chat session (Claude CLI, Codex CLI, or OpenCode)
+/// Backend for a chat session (Claude CLI, Codex CLI, OpenCode, or Gemini CLI)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum Backend {
@@ -101,6 +112,9 @@ pub enum Backend {
     Claude,
     Codex,
     Opencode,
+    Gemini,
+    /// Replays scripted fixtures (`mock/<fixture>` models), for tests
+    Mock,
 }
 
 /// Role of a chat message sender
@@ -218,14 +232,83 @@ pub struct DeniedMessageContext {
     pub thinking_level: String,
 }
 
-/// A content block in a message - text, tool use, or thinking
+/// A follow-up prompt queued while a run is in progress.
+/// Snapshots the send_chat_message parameters so it can be dispatched later
+/// (including after an app restart) without frontend involvement.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueuedMessage {
+    /// Unique ID for this queued message (for editing/reordering/removal)
+    pub id: String,
+    /// The message text (already formatted with file/image references)
+    pub message: String,
+    /// Worktree path the message will be sent in
+    pub worktree_path: String,
+    /// Unix timestamp when the message was queued
+    pub queued_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub execution_mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking_level: Option<ThinkingLevel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effort_level: Option<EffortLevel>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_execution_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_config: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chrome_enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_profile_name: Option<String>,
+    /// Backend override: a lowercase `Backend` name, passed to
+    /// `send_chat_message` as is
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+}
+
+/// Where a forked session came from (see `fork_session`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForkOrigin {
+    /// Session the fork was created from
+    pub session_id: String,
+    /// Message the fork was taken at (runs up to this message were copied)
+    pub message_id: String,
+    /// Unix timestamp when the fork was created
+    pub forked_at: u64,
+    /// Claude CLI message UUID to truncate the resumed conversation at
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resume_at: Option<String>,
+    /// Whether the next run still has to fork (Claude) or seed (Codex/OpenCode)
+    /// the backend conversation. Cleared after the first successful run.
+    #[serde(default)]
+    pub pending: bool,
+}
+
+/// A content block in a message - text, tool use, thinking, or a compaction boundary
 /// Used to preserve the order of content in Claude's response
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum ContentBlock {
-    Text { text: String },
-    ToolUse { tool_call_id: String },
-    Thinking { thinking: String },
+    Text {
+        text: String,
+    },
+    ToolUse {
+        tool_call_id: String,
+    },
+    Thinking {
+        thinking: String,
+    },
+    /// The conversation was compacted at this point
+    Compaction {
+        trigger: String,
+        #[serde(default)]
+        pre_tokens: u64,
+    },
 }
 
 /// A single chat message
@@ -334,6 +417,30 @@ pub struct SaveTextResponse {
     pub size: usize,
 }
 
+/// Response from saving a dropped document (PDF, DOCX, spreadsheet)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveDocumentResponse {
+    /// Unique ID for this document
+    pub id: String,
+    /// Name of the dropped file (e.g., "spec.pdf")
+    pub filename: String,
+    /// Full path to the extracted text file (attached like a pasted text file)
+    pub path: String,
+    /// Size of the extracted text in bytes
+    pub size: usize,
+    /// Full path to the stored copy of the original document
+    pub original_path: String,
+    /// Pages (sheets for spreadsheets) in the document, when it has them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_count: Option<u32>,
+    /// Pages the text was extracted from (e.g., "1-3, 7")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<String>,
+    /// Rendered page images (PDFs, for backends that can look at images)
+    #[serde(default)]
+    pub images: Vec<SaveImageResponse>,
+}
+
 /// Response from reading a pasted text file
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadTextResponse {
@@ -366,7 +473,7 @@ pub struct Session {
     /// Message count (populated separately for efficiency when full messages not needed)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub message_count: Option<u32>,
-    /// Backend for this session (claude, codex, or opencode)
+    /// Backend for this session (claude, codex, opencode, or gemini)
     #[serde(default)]
     pub backend: Backend,
     /// Claude CLI session ID for resuming conversations
@@ -378,6 +485,9 @@ pub struct Session {
     /// OpenCode session ID for resuming conversations
     #[serde(default)]
     pub opencode_session_id: Option<String>,
+    /// Gemini CLI session ID for resuming conversations
+    #[serde(default)]
+    pub gemini_session_id: Option<String>,
     /// Selected model for this session
     #[serde(default)]
     pub selected_model: Option<String>,
@@ -443,6 +553,15 @@ pub struct Session {
     /// Persisted session digest (recap summary)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub digest: Option<SessionDigest>,
+    /// Context window fill after the session's last Claude run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<ContextWindowUsage>,
+    /// Follow-up prompts waiting to be sent after the current run completes
+    #[serde(default)]
+    pub queued_messages: Vec<QueuedMessage>,
+    /// Origin of this session if it was forked from another session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<ForkOrigin>,
 
     // ========================================================================
     // Run recovery state (for showing correct status on app restart)
@@ -483,6 +602,7 @@ impl Session {
             claude_session_id: None,
             codex_thread_id: None,
             opencode_session_id: None,
+            gemini_session_id: None,
             selected_model: None,
             selected_thinking_level: None,
             selected_provider: None,
@@ -504,6 +624,9 @@ impl Session {
             pending_plan_message_id: None,
             enabled_mcp_servers: None,
             digest: None,
+            context_window: None,
+            queued_messages: vec![],
+            forked_from: None,
             last_run_status: None,
             last_run_execution_mode: None,
             label: None,
@@ -522,7 +645,7 @@ impl Session {
 }
 
 /// Lightweight session entry for index files (fast tab rendering)
-/// Stored in sessions/index/{worktree_id}.json
+/// Stored in the `worktree_indexes` table of the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionIndexEntry {
     /// Unique session identifier (UUID v4)
@@ -540,7 +663,7 @@ pub struct SessionIndexEntry {
 }
 
 /// Worktree index - lightweight data for tab bar rendering
-/// Stored in sessions/index/{worktree_id}.json
+/// Stored in the `worktree_indexes` table of the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorktreeIndex {
     /// Worktree ID for reference
@@ -572,7 +695,7 @@ impl Default for WorktreeIndex {
                 message_count: 0,
                 archived_at: None,
             }],
-            version: 1,
+            version: crate::migrations::WORKTREE_INDEX_VERSION,
             branch_naming_completed: false,
         }
     }
@@ -587,7 +710,7 @@ impl WorktreeIndex {
             worktree_id,
             active_session_id: None,
             sessions: vec![],
-            version: 1,
+            version: crate::migrations::WORKTREE_INDEX_VERSION,
             branch_naming_completed: true,
         }
     }
@@ -605,7 +728,7 @@ impl WorktreeIndex {
                 message_count: 0,
                 archived_at: None,
             }],
-            version: 1,
+            version: crate::migrations::WORKTREE_INDEX_VERSION,
             branch_naming_completed: false,
         }
     }
@@ -661,6 +784,7 @@ impl SessionMetadata {
             claude_session_id: self.claude_session_id.clone(),
             codex_thread_id: self.codex_thread_id.clone(),
             opencode_session_id: self.opencode_session_id.clone(),
+            gemini_session_id: self.gemini_session_id.clone(),
             selected_model: self.selected_model.clone(),
             selected_thinking_level: self.selected_thinking_level.clone(),
             selected_provider: self.selected_provider.clone(),
@@ -681,6 +805,9 @@ impl SessionMetadata {
             pending_plan_message_id: self.pending_plan_message_id.clone(),
             enabled_mcp_servers: self.enabled_mcp_servers.clone(),
             digest: self.digest.clone(),
+            context_window: self.context_window.clone(),
+            queued_messages: self.queued_messages.clone(),
+            forked_from: self.forked_from.clone(),
             // Populate from last run for status recovery on app restart
             last_run_status: last_run.map(|r| r.status.clone()),
             last_run_execution_mode: last_run.and_then(|r| r.execution_mode.clone()),
@@ -696,6 +823,7 @@ impl SessionMetadata {
         self.claude_session_id = session.claude_session_id.clone();
         self.codex_thread_id = session.codex_thread_id.clone();
         self.opencode_session_id = session.opencode_session_id.clone();
+        self.gemini_session_id = session.gemini_session_id.clone();
         self.selected_model = session.selected_model.clone();
         self.selected_thinking_level = session.selected_thinking_level.clone();
         self.selected_provider = session.selected_provider.clone();
@@ -877,6 +1005,114 @@ pub enum RunStatus {
     Crashed,
     /// Process still running after app restart (can resume tailing)
     Resumable,
+    /// Killed by the watchdog (no output for too long, or wall-clock limit hit)
+    TimedOut,
+}
+
+/// Budget a limit belongs to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetScope {
+    /// Lifetime spend of the session
+    Session,
+    /// Lifetime spend of all sessions in the project
+    Project,
+    /// Spend across all sessions in the current UTC day
+    Daily,
+}
+
+/// What a budget limit measures
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetMetric {
+    /// Cost in USD (from the pricing table)
+    CostUsd,
+    /// Input + output tokens
+    Tokens,
+}
+
+/// Why a run was cancelled
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CancelReason {
+    /// Cancelled by the user (or by closing the session/worktree)
+    User,
+    /// Stopped automatically after crossing a hard budget limit
+    Budget {
+        scope: BudgetScope,
+        metric: BudgetMetric,
+        limit: f64,
+        spent: f64,
+    },
+    /// Killed by the watchdog; the run ends as `RunStatus::TimedOut`
+    Timeout {
+        kind: TimeoutKind,
+        /// The limit that was hit, in seconds
+        limit_secs: u64,
+    },
+}
+
+/// Which watchdog limit a run hit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutKind {
+    /// No new NDJSON output for the idle timeout
+    Idle,
+    /// Total run time exceeded the wall-clock limit
+    WallClock,
+}
+
+impl CancelReason {
+    /// Run status a run cancelled for this reason ends with
+    pub fn run_status(&self) -> RunStatus {
+        match self {
+            CancelReason::Timeout { .. } => RunStatus::TimedOut,
+            _ => RunStatus::Cancelled,
+        }
+    }
+}
+
+/// Snapshot of a worktree taken before a build/yolo run (see `chat::checkpoint`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunCheckpoint {
+    /// Hidden ref holding the snapshot (`refs/jean/checkpoints/{session}/{run}`)
+    pub git_ref: String,
+    /// Snapshot commit (tracked and untracked, non-ignored files)
+    pub commit: String,
+    /// HEAD when the snapshot was taken (None on an unborn branch)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+}
+
+/// Files a run changed, from diffing its start checkpoint against a snapshot
+/// taken when it ended
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunChanges {
+    /// Hidden ref holding the end-of-run snapshot
+    pub git_ref: String,
+    /// End-of-run snapshot commit
+    pub commit: String,
+    /// Lines added across all files
+    pub additions: u32,
+    /// Lines removed across all files
+    pub deletions: u32,
+    pub files: Vec<RunFileChange>,
+}
+
+/// A file created, modified, deleted or renamed by a run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RunFileChange {
+    /// File path relative to the worktree root
+    pub path: String,
+    /// Previous path (for renames)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// "added", "modified", "deleted" or "renamed"
+    pub status: String,
+    pub additions: u32,
+    pub deletions: u32,
+    #[serde(default)]
+    pub is_binary: bool,
 }
 
 /// Metadata for a single Claude CLI execution (stored in manifest)
@@ -925,10 +1161,68 @@ pub struct RunEntry {
     /// Token usage for this run (captured from Claude CLI result)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub usage: Option<UsageData>,
+    /// Backend that executed this run (None for runs recorded before it was tracked)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<Backend>,
+    /// Custom CLI profile (provider) the run was executed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Cost in USD, computed from usage and the pricing table on completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Why the run was cancelled (None for runs that weren't cancelled via the registry)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancel_reason: Option<CancelReason>,
+    /// Worktree snapshot taken before the run started (build/yolo runs only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<RunCheckpoint>,
+    /// Files the run changed (set when a checkpointed run ends)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<RunChanges>,
+    /// Context compactions that happened during the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compactions: Vec<CompactMetadata>,
+    /// Whether a storage quota deleted this run's log
+    #[serde(default)]
+    pub log_evicted: bool,
+}
+
+impl RunEntry {
+    /// Run with every optional field unset (used in tests, override fields
+    /// with struct update syntax)
+    #[cfg(test)]
+    pub fn test(run_id: &str, status: RunStatus) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            user_message_id: "msg-1".to_string(),
+            user_message: "Hello".to_string(),
+            model: None,
+            execution_mode: None,
+            thinking_level: None,
+            effort_level: None,
+            started_at: 0,
+            ended_at: None,
+            status,
+            assistant_message_id: None,
+            cancelled: false,
+            recovered: false,
+            claude_session_id: None,
+            pid: None,
+            usage: None,
+            backend: None,
+            provider: None,
+            cost_usd: None,
+            cancel_reason: None,
+            checkpoint: None,
+            changes: None,
+            compactions: vec![],
+            log_evicted: false,
+        }
+    }
 }
 
 /// Session metadata - single source of truth for session data and run history
-/// Stored in sessions/data/{session_id}/metadata.json
+/// Stored in the `sessions` table of the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionMetadata {
     /// Unique session identifier (UUID v4)
@@ -943,7 +1237,7 @@ pub struct SessionMetadata {
     pub order: u32,
     /// Unix timestamp when session was created
     pub created_at: u64,
-    /// Backend for this session (claude, codex, or opencode)
+    /// Backend for this session (claude, codex, opencode, or gemini)
     #[serde(default)]
     pub backend: Backend,
     /// Claude CLI session ID for resuming conversations
@@ -955,6 +1249,9 @@ pub struct SessionMetadata {
     /// OpenCode session ID for resuming conversations
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub opencode_session_id: Option<String>,
+    /// Gemini CLI session ID for resuming conversations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gemini_session_id: Option<String>,
     /// Selected model for this session
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub selected_model: Option<String>,
@@ -1014,6 +1311,18 @@ pub struct SessionMetadata {
     /// Persisted session digest (recap summary)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub digest: Option<SessionDigest>,
+    /// Digests `digest` replaced, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub digest_history: Vec<SessionDigest>,
+    /// Context window fill after the session's last Claude run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<ContextWindowUsage>,
+    /// Follow-up prompts waiting to be sent after the current run completes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub queued_messages: Vec<QueuedMessage>,
+    /// Origin of this session if it was forked from another session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forked_from: Option<ForkOrigin>,
     /// User-assigned label with color (e.g. "Needs testing")
     #[serde(
         default,
@@ -1057,6 +1366,18 @@ pub struct RunLogFileInfo {
     /// Token usage for this run (if completed)
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub usage: Option<UsageData>,
+    /// Cost in USD for this run (if completed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Worktree snapshot the run can be rolled back to (build/yolo runs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint: Option<RunCheckpoint>,
+    /// Files the run changed (checkpointed runs that have ended)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub changes: Option<RunChanges>,
+    /// Context compactions that happened during the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compactions: Vec<CompactMetadata>,
 }
 
 /// Debug information about a session's storage
@@ -1064,11 +1385,11 @@ pub struct RunLogFileInfo {
 pub struct SessionDebugInfo {
     /// App data directory path
     pub app_data_dir: String,
-    /// Path to the index JSON file for this worktree
+    /// Path to the database holding this worktree's session index
     pub sessions_file: String,
     /// Path to the data directory (contains all session directories)
     pub runs_dir: String,
-    /// Path to this session's metadata file (if exists)
+    /// Path to the database holding this session's metadata (if it has any)
     pub manifest_file: Option<String>,
     /// Claude CLI session ID (if any)
     pub claude_session_id: Option<String>,
@@ -1097,6 +1418,7 @@ impl SessionMetadata {
             claude_session_id: None,
             codex_thread_id: None,
             opencode_session_id: None,
+            gemini_session_id: None,
             selected_model: None,
             selected_thinking_level: None,
             selected_provider: None,
@@ -1116,10 +1438,14 @@ impl SessionMetadata {
             pending_plan_message_id: None,
             enabled_mcp_servers: None,
             digest: None,
+            digest_history: vec![],
+            context_window: None,
+            queued_messages: vec![],
+            forked_from: None,
             label: None,
             last_opened_at: None,
             runs: vec![],
-            version: 1,
+            version: crate::migrations::SESSION_METADATA_VERSION,
         }
     }
 
@@ -1348,6 +1674,17 @@ mod tests {
         assert!(json.contains("\"thinking\":\"Let me analyze...\""));
     }
 
+    #[test]
+    fn test_content_block_compaction_serialization() {
+        let block = ContentBlock::Compaction {
+            trigger: "auto".to_string(),
+            pre_tokens: 160_000,
+        };
+        let json = serde_json::to_string(&block).unwrap();
+        assert!(json.contains("\"type\":\"compaction\""));
+        assert!(json.contains("\"pre_tokens\":160000"));
+    }
+
     // ========================================================================
     // ChatMessage tests
     // ========================================================================
@@ -1450,7 +1787,10 @@ mod tests {
         assert_eq!(metadata.name, "Test Session");
         assert_eq!(metadata.order, 0);
         assert!(metadata.runs.is_empty());
-        assert_eq!(metadata.version, 1);
+        assert_eq!(
+            metadata.version,
+            crate::migrations::SESSION_METADATA_VERSION
+        );
     }
 
     #[test]
@@ -1463,22 +1803,9 @@ mod tests {
         );
 
         metadata.runs.push(RunEntry {
-            run_id: "run-1".to_string(),
-            user_message_id: "msg-1".to_string(),
-            user_message: "Hello".to_string(),
-            model: None,
-            execution_mode: None,
-            thinking_level: None,
-            effort_level: None,
             started_at: 1234567890,
-            ended_at: None,
-            status: RunStatus::Running,
-            assistant_message_id: None,
-            cancelled: false,
-            recovered: false,
-            claude_session_id: None,
             pid: Some(12345),
-            usage: None,
+            ..RunEntry::test("run-1", RunStatus::Running)
         });
 
         assert!(metadata.find_run("run-1").is_some());
@@ -1499,44 +1826,20 @@ mod tests {
 
         // Add run without claude_session_id
         metadata.runs.push(RunEntry {
-            run_id: "run-1".to_string(),
-            user_message_id: "msg-1".to_string(),
             user_message: "First".to_string(),
-            model: None,
-            execution_mode: None,
-            thinking_level: None,
-            effort_level: None,
             started_at: 1234567890,
-            ended_at: None,
-            status: RunStatus::Completed,
-            assistant_message_id: None,
-            cancelled: false,
-            recovered: false,
-            claude_session_id: None,
-            pid: None,
-            usage: None,
+            ..RunEntry::test("run-1", RunStatus::Completed)
         });
 
         assert!(metadata.latest_claude_session_id().is_none());
 
         // Add run with claude_session_id
         metadata.runs.push(RunEntry {
-            run_id: "run-2".to_string(),
             user_message_id: "msg-2".to_string(),
             user_message: "Second".to_string(),
-            model: None,
-            execution_mode: None,
-            thinking_level: None,
-            effort_level: None,
             started_at: 1234567891,
-            ended_at: None,
-            status: RunStatus::Completed,
-            assistant_message_id: None,
-            cancelled: false,
-            recovered: false,
             claude_session_id: Some("claude-sess-abc".to_string()),
-            pid: None,
-            usage: None,
+            ..RunEntry::test("run-2", RunStatus::Completed)
         });
 
         assert_eq!(metadata.latest_claude_session_id(), Some("claude-sess-abc"));
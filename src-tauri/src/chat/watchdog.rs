@@ -0,0 +1,260 @@
+//! Watchdog for hung detached CLI processes
+//!
+//! Detached Claude and Codex processes can stall forever on network or auth
+//! problems. Every watched run is checked periodically against two limits from
+//! preferences:
+//!
+//! - `run_idle_timeout_minutes`: no new NDJSON output for this long
+//! - `run_max_duration_minutes`: total wall-clock time of the run
+//!
+//! A run that hits either is killed via `registry::cancel_process_with_reason`
+//! (which terminates the process tree) with `CancelReason::Timeout`, so it ends
+//! as `RunStatus::TimedOut` instead of the generic `Crashed`.
+//!
+//! Idle time doesn't accrue while a run waits on the user (a Codex approval
+//! prompt, see `set_waiting`). The idle limit is off by default: long tool
+//! calls can be silent for a long time.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use super::types::{CancelReason, TimeoutKind};
+use crate::http_server::EmitExt;
+
+/// How often watched runs are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Limits for a run (None = not enforced)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Limits {
+    idle: Option<Duration>,
+    wall_clock: Option<Duration>,
+}
+
+impl Limits {
+    fn from_minutes(idle_minutes: u32, max_minutes: u32) -> Self {
+        let minutes = |m: u32| (m > 0).then(|| Duration::from_secs(u64::from(m) * 60));
+        Self {
+            idle: minutes(idle_minutes),
+            wall_clock: minutes(max_minutes),
+        }
+    }
+
+    /// The limit a run has exceeded, if any
+    fn exceeded(
+        &self,
+        idle_for: Duration,
+        running_for: Duration,
+    ) -> Option<(TimeoutKind, Duration)> {
+        if let Some(limit) = self.wall_clock.filter(|l| running_for >= *l) {
+            return Some((TimeoutKind::WallClock, limit));
+        }
+        if let Some(limit) = self.idle.filter(|l| idle_for >= *l) {
+            return Some((TimeoutKind::Idle, limit));
+        }
+        None
+    }
+}
+
+/// A run being watched
+struct WatchedRun {
+    worktree_id: String,
+    run_id: String,
+    output_file: PathBuf,
+    limits: Limits,
+    started_at: Instant,
+    last_activity: Instant,
+    last_size: u64,
+    /// Waiting on the user; not idle
+    waiting: bool,
+}
+
+/// Watched runs keyed by session ID
+static WATCHED_RUNS: Lazy<Mutex<HashMap<String, WatchedRun>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Set once the checking thread is running
+static WATCHDOG_STARTED: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+
+/// Payload for chat:run_timeout events
+#[derive(Debug, Clone, Serialize)]
+pub struct RunTimeoutEvent {
+    pub session_id: String,
+    pub worktree_id: String,
+    pub run_id: String,
+    pub kind: TimeoutKind,
+    pub limit_secs: u64,
+}
+
+/// Start watching a run. `elapsed` is how long the run has already been
+/// going (non-zero when re-attaching to a resumed run).
+pub fn watch_run(
+    app: &AppHandle,
+    session_id: &str,
+    worktree_id: &str,
+    run_id: &str,
+    output_file: PathBuf,
+    elapsed: Duration,
+) {
+    let limits = match crate::load_preferences_sync(app) {
+        Ok(prefs) => Limits::from_minutes(
+            prefs.run_idle_timeout_minutes,
+            prefs.run_max_duration_minutes,
+        ),
+        Err(e) => {
+            log::warn!("Failed to load preferences for run watchdog: {e}");
+            return;
+        }
+    };
+    if limits.idle.is_none() && limits.wall_clock.is_none() {
+        return;
+    }
+
+    let now = Instant::now();
+    let last_size = std::fs::metadata(&output_file)
+        .map(|m| m.len())
+        .unwrap_or(0);
+    WATCHED_RUNS.lock().unwrap().insert(
+        session_id.to_string(),
+        WatchedRun {
+            worktree_id: worktree_id.to_string(),
+            run_id: run_id.to_string(),
+            output_file,
+            limits,
+            started_at: now.checked_sub(elapsed).unwrap_or(now),
+            last_activity: now,
+            last_size,
+            waiting: false,
+        },
+    );
+    ensure_started(app);
+}
+
+/// Stop watching a session's run (called when the run ends)
+pub fn unwatch_run(session_id: &str) {
+    WATCHED_RUNS.lock().unwrap().remove(session_id);
+}
+
+/// Mark a session's run as waiting on the user (or done waiting). Idle time
+/// restarts when the wait ends.
+pub fn set_waiting(session_id: &str, waiting: bool) {
+    if let Some(run) = WATCHED_RUNS.lock().unwrap().get_mut(session_id) {
+        run.waiting = waiting;
+        run.last_activity = Instant::now();
+    }
+}
+
+fn ensure_started(app: &AppHandle) {
+    let mut started = WATCHDOG_STARTED.lock().unwrap();
+    if *started {
+        return;
+    }
+    *started = true;
+
+    let app = app.clone();
+    thread::spawn(move || {
+        log::trace!("Run watchdog started");
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+            check_runs(&app);
+        }
+    });
+}
+
+/// Update activity for every watched run and time out the ones over a limit
+fn check_runs(app: &AppHandle) {
+    let now = Instant::now();
+    let mut timed_out = Vec::new();
+    {
+        let mut watched = WATCHED_RUNS.lock().unwrap();
+        for (session_id, run) in watched.iter_mut() {
+            // Output growth = the CLI is still producing NDJSON lines
+            let size = std::fs::metadata(&run.output_file)
+                .map(|m| m.len())
+                .unwrap_or(run.last_size);
+            if size != run.last_size || run.waiting {
+                run.last_size = size;
+                run.last_activity = now;
+            }
+
+            let idle_for = now.duration_since(run.last_activity);
+            let running_for = now.duration_since(run.started_at);
+            if let Some((kind, limit)) = run.limits.exceeded(idle_for, running_for) {
+                timed_out.push((
+                    session_id.clone(),
+                    run.worktree_id.clone(),
+                    run.run_id.clone(),
+                    kind,
+                    limit,
+                ));
+            }
+        }
+        for (session_id, ..) in &timed_out {
+            watched.remove(session_id);
+        }
+    }
+
+    for (session_id, worktree_id, run_id, kind, limit) in timed_out {
+        let limit_secs = limit.as_secs();
+        log::warn!(
+            "Run {run_id} for session {session_id} timed out ({kind:?}, limit {limit_secs}s), killing process tree"
+        );
+
+        let event = RunTimeoutEvent {
+            session_id: session_id.clone(),
+            worktree_id: worktree_id.clone(),
+            run_id,
+            kind,
+            limit_secs,
+        };
+        if let Err(e) = app.emit_all("chat:run_timeout", &event) {
+            log::error!("Failed to emit chat:run_timeout event: {e}");
+        }
+
+        let reason = CancelReason::Timeout { kind, limit_secs };
+        if let Err(e) =
+            super::registry::cancel_process_with_reason(app, &session_id, &worktree_id, reason)
+        {
+            log::error!("Failed to kill timed out run for session {session_id}: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_from_minutes() {
+        let limits = Limits::from_minutes(30, 0);
+        assert_eq!(limits.idle, Some(Duration::from_secs(1_800)));
+        assert_eq!(limits.wall_clock, None);
+    }
+
+    #[test]
+    fn test_limits_exceeded() {
+        let limits = Limits::from_minutes(10, 60);
+        let mins = |m: u64| Duration::from_secs(m * 60);
+
+        assert_eq!(limits.exceeded(mins(5), mins(30)), None);
+        assert_eq!(
+            limits.exceeded(mins(10), mins(30)),
+            Some((TimeoutKind::Idle, mins(10)))
+        );
+        // Wall-clock limit wins when both are hit
+        assert_eq!(
+            limits.exceeded(mins(15), mins(60)),
+            Some((TimeoutKind::WallClock, mins(60)))
+        );
+
+        let disabled = Limits::from_minutes(0, 0);
+        assert_eq!(disabled.exceeded(mins(600), mins(600)), None);
+    }
+}
@@ -0,0 +1,15 @@
+//! Tauri commands for database queries
+
+use tauri::AppHandle;
+
+use super::{with_connection, SessionQuery, SessionSummary};
+
+/// List sessions matching a filter, most recently updated first
+#[tauri::command]
+pub async fn query_sessions(
+    app: AppHandle,
+    query: SessionQuery,
+) -> Result<Vec<SessionSummary>, String> {
+    log::trace!("Querying sessions: {query:?}");
+    with_connection(&app, |conn| super::query_sessions(conn, &query))
+}
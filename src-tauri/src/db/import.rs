@@ -0,0 +1,223 @@
+//! One-time import of the JSON storage layout
+//!
+//! Before the database, `projects.json`, `sessions/index/{worktree_id}.json`,
+//! `sessions/index/base-{project_id}.json` and
+//! `sessions/data/{session_id}/metadata.json` each held one document. They are
+//! read into the database in a single transaction; once it commits, the files
+//! are moved to `app-data/backups/json-import/{timestamp}/` (keeping their
+//! relative paths) so nothing reads stale copies. A `meta` row records that
+//! the import ran, so it never runs twice.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use super::{projects, sessions, transaction};
+use crate::chat::types::{SessionMetadata, WorktreeIndex};
+use crate::migrations::{migrate_value, StorageFormat};
+use crate::projects::types::ProjectsData;
+
+const IMPORTED_KEY: &str = "json_layout_imported_at";
+
+/// Read a JSON document, upgrading it to its format's current version
+fn read_document<T: DeserializeOwned>(path: &Path, format: StorageFormat) -> Result<T, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read {path:?}: {e}"))?;
+    let mut value: Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {path:?}: {e}"))?;
+    migrate_value(format, &mut value).map_err(|e| format!("Failed to migrate {path:?}: {e}"))?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to decode {path:?}: {e}"))
+}
+
+/// Import the JSON files under `app_data_dir` unless that already happened
+pub(super) fn import_json_layout(conn: &mut Connection, app_data_dir: &Path) -> Result<(), String> {
+    let imported: Option<String> = conn
+        .query_row(
+            "SELECT value FROM meta WHERE key = ?1",
+            [IMPORTED_KEY],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read import marker: {e}"))?;
+    if imported.is_some() {
+        return Ok(());
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let imported_files = transaction(conn, |tx| {
+        let files = import_files(tx, app_data_dir)?;
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+            rusqlite::params![IMPORTED_KEY, timestamp.to_string()],
+        )
+        .map_err(|e| format!("Failed to record import: {e}"))?;
+        Ok(files)
+    })?;
+
+    if imported_files.is_empty() {
+        return Ok(());
+    }
+
+    let backup_dir = app_data_dir
+        .join("backups")
+        .join("json-import")
+        .join(timestamp.to_string());
+    for path in &imported_files {
+        let relative = path.strip_prefix(app_data_dir).unwrap_or(path);
+        let target = backup_dir.join(relative);
+        let moved = target
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::rename(path, &target));
+        if let Err(e) = moved {
+            log::warn!("Failed to move imported file {path:?} to backups: {e}");
+        }
+    }
+    log::info!(
+        "Imported {} JSON storage files into the database (originals in {backup_dir:?})",
+        imported_files.len()
+    );
+    Ok(())
+}
+
+/// Insert every JSON document found. Returns the files that were imported.
+/// An unreadable file is logged and left in place.
+fn import_files(conn: &Connection, app_data_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut imported = Vec::new();
+
+    let projects_path = app_data_dir.join("projects.json");
+    if projects_path.is_file() {
+        match read_document::<ProjectsData>(&projects_path, StorageFormat::Projects) {
+            Ok(data) => {
+                projects::save_projects(conn, &data)?;
+                imported.push(projects_path);
+            }
+            Err(e) => log::warn!("Skipping projects import: {e}"),
+        }
+    }
+
+    let sessions_dir = app_data_dir.join("sessions");
+    if let Ok(entries) = fs::read_dir(sessions_dir.join("index")) {
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if !path.extension().is_some_and(|ext| ext == "json") {
+                continue;
+            }
+            let index = match read_document::<WorktreeIndex>(&path, StorageFormat::WorktreeIndex) {
+                Ok(index) => index,
+                Err(e) => {
+                    log::warn!("Skipping index import: {e}");
+                    continue;
+                }
+            };
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            match stem.strip_prefix("base-") {
+                Some(project_id) => sessions::save_preserved_index(conn, project_id, &index)?,
+                None => sessions::save_index(conn, &index)?,
+            }
+            imported.push(path);
+        }
+    }
+
+    if let Ok(entries) = fs::read_dir(sessions_dir.join("data")) {
+        for path in entries
+            .flatten()
+            .map(|entry| entry.path().join("metadata.json"))
+        {
+            if !path.is_file() {
+                continue;
+            }
+            match read_document::<SessionMetadata>(&path, StorageFormat::SessionMetadata) {
+                Ok(metadata) => {
+                    sessions::save_metadata(conn, &metadata)?;
+                    imported.push(path);
+                }
+                Err(e) => log::warn!("Skipping session metadata import: {e}"),
+            }
+        }
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_in_memory;
+
+    fn write_json(path: &Path, value: Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, serde_json::to_string(&value).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_import_moves_files_and_runs_once() {
+        let dir = tempfile::tempdir().unwrap();
+        write_json(
+            &dir.path().join("projects.json"),
+            serde_json::json!({ "projects": [], "worktrees": [] }),
+        );
+        write_json(
+            &dir.path().join("sessions/index/wt1.json"),
+            serde_json::json!({
+                "worktree_id": "wt1",
+                "sessions": [{ "id": "s1", "name": "Session 1", "order": 0 }]
+            }),
+        );
+        write_json(
+            &dir.path().join("sessions/index/base-p1.json"),
+            serde_json::json!({ "worktree_id": "old", "sessions": [] }),
+        );
+        write_json(
+            &dir.path().join("sessions/data/s1/metadata.json"),
+            serde_json::json!({
+                "session_id": "s1",
+                "worktree_id": "wt1",
+                "name": "Session 1",
+                "order": 0,
+                "created_at": 1700000000,
+                "label": "Needs testing"
+            }),
+        );
+        fs::write(dir.path().join("sessions/index/broken.json"), "{").unwrap();
+
+        let mut conn = open_in_memory();
+        import_json_layout(&mut conn, dir.path()).unwrap();
+
+        let index = sessions::load_index(&conn, "wt1").unwrap().unwrap();
+        assert_eq!(index.sessions[0].id, "s1");
+        assert!(sessions::load_preserved_index(&conn, "p1")
+            .unwrap()
+            .is_some());
+        let metadata = sessions::load_metadata(&conn, "s1").unwrap().unwrap();
+        assert_eq!(metadata.label.unwrap().name, "Needs testing");
+
+        assert!(!dir.path().join("projects.json").exists());
+        assert!(!dir.path().join("sessions/data/s1/metadata.json").exists());
+        assert!(dir.path().join("sessions/index/broken.json").exists());
+        let backups = fs::read_dir(dir.path().join("backups/json-import"))
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert!(backups.join("sessions/index/wt1.json").exists());
+
+        // Files that show up later are not imported again
+        write_json(
+            &dir.path().join("sessions/index/wt2.json"),
+            serde_json::json!({ "worktree_id": "wt2", "sessions": [] }),
+        );
+        import_json_layout(&mut conn, dir.path()).unwrap();
+        assert!(sessions::load_index(&conn, "wt2").unwrap().is_none());
+    }
+}
@@ -0,0 +1,258 @@
+//! Format migrations for stored documents
+//!
+//! Worktree indexes and session metadata keep their format `version` inside
+//! the row's JSON, so the steps in `migrations` apply to them as they did to
+//! the JSON files. When the database opens, rows below their format's current
+//! version are rewritten in a single transaction, after the database is copied
+//! to `app-data/backups/migrations/{timestamp}/jean.db`. A row that can't be
+//! upgraded (e.g. written by a newer app version) is left untouched.
+
+use std::fs;
+use std::path::Path;
+
+use rusqlite::Connection;
+use serde_json::Value;
+
+use super::{sessions, transaction, DATABASE_FILE};
+use crate::chat::time::now;
+use crate::migrations::{
+    backup_dir, migrate_value, stored_version, FileMigration, MigrationFailure, MigrationReport,
+    StorageFormat,
+};
+
+/// Tables whose `data` column holds a versioned document
+#[derive(Debug, Clone, Copy)]
+enum Table {
+    WorktreeIndexes,
+    PreservedIndexes,
+    Sessions,
+}
+
+impl Table {
+    const ALL: [Table; 3] = [
+        Table::WorktreeIndexes,
+        Table::PreservedIndexes,
+        Table::Sessions,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Table::WorktreeIndexes => "worktree_indexes",
+            Table::PreservedIndexes => "preserved_indexes",
+            Table::Sessions => "sessions",
+        }
+    }
+
+    fn format(self) -> StorageFormat {
+        match self {
+            Table::WorktreeIndexes | Table::PreservedIndexes => StorageFormat::WorktreeIndex,
+            Table::Sessions => StorageFormat::SessionMetadata,
+        }
+    }
+
+    /// Selects (key, data) for every row
+    fn select_sql(self) -> &'static str {
+        match self {
+            Table::WorktreeIndexes => "SELECT worktree_id, data FROM worktree_indexes",
+            Table::PreservedIndexes => "SELECT project_id, data FROM preserved_indexes",
+            Table::Sessions => "SELECT id, data FROM sessions",
+        }
+    }
+
+    /// Write an upgraded document back through the table's save function, so
+    /// columns derived from it are refreshed too
+    fn save(self, conn: &Connection, key: &str, value: Value) -> Result<(), String> {
+        let decode_error = |e: serde_json::Error| format!("Failed to decode migrated {key}: {e}");
+        match self {
+            Table::WorktreeIndexes => {
+                sessions::save_index(conn, &serde_json::from_value(value).map_err(decode_error)?)
+            }
+            Table::PreservedIndexes => sessions::save_preserved_index(
+                conn,
+                key,
+                &serde_json::from_value(value).map_err(decode_error)?,
+            ),
+            Table::Sessions => {
+                sessions::save_metadata(conn, &serde_json::from_value(value).map_err(decode_error)?)
+            }
+        }
+    }
+}
+
+fn load_rows(conn: &Connection, table: Table) -> Result<Vec<(String, String)>, String> {
+    let mut stmt = conn
+        .prepare(table.select_sql())
+        .map_err(|e| format!("Failed to query {}: {e}", table.name()))?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to query {}: {e}", table.name()))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Failed to read {} row: {e}", table.name()))
+}
+
+/// Upgrade (or with `dry_run`, check) every stored document below its
+/// format's current version. Call inside a transaction unless `dry_run`.
+pub(super) fn migrate_rows(conn: &Connection, dry_run: bool) -> Result<MigrationReport, String> {
+    let mut report = MigrationReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    for table in Table::ALL {
+        let format = table.format();
+        for (key, data) in load_rows(conn, table)? {
+            let path = format!("{DATABASE_FILE}/{}/{key}", table.name());
+            let upgraded = serde_json::from_str::<Value>(&data)
+                .map_err(|e| format!("Failed to parse stored document: {e}"))
+                .and_then(|mut value| {
+                    let from_version = stored_version(format, &value);
+                    let applied = migrate_value(format, &mut value)?;
+                    Ok((value, from_version, applied))
+                });
+            let (value, from_version, applied) = match upgraded {
+                Ok(upgraded) => upgraded,
+                Err(error) => {
+                    report.failed.push(MigrationFailure {
+                        path,
+                        format,
+                        error,
+                    });
+                    continue;
+                }
+            };
+            if applied.is_empty() {
+                continue;
+            }
+
+            if !dry_run {
+                if let Err(error) = table.save(conn, &key, value) {
+                    report.failed.push(MigrationFailure {
+                        path,
+                        format,
+                        error,
+                    });
+                    continue;
+                }
+            }
+            report.migrated.push(FileMigration {
+                path,
+                format,
+                from_version,
+                to_version: format.current_version(),
+                steps: applied.iter().map(|s| s.to_string()).collect(),
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Upgrade stored documents, backing up the database first if any row changes
+pub(super) fn migrate_stored_documents(
+    conn: &mut Connection,
+    app_data_dir: &Path,
+) -> Result<MigrationReport, String> {
+    let pending = migrate_rows(conn, true)?;
+    if pending.migrated.is_empty() {
+        return Ok(pending);
+    }
+
+    let backup_dir = backup_dir(app_data_dir, now());
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {e}"))?;
+    // VACUUM INTO writes a consistent copy, including pages still in the WAL
+    let backup_path = backup_dir.join(DATABASE_FILE);
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy()])
+        .map_err(|e| format!("Failed to back up database: {e}"))?;
+
+    let mut report = transaction(conn, |tx| migrate_rows(tx, false))?;
+    report.backup_dir = Some(backup_dir.to_string_lossy().to_string());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::open_database;
+
+    fn insert_legacy_session(conn: &Connection) {
+        let data = serde_json::json!({
+            "session_id": "s1",
+            "worktree_id": "wt1",
+            "name": "Session 1",
+            "order": 0,
+            "created_at": 1700000000,
+            "label": "Needs testing",
+            "version": 1
+        });
+        conn.execute(
+            "INSERT INTO sessions
+                (id, worktree_id, name, backend, created_at, updated_at, run_count, data)
+             VALUES ('s1', 'wt1', 'Session 1', 'claude', 1700000000, 1700000000, 0, ?1)",
+            [data.to_string()],
+        )
+        .unwrap();
+    }
+
+    fn stored_data(conn: &Connection) -> Value {
+        let data: String = conn
+            .query_row("SELECT data FROM sessions WHERE id = 's1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        serde_json::from_str(&data).unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_reports_rows_without_writing() {
+        let conn = crate::db::open_in_memory();
+        insert_legacy_session(&conn);
+
+        let report = migrate_rows(&conn, true).unwrap();
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.migrated[0].path, "jean.db/sessions/s1");
+        assert_eq!(report.migrated[0].from_version, 1);
+        assert_eq!(stored_data(&conn)["version"], 1);
+    }
+
+    #[test]
+    fn test_rows_are_rewritten_after_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut conn = open_database(&dir.path().join(DATABASE_FILE)).unwrap();
+        insert_legacy_session(&conn);
+        conn.execute(
+            "INSERT INTO worktree_indexes (worktree_id, data) VALUES ('wt1', ?1)",
+            [r#"{"worktree_id":"wt1","sessions":[],"version":99}"#],
+        )
+        .unwrap();
+
+        let report = migrate_stored_documents(&mut conn, dir.path()).unwrap();
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].path, "jean.db/worktree_indexes/wt1");
+
+        let data = stored_data(&conn);
+        assert_eq!(data["version"], 2);
+        assert_eq!(data["id"], "s1");
+        let label: String = conn
+            .query_row("SELECT label FROM sessions WHERE id = 's1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(label, "Needs testing");
+
+        // The backup still holds the original row
+        let backup =
+            Connection::open(Path::new(&report.backup_dir.unwrap()).join(DATABASE_FILE)).unwrap();
+        let original: String = backup
+            .query_row("SELECT data FROM sessions WHERE id = 's1'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert!(original.contains("session_id"));
+
+        // A second pass has nothing left to do
+        let again = migrate_stored_documents(&mut conn, dir.path()).unwrap();
+        assert!(again.migrated.is_empty());
+        assert!(again.backup_dir.is_none());
+    }
+}
@@ -0,0 +1,259 @@
+//! Embedded SQLite store
+//!
+//! Projects, worktrees, worktree session indexes and session metadata live in
+//! `app-data/jean.db` rather than one JSON file each. Every row keeps its
+//! document as JSON in a `data` column, next to the columns used to order and
+//! filter it, so a change rewrites one row instead of a whole file, and
+//! multi-row updates (all projects, a worktree's index together with its
+//! sessions) commit in a single transaction. Run logs and other per-session
+//! files stay in `sessions/data/{session_id}/`.
+//!
+//! The JSON files of the previous layout are imported the first time the
+//! database is opened (see `import`). Index and metadata documents written by
+//! older versions are then upgraded in place (see `migrate`); reads still pass
+//! them through `migrations::migrate_value` in case that failed.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use once_cell::sync::OnceCell;
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::migrations::{migrate_value, MigrationReport, StorageFormat};
+
+pub mod commands;
+mod import;
+mod migrate;
+mod projects;
+mod sessions;
+
+pub use projects::{load_projects, save_projects};
+pub use sessions::*;
+
+/// Database file name in the app data directory
+const DATABASE_FILE: &str = "jean.db";
+
+/// Bump together with a change to `SCHEMA` (stored as `PRAGMA user_version`)
+const SCHEMA_VERSION: i32 = 1;
+
+/// How long a statement waits for a lock held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    position INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS worktrees (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS worktrees_by_project ON worktrees (project_id);
+
+CREATE TABLE IF NOT EXISTS worktree_indexes (
+    worktree_id TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS preserved_indexes (
+    project_id TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    worktree_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    backend TEXT NOT NULL,
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    archived_at INTEGER,
+    label TEXT,
+    run_count INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS sessions_by_worktree ON sessions (worktree_id);
+CREATE INDEX IF NOT EXISTS sessions_by_updated ON sessions (updated_at);
+CREATE INDEX IF NOT EXISTS sessions_by_label ON sessions (label);
+";
+
+/// The app's connection, opened (and the JSON layout imported) on first use
+static DB: OnceCell<Mutex<Connection>> = OnceCell::new();
+
+/// Path of the database file
+pub fn get_database_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join(DATABASE_FILE))
+}
+
+fn connection(app: &AppHandle) -> Result<&'static Mutex<Connection>, String> {
+    DB.get_or_try_init(|| {
+        let path = get_database_path(app)?;
+        let app_data_dir = path
+            .parent()
+            .ok_or_else(|| "Invalid database path".to_string())?;
+        std::fs::create_dir_all(app_data_dir)
+            .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+
+        let mut conn = open_database(&path)?;
+        // Only the instance holding the app data lease imports (see `instance`)
+        if crate::instance::ensure_writer().is_ok() {
+            import::import_json_layout(&mut conn, app_data_dir)?;
+            match migrate::migrate_stored_documents(&mut conn, app_data_dir) {
+                Ok(report) => crate::migrations::log_report(&report),
+                Err(e) => log::error!("Failed to migrate stored documents: {e}"),
+            }
+        }
+        log::trace!("Opened database at {path:?}");
+        Ok(Mutex::new(conn))
+    })
+}
+
+/// Open a database file and bring its schema up to date
+fn open_database(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("Failed to open database: {e}"))?;
+    configure(&conn)?;
+    Ok(conn)
+}
+
+fn configure(conn: &Connection) -> Result<(), String> {
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| format!("Failed to set database busy timeout: {e}"))?;
+    // WAL lets readers proceed while a write is in progress
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to enable WAL journal: {e}"))?;
+
+    let version: i32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(|e| format!("Failed to read database version: {e}"))?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Database was created by a newer version of the app (schema {version}, this app supports {SCHEMA_VERSION})"
+        ));
+    }
+
+    conn.execute_batch(SCHEMA)
+        .map_err(|e| format!("Failed to create database schema: {e}"))?;
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)
+        .map_err(|e| format!("Failed to set database version: {e}"))?;
+    Ok(())
+}
+
+/// Open the database at startup, importing the JSON layout if needed
+pub fn init(app: &AppHandle) {
+    if let Err(e) = connection(app) {
+        log::error!("Failed to open database: {e}");
+    }
+}
+
+/// Report which stored documents would be upgraded, without changing them
+pub fn check_stored_documents(app: &AppHandle) -> Result<MigrationReport, String> {
+    with_connection(app, |conn| migrate::migrate_rows(conn, true))
+}
+
+/// Run `f` with the app's connection
+pub fn with_connection<F, T>(app: &AppHandle, f: F) -> Result<T, String>
+where
+    F: FnOnce(&Connection) -> Result<T, String>,
+{
+    let conn = connection(app)?.lock().unwrap();
+    f(&conn)
+}
+
+/// Run `f` in a transaction. It commits if `f` succeeds and rolls back
+/// otherwise.
+pub fn with_transaction<F, T>(app: &AppHandle, f: F) -> Result<T, String>
+where
+    F: FnOnce(&Connection) -> Result<T, String>,
+{
+    let mut conn = connection(app)?.lock().unwrap();
+    transaction(&mut conn, f)
+}
+
+fn transaction<F, T>(conn: &mut Connection, f: F) -> Result<T, String>
+where
+    F: FnOnce(&Connection) -> Result<T, String>,
+{
+    let tx = conn
+        .transaction()
+        .map_err(|e| format!("Failed to start transaction: {e}"))?;
+    let result = f(&tx)?;
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {e}"))?;
+    Ok(result)
+}
+
+// ============================================================================
+// Documents
+// ============================================================================
+
+fn encode<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string(value).map_err(|e| format!("Failed to serialize document: {e}"))
+}
+
+/// Decode a stored document, upgrading it first if it was written in an older
+/// format
+fn decode<T: DeserializeOwned>(format: StorageFormat, data: &str) -> Result<T, String> {
+    let mut value: Value =
+        serde_json::from_str(data).map_err(|e| format!("Failed to parse stored document: {e}"))?;
+    migrate_value(format, &mut value)?;
+    serde_json::from_value(value).map_err(|e| format!("Failed to decode stored document: {e}"))
+}
+
+#[cfg(test)]
+pub(crate) fn open_in_memory() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    configure(&conn).unwrap();
+    conn
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_transaction_rolls_back() {
+        let mut conn = open_in_memory();
+        let result: Result<(), String> = transaction(&mut conn, |tx| {
+            tx.execute("INSERT INTO meta (key, value) VALUES ('a', '1')", [])
+                .unwrap();
+            Err("boom".to_string())
+        });
+        assert!(result.is_err());
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM meta", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DATABASE_FILE);
+        let conn = open_database(&path).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        drop(conn);
+
+        let error = open_database(&path).unwrap_err();
+        assert!(error.contains("newer version"), "{error}");
+    }
+}
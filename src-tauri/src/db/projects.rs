@@ -0,0 +1,75 @@
+//! Projects and worktrees
+
+use rusqlite::{params, Connection};
+
+use super::encode;
+use crate::migrations::PROJECTS_VERSION;
+use crate::projects::types::{Project, ProjectsData, Worktree};
+
+/// Load all projects and worktrees, in their saved order
+pub fn load_projects(conn: &Connection) -> Result<ProjectsData, String> {
+    Ok(ProjectsData {
+        projects: load_rows(conn, "SELECT data FROM projects ORDER BY position")?,
+        worktrees: load_rows(conn, "SELECT data FROM worktrees ORDER BY position")?,
+        version: PROJECTS_VERSION,
+    })
+}
+
+fn load_rows<T: serde::de::DeserializeOwned>(
+    conn: &Connection,
+    sql: &str,
+) -> Result<Vec<T>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to query projects: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query projects: {e}"))?;
+
+    let mut items = Vec::new();
+    for row in rows {
+        let data = row.map_err(|e| format!("Failed to read project row: {e}"))?;
+        items.push(
+            serde_json::from_str(&data)
+                .map_err(|e| format!("Failed to parse stored project data: {e}"))?,
+        );
+    }
+    Ok(items)
+}
+
+/// Replace all projects and worktrees. Call inside a transaction so readers
+/// never see a partial list.
+pub fn save_projects(conn: &Connection, data: &ProjectsData) -> Result<(), String> {
+    conn.execute("DELETE FROM projects", [])
+        .map_err(|e| format!("Failed to clear projects: {e}"))?;
+    conn.execute("DELETE FROM worktrees", [])
+        .map_err(|e| format!("Failed to clear worktrees: {e}"))?;
+
+    let mut insert_project = conn
+        .prepare("INSERT INTO projects (id, position, data) VALUES (?1, ?2, ?3)")
+        .map_err(|e| format!("Failed to save projects: {e}"))?;
+    for (position, project) in data.projects.iter().enumerate() {
+        insert_project
+            .execute(params![
+                project.id,
+                position as i64,
+                encode::<Project>(project)?
+            ])
+            .map_err(|e| format!("Failed to save project {}: {e}", project.id))?;
+    }
+
+    let mut insert_worktree = conn
+        .prepare("INSERT INTO worktrees (id, project_id, position, data) VALUES (?1, ?2, ?3, ?4)")
+        .map_err(|e| format!("Failed to save worktrees: {e}"))?;
+    for (position, worktree) in data.worktrees.iter().enumerate() {
+        insert_worktree
+            .execute(params![
+                worktree.id,
+                worktree.project_id,
+                position as i64,
+                encode::<Worktree>(worktree)?
+            ])
+            .map_err(|e| format!("Failed to save worktree {}: {e}", worktree.id))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,389 @@
+//! Worktree session indexes and session metadata
+
+use std::collections::HashSet;
+
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::{decode, encode};
+use crate::chat::types::{Backend, SessionMetadata, WorktreeIndex};
+use crate::migrations::StorageFormat;
+
+fn load_document(conn: &Connection, sql: &str, key: &str) -> Result<Option<String>, String> {
+    conn.query_row(sql, [key], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to load {key}: {e}"))
+}
+
+// ============================================================================
+// Worktree indexes
+// ============================================================================
+
+/// Load a worktree's session index
+pub fn load_index(conn: &Connection, worktree_id: &str) -> Result<Option<WorktreeIndex>, String> {
+    load_document(
+        conn,
+        "SELECT data FROM worktree_indexes WHERE worktree_id = ?1",
+        worktree_id,
+    )?
+    .map(|data| decode(StorageFormat::WorktreeIndex, &data))
+    .transpose()
+}
+
+/// Insert or replace a worktree's session index
+pub fn save_index(conn: &Connection, index: &WorktreeIndex) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO worktree_indexes (worktree_id, data) VALUES (?1, ?2)",
+        params![index.worktree_id, encode(index)?],
+    )
+    .map_err(|e| format!("Failed to save index for {}: {e}", index.worktree_id))?;
+    Ok(())
+}
+
+/// Delete a worktree's session index. Returns whether it existed.
+pub fn delete_index(conn: &Connection, worktree_id: &str) -> Result<bool, String> {
+    conn.execute(
+        "DELETE FROM worktree_indexes WHERE worktree_id = ?1",
+        [worktree_id],
+    )
+    .map(|deleted| deleted > 0)
+    .map_err(|e| format!("Failed to delete index for {worktree_id}: {e}"))
+}
+
+/// Load the index kept for a project's closed base session
+pub fn load_preserved_index(
+    conn: &Connection,
+    project_id: &str,
+) -> Result<Option<WorktreeIndex>, String> {
+    load_document(
+        conn,
+        "SELECT data FROM preserved_indexes WHERE project_id = ?1",
+        project_id,
+    )?
+    .map(|data| decode(StorageFormat::WorktreeIndex, &data))
+    .transpose()
+}
+
+/// Keep an index for a project's closed base session
+pub fn save_preserved_index(
+    conn: &Connection,
+    project_id: &str,
+    index: &WorktreeIndex,
+) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO preserved_indexes (project_id, data) VALUES (?1, ?2)",
+        params![project_id, encode(index)?],
+    )
+    .map_err(|e| format!("Failed to preserve index for project {project_id}: {e}"))?;
+    Ok(())
+}
+
+/// Delete a project's preserved base session index. Returns whether it existed.
+pub fn delete_preserved_index(conn: &Connection, project_id: &str) -> Result<bool, String> {
+    conn.execute(
+        "DELETE FROM preserved_indexes WHERE project_id = ?1",
+        [project_id],
+    )
+    .map(|deleted| deleted > 0)
+    .map_err(|e| format!("Failed to delete preserved index for project {project_id}: {e}"))
+}
+
+/// IDs of all sessions listed in a worktree index or a preserved base index
+pub fn referenced_session_ids(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT data FROM worktree_indexes UNION ALL SELECT data FROM preserved_indexes")
+        .map_err(|e| format!("Failed to query indexes: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query indexes: {e}"))?;
+
+    let mut ids = HashSet::new();
+    for row in rows {
+        let data = row.map_err(|e| format!("Failed to read index row: {e}"))?;
+        match decode::<WorktreeIndex>(StorageFormat::WorktreeIndex, &data) {
+            Ok(index) => ids.extend(index.sessions.into_iter().map(|entry| entry.id)),
+            Err(e) => log::warn!("Skipping unreadable worktree index: {e}"),
+        }
+    }
+    Ok(ids)
+}
+
+// ============================================================================
+// Session metadata
+// ============================================================================
+
+/// Load a session's metadata
+pub fn load_metadata(
+    conn: &Connection,
+    session_id: &str,
+) -> Result<Option<SessionMetadata>, String> {
+    load_document(conn, "SELECT data FROM sessions WHERE id = ?1", session_id)?
+        .map(|data| decode(StorageFormat::SessionMetadata, &data))
+        .transpose()
+}
+
+/// Insert or replace a session's metadata
+pub fn save_metadata(conn: &Connection, metadata: &SessionMetadata) -> Result<(), String> {
+    let backend = serde_json::to_value(&metadata.backend)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let updated_at = metadata
+        .runs
+        .last()
+        .map(|r| r.ended_at.unwrap_or(r.started_at))
+        .unwrap_or(metadata.created_at);
+
+    conn.execute(
+        "INSERT OR REPLACE INTO sessions
+            (id, worktree_id, name, backend, created_at, updated_at, archived_at, label, run_count, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            metadata.id,
+            metadata.worktree_id,
+            metadata.name,
+            backend,
+            metadata.created_at as i64,
+            updated_at as i64,
+            metadata.archived_at.map(|t| t as i64),
+            metadata.label.as_ref().map(|l| l.name.as_str()),
+            metadata.runs.len() as i64,
+            encode(metadata)?
+        ],
+    )
+    .map_err(|e| format!("Failed to save metadata for {}: {e}", metadata.id))?;
+    Ok(())
+}
+
+/// Delete a session's metadata
+pub fn delete_metadata(conn: &Connection, session_id: &str) -> Result<(), String> {
+    conn.execute("DELETE FROM sessions WHERE id = ?1", [session_id])
+        .map_err(|e| format!("Failed to delete metadata for {session_id}: {e}"))?;
+    Ok(())
+}
+
+/// IDs of all sessions with metadata
+pub fn list_session_ids(conn: &Connection) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT id FROM sessions")
+        .map_err(|e| format!("Failed to list sessions: {e}"))?;
+    let ids = stmt
+        .query_map([], |row| row.get(0))
+        .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+        .map_err(|e| format!("Failed to list sessions: {e}"))?;
+    Ok(ids)
+}
+
+// ============================================================================
+// Queries
+// ============================================================================
+
+/// Filter for `query_sessions`. Unset fields match everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SessionQuery {
+    #[serde(default)]
+    pub project_id: Option<String>,
+    #[serde(default)]
+    pub worktree_id: Option<String>,
+    #[serde(default)]
+    pub backend: Option<Backend>,
+    /// Label name
+    #[serde(default)]
+    pub label: Option<String>,
+    /// true for archived sessions only, false for active ones only
+    #[serde(default)]
+    pub archived: Option<bool>,
+    /// Case-insensitive substring of the session name
+    #[serde(default)]
+    pub name_contains: Option<String>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+/// One row of `query_sessions`
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub worktree_id: String,
+    /// None when the worktree no longer exists
+    pub project_id: Option<String>,
+    pub name: String,
+    pub backend: Backend,
+    pub created_at: u64,
+    /// End of the last run (or creation time without runs)
+    pub updated_at: u64,
+    pub archived_at: Option<u64>,
+    pub label: Option<String>,
+    pub run_count: u32,
+}
+
+/// Sessions matching `query`, most recently updated first
+pub fn query_sessions(
+    conn: &Connection,
+    query: &SessionQuery,
+) -> Result<Vec<SessionSummary>, String> {
+    let mut conditions = Vec::new();
+    let mut values: Vec<SqlValue> = Vec::new();
+
+    if let Some(project_id) = &query.project_id {
+        values.push(project_id.clone().into());
+        conditions.push(format!("w.project_id = ?{}", values.len()));
+    }
+    if let Some(worktree_id) = &query.worktree_id {
+        values.push(worktree_id.clone().into());
+        conditions.push(format!("s.worktree_id = ?{}", values.len()));
+    }
+    if let Some(backend) = &query.backend {
+        let backend = serde_json::to_value(backend)
+            .ok()
+            .and_then(|v| v.as_str().map(str::to_string))
+            .unwrap_or_default();
+        values.push(backend.into());
+        conditions.push(format!("s.backend = ?{}", values.len()));
+    }
+    if let Some(label) = &query.label {
+        values.push(label.clone().into());
+        conditions.push(format!("s.label = ?{}", values.len()));
+    }
+    match query.archived {
+        Some(true) => conditions.push("s.archived_at IS NOT NULL".to_string()),
+        Some(false) => conditions.push("s.archived_at IS NULL".to_string()),
+        None => {}
+    }
+    if let Some(needle) = &query.name_contains {
+        values.push(needle.to_lowercase().into());
+        conditions.push(format!("instr(lower(s.name), ?{}) > 0", values.len()));
+    }
+
+    let mut sql = "SELECT s.id, s.worktree_id, w.project_id, s.name, s.backend, s.created_at,
+                          s.updated_at, s.archived_at, s.label, s.run_count
+                   FROM sessions s LEFT JOIN worktrees w ON w.id = s.worktree_id"
+        .to_string();
+    if !conditions.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&conditions.join(" AND "));
+    }
+    sql.push_str(" ORDER BY s.updated_at DESC, s.id");
+    if let Some(limit) = query.limit {
+        values.push(i64::from(limit).into());
+        sql.push_str(&format!(" LIMIT ?{}", values.len()));
+    }
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Failed to query sessions: {e}"))?;
+    let rows = stmt
+        .query_map(params_from_iter(values), |row| {
+            Ok(SessionSummary {
+                id: row.get(0)?,
+                worktree_id: row.get(1)?,
+                project_id: row.get(2)?,
+                name: row.get(3)?,
+                backend: serde_json::from_value(serde_json::Value::String(row.get(4)?))
+                    .unwrap_or_default(),
+                created_at: row.get::<_, i64>(5)? as u64,
+                updated_at: row.get::<_, i64>(6)? as u64,
+                archived_at: row.get::<_, Option<i64>>(7)?.map(|t| t as u64),
+                label: row.get(8)?,
+                run_count: row.get::<_, i64>(9)? as u32,
+            })
+        })
+        .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| format!("Failed to query sessions: {e}"))?;
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::LabelData;
+    use crate::db::open_in_memory;
+
+    fn session(id: &str, worktree_id: &str, name: &str, created_at: u64) -> SessionMetadata {
+        let mut metadata =
+            SessionMetadata::new(id.to_string(), worktree_id.to_string(), name.to_string(), 0);
+        metadata.created_at = created_at;
+        metadata
+    }
+
+    #[test]
+    fn test_metadata_round_trip_and_delete() {
+        let conn = open_in_memory();
+        let metadata = session("s1", "wt1", "First", 100);
+        save_metadata(&conn, &metadata).unwrap();
+
+        let loaded = load_metadata(&conn, "s1").unwrap().unwrap();
+        assert_eq!(loaded.name, "First");
+        assert_eq!(list_session_ids(&conn).unwrap(), ["s1"]);
+
+        delete_metadata(&conn, "s1").unwrap();
+        assert!(load_metadata(&conn, "s1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_query_sessions_filters() {
+        let conn = open_in_memory();
+        let mut archived = session("s1", "wt1", "Fix login bug", 100);
+        archived.archived_at = Some(500);
+        let mut labelled = session("s2", "wt1", "Refactor", 200);
+        labelled.label = Some(LabelData {
+            name: "Needs testing".to_string(),
+            color: "#eab308".to_string(),
+        });
+        let mut other = session("s3", "wt2", "Login page", 300);
+        other.backend = Backend::Codex;
+        for metadata in [&archived, &labelled, &other] {
+            save_metadata(&conn, metadata).unwrap();
+        }
+
+        let ids = |query: SessionQuery| -> Vec<String> {
+            query_sessions(&conn, &query)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.id)
+                .collect()
+        };
+
+        assert_eq!(ids(SessionQuery::default()), ["s3", "s2", "s1"]);
+        let by_worktree = SessionQuery {
+            worktree_id: Some("wt1".to_string()),
+            archived: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_worktree), ["s2"]);
+        let by_name = SessionQuery {
+            name_contains: Some("LOGIN".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_name), ["s3"]);
+        let by_label = SessionQuery {
+            label: Some("Needs testing".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_label), ["s2"]);
+        let by_backend = SessionQuery {
+            backend: Some(Backend::Codex),
+            ..Default::default()
+        };
+        assert_eq!(ids(by_backend), ["s3"]);
+    }
+
+    #[test]
+    fn test_referenced_session_ids_include_preserved_indexes() {
+        let conn = open_in_memory();
+        let index = WorktreeIndex::new("wt1".to_string());
+        save_index(&conn, &index).unwrap();
+        let mut preserved = WorktreeIndex::new("base".to_string());
+        preserved.sessions[0].id = "kept".to_string();
+        save_preserved_index(&conn, "project-1", &preserved).unwrap();
+
+        let ids = referenced_session_ids(&conn).unwrap();
+        assert!(ids.contains(&index.sessions[0].id));
+        assert!(ids.contains("kept"));
+
+        assert!(delete_preserved_index(&conn, "project-1").unwrap());
+        assert!(!delete_preserved_index(&conn, "project-1").unwrap());
+    }
+}
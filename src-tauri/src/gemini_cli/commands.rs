@@ -0,0 +1,351 @@
+//! Tauri commands for Gemini CLI management
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::config::{ensure_cli_dir, resolve_cli_binary, NPM_PACKAGE};
+use crate::http_server::EmitExt;
+use crate::platform::silent_command;
+
+/// npm registry metadata for the Gemini CLI package
+const GEMINI_REGISTRY_URL: &str = "https://registry.npmjs.org/@google/gemini-cli";
+
+/// npm executable (a .cmd shim on Windows)
+#[cfg(windows)]
+const NPM_BINARY: &str = "npm.cmd";
+#[cfg(not(windows))]
+const NPM_BINARY: &str = "npm";
+
+/// Environment variables Gemini CLI accepts as credentials
+const AUTH_ENV_VARS: [&str; 3] = [
+    "GEMINI_API_KEY",
+    "GOOGLE_API_KEY",
+    "GOOGLE_GENAI_USE_VERTEXAI",
+];
+
+/// Status of the Gemini CLI installation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiCliStatus {
+    pub installed: bool,
+    pub version: Option<String>,
+    pub path: Option<String>,
+}
+
+/// Auth status of the Gemini CLI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiAuthStatus {
+    pub authenticated: bool,
+    pub error: Option<String>,
+}
+
+/// Information about a Gemini CLI release
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiReleaseInfo {
+    pub version: String,
+    pub tag_name: String,
+    pub published_at: String,
+    pub prerelease: bool,
+}
+
+/// Progress event for CLI installation
+#[derive(Debug, Clone, Serialize)]
+pub struct GeminiInstallProgress {
+    pub stage: String,
+    pub message: String,
+    pub percent: u8,
+}
+
+/// npm registry package document (only the fields we need)
+#[derive(Debug, Deserialize)]
+struct NpmPackage {
+    #[serde(rename = "dist-tags", default)]
+    dist_tags: HashMap<String, String>,
+    /// Version → publish timestamp (also contains "created"/"modified")
+    #[serde(default)]
+    time: HashMap<String, String>,
+}
+
+fn emit_progress(app: &AppHandle, stage: &str, message: &str, percent: u8) {
+    let _ = app.emit_all(
+        "gemini-cli:install-progress",
+        &GeminiInstallProgress {
+            stage: stage.to_string(),
+            message: message.to_string(),
+            percent,
+        },
+    );
+}
+
+async fn fetch_package_info() -> Result<NpmPackage, String> {
+    let client = reqwest::Client::builder()
+        .user_agent("Jean-App/1.0")
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {e}"))?;
+
+    let response = client
+        .get(GEMINI_REGISTRY_URL)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch package info: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "npm registry returned status: {}",
+            response.status()
+        ));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse npm registry response: {e}"))
+}
+
+/// Newest stable releases (no prerelease suffix), newest first
+fn stable_releases(package: &NpmPackage, limit: usize) -> Vec<GeminiReleaseInfo> {
+    let mut releases: Vec<GeminiReleaseInfo> = package
+        .time
+        .iter()
+        .filter(|(version, _)| {
+            version.chars().next().is_some_and(|c| c.is_ascii_digit()) && !version.contains('-')
+        })
+        .map(|(version, published_at)| GeminiReleaseInfo {
+            version: version.clone(),
+            tag_name: format!("v{version}"),
+            published_at: published_at.clone(),
+            prerelease: false,
+        })
+        .collect();
+    // RFC 3339 timestamps sort chronologically as strings
+    releases.sort_by(|a, b| b.published_at.cmp(&a.published_at));
+    releases.truncate(limit);
+    releases
+}
+
+/// Which credential source Gemini CLI would pick up, if any
+fn find_auth_source(
+    gemini_dir: Option<&Path>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    if let Some(var) = AUTH_ENV_VARS
+        .iter()
+        .find(|var| env(var).is_some_and(|v| !v.trim().is_empty()))
+    {
+        return Some(format!("{var} environment variable"));
+    }
+
+    let gemini_dir = gemini_dir?;
+    if gemini_dir.join("oauth_creds.json").exists() {
+        return Some("Google login".to_string());
+    }
+    // Gemini CLI also loads ~/.gemini/.env
+    let dotenv = std::fs::read_to_string(gemini_dir.join(".env")).unwrap_or_default();
+    dotenv
+        .lines()
+        .filter_map(|line| line.trim().split_once('='))
+        .map(|(key, value)| (key.trim_start_matches("export ").trim(), value.trim()))
+        .find(|(key, value)| AUTH_ENV_VARS.contains(key) && !value.is_empty())
+        .map(|(key, _)| format!("{key} in ~/.gemini/.env"))
+}
+
+/// Check if Gemini CLI is installed and get its status
+#[tauri::command]
+pub async fn check_gemini_cli_installed(app: AppHandle) -> Result<GeminiCliStatus, String> {
+    log::trace!("Checking Gemini CLI installation status");
+
+    let binary_path = resolve_cli_binary(&app);
+
+    if !binary_path.exists() {
+        log::trace!("Gemini CLI not found at {:?}", binary_path);
+        return Ok(GeminiCliStatus {
+            installed: false,
+            version: None,
+            path: None,
+        });
+    }
+
+    // gemini --version prints just the version, e.g. "0.9.0"
+    let version = match silent_command(&binary_path).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            version_str
+                .split_whitespace()
+                .last()
+                .map(|s| s.trim_start_matches('v').to_string())
+        }
+        _ => None,
+    };
+
+    Ok(GeminiCliStatus {
+        installed: true,
+        version,
+        path: Some(binary_path.to_string_lossy().to_string()),
+    })
+}
+
+/// Check if Gemini CLI has credentials.
+///
+/// Gemini CLI has no non-interactive auth status command, so this looks for the
+/// credentials it would use: an API key in the environment or `~/.gemini/.env`,
+/// or a cached Google login in `~/.gemini/oauth_creds.json`.
+#[tauri::command]
+pub async fn check_gemini_cli_auth(app: AppHandle) -> Result<GeminiAuthStatus, String> {
+    log::trace!("Checking Gemini CLI authentication status");
+
+    if !resolve_cli_binary(&app).exists() {
+        return Ok(GeminiAuthStatus {
+            authenticated: false,
+            error: Some("Gemini CLI not installed".to_string()),
+        });
+    }
+
+    let gemini_dir = dirs::home_dir().map(|home| home.join(".gemini"));
+    match find_auth_source(gemini_dir.as_deref(), |var| std::env::var(var).ok()) {
+        Some(source) => {
+            log::trace!("Gemini CLI credentials found: {source}");
+            Ok(GeminiAuthStatus {
+                authenticated: true,
+                error: None,
+            })
+        }
+        None => Ok(GeminiAuthStatus {
+            authenticated: false,
+            error: Some(
+                "Not authenticated. Run `gemini` once to sign in with Google, or set GEMINI_API_KEY."
+                    .to_string(),
+            ),
+        }),
+    }
+}
+
+/// Get available Gemini CLI versions from the npm registry
+#[tauri::command]
+pub async fn get_available_gemini_versions() -> Result<Vec<GeminiReleaseInfo>, String> {
+    log::trace!("Fetching available Gemini CLI versions from npm registry");
+
+    let package = fetch_package_info().await?;
+    let versions = stable_releases(&package, 5);
+
+    log::trace!("Found {} Gemini CLI versions", versions.len());
+    Ok(versions)
+}
+
+/// Install Gemini CLI into the Jean-managed npm prefix
+#[tauri::command]
+pub async fn install_gemini_cli(app: AppHandle, version: Option<String>) -> Result<(), String> {
+    log::trace!("Installing Gemini CLI, version: {:?}", version);
+
+    let cli_dir = ensure_cli_dir(&app)?;
+
+    // Emit progress: starting
+    emit_progress(&app, "starting", "Preparing installation...", 0);
+
+    // Determine version
+    let version = match version {
+        Some(v) => v,
+        None => fetch_package_info()
+            .await?
+            .dist_tags
+            .get("latest")
+            .cloned()
+            .ok_or("npm registry has no latest Gemini CLI version")?,
+    };
+
+    // Emit progress: installing
+    emit_progress(
+        &app,
+        "installing",
+        &format!("Installing Gemini CLI {version} with npm..."),
+        20,
+    );
+
+    let package_spec = format!("{NPM_PACKAGE}@{version}");
+    let install_dir = cli_dir.clone();
+    let output = tokio::task::spawn_blocking(move || {
+        silent_command(NPM_BINARY)
+            .args(["install", "--no-audit", "--no-fund", "--prefix"])
+            .arg(&install_dir)
+            .arg(&package_spec)
+            .current_dir(&install_dir)
+            .output()
+    })
+    .await
+    .map_err(|e| format!("npm install task failed: {e}"))?
+    .map_err(|e| {
+        format!("Failed to run npm: {e}. Gemini CLI requires Node.js 20 or newer with npm.")
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("npm install failed: {}", stderr.trim()));
+    }
+
+    // Emit progress: verifying
+    emit_progress(&app, "verifying", "Verifying installation...", 80);
+
+    let binary_path = resolve_cli_binary(&app);
+    let version_output = silent_command(&binary_path)
+        .arg("--version")
+        .output()
+        .map_err(|e| format!("Failed to verify Gemini CLI: {e}"))?;
+
+    if !version_output.status.success() {
+        let stderr = String::from_utf8_lossy(&version_output.stderr);
+        return Err(format!("Gemini CLI verification failed: {stderr}"));
+    }
+
+    // Emit progress: complete
+    emit_progress(&app, "complete", "Installation complete!", 100);
+
+    log::trace!("Gemini CLI installed successfully at {:?}", binary_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_releases_skip_prereleases_and_metadata() {
+        let package: NpmPackage = serde_json::from_value(serde_json::json!({
+            "dist-tags": { "latest": "0.9.0", "preview": "0.10.0-preview.1" },
+            "time": {
+                "created": "2025-06-01T00:00:00.000Z",
+                "modified": "2025-10-01T00:00:00.000Z",
+                "0.8.2": "2025-09-20T00:00:00.000Z",
+                "0.9.0": "2025-09-30T00:00:00.000Z",
+                "0.10.0-preview.1": "2025-10-01T00:00:00.000Z"
+            }
+        }))
+        .unwrap();
+
+        let releases = stable_releases(&package, 5);
+        let versions: Vec<&str> = releases.iter().map(|r| r.version.as_str()).collect();
+        assert_eq!(versions, ["0.9.0", "0.8.2"]);
+        assert_eq!(releases[0].tag_name, "v0.9.0");
+        assert_eq!(package.dist_tags["latest"], "0.9.0");
+    }
+
+    #[test]
+    fn auth_source_from_env_and_dotenv() {
+        let env_key = find_auth_source(None, |var| {
+            (var == "GEMINI_API_KEY").then(|| "secret".to_string())
+        });
+        assert_eq!(
+            env_key.as_deref(),
+            Some("GEMINI_API_KEY environment variable")
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(find_auth_source(Some(dir.path()), |_| None), None);
+
+        std::fs::write(dir.path().join(".env"), "export GOOGLE_API_KEY=abc\n").unwrap();
+        assert_eq!(
+            find_auth_source(Some(dir.path()), |_| None).as_deref(),
+            Some("GOOGLE_API_KEY in ~/.gemini/.env")
+        );
+    }
+}
@@ -0,0 +1,77 @@
+//! Configuration and path management for the Gemini CLI
+//!
+//! Gemini CLI is distributed as an npm package (`@google/gemini-cli`), so it
+//! is installed into a Jean-managed npm prefix and run through the
+//! `node_modules/.bin` shim.
+
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+/// Directory name for the Gemini CLI npm prefix
+pub const CLI_DIR_NAME: &str = "gemini-cli";
+
+/// npm package that provides the `gemini` binary
+pub const NPM_PACKAGE: &str = "@google/gemini-cli";
+
+/// Name of the Gemini CLI shim in `node_modules/.bin`
+#[cfg(windows)]
+pub const CLI_BINARY_NAME: &str = "gemini.cmd";
+#[cfg(not(windows))]
+pub const CLI_BINARY_NAME: &str = "gemini";
+
+/// Get the npm prefix directory Gemini CLI is installed into
+///
+/// Returns: `~/Library/Application Support/jean/gemini-cli/`
+pub fn get_cli_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join(CLI_DIR_NAME))
+}
+
+/// Get the full path to the Gemini CLI binary
+///
+/// Returns: `~/Library/Application Support/jean/gemini-cli/node_modules/.bin/gemini`
+pub fn get_cli_binary_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(binary_path_in(get_cli_dir(app)?))
+}
+
+/// Resolve Gemini binary path in Jean-managed app data only.
+///
+/// This intentionally does not fall back to PATH/global installs.
+pub fn resolve_cli_binary(app: &AppHandle) -> PathBuf {
+    get_cli_binary_path(app).unwrap_or_else(|_| binary_path_in(PathBuf::from(CLI_DIR_NAME)))
+}
+
+/// Ensure the CLI directory exists, creating it if necessary
+pub fn ensure_cli_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let cli_dir = get_cli_dir(app)?;
+    std::fs::create_dir_all(&cli_dir)
+        .map_err(|e| format!("Failed to create CLI directory: {e}"))?;
+    Ok(cli_dir)
+}
+
+fn binary_path_in(cli_dir: PathBuf) -> PathBuf {
+    cli_dir
+        .join("node_modules")
+        .join(".bin")
+        .join(CLI_BINARY_NAME)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_lives_in_npm_bin_dir() {
+        let resolved = binary_path_in(PathBuf::from(CLI_DIR_NAME));
+
+        assert!(resolved.ends_with(
+            PathBuf::from("node_modules")
+                .join(".bin")
+                .join(CLI_BINARY_NAME)
+        ));
+        assert!(resolved.starts_with(CLI_DIR_NAME));
+    }
+}
@@ -0,0 +1,123 @@
+//! MCP server discovery for Gemini CLI settings files.
+//!
+//! Reads:
+//! - Project scope: <worktree_path>/.gemini/settings.json → `mcpServers` object
+//! - Global scope:  ~/.gemini/settings.json                → same format
+//!
+//! Gemini settings example:
+//!   {
+//!     "mcpServers": {
+//!       "filesystem": { "command": "npx", "args": ["-y", "..."] },
+//!       "notion":     { "httpUrl": "https://mcp.notion.com/mcp" }
+//!     },
+//!     "mcp": { "excluded": ["notion"] }
+//!   }
+
+use crate::chat::McpServerInfo;
+use std::collections::HashSet;
+
+/// Discover Gemini MCP servers from all configuration sources.
+/// Precedence (highest to lowest): project → global.
+pub fn get_mcp_servers(worktree_path: Option<&str>) -> Vec<McpServerInfo> {
+    let mut servers = Vec::new();
+    let mut seen_names = HashSet::new();
+
+    // 1. Project scope (highest precedence): <worktree_path>/.gemini/settings.json
+    if let Some(wt_path) = worktree_path {
+        let project_config = std::path::PathBuf::from(wt_path)
+            .join(".gemini")
+            .join("settings.json");
+        collect_from_settings(&project_config, "project", &mut servers, &mut seen_names);
+    }
+
+    // 2. Global scope: ~/.gemini/settings.json
+    if let Some(home) = dirs::home_dir() {
+        let global_config = home.join(".gemini").join("settings.json");
+        collect_from_settings(&global_config, "user", &mut servers, &mut seen_names);
+    }
+
+    servers
+}
+
+fn collect_from_settings(
+    path: &std::path::Path,
+    scope: &str,
+    servers: &mut Vec<McpServerInfo>,
+    seen_names: &mut HashSet<String>,
+) {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return;
+    };
+
+    // Settings may contain comments; only strip them when plain JSON fails so
+    // `//` inside URLs survives
+    let json = serde_json::from_str::<serde_json::Value>(&content).or_else(|_| {
+        serde_json::from_str(&crate::opencode_cli::mcp::strip_jsonc_comments(&content))
+    });
+    let Ok(json) = json else {
+        log::warn!("Failed to parse Gemini settings at {}", path.display());
+        return;
+    };
+
+    servers.extend(servers_from_settings(&json, scope, seen_names));
+}
+
+fn servers_from_settings(
+    json: &serde_json::Value,
+    scope: &str,
+    seen_names: &mut HashSet<String>,
+) -> Vec<McpServerInfo> {
+    let Some(mcp_servers) = json.get("mcpServers").and_then(|v| v.as_object()) else {
+        return Vec::new();
+    };
+
+    // Servers listed in mcp.excluded are not started by Gemini
+    let excluded: HashSet<&str> = json
+        .pointer("/mcp/excluded")
+        .and_then(|v| v.as_array())
+        .map(|names| names.iter().filter_map(|n| n.as_str()).collect())
+        .unwrap_or_default();
+
+    mcp_servers
+        .iter()
+        .filter(|(name, _)| seen_names.insert((*name).clone()))
+        .map(|(name, config)| McpServerInfo {
+            name: name.clone(),
+            config: config.clone(),
+            scope: scope.to_string(),
+            disabled: excluded.contains(name.as_str()),
+            backend: "gemini".to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn project_servers_shadow_global_and_excluded_are_disabled() {
+        let mut seen = HashSet::new();
+        let project = serde_json::json!({
+            "mcpServers": { "notion": { "httpUrl": "https://mcp.notion.com/mcp" } },
+            "mcp": { "excluded": ["notion"] }
+        });
+        let global = serde_json::json!({
+            "mcpServers": {
+                "notion": { "command": "notion-mcp" },
+                "filesystem": { "command": "npx", "args": ["-y", "fs-server"] }
+            }
+        });
+
+        let mut servers = servers_from_settings(&project, "project", &mut seen);
+        servers.extend(servers_from_settings(&global, "user", &mut seen));
+
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].name, "notion");
+        assert_eq!(servers[0].scope, "project");
+        assert!(servers[0].disabled);
+        assert_eq!(servers[1].name, "filesystem");
+        assert!(!servers[1].disabled);
+        assert_eq!(servers[1].backend, "gemini");
+    }
+}
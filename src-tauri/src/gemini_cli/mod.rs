@@ -0,0 +1,10 @@
+//! Gemini CLI management module
+//!
+//! Handles resolving, installing, and authenticating the Gemini CLI.
+
+mod commands;
+mod config;
+pub mod mcp;
+
+pub use commands::*;
+pub use config::{get_cli_binary_path, resolve_cli_binary};
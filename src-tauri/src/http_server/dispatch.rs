@@ -658,6 +658,28 @@ pub async fn dispatch_command(
                     .await?;
             to_value(result)
         }
+        "fork_session" => {
+            let worktree_id: String = field(&args, "worktreeId", "worktree_id")?;
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let message_id: String = field(&args, "messageId", "message_id")?;
+            let target_worktree_id: Option<String> =
+                field_opt(&args, "targetWorktreeId", "target_worktree_id")?;
+            let target_worktree_path: Option<String> =
+                field_opt(&args, "targetWorktreePath", "target_worktree_path")?;
+            let name: Option<String> = from_field_opt(&args, "name")?;
+            let result = crate::chat::fork_session(
+                app.clone(),
+                worktree_id,
+                session_id,
+                message_id,
+                target_worktree_id,
+                target_worktree_path,
+                name,
+            )
+            .await?;
+            emit_cache_invalidation(app, &["sessions"]);
+            to_value(result)
+        }
         "rename_session" => {
             let worktree_id: String = field(&args, "worktreeId", "worktree_id")?;
             let worktree_path: String = field(&args, "worktreePath", "worktree_path")?;
@@ -837,6 +859,290 @@ pub async fn dispatch_command(
             to_value(result)
         }
 
+        // =====================================================================
+        // Message Queue
+        // =====================================================================
+        "get_queued_messages" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let result = crate::chat::get_queued_messages(app.clone(), session_id).await?;
+            to_value(result)
+        }
+        "enqueue_message" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let worktree_path: String = field(&args, "worktreePath", "worktree_path")?;
+            let message: String = from_field(&args, "message")?;
+            let model: Option<String> = from_field_opt(&args, "model")?;
+            let execution_mode: Option<String> =
+                field_opt(&args, "executionMode", "execution_mode")?;
+            let thinking_level = field_opt(&args, "thinkingLevel", "thinking_level")?;
+            let effort_level: Option<crate::chat::types::EffortLevel> =
+                field_opt(&args, "effortLevel", "effort_level")?;
+            let parallel_execution_prompt: Option<String> = field_opt(
+                &args,
+                "parallelExecutionPrompt",
+                "parallel_execution_prompt",
+            )?;
+            let ai_language: Option<String> = field_opt(&args, "aiLanguage", "ai_language")?;
+            let allowed_tools: Option<Vec<String>> =
+                field_opt(&args, "allowedTools", "allowed_tools")?;
+            let mcp_config: Option<String> = field_opt(&args, "mcpConfig", "mcp_config")?;
+            let chrome_enabled: Option<bool> = field_opt(&args, "chromeEnabled", "chrome_enabled")?;
+            let custom_profile_name: Option<String> =
+                field_opt(&args, "customProfileName", "custom_profile_name")?;
+            let backend: Option<String> = field_opt(&args, "backend", "backend")?;
+            let result = crate::chat::enqueue_message(
+                app.clone(),
+                session_id,
+                worktree_path,
+                message,
+                model,
+                execution_mode,
+                thinking_level,
+                effort_level,
+                parallel_execution_prompt,
+                ai_language,
+                allowed_tools,
+                mcp_config,
+                chrome_enabled,
+                custom_profile_name,
+                backend,
+            )
+            .await?;
+            to_value(result)
+        }
+        "update_queued_message" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let queued_message_id: String = field(&args, "queuedMessageId", "queued_message_id")?;
+            let message: String = from_field(&args, "message")?;
+            let model: Option<String> = from_field_opt(&args, "model")?;
+            let execution_mode: Option<String> =
+                field_opt(&args, "executionMode", "execution_mode")?;
+            let result = crate::chat::update_queued_message(
+                app.clone(),
+                session_id,
+                queued_message_id,
+                message,
+                model,
+                execution_mode,
+            )
+            .await?;
+            to_value(result)
+        }
+        "reorder_queued_messages" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let queued_message_ids: Vec<String> =
+                field(&args, "queuedMessageIds", "queued_message_ids")?;
+            let result =
+                crate::chat::reorder_queued_messages(app.clone(), session_id, queued_message_ids)
+                    .await?;
+            to_value(result)
+        }
+        "remove_queued_message" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let queued_message_id: String = field(&args, "queuedMessageId", "queued_message_id")?;
+            let result =
+                crate::chat::remove_queued_message(app.clone(), session_id, queued_message_id)
+                    .await?;
+            to_value(result)
+        }
+        "clear_message_queue" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            crate::chat::clear_message_queue(app.clone(), session_id).await?;
+            Ok(Value::Null)
+        }
+        "resume_message_queue" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let result = crate::chat::resume_message_queue(app.clone(), session_id).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Search
+        // =====================================================================
+        "search_sessions" => {
+            let query: String = from_field(&args, "query")?;
+            let filters: Option<crate::chat::SearchFilters> = from_field_opt(&args, "filters")?;
+            let result = crate::chat::search_sessions(app.clone(), query, filters).await?;
+            to_value(result)
+        }
+        "rebuild_search_index" => {
+            crate::chat::rebuild_search_index(app.clone()).await?;
+            Ok(Value::Null)
+        }
+        // =====================================================================
+        // Export / Import
+        // =====================================================================
+        "export_session_bundle" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let output_path: String = field(&args, "outputPath", "output_path")?;
+            let result =
+                crate::chat::export_session_bundle(app.clone(), session_id, output_path).await?;
+            to_value(result)
+        }
+        "import_session_bundle" => {
+            let worktree_id: String = field(&args, "worktreeId", "worktree_id")?;
+            let bundle_path: String = field(&args, "bundlePath", "bundle_path")?;
+            let result =
+                crate::chat::import_session_bundle(app.clone(), worktree_id, bundle_path).await?;
+            emit_cache_invalidation(app, &["sessions"]);
+            to_value(result)
+        }
+        "export_session_transcript" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let format: crate::chat::TranscriptFormat = from_field(&args, "format")?;
+            let output_path: Option<String> = field_opt(&args, "outputPath", "output_path")?;
+            let result = crate::chat::export_session_transcript(
+                app.clone(),
+                session_id,
+                format,
+                output_path,
+            )
+            .await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Cost Accounting
+        // =====================================================================
+        "get_pricing_table" => {
+            let result = crate::chat::get_pricing_table(app.clone()).await?;
+            to_value(result)
+        }
+        "set_pricing_overrides" => {
+            let overrides: Vec<crate::chat::ModelRate> = from_field(&args, "overrides")?;
+            let result = crate::chat::set_pricing_overrides(app.clone(), overrides).await?;
+            to_value(result)
+        }
+        "get_cost_summary" => {
+            let group_by: crate::chat::CostGroupBy = field(&args, "groupBy", "group_by")?;
+            let filters: Option<crate::chat::CostFilters> = from_field_opt(&args, "filters")?;
+            let reprice: Option<bool> = from_field_opt(&args, "reprice")?;
+            let result =
+                crate::chat::get_cost_summary(app.clone(), group_by, filters, reprice).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Usage analytics
+        // =====================================================================
+        "get_usage_analytics" => {
+            let filters: Option<crate::chat::CostFilters> = from_field_opt(&args, "filters")?;
+            let result = crate::chat::get_usage_analytics(app.clone(), filters).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Budgets
+        // =====================================================================
+        "get_budget_settings" => {
+            let result = crate::chat::get_budget_settings(app.clone()).await?;
+            to_value(result)
+        }
+        "set_budget_settings" => {
+            let settings: crate::chat::BudgetSettings = from_field(&args, "settings")?;
+            crate::chat::set_budget_settings(app.clone(), settings).await?;
+            Ok(Value::Null)
+        }
+        "get_budget_status" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let result = crate::chat::get_budget_status(app.clone(), session_id).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Backends
+        // =====================================================================
+        "get_backend_capabilities" => {
+            let backend: String = field(&args, "backend", "backend")?;
+            let result = crate::chat::get_backend_capabilities(backend).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Replay
+        // =====================================================================
+        "replay_run" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let run_id: String = field(&args, "runId", "run_id")?;
+            let speed: Option<f64> = from_field_opt(&args, "speed")?;
+            let result = crate::chat::replay_run(app.clone(), session_id, run_id, speed).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Checkpoints
+        // =====================================================================
+        "restore_checkpoint" => {
+            let worktree_path: String = field(&args, "worktreePath", "worktree_path")?;
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let run_id: String = field(&args, "runId", "run_id")?;
+            let result =
+                crate::chat::restore_checkpoint(app.clone(), worktree_path, session_id, run_id)
+                    .await?;
+            to_value(result)
+        }
+        "get_run_diff" => {
+            let worktree_path: String = field(&args, "worktreePath", "worktree_path")?;
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let run_id: String = field(&args, "runId", "run_id")?;
+            let result =
+                crate::chat::get_run_diff(app.clone(), worktree_path, session_id, run_id).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Permission policy
+        // =====================================================================
+        "get_permission_audit" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let result = crate::chat::policy::get_permission_audit(app.clone(), session_id).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Compaction
+        // =====================================================================
+        "compact_session" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let worktree_id: String = field(&args, "worktreeId", "worktree_id")?;
+            let worktree_path: String = field(&args, "worktreePath", "worktree_path")?;
+            let instructions: Option<String> = from_field_opt(&args, "instructions")?;
+            let result = crate::chat::compact_session(
+                app.clone(),
+                session_id,
+                worktree_id,
+                worktree_path,
+                instructions,
+            )
+            .await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Storage retention
+        // =====================================================================
+        "get_storage_settings" => {
+            let result = crate::chat::get_storage_settings(app.clone()).await?;
+            to_value(result)
+        }
+        "set_storage_settings" => {
+            let settings: crate::chat::StorageSettings = from_field(&args, "settings")?;
+            crate::chat::set_storage_settings(app.clone(), settings).await?;
+            Ok(Value::Null)
+        }
+        "get_disk_usage" => {
+            let result = crate::chat::get_disk_usage(app.clone()).await?;
+            to_value(result)
+        }
+        "run_storage_maintenance" => {
+            let result = crate::chat::run_storage_maintenance(app.clone()).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Storage migrations
+        // =====================================================================
+        "check_storage_migrations" => {
+            let result = crate::migrations::commands::check_storage_migrations(app.clone()).await?;
+            to_value(result)
+        }
+        // =====================================================================
+        // Session queries
+        // =====================================================================
+        "query_sessions" => {
+            let query = from_field(&args, "query")?;
+            let result = crate::db::commands::query_sessions(app.clone(), query).await?;
+            to_value(result)
+        }
         // =====================================================================
         // Chat - Saved Contexts
         // =====================================================================
@@ -959,6 +1265,53 @@ pub async fn dispatch_command(
             to_value(result)
         }
 
+        // =====================================================================
+        // Scheduler
+        // =====================================================================
+        "list_schedules" => {
+            let result = crate::scheduler::commands::list_schedules(app.clone()).await?;
+            to_value(result)
+        }
+        "create_schedule" => {
+            let config: crate::scheduler::ScheduleConfig = from_field(&args, "config")?;
+            let result = crate::scheduler::commands::create_schedule(app.clone(), config).await?;
+            to_value(result)
+        }
+        "update_schedule" => {
+            let schedule_id: String = field(&args, "scheduleId", "schedule_id")?;
+            let config: crate::scheduler::ScheduleConfig = from_field(&args, "config")?;
+            let result =
+                crate::scheduler::commands::update_schedule(app.clone(), schedule_id, config)
+                    .await?;
+            to_value(result)
+        }
+        "set_schedule_enabled" => {
+            let schedule_id: String = field(&args, "scheduleId", "schedule_id")?;
+            let enabled: bool = from_field(&args, "enabled")?;
+            let result =
+                crate::scheduler::commands::set_schedule_enabled(app.clone(), schedule_id, enabled)
+                    .await?;
+            to_value(result)
+        }
+        "delete_schedule" => {
+            let schedule_id: String = field(&args, "scheduleId", "schedule_id")?;
+            crate::scheduler::commands::delete_schedule(app.clone(), schedule_id).await?;
+            Ok(Value::Null)
+        }
+        "run_schedule_now" => {
+            let schedule_id: String = field(&args, "scheduleId", "schedule_id")?;
+            let result =
+                crate::scheduler::commands::run_schedule_now(app.clone(), schedule_id).await?;
+            to_value(result)
+        }
+        "preview_schedule_trigger" => {
+            let trigger: crate::scheduler::ScheduleTrigger = from_field(&args, "trigger")?;
+            let count: Option<usize> = from_field_opt(&args, "count")?;
+            let result =
+                crate::scheduler::commands::preview_schedule_trigger(trigger, count).await?;
+            to_value(result)
+        }
+
         // =====================================================================
         // Terminal
         // =====================================================================
@@ -1482,6 +1835,10 @@ pub async fn dispatch_command(
             let result = crate::chat::read_pasted_text(app.clone(), path).await?;
             to_value(result)
         }
+        "save_dropped_document" => {
+            // NATIVE ONLY: Drag-drop from native file paths doesn't work in browser
+            Ok(Value::Null)
+        }
 
         // =====================================================================
         // File Operations (additional)
@@ -1518,6 +1875,25 @@ pub async fn dispatch_command(
             crate::chat::update_session_digest(app.clone(), session_id, digest).await?;
             Ok(Value::Null)
         }
+        "get_digest_history" => {
+            let session_id: String = field(&args, "sessionId", "session_id")?;
+            let result = crate::chat::get_digest_history(app.clone(), session_id).await?;
+            to_value(result)
+        }
+        "get_project_digest_rollup" => {
+            let project_id: String = field(&args, "projectId", "project_id")?;
+            let result = crate::chat::get_project_digest_rollup(app.clone(), project_id).await?;
+            to_value(result)
+        }
+        "get_digest_settings" => {
+            let result = crate::chat::get_digest_settings(app.clone()).await?;
+            to_value(result)
+        }
+        "set_digest_settings" => {
+            let settings: crate::chat::DigestSettings = from_field(&args, "settings")?;
+            crate::chat::set_digest_settings(app.clone(), settings).await?;
+            Ok(Value::Null)
+        }
         "get_session_debug_info" => {
             let worktree_id: String = field(&args, "worktreeId", "worktree_id")?;
             let worktree_path: String = field(&args, "worktreePath", "worktree_path")?;
@@ -1586,6 +1962,23 @@ pub async fn dispatch_command(
             let result = crate::opencode_cli::list_opencode_models(app.clone()).await?;
             to_value(result)
         }
+        "check_gemini_cli_installed" => {
+            let result = crate::gemini_cli::check_gemini_cli_installed(app.clone()).await?;
+            to_value(result)
+        }
+        "check_gemini_cli_auth" => {
+            let result = crate::gemini_cli::check_gemini_cli_auth(app.clone()).await?;
+            to_value(result)
+        }
+        "get_available_gemini_versions" => {
+            let result = crate::gemini_cli::get_available_gemini_versions().await?;
+            to_value(result)
+        }
+        "install_gemini_cli" => {
+            let version: Option<String> = from_field_opt(&args, "version")?;
+            crate::gemini_cli::install_gemini_cli(app.clone(), version).await?;
+            Ok(Value::Null)
+        }
         "check_gh_cli_installed" => {
             let result = crate::gh_cli::check_gh_cli_installed(app.clone()).await?;
             to_value(result)
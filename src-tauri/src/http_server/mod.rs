@@ -3,6 +3,8 @@ pub mod dispatch;
 pub mod server;
 pub mod websocket;
 
+use std::cell::Cell;
+
 use serde::Serialize;
 use serde_json::Value;
 use tauri::{AppHandle, Emitter, Manager};
@@ -41,6 +43,18 @@ impl WsBroadcaster {
     }
 }
 
+thread_local! {
+    /// Set on threads replaying a recorded run
+    static REPLAYING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Mark everything this thread emits from now on as a replay: object payloads
+/// get `"replay": true`, so the frontend renders them without treating them
+/// as a live run (see `chat::replay`).
+pub fn mark_replay_thread() {
+    REPLAYING.with(|replaying| replaying.set(true));
+}
+
 /// Extension trait on AppHandle that sends to both Tauri IPC and WebSocket clients.
 /// Use `app.emit_all("event", &payload)` instead of `app.emit("event", &payload)`.
 pub trait EmitExt {
@@ -49,17 +63,34 @@ pub trait EmitExt {
 
 impl EmitExt for AppHandle {
     fn emit_all<S: Serialize + Clone>(&self, event: &str, payload: &S) -> Result<(), String> {
-        // Send to Tauri frontend (native app)
-        self.emit(event, payload.clone())
-            .map_err(|e| format!("Tauri emit failed: {e}"))?;
-
-        // Broadcast to WebSocket clients (if server is running)
-        if let Some(ws) = self.try_state::<WsBroadcaster>() {
-            let value = serde_json::to_value(payload)
-                .map_err(|e| format!("Failed to serialize for WS broadcast: {e}"))?;
-            ws.broadcast(event, &value);
+        if REPLAYING.with(Cell::get) {
+            let mut value = serde_json::to_value(payload)
+                .map_err(|e| format!("Failed to serialize replayed event: {e}"))?;
+            if let Some(fields) = value.as_object_mut() {
+                fields.insert("replay".to_string(), Value::Bool(true));
+            }
+            return emit_to_all(self, event, &value);
         }
+        emit_to_all(self, event, payload)
+    }
+}
 
-        Ok(())
+/// Send to the Tauri frontend and every WebSocket client
+fn emit_to_all<S: Serialize + Clone>(
+    app: &AppHandle,
+    event: &str,
+    payload: &S,
+) -> Result<(), String> {
+    // Send to Tauri frontend (native app)
+    app.emit(event, payload.clone())
+        .map_err(|e| format!("Tauri emit failed: {e}"))?;
+
+    // Broadcast to WebSocket clients (if server is running)
+    if let Some(ws) = app.try_state::<WsBroadcaster>() {
+        let value = serde_json::to_value(payload)
+            .map_err(|e| format!("Failed to serialize for WS broadcast: {e}"))?;
+        ws.broadcast(event, &value);
     }
+
+    Ok(())
 }
@@ -174,6 +174,12 @@ pub async fn start_server(
             .unwrap_or_else(|e| log::error!("HTTP server error: {e}"));
     });
 
+    // Let a second instance find this server and attach to it
+    crate::instance::record_http_server(
+        Some(local_addr.port()),
+        token_required.then_some(token.as_str()),
+    );
+
     Ok(HttpServerHandle {
         shutdown_tx,
         port: local_addr.port(),
@@ -0,0 +1,291 @@
+//! Single-writer lease on the app data directory
+//!
+//! The GUI and a `--headless` instance can point at the same app data. Only
+//! one of them may write projects and sessions: the first to start takes an
+//! exclusive lock on `app-data/instance.lock` and holds it until it exits (the
+//! OS releases it if the process dies). It describes itself in
+//! `instance.json`, including its HTTP server once one is running.
+//!
+//! A later instance finds the lock taken and becomes read-only:
+//! `ensure_writer` refuses every `chat::storage` / `projects::storage`
+//! mutation. A headless one prints how to reach the running instance and
+//! exits; a GUI one offers to open it in the browser (see `offer_attach`).
+//!
+//! If the lock can't be taken at all (e.g. the filesystem doesn't support file
+//! locks), the instance still becomes the writer, with a warning.
+
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::{Lazy, OnceCell};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri_plugin_opener::OpenerExt;
+
+const LOCK_FILE: &str = "instance.lock";
+const INFO_FILE: &str = "instance.json";
+
+/// The instance holding the lease
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    pub pid: u32,
+    pub headless: bool,
+    pub started_at: u64,
+    /// Port of its HTTP server, while one is running
+    #[serde(default)]
+    pub http_port: Option<u16>,
+    #[serde(default)]
+    pub http_token: Option<String>,
+}
+
+impl InstanceInfo {
+    /// Local URL (with token) for attaching to this instance's HTTP server
+    pub fn attach_url(&self) -> Option<String> {
+        let port = self.http_port?;
+        Some(match &self.http_token {
+            Some(token) => format!("http://localhost:{port}/?token={token}"),
+            None => format!("http://localhost:{port}/"),
+        })
+    }
+}
+
+fn describe(owner: Option<&InstanceInfo>) -> String {
+    match owner {
+        Some(info) if info.headless => format!("a headless instance (pid {})", info.pid),
+        Some(info) => format!("a desktop instance (pid {})", info.pid),
+        None => "another instance".to_string(),
+    }
+}
+
+/// Outcome of trying to take the lease
+enum Lease {
+    /// This process is the writer; the file must stay open to keep the lock
+    Acquired(File),
+    /// Another process holds it (its info, if readable)
+    Held(Option<InstanceInfo>),
+}
+
+/// Lock file held for the life of the process
+static LEASE: OnceCell<File> = OnceCell::new();
+
+/// Whether this process holds the lease
+static IS_WRITER: AtomicBool = AtomicBool::new(false);
+
+/// Where this instance's info is written, and its current contents
+static OWN_INFO: Lazy<Mutex<Option<(PathBuf, InstanceInfo)>>> = Lazy::new(|| Mutex::new(None));
+
+fn try_lease(app_data_dir: &Path) -> Result<Lease, String> {
+    fs::create_dir_all(app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(app_data_dir.join(LOCK_FILE))
+        .map_err(|e| format!("Failed to open instance lock: {e}"))?;
+
+    match file.try_lock() {
+        Ok(()) => Ok(Lease::Acquired(file)),
+        Err(fs::TryLockError::WouldBlock) => Ok(Lease::Held(read_info(app_data_dir))),
+        Err(fs::TryLockError::Error(e)) => Err(format!("Failed to lock instance file: {e}")),
+    }
+}
+
+fn read_info(app_data_dir: &Path) -> Option<InstanceInfo> {
+    let contents = fs::read_to_string(app_data_dir.join(INFO_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_info(path: &Path, info: &InstanceInfo) {
+    let result = serde_json::to_string_pretty(info)
+        .map_err(|e| e.to_string())
+        .and_then(|json| {
+            let temp_path = path.with_extension("tmp");
+            fs::write(&temp_path, json)
+                .and_then(|_| fs::rename(&temp_path, path))
+                .map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        log::warn!("Failed to write instance info: {e}");
+    }
+}
+
+/// What this process may do with the app data
+pub enum Role {
+    Writer,
+    /// Another instance holds the lease (its info, if readable)
+    ReadOnly(Option<InstanceInfo>),
+}
+
+/// Take the lease on the app data directory if it's free
+pub fn acquire(app: &AppHandle, headless: bool) -> Result<Role, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+
+    let lease = match try_lease(&app_data_dir) {
+        Ok(lease) => lease,
+        Err(e) => {
+            // E.g. a filesystem without file locks. Writing unguarded beats an
+            // instance that can't save anything.
+            log::warn!("Failed to take the app data lease, writing without it: {e}");
+            become_writer(&app_data_dir, headless);
+            return Ok(Role::Writer);
+        }
+    };
+
+    match lease {
+        Lease::Acquired(file) => {
+            let _ = LEASE.set(file);
+            become_writer(&app_data_dir, headless);
+            log::trace!("Acquired app data lease");
+            Ok(Role::Writer)
+        }
+        Lease::Held(owner) => {
+            log::warn!(
+                "App data is in use by {}; this instance is read-only",
+                describe(owner.as_ref())
+            );
+            Ok(Role::ReadOnly(owner))
+        }
+    }
+}
+
+/// Describe this process in `instance.json` and allow writes
+fn become_writer(app_data_dir: &Path, headless: bool) {
+    let info = InstanceInfo {
+        pid: std::process::id(),
+        headless,
+        started_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        http_port: None,
+        http_token: None,
+    };
+    let info_path = app_data_dir.join(INFO_FILE);
+    write_info(&info_path, &info);
+    *OWN_INFO.lock().unwrap() = Some((info_path, info));
+    IS_WRITER.store(true, Ordering::SeqCst);
+}
+
+/// Fail unless this process is the writer. Called before every storage
+/// mutation.
+pub fn ensure_writer() -> Result<(), String> {
+    if IS_WRITER.load(Ordering::SeqCst) {
+        Ok(())
+    } else {
+        Err("Another Jean instance is using this data directory; attach to it instead of writing from here".to_string())
+    }
+}
+
+/// Record (or with None, clear) this instance's HTTP server in `instance.json`
+pub fn record_http_server(port: Option<u16>, token: Option<&str>) {
+    let mut own = OWN_INFO.lock().unwrap();
+    if let Some((path, info)) = own.as_mut() {
+        info.http_port = port;
+        info.http_token = token.map(str::to_string);
+        write_info(path, info);
+    }
+}
+
+/// Message for a headless instance that can't start because `owner` holds the
+/// lease
+pub fn headless_conflict_message(owner: Option<&InstanceInfo>) -> String {
+    let intro = format!(
+        "Jean is already running as {} with this data directory.",
+        describe(owner)
+    );
+    match owner.and_then(InstanceInfo::attach_url) {
+        Some(url) => format!("{intro}\nAttach to it at: {url}"),
+        None => format!("{intro}\nEnable its HTTP server to attach to it, or quit it first."),
+    }
+}
+
+/// Tell the user another instance owns the app data, offer to open it in the
+/// browser, then quit
+pub fn offer_attach(app: AppHandle, owner: Option<InstanceInfo>) {
+    std::thread::spawn(move || {
+        let intro = format!(
+            "Jean is already running as {} with the same data. Running both at once could corrupt your projects and sessions.",
+            describe(owner.as_ref())
+        );
+
+        match owner.as_ref().and_then(InstanceInfo::attach_url) {
+            Some(url) => {
+                let open = app
+                    .dialog()
+                    .message(format!(
+                        "{intro}\n\nOpen the running instance in your browser?"
+                    ))
+                    .title("Jean is already running")
+                    .kind(MessageDialogKind::Warning)
+                    .buttons(MessageDialogButtons::OkCancelCustom(
+                        "Open in Browser".to_string(),
+                        "Quit".to_string(),
+                    ))
+                    .blocking_show();
+                if open {
+                    if let Err(e) = app.opener().open_url(&url, None::<&str>) {
+                        log::error!("Failed to open {url}: {e}");
+                    }
+                }
+            }
+            None => {
+                app.dialog()
+                    .message(format!(
+                        "{intro}\n\nSwitch to it, or enable its HTTP server to attach from a browser."
+                    ))
+                    .title("Jean is already running")
+                    .kind(MessageDialogKind::Warning)
+                    .blocking_show();
+            }
+        }
+
+        app.exit(0);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lease_sees_the_holder() {
+        let dir = tempfile::tempdir().unwrap();
+        let Lease::Acquired(_file) = try_lease(dir.path()).unwrap() else {
+            panic!("first lease should be acquired");
+        };
+        let info = InstanceInfo {
+            pid: 42,
+            headless: true,
+            started_at: 1,
+            http_port: Some(3456),
+            http_token: Some("secret".to_string()),
+        };
+        write_info(&dir.path().join(INFO_FILE), &info);
+
+        let Lease::Held(owner) = try_lease(dir.path()).unwrap() else {
+            panic!("second lease should be refused");
+        };
+        let owner = owner.unwrap();
+        assert_eq!(owner, info);
+        assert_eq!(
+            owner.attach_url().as_deref(),
+            Some("http://localhost:3456/?token=secret")
+        );
+    }
+
+    #[test]
+    fn test_lease_is_released_with_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = try_lease(dir.path()).unwrap();
+        drop(first);
+        assert!(matches!(try_lease(dir.path()).unwrap(), Lease::Acquired(_)));
+    }
+}
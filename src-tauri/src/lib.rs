@@ -12,12 +12,17 @@ mod background_tasks;
 mod chat;
 mod claude_cli;
 mod codex_cli;
+mod db;
+mod gemini_cli;
 mod gh_cli;
+mod instance;
+mod migrations;
 pub mod http_server;
 mod opencode_cli;
 mod opencode_server;
 mod platform;
 mod projects;
+mod scheduler;
 mod terminal;
 
 // Validation functions
@@ -179,7 +184,7 @@ pub struct AppPreferences {
     #[serde(default = "default_confirm_session_close")]
     pub confirm_session_close: bool, // Show confirmation dialog before closing sessions/worktrees
     #[serde(default = "default_backend")]
-    pub default_backend: String, // Default CLI backend: "claude", "codex", or "opencode"
+    pub default_backend: String, // Default CLI backend: "claude", "codex", "opencode", or "gemini"
     #[serde(default = "default_codex_model")]
     pub selected_codex_model: String, // Default Codex model
     #[serde(default = "default_opencode_model")]
@@ -200,6 +205,10 @@ pub struct AppPreferences {
     pub yolo_model: Option<String>, // Model override for yolo plan approval, None = use session model
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub linear_api_key: Option<String>, // Global Linear personal API key (inherited by all projects)
+    #[serde(default)]
+    pub run_idle_timeout_minutes: u32, // Kill a run after this long without new CLI output (0 = disabled)
+    #[serde(default)]
+    pub run_max_duration_minutes: u32, // Kill a run after this long in total (0 = no limit)
 }
 
 fn default_true() -> Option<bool> {
@@ -821,6 +830,12 @@ pub fn is_codex_model(model: &str) -> bool {
     !is_opencode_model(model) && (model.contains("codex") || model.starts_with("gpt-"))
 }
 
+/// Returns true if the given model string identifies a Gemini CLI model.
+/// Gemini model IDs start with "gemini-" (e.g. "gemini-2.5-pro").
+pub fn is_gemini_model(model: &str) -> bool {
+    model.starts_with("gemini-")
+}
+
 /// Per-prompt provider overrides for magic prompts (None = use global default_provider)
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MagicPromptProviders {
@@ -965,6 +980,8 @@ impl Default for AppPreferences {
             build_model: None,
             yolo_model: None,
             linear_api_key: None,
+            run_idle_timeout_minutes: 0,
+            run_max_duration_minutes: 0,
         }
     }
 }
@@ -1075,7 +1092,7 @@ impl Default for UIState {
             project_access_timestamps: std::collections::HashMap::new(),
             dashboard_worktree_collapse_overrides: std::collections::HashMap::new(),
             last_opened_per_project: std::collections::HashMap::new(),
-            version: default_ui_state_version(),
+            version: migrations::UI_STATE_VERSION,
         }
     }
 }
@@ -1625,6 +1642,7 @@ async fn stop_http_server(app: AppHandle) -> Result<(), String> {
         let mut guard = state.lock().await;
         if let Some(handle) = guard.take() {
             let _ = handle.shutdown_tx.send(());
+            instance::record_http_server(None, None);
             log::info!("HTTP server stopped");
         }
     }
@@ -1933,6 +1951,33 @@ pub fn run() {
                 app.package_info().name
             );
 
+            // Only one instance may write app data; a later one attaches to it instead
+            match instance::acquire(app.handle(), headless) {
+                Ok(instance::Role::Writer) => {
+                    // Upgrade on-disk storage formats before anything reads them
+                    migrations::run_startup_migrations(app.handle());
+
+                    // Open the database, importing the JSON storage layout on first run
+                    db::init(app.handle());
+                }
+                Ok(instance::Role::ReadOnly(owner)) => {
+                    if headless {
+                        eprintln!(
+                            "Error: {}",
+                            instance::headless_conflict_message(owner.as_ref())
+                        );
+                        std::process::exit(1);
+                    }
+                    instance::offer_attach(app.handle().clone(), owner);
+                }
+                Err(e) => {
+                    // Only fails without an app data directory, where nothing could be saved
+                    log::error!("Failed to acquire app data lease: {e}");
+                    eprintln!("Error: {e}");
+                    std::process::exit(1);
+                }
+            }
+
             // In headless mode, close the window immediately
             if headless {
                 log::info!("Running in headless mode");
@@ -2040,6 +2085,15 @@ pub fn run() {
             app.manage(task_manager);
             log::trace!("Background task manager initialized");
 
+            // Initialize scheduler for scheduled/recurring prompts
+            let schedule_manager = scheduler::SchedulerManager::new(app.handle().clone());
+            schedule_manager.start();
+            app.manage(schedule_manager);
+            log::trace!("Scheduler initialized");
+
+            // Compress old run logs and enforce storage quotas in the background
+            chat::start_storage_maintenance(app.handle().clone());
+
             // Initialize HTTP server infrastructure
             let (broadcaster, _) = http_server::WsBroadcaster::new();
             app.manage(broadcaster);
@@ -2263,6 +2317,7 @@ pub fn run() {
             chat::list_all_sessions,
             chat::get_session,
             chat::create_session,
+            chat::fork_session,
             chat::rename_session,
             chat::regenerate_session_name,
             chat::update_session_state,
@@ -2290,6 +2345,51 @@ pub fn run() {
             chat::save_cancelled_message,
             chat::mark_plan_approved,
             chat::approve_codex_command,
+            // Chat commands - Message queue
+            chat::get_queued_messages,
+            chat::enqueue_message,
+            chat::update_queued_message,
+            chat::reorder_queued_messages,
+            chat::remove_queued_message,
+            chat::clear_message_queue,
+            chat::resume_message_queue,
+            // Chat commands - Search
+            chat::search_sessions,
+            chat::rebuild_search_index,
+            // Chat commands - Export/import
+            chat::export_session_bundle,
+            chat::import_session_bundle,
+            chat::export_session_transcript,
+            // Chat commands - Cost accounting
+            chat::get_pricing_table,
+            chat::set_pricing_overrides,
+            chat::get_cost_summary,
+            // Chat commands - Usage analytics
+            chat::get_usage_analytics,
+            // Chat commands - Budgets
+            chat::get_budget_settings,
+            chat::set_budget_settings,
+            chat::get_budget_status,
+            // Chat commands - Backends
+            chat::get_backend_capabilities,
+            // Chat commands - Replay
+            chat::replay_run,
+            // Chat commands - Checkpoints
+            chat::restore_checkpoint,
+            chat::get_run_diff,
+            // Chat commands - Permission policy
+            chat::policy::get_permission_audit,
+            // Chat commands - Compaction
+            chat::compact_session,
+            // Storage migrations
+            migrations::commands::check_storage_migrations,
+            // Session queries
+            db::commands::query_sessions,
+            // Chat commands - Storage retention
+            chat::get_storage_settings,
+            chat::set_storage_settings,
+            chat::get_disk_usage,
+            chat::run_storage_maintenance,
             // Chat commands - Image handling
             chat::read_clipboard_image,
             chat::save_pasted_image,
@@ -2300,6 +2400,8 @@ pub fn run() {
             chat::update_pasted_text,
             chat::delete_pasted_text,
             chat::read_pasted_text,
+            // Chat commands - Document attachments
+            chat::save_dropped_document,
             // Chat commands - Plan file handling
             chat::read_plan_file,
             // Chat commands - File content preview/edit
@@ -2316,6 +2418,10 @@ pub fn run() {
             // Chat commands - Session digest (context recall)
             chat::generate_session_digest,
             chat::update_session_digest,
+            chat::get_digest_history,
+            chat::get_project_digest_rollup,
+            chat::get_digest_settings,
+            chat::set_digest_settings,
             // Chat commands - Real-time setting sync
             chat::broadcast_session_setting,
             // Chat commands - Debug info
@@ -2339,6 +2445,11 @@ pub fn run() {
             opencode_cli::get_available_opencode_versions,
             opencode_cli::install_opencode_cli,
             opencode_cli::list_opencode_models,
+            // Gemini CLI management commands
+            gemini_cli::check_gemini_cli_installed,
+            gemini_cli::check_gemini_cli_auth,
+            gemini_cli::get_available_gemini_versions,
+            gemini_cli::install_gemini_cli,
             // GitHub CLI management commands
             gh_cli::check_gh_cli_installed,
             gh_cli::check_gh_cli_auth,
@@ -2355,6 +2466,14 @@ pub fn run() {
             background_tasks::commands::set_remote_poll_interval,
             background_tasks::commands::get_remote_poll_interval,
             background_tasks::commands::trigger_immediate_remote_poll,
+            // Scheduler commands
+            scheduler::commands::list_schedules,
+            scheduler::commands::create_schedule,
+            scheduler::commands::update_schedule,
+            scheduler::commands::set_schedule_enabled,
+            scheduler::commands::delete_schedule,
+            scheduler::commands::run_schedule_now,
+            scheduler::commands::preview_schedule_trigger,
             // HTTP server commands
             start_http_server,
             stop_http_server,
@@ -0,0 +1,13 @@
+//! Tauri commands for storage migrations
+
+use tauri::AppHandle;
+
+use super::{run_migrations, MigrationReport};
+
+/// Report which storage files and stored documents would be migrated, without
+/// changing anything
+#[tauri::command]
+pub async fn check_storage_migrations(app: AppHandle) -> Result<MigrationReport, String> {
+    log::trace!("Checking storage migrations (dry run)");
+    run_migrations(&app, true)
+}
@@ -0,0 +1,361 @@
+//! Versioned migrations for on-disk storage
+//!
+//! `projects.json`, `ui-state.json`, worktree indexes (`sessions/index/*.json`)
+//! and session metadata (`sessions/data/*/metadata.json`) each carry a
+//! `version`. At startup, before anything reads them, every file below its
+//! format's current version is run through that format's ordered steps (see
+//! `steps`) and rewritten. The original is first copied to
+//! `app-data/backups/migrations/{timestamp}/`, keeping its path relative to the
+//! app data directory.
+//!
+//! `check_storage_migrations` runs the same pass as a dry run and reports which
+//! files would change. Files written by a newer app version are left alone.
+//!
+//! Once the JSON files are imported into the database (see `crate::db`), only
+//! `ui-state.json` remains on disk. Documents stored in the database go
+//! through the same steps when it opens (see `db::migrate`), and the dry run
+//! reports those rows too.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+pub mod commands;
+mod steps;
+
+pub use steps::{
+    PROJECTS_VERSION, SESSION_METADATA_VERSION, UI_STATE_VERSION, WORKTREE_INDEX_VERSION,
+};
+
+/// A versioned on-disk JSON format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageFormat {
+    Projects,
+    UiState,
+    WorktreeIndex,
+    SessionMetadata,
+}
+
+impl StorageFormat {
+    #[cfg(test)]
+    pub const ALL: [StorageFormat; 4] = [
+        StorageFormat::Projects,
+        StorageFormat::UiState,
+        StorageFormat::WorktreeIndex,
+        StorageFormat::SessionMetadata,
+    ];
+
+    pub fn current_version(self) -> u32 {
+        match self {
+            StorageFormat::Projects => PROJECTS_VERSION,
+            StorageFormat::UiState => UI_STATE_VERSION,
+            StorageFormat::WorktreeIndex => WORKTREE_INDEX_VERSION,
+            StorageFormat::SessionMetadata => SESSION_METADATA_VERSION,
+        }
+    }
+}
+
+/// One upgrade step, from `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    /// Shown in migration reports
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> Result<(), String>,
+}
+
+/// Version a document was written with
+pub(crate) fn stored_version(format: StorageFormat, value: &Value) -> u32 {
+    value
+        .get("version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or_else(|| steps::initial_version(format))
+}
+
+/// Upgrade a document to its format's current version. Returns the
+/// descriptions of the steps applied (empty if it was already current).
+pub fn migrate_value(
+    format: StorageFormat,
+    value: &mut Value,
+) -> Result<Vec<&'static str>, String> {
+    let current = format.current_version();
+    let mut version = stored_version(format, value);
+    if version > current {
+        return Err(format!(
+            "Written by a newer version of the app (format version {version}, this app supports {current})"
+        ));
+    }
+
+    let mut applied = Vec::new();
+    while version < current {
+        let step = steps::migrations(format)
+            .iter()
+            .find(|m| m.from == version)
+            .ok_or_else(|| format!("No migration from {format:?} version {version}"))?;
+        (step.apply)(value).map_err(|e| format!("Migration from version {version} failed: {e}"))?;
+        applied.push(step.description);
+        version += 1;
+    }
+
+    if !applied.is_empty() {
+        value["version"] = Value::from(current);
+    }
+    Ok(applied)
+}
+
+// ============================================================================
+// Files
+// ============================================================================
+
+/// A file that was upgraded (or would be, in a dry run)
+#[derive(Debug, Clone, Serialize)]
+pub struct FileMigration {
+    /// Relative to the app data directory; `jean.db/{table}/{key}` for a
+    /// document stored in the database
+    pub path: String,
+    pub format: StorageFormat,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub steps: Vec<String>,
+}
+
+/// A file that could not be upgraded (it is left untouched)
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationFailure {
+    pub path: String,
+    pub format: StorageFormat,
+    pub error: String,
+}
+
+/// Result of a migration pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MigrationReport {
+    pub dry_run: bool,
+    pub migrated: Vec<FileMigration>,
+    pub failed: Vec<MigrationFailure>,
+    /// Where originals were backed up (None if nothing was migrated)
+    pub backup_dir: Option<String>,
+}
+
+/// All versioned files under the app data directory
+fn storage_files(app_data_dir: &Path) -> Vec<(PathBuf, StorageFormat)> {
+    let mut files = vec![
+        (app_data_dir.join("projects.json"), StorageFormat::Projects),
+        (app_data_dir.join("ui-state.json"), StorageFormat::UiState),
+    ];
+
+    let sessions_dir = app_data_dir.join("sessions");
+    if let Ok(entries) = fs::read_dir(sessions_dir.join("index")) {
+        files.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .map(|path| (path, StorageFormat::WorktreeIndex)),
+        );
+    }
+    if let Ok(entries) = fs::read_dir(sessions_dir.join("data")) {
+        files.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path().join("metadata.json"))
+                .map(|path| (path, StorageFormat::SessionMetadata)),
+        );
+    }
+
+    files.retain(|(path, _)| path.is_file());
+    files
+}
+
+/// Migrate one file. Returns None if it was already current.
+fn migrate_file(
+    path: &Path,
+    format: StorageFormat,
+    app_data_dir: &Path,
+    backup_dir: &Path,
+    dry_run: bool,
+) -> Result<Option<FileMigration>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {e}"))?;
+    let mut value: Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Failed to parse JSON: {e}"))?;
+
+    let from_version = stored_version(format, &value);
+    let applied = migrate_value(format, &mut value)?;
+    if applied.is_empty() {
+        return Ok(None);
+    }
+
+    let relative = path.strip_prefix(app_data_dir).unwrap_or(path);
+    if !dry_run {
+        let backup_path = backup_dir.join(relative);
+        if let Some(parent) = backup_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create backup directory: {e}"))?;
+        }
+        fs::copy(path, &backup_path).map_err(|e| format!("Failed to back up file: {e}"))?;
+
+        let json_content = serde_json::to_string_pretty(&value)
+            .map_err(|e| format!("Failed to serialize migrated file: {e}"))?;
+        let temp_path = path.with_extension("migrating");
+        fs::write(&temp_path, json_content)
+            .map_err(|e| format!("Failed to write migrated file: {e}"))?;
+        fs::rename(&temp_path, path)
+            .map_err(|e| format!("Failed to finalize migrated file: {e}"))?;
+    }
+
+    Ok(Some(FileMigration {
+        path: relative.to_string_lossy().to_string(),
+        format,
+        from_version,
+        to_version: format.current_version(),
+        steps: applied.iter().map(|s| s.to_string()).collect(),
+    }))
+}
+
+/// Where a migration pass started at `timestamp` backs up originals
+pub(crate) fn backup_dir(app_data_dir: &Path, timestamp: u64) -> PathBuf {
+    app_data_dir
+        .join("backups")
+        .join("migrations")
+        .join(timestamp.to_string())
+}
+
+/// Migrate (or with `dry_run`, check) every versioned file under `app_data_dir`
+fn run_migrations_in(app_data_dir: &Path, dry_run: bool, timestamp: u64) -> MigrationReport {
+    let backup_dir = backup_dir(app_data_dir, timestamp);
+    let mut report = MigrationReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    for (path, format) in storage_files(app_data_dir) {
+        match migrate_file(&path, format, app_data_dir, &backup_dir, dry_run) {
+            Ok(Some(migration)) => report.migrated.push(migration),
+            Ok(None) => {}
+            Err(error) => report.failed.push(MigrationFailure {
+                path: path.to_string_lossy().to_string(),
+                format,
+                error,
+            }),
+        }
+    }
+
+    if !dry_run && !report.migrated.is_empty() {
+        report.backup_dir = Some(backup_dir.to_string_lossy().to_string());
+    }
+    report
+}
+
+pub(crate) fn run_migrations(app: &AppHandle, dry_run: bool) -> Result<MigrationReport, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let mut report = run_migrations_in(&app_data_dir, dry_run, timestamp);
+
+    // Stored documents are upgraded when the database opens; a dry run
+    // reports them alongside the files
+    if dry_run {
+        let rows = crate::db::check_stored_documents(app)?;
+        report.migrated.extend(rows.migrated);
+        report.failed.extend(rows.failed);
+    }
+    Ok(report)
+}
+
+/// Log what a migration pass did
+pub(crate) fn log_report(report: &MigrationReport) {
+    for migration in &report.migrated {
+        log::info!(
+            "Migrated {} from version {} to {}",
+            migration.path,
+            migration.from_version,
+            migration.to_version
+        );
+    }
+    for failure in &report.failed {
+        log::warn!("Failed to migrate {}: {}", failure.path, failure.error);
+    }
+    if let Some(backup_dir) = &report.backup_dir {
+        log::info!("Originals of migrated files backed up to {backup_dir}");
+    }
+}
+
+/// Upgrade storage at startup, before any other subsystem reads it
+pub fn run_startup_migrations(app: &AppHandle) {
+    match run_migrations(app, false) {
+        Ok(report) => log_report(&report),
+        Err(e) => log::error!("Storage migrations failed: {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_json(path: &Path, value: Value) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, serde_json::to_string(&value).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_reports_without_writing() {
+        let dir = tempfile::tempdir().unwrap();
+        let metadata_path = dir.path().join("sessions/data/s1/metadata.json");
+        write_json(
+            &metadata_path,
+            serde_json::json!({ "session_id": "s1", "version": 1 }),
+        );
+        let original = fs::read_to_string(&metadata_path).unwrap();
+
+        let report = run_migrations_in(dir.path(), true, 42);
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.migrated[0].format, StorageFormat::SessionMetadata);
+        assert_eq!(report.migrated[0].from_version, 1);
+        assert!(report.backup_dir.is_none());
+        assert_eq!(fs::read_to_string(&metadata_path).unwrap(), original);
+    }
+
+    #[test]
+    fn test_migration_backs_up_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let projects_path = dir.path().join("projects.json");
+        write_json(
+            &projects_path,
+            serde_json::json!({ "projects": [], "worktrees": [] }),
+        );
+        write_json(
+            &dir.path().join("sessions/index/wt.json"),
+            serde_json::json!({ "worktree_id": "wt", "sessions": [], "version": 1 }),
+        );
+        write_json(
+            &dir.path().join("ui-state.json"),
+            serde_json::json!({ "version": 99 }),
+        );
+
+        let report = run_migrations_in(dir.path(), false, 42);
+        assert_eq!(report.migrated.len(), 1);
+        assert_eq!(report.migrated[0].path, "projects.json");
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].format, StorageFormat::UiState);
+
+        let migrated: Value =
+            serde_json::from_str(&fs::read_to_string(&projects_path).unwrap()).unwrap();
+        assert_eq!(migrated["version"], PROJECTS_VERSION);
+        let backup = dir.path().join("backups/migrations/42/projects.json");
+        let backed_up: Value = serde_json::from_str(&fs::read_to_string(backup).unwrap()).unwrap();
+        assert!(backed_up.get("version").is_none());
+
+        // A second pass has nothing left to do
+        assert!(run_migrations_in(dir.path(), false, 43).migrated.is_empty());
+    }
+}
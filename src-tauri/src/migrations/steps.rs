@@ -0,0 +1,148 @@
+//! Migration steps for each storage format
+//!
+//! Each format lists its steps in order; a step upgrades a document from
+//! `from` to `from + 1`. When changing a format: bump its version constant,
+//! append a step here, and add a test with a document in the old shape.
+
+use serde_json::{json, Map, Value};
+
+use super::{Migration, StorageFormat};
+use crate::chat::types::DEFAULT_LABEL_COLOR;
+
+/// Current version of `projects.json`
+pub const PROJECTS_VERSION: u32 = 1;
+
+/// Current version of `ui-state.json`
+pub const UI_STATE_VERSION: u32 = 1;
+
+/// Current version of `sessions/index/{worktree_id}.json`
+pub const WORKTREE_INDEX_VERSION: u32 = 1;
+
+/// Current version of `sessions/data/{session_id}/metadata.json`
+pub const SESSION_METADATA_VERSION: u32 = 2;
+
+static PROJECTS_MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "Add a format version to projects.json",
+    apply: stamp_version,
+}];
+
+static SESSION_METADATA_MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "Rename legacy session_id/session_name keys and convert plain-string labels",
+    apply: session_metadata_v2,
+}];
+
+/// Steps for a format, in order
+pub(super) fn migrations(format: StorageFormat) -> &'static [Migration] {
+    match format {
+        StorageFormat::Projects => PROJECTS_MIGRATIONS,
+        StorageFormat::SessionMetadata => SESSION_METADATA_MIGRATIONS,
+        StorageFormat::UiState | StorageFormat::WorktreeIndex => &[],
+    }
+}
+
+/// Version of documents written before the format had a `version` field
+pub(super) fn initial_version(format: StorageFormat) -> u32 {
+    match format {
+        StorageFormat::Projects => 0,
+        StorageFormat::UiState | StorageFormat::WorktreeIndex | StorageFormat::SessionMetadata => 1,
+    }
+}
+
+fn as_object(value: &mut Value) -> Result<&mut Map<String, Value>, String> {
+    value
+        .as_object_mut()
+        .ok_or_else(|| "Document is not a JSON object".to_string())
+}
+
+/// Move `from` to `to`. If both exist, `to` wins (serde rejects the duplicate
+/// when `from` is an alias).
+fn rename_key(object: &mut Map<String, Value>, from: &str, to: &str) {
+    if let Some(value) = object.remove(from) {
+        object.entry(to).or_insert(value);
+    }
+}
+
+/// 0 → 1 (projects.json): nothing changes but the version itself
+fn stamp_version(value: &mut Value) -> Result<(), String> {
+    as_object(value).map(|_| ())
+}
+
+/// 1 → 2 (session metadata): early sessions were written with `session_id` and
+/// `session_name`, and labels used to be plain strings
+fn session_metadata_v2(value: &mut Value) -> Result<(), String> {
+    let object = as_object(value)?;
+    rename_key(object, "session_id", "id");
+    rename_key(object, "session_name", "name");
+
+    match object.get("label") {
+        Some(Value::String(name)) => {
+            let label = json!({ "name": name, "color": DEFAULT_LABEL_COLOR });
+            object.insert("label".to_string(), label);
+        }
+        Some(Value::Object(_)) | Some(Value::Null) | None => {}
+        Some(_) => {
+            object.remove("label");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chat::types::SessionMetadata;
+    use crate::migrations::migrate_value;
+
+    #[test]
+    fn test_every_format_reaches_its_current_version() {
+        for format in StorageFormat::ALL {
+            let mut version = initial_version(format);
+            for step in migrations(format) {
+                assert_eq!(step.from, version, "{format:?} steps must be contiguous");
+                version += 1;
+            }
+            assert_eq!(version, format.current_version(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn test_session_metadata_v1_legacy_keys_and_label() {
+        let mut value = json!({
+            "session_id": "sess-1",
+            "session_name": "Old",
+            "worktree_id": "wt-1",
+            "order": 0,
+            "created_at": 1700000000,
+            "label": "Needs testing",
+            "version": 1
+        });
+
+        let applied = migrate_value(StorageFormat::SessionMetadata, &mut value).unwrap();
+        assert_eq!(applied.len(), 1);
+        assert_eq!(value["version"], 2);
+        assert_eq!(value["id"], "sess-1");
+        assert!(value.get("session_id").is_none());
+        assert_eq!(value["label"]["name"], "Needs testing");
+        assert_eq!(value["label"]["color"], DEFAULT_LABEL_COLOR);
+
+        let metadata: SessionMetadata = serde_json::from_value(value).unwrap();
+        assert_eq!(metadata.name, "Old");
+    }
+
+    #[test]
+    fn test_session_metadata_v1_with_both_id_keys() {
+        let mut value = json!({ "id": "new", "session_id": "old", "label": 3 });
+        session_metadata_v2(&mut value).unwrap();
+        assert_eq!(value, json!({ "id": "new" }));
+    }
+
+    #[test]
+    fn test_projects_without_version() {
+        let mut value = json!({ "projects": [], "worktrees": [] });
+        let applied = migrate_value(StorageFormat::Projects, &mut value).unwrap();
+        assert_eq!(applied, ["Add a format version to projects.json"]);
+        assert_eq!(value["version"], PROJECTS_VERSION);
+    }
+}
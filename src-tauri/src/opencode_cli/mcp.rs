@@ -80,7 +80,7 @@ fn collect_from_opencode_json(
 
 /// Minimal JSONC comment stripper — removes `//` line comments and `/* */` block comments.
 /// Does not handle comments inside strings (good enough for config files).
-fn strip_jsonc_comments(input: &str) -> String {
+pub(crate) fn strip_jsonc_comments(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     let mut chars = input.chars().peekable();
 
@@ -33,6 +33,8 @@ use super::types::{
     WorktreeCreatingEvent, WorktreeDeleteErrorEvent, WorktreeDeletedEvent, WorktreeDeletingEvent,
     WorktreePathExistsEvent, WorktreePermanentlyDeletedEvent, WorktreeUnarchivedEvent,
 };
+use crate::chat::claude::extract_structured_output;
+use crate::chat::types::Backend;
 use crate::claude_cli::resolve_cli_binary;
 use crate::codex_cli::resolve_cli_binary as resolve_codex_cli_binary;
 use crate::gh_cli::config::resolve_gh_binary;
@@ -466,29 +468,21 @@ pub async fn remove_project(app: AppHandle, project_id: String) -> Result<(), St
 
     save_projects_data(&app, &data)?;
 
-    // Clean up sessions files for archived worktrees (in background, non-blocking)
+    // Clean up session indexes for archived worktrees
     for worktree_id in archived_worktree_ids {
-        if let Ok(sessions_file) = crate::chat::storage::get_sessions_path(&app, &worktree_id) {
-            if sessions_file.exists() {
-                if let Err(e) = std::fs::remove_file(&sessions_file) {
-                    log::warn!("Failed to delete sessions file for {worktree_id}: {e}");
-                } else {
-                    log::trace!("Deleted sessions file for archived worktree: {worktree_id}");
-                }
-            }
+        match crate::chat::storage::delete_index(&app, &worktree_id) {
+            Ok(true) => log::trace!("Deleted session index for archived worktree: {worktree_id}"),
+            Ok(false) => {}
+            Err(e) => log::warn!("Failed to delete session index for {worktree_id}: {e}"),
         }
     }
 
-    // Also clean up preserved base sessions file for this project
-    if let Ok(base_sessions_file) =
-        crate::chat::storage::get_closed_base_sessions_path(&app, &project_id)
-    {
-        if base_sessions_file.exists() {
-            if let Err(e) = std::fs::remove_file(&base_sessions_file) {
-                log::warn!("Failed to delete base sessions file for project {project_id}: {e}");
-            } else {
-                log::trace!("Deleted base sessions file for project: {project_id}");
-            }
+    // Also clean up preserved base sessions for this project
+    match crate::chat::storage::delete_preserved_base_sessions(&app, &project_id) {
+        Ok(true) => log::trace!("Deleted preserved base sessions for project: {project_id}"),
+        Ok(false) => {}
+        Err(e) => {
+            log::warn!("Failed to delete preserved base sessions for project {project_id}: {e}")
         }
     }
 
@@ -2589,25 +2583,21 @@ async fn close_base_session_internal(
     }
 
     if preserve_sessions || archive_sessions {
-        // Preserve the sessions file before removing the worktree
-        // This renames {worktree_id}.json to base-{project_id}.json
+        // Preserve the session index before removing the worktree
+        // This moves it from the worktree to the project's preserved base index
         log::info!(
             "[BASE_CLOSE] Preserving sessions file for worktree_id={worktree_id}, project_id={}",
             worktree.project_id
         );
         crate::chat::preserve_base_sessions(app, worktree_id, &worktree.project_id)?;
     } else {
-        // Delete the sessions file entirely for a clean close
-        if let Ok(sessions_file) = crate::chat::storage::get_sessions_path(app, worktree_id) {
-            if sessions_file.exists() {
-                if let Err(e) = std::fs::remove_file(&sessions_file) {
-                    log::warn!("Failed to delete sessions file for {worktree_id}: {e}");
-                } else {
-                    log::trace!(
-                        "Deleted sessions file for clean base session close: {worktree_id}"
-                    );
-                }
+        // Delete the session index entirely for a clean close
+        match crate::chat::storage::delete_index(app, worktree_id) {
+            Ok(true) => {
+                log::trace!("Deleted session index for clean base session close: {worktree_id}")
             }
+            Ok(false) => {}
+            Err(e) => log::warn!("Failed to delete session index for {worktree_id}: {e}"),
         }
     }
 
@@ -4619,41 +4609,6 @@ pub struct CreatePrResponse {
     pub existing: bool,
 }
 
-/// Extract structured output from Claude CLI stream-json response
-/// Handles the StructuredOutput tool call pattern used with --json-schema
-fn extract_structured_output(output: &str) -> Result<String, String> {
-    for line in output.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            continue;
-        }
-
-        let parsed: serde_json::Value = match serde_json::from_str(line) {
-            Ok(v) => v,
-            Err(_) => continue,
-        };
-
-        if parsed.get("type").and_then(|t| t.as_str()) == Some("assistant") {
-            if let Some(message) = parsed.get("message") {
-                if let Some(content) = message.get("content").and_then(|c| c.as_array()) {
-                    for block in content {
-                        if block.get("type").and_then(|t| t.as_str()) == Some("tool_use")
-                            && block.get("name").and_then(|n| n.as_str())
-                                == Some("StructuredOutput")
-                        {
-                            if let Some(input) = block.get("input") {
-                                return Ok(input.to_string());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    Err("No structured output found in Claude response".to_string())
-}
-
 /// Truncate a diff at file boundaries instead of mid-file.
 /// Splits on `\ndiff --git` markers and keeps complete file diffs until the budget is exceeded.
 fn truncate_diff_at_file_boundaries(diff: &str, max_chars: usize) -> String {
@@ -4779,116 +4734,18 @@ fn generate_pr_content(
 
     let model_str = model.unwrap_or("haiku");
 
-    // Route to OpenCode if model is an OpenCode model
-    if crate::is_opencode_model(model_str) {
-        log::trace!("Generating PR content with OpenCode");
-        let json_str = crate::chat::opencode::execute_one_shot_opencode(
-            app,
-            &prompt,
-            model_str,
-            Some(PR_CONTENT_SCHEMA),
-            Some(std::path::Path::new(repo_path)),
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse OpenCode PR content JSON: {e}, content: {json_str}");
-            format!("Failed to parse PR content: {e}")
-        });
-    }
-
-    // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(model_str) {
-        log::trace!("Generating PR content with Codex CLI (output-schema)");
-        let json_str = crate::chat::codex::execute_one_shot_codex(
-            app,
-            &prompt,
-            model_str,
-            PR_CONTENT_SCHEMA,
-            Some(std::path::Path::new(repo_path)),
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse Codex PR content JSON: {e}, content: {json_str}");
-            format!("Failed to parse PR content: {e}")
-        });
-    }
-
-    log::trace!("Generating PR content with Claude CLI (JSON schema)");
-
-    let cli_path = resolve_cli_binary(app);
-    if !cli_path.exists() {
-        return Err("Claude CLI not installed".to_string());
-    }
-
-    let mut cmd = silent_command(&cli_path);
-    crate::chat::claude::apply_custom_profile_settings(&mut cmd, custom_profile_name);
-    cmd.args([
-        "--print",
-        "--verbose",
-        "--input-format",
-        "stream-json",
-        "--output-format",
-        "stream-json",
-        "--model",
+    let backend = crate::chat::one_shot_backend(model_str);
+    log::trace!("Generating PR content with {}", backend.display_name());
+    let json_str = backend.one_shot(
+        app,
+        &prompt,
         model_str,
-        "--no-session-persistence",
-        "--tools",
-        "",
-        "--max-turns",
-        "1",
-        "--json-schema",
         PR_CONTENT_SCHEMA,
-    ]);
-
-    cmd.stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to spawn Claude CLI: {e}"))?;
-
-    // Write prompt to stdin
-    {
-        let input_message = serde_json::json!({
-            "type": "user",
-            "message": {
-                "role": "user",
-                "content": prompt
-            }
-        });
-
-        let write_result = if let Some(stdin) = child.stdin.as_mut() {
-            writeln!(stdin, "{input_message}")
-        } else {
-            Err(std::io::Error::other("Failed to open stdin"))
-        };
-
-        if let Err(e) = write_result {
-            return Err(format!("Failed to write to stdin: {e}"));
-        }
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for Claude CLI: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "Claude CLI failed: stderr={}, stdout={}",
-            stderr.trim(),
-            stdout.trim()
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    log::trace!("Claude CLI PR generation stdout: {stdout}");
-
-    let json_content = extract_structured_output(&stdout)?;
-    log::trace!("Extracted PR content JSON: {json_content}");
-
-    serde_json::from_str(&json_content).map_err(|e| {
-        log::error!("Failed to parse PR content JSON: {e}, content: {json_content}");
+        Some(std::path::Path::new(repo_path)),
+        custom_profile_name,
+    )?;
+    serde_json::from_str(&json_str).map_err(|e| {
+        log::error!("Failed to parse PR content JSON: {e}, content: {json_str}");
         format!("Failed to parse PR content: {e}")
     })
 }
@@ -5516,108 +5373,20 @@ fn generate_commit_message(
 ) -> Result<CommitMessageResponse, String> {
     let model_str = model.unwrap_or("haiku");
 
-    // Route to OpenCode if model is an OpenCode model
-    if crate::is_opencode_model(model_str) {
-        log::trace!("Generating commit message with OpenCode");
-        let json_str = crate::chat::opencode::execute_one_shot_opencode(
-            app,
-            prompt,
-            model_str,
-            Some(COMMIT_MESSAGE_SCHEMA),
-            working_dir,
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse OpenCode commit message JSON: {e}, content: {json_str}");
-            format!("Failed to parse commit message: {e}")
-        });
-    }
-
-    // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(model_str) {
-        log::trace!("Generating commit message with Codex CLI (output-schema)");
-        let json_str = crate::chat::codex::execute_one_shot_codex(
-            app,
-            prompt,
-            model_str,
-            COMMIT_MESSAGE_SCHEMA,
-            working_dir,
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse Codex commit message JSON: {e}, content: {json_str}");
-            format!("Failed to parse commit message: {e}")
-        });
-    }
-
-    log::trace!("Generating commit message with Claude CLI (JSON schema)");
-
-    let cli_path = resolve_cli_binary(app);
-    if !cli_path.exists() {
-        return Err("Claude CLI not installed".to_string());
-    }
-
-    let mut cmd = silent_command(&cli_path);
-    crate::chat::claude::apply_custom_profile_settings(&mut cmd, custom_profile_name);
-    cmd.args([
-        "--print",
-        "--verbose",
-        "--input-format",
-        "stream-json",
-        "--output-format",
-        "stream-json",
-        "--model",
+    let backend = crate::chat::one_shot_backend(model_str);
+    log::trace!("Generating commit message with {}", backend.display_name());
+    let json_str = backend.one_shot(
+        app,
+        prompt,
         model_str,
-        "--no-session-persistence",
-        "--tools",
-        "",
-        "--max-turns",
-        "1",
-        "--json-schema",
         COMMIT_MESSAGE_SCHEMA,
-    ]);
-
-    cmd.stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to spawn Claude CLI: {e}"))?;
-
-    // Write prompt to stdin
-    {
-        let stdin = child.stdin.as_mut().ok_or("Failed to open stdin")?;
-        let input_message = serde_json::json!({
-            "type": "user",
-            "message": {
-                "role": "user",
-                "content": prompt
-            }
-        });
-        writeln!(stdin, "{input_message}").map_err(|e| format!("Failed to write to stdin: {e}"))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for Claude CLI: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "Claude CLI failed: stderr={}, stdout={}",
-            stderr.trim(),
-            stdout.trim()
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    log::trace!("Claude CLI commit generation stdout: {stdout}");
-
-    let json_content = extract_structured_output(&stdout)?;
-    log::trace!("Extracted commit message JSON: {json_content}");
-
-    serde_json::from_str::<CommitMessageResponse>(&json_content)
-        .map_err(|e| format!("Failed to parse commit message response: {e}"))
+        working_dir,
+        custom_profile_name,
+    )?;
+    serde_json::from_str::<CommitMessageResponse>(&json_str).map_err(|e| {
+        log::error!("Failed to parse commit message JSON: {e}, content: {json_str}");
+        format!("Failed to parse commit message response: {e}")
+    })
 }
 
 /// Create a commit with AI-generated message
@@ -5953,24 +5722,27 @@ fn generate_review(
 ) -> Result<ReviewResponse, String> {
     let model_str = model.unwrap_or("haiku");
 
-    // Route to OpenCode if model is an OpenCode model
-    if crate::is_opencode_model(model_str) {
-        log::trace!("Running code review with OpenCode");
-        let json_str = crate::chat::opencode::execute_one_shot_opencode(
+    // Claude and Codex reviews run as cancellable processes; any other backend
+    // goes through its plain one-shot call
+    let backend = crate::chat::one_shot_backend(model_str);
+    if !matches!(backend.kind(), Backend::Claude | Backend::Codex) {
+        log::trace!("Running code review with {}", backend.display_name());
+        let json_str = backend.one_shot(
             app,
             prompt,
             model_str,
-            Some(REVIEW_SCHEMA),
+            REVIEW_SCHEMA,
             working_dir,
+            custom_profile_name,
         )?;
         return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse OpenCode review JSON: {e}, content: {json_str}");
+            log::error!("Failed to parse review JSON: {e}, content: {json_str}");
             format!("Failed to parse review: {e}")
         });
     }
 
     // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(model_str) {
+    if backend.kind() == Backend::Codex {
         log::trace!("Running code review with Codex CLI (output-schema)");
         let json_str = execute_codex_review(app, prompt, model_str, working_dir, review_run_id)?;
         return serde_json::from_str(&json_str).map_err(|e| {
@@ -6465,108 +6237,20 @@ fn generate_release_notes_content(
 
     let model_str = model.unwrap_or("haiku");
 
-    // Route to OpenCode if model is an OpenCode model
-    if crate::is_opencode_model(model_str) {
-        log::trace!("Generating release notes with OpenCode");
-        let json_str = crate::chat::opencode::execute_one_shot_opencode(
-            app,
-            &prompt,
-            model_str,
-            Some(RELEASE_NOTES_SCHEMA),
-            Some(std::path::Path::new(project_path)),
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse OpenCode release notes JSON: {e}, content: {json_str}");
-            format!("Failed to parse release notes: {e}")
-        });
-    }
-
-    // Route to Codex CLI if model is a Codex model
-    if crate::is_codex_model(model_str) {
-        log::trace!("Generating release notes with Codex CLI (output-schema)");
-        let json_str = crate::chat::codex::execute_one_shot_codex(
-            app,
-            &prompt,
-            model_str,
-            RELEASE_NOTES_SCHEMA,
-            Some(std::path::Path::new(project_path)),
-        )?;
-        return serde_json::from_str(&json_str).map_err(|e| {
-            log::error!("Failed to parse Codex release notes JSON: {e}, content: {json_str}");
-            format!("Failed to parse release notes: {e}")
-        });
-    }
-
-    let cli_path = resolve_cli_binary(app);
-    if !cli_path.exists() {
-        return Err("Claude CLI not installed".to_string());
-    }
-
-    log::trace!("Generating release notes with Claude CLI (JSON schema)");
-
-    let mut cmd = silent_command(&cli_path);
-    crate::chat::claude::apply_custom_profile_settings(&mut cmd, custom_profile_name);
-    cmd.args([
-        "--print",
-        "--verbose",
-        "--input-format",
-        "stream-json",
-        "--output-format",
-        "stream-json",
-        "--model",
+    let backend = crate::chat::one_shot_backend(model_str);
+    log::trace!("Generating release notes with {}", backend.display_name());
+    let json_str = backend.one_shot(
+        app,
+        &prompt,
         model_str,
-        "--no-session-persistence",
-        "--tools",
-        "",
-        "--max-turns",
-        "1",
-        "--json-schema",
         RELEASE_NOTES_SCHEMA,
-    ]);
-
-    cmd.stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    let mut child = cmd
-        .spawn()
-        .map_err(|e| format!("Failed to spawn Claude CLI: {e}"))?;
-
-    // Write prompt to stdin
-    {
-        let stdin = child.stdin.as_mut().ok_or("Failed to open stdin")?;
-        let input_message = serde_json::json!({
-            "type": "user",
-            "message": {
-                "role": "user",
-                "content": prompt
-            }
-        });
-        writeln!(stdin, "{input_message}").map_err(|e| format!("Failed to write to stdin: {e}"))?;
-    }
-
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait for Claude CLI: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        return Err(format!(
-            "Claude CLI failed: stderr={}, stdout={}",
-            stderr.trim(),
-            stdout.trim()
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    log::trace!("Claude CLI release notes stdout: {stdout}");
-
-    let json_content = extract_structured_output(&stdout)?;
-    log::trace!("Extracted release notes JSON: {json_content}");
-
-    serde_json::from_str::<ReleaseNotesResponse>(&json_content)
-        .map_err(|e| format!("Failed to parse release notes response: {e}"))
+        Some(std::path::Path::new(project_path)),
+        custom_profile_name,
+    )?;
+    serde_json::from_str::<ReleaseNotesResponse>(&json_str).map_err(|e| {
+        log::error!("Failed to parse release notes JSON: {e}, content: {json_str}");
+        format!("Failed to parse release notes response: {e}")
+    })
 }
 
 /// Generate release notes comparing a tag to HEAD
@@ -7033,11 +6717,14 @@ pub async fn cleanup_old_archives(
                 .map(|ws| ws.sessions.iter().map(|s| s.id.clone()).collect())
                 .unwrap_or_default();
 
-        // Delete session data directories
+        // Delete session data directories and checkpoint refs
         for sid in &session_ids {
             if let Err(e) = crate::chat::storage::delete_session_data(&app, sid) {
                 log::warn!("Failed to delete session data for {sid}: {e}");
             }
+            if let Some(proj) = project {
+                crate::chat::delete_session_checkpoints(&proj.path, sid);
+            }
         }
 
         // Delete the sessions index file
@@ -7190,11 +6877,14 @@ pub async fn delete_all_archives(app: AppHandle) -> Result<CleanupResult, String
                 .map(|ws| ws.sessions.iter().map(|s| s.id.clone()).collect())
                 .unwrap_or_default();
 
-        // Delete session data directories
+        // Delete session data directories and checkpoint refs
         for sid in &session_ids {
             if let Err(e) = crate::chat::storage::delete_session_data(&app, sid) {
                 log::warn!("Failed to delete session data for {sid}: {e}");
             }
+            if let Some(proj) = project {
+                crate::chat::delete_session_checkpoints(&proj.path, sid);
+            }
         }
 
         // Delete the sessions index file
@@ -460,7 +460,7 @@ pub struct DiffFile {
 /// Complete diff response
 #[derive(Debug, Clone, Serialize)]
 pub struct GitDiff {
-    /// Type of diff: "uncommitted" or "branch"
+    /// Type of diff: "uncommitted", "branch" or "run" (one agent run's changes)
     pub diff_type: String,
     /// Base ref (e.g., "origin/main" or "HEAD")
     pub base_ref: String,
@@ -501,46 +501,8 @@ fn parse_hunk_header(header: &str) -> Option<(u32, u32, u32, u32)> {
     Some((old_start, old_lines, new_start, new_lines))
 }
 
-/// Get detailed diff content for a repository
-///
-/// `diff_type` can be "uncommitted" (working directory vs HEAD) or "branch" (HEAD vs base branch)
-pub fn get_git_diff(
-    repo_path: &str,
-    diff_type: &str,
-    base_branch: Option<&str>,
-) -> Result<GitDiff, String> {
-    let base = base_branch.unwrap_or("main");
-    let range = format!("origin/{base}...HEAD");
-
-    let (base_ref, target_ref, args): (String, String, Vec<&str>) = match diff_type {
-        "uncommitted" => (
-            "HEAD".to_string(),
-            "working directory".to_string(),
-            vec!["diff", "HEAD", "--unified=3"],
-        ),
-        "branch" => {
-            let origin_ref = format!("origin/{base}");
-            (
-                origin_ref,
-                "HEAD".to_string(),
-                vec!["diff", "--unified=3", &range],
-            )
-        }
-        _ => return Err(format!("Invalid diff_type: {diff_type}")),
-    };
-
-    let output = silent_command("git")
-        .args(&args)
-        .current_dir(repo_path)
-        .output()
-        .map_err(|e| format!("Failed to run git diff: {e}"))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Git diff failed: {stderr}"));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
+/// Parse `git diff` output into files and hunks
+pub fn parse_diff(stdout: &str) -> Vec<DiffFile> {
     let mut files: Vec<DiffFile> = Vec::new();
     let mut current_file: Option<DiffFile> = None;
     let mut current_hunk: Option<DiffHunk> = None;
@@ -669,6 +631,50 @@ pub fn get_git_diff(
         files.push(file);
     }
 
+    files
+}
+
+/// Get detailed diff content for a repository
+///
+/// `diff_type` can be "uncommitted" (working directory vs HEAD) or "branch" (HEAD vs base branch)
+pub fn get_git_diff(
+    repo_path: &str,
+    diff_type: &str,
+    base_branch: Option<&str>,
+) -> Result<GitDiff, String> {
+    let base = base_branch.unwrap_or("main");
+    let range = format!("origin/{base}...HEAD");
+
+    let (base_ref, target_ref, args): (String, String, Vec<&str>) = match diff_type {
+        "uncommitted" => (
+            "HEAD".to_string(),
+            "working directory".to_string(),
+            vec!["diff", "HEAD", "--unified=3"],
+        ),
+        "branch" => {
+            let origin_ref = format!("origin/{base}");
+            (
+                origin_ref,
+                "HEAD".to_string(),
+                vec!["diff", "--unified=3", &range],
+            )
+        }
+        _ => return Err(format!("Invalid diff_type: {diff_type}")),
+    };
+
+    let output = silent_command("git")
+        .args(&args)
+        .current_dir(repo_path)
+        .output()
+        .map_err(|e| format!("Failed to run git diff: {e}"))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Git diff failed: {stderr}"));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut files = parse_diff(&stdout);
     // Build raw patch - start with git diff output
     let mut raw_patch = stdout.to_string();
 
@@ -2,29 +2,16 @@ use std::path::PathBuf;
 use std::sync::Mutex;
 
 use once_cell::sync::Lazy;
-use tauri::{AppHandle, Manager};
+use tauri::AppHandle;
 
 use super::types::ProjectsData;
+use crate::db;
+use crate::instance::ensure_writer;
 
-/// Global mutex to prevent concurrent read-modify-write races on projects.json.
-/// Multiple threads (e.g., fetch_worktrees_status) can call save_projects_data simultaneously,
-/// causing race conditions with the atomic write pattern (temp file + rename).
+/// Global mutex to prevent concurrent read-modify-write races on projects data.
+/// Multiple threads (e.g., fetch_worktrees_status) can call save_projects_data simultaneously.
 static PROJECTS_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
-/// Get the path to the projects.json data file
-pub fn get_projects_path(app: &AppHandle) -> Result<PathBuf, String> {
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
-
-    // Ensure the directory exists
-    std::fs::create_dir_all(&app_data_dir)
-        .map_err(|e| format!("Failed to create app data directory: {e}"))?;
-
-    Ok(app_data_dir.join("projects.json"))
-}
-
 /// Get the base directory for all worktrees (~/jean)
 pub fn get_worktrees_base_dir() -> Result<PathBuf, String> {
     let home_dir = dirs::home_dir().ok_or_else(|| "Failed to get home directory".to_string())?;
@@ -71,24 +58,12 @@ pub fn sanitize_directory_name(name: &str) -> String {
         .collect()
 }
 
-/// Load projects data from disk (internal, no locking)
+/// Load projects data from the database (internal, no locking)
 fn load_projects_data_internal(app: &AppHandle) -> Result<ProjectsData, String> {
-    log::trace!("Loading projects data from disk");
-    let path = get_projects_path(app)?;
-
-    if !path.exists() {
-        log::trace!("Projects file not found, returning empty data");
-        return Ok(ProjectsData::default());
-    }
-
-    let contents = std::fs::read_to_string(&path).map_err(|e| {
-        log::error!("Failed to read projects file: {e}");
-        format!("Failed to read projects file: {e}")
-    })?;
-
-    let data: ProjectsData = serde_json::from_str(&contents).map_err(|e| {
-        log::error!("Failed to parse projects JSON: {e}");
-        format!("Failed to parse projects data: {e}")
+    log::trace!("Loading projects data from the database");
+    let data = db::with_connection(app, db::load_projects).map_err(|e| {
+        log::error!("Failed to load projects data: {e}");
+        format!("Failed to load projects data: {e}")
     })?;
 
     let original_count = data.worktrees.len();
@@ -130,10 +105,12 @@ fn load_projects_data_internal(app: &AppHandle) -> Result<ProjectsData, String>
     let data = ProjectsData {
         projects: data.projects,
         worktrees: valid_worktrees,
+        version: data.version,
     };
 
-    // Save cleaned data if any orphans were removed
-    if removed_count > 0 {
+    // Save cleaned data if any orphans were removed (a read-only instance
+    // just filters them out)
+    if removed_count > 0 && ensure_writer().is_ok() {
         log::trace!("Cleaned up {removed_count} orphaned worktree(s)");
         save_projects_data_internal(app, &data)?;
     }
@@ -146,44 +123,31 @@ fn load_projects_data_internal(app: &AppHandle) -> Result<ProjectsData, String>
     Ok(data)
 }
 
-/// Load projects data from disk (with locking for thread safety)
+/// Load projects data from the database (with locking for thread safety)
 pub fn load_projects_data(app: &AppHandle) -> Result<ProjectsData, String> {
     let _lock = PROJECTS_LOCK.lock().unwrap();
     load_projects_data_internal(app)
 }
 
-/// Save projects data to disk (internal, no locking - atomic write: temp file + rename)
+/// Save projects data (internal, no locking). All rows are replaced in one
+/// transaction.
 fn save_projects_data_internal(app: &AppHandle, data: &ProjectsData) -> Result<(), String> {
-    log::trace!("Saving projects data to disk");
-    let path = get_projects_path(app)?;
-
-    let json_content = serde_json::to_string_pretty(data).map_err(|e| {
-        log::error!("Failed to serialize projects data: {e}");
-        format!("Failed to serialize projects data: {e}")
-    })?;
-
-    // Write to a temporary file first, then rename (atomic operation)
-    let temp_path = path.with_extension("tmp");
-
-    std::fs::write(&temp_path, json_content).map_err(|e| {
-        log::error!("Failed to write projects file: {e}");
-        format!("Failed to write projects file: {e}")
-    })?;
-
-    std::fs::rename(&temp_path, &path).map_err(|e| {
-        log::error!("Failed to finalize projects file: {e}");
-        format!("Failed to finalize projects file: {e}")
+    ensure_writer()?;
+    log::trace!("Saving projects data to the database");
+    db::with_transaction(app, |conn| db::save_projects(conn, data)).map_err(|e| {
+        log::error!("Failed to save projects data: {e}");
+        format!("Failed to save projects data: {e}")
     })?;
 
     log::trace!(
-        "Saved {} projects and {} worktrees to {path:?}",
+        "Saved {} projects and {} worktrees",
         data.projects.len(),
         data.worktrees.len()
     );
     Ok(())
 }
 
-/// Save projects data to disk (with locking for thread safety)
+/// Save projects data (with locking for thread safety)
 pub fn save_projects_data(app: &AppHandle, data: &ProjectsData) -> Result<(), String> {
     let _lock = PROJECTS_LOCK.lock().unwrap();
     save_projects_data_internal(app, data)
@@ -30,6 +30,9 @@ pub enum MergeType {
 pub struct JeanConfig {
     #[serde(default)]
     pub scripts: JeanScripts,
+    /// Tool permission rules for agent sessions
+    #[serde(default, skip_serializing_if = "PermissionPolicy::is_empty")]
+    pub permissions: PermissionPolicy,
 }
 
 /// Scripts section of jean.json
@@ -43,6 +46,48 @@ pub struct JeanScripts {
     pub run: Option<String>,
 }
 
+/// Permissions section of jean.json (see `chat::policy` for evaluation)
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct PermissionPolicy {
+    #[serde(default)]
+    pub rules: Vec<PermissionRule>,
+}
+
+impl PermissionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// A single permission rule. It applies when every condition it sets matches;
+/// a rule with no conditions applies to every tool call.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PermissionRule {
+    pub action: PolicyAction,
+    /// Tool name, e.g. "Bash", "Edit", or "mcp__server__*" for a whole MCP server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    /// Glob matched against the full Bash command (`*` matches anything)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Glob matched against the file path relative to the worktree root
+    /// (`*` stays within a directory, `**` crosses directories)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// What a matching permission rule does with a tool call
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// Run without asking
+    Allow,
+    /// Ask the user, even when the execution mode would allow it
+    Ask,
+    /// Refuse without asking
+    Deny,
+}
+
 /// A git project that has been added to Jean, or a folder for organizing projects
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
@@ -185,10 +230,23 @@ pub struct Worktree {
 }
 
 /// Container for all persisted project data
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectsData {
     pub projects: Vec<Project>,
     pub worktrees: Vec<Worktree>,
+    /// Storage format version for migrations (missing in files from before versioning)
+    #[serde(default)]
+    pub version: u32,
+}
+
+impl Default for ProjectsData {
+    fn default() -> Self {
+        Self {
+            projects: Vec::new(),
+            worktrees: Vec::new(),
+            version: crate::migrations::PROJECTS_VERSION,
+        }
+    }
 }
 
 impl ProjectsData {
@@ -0,0 +1,159 @@
+//! Tauri commands for managing scheduled prompts
+
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use super::{
+    emit_schedules_changed, load_schedules, now, spawn_fire, with_schedules_mut, Schedule,
+    ScheduleConfig, ScheduleTrigger,
+};
+
+/// List all schedules with their history
+#[tauri::command]
+pub async fn list_schedules(app: AppHandle) -> Result<Vec<Schedule>, String> {
+    load_schedules(&app)
+}
+
+/// Create a schedule
+#[tauri::command]
+pub async fn create_schedule(app: AppHandle, config: ScheduleConfig) -> Result<Schedule, String> {
+    log::trace!("Creating schedule: {}", config.name);
+    config.validate()?;
+
+    let now = now();
+    let schedule = Schedule {
+        id: Uuid::new_v4().to_string(),
+        next_run_at: if config.enabled {
+            config.trigger.next_after(now)
+        } else {
+            None
+        },
+        config,
+        created_at: now,
+        last_run_at: None,
+        history: vec![],
+    };
+
+    let created = schedule.clone();
+    with_schedules_mut(&app, move |schedules| {
+        schedules.push(schedule);
+        Ok(())
+    })?;
+    emit_schedules_changed(&app);
+    Ok(created)
+}
+
+/// Replace a schedule's configuration (history is kept)
+#[tauri::command]
+pub async fn update_schedule(
+    app: AppHandle,
+    schedule_id: String,
+    config: ScheduleConfig,
+) -> Result<Schedule, String> {
+    log::trace!("Updating schedule: {schedule_id}");
+    config.validate()?;
+
+    let now = now();
+    let updated = with_schedules_mut(&app, |schedules| {
+        let schedule = schedules
+            .iter_mut()
+            .find(|s| s.id == schedule_id)
+            .ok_or_else(|| format!("Schedule not found: {schedule_id}"))?;
+        schedule.next_run_at = if config.enabled {
+            config.trigger.next_after(now)
+        } else {
+            None
+        };
+        schedule.config = config;
+        Ok(schedule.clone())
+    })?;
+    emit_schedules_changed(&app);
+    Ok(updated)
+}
+
+/// Enable or disable a schedule
+#[tauri::command]
+pub async fn set_schedule_enabled(
+    app: AppHandle,
+    schedule_id: String,
+    enabled: bool,
+) -> Result<Schedule, String> {
+    log::trace!("Setting schedule {schedule_id} enabled: {enabled}");
+
+    let now = now();
+    let updated = with_schedules_mut(&app, |schedules| {
+        let schedule = schedules
+            .iter_mut()
+            .find(|s| s.id == schedule_id)
+            .ok_or_else(|| format!("Schedule not found: {schedule_id}"))?;
+        schedule.config.enabled = enabled;
+        schedule.next_run_at = if enabled {
+            schedule.config.trigger.next_after(now)
+        } else {
+            None
+        };
+        Ok(schedule.clone())
+    })?;
+    emit_schedules_changed(&app);
+    Ok(updated)
+}
+
+/// Delete a schedule and its history
+#[tauri::command]
+pub async fn delete_schedule(app: AppHandle, schedule_id: String) -> Result<(), String> {
+    log::trace!("Deleting schedule: {schedule_id}");
+
+    with_schedules_mut(&app, |schedules| {
+        let before = schedules.len();
+        schedules.retain(|s| s.id != schedule_id);
+        if schedules.len() == before {
+            return Err(format!("Schedule not found: {schedule_id}"));
+        }
+        Ok(())
+    })?;
+    emit_schedules_changed(&app);
+    Ok(())
+}
+
+/// Fire a schedule immediately (does not change its next run time).
+/// Returns the ID of the new history entry.
+#[tauri::command]
+pub async fn run_schedule_now(app: AppHandle, schedule_id: String) -> Result<String, String> {
+    log::trace!("Running schedule now: {schedule_id}");
+
+    let now = now();
+    let (schedule, history_id) = with_schedules_mut(&app, |schedules| {
+        let schedule = schedules
+            .iter_mut()
+            .find(|s| s.id == schedule_id)
+            .ok_or_else(|| format!("Schedule not found: {schedule_id}"))?;
+        let history_id = schedule.begin_run(now, true);
+        Ok((schedule.clone(), history_id))
+    })?;
+    emit_schedules_changed(&app);
+
+    spawn_fire(&app, schedule, history_id.clone());
+    Ok(history_id)
+}
+
+/// Preview the next firing times of a trigger (for validating input in the UI)
+#[tauri::command]
+pub async fn preview_schedule_trigger(
+    trigger: ScheduleTrigger,
+    count: Option<usize>,
+) -> Result<Vec<u64>, String> {
+    trigger.validate()?;
+
+    let mut times = Vec::new();
+    let mut after = now();
+    for _ in 0..count.unwrap_or(5).min(50) {
+        match trigger.next_after(after) {
+            Some(next) => {
+                times.push(next);
+                after = next;
+            }
+            None => break,
+        }
+    }
+    Ok(times)
+}
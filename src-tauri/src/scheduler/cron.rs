@@ -0,0 +1,272 @@
+//! Minimal cron expression parser
+//!
+//! Supports the standard five fields (`minute hour day-of-month month day-of-week`)
+//! with `*`, lists (`1,15`), ranges (`1-5`), steps (`*/15`, `0-30/10`), month and
+//! weekday names (`jan`, `mon`), and the `@hourly`, `@daily`/`@midnight`,
+//! `@weekly`, `@monthly` and `@yearly`/`@annually` shortcuts.
+//!
+//! As in Vixie cron, when both day-of-month and day-of-week are restricted a day
+//! matches if either field matches.
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead `next_after` searches before giving up (e.g. `0 0 30 2 *`)
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// A parsed cron expression. Each field is a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Day-of-month field was `*` (affects how it combines with day-of-week)
+    any_day_of_month: bool,
+    /// Day-of-week field was `*`
+    any_day_of_week: bool,
+}
+
+impl CronExpr {
+    /// Parse a cron expression
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let expression = expression.trim();
+        let expanded = match expression.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *".to_string(),
+            "@daily" | "@midnight" => "0 0 * * *".to_string(),
+            "@weekly" => "0 0 * * 0".to_string(),
+            "@monthly" => "0 0 1 * *".to_string(),
+            "@yearly" | "@annually" => "0 0 1 1 *".to_string(),
+            other if other.starts_with('@') => {
+                return Err(format!("Unknown cron shortcut: {expression}"))
+            }
+            other => other.to_string(),
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Cron expression must have 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        }
+
+        let mut days_of_week = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES, 0)
+            .map_err(|e| format!("Invalid day-of-week field: {e}"))?;
+        // 7 is an alias for Sunday
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week & !(1 << 7)) | 1;
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, &[], 0)
+                .map_err(|e| format!("Invalid minute field: {e}"))?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)
+                .map_err(|e| format!("Invalid hour field: {e}"))?,
+            days_of_month: parse_field(fields[2], 1, 31, &[], 0)
+                .map_err(|e| format!("Invalid day-of-month field: {e}"))?,
+            months: parse_field(fields[3], 1, 12, &MONTH_NAMES, 1)
+                .map_err(|e| format!("Invalid month field: {e}"))?,
+            days_of_week,
+            any_day_of_month: fields[2] == "*",
+            any_day_of_week: fields[4] == "*",
+        })
+    }
+
+    /// First matching minute strictly after `after` (unix seconds).
+    /// `utc_offset_minutes` shifts the wall clock the expression is evaluated in.
+    pub fn next_after(&self, after: u64, utc_offset_minutes: i32) -> Option<u64> {
+        let offset_secs = i64::from(utc_offset_minutes) * 60;
+        let mut minute = (after as i64 + offset_secs).div_euclid(60) + 1;
+        let limit = minute + MAX_SEARCH_DAYS * 1_440;
+
+        while minute < limit {
+            let days = minute.div_euclid(1_440);
+            let (_, month, day) = civil_from_days(days);
+            if !self.day_matches(days, month, day) {
+                minute = (days + 1) * 1_440;
+                continue;
+            }
+
+            let minute_of_day = minute.rem_euclid(1_440);
+            if !has_bit(self.hours, minute_of_day / 60) {
+                minute = days * 1_440 + (minute_of_day / 60 + 1) * 60;
+                continue;
+            }
+            if !has_bit(self.minutes, minute_of_day % 60) {
+                minute += 1;
+                continue;
+            }
+
+            return u64::try_from(minute * 60 - offset_secs).ok();
+        }
+        None
+    }
+
+    fn day_matches(&self, days: i64, month: i64, day: i64) -> bool {
+        if !has_bit(self.months, month) {
+            return false;
+        }
+        // 1970-01-01 was a Thursday
+        let weekday = (days + 4).rem_euclid(7);
+        let dom = has_bit(self.days_of_month, day);
+        let dow = has_bit(self.days_of_week, weekday);
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (false, true) => dom,
+            (true, false) => dow,
+            (false, false) => dom || dow,
+        }
+    }
+}
+
+fn has_bit(mask: u64, value: i64) -> bool {
+    (0..64).contains(&value) && mask & (1 << value) != 0
+}
+
+/// Parse one field into a bitmask. `names[i]` is an alias for `i + name_base`.
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+    name_base: u32,
+) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        if let Some(i) = names.iter().position(|n| *n == s) {
+            return Ok(i as u32 + name_base);
+        }
+        let v: u32 = s.parse().map_err(|_| format!("'{s}' is not a number"))?;
+        if v < min || v > max {
+            return Err(format!("{v} is out of range {min}-{max}"));
+        }
+        Ok(v)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("'{step}' is not a valid step"))?;
+                if step == 0 {
+                    return Err("step must be at least 1".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            let (a, b) = (value(a)?, value(b)?);
+            if a > b {
+                return Err(format!("range {a}-{b} is backwards"));
+            }
+            (a, b)
+        } else {
+            let v = value(range)?;
+            // `5/15` means "from 5 to the end, every 15"
+            if part.contains('/') {
+                (v, max)
+            } else {
+                (v, v)
+            }
+        };
+
+        for v in (start..=end).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+/// Civil date (year, month, day) from days since the unix epoch
+/// (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-03-02 00:00:00 UTC (a Monday)
+    const MONDAY: u64 = 1_772_409_600;
+
+    #[test]
+    fn test_parse_fields() {
+        let expr = CronExpr::parse("*/15 9-17 * jan,jul mon-fri").unwrap();
+        assert_eq!(expr.minutes, (1 << 0) | (1 << 15) | (1 << 30) | (1 << 45));
+        assert_eq!(expr.hours, (9..=17).fold(0, |m, h| m | (1 << h)));
+        assert_eq!(expr.months, (1 << 1) | (1 << 7));
+        assert_eq!(expr.days_of_week, (1..=5).fold(0, |m, d| m | (1 << d)));
+
+        assert_eq!(CronExpr::parse("0 0 * * 7").unwrap().days_of_week, 1);
+        assert_eq!(
+            CronExpr::parse("@daily").unwrap(),
+            CronExpr::parse("0 0 * * *").unwrap()
+        );
+
+        assert!(CronExpr::parse("0 0 * *").is_err());
+        assert!(CronExpr::parse("60 * * * *").is_err());
+        assert!(CronExpr::parse("*/0 * * * *").is_err());
+        assert!(CronExpr::parse("5-1 * * * *").is_err());
+        assert!(CronExpr::parse("@sometimes").is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        let nightly = CronExpr::parse("30 2 * * *").unwrap();
+        assert_eq!(
+            nightly.next_after(MONDAY, 0),
+            Some(MONDAY + 2 * 3_600 + 30 * 60)
+        );
+        // Strictly after: firing time itself moves on to the next day
+        let fired = MONDAY + 2 * 3_600 + 30 * 60;
+        assert_eq!(nightly.next_after(fired, 0), Some(fired + 86_400));
+
+        // Weekly on Friday at 09:00
+        let weekly = CronExpr::parse("0 9 * * fri").unwrap();
+        assert_eq!(
+            weekly.next_after(MONDAY, 0),
+            Some(MONDAY + 4 * 86_400 + 9 * 3_600)
+        );
+
+        // Day-of-month OR day-of-week when both are restricted: the 1st, or any Sunday
+        let either = CronExpr::parse("0 0 1 * sun").unwrap();
+        assert_eq!(either.next_after(MONDAY, 0), Some(MONDAY + 6 * 86_400));
+
+        // Impossible dates never match
+        assert_eq!(
+            CronExpr::parse("0 0 30 2 *").unwrap().next_after(MONDAY, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn test_next_after_with_utc_offset() {
+        // 02:30 at UTC+2 is 00:30 UTC
+        let nightly = CronExpr::parse("30 2 * * *").unwrap();
+        assert_eq!(nightly.next_after(MONDAY, 120), Some(MONDAY + 30 * 60));
+        // 02:30 at UTC-5 is 07:30 UTC
+        assert_eq!(
+            nightly.next_after(MONDAY, -300),
+            Some(MONDAY + 7 * 3_600 + 30 * 60)
+        );
+    }
+}
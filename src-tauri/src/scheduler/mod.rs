@@ -0,0 +1,691 @@
+//! Scheduled and recurring prompts
+//!
+//! A schedule fires a stored prompt through `send_chat_message`, either into an
+//! existing session or into a freshly created worktree, on a cron expression or
+//! a fixed interval. Schedules live in `app-data/schedules.json` together with a
+//! short history of the runs they fired.
+//!
+//! The scheduler runs its own polling thread alongside the background task
+//! manager. A schedule that came due while the app was closed fires once on the
+//! next launch.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use std::{fs, path::PathBuf};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::chat::time::now;
+use crate::chat::types::RunStatus;
+use crate::http_server::EmitExt;
+
+pub mod commands;
+mod cron;
+
+pub use cron::CronExpr;
+
+/// How often the polling loop checks for due schedules
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Minimum interval for interval schedules in seconds (1 minute)
+pub const MIN_INTERVAL_SECS: u64 = 60;
+
+/// Fired runs kept per schedule (oldest are dropped first)
+const MAX_HISTORY: usize = 50;
+
+/// How long to wait for a new worktree to finish creating before giving up
+const WORKTREE_READY_TIMEOUT: Duration = Duration::from_secs(600);
+
+// ============================================================================
+// Types
+// ============================================================================
+
+/// When a schedule fires
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTrigger {
+    /// Five-field cron expression (see `cron.rs`), evaluated in the wall clock
+    /// `utc_offset_minutes` away from UTC
+    Cron {
+        expression: String,
+        #[serde(default)]
+        utc_offset_minutes: i32,
+    },
+    /// Fixed interval in seconds, measured from the previous firing
+    Interval { seconds: u64 },
+}
+
+impl ScheduleTrigger {
+    /// Check that the trigger can be parsed and will fire
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScheduleTrigger::Cron {
+                expression,
+                utc_offset_minutes,
+            } => {
+                if utc_offset_minutes.abs() > 14 * 60 {
+                    return Err(format!("UTC offset {utc_offset_minutes} is out of range"));
+                }
+                let expr = CronExpr::parse(expression)?;
+                if expr.next_after(now(), *utc_offset_minutes).is_none() {
+                    return Err(format!("Cron expression never fires: {expression}"));
+                }
+                Ok(())
+            }
+            ScheduleTrigger::Interval { seconds } => {
+                if *seconds < MIN_INTERVAL_SECS {
+                    return Err(format!(
+                        "Interval must be at least {MIN_INTERVAL_SECS} seconds"
+                    ));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Next firing time strictly after `after` (unix seconds)
+    pub fn next_after(&self, after: u64) -> Option<u64> {
+        match self {
+            ScheduleTrigger::Cron {
+                expression,
+                utc_offset_minutes,
+            } => CronExpr::parse(expression)
+                .ok()?
+                .next_after(after, *utc_offset_minutes),
+            ScheduleTrigger::Interval { seconds } => Some(after + seconds),
+        }
+    }
+}
+
+/// Where a schedule sends its prompt
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScheduleTarget {
+    /// An existing session
+    Session {
+        worktree_id: String,
+        session_id: String,
+    },
+    /// A new worktree created from the project's base branch for every firing
+    NewWorktree {
+        project_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base_branch: Option<String>,
+    },
+}
+
+/// User-editable part of a schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduleConfig {
+    pub name: String,
+    /// Prompt sent as the user message
+    pub prompt: String,
+    pub trigger: ScheduleTrigger,
+    pub target: ScheduleTarget,
+    /// Model to run with (None = backend default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Backend override ("claude", "codex", "opencode", "gemini"; None = session default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+    /// Execution mode ("plan", "build", "yolo")
+    pub execution_mode: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl ScheduleConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Schedule name cannot be empty".to_string());
+        }
+        if self.prompt.trim().is_empty() {
+            return Err("Schedule prompt cannot be empty".to_string());
+        }
+        if !matches!(self.execution_mode.as_str(), "plan" | "build" | "yolo") {
+            return Err(format!("Unknown execution mode: {}", self.execution_mode));
+        }
+        self.trigger.validate()
+    }
+}
+
+/// Outcome of a fired run
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledRunStatus {
+    /// Prompt was sent and the run is still in progress
+    Running,
+    Completed,
+    Cancelled,
+    /// Worktree creation, send, or the run itself failed
+    Failed,
+    /// Not sent (e.g. the target session was busy)
+    Skipped,
+}
+
+/// One firing of a schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScheduledRun {
+    pub id: String,
+    /// Unix timestamp when the schedule fired
+    pub fired_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+    /// Fired by `run_schedule_now` rather than the trigger
+    #[serde(default)]
+    pub manual: bool,
+    pub status: ScheduledRunStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worktree_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Chat run the prompt started (see `RunEntry.run_id`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A stored schedule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Schedule {
+    pub id: String,
+    #[serde(flatten)]
+    pub config: ScheduleConfig,
+    pub created_at: u64,
+    /// Next firing time (None when disabled or the trigger never fires again)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_run_at: Option<u64>,
+    /// Fired runs, oldest first
+    #[serde(default)]
+    pub history: Vec<ScheduledRun>,
+}
+
+impl Schedule {
+    /// Record a firing and return the new history entry's ID
+    fn begin_run(&mut self, fired_at: u64, manual: bool) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.last_run_at = Some(fired_at);
+        self.history.push(ScheduledRun {
+            id: id.clone(),
+            fired_at,
+            finished_at: None,
+            manual,
+            status: ScheduledRunStatus::Running,
+            worktree_id: None,
+            session_id: None,
+            run_id: None,
+            error: None,
+        });
+        if self.history.len() > MAX_HISTORY {
+            let excess = self.history.len() - MAX_HISTORY;
+            self.history.drain(..excess);
+        }
+        id
+    }
+
+    fn history_entry_mut(&mut self, history_id: &str) -> Option<&mut ScheduledRun> {
+        self.history.iter_mut().find(|r| r.id == history_id)
+    }
+}
+
+/// schedules.json contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SchedulesData {
+    #[serde(default)]
+    schedules: Vec<Schedule>,
+}
+
+/// Payload for schedule:fired events
+#[derive(Debug, Clone, Serialize)]
+struct ScheduleFiredEvent {
+    schedule_id: String,
+    run: ScheduledRun,
+}
+
+// ============================================================================
+// Storage
+// ============================================================================
+
+/// Serializes read-modify-write of schedules.json (polling loop vs commands)
+static SCHEDULES_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+fn get_schedules_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {e}"))?;
+    Ok(app_data_dir.join("schedules.json"))
+}
+
+fn load_schedules_internal(app: &AppHandle) -> Result<SchedulesData, String> {
+    let path = get_schedules_path(app)?;
+    if !path.exists() {
+        return Ok(SchedulesData::default());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read schedules: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse schedules: {e}"))
+}
+
+fn save_schedules_internal(app: &AppHandle, data: &SchedulesData) -> Result<(), String> {
+    let path = get_schedules_path(app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create app data directory: {e}"))?;
+    }
+    let content = serde_json::to_string_pretty(data)
+        .map_err(|e| format!("Failed to serialize schedules: {e}"))?;
+    let temp_path = path.with_extension("tmp");
+    fs::write(&temp_path, content).map_err(|e| format!("Failed to write schedules: {e}"))?;
+    fs::rename(&temp_path, &path).map_err(|e| format!("Failed to finalize schedules: {e}"))?;
+    Ok(())
+}
+
+/// Load all schedules
+pub fn load_schedules(app: &AppHandle) -> Result<Vec<Schedule>, String> {
+    let _guard = SCHEDULES_LOCK.lock().unwrap();
+    Ok(load_schedules_internal(app)?.schedules)
+}
+
+/// Atomically modify the stored schedules
+fn with_schedules_mut<F, T>(app: &AppHandle, f: F) -> Result<T, String>
+where
+    F: FnOnce(&mut Vec<Schedule>) -> Result<T, String>,
+{
+    let _guard = SCHEDULES_LOCK.lock().unwrap();
+    let mut data = load_schedules_internal(app)?;
+    let result = f(&mut data.schedules)?;
+    save_schedules_internal(app, &data)?;
+    Ok(result)
+}
+
+fn emit_schedules_changed(app: &AppHandle) {
+    if let Err(e) = app.emit_all("schedule:updated", &()) {
+        log::error!("Failed to emit schedule:updated event: {e}");
+    }
+}
+
+// ============================================================================
+// Scheduler
+// ============================================================================
+
+/// Runs the polling loop that fires due schedules
+pub struct SchedulerManager {
+    app: AppHandle,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl SchedulerManager {
+    /// Create a new scheduler
+    pub fn new(app: AppHandle) -> Self {
+        Self {
+            app,
+            shutdown: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Start the polling loop on a background thread
+    pub fn start(&self) {
+        log::trace!("Starting scheduler");
+
+        let app = self.app.clone();
+        let shutdown = Arc::clone(&self.shutdown);
+
+        thread::spawn(move || {
+            log::trace!("Scheduler polling loop started");
+
+            // Sleep before the first check so startup recovery runs first
+            loop {
+                thread::sleep(TICK_INTERVAL);
+                if shutdown.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Err(e) = fire_due_schedules(&app) {
+                    log::warn!("Scheduler tick failed: {e}");
+                }
+            }
+
+            log::trace!("Scheduler shutting down");
+        });
+    }
+
+    /// Signal the polling loop to stop
+    #[allow(dead_code)]
+    pub fn stop(&self) {
+        log::trace!("Signaling scheduler to stop");
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Advance every enabled schedule whose time has come and fire it
+fn fire_due_schedules(app: &AppHandle) -> Result<(), String> {
+    let now = now();
+
+    // Fast path: avoid rewriting schedules.json on every tick
+    let any_pending = load_schedules(app)?
+        .iter()
+        .any(|s| s.config.enabled && matches!(s.next_run_at, Some(at) if at <= now));
+    if !any_pending {
+        return Ok(());
+    }
+
+    let due = with_schedules_mut(app, |schedules| {
+        let mut due = Vec::new();
+        for schedule in schedules.iter_mut().filter(|s| s.config.enabled) {
+            if matches!(schedule.next_run_at, Some(at) if at <= now) {
+                schedule.next_run_at = schedule.config.trigger.next_after(now);
+                let history_id = schedule.begin_run(now, false);
+                due.push((schedule.clone(), history_id));
+            }
+        }
+        Ok(due)
+    })?;
+
+    if !due.is_empty() {
+        emit_schedules_changed(app);
+    }
+    for (schedule, history_id) in due {
+        spawn_fire(app, schedule, history_id);
+    }
+    Ok(())
+}
+
+/// Send a schedule's prompt in the background and record the outcome
+fn spawn_fire(app: &AppHandle, schedule: Schedule, history_id: String) {
+    log::info!(
+        "Firing schedule '{}' ({})",
+        schedule.config.name,
+        schedule.id
+    );
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let outcome = execute(&app, &schedule, &history_id).await;
+        let finished_at = now();
+        let result = update_history(&app, &schedule.id, &history_id, |run| {
+            run.finished_at = Some(finished_at);
+            match outcome {
+                Ok((status, run_id)) => {
+                    run.status = status;
+                    run.run_id = run_id;
+                }
+                Err(e) => {
+                    log::warn!("Schedule {} failed: {e}", schedule.id);
+                    run.status = ScheduledRunStatus::Failed;
+                    run.error = Some(e);
+                }
+            }
+        });
+        if let Err(e) = result {
+            log::error!("Failed to record schedule run: {e}");
+        }
+    });
+}
+
+/// Apply a change to one history entry and notify the frontend.
+/// Returns the updated entry (None if the schedule or entry was deleted).
+fn update_history<F>(
+    app: &AppHandle,
+    schedule_id: &str,
+    history_id: &str,
+    f: F,
+) -> Result<Option<ScheduledRun>, String>
+where
+    F: FnOnce(&mut ScheduledRun),
+{
+    let updated = with_schedules_mut(app, |schedules| {
+        let entry = schedules
+            .iter_mut()
+            .find(|s| s.id == schedule_id)
+            .and_then(|s| s.history_entry_mut(history_id));
+        Ok(entry.map(|run| {
+            f(run);
+            run.clone()
+        }))
+    })?;
+    if updated.is_some() {
+        emit_schedules_changed(app);
+    }
+    Ok(updated)
+}
+
+/// Resolve the target, send the prompt and wait for the run to finish.
+/// Returns the run's outcome and chat run ID.
+async fn execute(
+    app: &AppHandle,
+    schedule: &Schedule,
+    history_id: &str,
+) -> Result<(ScheduledRunStatus, Option<String>), String> {
+    let config = &schedule.config;
+
+    let (worktree_id, worktree_path, session_id) = match &config.target {
+        ScheduleTarget::Session {
+            worktree_id,
+            session_id,
+        } => {
+            let data = crate::projects::storage::load_projects_data(app)?;
+            let worktree = data
+                .find_worktree(worktree_id)
+                .ok_or_else(|| format!("Worktree not found: {worktree_id}"))?;
+            if crate::chat::registry::is_process_running(session_id) {
+                log::trace!(
+                    "Skipping schedule {}: session {session_id} is busy",
+                    schedule.id
+                );
+                update_history(app, &schedule.id, history_id, |run| {
+                    run.worktree_id = Some(worktree_id.clone());
+                    run.session_id = Some(session_id.clone());
+                    run.error = Some("Session was busy with another run".to_string());
+                })?;
+                return Ok((ScheduledRunStatus::Skipped, None));
+            }
+            (
+                worktree_id.clone(),
+                worktree.path.clone(),
+                session_id.clone(),
+            )
+        }
+        ScheduleTarget::NewWorktree {
+            project_id,
+            base_branch,
+        } => {
+            let pending = crate::projects::create_worktree(
+                app.clone(),
+                project_id.clone(),
+                base_branch.clone(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+            .await?;
+
+            let app_clone = app.clone();
+            let worktree_id = pending.id.clone();
+            let ready =
+                tokio::task::spawn_blocking(move || wait_for_worktree(&app_clone, &worktree_id))
+                    .await
+                    .map_err(|e| format!("Failed to wait for worktree: {e}"))?;
+            if !ready {
+                return Err(format!("Worktree {} was not created", pending.name));
+            }
+
+            // A new worktree starts with a single default session
+            let sessions = crate::chat::storage::load_sessions_by_id(app, &pending.id)?;
+            let session = sessions
+                .sessions
+                .first()
+                .ok_or_else(|| format!("Worktree {} has no session", pending.name))?;
+            (pending.id.clone(), pending.path.clone(), session.id.clone())
+        }
+    };
+
+    let fired = update_history(app, &schedule.id, history_id, |run| {
+        run.worktree_id = Some(worktree_id.clone());
+        run.session_id = Some(session_id.clone());
+    })?;
+    if let Some(run) = fired {
+        let event = ScheduleFiredEvent {
+            schedule_id: schedule.id.clone(),
+            run,
+        };
+        if let Err(e) = app.emit_all("schedule:fired", &event) {
+            log::error!("Failed to emit schedule:fired event: {e}");
+        }
+    }
+
+    let message = crate::chat::send_chat_message(
+        app.clone(),
+        session_id.clone(),
+        worktree_id,
+        worktree_path,
+        config.prompt.clone(),
+        config.model.clone(),
+        Some(config.execution_mode.clone()),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        config.backend.clone(),
+    )
+    .await?;
+
+    // Find the run that produced the response to report its final status
+    let run = crate::chat::storage::load_metadata(app, &session_id)?.and_then(|metadata| {
+        metadata
+            .runs
+            .iter()
+            .rev()
+            .find(|r| r.assistant_message_id.as_deref() == Some(message.id.as_str()))
+            .map(|r| (r.run_id.clone(), r.status.clone()))
+    });
+    let status = match run.as_ref().map(|(_, status)| status) {
+        Some(RunStatus::Cancelled) => ScheduledRunStatus::Cancelled,
+        Some(RunStatus::Crashed) => ScheduledRunStatus::Failed,
+        _ if message.cancelled => ScheduledRunStatus::Cancelled,
+        _ => ScheduledRunStatus::Completed,
+    };
+    Ok((status, run.map(|(run_id, _)| run_id)))
+}
+
+/// Block until the worktree has been saved (creation finished) or creation
+/// timed out. Creation failures never save the worktree, so they time out too.
+fn wait_for_worktree(app: &AppHandle, worktree_id: &str) -> bool {
+    let deadline = std::time::Instant::now() + WORKTREE_READY_TIMEOUT;
+    while std::time::Instant::now() < deadline {
+        let exists = crate::projects::storage::load_projects_data(app)
+            .map(|data| data.find_worktree(worktree_id).is_some())
+            .unwrap_or(false);
+        if exists {
+            return true;
+        }
+        thread::sleep(Duration::from_secs(2));
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(trigger: ScheduleTrigger) -> Schedule {
+        Schedule {
+            id: "s1".to_string(),
+            config: ScheduleConfig {
+                name: "Nightly deps".to_string(),
+                prompt: "Update dependencies and run tests".to_string(),
+                trigger,
+                target: ScheduleTarget::NewWorktree {
+                    project_id: "p1".to_string(),
+                    base_branch: None,
+                },
+                model: Some("opus".to_string()),
+                backend: Some("claude".to_string()),
+                execution_mode: "yolo".to_string(),
+                enabled: true,
+            },
+            created_at: 0,
+            next_run_at: None,
+            last_run_at: None,
+            history: vec![],
+        }
+    }
+
+    #[test]
+    fn test_trigger_validation_and_next_run() {
+        let interval = ScheduleTrigger::Interval { seconds: 3_600 };
+        assert!(interval.validate().is_ok());
+        assert_eq!(interval.next_after(1_000), Some(4_600));
+        assert!(ScheduleTrigger::Interval { seconds: 5 }.validate().is_err());
+
+        let weekly = ScheduleTrigger::Cron {
+            expression: "0 9 * * mon".to_string(),
+            utc_offset_minutes: 0,
+        };
+        assert!(weekly.validate().is_ok());
+        // Thursday 1970-01-01 00:00 UTC -> Monday 1970-01-05 09:00 UTC
+        assert_eq!(weekly.next_after(0), Some(4 * 86_400 + 9 * 3_600));
+
+        let invalid = ScheduleTrigger::Cron {
+            expression: "every night".to_string(),
+            utc_offset_minutes: 0,
+        };
+        assert!(invalid.validate().is_err());
+        assert_eq!(invalid.next_after(0), None);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let mut s = schedule(ScheduleTrigger::Interval { seconds: 60 });
+        assert!(s.config.validate().is_ok());
+        s.config.execution_mode = "turbo".to_string();
+        assert!(s.config.validate().is_err());
+        s.config.execution_mode = "plan".to_string();
+        s.config.prompt = "  ".to_string();
+        assert!(s.config.validate().is_err());
+    }
+
+    #[test]
+    fn test_history_is_capped() {
+        let mut s = schedule(ScheduleTrigger::Interval { seconds: 60 });
+        let first = s.begin_run(1, false);
+        for i in 0..MAX_HISTORY {
+            s.begin_run(2 + i as u64, false);
+        }
+        assert_eq!(s.history.len(), MAX_HISTORY);
+        assert!(s.history_entry_mut(&first).is_none());
+        assert_eq!(s.last_run_at, Some(1 + MAX_HISTORY as u64));
+    }
+
+    #[test]
+    fn test_schedule_serialization_flattens_config() {
+        let s = schedule(ScheduleTrigger::Cron {
+            expression: "@daily".to_string(),
+            utc_offset_minutes: 60,
+        });
+        let json = serde_json::to_value(&s).unwrap();
+        assert_eq!(json["name"], "Nightly deps");
+        assert_eq!(json["trigger"]["type"], "cron");
+        assert_eq!(json["target"]["type"], "new_worktree");
+
+        let parsed: Schedule = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, s);
+    }
+}
@@ -310,8 +310,8 @@ function App() {
   // even when ChatWindow is unmounted (e.g., when viewing a different worktree)
   useStreamingEvents({ queryClient })
 
-  // Global queue processor - must be at App level so queued messages execute
-  // even when the worktree is not focused (ChatWindow unmounted)
+  // Global queue sync - must be at App level so queued messages show up and
+  // dispatch correctly even when the worktree is not focused
   useQueueProcessor()
 
   // Headless background investigation - starts investigations on background
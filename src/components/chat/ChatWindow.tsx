@@ -35,6 +35,7 @@ import {
   useCreateSession,
   markPlanApproved as markPlanApprovedService,
   chatQueryKeys,
+  toQueuedMessage,
 } from '@/services/chat'
 import { useWorktree, useProjects, useRunScript } from '@/services/projects'
 import {
@@ -636,6 +637,14 @@ export function ChatWindow({
       ? (state.messageQueues[deferredSessionId] ?? EMPTY_QUEUED_MESSAGES)
       : EMPTY_QUEUED_MESSAGES
   )
+  // Seed the queue from session metadata until chat:queue_updated arrives
+  const persistedQueue = session?.queued_messages
+  useEffect(() => {
+    if (!deferredSessionId || !persistedQueue) return
+    const { messageQueues, setQueuedMessages } = useChatStore.getState()
+    if (messageQueues[deferredSessionId]) return
+    setQueuedMessages(deferredSessionId, persistedQueue.map(toQueuedMessage))
+  }, [deferredSessionId, persistedQueue])
   // Per-session pending permission denials (uses deferredSessionId for content consistency)
   const pendingDenials = useChatStore(state =>
     deferredSessionId
@@ -741,8 +750,10 @@ export function ChatWindow({
     activeWorktreeId,
   })
 
-  // Drag and drop images into chat input
-  const { isDragging } = useDragAndDropImages(activeSessionId)
+  // Drag and drop images and documents into chat input
+  const { isDragging } = useDragAndDropImages(activeSessionId, {
+    backend: selectedBackend,
+  })
 
   // State for file content modal (opened by clicking filenames in tool calls)
   const [viewingFilePath, setViewingFilePath] = useState<string | null>(null)
@@ -1002,8 +1013,8 @@ export function ChatWindow({
     clearInputDraft,
   })
 
-  // Note: Queue processing moved to useQueueProcessor hook in App.tsx
-  // This ensures queued messages execute even when the worktree is unfocused
+  // Note: The backend sends queued messages; useQueueProcessor in App.tsx
+  // mirrors the queue so it shows even when the worktree is unfocused
 
   // Git operations hook - handles commit, PR, review, merge operations
   const {
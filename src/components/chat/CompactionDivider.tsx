@@ -0,0 +1,35 @@
+import { memo } from 'react'
+import { Minimize2 } from 'lucide-react'
+
+interface CompactionDividerProps {
+  /** How compaction was triggered: "manual" or "auto" */
+  trigger: string
+  /** Token count before compaction */
+  preTokens: number
+}
+
+/**
+ * Divider marking where the conversation was compacted; context before it
+ * was summarized by the CLI
+ */
+export const CompactionDivider = memo(function CompactionDivider({
+  trigger,
+  preTokens,
+}: CompactionDividerProps) {
+  const label =
+    trigger === 'auto' ? 'Context auto-compacted' : 'Context compacted'
+  const tokens =
+    preTokens > 0 ? ` (${Math.round(preTokens / 1000)}k tokens summarized)` : ''
+
+  return (
+    <div className="flex items-center gap-2 text-xs text-muted-foreground">
+      <div className="h-px flex-1 bg-border" />
+      <Minimize2 className="h-3.5 w-3.5" />
+      <span>
+        {label}
+        {tokens}
+      </span>
+      <div className="h-px flex-1 bg-border" />
+    </div>
+  )
+})
@@ -26,6 +26,7 @@ import {
   TooltipContent,
 } from '@/components/ui/tooltip'
 import { ThinkingBlock } from './ThinkingBlock'
+import { CompactionDivider } from './CompactionDivider'
 import { ErrorBoundary } from '@/components/ui/ErrorBoundary'
 import { logger } from '@/lib/logger'
 import {
@@ -432,6 +433,13 @@ export const MessageItem = memo(function MessageItem({
                           />
                         )
                       }
+                      case 'compaction':
+                        return (
+                          <CompactionDivider
+                            trigger={item.trigger}
+                            preTokens={item.preTokens}
+                          />
+                        )
                       case 'unknown':
                         return (
                           <div className="text-xs text-muted-foreground border rounded px-2 py-1">
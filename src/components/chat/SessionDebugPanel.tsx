@@ -4,9 +4,10 @@ import { invoke } from '@/lib/transport'
 import { isNativeApp } from '@/lib/environment'
 import { toast } from 'sonner'
 import { Button } from '@/components/ui/button'
-import { Copy, FileText } from 'lucide-react'
+import { Copy, FileText, Undo2 } from 'lucide-react'
 import type { SessionDebugInfo, RunStatus, UsageData } from '@/types/chat'
 import { cn } from '@/lib/utils'
+import { getPermissionAudit, restoreCheckpoint } from '@/services/chat'
 import {
   Tooltip,
   TooltipTrigger,
@@ -48,6 +49,8 @@ function getStatusColor(status: RunStatus): string {
       return 'text-green-500'
     case 'cancelled':
       return 'text-yellow-500'
+    case 'timed_out':
+      return 'text-red-500'
     case 'resumable':
       return 'text-blue-500'
     case 'running':
@@ -64,6 +67,8 @@ function getStatusText(status: RunStatus): string {
       return 'completed (recovered)'
     case 'resumable':
       return 'resumable'
+    case 'timed_out':
+      return 'timed out'
     default:
       return status
   }
@@ -90,6 +95,13 @@ export function SessionDebugPanel({
     refetchInterval: 1000, // Poll every second for real-time updates
   })
 
+  const { data: auditEntries } = useQuery({
+    queryKey: ['permission-audit', sessionId],
+    queryFn: () => getPermissionAudit(sessionId),
+    staleTime: 1000,
+    refetchInterval: 1000,
+  })
+
   const handleCopyAll = useCallback(async () => {
     if (!debugInfo) return
 
@@ -132,6 +144,18 @@ export function SessionDebugPanel({
     }
   }, [debugInfo, sessionId, selectedModel, selectedProvider, selectedBackend])
 
+  const handleRestore = useCallback(
+    async (runId: string) => {
+      try {
+        await restoreCheckpoint(worktreePath, sessionId, runId)
+        toast.success('Files restored to the state before this run')
+      } catch (error) {
+        toast.error(`Failed to restore checkpoint: ${error}`)
+      }
+    },
+    [worktreePath, sessionId]
+  )
+
   if (!debugInfo) {
     return null
   }
@@ -288,14 +312,83 @@ export function SessionDebugPanel({
                     ({formatUsage(file.usage)})
                   </span>
                 )}
+                {file.changes && (
+                  <span className="font-mono text-xs shrink-0">
+                    <span className="text-green-500">
+                      +{file.changes.additions}
+                    </span>{' '}
+                    <span className="text-red-500">
+                      -{file.changes.deletions}
+                    </span>
+                  </span>
+                )}
+                {file.compactions && file.compactions.length > 0 && (
+                  <span className="text-muted-foreground text-xs shrink-0">
+                    compacted
+                    {file.compactions.length > 1 &&
+                      ` ×${file.compactions.length}`}
+                  </span>
+                )}
                 <span className="text-foreground truncate">
                   {file.user_message_preview}
                 </span>
+                {file.checkpoint && file.status !== 'running' && (
+                  <Tooltip>
+                    <TooltipTrigger asChild>
+                      <Button
+                        variant="ghost"
+                        size="sm"
+                        className="h-5 px-1 ml-auto shrink-0 text-muted-foreground"
+                        onClick={e => {
+                          e.stopPropagation()
+                          handleRestore(file.run_id)
+                        }}
+                      >
+                        <Undo2 className="size-3" />
+                      </Button>
+                    </TooltipTrigger>
+                    <TooltipContent>
+                      Restore files to before this run
+                    </TooltipContent>
+                  </Tooltip>
+                )}
               </div>
             ))}
           </div>
         )}
       </div>
+
+      {/* Automatic decisions from the jean.json permission policy */}
+      {auditEntries && auditEntries.length > 0 && (
+        <div className="mt-4">
+          <div className="font-medium mb-2">
+            Policy decisions ({auditEntries.length}):
+          </div>
+          <div className="space-y-1 ml-2">
+            {auditEntries.map((entry, index) => (
+              <div
+                key={`${entry.timestamp}-${index}`}
+                className="flex items-center gap-2"
+              >
+                <span
+                  className={cn(
+                    'font-medium shrink-0',
+                    entry.action === 'deny' ? 'text-red-500' : 'text-green-500'
+                  )}
+                >
+                  {entry.action}
+                </span>
+                <span className="text-muted-foreground shrink-0">
+                  {entry.tool}
+                </span>
+                <span className="text-foreground font-mono text-xs truncate">
+                  {entry.command ?? entry.path}
+                </span>
+              </div>
+            ))}
+          </div>
+        </div>
+      )}
     </div>
   )
 }
@@ -2,7 +2,7 @@ import { useState, useCallback, useRef } from 'react'
 import { X, FileText, Copy, Pencil, Check } from 'lucide-react'
 import { invoke } from '@/lib/transport'
 import { toast } from 'sonner'
-import type { PendingTextFile } from '@/types/chat'
+import type { PendingTextFile, SaveTextResponse } from '@/types/chat'
 import {
   Dialog,
   DialogContent,
@@ -103,13 +103,20 @@ export function TextFilePreview({
 
       setIsSaving(true)
       try {
-        const newSize = await invoke<number>('update_pasted_text', {
+        // Edits are stored as a new file (pasted files may be shared)
+        const updated = await invoke<SaveTextResponse>('update_pasted_text', {
           path: textFile.path,
           content: editContent,
         })
 
         const { updatePendingTextFile } = useChatStore.getState()
-        updatePendingTextFile(sessionId, textFile.id, editContent, newSize)
+        updatePendingTextFile(
+          sessionId,
+          textFile.id,
+          editContent,
+          updated.size,
+          updated.path
+        )
 
         setIsEditing(false)
         setEditContent('')
@@ -2,7 +2,12 @@ import { useEffect, useState } from 'react'
 import { invoke } from '@/lib/transport'
 import { toast } from 'sonner'
 import { useChatStore } from '@/store/chat-store'
-import type { SaveImageResponse, SaveTextResponse } from '@/types/chat'
+import type {
+  ReadTextResponse,
+  SaveImageResponse,
+  SaveTextResponse,
+} from '@/types/chat'
+import { saveDroppedDocument } from '@/services/chat'
 import { MAX_IMAGE_SIZE } from '../image-constants'
 import { isNativeApp } from '@/lib/environment'
 
@@ -12,9 +17,14 @@ const ALLOWED_EXTENSIONS = ['png', 'jpg', 'jpeg', 'gif', 'webp']
 /** Extensions handled as text files (vector formats) */
 const TEXT_IMAGE_EXTENSIONS = ['svg']
 
+/** Documents whose text is extracted by the backend */
+const DOCUMENT_EXTENSIONS = ['pdf', 'docx', 'xlsx', 'csv', 'tsv']
+
 interface UseDragAndDropImagesOptions {
   /** Whether drag-and-drop is disabled */
   disabled?: boolean
+  /** Session backend; PDF page images are only rendered when it has vision */
+  backend?: string
 }
 
 interface UseDragAndDropImagesResult {
@@ -63,16 +73,21 @@ export function useDragAndDropImages(
           const paths = event.payload.paths
           const imagePaths: string[] = []
           const svgPaths: string[] = []
+          const documentPaths: string[] = []
           for (const path of paths) {
             const ext = path.split('.').pop()?.toLowerCase() ?? ''
             if (ALLOWED_EXTENSIONS.includes(ext)) imagePaths.push(path)
             else if (TEXT_IMAGE_EXTENSIONS.includes(ext)) svgPaths.push(path)
+            else if (DOCUMENT_EXTENSIONS.includes(ext))
+              documentPaths.push(path)
           }
 
-          if (imagePaths.length === 0 && svgPaths.length === 0) {
-            toast.error('No image detected', {
+          const acceptedCount =
+            imagePaths.length + svgPaths.length + documentPaths.length
+          if (acceptedCount === 0) {
+            toast.error('No image or document detected', {
               description:
-                'Only PNG, JPEG, GIF, WebP, SVG files are accepted',
+                'Accepted: PNG, JPEG, GIF, WebP, SVG, PDF, DOCX, XLSX, CSV, TSV',
             })
             return
           }
@@ -87,12 +102,16 @@ export function useDragAndDropImages(
             processDroppedSvg(sourcePath, sessionId)
           }
 
+          // Process documents (text extracted by the backend)
+          for (const sourcePath of documentPaths) {
+            processDroppedDocument(sourcePath, sessionId, options?.backend)
+          }
+
           // Notify if some files were skipped
-          const skippedCount =
-            paths.length - imagePaths.length - svgPaths.length
+          const skippedCount = paths.length - acceptedCount
           if (skippedCount > 0) {
             toast.warning(`${skippedCount} file(s) skipped`, {
-              description: 'Only images are accepted',
+              description: 'Only images and documents are accepted',
             })
           }
         } else if (event.payload.type === 'leave') {
@@ -114,7 +133,7 @@ export function useDragAndDropImages(
       cancelled = true
       unlisten?.()
     }
-  }, [sessionId, options?.disabled])
+  }, [sessionId, options?.disabled, options?.backend])
 
   return { isDragging }
 }
@@ -150,6 +169,54 @@ async function processDroppedSvg(
   }
 }
 
+/**
+ * Process a dropped document: the backend stores it and extracts its text,
+ * which is attached like a pasted text file. Rendered page images (PDFs on
+ * vision-capable backends) are attached as images.
+ */
+async function processDroppedDocument(
+  sourcePath: string,
+  sessionId: string,
+  backend: string | undefined
+): Promise<void> {
+  const filename = sourcePath.split(/[\\/]/).pop() ?? sourcePath
+  const toastId = toast.loading(`Extracting ${filename}...`)
+
+  try {
+    const result = await saveDroppedDocument(sourcePath, undefined, backend)
+    const { content } = await invoke<ReadTextResponse>('read_pasted_text', {
+      path: result.path,
+    })
+
+    const { addPendingTextFile, addPendingImage } = useChatStore.getState()
+    addPendingTextFile(sessionId, {
+      id: result.id,
+      path: result.path,
+      filename: result.filename,
+      size: result.size,
+      content,
+    })
+    for (const image of result.images) {
+      addPendingImage(sessionId, {
+        id: image.id,
+        path: image.path,
+        filename: image.filename,
+      })
+    }
+
+    const pages = result.page_count
+      ? ` (${result.page_count} page${result.page_count === 1 ? '' : 's'})`
+      : ''
+    toast.success(`Attached ${result.filename}${pages}`, { id: toastId })
+  } catch (error) {
+    console.error('Failed to process dropped document:', error)
+    toast.error(`Failed to attach ${filename}`, {
+      id: toastId,
+      description: String(error),
+    })
+  }
+}
+
 /**
  * Process a dropped image file by saving it via Tauri and adding to pending images.
  */
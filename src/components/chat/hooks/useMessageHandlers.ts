@@ -3,6 +3,7 @@ import type { QueryClient } from '@tanstack/react-query'
 import { toast } from 'sonner'
 import { invoke } from '@/lib/transport'
 import {
+  addToMessageQueue,
   chatQueryKeys,
   markPlanApproved as markPlanApprovedService,
   readPlanFile,
@@ -1398,7 +1399,6 @@ Please apply this fix to the file.`
         setExecutingMode,
         markFindingFixed,
         isSending,
-        enqueueMessage,
       } = useChatStore.getState()
 
       // Mark this finding as fixed (we don't have the index here, so we generate a key based on file+line)
@@ -1426,7 +1426,7 @@ Please apply this fix to the file.`
 
       // If session is already busy, queue the fix message
       if (isSending(sessionId)) {
-        enqueueMessage(sessionId, {
+        addToMessageQueue(sessionId, {
           id: generateId(),
           message,
           pendingImages: [],
@@ -1442,7 +1442,7 @@ Please apply this fix to the file.`
             : undefined,
           mcpConfig: getMcpConfig(),
           queuedAt: Date.now(),
-        })
+        }).catch(error => toast.error(`Failed to queue fix: ${error}`))
         toast.info('Fix queued — will start when current task completes')
         return
       }
@@ -1534,7 +1534,6 @@ Please apply all these fixes to the respective files.`
         setExecutingMode,
         markFindingFixed,
         isSending,
-        enqueueMessage,
       } = useChatStore.getState()
 
       // Mark all findings as fixed
@@ -1564,7 +1563,7 @@ Please apply all these fixes to the respective files.`
 
       // If session is already busy, queue the fix message
       if (isSending(sessionId)) {
-        enqueueMessage(sessionId, {
+        addToMessageQueue(sessionId, {
           id: generateId(),
           message,
           pendingImages: [],
@@ -1580,7 +1579,7 @@ Please apply all these fixes to the respective files.`
             : undefined,
           mcpConfig: getMcpConfig(),
           queuedAt: Date.now(),
-        })
+        }).catch(error => toast.error(`Failed to queue fix: ${error}`))
         toast.info('Fix queued — will start when current task completes')
         return
       }
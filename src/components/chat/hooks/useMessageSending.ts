@@ -2,7 +2,11 @@ import { useCallback, type RefObject } from 'react'
 import { generateId } from '@/lib/uuid'
 import { toast } from 'sonner'
 import { useChatStore } from '@/store/chat-store'
-import { chatQueryKeys, cancelChatMessage } from '@/services/chat'
+import {
+  addToMessageQueue,
+  chatQueryKeys,
+  cancelChatMessage,
+} from '@/services/chat'
 import { buildMcpConfigJson } from '@/services/mcp'
 import { DEFAULT_PARALLEL_EXECUTION_PROMPT } from '@/types/preferences'
 import type {
@@ -332,7 +336,6 @@ export function useMessageSending({
         clearPendingTextFiles,
         getPendingSkills,
         clearPendingSkills,
-        enqueueMessage,
         isSending: checkIsSendingNow,
         setSessionReviewing,
       } = useChatStore.getState()
@@ -424,7 +427,9 @@ export function useMessageSending({
       scrollToBottom(true)
 
       if (checkIsSendingNow(activeSessionId)) {
-        enqueueMessage(activeSessionId, queuedMessage)
+        addToMessageQueue(activeSessionId, queuedMessage).catch(error =>
+          toast.error(`Failed to queue message: ${error}`)
+        )
         return
       }
 
@@ -1,7 +1,13 @@
 import { useCallback, type RefObject } from 'react'
+import { toast } from 'sonner'
 import { generateId } from '@/lib/uuid'
 import { useChatStore } from '@/store/chat-store'
 import { buildMcpConfigJson } from '@/services/mcp'
+import {
+  addToMessageQueue,
+  removeFromMessageQueue,
+  resumeMessageQueue,
+} from '@/services/chat'
 import { getFilename } from '@/lib/path-utils'
 import type {
   QueuedMessage,
@@ -124,10 +130,11 @@ export function usePendingAttachments({
         queuedAt: Date.now(),
       }
 
-      const { isSending: checkIsSendingNow, enqueueMessage } =
-        useChatStore.getState()
+      const { isSending: checkIsSendingNow } = useChatStore.getState()
       if (checkIsSendingNow(activeSessionId)) {
-        enqueueMessage(activeSessionId, queuedMessage)
+        addToMessageQueue(activeSessionId, queuedMessage).catch(error =>
+          toast.error(`Failed to queue message: ${error}`)
+        )
       } else {
         sendMessageNow(queuedMessage)
       }
@@ -137,13 +144,19 @@ export function usePendingAttachments({
 
   const handleRemoveQueuedMessage = useCallback(
     (sessionId: string, messageId: string) => {
-      useChatStore.getState().removeQueuedMessage(sessionId, messageId)
+      removeFromMessageQueue(sessionId, messageId).catch(error =>
+        toast.error(`Failed to remove queued message: ${error}`)
+      )
     },
     []
   )
 
   const handleForceSendQueued = useCallback((sessionId: string) => {
+    // Clear stale sending/waiting flags, then send the head of the queue
     useChatStore.getState().forceProcessQueue(sessionId)
+    resumeMessageQueue(sessionId).catch(error =>
+      toast.error(`Failed to send queued message: ${error}`)
+    )
   }, [])
 
   return {
@@ -3,6 +3,7 @@ import { useQueryClient } from '@tanstack/react-query'
 import { toast } from 'sonner'
 import { useChatStore } from '@/store/chat-store'
 import {
+  addToMessageQueue,
   chatQueryKeys,
   markPlanApproved as markPlanApprovedService,
 } from '@/services/chat'
@@ -99,7 +100,7 @@ export function usePlanDialogApproval({
         : defaultText
 
       // Queue instead of immediate execution
-      const { enqueueMessage, setExecutionMode } = useChatStore.getState()
+      const { setExecutionMode } = useChatStore.getState()
       setExecutionMode(activeSessionId, mode)
 
       const modelOverride = mode === 'yolo' ? yoloModelRef.current : buildModelRef.current
@@ -130,7 +131,9 @@ export function usePlanDialogApproval({
         queuedAt: Date.now(),
       }
 
-      enqueueMessage(activeSessionId, queuedMessage)
+      addToMessageQueue(activeSessionId, queuedMessage).catch(error =>
+        toast.error(`Failed to queue plan approval: ${error}`)
+      )
     },
     [
       activeSessionId,
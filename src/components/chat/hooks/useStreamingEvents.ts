@@ -5,7 +5,11 @@ import { toast } from 'sonner'
 import type { QueryClient } from '@tanstack/react-query'
 import { useChatStore } from '@/store/chat-store'
 import { useUIStore } from '@/store/ui-store'
-import { chatQueryKeys } from '@/services/chat'
+import {
+  chatQueryKeys,
+  compactSession,
+  resumeMessageQueue,
+} from '@/services/chat'
 import { isTauri, saveWorktreePr, projectsQueryKeys } from '@/services/projects'
 import type { Project, Worktree } from '@/types/projects'
 import { preferencesQueryKeys } from '@/services/preferences'
@@ -27,6 +31,8 @@ import type {
   PermissionDeniedEvent,
   CompactingEvent,
   CompactedEvent,
+  ContextWindowEvent,
+  DigestUpdatedEvent,
   Session,
   SessionDigest,
   WorktreeSessions,
@@ -108,7 +114,7 @@ function lookupSessionLabel(
  *
  * Handles: chat:chunk, chat:tool_use, chat:tool_block, chat:thinking,
  * chat:tool_result, chat:permission_denied, chat:done, chat:error,
- * chat:cancelled, chat:compacted
+ * chat:cancelled, chat:compacted, chat:digest_updated
  */
 export default function useStreamingEvents({
   queryClient,
@@ -129,13 +135,34 @@ export default function useStreamingEvents({
       addSendingSession,
     } = useChatStore.getState()
 
+    // A replayed run (replay_run) streams like a live one, but ends without
+    // persisting anything or changing the session's status
+    const endReplay = (sessionId: string) => {
+      const {
+        clearStreamingContent,
+        clearToolCalls,
+        clearStreamingContentBlocks,
+        clearThinkingContent,
+        setCompacting,
+        removeSendingSession,
+      } = useChatStore.getState()
+      clearStreamingContent(sessionId)
+      clearToolCalls(sessionId)
+      clearStreamingContentBlocks(sessionId)
+      clearThinkingContent(sessionId)
+      setCompacting(sessionId, false)
+      removeSendingSession(sessionId)
+    }
+
     // Sync sending state across clients (web <-> native)
     const unlistenSending = listen<{
       session_id: string
       worktree_id: string
+      replay?: boolean
     }>('chat:sending', event => {
-      const { session_id, worktree_id: wtId } = event.payload
+      const { session_id, worktree_id: wtId, replay } = event.payload
       addSendingSession(session_id)
+      if (replay) return
       // Invalidate sessions list so non-sender windows update metadata.
       // IMPORTANT: Do NOT invalidate individual session queries here — it races
       // with the mutation's optimistic updates and can overwrite them with stale
@@ -211,7 +238,9 @@ export default function useStreamingEvents({
     const unlistenPermissionDenied = listen<PermissionDeniedEvent>(
       'chat:permission_denied',
       event => {
-        const { session_id, denials } = event.payload
+        const { session_id, denials, replay } = event.payload
+        // Replays never wait for approvals
+        if (replay) return
         const {
           setPendingDenials,
           lastSentMessages,
@@ -240,6 +269,10 @@ export default function useStreamingEvents({
     const unlistenDone = listen<DoneEvent>('chat:done', event => {
       const sessionId = event.payload.session_id
       const worktreeId = event.payload.worktree_id
+      if (event.payload.replay) {
+        endReplay(sessionId)
+        return
+      }
 
       const {
         streamingContents,
@@ -797,6 +830,10 @@ export default function useStreamingEvents({
     // Handle errors from Claude CLI
     const unlistenError = listen<ErrorEvent>('chat:error', event => {
       const { session_id, error } = event.payload
+      if (event.payload.replay) {
+        endReplay(session_id)
+        return
+      }
 
       // Store error for inline display and restore input
       const {
@@ -963,7 +1000,12 @@ export default function useStreamingEvents({
           session_id,
           worktree_id: eventWorktreeId,
           undo_send,
+          replay,
         } = event.payload
+        if (replay) {
+          endReplay(session_id)
+          return
+        }
 
         // Capture streaming state BEFORE clearing (like chat:done does)
         const {
@@ -1195,6 +1237,16 @@ export default function useStreamingEvents({
             })
           }
           queryClient.invalidateQueries({ queryKey: ['all-sessions'] })
+          // The backend pauses the queue after a cancel; continue with the
+          // next queued message ("Skip to Next")
+          if (hasQueuedMessages) {
+            resumeMessageQueue(session_id).catch(err =>
+              console.debug(
+                '[useStreamingEvents] Failed to resume message queue:',
+                err
+              )
+            )
+          }
         }
 
         if (resolvedWorktreeId && wtPath) {
@@ -1230,9 +1282,10 @@ export default function useStreamingEvents({
     const unlistenCompacting = listen<CompactingEvent>(
       'chat:compacting',
       event => {
-        const { session_id, worktree_id } = event.payload
+        const { session_id, worktree_id, replay } = event.payload
         const { setCompacting } = useChatStore.getState()
         setCompacting(session_id, true)
+        if (replay) return
         const label = lookupSessionLabel(queryClient, session_id, worktree_id)
         toast.info(label ? `Compacting context: ${label}...` : 'Compacting context...')
       }
@@ -1241,9 +1294,10 @@ export default function useStreamingEvents({
     const unlistenCompacted = listen<CompactedEvent>(
       'chat:compacted',
       event => {
-        const { session_id, worktree_id, metadata } = event.payload
+        const { session_id, worktree_id, metadata, replay } = event.payload
         const { setLastCompaction, setCompacting } = useChatStore.getState()
         setCompacting(session_id, false)
+        if (replay) return
         setLastCompaction(session_id, metadata.trigger)
 
         const label = lookupSessionLabel(queryClient, session_id, worktree_id)
@@ -1252,6 +1306,42 @@ export default function useStreamingEvents({
       }
     )
 
+    // Warn when a session's context window is nearly full, offering to
+    // compact it once the current run finishes
+    const unlistenContextWarning = listen<ContextWindowEvent>(
+      'chat:context_warning',
+      event => {
+        const { session_id, worktree_id, used_tokens, limit_tokens } =
+          event.payload
+        const percent = Math.round((used_tokens / limit_tokens) * 100)
+        const label = lookupSessionLabel(queryClient, session_id, worktree_id)
+        const message = `Context window ${percent}% full`
+        toast.warning(label ? `${message}: ${label}` : message, {
+          id: `context-warning-${session_id}`,
+          action: {
+            label: 'Compact',
+            onClick: () => {
+              const worktreePath =
+                useChatStore.getState().worktreePaths[worktree_id]
+              if (!worktreePath) return
+              compactSession(session_id, worktree_id, worktreePath).catch(
+                error => toast.error(`Failed to compact: ${error}`)
+              )
+            },
+          },
+        })
+      }
+    )
+
+    // Digests refreshed in the background as sessions progress
+    const unlistenDigestUpdated = listen<DigestUpdatedEvent>(
+      'chat:digest_updated',
+      event => {
+        const { session_id, digest } = event.payload
+        useChatStore.getState().setSessionDigest(session_id, digest)
+      }
+    )
+
     // Handle session setting changes (model, thinking level, execution mode)
     // Broadcast by other clients via broadcast_session_setting command
     const unlistenSettingChanged = listen<{
@@ -1290,6 +1380,8 @@ export default function useStreamingEvents({
       unlistenCancelled.then(f => f())
       unlistenCompacting.then(f => f())
       unlistenCompacted.then(f => f())
+      unlistenContextWarning.then(f => f())
+      unlistenDigestUpdated.then(f => f())
       unlistenSettingChanged.then(f => f())
     }
   }, [queryClient, wsConnected])
@@ -118,6 +118,7 @@ export type TimelineItem =
   | { type: 'askUserQuestion'; tool: ToolCall; introText?: string; key: string }
   | { type: 'enterPlanMode'; tool: ToolCall; key: string }
   | { type: 'exitPlanMode'; tool: ToolCall; key: string }
+  | { type: 'compaction'; trigger: string; preTokens: number; key: string }
   | { type: 'unknown'; rawType: string; rawData: unknown; key: string }
 
 /**
@@ -368,6 +369,13 @@ export function buildTimeline(
           key: `tool-${toolCall.id}`,
         })
       }
+    } else if (block.type === 'compaction') {
+      result.push({
+        type: 'compaction',
+        trigger: block.trigger,
+        preTokens: block.pre_tokens,
+        key: `compaction-${i}`,
+      })
     } else {
       // Unknown content block type — render a visible indicator
       result.push({
@@ -62,9 +62,18 @@ export function JeanJsonPane({
           teardown: localTeardown.trim() || null,
           run: localRun.trim() || null,
         },
+        // Not edited here; keep what's in the file
+        permissions: jeanConfig?.permissions,
       },
     })
-  }, [localSetup, localTeardown, localRun, projectPath, saveJeanConfig])
+  }, [
+    localSetup,
+    localTeardown,
+    localRun,
+    jeanConfig,
+    projectPath,
+    saveJeanConfig,
+  ])
 
   return (
     <div className="space-y-6">
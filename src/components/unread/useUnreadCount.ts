@@ -6,7 +6,7 @@ import type { Session } from '@/types/chat'
 function isUnread(session: Session): boolean {
   if (session.archived_at) return false
 
-  const actionableStatuses = ['completed', 'cancelled', 'crashed']
+  const actionableStatuses = ['completed', 'cancelled', 'crashed', 'timed_out']
   const hasFinishedRun =
     session.last_run_status &&
     actionableStatuses.includes(session.last_run_status)
@@ -4,7 +4,7 @@ import { useChatStore } from '@/store/chat-store'
 import { useUIStore } from '@/store/ui-store'
 import { usePreferences } from '@/services/preferences'
 import { useClaudeCliStatus } from '@/services/claude-cli'
-import { chatQueryKeys } from '@/services/chat'
+import { addToMessageQueue, chatQueryKeys } from '@/services/chat'
 import { resolveBackend, supportsAdaptiveThinking } from '@/lib/model-utils'
 import {
   DEFAULT_INVESTIGATE_ISSUE_PROMPT,
@@ -26,8 +26,8 @@ type InvestigationType = 'issue' | 'pr' | 'security-alert' | 'advisory'
  *
  * When a worktree is created via CMD+Click with auto-investigate, the ChatWindow
  * never mounts (no modal opens), so the auto-investigate flag is never consumed.
- * This hook watches those flags, builds the investigation prompt, and enqueues
- * it into messageQueues for useQueueProcessor to send — no modal needed.
+ * This hook watches those flags, builds the investigation prompt, and adds it
+ * to the session's backend message queue, which sends it — no modal needed.
  *
  * Must be mounted at App level alongside useQueueProcessor.
  */
@@ -270,7 +270,7 @@ async function processBackgroundInvestigation(
     return
   }
 
-  // Register session-worktree mapping so useQueueProcessor can find the worktree
+  // Register session-worktree mapping so addToMessageQueue finds the worktree
   const { setActiveSession } = useChatStore.getState()
   setActiveSession(worktreeId, sessionId)
 
@@ -333,7 +333,6 @@ async function processBackgroundInvestigation(
     setSelectedProvider,
     setSelectedBackend,
     setExecutingMode,
-    enqueueMessage,
   } = useChatStore.getState()
 
   setSelectedModel(sessionId, selectedModel)
@@ -363,7 +362,7 @@ async function processBackgroundInvestigation(
     queuedAt: Date.now(),
   }
 
-  enqueueMessage(sessionId, queuedMessage)
+  await addToMessageQueue(sessionId, queuedMessage)
 
   logger.info('Background investigation enqueued', {
     worktreeId,
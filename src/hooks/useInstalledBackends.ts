@@ -2,6 +2,7 @@ import { useMemo } from 'react'
 import { useClaudeCliStatus } from '@/services/claude-cli'
 import { useCodexCliStatus } from '@/services/codex-cli'
 import { useOpencodeCliStatus } from '@/services/opencode-cli'
+import { useGeminiCliStatus } from '@/services/gemini-cli'
 import type { CliBackend } from '@/types/preferences'
 
 /**
@@ -12,17 +13,28 @@ export function useInstalledBackends() {
   const claude = useClaudeCliStatus()
   const codex = useCodexCliStatus()
   const opencode = useOpencodeCliStatus()
+  const gemini = useGeminiCliStatus()
 
   const installedBackends = useMemo(() => {
     const backends: CliBackend[] = []
     if (claude.data?.installed) backends.push('claude')
     if (codex.data?.installed) backends.push('codex')
     if (opencode.data?.installed) backends.push('opencode')
+    if (gemini.data?.installed) backends.push('gemini')
     return backends
-  }, [claude.data?.installed, codex.data?.installed, opencode.data?.installed])
+  }, [
+    claude.data?.installed,
+    codex.data?.installed,
+    opencode.data?.installed,
+    gemini.data?.installed,
+  ])
 
   return {
     installedBackends,
-    isLoading: claude.isLoading || codex.isLoading || opencode.isLoading,
+    isLoading:
+      claude.isLoading ||
+      codex.isLoading ||
+      opencode.isLoading ||
+      gemini.isLoading,
   }
 }
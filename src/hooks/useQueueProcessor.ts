@@ -1,167 +1,65 @@
-import { useEffect, useRef } from 'react'
+import { useEffect } from 'react'
+import { useQueryClient } from '@tanstack/react-query'
+import { listen, useWsConnectionStatus } from '@/lib/transport'
 import { useChatStore } from '@/store/chat-store'
-import { useSendMessage } from '@/services/chat'
-import { usePreferences } from '@/services/preferences'
-import { DEFAULT_PARALLEL_EXECUTION_PROMPT } from '@/types/preferences'
+import { chatQueryKeys, toQueuedMessage } from '@/services/chat'
 import { isTauri } from '@/services/projects'
-import { useWsConnectionStatus } from '@/lib/transport'
-import type { QueuedMessage } from '@/types/chat'
+import { generateId } from '@/lib/uuid'
+import type { ChatMessage, PersistedQueuedMessage, Session } from '@/types/chat'
 import { logger } from '@/lib/logger'
 
-// GIT_ALLOWED_TOOLS duplicated from ChatWindow - tools always allowed for git operations
-const GIT_ALLOWED_TOOLS = ['Bash', 'Read', 'Glob', 'Grep']
-
-/**
- * Build full message with attachment references for backend
- * Pure function extracted from ChatWindow
- */
-function buildMessageWithRefs(queuedMsg: QueuedMessage): string {
-  let message = queuedMsg.message
-
-  // Add file/directory references (from @ mentions)
-  if (queuedMsg.pendingFiles.length > 0) {
-    const fileRefs = queuedMsg.pendingFiles
-      .map(f =>
-        f.isDirectory
-          ? `[Directory: ${f.relativePath} - Use Glob and Read tools to explore this directory]`
-          : `[File: ${f.relativePath} - Use the Read tool to view this file]`
-      )
-      .join('\n')
-    message = message ? `${message}\n\n${fileRefs}` : fileRefs
-  }
-
-  // Add skill references (from / mentions)
-  if (queuedMsg.pendingSkills.length > 0) {
-    const skillRefs = queuedMsg.pendingSkills
-      .map(
-        s =>
-          `[Skill: ${s.path} - Read and use this skill to guide your response]`
-      )
-      .join('\n')
-    message = message ? `${message}\n\n${skillRefs}` : skillRefs
-  }
-
-  // Add image references
-  if (queuedMsg.pendingImages.length > 0) {
-    const imageRefs = queuedMsg.pendingImages
-      .map(
-        img =>
-          `[Image attached: ${img.path} - Use the Read tool to view this image]`
-      )
-      .join('\n')
-    message = message ? `${message}\n\n${imageRefs}` : imageRefs
-  }
-
-  // Add text file references
-  if (queuedMsg.pendingTextFiles.length > 0) {
-    const textFileRefs = queuedMsg.pendingTextFiles
-      .map(
-        tf =>
-          `[Text file attached: ${tf.path} - Use the Read tool to view this file]`
-      )
-      .join('\n')
-    message = message ? `${message}\n\n${textFileRefs}` : textFileRefs
-  }
-
-  return message
-}
-
 /**
- * Global queue processor hook - must be at App level so it stays active
+ * Global queue sync hook - must be at App level so it stays active
  * even when ChatWindow is unmounted (e.g., when viewing a different worktree)
  *
- * Processes queued messages for ALL sessions, not just the active one.
- * This fixes the bug where queued prompts don't execute when the worktree is unfocused.
+ * The backend owns the message queue and sends the next message when a run
+ * completes. This mirrors each session's queue into the chat store and sets
+ * up the session's streaming state when a queued message is dispatched.
  */
 export function useQueueProcessor(): void {
-  const sendMessage = useSendMessage()
-  const { data: preferences } = usePreferences()
-  // Re-run effect when WS connects so queue processing works in web mode
+  const queryClient = useQueryClient()
+  // Re-subscribe when WS connects so queue events arrive in web mode
   const wsConnected = useWsConnectionStatus()
 
-  // Track which sessions we're currently processing to prevent race conditions
-  const processingRef = useRef<Set<string>>(new Set())
-
-  // PERFORMANCE: Derived boolean selector — only re-renders when the answer changes,
-  // not on every mutation to any key in the underlying records.
-  const hasProcessableQueue = useChatStore(state => {
-    for (const [sessionId, queue] of Object.entries(state.messageQueues)) {
-      if (
-        queue &&
-        queue.length > 0 &&
-        !state.sendingSessionIds[sessionId] &&
-        !state.waitingForInputSessionIds[sessionId]
-      ) {
-        return true
-      }
-    }
-    return false
-  })
-
   useEffect(() => {
-    if (!hasProcessableQueue || !isTauri()) return
-
-    // Read fresh state inside effect to avoid subscribing to full records
-    const {
-      messageQueues,
-      sendingSessionIds,
-      waitingForInputSessionIds,
-      sessionWorktreeMap,
-      worktreePaths,
-      dequeueMessage,
-      addSendingSession,
-      setLastSentMessage,
-      setError,
-      setExecutingMode,
-      setSelectedModel,
-      getApprovedTools,
-      clearStreamingContent,
-      clearToolCalls,
-      clearStreamingContentBlocks,
-      setSessionReviewing,
-    } = useChatStore.getState()
-
-    // Process each session's queue
-    for (const [sessionId, queue] of Object.entries(messageQueues)) {
-      // Skip if queue is empty
-      if (!queue || queue.length === 0) continue
-
-      // Skip if already processing this session
-      if (processingRef.current.has(sessionId)) continue
-
-      // Skip if session is currently sending
-      if (sendingSessionIds[sessionId]) continue
-
-      // Skip if session is waiting for user input (AskUserQuestion/ExitPlanMode)
-      if (waitingForInputSessionIds[sessionId]) continue
-
-      const worktreeId = sessionWorktreeMap[sessionId]
-      const worktreePath = worktreeId ? worktreePaths[worktreeId] : undefined
-
-      // Skip if we can't find the worktree for this session
-      if (!worktreeId || !worktreePath) {
-        logger.warn('Queue processor: Cannot find worktree for session', {
-          sessionId,
-        })
-        continue
-      }
-
-      // Mark as processing to prevent duplicate processing
-      processingRef.current.add(sessionId)
-
-      // Dequeue the message
-      const queuedMsg = dequeueMessage(sessionId)
-      if (!queuedMsg) {
-        processingRef.current.delete(sessionId)
-        continue
-      }
-
-      logger.info('Queue processor: Processing queued message', {
+    if (!isTauri()) return
+
+    const unlistenUpdated = listen<{
+      session_id: string
+      worktree_id: string
+      queued_messages: PersistedQueuedMessage[]
+    }>('chat:queue_updated', event => {
+      const { session_id, queued_messages } = event.payload
+      useChatStore
+        .getState()
+        .setQueuedMessages(session_id, queued_messages.map(toQueuedMessage))
+    })
+
+    const unlistenDispatched = listen<{
+      session_id: string
+      worktree_id: string
+      queued_message: PersistedQueuedMessage
+    }>('chat:queue_dispatched', event => {
+      const { session_id: sessionId, queued_message } = event.payload
+      const queuedMsg = toQueuedMessage(queued_message)
+
+      logger.info('Queue: Dispatched queued message', {
         sessionId,
-        worktreeId,
         messageId: queuedMsg.id,
       })
 
+      const {
+        addSendingSession,
+        setLastSentMessage,
+        setError,
+        setExecutingMode,
+        setSelectedModel,
+        clearStreamingContent,
+        clearToolCalls,
+        clearStreamingContentBlocks,
+        setSessionReviewing,
+      } = useChatStore.getState()
+
       // Clear stale streaming state before starting new message
       clearStreamingContent(sessionId)
       clearToolCalls(sessionId)
@@ -173,53 +71,32 @@ export function useQueueProcessor(): void {
       addSendingSession(sessionId)
       setSessionReviewing(sessionId, false) // Clear stale review state so canvas shows running status
       setExecutingMode(sessionId, queuedMsg.executionMode)
-      setSelectedModel(sessionId, queuedMsg.model)
-
-      // Get session-approved tools
-      const sessionApprovedTools = getApprovedTools(sessionId)
-      const allowedTools =
-        sessionApprovedTools.length > 0
-          ? [...GIT_ALLOWED_TOOLS, ...sessionApprovedTools]
-          : undefined
-
-      // Build full message with attachment refs
-      const fullMessage = buildMessageWithRefs(queuedMsg)
+      if (queuedMsg.model) {
+        setSelectedModel(sessionId, queuedMsg.model)
+      }
 
-      // Send the message
-      sendMessage.mutate(
-        {
-          sessionId,
-          worktreeId,
-          worktreePath,
-          message: fullMessage,
-          model: queuedMsg.model,
-          executionMode: queuedMsg.executionMode,
-          thinkingLevel: queuedMsg.thinkingLevel,
-          effortLevel: queuedMsg.effortLevel,
-          mcpConfig: queuedMsg.mcpConfig,
-          customProfileName: queuedMsg.provider ?? undefined,
-          parallelExecutionPrompt:
-            preferences?.parallel_execution_prompt_enabled
-              ? (preferences.magic_prompts?.parallel_execution ??
-                DEFAULT_PARALLEL_EXECUTION_PROMPT)
-              : undefined,
-          chromeEnabled: preferences?.chrome_enabled ?? false,
-          allowedTools,
-        },
-        {
-          onSettled: () => {
-            // Clear processing flag when done
-            processingRef.current.delete(sessionId)
-          },
-        }
+      // Show the user message right away; the session reloads when the run ends
+      const userMessage: ChatMessage = {
+        id: generateId(),
+        session_id: sessionId,
+        role: 'user',
+        content: queuedMsg.message,
+        timestamp: Math.floor(Date.now() / 1000),
+        tool_calls: [],
+        model: queuedMsg.model,
+        execution_mode: queuedMsg.executionMode,
+        thinking_level: queuedMsg.thinkingLevel,
+      }
+      queryClient.setQueryData<Session>(
+        chatQueryKeys.session(sessionId),
+        old =>
+          old ? { ...old, messages: [...old.messages, userMessage] } : old
       )
+    })
+
+    return () => {
+      unlistenUpdated.then(f => f())
+      unlistenDispatched.then(f => f())
     }
-  }, [
-    hasProcessableQueue,
-    sendMessage,
-    preferences?.parallel_execution_prompt_enabled,
-    preferences?.magic_prompts?.parallel_execution,
-    preferences?.chrome_enabled,
-    wsConnected,
-  ])
+  }, [queryClient, wsConnected])
 }
@@ -9,22 +9,40 @@ import type {
   ArchivedSessionEntry,
   ChatMessage,
   ChatHistory,
+  CostFilters,
+  DigestSettings,
+  ProjectDigestRollup,
   Session,
+  SessionDigest,
   WorktreeSessions,
   Question,
   QuestionAnswer,
   ThinkingLevel,
   ExecutionMode,
   LabelData,
+  PermissionAuditEntry,
+  PersistedQueuedMessage,
+  QueuedMessage,
+  ReplayInfo,
+  RunCheckpoint,
+  SaveDocumentResponse,
+  SessionQuery,
+  SessionSummary,
+  UsageAnalytics,
 } from '@/types/chat'
 import {
   isTauri,
   projectsQueryKeys,
 } from '@/services/projects'
 import { preferencesQueryKeys } from '@/services/preferences'
-import type { AppPreferences } from '@/types/preferences'
+import {
+  DEFAULT_PARALLEL_EXECUTION_PROMPT,
+  type AppPreferences,
+} from '@/types/preferences'
+import { queryClient as appQueryClient } from '@/lib/query-client'
 import { useChatStore } from '@/store/chat-store'
 import type { ReviewResponse, Worktree } from '@/types/projects'
+import type { GitDiff } from '@/types/git-diff'
 
 // Query keys for chat
 export const chatQueryKeys = {
@@ -1933,3 +1951,389 @@ export async function markPlanApproved(
     throw error
   }
 }
+
+// ============================================================================
+// Run Replay
+// ============================================================================
+
+/**
+ * Re-emit a recorded run's streaming events into its session
+ * `speed` scales the original timing (2 = twice as fast, default 1)
+ */
+export async function replayRun(
+  sessionId: string,
+  runId: string,
+  speed?: number
+): Promise<ReplayInfo> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  logger.debug('Replaying run', { sessionId, runId, speed })
+  return invoke<ReplayInfo>('replay_run', { sessionId, runId, speed })
+}
+
+// ============================================================================
+// Checkpoints
+// ============================================================================
+
+/**
+ * Roll a worktree's files back to the snapshot taken before a run started
+ * Branch history is untouched; the replaced state is kept under a pre-restore ref
+ */
+export async function restoreCheckpoint(
+  worktreePath: string,
+  sessionId: string,
+  runId: string
+): Promise<RunCheckpoint> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  logger.debug('Restoring checkpoint', { sessionId, runId })
+  return invoke<RunCheckpoint>('restore_checkpoint', {
+    worktreePath,
+    sessionId,
+    runId,
+  })
+}
+
+/**
+ * Get the full diff of the files a run changed (checkpointed runs only)
+ */
+export async function getRunDiff(
+  worktreePath: string,
+  sessionId: string,
+  runId: string
+): Promise<GitDiff> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<GitDiff>('get_run_diff', { worktreePath, sessionId, runId })
+}
+
+// ============================================================================
+// Permission Policy
+// ============================================================================
+
+/**
+ * Get the tool calls a session's jean.json permission policy decided
+ * automatically, oldest first
+ */
+export async function getPermissionAudit(
+  sessionId: string
+): Promise<PermissionAuditEntry[]> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<PermissionAuditEntry[]>('get_permission_audit', { sessionId })
+}
+
+// ============================================================================
+// Compaction
+// ============================================================================
+
+/**
+ * Compact a Claude session's conversation now, optionally focusing the
+ * summary with an instruction. Runs as a regular turn.
+ */
+export async function compactSession(
+  sessionId: string,
+  worktreeId: string,
+  worktreePath: string,
+  instructions?: string
+): Promise<ChatMessage> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<ChatMessage>('compact_session', {
+    sessionId,
+    worktreeId,
+    worktreePath,
+    instructions,
+  })
+}
+
+// ============================================================================
+// Session Queries
+// ============================================================================
+
+/**
+ * List sessions across all worktrees matching a filter
+ */
+export async function querySessions(
+  query: SessionQuery
+): Promise<SessionSummary[]> {
+  if (!isTauri()) {
+    return []
+  }
+
+  return invoke<SessionSummary[]>('query_sessions', { query })
+}
+
+// ============================================================================
+// Document Attachments
+// ============================================================================
+
+/**
+ * Save a dropped PDF, Word document or spreadsheet and extract its text.
+ * `pages` limits extraction (e.g. "1-3, 7"); PDF page images are rendered
+ * too when `backend` supports vision.
+ */
+export async function saveDroppedDocument(
+  sourcePath: string,
+  pages?: string,
+  backend?: string
+): Promise<SaveDocumentResponse> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<SaveDocumentResponse>('save_dropped_document', {
+    sourcePath,
+    pages,
+    backend,
+  })
+}
+
+// ============================================================================
+// Usage Analytics
+// ============================================================================
+
+/**
+ * Aggregate runs across all sessions: runs per day, outcome rates, durations,
+ * tokens by project, model and backend, and execution modes
+ */
+export async function getUsageAnalytics(
+  filters?: CostFilters
+): Promise<UsageAnalytics> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<UsageAnalytics>('get_usage_analytics', { filters })
+}
+
+// ============================================================================
+// Session Digests
+// ============================================================================
+
+/** Get the background digest refresh configuration */
+export async function getDigestSettings(): Promise<DigestSettings> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<DigestSettings>('get_digest_settings')
+}
+
+/** Replace the background digest refresh configuration */
+export async function setDigestSettings(
+  settings: DigestSettings
+): Promise<void> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  await invoke('set_digest_settings', { settings })
+}
+
+/** A session's digests, oldest first, ending with the current one */
+export async function getDigestHistory(
+  sessionId: string
+): Promise<SessionDigest[]> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<SessionDigest[]>('get_digest_history', { sessionId })
+}
+
+/** Latest digest of every active session of a project */
+export async function getProjectDigestRollup(
+  projectId: string
+): Promise<ProjectDigestRollup> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<ProjectDigestRollup>('get_project_digest_rollup', {
+    projectId,
+  })
+}
+
+// ============================================================================
+// Message Queue
+// ============================================================================
+
+// Tools always allowed alongside session-approved tools for queued messages
+const GIT_ALLOWED_TOOLS = ['Bash', 'Read', 'Glob', 'Grep']
+
+/**
+ * Build the full message text for a queued message, with attachment
+ * references appended
+ */
+export function buildMessageWithRefs(queuedMsg: QueuedMessage): string {
+  let message = queuedMsg.message
+
+  // Add file/directory references (from @ mentions)
+  if (queuedMsg.pendingFiles.length > 0) {
+    const fileRefs = queuedMsg.pendingFiles
+      .map(f =>
+        f.isDirectory
+          ? `[Directory: ${f.relativePath} - Use Glob and Read tools to explore this directory]`
+          : `[File: ${f.relativePath} - Use the Read tool to view this file]`
+      )
+      .join('\n')
+    message = message ? `${message}\n\n${fileRefs}` : fileRefs
+  }
+
+  // Add skill references (from / mentions)
+  if (queuedMsg.pendingSkills.length > 0) {
+    const skillRefs = queuedMsg.pendingSkills
+      .map(
+        s =>
+          `[Skill: ${s.path} - Read and use this skill to guide your response]`
+      )
+      .join('\n')
+    message = message ? `${message}\n\n${skillRefs}` : skillRefs
+  }
+
+  // Add image references
+  if (queuedMsg.pendingImages.length > 0) {
+    const imageRefs = queuedMsg.pendingImages
+      .map(
+        img =>
+          `[Image attached: ${img.path} - Use the Read tool to view this image]`
+      )
+      .join('\n')
+    message = message ? `${message}\n\n${imageRefs}` : imageRefs
+  }
+
+  // Add text file references
+  if (queuedMsg.pendingTextFiles.length > 0) {
+    const textFileRefs = queuedMsg.pendingTextFiles
+      .map(
+        tf =>
+          `[Text file attached: ${tf.path} - Use the Read tool to view this file]`
+      )
+      .join('\n')
+    message = message ? `${message}\n\n${textFileRefs}` : textFileRefs
+  }
+
+  return message
+}
+
+/**
+ * Convert a message from the persisted queue for the chat window. Attachment
+ * references are already part of its text.
+ */
+export function toQueuedMessage(
+  queued: PersistedQueuedMessage
+): QueuedMessage {
+  return {
+    id: queued.id,
+    message: queued.message,
+    pendingImages: [],
+    pendingFiles: [],
+    pendingSkills: [],
+    pendingTextFiles: [],
+    model: queued.model ?? '',
+    provider: queued.custom_profile_name ?? null,
+    executionMode: queued.execution_mode ?? 'plan',
+    thinkingLevel: queued.thinking_level ?? 'off',
+    effortLevel: queued.effort_level,
+    mcpConfig: queued.mcp_config,
+    backend: queued.backend,
+    queuedAt: queued.queued_at * 1000,
+  }
+}
+
+/**
+ * Add a message to a session's persisted queue. The backend sends it when the
+ * current run completes; on an idle session it is sent right away.
+ */
+export async function addToMessageQueue(
+  sessionId: string,
+  queuedMsg: QueuedMessage
+): Promise<void> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  const {
+    sessionWorktreeMap,
+    worktreePaths,
+    getApprovedTools,
+    isSending,
+    isWaitingForInput,
+  } = useChatStore.getState()
+  const worktreeId = sessionWorktreeMap[sessionId]
+  const worktreePath = worktreeId ? worktreePaths[worktreeId] : undefined
+  if (!worktreePath) {
+    throw new Error(`Cannot find worktree for session ${sessionId}`)
+  }
+
+  const preferences = appQueryClient.getQueryData<AppPreferences>(
+    preferencesQueryKeys.preferences()
+  )
+  const sessionApprovedTools = getApprovedTools(sessionId)
+
+  await invoke<PersistedQueuedMessage>('enqueue_message', {
+    sessionId,
+    worktreePath,
+    message: buildMessageWithRefs(queuedMsg),
+    model: queuedMsg.model,
+    executionMode: queuedMsg.executionMode,
+    thinkingLevel: queuedMsg.thinkingLevel,
+    effortLevel: queuedMsg.effortLevel,
+    parallelExecutionPrompt: preferences?.parallel_execution_prompt_enabled
+      ? (preferences.magic_prompts?.parallel_execution ??
+        DEFAULT_PARALLEL_EXECUTION_PROMPT)
+      : undefined,
+    allowedTools:
+      sessionApprovedTools.length > 0
+        ? [...GIT_ALLOWED_TOOLS, ...sessionApprovedTools]
+        : undefined,
+    mcpConfig: queuedMsg.mcpConfig,
+    chromeEnabled: preferences?.chrome_enabled ?? false,
+    customProfileName: queuedMsg.provider ?? undefined,
+    backend: queuedMsg.backend,
+  })
+
+  // The backend holds the queue after a cancelled run or while a question or
+  // plan is open; queueing from an idle chat window means "send now"
+  if (!isSending(sessionId) && !isWaitingForInput(sessionId)) {
+    await resumeMessageQueue(sessionId)
+  }
+}
+
+/** Remove a message from a session's persisted queue */
+export async function removeFromMessageQueue(
+  sessionId: string,
+  queuedMessageId: string
+): Promise<boolean> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<boolean>('remove_queued_message', {
+    sessionId,
+    queuedMessageId,
+  })
+}
+
+/**
+ * Send the head of a session's queue now, even after a cancelled run.
+ * Returns false when nothing was sent (empty queue or a run in progress).
+ */
+export async function resumeMessageQueue(sessionId: string): Promise<boolean> {
+  if (!isTauri()) {
+    throw new Error('Not in Tauri context')
+  }
+
+  return invoke<boolean>('resume_message_queue', { sessionId })
+}
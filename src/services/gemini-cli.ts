@@ -0,0 +1,159 @@
+/**
+ * Gemini CLI management service.
+ */
+
+import { useQuery, useMutation, useQueryClient } from '@tanstack/react-query'
+import { invoke } from '@/lib/transport'
+import { listen } from '@/lib/transport'
+import { toast } from 'sonner'
+import { useCallback, useEffect, useState } from 'react'
+import { logger } from '@/lib/logger'
+import type {
+  GeminiAuthStatus,
+  GeminiCliStatus,
+  GeminiInstallProgress,
+  GeminiReleaseInfo,
+} from '@/types/gemini-cli'
+import { hasBackend } from '@/lib/environment'
+
+const isTauri = hasBackend
+
+export const geminiCliQueryKeys = {
+  all: ['gemini-cli'] as const,
+  status: () => [...geminiCliQueryKeys.all, 'status'] as const,
+  auth: () => [...geminiCliQueryKeys.all, 'auth'] as const,
+  versions: () => [...geminiCliQueryKeys.all, 'versions'] as const,
+}
+
+export function useGeminiCliStatus() {
+  return useQuery({
+    queryKey: geminiCliQueryKeys.status(),
+    queryFn: async (): Promise<GeminiCliStatus> => {
+      if (!isTauri()) return { installed: false, version: null, path: null }
+      try {
+        return await invoke<GeminiCliStatus>('check_gemini_cli_installed')
+      } catch (error) {
+        logger.error('Failed to check Gemini CLI status', { error })
+        return { installed: false, version: null, path: null }
+      }
+    },
+    staleTime: 1000 * 60 * 5,
+    gcTime: 1000 * 60 * 10,
+    refetchInterval: 1000 * 60 * 60,
+  })
+}
+
+export function useGeminiCliAuth(options?: { enabled?: boolean }) {
+  return useQuery({
+    queryKey: geminiCliQueryKeys.auth(),
+    queryFn: async (): Promise<GeminiAuthStatus> => {
+      if (!isTauri()) {
+        return { authenticated: false, error: 'Not in Tauri context' }
+      }
+      try {
+        return await invoke<GeminiAuthStatus>('check_gemini_cli_auth')
+      } catch (error) {
+        logger.error('Failed to check Gemini CLI auth', { error })
+        return {
+          authenticated: false,
+          error: error instanceof Error ? error.message : String(error),
+        }
+      }
+    },
+    enabled: options?.enabled ?? true,
+    staleTime: 1000 * 60 * 5,
+    gcTime: 1000 * 60 * 10,
+  })
+}
+
+export function useAvailableGeminiVersions() {
+  return useQuery({
+    queryKey: geminiCliQueryKeys.versions(),
+    queryFn: async (): Promise<GeminiReleaseInfo[]> => {
+      if (!isTauri()) return []
+      return await invoke<GeminiReleaseInfo[]>('get_available_gemini_versions')
+    },
+    staleTime: 1000 * 60 * 15, // Cache for 15 minutes to avoid rate limiting
+    gcTime: 1000 * 60 * 30,
+  })
+}
+
+export function useInstallGeminiCli() {
+  const queryClient = useQueryClient()
+  return useMutation({
+    mutationFn: async (version?: string) => {
+      if (!isTauri())
+        throw new Error('Cannot install Gemini CLI outside Tauri context')
+      await invoke('install_gemini_cli', { version: version ?? null })
+    },
+    retry: false,
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: geminiCliQueryKeys.status() })
+      toast.success('Gemini CLI installed successfully')
+    },
+    onError: error => {
+      const message = error instanceof Error ? error.message : String(error)
+      toast.error('Failed to install Gemini CLI', { description: message })
+    },
+  })
+}
+
+export function useGeminiInstallProgress(): [
+  GeminiInstallProgress | null,
+  () => void,
+] {
+  const [progress, setProgress] = useState<GeminiInstallProgress | null>(null)
+  const resetProgress = useCallback(() => setProgress(null), [])
+
+  useEffect(() => {
+    if (!isTauri()) return
+    let unlistenFn: (() => void) | null = null
+
+    const setup = async () => {
+      unlistenFn = await listen<GeminiInstallProgress>(
+        'gemini-cli:install-progress',
+        event => setProgress(event.payload)
+      )
+    }
+    setup()
+
+    return () => {
+      if (unlistenFn) unlistenFn()
+    }
+  }, [])
+
+  return [progress, resetProgress]
+}
+
+export function useGeminiCliSetup() {
+  const status = useGeminiCliStatus()
+  const versions = useAvailableGeminiVersions()
+  const installMutation = useInstallGeminiCli()
+  const [progress, resetProgress] = useGeminiInstallProgress()
+
+  const install = (
+    version: string,
+    options?: { onSuccess?: () => void; onError?: (error: Error) => void }
+  ) => {
+    resetProgress()
+    installMutation.mutate(version, {
+      onSuccess: () => options?.onSuccess?.(),
+      onError: error => options?.onError?.(error),
+    })
+  }
+
+  return {
+    status: status.data,
+    isStatusLoading: status.isLoading,
+    versions: versions.data ?? [],
+    isVersionsLoading: versions.isFetching,
+    isVersionsError: versions.isError,
+    refetchVersions: versions.refetch,
+    needsSetup: !status.isLoading && !status.data?.installed,
+    isInstalling: installMutation.isPending,
+    installError: installMutation.error,
+    progress,
+    install,
+    refetchStatus: status.refetch,
+  }
+}
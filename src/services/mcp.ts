@@ -51,6 +51,7 @@ export function invalidateAllMcpServers(
  * - Claude:   ~/.claude.json + .mcp.json
  * - Codex:    ~/.codex/config.toml + .codex/config.toml
  * - OpenCode: ~/.config/opencode/opencode.json + opencode.json
+ * - Gemini:   ~/.gemini/settings.json + .gemini/settings.json
  */
 export function useMcpServers(
   worktreePath: string | null | undefined,
@@ -90,6 +91,7 @@ export function useAllBackendsMcpServers(
     worktreePath,
     'opencode'
   )
+  const gemini = useMcpServers(worktreePath, 'gemini')
 
   const has = useMemo(
     () => new Set(installedBackends),
@@ -101,13 +103,15 @@ export function useAllBackendsMcpServers(
     if (has.has('claude') && claude.data) result.push(...claude.data)
     if (has.has('codex') && codex.data) result.push(...codex.data)
     if (has.has('opencode') && opencode.data) result.push(...opencode.data)
+    if (has.has('gemini') && gemini.data) result.push(...gemini.data)
     return result
-  }, [has, claude.data, codex.data, opencode.data])
+  }, [has, claude.data, codex.data, opencode.data, gemini.data])
 
   const isLoading =
     (has.has('claude') && claude.isLoading) ||
     (has.has('codex') && codex.isLoading) ||
-    (has.has('opencode') && opencode.isLoading)
+    (has.has('opencode') && opencode.isLoading) ||
+    (has.has('gemini') && gemini.isLoading)
 
   return { data: servers, isLoading }
 }
@@ -141,6 +145,7 @@ export function useAllBackendsMcpHealth(installedBackends: CliBackend[]) {
   const claude = useMcpHealthCheck('claude')
   const codex = useMcpHealthCheck('codex')
   const opencode = useMcpHealthCheck('opencode')
+  const gemini = useMcpHealthCheck('gemini')
 
   const has = useMemo(
     () => new Set(installedBackends),
@@ -158,21 +163,26 @@ export function useAllBackendsMcpHealth(installedBackends: CliBackend[]) {
     if (has.has('opencode') && opencode.data?.statuses) {
       Object.assign(merged, opencode.data.statuses)
     }
+    if (has.has('gemini') && gemini.data?.statuses) {
+      Object.assign(merged, gemini.data.statuses)
+    }
     return merged
-  }, [has, claude.data, codex.data, opencode.data])
+  }, [has, claude.data, codex.data, opencode.data, gemini.data])
 
   const isFetching =
     (has.has('claude') && claude.isFetching) ||
     (has.has('codex') && codex.isFetching) ||
-    (has.has('opencode') && opencode.isFetching)
+    (has.has('opencode') && opencode.isFetching) ||
+    (has.has('gemini') && gemini.isFetching)
 
   const refetchAll = useMemo(
     () => () => {
       if (has.has('claude')) claude.refetch()
       if (has.has('codex')) codex.refetch()
       if (has.has('opencode')) opencode.refetch()
+      if (has.has('gemini')) gemini.refetch()
     },
-    [has, claude.refetch, codex.refetch, opencode.refetch] // eslint-disable-line react-hooks/exhaustive-deps
+    [has, claude.refetch, codex.refetch, opencode.refetch, gemini.refetch] // eslint-disable-line react-hooks/exhaustive-deps
   )
 
   return { statuses, isFetching, refetchAll }
@@ -234,6 +244,7 @@ export const BACKEND_LABELS: Record<CliBackend, string> = {
   claude: 'Claude',
   codex: 'Codex',
   opencode: 'OpenCode',
+  gemini: 'Gemini',
 }
 
 /** Group servers by their backend field */
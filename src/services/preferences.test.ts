@@ -142,6 +142,8 @@ describe('preferences service', () => {
         build_model: null,
         yolo_model: null,
         linear_api_key: null,
+        run_idle_timeout_minutes: 30,
+        run_max_duration_minutes: 0,
       }
       vi.mocked(invoke).mockResolvedValueOnce(mockPreferences)
 
@@ -251,6 +253,8 @@ describe('preferences service', () => {
         build_model: null,
         yolo_model: null,
         linear_api_key: null,
+        run_idle_timeout_minutes: 30,
+        run_max_duration_minutes: 0,
       }
       vi.mocked(invoke).mockResolvedValueOnce(prefsWithOldBinding)
 
@@ -336,6 +340,8 @@ describe('preferences service', () => {
         build_model: null,
         yolo_model: null,
         linear_api_key: null,
+        run_idle_timeout_minutes: 30,
+        run_max_duration_minutes: 0,
       }
 
       const { result } = renderHook(() => useSavePreferences(), {
@@ -420,6 +426,8 @@ describe('preferences service', () => {
         build_model: null,
         yolo_model: null,
         linear_api_key: null,
+        run_idle_timeout_minutes: 30,
+        run_max_duration_minutes: 0,
       }
 
       const { result } = renderHook(() => useSavePreferences(), {
@@ -504,6 +512,8 @@ describe('preferences service', () => {
         build_model: null,
         yolo_model: null,
         linear_api_key: null,
+        run_idle_timeout_minutes: 30,
+        run_max_duration_minutes: 0,
       }
 
       const { result } = renderHook(() => useSavePreferences(), {
@@ -586,6 +596,8 @@ describe('preferences service', () => {
         build_model: null,
         yolo_model: null,
         linear_api_key: null,
+        run_idle_timeout_minutes: 30,
+        run_max_duration_minutes: 0,
       }
 
       const { result } = renderHook(() => useSavePreferences(), {
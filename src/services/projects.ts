@@ -1967,6 +1967,15 @@ export interface JeanConfig {
     teardown: string | null
     run: string | null
   }
+  /** Tool permission rules for agent sessions (see chat/policy.rs) */
+  permissions?: {
+    rules: {
+      action: 'allow' | 'ask' | 'deny'
+      tool?: string
+      command?: string
+      path?: string
+    }[]
+  }
 }
 
 /**
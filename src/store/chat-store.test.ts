@@ -446,6 +446,20 @@ describe('ChatStore', () => {
 
       expect(getQueueLength('session-1')).toBe(0)
     })
+
+    it('replaces queue with the persisted one', () => {
+      const { enqueueMessage, setQueuedMessages, getQueuedMessages } =
+        useChatStore.getState()
+
+      enqueueMessage('session-1', mockMessage)
+      setQueuedMessages('session-1', [
+        createMockMessage('msg-2', 'Second'),
+        createMockMessage('msg-3', 'Third'),
+      ])
+
+      const messages = getQueuedMessages('session-1')
+      expect(messages.map(m => m.id)).toEqual(['msg-2', 'msg-3'])
+    })
   })
 
   describe('permission approvals', () => {
@@ -413,7 +413,8 @@ interface ChatUIState {
     sessionId: string,
     textFileId: string,
     content: string,
-    size: number
+    size: number,
+    path?: string
   ) => void
   removePendingTextFile: (sessionId: string, textFileId: string) => void
   clearPendingTextFiles: (sessionId: string) => void
@@ -445,6 +446,7 @@ interface ChatUIState {
   getQueueLength: (sessionId: string) => number
   getQueuedMessages: (sessionId: string) => QueuedMessage[]
   forceProcessQueue: (sessionId: string) => void
+  setQueuedMessages: (sessionId: string, messages: QueuedMessage[]) => void
 
   // Actions - Executing mode (tracks mode prompt was sent with)
   setExecutingMode: (sessionId: string, mode: ExecutionMode) => void
@@ -1725,13 +1727,15 @@ export const useChatStore = create<ChatUIState>()(
           'addPendingTextFile'
         ),
 
-      updatePendingTextFile: (sessionId, textFileId, content, size) =>
+      updatePendingTextFile: (sessionId, textFileId, content, size, path) =>
         set(
           state => ({
             pendingTextFiles: {
               ...state.pendingTextFiles,
               [sessionId]: (state.pendingTextFiles[sessionId] ?? []).map(tf =>
-                tf.id === textFileId ? { ...tf, content, size } : tf
+                tf.id === textFileId
+                  ? { ...tf, content, size, path: path ?? tf.path }
+                  : tf
               ),
             },
           }),
@@ -1923,6 +1927,18 @@ export const useChatStore = create<ChatUIState>()(
           'forceProcessQueue'
         ),
 
+      setQueuedMessages: (sessionId, messages) =>
+        set(
+          state => ({
+            messageQueues: {
+              ...state.messageQueues,
+              [sessionId]: messages,
+            },
+          }),
+          undefined,
+          'setQueuedMessages'
+        ),
+
       // Executing mode actions (tracks mode prompt was sent with)
       setExecutingMode: (sessionId, mode) =>
         set(
@@ -27,9 +27,86 @@ export type ThinkingLevel = 'off' | 'think' | 'megathink' | 'ultrathink'
 export type EffortLevel = 'low' | 'medium' | 'high' | 'max'
 
 /**
- * Backend for a chat session (Claude CLI, Codex CLI, or OpenCode)
+ * Backend for a chat session (Claude CLI, Codex CLI, OpenCode, or Gemini CLI).
+ * 'mock' replays scripted fixtures (`mock/<fixture>` models) for tests.
  */
-export type Backend = 'claude' | 'codex' | 'opencode'
+export type Backend = 'claude' | 'codex' | 'opencode' | 'gemini' | 'mock'
+
+/**
+ * What a backend supports (from get_backend_capabilities)
+ */
+export interface BackendCapabilities {
+  plan_mode: boolean
+  /** Plans end with an ExitPlanMode tool call the user approves */
+  plan_approval_tool: boolean
+  thinking_levels: boolean
+  effort_levels: boolean
+  /** Tool calls are approved interactively in build mode */
+  approvals: boolean
+  web_search_in_plan: boolean
+  /** Runs outlive the app and are re-attached after a restart */
+  detached: boolean
+  /** Stalled runs are killed by the run watchdog */
+  watchdog: boolean
+  native_fork: boolean
+  /** Runs under a custom provider profile when one is selected */
+  custom_profiles: boolean
+  /** Can spread work over parallel agent threads */
+  multi_agent: boolean
+  /** Attached images reach the model (page images are made for these) */
+  vision: boolean
+  /** How the jean.json permission policy is applied to tool calls */
+  policy_enforcement: PolicyEnforcement
+}
+
+/**
+ * How a backend applies the permission policy: not at all, through the CLI's
+ * own permission settings, or by answering build-mode approval requests
+ */
+export type PolicyEnforcement = 'none' | 'cli_settings' | 'build_approvals'
+
+/**
+ * A started replay of a recorded run (from replay_run)
+ */
+export interface ReplayInfo {
+  session_id: string
+  run_id: string
+  /** Log lines that will be replayed */
+  lines: number
+  /** Expected replay duration at the requested speed */
+  duration_ms: number
+}
+
+/** Snapshot of a worktree taken before a build/yolo run */
+export interface RunCheckpoint {
+  /** Hidden ref holding the snapshot (refs/jean/checkpoints/{session}/{run}) */
+  git_ref: string
+  /** Snapshot commit */
+  commit: string
+  /** HEAD when the snapshot was taken (unset on an unborn branch) */
+  head?: string
+}
+
+/** A file created, modified, deleted or renamed by a run */
+export interface RunFileChange {
+  path: string
+  /** Previous path (for renames) */
+  old_path?: string
+  status: 'added' | 'modified' | 'deleted' | 'renamed'
+  additions: number
+  deletions: number
+  is_binary: boolean
+}
+
+/** Files a run changed, diffed between its checkpoint and its end */
+export interface RunChanges {
+  /** Hidden ref holding the end-of-run snapshot */
+  git_ref: string
+  commit: string
+  additions: number
+  deletions: number
+  files: RunFileChange[]
+}
 
 /**
  * Execution mode for Claude CLI permission handling
@@ -59,7 +136,7 @@ export interface ToolCall {
 }
 
 /**
- * A content block in a message - text, tool use, or thinking
+ * A content block in a message - text, tool use, thinking, or a compaction boundary
  * Used to preserve the order of content in Claude's response
  * Note: Uses snake_case to match Rust serde serialization (rename_all = "snake_case")
  */
@@ -67,6 +144,7 @@ export type ContentBlock =
   | { type: 'text'; text: string }
   | { type: 'tool_use'; tool_call_id: string }
   | { type: 'thinking'; thinking: string }
+  | { type: 'compaction'; trigger: string; pre_tokens: number }
 
 /**
  * A single chat message
@@ -134,7 +212,7 @@ export interface Session {
   messages: ChatMessage[]
   /** Message count (populated separately for efficiency when full messages not needed) */
   message_count?: number
-  /** Backend for this session (claude, codex, or opencode) */
+  /** Backend for this session (claude, codex, opencode, or gemini) */
   backend?: Backend
   /** Claude CLI session ID for resuming conversations */
   claude_session_id?: string
@@ -142,6 +220,8 @@ export interface Session {
   codex_thread_id?: string
   /** OpenCode session ID for resuming conversations */
   opencode_session_id?: string
+  /** Gemini CLI session ID for resuming conversations */
+  gemini_session_id?: string
   /** Selected model for this session */
   selected_model?: string
   /** Selected thinking level for this session */
@@ -185,6 +265,12 @@ export interface Session {
   enabled_mcp_servers?: string[]
   /** Persisted session digest (recap summary) */
   digest?: SessionDigest
+  /** Context window fill after the session's last Claude run */
+  context_window?: ContextWindowUsage
+  /** Follow-up prompts waiting to be sent after the current run completes */
+  queued_messages?: PersistedQueuedMessage[]
+  /** Origin of this session if it was forked from another session */
+  forked_from?: ForkOrigin
   /** Unix timestamp when session was last opened/viewed by the user */
   last_opened_at?: number
   /** Status of the last run (for immediate status on app restart) */
@@ -195,6 +281,92 @@ export interface Session {
   label?: LabelData
 }
 
+/**
+ * Where a forked session came from (set by fork_session)
+ */
+export interface ForkOrigin {
+  /** Session the fork was created from */
+  session_id: string
+  /** Message the fork was taken at */
+  message_id: string
+  /** Unix timestamp when the fork was created */
+  forked_at: number
+  /** Claude CLI message UUID the resumed conversation is truncated at */
+  resume_at?: string
+  /** Whether the first forked run is still pending */
+  pending?: boolean
+}
+
+/** Part of a transcript a search result came from */
+export type SearchDocumentKind =
+  | 'user_message'
+  | 'assistant_text'
+  | 'tool_input'
+  | 'tool_output'
+  | 'plan'
+
+/**
+ * Filters for search_sessions (all optional)
+ */
+export interface SearchFilters {
+  project_id?: string
+  worktree_id?: string
+  backend?: Backend
+  /** Label name (case-insensitive) */
+  label?: string
+  /** Unix timestamp lower bound */
+  from?: number
+  /** Unix timestamp upper bound */
+  to?: number
+  kinds?: SearchDocumentKind[]
+  limit?: number
+}
+
+/**
+ * A full-text search hit, linking to the exact message
+ */
+export interface SearchResult {
+  session_id: string
+  session_name: string
+  worktree_id: string
+  project_id?: string
+  backend: Backend
+  run_id: string
+  message_id: string
+  kind: SearchDocumentKind
+  tool_call_id?: string
+  tool_name?: string
+  /** Plan file path (for plan results) */
+  path?: string
+  snippet: string
+  timestamp: number
+  score: number
+}
+
+/** Kind of file carried in a session bundle besides run logs */
+export type BundleAttachmentKind = 'image' | 'text' | 'context' | 'plan'
+
+/**
+ * Manifest of an exported session bundle (returned by export_session_bundle)
+ */
+export interface BundleManifest {
+  format_version: number
+  exported_at: number
+  app_version: string
+  session_id: string
+  session_name: string
+  attachments: {
+    kind: BundleAttachmentKind
+    /** Absolute path on the exporting machine */
+    original_path: string
+    /** Entry name inside the archive */
+    entry: string
+  }[]
+}
+
+/** Output format for export_session_transcript */
+export type TranscriptFormat = 'markdown' | 'html'
+
 /**
  * An archived session with its worktree context
  * Used for displaying archived sessions in the ArchivedModal
@@ -258,6 +430,249 @@ export interface UsageData {
   cache_creation_input_tokens?: number
 }
 
+// ============================================================================
+// Cost Types
+// ============================================================================
+
+/**
+ * Token rates for a model, in USD per million tokens
+ */
+export interface ModelRate {
+  /** Model name or alias (matches models containing it; "*" matches any) */
+  model: string
+  /** Custom CLI profile this rate applies to (omit for any provider) */
+  provider?: string
+  input_per_mtok: number
+  output_per_mtok: number
+  cache_read_per_mtok: number
+  cache_write_per_mtok: number
+}
+
+/** Built-in rates plus user overrides (overrides take precedence) */
+export interface PricingTable {
+  defaults: ModelRate[]
+  overrides: ModelRate[]
+}
+
+export type CostGroupBy =
+  | 'session'
+  | 'worktree'
+  | 'project'
+  | 'backend'
+  | 'model'
+  | 'day'
+
+export interface CostFilters {
+  project_id?: string
+  worktree_id?: string
+  session_id?: string
+  backend?: Backend
+  /** Unix timestamp lower bound (run start) */
+  from?: number
+  /** Unix timestamp upper bound (run start) */
+  to?: number
+}
+
+/** Aggregated usage and cost for one group */
+export interface CostBucket {
+  key: string
+  label?: string
+  runs: number
+  /** Runs with usage but no matching rate (excluded from cost_usd) */
+  unpriced_runs: number
+  input_tokens: number
+  output_tokens: number
+  cache_read_input_tokens: number
+  cache_creation_input_tokens: number
+  cost_usd: number
+}
+
+export interface CostSummary {
+  buckets: CostBucket[]
+  total: CostBucket
+}
+
+// ============================================================================
+// Usage Analytics Types
+// ============================================================================
+
+/** Run counts, tokens and duration for one group of runs */
+export interface UsageBucket {
+  key: string
+  /** Project name, or the backend of a model */
+  label?: string
+  runs: number
+  completed: number
+  cancelled: number
+  crashed: number
+  timed_out: number
+  input_tokens: number
+  output_tokens: number
+  cache_read_input_tokens: number
+  cache_creation_input_tokens: number
+  /** Mean duration of finished runs, in seconds */
+  average_duration_secs?: number
+}
+
+/** Share of finished runs per outcome (0-1) */
+export interface OutcomeRates {
+  success: number
+  cancel: number
+  crash: number
+  timeout: number
+}
+
+export interface ExecutionModeUsage {
+  mode: string
+  runs: number
+  /** Share of all runs (0-1) */
+  share: number
+}
+
+export interface UsageAnalytics {
+  total: UsageBucket
+  rates: OutcomeRates
+  /** One bucket per UTC day with runs, oldest first */
+  per_day: UsageBucket[]
+  /** Sorted by total tokens, highest first */
+  by_project: UsageBucket[]
+  by_model: UsageBucket[]
+  by_backend: UsageBucket[]
+  /** Most used first */
+  execution_modes: ExecutionModeUsage[]
+}
+
+// ============================================================================
+// Budget Types
+// ============================================================================
+
+/** Budget a limit belongs to (daily = all sessions in the current UTC day) */
+export type BudgetScope = 'session' | 'project' | 'daily'
+
+/** What a budget limit measures (tokens = input + output) */
+export type BudgetMetric = 'cost_usd' | 'tokens'
+
+/** Soft/hard limits for one budget. Unset thresholds are not enforced. */
+export interface BudgetLimit {
+  soft_cost_usd?: number
+  hard_cost_usd?: number
+  soft_tokens?: number
+  hard_tokens?: number
+}
+
+/** Budget configuration (app-data/budgets.json) */
+export interface BudgetSettings {
+  /** Default limit for every session */
+  session?: BudgetLimit
+  /** Per-session overrides of the default (keyed by session ID) */
+  sessions: Record<string, BudgetLimit>
+  /** Per-project limits (keyed by project ID) */
+  projects: Record<string, BudgetLimit>
+  /** Global limit per UTC day */
+  daily?: BudgetLimit
+}
+
+/** Payload of chat:budget_warning and chat:budget_exceeded events */
+export interface BudgetEvent {
+  session_id: string
+  worktree_id: string
+  run_id?: string
+  scope: BudgetScope
+  metric: BudgetMetric
+  limit: number
+  spent: number
+}
+
+/** A budget that applies to a session and how much of it is used */
+export interface BudgetStatus {
+  scope: BudgetScope
+  limit: BudgetLimit
+  spent: { cost_usd: number; tokens: number }
+}
+
+/** Which watchdog limit a run hit (idle = no new CLI output) */
+export type TimeoutKind = 'idle' | 'wall_clock'
+
+/** Why a run was cancelled */
+export type CancelReason =
+  | { type: 'user' }
+  | {
+      type: 'budget'
+      scope: BudgetScope
+      metric: BudgetMetric
+      limit: number
+      spent: number
+    }
+  | { type: 'timeout'; kind: TimeoutKind; limit_secs: number }
+
+/** Payload of chat:run_timeout (the run is then cancelled as 'timed_out') */
+export interface RunTimeoutEvent {
+  session_id: string
+  worktree_id: string
+  run_id: string
+  kind: TimeoutKind
+  limit_secs: number
+}
+
+// ============================================================================
+// Storage Types
+// ============================================================================
+
+/** Run log retention and quotas (app-data/storage.json) */
+export interface StorageSettings {
+  /** Gzip run logs of runs that ended more than this many days ago (0 = never) */
+  compress_after_days: number
+  /** Maximum size of one session's run logs in MB (unset = unlimited) */
+  session_quota_mb?: number
+  /** Maximum size of all run logs and pasted assets in MB (unset = unlimited) */
+  global_quota_mb?: number
+  /** Checkpointed runs per session whose checkpoints are kept (0 = all) */
+  checkpoints_per_session: number
+}
+
+/** Disk usage of one session's data directory */
+export interface SessionDiskUsage {
+  session_id: string
+  session_name: string
+  worktree_id: string
+  bytes: number
+  run_logs: number
+  /** Run logs stored gzipped */
+  compressed_logs: number
+}
+
+/** Disk usage of a project's sessions (project_id is null for deleted worktrees) */
+export interface ProjectDiskUsage {
+  project_id: string | null
+  project_name: string
+  bytes: number
+  sessions: SessionDiskUsage[]
+}
+
+/** Result of get_disk_usage */
+export interface DiskUsageReport {
+  total_bytes: number
+  sessions_bytes: number
+  pasted_images_bytes: number
+  pasted_texts_bytes: number
+  pasted_documents_bytes: number
+  projects: ProjectDiskUsage[]
+  settings: StorageSettings
+}
+
+/** Result of run_storage_maintenance */
+export interface StorageMaintenanceResult {
+  compressed_logs: number
+  evicted_logs: number
+  evicted_assets: number
+  /** Unreferenced pasted files removed */
+  collected_assets: number
+  /** Runs whose worktree checkpoints were deleted */
+  pruned_checkpoints: number
+  /** Bytes reclaimed by compression, collection and eviction */
+  bytes_freed: number
+}
+
 // ============================================================================
 // Compaction Types
 // ============================================================================
@@ -272,6 +687,18 @@ export interface CompactMetadata {
   pre_tokens: number
 }
 
+/**
+ * How full a session's context window was after its last API call
+ */
+export interface ContextWindowUsage {
+  /** Tokens in context (input + cache read + cache creation) */
+  used_tokens: number
+  /** Context window size of the model */
+  limit_tokens: number
+  /** Unix timestamp of the measurement */
+  updated_at: number
+}
+
 // ============================================================================
 // Event Types (updated for sessions)
 // ============================================================================
@@ -306,6 +733,8 @@ export interface DoneEvent {
   worktree_id: string // Kept for backward compatibility
   /** True when a Codex/Opencode plan-mode run completed with content */
   waiting_for_plan?: boolean
+  /** Set on events re-emitted by replay_run (not a live run) */
+  replay?: boolean
 }
 
 /**
@@ -314,6 +743,8 @@ export interface DoneEvent {
 export interface CompactingEvent {
   session_id: string
   worktree_id: string
+  /** Set on events re-emitted by replay_run (not a live run) */
+  replay?: boolean
 }
 
 /**
@@ -323,6 +754,18 @@ export interface CompactedEvent {
   session_id: string
   worktree_id: string
   metadata: CompactMetadata
+  /** Set on events re-emitted by replay_run (not a live run) */
+  replay?: boolean
+}
+
+/**
+ * Event payload for chat:context_window and chat:context_warning from Rust
+ */
+export interface ContextWindowEvent {
+  session_id: string
+  worktree_id: string
+  used_tokens: number
+  limit_tokens: number
 }
 
 /**
@@ -332,6 +775,8 @@ export interface ErrorEvent {
   session_id: string
   worktree_id: string // Kept for backward compatibility
   error: string
+  /** Set on events re-emitted by replay_run (not a live run) */
+  replay?: boolean
 }
 
 /**
@@ -341,6 +786,8 @@ export interface CancelledEvent {
   session_id: string
   worktree_id: string // Kept for backward compatibility
   undo_send: boolean // True if user message should be restored to input (instant cancellation)
+  /** Set on events re-emitted by replay_run (not a live run) */
+  replay?: boolean
 }
 
 /**
@@ -399,6 +846,24 @@ export interface PermissionDeniedEvent {
   session_id: string
   worktree_id: string // Kept for backward compatibility
   denials: PermissionDenial[]
+  /** Set on events re-emitted by replay_run (not a live run) */
+  replay?: boolean
+}
+
+/** What a jean.json permission rule does with a matching tool call */
+export type PolicyAction = 'allow' | 'ask' | 'deny'
+
+/** A tool call the project permission policy decided without asking */
+export interface PermissionAuditEntry {
+  /** Unix timestamp (seconds) */
+  timestamp: number
+  backend: Backend
+  tool: string
+  command?: string
+  /** Relative to the worktree root when the call targeted a file inside it */
+  path?: string
+  action: PolicyAction
+  tool_use_id?: string
 }
 
 // ============================================================================
@@ -594,6 +1059,28 @@ export interface SaveTextResponse {
   size: number
 }
 
+/**
+ * Response from the save_dropped_document Tauri command
+ */
+export interface SaveDocumentResponse {
+  /** Unique ID for this document */
+  id: string
+  /** Name of the dropped file (e.g., "spec.pdf") */
+  filename: string
+  /** Full path to the extracted text file (attached like a pasted text file) */
+  path: string
+  /** Size of the extracted text in bytes */
+  size: number
+  /** Full path to the stored copy of the original document */
+  original_path: string
+  /** Pages (sheets for spreadsheets) in the document, when it has them */
+  page_count?: number
+  /** Pages the text was extracted from (e.g., "1-3, 7") */
+  pages?: string
+  /** Rendered page images (PDFs, for backends that can look at images) */
+  images: SaveImageResponse[]
+}
+
 
 /**
  * Response from the read_pasted_text Tauri command
@@ -776,6 +1263,32 @@ export interface QueuedMessage {
   queuedAt: number
 }
 
+/**
+ * A queued message persisted in session metadata (backend-dispatched)
+ * Survives app restarts and is sent automatically when the current run completes
+ */
+export interface PersistedQueuedMessage {
+  /** Unique ID for this queued message (for editing/reordering/removal) */
+  id: string
+  /** The message text (already formatted with file/image references) */
+  message: string
+  /** Worktree path the message will be sent in */
+  worktree_path: string
+  /** Unix timestamp when the message was queued */
+  queued_at: number
+  model?: string
+  execution_mode?: ExecutionMode
+  thinking_level?: ThinkingLevel
+  effort_level?: EffortLevel
+  parallel_execution_prompt?: string
+  ai_language?: string
+  allowed_tools?: string[]
+  mcp_config?: string
+  chrome_enabled?: boolean
+  custom_profile_name?: string
+  backend?: Backend
+}
+
 // ============================================================================
 // MCP Server Types
 // ============================================================================
@@ -790,7 +1303,7 @@ export interface McpServerInfo {
   scope: 'user' | 'local' | 'project'
   /** Whether the server has "disabled": true in its config */
   disabled: boolean
-  /** Which backend this server belongs to: "claude", "codex", or "opencode" */
+  /** Which backend this server belongs to: "claude", "codex", "opencode", or "gemini" */
   backend: string
 }
 
@@ -883,6 +1396,40 @@ export interface AllSessionsResponse {
   entries: AllSessionsEntry[]
 }
 
+/**
+ * Filter for the query_sessions Tauri command (unset fields match everything)
+ */
+export interface SessionQuery {
+  project_id?: string
+  worktree_id?: string
+  backend?: Backend
+  /** Label name */
+  label?: string
+  /** true for archived sessions only, false for active ones only */
+  archived?: boolean
+  /** Case-insensitive substring of the session name */
+  name_contains?: string
+  limit?: number
+}
+
+/**
+ * One session returned by query_sessions (most recently updated first)
+ */
+export interface SessionSummary {
+  id: string
+  worktree_id: string
+  /** null when the worktree no longer exists */
+  project_id: string | null
+  name: string
+  backend: Backend
+  created_at: number
+  /** End of the last run (or creation time without runs) */
+  updated_at: number
+  archived_at: number | null
+  label: string | null
+  run_count: number
+}
+
 // ============================================================================
 // Debug Info Types (for SessionDebugPanel)
 // ============================================================================
@@ -896,6 +1443,7 @@ export type RunStatus =
   | 'cancelled'
   | 'crashed'
   | 'resumable'
+  | 'timed_out'
 
 /**
  * Information about a single JSONL run log file
@@ -911,6 +1459,14 @@ export interface RunLogFileInfo {
   user_message_preview: string
   /** Token usage for this run (if completed) */
   usage?: UsageData
+  /** Cost in USD for this run (if completed) */
+  cost_usd?: number
+  /** Worktree snapshot the run can be rolled back to (build/yolo runs) */
+  checkpoint?: RunCheckpoint
+  /** Files the run changed (checkpointed runs that have ended) */
+  changes?: RunChanges
+  /** Context compactions that happened during the run */
+  compactions?: CompactMetadata[]
 }
 
 /**
@@ -954,6 +1510,52 @@ export interface SessionDigest {
   message_count?: number
 }
 
+/** Background digest refresh configuration (app-data/digest.json) */
+export interface DigestSettings {
+  /** Regenerate digests in the background as sessions progress */
+  auto_refresh: boolean
+  /** New messages since the current digest that trigger a refresh */
+  refresh_after_messages: number
+  /** Model for background refreshes (unset = session recap model) */
+  model?: string
+  /** Custom CLI profile for `model` */
+  provider?: string
+  /** Earlier digests kept per session */
+  history_limit: number
+}
+
+/**
+ * Event payload for chat:digest_updated (digest refreshed in the background)
+ */
+export interface DigestUpdatedEvent {
+  session_id: string
+  worktree_id: string
+  digest: SessionDigest
+}
+
+/** Latest digest of one session in a project rollup */
+export interface SessionDigestSummary {
+  session_id: string
+  session_name: string
+  worktree_id: string
+  worktree_name: string
+  /** Missing until the session's first digest */
+  digest?: SessionDigest
+  /** Messages the digest doesn't cover yet */
+  messages_since_digest: number
+  last_run_status?: RunStatus
+  /** Unix seconds of the session's last activity */
+  last_activity_at: number
+}
+
+/** Digests of all active sessions of a project */
+export interface ProjectDigestRollup {
+  project_id: string
+  project_name: string
+  /** Most recently active first */
+  sessions: SessionDigestSummary[]
+}
+
 /** User-assigned label with color for session cards */
 export interface LabelData {
   /** Label name (e.g. "Needs testing") */
@@ -0,0 +1,27 @@
+/**
+ * Types for Gemini CLI management
+ */
+
+export interface GeminiCliStatus {
+  installed: boolean
+  version: string | null
+  path: string | null
+}
+
+export interface GeminiAuthStatus {
+  authenticated: boolean
+  error: string | null
+}
+
+export interface GeminiReleaseInfo {
+  version: string
+  tag_name: string
+  published_at: string
+  prerelease: boolean
+}
+
+export interface GeminiInstallProgress {
+  stage: 'starting' | 'installing' | 'verifying' | 'complete'
+  message: string
+  percent: number
+}
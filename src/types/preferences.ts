@@ -706,7 +706,7 @@ export interface AppPreferences {
   default_provider: string | null // Default provider profile name (null = Anthropic direct)
   canvas_layout: CanvasLayout // Canvas display mode: grid (cards) or list (compact rows)
   confirm_session_close: boolean // Show confirmation dialog before closing sessions/worktrees
-  default_backend: CliBackend // Default CLI backend for new sessions: 'claude', 'codex', or 'opencode'
+  default_backend: CliBackend // Default CLI backend for new sessions: 'claude', 'codex', 'opencode', or 'gemini'
   selected_codex_model: CodexModel // Default Codex model
   selected_opencode_model: string // Default OpenCode model (provider/model)
   default_codex_reasoning_effort: CodexReasoningEffort // Default reasoning effort for Codex: 'low' | 'medium' | 'high' | 'xhigh'
@@ -717,6 +717,8 @@ export interface AppPreferences {
   build_model: string | null // Model override for plan approval (build mode), null = use session model
   yolo_model: string | null // Model override for yolo plan approval, null = use session model
   linear_api_key: string | null // Global Linear personal API key (inherited by all projects)
+  run_idle_timeout_minutes: number // Kill a run after this long without new CLI output (0 = disabled)
+  run_max_duration_minutes: number // Kill a run after this long in total (0 = no limit)
 }
 
 export type CanvasLayout = 'grid' | 'list'
@@ -863,6 +865,22 @@ export const codexModelOptions: { value: CodexModel; label: string }[] = [
 
 export type CodexReasoningEffort = 'low' | 'medium' | 'high' | 'xhigh'
 
+// =============================================================================
+// Gemini Types
+// =============================================================================
+
+export type GeminiModel = 'gemini-2.5-pro' | 'gemini-2.5-flash'
+
+export const geminiModelOptions: { value: GeminiModel; label: string }[] = [
+  { value: 'gemini-2.5-pro', label: 'Gemini 2.5 Pro' },
+  { value: 'gemini-2.5-flash', label: 'Gemini 2.5 Flash' },
+]
+
+/** Check if a model string identifies a Gemini model */
+export function isGeminiModel(model: string): boolean {
+  return model.startsWith('gemini-')
+}
+
 // =============================================================================
 // Magic Prompt Model (unified type for both Claude and Codex)
 // =============================================================================
@@ -896,12 +914,13 @@ export const codexReasoningOptions: {
 // CLI Backend
 // =============================================================================
 
-export type CliBackend = 'claude' | 'codex' | 'opencode'
+export type CliBackend = 'claude' | 'codex' | 'opencode' | 'gemini'
 
 export const backendOptions: { value: CliBackend; label: string }[] = [
   { value: 'claude', label: 'Claude' },
   { value: 'codex', label: 'Codex' },
   { value: 'opencode', label: 'OpenCode' },
+  { value: 'gemini', label: 'Gemini' },
 ]
 
 export type TerminalApp =
@@ -1204,4 +1223,6 @@ export const defaultPreferences: AppPreferences = {
   build_model: null, // Default: use session model
   yolo_model: null, // Default: use session model
   linear_api_key: null, // Default: no global Linear API key
+  run_idle_timeout_minutes: 0, // Default: off (long tool calls can be silent)
+  run_max_duration_minutes: 0, // Default: no wall-clock limit
 }
@@ -0,0 +1,72 @@
+/**
+ * When a schedule fires. Cron expressions use the standard five fields and
+ * are evaluated in the wall clock `utc_offset_minutes` away from UTC.
+ */
+export type ScheduleTrigger =
+  | { type: 'cron'; expression: string; utc_offset_minutes: number }
+  | { type: 'interval'; seconds: number }
+
+/**
+ * Where a schedule sends its prompt
+ */
+export type ScheduleTarget =
+  | { type: 'session'; worktree_id: string; session_id: string }
+  | { type: 'new_worktree'; project_id: string; base_branch?: string }
+
+/**
+ * User-editable part of a schedule
+ */
+export interface ScheduleConfig {
+  name: string
+  prompt: string
+  trigger: ScheduleTrigger
+  target: ScheduleTarget
+  model?: string
+  /** Backend override ("claude", "codex", "opencode") */
+  backend?: string
+  execution_mode: 'plan' | 'build' | 'yolo'
+  enabled: boolean
+}
+
+export type ScheduledRunStatus =
+  | 'running'
+  | 'completed'
+  | 'cancelled'
+  | 'failed'
+  | 'skipped'
+
+/**
+ * One firing of a schedule
+ */
+export interface ScheduledRun {
+  id: string
+  fired_at: number
+  finished_at?: number
+  /** Fired by run_schedule_now rather than the trigger */
+  manual: boolean
+  status: ScheduledRunStatus
+  worktree_id?: string
+  session_id?: string
+  run_id?: string
+  error?: string
+}
+
+/**
+ * A stored schedule (config fields are flattened in)
+ */
+export interface Schedule extends ScheduleConfig {
+  id: string
+  created_at: number
+  next_run_at?: number
+  last_run_at?: number
+  /** Fired runs, oldest first */
+  history: ScheduledRun[]
+}
+
+/**
+ * Payload of the schedule:fired event
+ */
+export interface ScheduleFiredEvent {
+  schedule_id: string
+  run: ScheduledRun
+}